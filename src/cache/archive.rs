@@ -0,0 +1,208 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::cache::cache::ContextCache;
+use crate::cache::invalidation::{sibling_path, unique_hex};
+#[cfg(feature = "fs")]
+use crate::cache::store::FsStore;
+use crate::cache::store::CacheStore;
+
+const BLOCK: usize = 512;
+
+impl ContextCache {
+    /// Write every file of this cache into a single uncompressed tar archive.
+    ///
+    /// Entries are sorted by path and carry fixed metadata (mode 0644,
+    /// uid/gid 0, mtime 0), so the archive bytes depend only on the cache
+    /// file contents. Two archives of caches with the same `cache_version`
+    /// are byte-identical when their manifests are (i.e. `created_at` is
+    /// pinned).
+    #[cfg(feature = "fs")]
+    pub fn export_archive(&self, archive: impl AsRef<Path>) -> Result<()> {
+        FsStore.write_file(archive.as_ref(), &self.to_archive_bytes()?)
+    }
+
+    /// The bytes `export_archive` writes.
+    pub fn to_archive_bytes(&self) -> Result<Vec<u8>> {
+        let mut files = Vec::new();
+        collect_files(self.store().as_ref(), &self.root, Path::new(""), &mut files)?;
+
+        let mut out = Vec::new();
+        for rel in &files {
+            let contents = self.store().read_file(&self.root.join(rel))?;
+            write_entry(&mut out, rel, &contents)?;
+        }
+        out.extend_from_slice(&[0u8; BLOCK * 2]);
+        Ok(out)
+    }
+
+    /// Extract an archive written by `export_archive` into `output_dir` and
+    /// open it. Fails if `output_dir` exists, if any entry path is not a
+    /// plain relative path, or if a document fails verification, in which
+    /// case `output_dir` is not created.
+    #[cfg(feature = "fs")]
+    pub fn import_archive(archive: impl AsRef<Path>, output_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::import_archive_in(Arc::new(FsStore), archive, output_dir)
+    }
+
+    /// Like `import_archive`, extracting into `store`.
+    #[cfg(feature = "fs")]
+    pub fn import_archive_in(
+        store: Arc<dyn CacheStore>,
+        archive: impl AsRef<Path>,
+        output_dir: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let bytes = std::fs::read(archive)?;
+        Self::import_archive_bytes_in(store, &bytes, output_dir)
+    }
+
+    /// Like `import_archive_in`, from archive bytes already in memory.
+    pub fn import_archive_bytes_in(
+        store: Arc<dyn CacheStore>,
+        archive: &[u8],
+        output_dir: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let output_dir = output_dir.as_ref();
+        if store.exists(output_dir) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("output directory already exists: {}", output_dir.display()),
+            ));
+        }
+
+        let entries = read_entries(archive)?;
+
+        // Named from the whole file name, and unique, so concurrent imports
+        // never share a temp dir
+        let temp_dir = sibling_path(output_dir, &format!("import.tmp.{}", unique_hex()));
+        store.create_dir_all(&temp_dir)?;
+        // Verified before the rename, so a bad archive never reaches
+        // `output_dir`
+        let extracted = entries
+            .into_iter()
+            .try_for_each(|(rel, contents)| store.write_file(&temp_dir.join(rel), contents))
+            .and_then(|()| Self::open_in(store.clone(), &temp_dir))
+            .and_then(|cache| cache.load_documents().map_err(Error::from));
+        if let Err(e) = extracted {
+            // Best effort; a leftover temp dir is never reused
+            let _ = store.remove_dir_all(&temp_dir);
+            return Err(e);
+        }
+        store.rename(&temp_dir, output_dir)?;
+        Self::open_in(store, output_dir)
+    }
+}
+
+/// Recursively list files under `root/rel`, sorted, as relative paths.
+pub(crate) fn collect_files(store: &dyn CacheStore, root: &Path, rel: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for name in store.list(&root.join(rel))? {
+        let child = rel.join(&name);
+        // Directories list successfully; files do not
+        match store.list(&root.join(&child)) {
+            Ok(_) => collect_files(store, root, &child, out)?,
+            Err(_) => out.push(child),
+        }
+    }
+    Ok(())
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    // Zero-padded octal, NUL-terminated
+    let digits = field.len() - 1;
+    let s = format!("{:0width$o}", value, width = digits);
+    field[..digits].copy_from_slice(s.as_bytes());
+    field[digits] = 0;
+}
+
+pub(crate) fn write_entry(out: &mut Vec<u8>, rel: &Path, contents: &[u8]) -> Result<()> {
+    let name = rel
+        .to_str()
+        .ok_or_else(|| invalid("non UTF-8 path"))?
+        .replace('\\', "/");
+    if name.len() > 100 {
+        return Err(invalid(format!("path too long for archive: {}", name)));
+    }
+
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644); // mode
+    write_octal(&mut header[108..116], 0); // uid
+    write_octal(&mut header[116..124], 0); // gid
+    write_octal(&mut header[124..136], contents.len() as u64); // size
+    write_octal(&mut header[136..148], 0); // mtime
+    header[156] = b'0'; // regular file
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // Checksum is computed with the checksum field set to spaces
+    header[148..156].copy_from_slice(b"        ");
+    let sum: u64 = header.iter().map(|b| *b as u64).sum();
+    let s = format!("{:06o}\0 ", sum);
+    header[148..156].copy_from_slice(s.as_bytes());
+
+    out.extend_from_slice(&header);
+    out.extend_from_slice(contents);
+    let padding = (BLOCK - contents.len() % BLOCK) % BLOCK;
+    out.extend(std::iter::repeat(0u8).take(padding));
+    Ok(())
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let s: String = field
+        .iter()
+        .take_while(|b| **b != 0 && **b != b' ')
+        .map(|b| *b as char)
+        .collect();
+    if s.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(&s, 8).map_err(|_| invalid("invalid octal field in archive"))
+}
+
+/// Parse regular-file entries, rejecting anything but plain relative paths.
+pub(crate) fn read_entries(bytes: &[u8]) -> Result<Vec<(PathBuf, &[u8])>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= bytes.len() {
+        let header = &bytes[offset..offset + BLOCK];
+        if header.iter().all(|b| *b == 0) {
+            return Ok(entries);
+        }
+
+        let stored = parse_octal(&header[148..156])?;
+        let mut check = header.to_vec();
+        check[148..156].copy_from_slice(b"        ");
+        let computed: u64 = check.iter().map(|b| *b as u64).sum();
+        if stored != computed {
+            return Err(invalid("archive header checksum mismatch"));
+        }
+
+        let name_len = header[..100].iter().position(|b| *b == 0).unwrap_or(100);
+        let name = std::str::from_utf8(&header[..name_len]).map_err(|_| invalid("non UTF-8 path"))?;
+        let size = parse_octal(&header[124..136])? as usize;
+        let typeflag = header[156];
+
+        let data_start = offset + BLOCK;
+        let data_end = data_start
+            .checked_add(size)
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| invalid("truncated archive"))?;
+
+        if typeflag != b'0' && typeflag != 0 {
+            return Err(invalid(format!("unsupported archive entry type for {}", name)));
+        }
+        let path = PathBuf::from(name);
+        if path.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(invalid(format!("unsafe path in archive: {}", name)));
+        }
+        entries.push((path, &bytes[data_start..data_end]));
+
+        offset = data_start + size.div_ceil(BLOCK) * BLOCK;
+    }
+    Err(invalid("archive is missing end-of-archive marker"))
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::cache::cache::{parse_manifest, read_failed, verify_document, CacheReadError, ContextCache, UnreadableDocuments};
+use crate::cache::store::FsStore;
+use crate::cache::ManifestDocumentEntry;
+use crate::document::Document;
+
+impl ContextCache {
+    /// Async `open`: reads the manifest (and `index.json`, when it has a
+    /// checksum) with `tokio::fs`.
+    pub async fn open_async(root: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let root = root.as_ref().to_path_buf();
+        let bytes = tokio::fs::read(root.join("manifest.json")).await?;
+        let cache = Self::from_parts(Arc::new(FsStore), root, parse_manifest(&bytes)?);
+
+        if cache.manifest.index_checksum.is_some() {
+            let index = tokio::fs::read(cache.root.join("index.json")).await?;
+            cache.check_index(&index)?;
+        }
+        Ok(cache)
+    }
+
+    /// Async `load_documents`, with the same verification. Filesystem-backed
+    /// caches are read with `tokio::fs`; other stores are read directly.
+    pub async fn load_documents_async(&self) -> Result<Vec<Document>, CacheReadError> {
+        let mut loaded_docs = Vec::with_capacity(self.manifest.documents.len());
+        for entry in &self.manifest.documents {
+            loaded_docs.push(self.load_document_async(entry).await?);
+        }
+        Ok(loaded_docs)
+    }
+
+    /// Async `load_readable_documents`.
+    pub async fn load_readable_documents_async(&self) -> (Vec<Document>, UnreadableDocuments) {
+        let mut loaded_docs = Vec::with_capacity(self.manifest.documents.len());
+        let mut unreadable = Vec::new();
+        for entry in &self.manifest.documents {
+            match self.load_document_async(entry).await {
+                Ok(doc) => loaded_docs.push(doc),
+                Err(e) => unreadable.push((entry.id.clone(), e)),
+            }
+        }
+        (loaded_docs, unreadable)
+    }
+
+    async fn load_document_async(&self, entry: &ManifestDocumentEntry) -> Result<Document, CacheReadError> {
+        let bytes = if self.store().is_filesystem() {
+            let path = self.document_path(entry)?;
+            tokio::fs::read(&path).await.map_err(|e| read_failed(entry, path, e))?
+        } else {
+            self.read_document(entry)?
+        };
+        verify_document(self.manifest.build_config.document_encoding, entry, &bytes, self.verification())
+    }
+}
@@ -3,46 +3,412 @@
 // no “update” methods
 // runtime reads only
 
-use std::path::PathBuf;
-use crate::cache::CacheManifest;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "fs")]
+use crate::cache::store::FsStore;
+use crate::cache::stats::{CacheStats, StatsOptions};
+use crate::cache::store::CacheStore;
+use crate::cache::vectors::VectorStore;
+use crate::cache::verify::VerifyOptions;
+use crate::cache::versioning::{CacheIndex, DocumentEncoding};
+use crate::cache::{CacheManifest, ManifestDocumentEntry};
+use crate::compat;
 use crate::document::Document;
-use crate::types::identifiers::DocumentVersion;
+use crate::selection::ranking::{ApproxTokenCounter, TokenCounter};
+use crate::types::identifiers::{DocumentId, DocumentVersion, HashAlgorithm};
+
+/// Documents that failed to load, with their errors, in ID order.
+pub type UnreadableDocuments = Vec<(DocumentId, CacheReadError)>;
+
+/// Anything selection can load verified documents from.
+pub trait DocumentLoader {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError>;
+
+    /// Like `load_documents`, but a document that cannot be read is
+    /// returned with its error instead of failing the whole load. Loaders
+    /// that cannot tell documents apart keep the default, which fails.
+    fn load_readable_documents(&self) -> Result<(Vec<Document>, UnreadableDocuments), CacheReadError> {
+        Ok((self.load_documents()?, Vec::new()))
+    }
+
+    /// Version identifying the loaded documents, if known. Used to key
+    /// memoized selection results.
+    fn cache_version(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Why documents could not be loaded from a cache.
+///
+/// Functions returning `std::io::Error` (`ContextCache::open`, `verify`)
+/// wrap it: `MissingFile` as `NotFound`, `Io` as the error itself, and the
+/// rest as `InvalidData`.
+#[derive(Debug, thiserror::Error)]
+pub enum CacheReadError {
+    /// A document file listed in the manifest does not exist.
+    #[error("Missing file for {}: {}", .id.as_str(), .path.display())]
+    MissingFile { id: DocumentId, path: PathBuf },
+
+    /// A document's content does not hash to its manifest version.
+    #[error(
+        "Document version mismatch for {}: manifest says {}, content hashes to {}",
+        .id.as_str(),
+        .expected.as_str(),
+        .actual.as_str()
+    )]
+    VersionMismatch {
+        id: DocumentId,
+        expected: DocumentVersion,
+        actual: DocumentVersion,
+    },
+
+    /// A document file holds a different document than its manifest entry.
+    #[error("Document ID mismatch: manifest says {}, file holds {}", .expected.as_str(), .actual.as_str())]
+    IdMismatch { expected: DocumentId, actual: DocumentId },
+
+    /// A document file (or its manifest entry) cannot be decoded.
+    #[error("Corrupt file for {}: {source}", .id.as_str())]
+    Corrupt { id: DocumentId, source: std::io::Error },
+
+    /// A manifest or index entry names a file outside the cache root: an
+    /// absolute path, or one climbing out with `..`.
+    #[error("Unsafe file path for {}: {path:?}", .id.as_str())]
+    UnsafePath { id: DocumentId, path: String },
+
+    /// Any other failure reading the store.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl From<CacheReadError> for std::io::Error {
+    fn from(e: CacheReadError) -> Self {
+        match e {
+            CacheReadError::Io(e) => e,
+            CacheReadError::MissingFile { .. } => std::io::Error::new(std::io::ErrorKind::NotFound, e),
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// How much `ContextCache::load_documents` checks each document file
+/// against its manifest entry. `ContextCache::verify` always checks fully.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationLevel {
+    /// Re-hash each document's content and compare it with the manifest
+    /// version, and check its ID.
+    #[default]
+    Full,
+    /// Check each document's ID and recorded version against the manifest
+    /// without hashing its content. Catches a file swapped for another
+    /// document, not one edited in place.
+    ManifestOnly,
+    /// Only decode. For trusted caches, e.g. ones this process just built.
+    None,
+}
 
 #[derive(Debug)]
 pub struct ContextCache {
     pub root: PathBuf,
     pub manifest: CacheManifest,
+    store: Arc<dyn CacheStore>,
+    verification: VerificationLevel,
+}
+
+impl DocumentLoader for ContextCache {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        ContextCache::load_documents(self)
+    }
+
+    fn load_readable_documents(&self) -> Result<(Vec<Document>, UnreadableDocuments), CacheReadError> {
+        Ok(ContextCache::load_readable_documents(self))
+    }
+
+    fn cache_version(&self) -> Option<String> {
+        Some(self.manifest.cache_version.clone())
+    }
 }
 
 impl ContextCache {
-    pub fn load_documents(&self) -> Result<Vec<Document>, std::io::Error> {
-        let mut loaded_docs = Vec::with_capacity(self.manifest.documents.len());
-        for entry in &self.manifest.documents {
-            let path = self.root.join(&entry.file);
-            let f = std::fs::File::open(&path)?;
-            let doc: Document = serde_json::from_reader(f)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-            
-            // Verify ID matches manifest
-            if doc.id != entry.id {
-                 return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Document ID mismatch"));
-            }
+    /// Open a cache directory on the local filesystem by reading its manifest.
+    #[cfg(feature = "fs")]
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        Self::open_in(Arc::new(FsStore), root)
+    }
+
+    /// Open a cache stored under `root` in `store`.
+    ///
+    /// Every manifest entry must name a file inside `root`. If the manifest
+    /// records an `index_checksum`, `index.json` must match it, and so must
+    /// its entries. Document files are not read; use `verify` for a full
+    /// check.
+    pub fn open_in(store: Arc<dyn CacheStore>, root: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let root = root.as_ref().to_path_buf();
+        let bytes = store.read_file(&root.join("manifest.json"))?;
+        let cache = Self::from_parts(store, root, parse_manifest(&bytes)?);
+
+        if cache.manifest.index_checksum.is_some() {
+            let index = cache.store.read_file(&cache.root.join("index.json"))?;
+            cache.check_index(&index)?;
+        }
+        Ok(cache)
+    }
 
-            // Verify version matches manifest (recompute from content)
-            let expected_version = DocumentVersion::from_content(doc.content.as_bytes());
-            if expected_version != entry.version {
+    /// Compare `index.json` bytes against the manifest's `index_checksum`,
+    /// and check that its entries name files inside the cache root.
+    pub(crate) fn check_index(&self, index: &[u8]) -> Result<(), std::io::Error> {
+        if let Some(expected) = &self.manifest.index_checksum {
+            let actual = self.hash_algorithm()?.checksum(index);
+            if &actual != expected {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidData,
-                    format!(
-                        "Document version mismatch for {}: manifest says {}, content hashes to {}",
-                        entry.id.as_str(),
-                        entry.version.as_str(),
-                        expected_version.as_str(),
-                    ),
+                    format!("index.json checksum mismatch: manifest says {}, file hashes to {}", expected, actual),
                 ));
             }
-            loaded_docs.push(doc);
         }
-        Ok(loaded_docs)
+        let index: CacheIndex =
+            serde_json::from_slice(index).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        for (id, file) in index.iter() {
+            check_file_path(id, file)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn from_parts(store: Arc<dyn CacheStore>, root: PathBuf, manifest: CacheManifest) -> Self {
+        Self {
+            root,
+            manifest,
+            store,
+            verification: VerificationLevel::Full,
+        }
+    }
+
+    /// Check loaded documents at `level` instead of `Full`, so selection
+    /// from a trusted cache skips hashing every document on each call.
+    pub fn with_verification(mut self, level: VerificationLevel) -> Self {
+        self.verification = level;
+        self
+    }
+
+    pub fn verification(&self) -> VerificationLevel {
+        self.verification
+    }
+
+    /// The store this cache reads from.
+    pub fn store(&self) -> &Arc<dyn CacheStore> {
+        &self.store
+    }
+
+    /// Path of a document's file, checked to be inside the cache root
+    /// (the manifest is public, so it may have changed since `open`).
+    pub(crate) fn document_path(&self, entry: &ManifestDocumentEntry) -> Result<PathBuf, CacheReadError> {
+        check_file_path(&entry.id, &entry.file)?;
+        Ok(self.root.join(&entry.file))
+    }
+
+    /// The bytes of a document's file.
+    pub(crate) fn read_document(&self, entry: &ManifestDocumentEntry) -> Result<Vec<u8>, CacheReadError> {
+        let path = self.document_path(entry)?;
+        self.store.read_file(&path).map_err(|e| read_failed(entry, path, e))
+    }
+
+    fn load_document(
+        &self,
+        entry: &ManifestDocumentEntry,
+        level: VerificationLevel,
+    ) -> Result<Document, CacheReadError> {
+        let bytes = self.read_document(entry)?;
+        verify_document(self.manifest.build_config.document_encoding, entry, &bytes, level)
+    }
+
+    /// Every document, checked at the cache's `verification` level.
+    pub fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        self.manifest
+            .documents
+            .iter()
+            .map(|entry| self.load_document(entry, self.verification))
+            .collect()
+    }
+
+    /// The documents whose ids pass `keep`, in manifest order; other files
+    /// are not read.
+    #[cfg(feature = "tantivy")]
+    pub(crate) fn load_documents_where(
+        &self,
+        keep: impl Fn(&DocumentId) -> bool,
+    ) -> Result<Vec<Document>, CacheReadError> {
+        self.manifest
+            .documents
+            .iter()
+            .filter(|entry| keep(&entry.id))
+            .map(|entry| self.load_document(entry, self.verification))
+            .collect()
+    }
+
+    /// Every document that loads and verifies, in manifest order, and the
+    /// ID and error of each that does not.
+    pub fn load_readable_documents(&self) -> (Vec<Document>, UnreadableDocuments) {
+        let mut loaded_docs = Vec::with_capacity(self.manifest.documents.len());
+        let mut unreadable = Vec::new();
+        for entry in &self.manifest.documents {
+            match self.load_document(entry, self.verification) {
+                Ok(doc) => loaded_docs.push(doc),
+                Err(e) => unreadable.push((entry.id.clone(), e)),
+            }
+        }
+        (loaded_docs, unreadable)
+    }
+
+    /// Document counts, sizes, metadata keys, and per-prefix totals, with
+    /// tokens counted as the default selector counts them.
+    ///
+    /// Loads (and so verifies) every document.
+    pub fn stats(&self) -> Result<CacheStats, std::io::Error> {
+        self.stats_with(&ApproxTokenCounter, &StatsOptions::default())
+    }
+
+    pub fn stats_with(&self, counter: &dyn TokenCounter, options: &StatsOptions) -> Result<CacheStats, std::io::Error> {
+        let documents = self.load_documents()?;
+        Ok(CacheStats::collect(&self.manifest.cache_version, &documents, counter, options))
+    }
+
+    /// Check the whole cache against its manifest: the tree hash over
+    /// `index.json`, every document file, and `vectors.json` (when the
+    /// manifest records one), then each document's ID and version.
+    ///
+    /// Manifests without a `tree_hash` only get the per-document checks.
+    /// See `verify_with` for threaded and incremental verification.
+    pub fn verify(&self) -> Result<(), std::io::Error> {
+        self.verify_with(&VerifyOptions::default()).map(|_| ())
+    }
+
+    pub(crate) fn hash_algorithm(&self) -> Result<HashAlgorithm, std::io::Error> {
+        let name = &self.manifest.build_config.hash_algorithm;
+        HashAlgorithm::from_name(name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported hash algorithm: {}", name),
+            )
+        })
+    }
+
+    /// Load `vectors.json`, if the cache was built with an embedder.
+    ///
+    /// Returns `Ok(None)` when the cache has no vectors. Every manifest
+    /// document must have exactly one vector.
+    pub fn load_vectors(&self) -> Result<Option<VectorStore>, std::io::Error> {
+        let path = self.root.join("vectors.json");
+        if !self.store.exists(&path) {
+            return Ok(None);
+        }
+        let bytes = self.store.read_file(&path)?;
+        let store: VectorStore = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        let covers_manifest = store.vectors.len() == self.manifest.documents.len()
+            && self
+                .manifest
+                .documents
+                .iter()
+                .all(|entry| store.vectors.contains_key(&entry.id));
+        if !covers_manifest {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "vectors.json does not match manifest documents",
+            ));
+        }
+        Ok(Some(store))
+    }
+}
+
+pub(crate) fn parse_manifest(bytes: &[u8]) -> Result<CacheManifest, std::io::Error> {
+    let manifest =
+        compat::cache_manifest_from_json(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    for entry in &manifest.documents {
+        check_file_path(&entry.id, &entry.file)?;
+    }
+    Ok(manifest)
+}
+
+/// Check that a file path from a manifest or index stays inside the cache
+/// root: relative, and made only of plain components (no `..`, `.`, root,
+/// or drive prefix). Symlinks inside the root are not resolved.
+pub(crate) fn check_file_path(id: &DocumentId, file: &str) -> Result<(), CacheReadError> {
+    let path = Path::new(file);
+    if file.is_empty() || path.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(CacheReadError::UnsafePath {
+            id: id.clone(),
+            path: file.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Classify a failed read of a document's file.
+pub(crate) fn read_failed(entry: &ManifestDocumentEntry, path: PathBuf, e: std::io::Error) -> CacheReadError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        CacheReadError::MissingFile {
+            id: entry.id.clone(),
+            path,
+        }
+    } else {
+        CacheReadError::Io(e)
+    }
+}
+
+/// Decode a document file and check it against its manifest entry, as
+/// far as `level` says.
+pub(crate) fn verify_document(
+    encoding: DocumentEncoding,
+    entry: &ManifestDocumentEntry,
+    bytes: &[u8],
+    level: VerificationLevel,
+) -> Result<Document, CacheReadError> {
+    let corrupt = |source| CacheReadError::Corrupt {
+        id: entry.id.clone(),
+        source,
+    };
+    let doc = encoding.decode(bytes).map_err(corrupt)?;
+    if level == VerificationLevel::None {
+        return Ok(doc);
+    }
+
+    // Verify ID matches manifest
+    if doc.id != entry.id {
+        return Err(CacheReadError::IdMismatch {
+            expected: entry.id.clone(),
+            actual: doc.id,
+        });
+    }
+
+    if level == VerificationLevel::ManifestOnly {
+        if doc.version != entry.version {
+            return Err(CacheReadError::VersionMismatch {
+                id: entry.id.clone(),
+                expected: entry.version.clone(),
+                actual: doc.version,
+            });
+        }
+        return Ok(doc);
+    }
+
+    // Verify version matches manifest (recompute from content with
+    // the algorithm named by the manifest's version prefix)
+    let algorithm = entry.version.algorithm().ok_or_else(|| {
+        corrupt(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unsupported version format: {}", entry.version.as_str()),
+        ))
+    })?;
+    let expected_version = DocumentVersion::from_content_with(algorithm, doc.content.as_bytes());
+    if expected_version != entry.version {
+        return Err(CacheReadError::VersionMismatch {
+            id: entry.id.clone(),
+            expected: entry.version.clone(),
+            actual: expected_version,
+        });
     }
+    Ok(doc)
 }
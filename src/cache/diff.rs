@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use crate::cache::versioning::CacheManifest;
+use crate::types::identifiers::{DocumentId, DocumentVersion};
+
+/// A document present in only one of the two manifests.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DiffEntry {
+    pub id: DocumentId,
+    pub version: DocumentVersion,
+}
+
+/// A document present in both manifests with different content.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ModifiedEntry {
+    pub id: DocumentId,
+    pub old_version: DocumentVersion,
+    pub new_version: DocumentVersion,
+}
+
+/// Document-level changes between two cache generations.
+/// Every list is sorted by document ID.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CacheDiff {
+    pub old_cache_version: String,
+    pub new_cache_version: String,
+    pub added: Vec<DiffEntry>,
+    pub removed: Vec<DiffEntry>,
+    pub modified: Vec<ModifiedEntry>,
+    pub unchanged: usize,
+}
+
+impl CacheDiff {
+    /// True when no document was added, removed, or modified.
+    /// The cache versions may still differ (e.g. a build config change).
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Compare two manifests by document ID and version.
+pub fn diff(old: &CacheManifest, new: &CacheManifest) -> CacheDiff {
+    let old_docs: BTreeMap<&DocumentId, &DocumentVersion> =
+        old.documents.iter().map(|e| (&e.id, &e.version)).collect();
+    let new_docs: BTreeMap<&DocumentId, &DocumentVersion> =
+        new.documents.iter().map(|e| (&e.id, &e.version)).collect();
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut modified = Vec::new();
+    let mut unchanged = 0;
+
+    for (id, old_version) in &old_docs {
+        match new_docs.get(id) {
+            None => removed.push(DiffEntry {
+                id: (*id).clone(),
+                version: (*old_version).clone(),
+            }),
+            Some(new_version) if new_version != old_version => modified.push(ModifiedEntry {
+                id: (*id).clone(),
+                old_version: (*old_version).clone(),
+                new_version: (*new_version).clone(),
+            }),
+            Some(_) => unchanged += 1,
+        }
+    }
+
+    for (id, new_version) in &new_docs {
+        if !old_docs.contains_key(id) {
+            added.push(DiffEntry {
+                id: (*id).clone(),
+                version: (*new_version).clone(),
+            });
+        }
+    }
+
+    CacheDiff {
+        old_cache_version: old.cache_version.clone(),
+        new_cache_version: new.cache_version.clone(),
+        added,
+        removed,
+        modified,
+        unchanged,
+    }
+}
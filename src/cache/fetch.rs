@@ -0,0 +1,167 @@
+//! Pulling a published cache over HTTP, pinned to the `cache_version` the
+//! caller expects. Runs the `curl` command-line tool, like
+//! `document::parser::ingest_url`.
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::cache::cache::{parse_manifest, ContextCache};
+use crate::cache::signing::SIGNATURE_FILE;
+use crate::cache::store::{CacheStore, FsStore};
+use crate::cache::vectors::VectorStore;
+use crate::cache::versioning::derived_cache_version;
+use crate::document::parser::url::fetch;
+use crate::document::parser::UrlError;
+
+/// Longest a single download may take.
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("Download failed: {0}")]
+    Http(#[from] UrlError),
+    #[error("Expected cache version {expected}, found {found}")]
+    VersionMismatch { expected: String, found: String },
+    #[error("Downloaded cache failed verification: {0}")]
+    Verification(io::Error),
+    #[error("Cache fetch I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Download the cache published at `base_url` into `dest_dir` (which must
+/// not exist), verify it, and open it.
+///
+/// A `base_url` ending in `.tar` is an archive written by
+/// `ContextCache::export_archive`; anything else is a cache directory
+/// served as is, whose `manifest.json`, `index.json`, document files and
+/// optional `vectors.json`, `tantivy.tar` and `manifest.sig` are fetched
+/// one by one. The manifest must be for `expected_cache_version` (checked
+/// before any document is downloaded), every file must hash to what the
+/// manifest records (`ContextCache::verify`), and the manifest's documents
+/// and config must hash to its `cache_version`, so a server cannot
+/// substitute another cache under the expected version. The cache is downloaded next
+/// to `dest_dir` and moved there only once all checks pass.
+pub fn fetch_http(
+    base_url: &str,
+    expected_cache_version: &str,
+    dest_dir: impl AsRef<Path>,
+) -> Result<ContextCache, FetchError> {
+    let dest_dir = dest_dir.as_ref();
+    if dest_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("output directory already exists: {}", dest_dir.display()),
+        )
+        .into());
+    }
+    let temp_dir = dest_dir.with_extension("fetch.tmp");
+    if temp_dir.exists() {
+        std::fs::remove_dir_all(&temp_dir)?;
+    }
+
+    let result = download(base_url, expected_cache_version, &temp_dir)
+        .and_then(|()| check(&temp_dir, expected_cache_version));
+    if result.is_err() {
+        // Best effort; a stale temp dir is also removed by the next fetch
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    result?;
+    std::fs::rename(&temp_dir, dest_dir)?;
+    Ok(ContextCache::open(dest_dir)?)
+}
+
+/// Download the cache at `base_url` into `temp_dir`.
+fn download(base_url: &str, expected_cache_version: &str, temp_dir: &Path) -> Result<(), FetchError> {
+    if base_url.ends_with(".tar") {
+        let archive = get(base_url)?.ok_or_else(|| not_found(base_url))?;
+        // Fails if a document does not match its manifest entry
+        ContextCache::import_archive_bytes_in(Arc::new(FsStore), &archive, temp_dir)
+            .map_err(FetchError::Verification)?;
+        return Ok(());
+    }
+
+    let base_url = base_url.trim_end_matches('/');
+    let url = |file: &str| format!("{}/{}", base_url, file);
+    let manifest_bytes = get(&url("manifest.json"))?.ok_or_else(|| not_found(&url("manifest.json")))?;
+    let manifest = parse_manifest(&manifest_bytes).map_err(FetchError::Verification)?;
+    if manifest.cache_version != expected_cache_version {
+        return Err(FetchError::VersionMismatch {
+            expected: expected_cache_version.to_string(),
+            found: manifest.cache_version,
+        });
+    }
+
+    let required = manifest.documents.iter().map(|entry| entry.file.as_str()).chain(["index.json"]);
+    for file in required {
+        let bytes = get(&url(file))?.ok_or_else(|| not_found(&url(file)))?;
+        FsStore.write_file_unsynced(&temp_dir.join(file), &bytes)?;
+    }
+    for file in ["vectors.json", "tantivy.tar", SIGNATURE_FILE] {
+        if let Some(bytes) = get(&url(file))? {
+            FsStore.write_file_unsynced(&temp_dir.join(file), &bytes)?;
+        }
+    }
+    // Last, so an interrupted download is never opened as a cache
+    FsStore.write_file(&temp_dir.join("manifest.json"), &manifest_bytes)?;
+    Ok(())
+}
+
+/// Verify the cache in `temp_dir` against its manifest, and the manifest
+/// against `expected_cache_version`.
+fn check(temp_dir: &Path, expected_cache_version: &str) -> Result<(), FetchError> {
+    let cache = ContextCache::open(temp_dir).map_err(FetchError::Verification)?;
+    if cache.manifest.cache_version != expected_cache_version {
+        return Err(FetchError::VersionMismatch {
+            expected: expected_cache_version.to_string(),
+            found: cache.manifest.cache_version,
+        });
+    }
+    cache.verify().map_err(FetchError::Verification)?;
+
+    let vectors_path = temp_dir.join("vectors.json");
+    let embedder = match std::fs::read(&vectors_path) {
+        Ok(bytes) => {
+            let vectors: VectorStore = serde_json::from_slice(&bytes)
+                .map_err(|e| FetchError::Verification(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+            Some(vectors.embedder)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let derived = derived_cache_version(&cache.manifest, embedder.as_deref());
+    if derived.as_deref() != Some(expected_cache_version) {
+        return Err(FetchError::Verification(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "manifest claims cache version {} but its documents hash to {}",
+                expected_cache_version,
+                derived.as_deref().unwrap_or("nothing (unknown hash algorithm)")
+            ),
+        )));
+    }
+    Ok(())
+}
+
+/// The body of `url`, or `None` on a 404.
+fn get(url: &str) -> Result<Option<Vec<u8>>, UrlError> {
+    let response = fetch(url, None, TIMEOUT)?;
+    match response.status {
+        200..=299 => Ok(Some(response.body)),
+        404 => Ok(None),
+        status => Err(UrlError::Status {
+            url: url.to_string(),
+            status,
+        }),
+    }
+}
+
+fn not_found(url: &str) -> UrlError {
+    UrlError::Status {
+        url: url.to_string(),
+        status: 404,
+    }
+}
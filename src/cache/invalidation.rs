@@ -1,39 +1,188 @@
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
 use std::collections::{BTreeMap, BTreeSet};
-use std::fs;
+use std::hash::{BuildHasher, Hasher};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-use chrono::Utc;
+use chrono::{DateTime, TimeZone, Utc};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 
-use crate::cache::cache::ContextCache;
-use crate::cache::versioning::{CacheBuildConfig, CacheIndex, CacheManifest, ManifestDocumentEntry};
-use crate::document::Document;
+use crate::cache::cache::{CacheReadError, ContextCache};
+use crate::cache::lock::{BuildLock, LockHolder};
+use crate::cache::memory::MemoryCache;
+use crate::cache::progress::{BuildObserver, BuildPhase, CancellationToken};
+use crate::cache::signing::{sign_tree_hash, SigningKey, SIGNATURE_FILE};
+#[cfg(feature = "fs")]
+use crate::cache::store::FsStore;
+#[cfg(not(feature = "fs"))]
+use crate::cache::store::MemoryStore;
+use crate::cache::store::CacheStore;
+use crate::cache::vectors::{quantize, Embedder, VectorStore};
+use crate::cache::versioning::{tree_hash, CacheBuildConfig, Durability, CacheIndex, CacheManifest, ManifestDocumentEntry};
+use crate::compat::{CACHE_FORMAT_VERSION, CACHE_MANIFEST_SCHEMA_VERSION};
+use crate::document::{Document, Source};
+use crate::metrics::{self, Metrics, Timer};
+use crate::types::identifiers::{DocumentId, DocumentVersion, HashAlgorithm, VersionHasher};
 
 #[derive(Debug, Error)]
 pub enum CacheBuildError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Cache read error: {0}")]
+    Read(#[from] CacheReadError),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[cfg(feature = "msgpack")]
+    #[error("MessagePack serialization error: {0}")]
+    Msgpack(#[from] rmp_serde::encode::Error),
+    #[cfg(feature = "tantivy")]
+    #[error("Tantivy index error: {0}")]
+    Tantivy(#[from] crate::selection::CandidateError),
     #[error("Output directory already exists: {0}")]
     OutputExists(PathBuf),
     #[error("Filename collision detected for hash fragment: {0}")]
     FilenameCollision(String),
-    #[error("Duplicate document ID: {0}")]
-    DuplicateDocumentId(String),
+    /// Two documents share an ID, possibly only after lowercasing (see
+    /// `CacheBuildConfig::case_sensitive_ids`); `sources` are theirs.
+    #[error("Duplicate document ID: {id} (from {})", sources.join(" and "))]
+    DuplicateDocumentId { id: String, sources: Vec<String> },
     #[error("Invalid version format: {0}")]
     InvalidVersionFormat(String),
+    #[error("Unsupported hash algorithm: {0}")]
+    UnsupportedHashAlgorithm(String),
+    #[error("Invalid SOURCE_DATE_EPOCH: {0}")]
+    InvalidSourceDateEpoch(String),
+    #[error("Document source error: {0}")]
+    Source(Box<dyn std::error::Error + Send + Sync>),
+    /// Another build holds the lock file `path` (see `cache::lock`).
+    #[error(
+        "Cache build locked by {}: {}",
+        holder.as_ref().map_or("another build".to_string(), LockHolder::to_string),
+        path.display()
+    )]
+    Locked { path: PathBuf, holder: Option<LockHolder> },
+    /// The builder's `CancellationToken` was cancelled.
+    #[error("Build cancelled")]
+    Cancelled,
+    /// The config asks for `setting`, which needs a crate feature this
+    /// build was compiled without.
+    #[error("{setting} requires the `{feature}` feature")]
+    FeatureDisabled { setting: &'static str, feature: &'static str },
 }
 
-/// CacheBuilder is single-threaded and non-reentrant by design.
+/// What a build does when its output directory already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BuildMode {
+    /// Fail with `CacheBuildError::OutputExists`.
+    #[default]
+    ErrorIfExists,
+    /// Build into a temp dir as usual, then move the existing directory
+    /// aside (to `<output>.old.<version prefix>`), rename the new cache into
+    /// place, and remove the old one. The old cache stays complete until the
+    /// swap, and readers that already opened it keep their files on Unix.
+    ///
+    /// The swap is two renames, not one atomic exchange: between them there
+    /// is no cache at the output path. If the second rename fails, the old
+    /// cache is moved back; after a crash between them, rename the `.old.`
+    /// directory back to recover it. An old cache that cannot be removed is
+    /// left aside without failing the build.
+    Overwrite,
+}
+
+/// CacheBuilder is single-threaded and non-reentrant by design. Builds hold
+/// a lock file next to their output (`<output>.lock`, see `cache::lock`),
+/// so builds from other processes into the same output wait their turn by
+/// failing with `CacheBuildError::Locked`; builds into other outputs, even
+/// in the same directory, do not wait.
 pub struct CacheBuilder {
     config: CacheBuildConfig,
+    embedder: Option<Box<dyn Embedder>>,
+    store: Arc<dyn CacheStore>,
+    signing_key: Option<SigningKey>,
+    mode: BuildMode,
+    stale_lock_after: Option<Duration>,
+    observer: Option<Box<dyn BuildObserver>>,
+    cancellation: Option<CancellationToken>,
+    metrics: Option<Box<dyn Metrics>>,
 }
 
 impl CacheBuilder {
+    /// Builds onto the local filesystem, or into a fresh `MemoryStore`
+    /// without the `fs` feature.
     pub fn new(config: CacheBuildConfig) -> Self {
-        Self { config }
+        #[cfg(feature = "fs")]
+        let store: Arc<dyn CacheStore> = Arc::new(FsStore);
+        #[cfg(not(feature = "fs"))]
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+
+        Self {
+            config,
+            embedder: None,
+            store,
+            signing_key: None,
+            mode: BuildMode::ErrorIfExists,
+            stale_lock_after: None,
+            observer: None,
+            cancellation: None,
+            metrics: None,
+        }
+    }
+
+    /// Write the cache through `store` instead of the local filesystem.
+    /// The returned `ContextCache` reads from the same store.
+    pub fn with_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Compute a vector per document with `embedder` and write them to
+    /// `vectors.json`. The embedder id is folded into the cache version.
+    pub fn with_embedder(mut self, embedder: impl Embedder + 'static) -> Self {
+        self.embedder = Some(Box::new(embedder));
+        self
+    }
+
+    pub fn with_build_mode(mut self, mode: BuildMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Take over a build lock held longer than `after`, assuming its build
+    /// crashed. By default a lock is never taken over; pick `after` well
+    /// above your longest build, as a build does not refresh its lock.
+    pub fn with_stale_lock_after(mut self, after: Duration) -> Self {
+        self.stale_lock_after = Some(after);
+        self
+    }
+
+    /// Report phases and written documents to `observer`.
+    pub fn with_observer(mut self, observer: impl BuildObserver + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Report each successful build to `metrics` (see `metrics` for the
+    /// names).
+    pub fn with_metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.metrics = Some(Box::new(metrics));
+        self
+    }
+
+    /// Stop building, between documents, once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sign the manifest `tree_hash` with `key` and write it to
+    /// `manifest.sig`. Load with `ContextCache::open_verified`.
+    pub fn with_signing_key(mut self, key: SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
     }
 
     pub fn build(
@@ -41,141 +190,689 @@ impl CacheBuilder {
         documents: Vec<Document>,
         output_dir: &Path,
     ) -> Result<ContextCache, CacheBuildError> {
-        if output_dir.exists() {
+        let timer = self.timer();
+        let _lock = BuildLock::acquire(&self.store, output_dir, self.stale_lock_after)?;
+        self.check_output(output_dir)?;
+
+        let BuildPlan { manifest, files, .. } = self.plan(documents)?;
+        let temp_dir = self.create_temp_dir(&manifest.cache_version, output_dir)?;
+        let result = self.commit(&temp_dir, output_dir, &files, manifest);
+        self.built(self.discard_on_error(result, &temp_dir), timer)
+    }
+
+    /// Build from documents already encoded by a cache with this builder's
+    /// config, writing each one's `bytes` as they are rather than
+    /// normalizing, summarizing, and encoding it again. `documents` must be
+    /// sorted by ID; `vectors` are theirs, under the embedder that computed
+    /// them. The cache version is the one `build` would give the documents.
+    pub(crate) fn build_encoded(
+        &self,
+        documents: Vec<(Document, Vec<u8>)>,
+        vectors: Option<VectorStore>,
+        output_dir: &Path,
+    ) -> Result<ContextCache, CacheBuildError> {
+        let timer = self.timer();
+        let _lock = BuildLock::acquire(&self.store, output_dir, self.stale_lock_after)?;
+        self.check_output(output_dir)?;
+        self.check_features()?;
+        let algorithm = self.hash_algorithm()?;
+        self.enter(BuildPhase::Planning);
+
+        let (manifest_documents, version_hasher) =
+            self.assign_files(algorithm, documents.iter().map(|(d, _)| (&d.id, &d.version, &d.source)))?;
+        let tantivy_index = self.tantivy_index(documents.iter().map(|(doc, _)| doc))?;
+        let document_files: BTreeMap<String, Vec<u8>> = documents
+            .into_iter()
+            .zip(&manifest_documents)
+            .map(|((_, bytes), entry)| (entry.file.clone(), bytes))
+            .collect();
+        let checksums = document_files
+            .iter()
+            .map(|(path, bytes)| (path.clone(), algorithm.checksum(bytes)))
+            .collect();
+
+        let vectors = vectors.map(|store| (store.embedder, store.vectors));
+        let BuildPlan { manifest, mut files, .. } =
+            self.assemble(algorithm, version_hasher, manifest_documents, vectors, tantivy_index, checksums)?;
+        files.extend(document_files);
+
+        let temp_dir = self.create_temp_dir(&manifest.cache_version, output_dir)?;
+        let result = self.commit(&temp_dir, output_dir, &files, manifest);
+        self.built(self.discard_on_error(result, &temp_dir), timer)
+    }
+
+    /// Remove `temp_dir` if `result` failed before it was renamed into
+    /// place.
+    fn discard_on_error<T>(&self, result: Result<T, CacheBuildError>, temp_dir: &Path) -> Result<T, CacheBuildError> {
+        if result.is_err() && self.store.exists(temp_dir) {
+            // Best effort; a stale temp dir is also removed by the next run
+            let _ = self.store.remove_dir_all(temp_dir);
+        }
+        result
+    }
+
+    /// Write a cache file, fsyncing it unless building `Durability::Fast`.
+    fn write_file(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        match self.config.durability {
+            Durability::Durable => self.store.write_file(path, contents),
+            Durability::Fast => self.store.write_file_unsynced(path, contents),
+        }
+    }
+
+    /// Times a build when reporting metrics.
+    fn timer(&self) -> Option<Timer> {
+        self.metrics.as_ref().map(|_| Timer::start())
+    }
+
+    /// Report a successful build to the metrics.
+    fn built(
+        &self,
+        result: Result<ContextCache, CacheBuildError>,
+        timer: Option<Timer>,
+    ) -> Result<ContextCache, CacheBuildError> {
+        if let Ok(cache) = &result {
+            self.record_build(&cache.manifest, timer);
+        }
+        result
+    }
+
+    fn record_build(&self, manifest: &CacheManifest, timer: Option<Timer>) {
+        if let (Some(metrics), Some(timer)) = (self.metrics.as_deref(), timer) {
+            metrics.counter(metrics::DOCUMENTS_BUILT, manifest.documents.len() as u64);
+            timer.record(metrics, metrics::CACHE_BUILD_MS);
+        }
+    }
+
+    fn enter(&self, phase: BuildPhase) {
+        if let Some(observer) = &self.observer {
+            observer.on_phase_change(phase);
+        }
+    }
+
+    fn written(&self, id: &DocumentId, written: usize, total: Option<usize>) {
+        if let Some(observer) = &self.observer {
+            observer.on_document_written(id, written, total);
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), CacheBuildError> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(CacheBuildError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if `output_dir` exists, unless overwriting it.
+    fn check_output(&self, output_dir: &Path) -> Result<(), CacheBuildError> {
+        if self.mode == BuildMode::ErrorIfExists && self.store.exists(output_dir) {
             return Err(CacheBuildError::OutputExists(output_dir.to_path_buf()));
         }
+        Ok(())
+    }
+
+    /// A free path next to `output_dir` to move the output being replaced
+    /// to: `<output>.old.<first 12 hex of cache_version>`, with a counter
+    /// appended if taken. Existing directories (another output's, a user's,
+    /// or one left by a crashed overwrite) are left alone.
+    fn aside_dir(&self, output_dir: &Path, cache_version: &str) -> PathBuf {
+        let version_hex = cache_version.split_once(':').map_or(cache_version, |(_, hex)| hex);
+        let base = format!("old.{}", &version_hex[..12.min(version_hex.len())]);
+        let mut aside = sibling_path(output_dir, &base);
+        let mut n = 1;
+        while self.store.exists(&aside) {
+            aside = sibling_path(output_dir, &format!("{}.{}", base, n));
+            n += 1;
+        }
+        aside
+    }
+
+    /// Create an empty temp dir (with `documents/`) to build `cache_version`
+    /// in before it is renamed to `output_dir`.
+    fn create_temp_dir(&self, cache_version: &str, output_dir: &Path) -> Result<PathBuf, CacheBuildError> {
+        // 4. Write to temp dir
+        // Use a deterministic-but-unique temp dir
+        // We use the first 12 chars of the new cache version to avoid collisions
+        // between different builds targeting the same parent dir (unlikely but safer)
+        let version_hex = cache_version.split_once(':').map_or(cache_version, |(_, hex)| hex);
+        let temp_suffix = format!("tmp.{}", &version_hex[..12]);
+        let temp_dir = sibling_path(output_dir, &temp_suffix);
+
+        // Clean up any stale temp dir from a crashed previous run of THIS specific version
+        if self.store.exists(&temp_dir) {
+            self.store.remove_dir_all(&temp_dir)?;
+        }
+        self.store.create_dir_all(&temp_dir.join("documents"))?;
+        Ok(temp_dir)
+    }
+
+    /// Build from a stream of documents without holding the corpus in memory.
+    ///
+    /// Each document is serialized to a spool file in the temp dir as it
+    /// arrives; only its ID, version, and checksum (plus its vector, with an
+    /// embedder) are kept. Once the stream ends, documents are named and
+    /// versioned in sorted ID order exactly as in `build`, so the output is
+    /// identical. The first `Err` from `documents` aborts the build.
+    pub fn build_from_iter<I, E>(&self, documents: I, output_dir: &Path) -> Result<ContextCache, CacheBuildError>
+    where
+        I: IntoIterator<Item = Result<Document, E>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let timer = self.timer();
+        let store = &self.store;
+        let _lock = BuildLock::acquire(&self.store, output_dir, self.stale_lock_after)?;
+        self.check_output(output_dir)?;
+        self.check_features()?;
+        let algorithm = self.hash_algorithm()?;
+
+        // The cache version is not known until the stream ends, so the temp
+        // dir name cannot depend on it
+        let temp_dir = sibling_path(output_dir, "tmp.stream");
+        if store.exists(&temp_dir) {
+            store.remove_dir_all(&temp_dir)?;
+        }
+        store.create_dir_all(&temp_dir.join("documents"))?;
+
+        let result = self.spool(documents, algorithm, &temp_dir, output_dir);
+        self.built(self.discard_on_error(result, &temp_dir), timer)
+    }
+
+    fn spool<I, E>(
+        &self,
+        documents: I,
+        algorithm: HashAlgorithm,
+        temp_dir: &Path,
+        output_dir: &Path,
+    ) -> Result<ContextCache, CacheBuildError>
+    where
+        I: IntoIterator<Item = Result<Document, E>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let store = &self.store;
+        let spool_dir = temp_dir.join("spool");
+
+        let mut spooled = Vec::new();
+        let mut vectors = BTreeMap::new();
+        self.enter(BuildPhase::Writing);
+        for (seq, item) in documents.into_iter().enumerate() {
+            self.check_cancelled()?;
+            let mut doc = item.map_err(|e| CacheBuildError::Source(Box::new(e)))?;
+            self.fold_case(&mut doc);
+            self.normalize(&mut doc, algorithm);
+            reversion(&mut doc, algorithm);
+            self.summarize(&mut doc);
+
+            let encoding = self.config.document_encoding;
+            let bytes = encoding.encode(&doc)?;
+            let spool_file = spool_dir.join(format!("{:08}.{}", seq, encoding.extension()));
+            self.write_file(&spool_file, &bytes)?;
+
+            if let Some(embedder) = &self.embedder {
+                vectors.insert(doc.id.clone(), quantize(&embedder.embed(&doc.content)));
+            }
+            self.written(&doc.id, seq + 1, None);
+            spooled.push(SpooledDocument {
+                id: doc.id,
+                version: doc.version,
+                source: doc.source,
+                checksum: algorithm.checksum(&bytes),
+                spool_file,
+            });
+        }
+
+        spooled.sort_by(|a, b| a.id.cmp(&b.id));
+        let (manifest_documents, version_hasher) =
+            self.assign_files(algorithm, spooled.iter().map(|s| (&s.id, &s.version, &s.source)))?;
+
+        // Move spooled files to their final names
+        let mut checksums = BTreeMap::new();
+        for (doc, entry) in spooled.into_iter().zip(&manifest_documents) {
+            let file = temp_dir.join(&entry.file);
+            if let Some(shard) = file.parent().filter(|shard| !store.exists(shard)) {
+                store.create_dir_all(shard)?;
+            }
+            store.rename(&doc.spool_file, &file)?;
+            checksums.insert(entry.file.clone(), doc.checksum);
+        }
+        if store.exists(&spool_dir) {
+            store.remove_dir_all(&spool_dir)?;
+        }
+
+        // The index is built in ID order, not stream order, so read the
+        // documents back
+        let tantivy_index = if self.config.tantivy_index {
+            let encoding = self.config.document_encoding;
+            let documents = manifest_documents
+                .iter()
+                .map(|entry| encoding.decode(&store.read_file(&temp_dir.join(&entry.file))?))
+                .collect::<Result<Vec<_>, std::io::Error>>()?;
+            self.tantivy_index(&documents)?
+        } else {
+            None
+        };
+
+        let vectors = self.embedder.as_ref().map(|embedder| (embedder.id(), vectors));
+        let BuildPlan { manifest, files, .. } =
+            self.assemble(algorithm, version_hasher, manifest_documents, vectors, tantivy_index, checksums)?;
+        self.commit(temp_dir, output_dir, &files, manifest)
+    }
+
+    /// Write `files`, the manifest, and the signature into `temp_dir`, then
+    /// atomically rename it to `output_dir`.
+    fn commit(
+        &self,
+        temp_dir: &Path,
+        output_dir: &Path,
+        files: &BTreeMap<String, Vec<u8>>,
+        manifest: CacheManifest,
+    ) -> Result<ContextCache, CacheBuildError> {
+        let store = &self.store;
+
+        // Write documents (in ID order, for progress), then index.json and
+        // vectors.json (serialized up front so the bytes match the manifest
+        // checksums)
+        let documents: Vec<(&ManifestDocumentEntry, &Vec<u8>)> = manifest
+            .documents
+            .iter()
+            .filter_map(|entry| files.get(&entry.file).map(|bytes| (entry, bytes)))
+            .collect();
+        if !documents.is_empty() {
+            self.enter(BuildPhase::Writing);
+        }
+        for (i, (entry, bytes)) in documents.iter().enumerate() {
+            self.check_cancelled()?;
+            self.write_file(&temp_dir.join(&entry.file), bytes)?;
+            self.written(&entry.id, i + 1, Some(documents.len()));
+        }
+        self.enter(BuildPhase::Committing);
+        for (relative_path, bytes) in files {
+            if !relative_path.starts_with("documents/") {
+                self.write_file(&temp_dir.join(relative_path), bytes)?;
+            }
+        }
+
+        // Write manifest.json
+        self.write_file(&temp_dir.join("manifest.json"), &serde_json::to_vec_pretty(&manifest)?)?;
+
+        // Write manifest.sig
+        if let (Some(key), Some(tree_hash)) = (&self.signing_key, &manifest.tree_hash) {
+            let signature = sign_tree_hash(key, tree_hash);
+            self.write_file(&temp_dir.join(SIGNATURE_FILE), signature.as_bytes())?;
+        }
+
+        // 5. Atomic Rename (moving an existing output aside when overwriting)
+        let replaced = if self.mode == BuildMode::Overwrite && store.exists(output_dir) {
+            let replaced = self.aside_dir(output_dir, &manifest.cache_version);
+            store.rename(output_dir, &replaced)?;
+            Some(replaced)
+        } else {
+            None
+        };
+        if let Err(e) = store.rename(temp_dir, output_dir) {
+            // Put the old cache back, so a failed swap leaves it in place
+            if let Some(replaced) = &replaced {
+                let _ = store.rename(replaced, output_dir);
+            }
+            return Err(e.into());
+        }
+        // Only the directory moved aside above; never one this build did
+        // not create. Best effort: the new cache is already in place
+        if let Some(replaced) = replaced {
+            let _ = store.remove_dir_all(&replaced);
+        }
+
+        Ok(ContextCache::from_parts(
+            Arc::clone(store),
+            output_dir.to_path_buf(),
+            manifest,
+        ))
+    }
+
+    /// Build the cache in memory only. Versioning is identical to `build`.
+    pub fn build_in_memory(&self, documents: Vec<Document>) -> Result<MemoryCache, CacheBuildError> {
+        let timer = self.timer();
+        let cache = MemoryCache::from_plan(self.plan(documents)?);
+        self.record_build(cache.manifest(), timer);
+        Ok(cache)
+    }
+
+    /// `created_at` for the manifest: the configured fixed timestamp, else
+    /// `SOURCE_DATE_EPOCH` (seconds since the Unix epoch), else now (the
+    /// Unix epoch without the `clock` feature).
+    fn created_at(&self) -> Result<DateTime<Utc>, CacheBuildError> {
+        if let Some(ts) = self.config.fixed_timestamp {
+            return Ok(ts);
+        }
+        match std::env::var("SOURCE_DATE_EPOCH") {
+            Ok(raw) => raw
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                .ok_or(CacheBuildError::InvalidSourceDateEpoch(raw)),
+            #[cfg(feature = "clock")]
+            Err(_) => Ok(Utc::now()),
+            #[cfg(not(feature = "clock"))]
+            Err(_) => Ok(DateTime::UNIX_EPOCH),
+        }
+    }
+
+    /// Lowercase `doc`'s ID unless `case_sensitive_ids` is set.
+    fn fold_case(&self, doc: &mut Document) {
+        if !self.config.case_sensitive_ids {
+            doc.id = doc.id.to_lowercase();
+        }
+    }
+
+    /// Apply the configured normalization to `doc`, re-versioning it if its
+    /// content changed.
+    fn normalize(&self, doc: &mut Document, algorithm: HashAlgorithm) {
+        let Some(normalization) = &self.config.normalization else {
+            return;
+        };
+        if let Cow::Owned(content) = normalization.apply(&doc.content) {
+            doc.version = DocumentVersion::from_content_with(algorithm, content.as_bytes());
+            doc.content = content;
+        }
+    }
+
+    /// Store the configured summarizer's summary of `doc`, if any.
+    fn summarize(&self, doc: &mut Document) {
+        if let Some(summarizer) = &self.config.summarizer {
+            doc.summary = summarizer.summarize(&doc.content);
+        }
+    }
+
+    /// Fail, before anything is written, on settings that need a feature
+    /// this build was compiled without.
+    fn check_features(&self) -> Result<(), CacheBuildError> {
+        #[cfg(not(feature = "tantivy"))]
+        if self.config.tantivy_index {
+            return Err(CacheBuildError::FeatureDisabled {
+                setting: "CacheBuildConfig::tantivy_index",
+                feature: "tantivy",
+            });
+        }
+        #[cfg(not(feature = "msgpack"))]
+        if self.config.document_encoding == crate::cache::versioning::DocumentEncoding::Msgpack {
+            return Err(CacheBuildError::FeatureDisabled {
+                setting: "DocumentEncoding::Msgpack",
+                feature: "msgpack",
+            });
+        }
+        Ok(())
+    }
+
+    /// The `tantivy.tar` bytes for `documents` (sorted by ID), if the
+    /// config asks for them.
+    fn tantivy_index<'a>(
+        &self,
+        documents: impl IntoIterator<Item = &'a Document>,
+    ) -> Result<Option<Vec<u8>>, CacheBuildError> {
+        #[cfg(feature = "tantivy")]
+        if self.config.tantivy_index {
+            return Ok(Some(crate::selection::tantivy_index::build_index(documents)?));
+        }
+        #[cfg(not(feature = "tantivy"))]
+        let _ = documents;
+        Ok(None)
+    }
+
+    fn hash_algorithm(&self) -> Result<HashAlgorithm, CacheBuildError> {
+        HashAlgorithm::from_name(&self.config.hash_algorithm)
+            .ok_or_else(|| CacheBuildError::UnsupportedHashAlgorithm(self.config.hash_algorithm.clone()))
+    }
+
+    /// Sort, validate, and version `documents`; compute manifest, index, and
+    /// vectors. No I/O. `manifest.documents` is index-aligned with the
+    /// returned (sorted) documents.
+    pub(crate) fn plan(&self, documents: Vec<Document>) -> Result<BuildPlan, CacheBuildError> {
+        self.check_features()?;
+        let algorithm = self.hash_algorithm()?;
+        self.enter(BuildPhase::Planning);
 
         // 1. Sort documents by ID to ensure determinism
         let mut sorted_docs = documents;
+        sorted_docs.iter_mut().for_each(|doc| self.fold_case(doc));
         sorted_docs.sort_by(|a, b| a.id.cmp(&b.id));
+        for doc in &mut sorted_docs {
+            self.check_cancelled()?;
+            self.normalize(doc, algorithm);
+            reversion(doc, algorithm);
+            self.summarize(doc);
+        }
+
+        let (manifest_documents, version_hasher) =
+            self.assign_files(algorithm, sorted_docs.iter().map(|d| (&d.id, &d.version, &d.source)))?;
 
-        // 1b. Check for duplicate document IDs (adjacent after sort)
-        for pair in sorted_docs.windows(2) {
-            if pair[0].id == pair[1].id {
-                return Err(CacheBuildError::DuplicateDocumentId(
-                    pair[0].id.as_str().to_string(),
-                ));
+        let vectors = match &self.embedder {
+            Some(embedder) => {
+                let mut vectors = BTreeMap::new();
+                for doc in &sorted_docs {
+                    self.check_cancelled()?;
+                    vectors.insert(doc.id.clone(), quantize(&embedder.embed(&doc.content)));
+                }
+                Some((embedder.id(), vectors))
             }
+            None => None,
+        };
+
+        // Serialize documents and checksum them
+        let mut document_files = BTreeMap::new();
+        for (doc, entry) in sorted_docs.iter().zip(&manifest_documents) {
+            self.check_cancelled()?;
+            document_files.insert(entry.file.clone(), self.config.document_encoding.encode(doc)?);
         }
+        let checksums = document_files
+            .iter()
+            .map(|(path, bytes)| (path.clone(), algorithm.checksum(bytes)))
+            .collect();
 
+        let tantivy_index = self.tantivy_index(&sorted_docs)?;
+        let mut plan =
+            self.assemble(algorithm, version_hasher, manifest_documents, vectors, tantivy_index, checksums)?;
+        plan.documents = sorted_docs;
+        plan.files.extend(document_files);
+        Ok(plan)
+    }
+
+    /// Check for duplicate IDs, hash the config and every `id:version` line
+    /// into the cache version, and assign each document its file name.
+    /// `documents` must be sorted by ID; the returned entries keep that order.
+    fn assign_files<'a>(
+        &self,
+        algorithm: HashAlgorithm,
+        documents: impl Iterator<Item = (&'a DocumentId, &'a DocumentVersion, &'a Source)>,
+    ) -> Result<(Vec<ManifestDocumentEntry>, VersionHasher), CacheBuildError> {
         // 2. Prepare structures and check for collisions
-        // We store pairs of (Document, ManifestEntry) to guarantee alignment explicitly
-        let mut doc_contexts = Vec::with_capacity(sorted_docs.len());
-        let mut index_entries = BTreeMap::new();
+        let mut manifest_documents: Vec<ManifestDocumentEntry> = Vec::new();
         let mut seen_filenames = BTreeSet::new();
 
         // Used for cache version computation
         // "sorted(document_id + ":" + document_version)"
-        let mut version_hasher = Sha256::new();
+        let mut version_hasher = algorithm.hasher();
 
         // Hash the config
         let config_json = serde_json::to_vec(&self.config)?;
         version_hasher.update(&config_json);
 
-        for doc in &sorted_docs {
+        let mut previous_source: Option<&Source> = None;
+        for (id, version, source) in documents {
+            // Check for duplicate document IDs (adjacent after sort)
+            if manifest_documents.last().is_some_and(|prev| &prev.id == id) {
+                let mut sources: Vec<String> =
+                    previous_source.into_iter().chain([source]).map(Source::to_string).collect();
+                sources.sort();
+                return Err(CacheBuildError::DuplicateDocumentId {
+                    id: id.as_str().to_string(),
+                    sources,
+                });
+            }
+            previous_source = Some(source);
+
             // Update cache version hash
-            let line = format!("{}:{}", doc.id.as_str(), doc.version.as_str());
+            let line = format!("{}:{}", id.as_str(), version.as_str());
             version_hasher.update(line.as_bytes());
 
             // Determine filename: first 12 chars of version hash (without prefix)
-            let full_hash = doc
-                .version
-                .as_str()
-                .strip_prefix("sha256:")
-                .ok_or_else(|| CacheBuildError::InvalidVersionFormat(doc.version.as_str().to_string()))?;
+            let full_hash = version
+                .digest()
+                .ok_or_else(|| CacheBuildError::InvalidVersionFormat(version.as_str().to_string()))?;
 
             if full_hash.len() < 12 {
-                // Should not happen for sha256, but safe handling
+                // Should not happen for sha256/blake3, but safe handling
                 return Err(CacheBuildError::FilenameCollision(full_hash.to_string()));
             }
-            let filename_stem = &full_hash[..12];
-            let filename = format!("{}.json", filename_stem);
+            let mut filename_stem = full_hash[..12].to_string();
 
-            // Check collision
-            if seen_filenames.contains(filename_stem) {
-                return Err(CacheBuildError::FilenameCollision(filename_stem.to_string()));
+            // Identical content (or a shared 12-char prefix) under another ID:
+            // disambiguate with a hash of the ID. Documents are visited in
+            // sorted ID order, so the first ID keeps the plain name and the
+            // assignment is deterministic.
+            if seen_filenames.contains(&filename_stem) {
+                let id_hash = hex::encode(Sha256::digest(id.as_str().as_bytes()));
+                filename_stem = format!("{}-{}", filename_stem, &id_hash[..12]);
             }
-            seen_filenames.insert(filename_stem.to_string());
-
-            // Add to entries
-            let relative_path = format!("documents/{}", filename);
 
-            let entry = ManifestDocumentEntry {
-                id: doc.id.clone(),
-                version: doc.version.clone(),
-                file: relative_path.clone(),
+            // Check collision
+            if seen_filenames.contains(&filename_stem) {
+                return Err(CacheBuildError::FilenameCollision(filename_stem));
+            }
+            let filename = format!("{}.{}", filename_stem, self.config.document_encoding.extension());
+            let file = if self.config.sharded_documents {
+                format!("documents/{}/{}", &filename_stem[..2], filename)
+            } else {
+                format!("documents/{}", filename)
             };
+            seen_filenames.insert(filename_stem);
 
-            index_entries.insert(doc.id.clone(), relative_path);
-            doc_contexts.push((doc, entry));
+            manifest_documents.push(ManifestDocumentEntry {
+                id: id.clone(),
+                version: version.clone(),
+                file,
+            });
         }
 
-        let hash_bytes = version_hasher.finalize();
-        let cache_version = format!("sha256:{}", hex::encode(hash_bytes));
+        Ok((manifest_documents, version_hasher))
+    }
 
-        // 3. Create Manifest
-        // Collect manifest documents from our aligned context
-        let mut manifest_documents: Vec<ManifestDocumentEntry> = doc_contexts
-            .iter()
-            .map(|(_, entry)| entry.clone())
-            .collect();
+    /// Finish a build from its named documents: embedder line, index,
+    /// vectors, checksums, and manifest. `vectors` come with the id of the
+    /// embedder that computed them. `checksums` holds the document files;
+    /// the returned plan has no documents and only the index, vectors and
+    /// tantivy index files.
+    fn assemble(
+        &self,
+        algorithm: HashAlgorithm,
+        mut version_hasher: VersionHasher,
+        manifest_documents: Vec<ManifestDocumentEntry>,
+        vectors: Option<(String, BTreeMap<DocumentId, Vec<i32>>)>,
+        tantivy_index: Option<Vec<u8>>,
+        mut checksums: BTreeMap<String, String>,
+    ) -> Result<BuildPlan, CacheBuildError> {
+        // Vectors are derived data, but a different embedder must still
+        // produce a different cache version
+        let vector_store = vectors.map(|(embedder_id, vectors)| {
+            version_hasher.update(format!("embedder:{}", embedder_id).as_bytes());
+            VectorStore {
+                embedder: embedder_id,
+                dimensions: vectors.values().next().map_or(0, Vec::len),
+                vectors,
+            }
+        });
+
+        let cache_version = version_hasher.finalize();
 
-        // Explicitly sort again just to be absolutely safe against refactors
-        manifest_documents.sort_by(|a, b| a.id.cmp(&b.id));
+        let index = CacheIndex::new(
+            manifest_documents
+                .iter()
+                .map(|entry| (entry.id.clone(), entry.file.clone()))
+                .collect(),
+        );
+
+        let mut files = BTreeMap::new();
+        // BTreeMap ensures lexicographical sort of keys
+        files.insert("index.json".to_string(), serde_json::to_vec_pretty(&index)?);
+        if let Some(vectors) = &vector_store {
+            files.insert("vectors.json".to_string(), serde_json::to_vec(vectors)?);
+        }
+        if let Some(bytes) = tantivy_index {
+            files.insert("tantivy.tar".to_string(), bytes);
+        }
+        for (path, bytes) in &files {
+            checksums.insert(path.clone(), algorithm.checksum(bytes));
+        }
+
+        // 3. Create Manifest
+        // manifest_documents was filled in sorted document order; the
+        // writer relies on this alignment
+        debug_assert!(manifest_documents.windows(2).all(|w| w[0].id < w[1].id));
 
         // Note: created_at is strictly informational
         let manifest = CacheManifest {
-            cache_version: cache_version.clone(),
+            schema_version: CACHE_MANIFEST_SCHEMA_VERSION,
+            format_version: CACHE_FORMAT_VERSION,
+            crate_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            cache_version,
             build_config: self.config.clone(),
-            created_at: Utc::now(),
-            document_count: sorted_docs.len(),
+            created_at: self.created_at()?,
+            document_count: manifest_documents.len(),
             documents: manifest_documents,
+            index_checksum: checksums.get("index.json").cloned(),
+            tree_hash: Some(tree_hash(algorithm, &checksums)),
         };
 
-        let index = CacheIndex::new(index_entries);
-
-        // 4. Write to temp dir
-        // Use a deterministic-but-unique temp dir
-        // We use the first 12 chars of the new cache version to avoid collisions
-        // between different builds targeting the same parent dir (unlikely but safer)
-        let temp_suffix = format!("tmp.{}", &cache_version[7..19]);
-        let temp_dir = output_dir.with_extension(temp_suffix);
-
-        // Clean up any stale temp dir from a crashed previous run of THIS specific version
-        if temp_dir.exists() {
-            fs::remove_dir_all(&temp_dir)?;
-        }
-        fs::create_dir_all(&temp_dir)?;
-        fs::create_dir(temp_dir.join("documents"))?;
+        Ok(BuildPlan {
+            documents: Vec::new(),
+            manifest,
+            index,
+            vectors: vector_store,
+            files,
+        })
+    }
+}
 
-        // Write documents
-        // doc_contexts guarantees alignment
-        for (doc, entry) in doc_contexts {
-            let path = temp_dir.join(&entry.file); // entry.file is "documents/..."
-            let f = fs::File::create(path)?;
-            serde_json::to_writer(&f, doc)?;
-            f.sync_all()?;
-        }
+/// `path` with `.<suffix>` appended to its file name: the temp, aside and
+/// lock paths of an output. Unlike `Path::with_extension`, outputs that
+/// differ only in extension get different paths.
+pub(crate) fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
 
-        // Write index.json
-        let index_path = temp_dir.join("index.json");
-        let f_idx = fs::File::create(index_path)?;
-        // BTreeMap ensures lexicographical sort of keys
-        serde_json::to_writer_pretty(&f_idx, &index)?;
-        f_idx.sync_all()?;
+/// 16 hex digits no other call returns: `RandomState` is seeded per
+/// process, and the counter tells this process's calls apart.
+pub(crate) fn unique_hex() -> String {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(CALLS.fetch_add(1, Ordering::Relaxed));
+    format!("{:016x}", hasher.finish())
+}
 
-        // Write manifest.json
-        let manifest_path = temp_dir.join("manifest.json");
-        let f_man = fs::File::create(manifest_path)?;
-        serde_json::to_writer_pretty(&f_man, &manifest)?;
-        f_man.sync_all()?;
+/// Re-version a document ingested with a different algorithm
+/// (`Document::ingest` always uses sha256).
+fn reversion(doc: &mut Document, algorithm: HashAlgorithm) {
+    if doc.version.algorithm() != Some(algorithm) {
+        doc.version = DocumentVersion::from_content_with(algorithm, doc.content.as_bytes());
+    }
+}
 
-        // 5. Atomic Rename
-        fs::rename(&temp_dir, output_dir)?;
+/// A streamed document after it has been written to its spool file.
+struct SpooledDocument {
+    id: DocumentId,
+    version: DocumentVersion,
+    source: Source,
+    checksum: String,
+    spool_file: PathBuf,
+}
 
-        Ok(ContextCache {
-            root: output_dir.to_path_buf(),
-            manifest,
-        })
-    }
+/// Output of the planning phase shared by all build targets.
+pub(crate) struct BuildPlan {
+    pub documents: Vec<Document>,
+    pub manifest: CacheManifest,
+    pub index: CacheIndex,
+    pub vectors: Option<VectorStore>,
+    /// Serialized artifacts keyed by relative path (all but the manifest).
+    pub files: BTreeMap<String, Vec<u8>>,
 }
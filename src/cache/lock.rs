@@ -0,0 +1,168 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::invalidation::{sibling_path, CacheBuildError};
+use crate::cache::store::CacheStore;
+
+/// Extension of the lock file a build holds next to its output: a build
+/// into `caches/a` holds `caches/a.lock`.
+pub const LOCK_EXTENSION: &str = "lock";
+
+/// The lock file builds into `output_dir` hold.
+pub fn lock_path(output_dir: &Path) -> PathBuf {
+    sibling_path(output_dir, LOCK_EXTENSION)
+}
+
+/// What a lock file records about the build holding it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockHolder {
+    /// Process id, where the platform has them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// Seconds since the Unix epoch, with the `clock` feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquired_at: Option<i64>,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        let pid = Some(std::process::id());
+        #[cfg(target_family = "wasm")]
+        let pid = None;
+        Self {
+            pid,
+            acquired_at: now(),
+        }
+    }
+
+    /// Whether the lock was taken more than `stale_after` ago. Without a
+    /// clock, or a recorded time, no lock is stale.
+    fn is_stale(&self, stale_after: Duration) -> bool {
+        match (self.acquired_at, now()) {
+            (Some(acquired_at), Some(now)) => {
+                now.saturating_sub(acquired_at) > i64::try_from(stale_after.as_secs()).unwrap_or(i64::MAX)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "process {}", pid)?,
+            None => write!(f, "another build")?,
+        }
+        if let Some(acquired_at) = self.acquired_at {
+            write!(f, " since {}", acquired_at)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "clock")]
+fn now() -> Option<i64> {
+    Some(chrono::Utc::now().timestamp())
+}
+
+#[cfg(not(feature = "clock"))]
+fn now() -> Option<i64> {
+    None
+}
+
+/// An advisory lock serializing builds into the same output directory,
+/// held until dropped. Builds into different outputs, even siblings, do not
+/// share a lock.
+///
+/// The lock is the `lock_path` file, created atomically (see
+/// `CacheStore::create_new`) and removed on drop, if it is still this
+/// build's. When a `stale_after` is given, a lock file older than that,
+/// left by a build that crashed, is taken over.
+#[derive(Debug)]
+pub(crate) struct BuildLock {
+    store: Arc<dyn CacheStore>,
+    path: PathBuf,
+    contents: Vec<u8>,
+}
+
+impl BuildLock {
+    pub(crate) fn acquire(
+        store: &Arc<dyn CacheStore>,
+        output_dir: &Path,
+        stale_after: Option<Duration>,
+    ) -> Result<Self, CacheBuildError> {
+        let path = lock_path(output_dir);
+        let contents = serde_json::to_vec(&LockHolder::current())?;
+        let lock = || {
+            store.create_new(&path, &contents).map(|()| BuildLock {
+                store: Arc::clone(store),
+                path: path.clone(),
+                contents: contents.clone(),
+            })
+        };
+
+        match lock() {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            result => return Ok(result?),
+        }
+        if let Some(stale_after) = stale_after {
+            // A lock file being written may not parse yet; it is held
+            let held = store.read_file(&path).ok();
+            let holder: Option<LockHolder> = held.as_deref().and_then(|b| serde_json::from_slice(b).ok());
+            if let (Some(held), true) = (held, holder.is_some_and(|holder| holder.is_stale(stale_after))) {
+                if take_over(store.as_ref(), &path, &held)? {
+                    match lock() {
+                        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                        result => return Ok(result?),
+                    }
+                }
+            }
+        }
+        let holder = store.read_file(&path).ok().and_then(|b| serde_json::from_slice(&b).ok());
+        Err(CacheBuildError::Locked { path, holder })
+    }
+}
+
+/// Remove the stale lock at `path`, if it still holds `stale`. The lock is
+/// first renamed to a name only this call uses, so of several builders
+/// taking over the same stale lock, one removes it; a later one that
+/// renames the winner's fresh lock instead sees different contents and
+/// puts it back.
+fn take_over(store: &dyn CacheStore, path: &Path, stale: &[u8]) -> std::io::Result<bool> {
+    static TAKEOVERS: AtomicUsize = AtomicUsize::new(0);
+    let pid = LockHolder::current().pid.unwrap_or(0);
+    let aside = sibling_path(
+        path,
+        &format!("stale.{}.{}", pid, TAKEOVERS.fetch_add(1, Ordering::Relaxed)),
+    );
+    match store.rename(path, &aside) {
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        result => result?,
+    }
+    let moved = store.read_file(&aside)?;
+    if moved == stale {
+        store.remove_file(&aside)?;
+        return Ok(true);
+    }
+    // Another builder's lock; if a third took the path meanwhile, it holds
+    let restored = store.create_new(path, &moved);
+    store.remove_file(&aside)?;
+    match restored {
+        Err(e) if e.kind() != ErrorKind::AlreadyExists => Err(e),
+        _ => Ok(false),
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        // Best effort; a leftover lock is held until removed (or taken
+        // over). A lock taken over from this build is not removed.
+        if self.store.read_file(&self.path).is_ok_and(|held| held == self.contents) {
+            let _ = self.store.remove_file(&self.path);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::cache::cache::{CacheReadError, DocumentLoader};
+use crate::cache::invalidation::BuildPlan;
+use crate::cache::stats::{CacheStats, StatsOptions};
+use crate::cache::vectors::VectorStore;
+use crate::cache::versioning::{CacheIndex, CacheManifest};
+use crate::document::Document;
+use crate::selection::ranking::{ApproxTokenCounter, TokenCounter};
+use crate::types::identifiers::DocumentId;
+
+/// A cache held entirely in memory.
+///
+/// Built by `CacheBuilder::build_in_memory` with the same sorting,
+/// validation, and versioning as an on-disk build, so `cache_version` is
+/// identical for identical inputs. Documents never leave memory, so loading
+/// does not re-verify content hashes.
+#[derive(Debug, Clone)]
+pub struct MemoryCache {
+    manifest: CacheManifest,
+    index: CacheIndex,
+    documents: Vec<Document>,
+    vectors: Option<VectorStore>,
+    #[cfg(feature = "tantivy")]
+    tantivy_index: Option<Vec<u8>>,
+}
+
+impl MemoryCache {
+    pub(crate) fn from_plan(plan: BuildPlan) -> Self {
+        Self {
+            manifest: plan.manifest,
+            index: plan.index,
+            #[cfg(feature = "tantivy")]
+            tantivy_index: plan.files.get("tantivy.tar").cloned(),
+            documents: plan.documents,
+            vectors: plan.vectors,
+        }
+    }
+
+    pub fn manifest(&self) -> &CacheManifest {
+        &self.manifest
+    }
+
+    pub fn index(&self) -> &CacheIndex {
+        &self.index
+    }
+
+    /// Documents sorted by ID.
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    pub fn get(&self, id: &DocumentId) -> Option<&Document> {
+        self.documents
+            .binary_search_by(|d| d.id.cmp(id))
+            .ok()
+            .map(|i| &self.documents[i])
+    }
+
+    pub fn vectors(&self) -> Option<&VectorStore> {
+        self.vectors.as_ref()
+    }
+
+    /// The `tantivy.tar` bytes, if the cache was built with an index.
+    #[cfg(feature = "tantivy")]
+    pub fn tantivy_index(&self) -> Option<&[u8]> {
+        self.tantivy_index.as_deref()
+    }
+
+    /// See `ContextCache::stats`.
+    pub fn stats(&self) -> CacheStats {
+        self.stats_with(&ApproxTokenCounter, &StatsOptions::default())
+    }
+
+    pub fn stats_with(&self, counter: &dyn TokenCounter, options: &StatsOptions) -> CacheStats {
+        CacheStats::collect(&self.manifest.cache_version, &self.documents, counter, options)
+    }
+}
+
+impl DocumentLoader for MemoryCache {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        Ok(self.documents.clone())
+    }
+
+    fn cache_version(&self) -> Option<String> {
+        Some(self.manifest.cache_version.clone())
+    }
+}
@@ -1,7 +1,46 @@
+#[allow(clippy::module_inception)]
 pub mod cache;
 pub mod versioning;
 pub mod invalidation;
+pub mod vectors;
+pub mod diff;
+pub mod prune;
+pub mod multi;
+pub mod store;
+pub mod lock;
+pub mod progress;
+pub mod memory;
+pub mod archive;
+pub mod signing;
+pub mod snapshot;
+pub mod repository;
+pub mod stats;
+pub mod verify;
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(feature = "http")]
+pub mod fetch;
+#[cfg(feature = "object-store")]
+pub mod remote;
 
-pub use invalidation::{CacheBuildError, CacheBuilder};
-pub use cache::ContextCache;
-pub use versioning::{CacheBuildConfig, CacheIndex, CacheManifest, ManifestDocumentEntry};
+pub use diff::{diff, CacheDiff, DiffEntry, ModifiedEntry};
+pub use invalidation::{BuildMode, CacheBuildError, CacheBuilder};
+pub use cache::{CacheReadError, ContextCache, DocumentLoader, UnreadableDocuments, VerificationLevel};
+pub use memory::MemoryCache;
+pub use progress::{BuildObserver, BuildPhase, CancellationToken};
+pub use prune::prune;
+pub use multi::{MultiCache, MultiCacheError};
+pub use signing::{SigningKey, VerifyingKey};
+pub use repository::CacheRepository;
+pub use snapshot::CacheSnapshot;
+pub use stats::{CacheStats, DocumentStats, PrefixStats, StatsOptions};
+#[cfg(feature = "fs")]
+pub use store::FsStore;
+pub use store::{CacheStore, FileStamp, MemoryStore};
+#[cfg(feature = "http")]
+pub use fetch::{fetch_http, FetchError};
+#[cfg(feature = "object-store")]
+pub use remote::{open_published, publish, RemoteStore};
+pub use verify::{VerifiedFile, VerifyOptions, VerifyReport};
+pub use vectors::{Embedder, HashingEmbedder, VectorError, VectorStore};
+pub use versioning::{CacheBuildConfig, CacheIndex, CacheManifest, DocumentEncoding, Durability, ManifestDocumentEntry};
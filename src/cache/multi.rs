@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::cache::cache::{CacheReadError, ContextCache, DocumentLoader, UnreadableDocuments};
+use crate::document::Document;
+use crate::types::identifiers::normalize_namespace;
+
+#[derive(Debug, Error)]
+pub enum MultiCacheError {
+    #[error("Invalid namespace: {0:?}")]
+    InvalidNamespace(String),
+    #[error("Duplicate namespace: {0}")]
+    DuplicateNamespace(String),
+}
+
+/// Several caches presented as one selection source.
+///
+/// Each cache is mounted under a namespace; document IDs are rewritten to
+/// `namespace/original_id` (as `DocumentId::from_path_namespaced` would have
+/// built them), so IDs stay unique across caches. Namespaces are lowercased
+/// (like `DocumentId`s) and may not be empty or contain `/`.
+#[derive(Debug)]
+pub struct MultiCache {
+    caches: BTreeMap<String, ContextCache>,
+}
+
+impl MultiCache {
+    pub fn new(
+        caches: impl IntoIterator<Item = (String, ContextCache)>,
+    ) -> Result<Self, MultiCacheError> {
+        let mut mounted = BTreeMap::new();
+        for (namespace, cache) in caches {
+            let Ok(namespace) = normalize_namespace(&namespace) else {
+                return Err(MultiCacheError::InvalidNamespace(namespace));
+            };
+            if mounted.contains_key(&namespace) {
+                return Err(MultiCacheError::DuplicateNamespace(namespace));
+            }
+            mounted.insert(namespace, cache);
+        }
+        Ok(Self { caches: mounted })
+    }
+
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.caches.keys().map(|s| s.as_str())
+    }
+
+    pub fn get(&self, namespace: &str) -> Option<&ContextCache> {
+        self.caches.get(namespace)
+    }
+
+    /// Composite version: `sha256` over sorted `namespace:cache_version` lines.
+    pub fn cache_version(&self) -> String {
+        let mut hasher = Sha256::new();
+        for (namespace, cache) in &self.caches {
+            hasher.update(format!("{}:{}\n", namespace, cache.manifest.cache_version).as_bytes());
+        }
+        format!("sha256:{}", hex::encode(hasher.finalize()))
+    }
+}
+
+impl DocumentLoader for MultiCache {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        let mut all = Vec::new();
+        for (namespace, cache) in &self.caches {
+            for mut doc in cache.load_documents()? {
+                doc.id = doc.id.with_namespace(namespace);
+                all.push(doc);
+            }
+        }
+        // "a-b/x" sorts before "a/x", so namespace order alone is not ID order
+        all.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(all)
+    }
+
+    fn load_readable_documents(&self) -> Result<(Vec<Document>, UnreadableDocuments), CacheReadError> {
+        let mut all = Vec::new();
+        let mut unreadable = Vec::new();
+        for (namespace, cache) in &self.caches {
+            let (docs, failed) = cache.load_readable_documents();
+            for mut doc in docs {
+                doc.id = doc.id.with_namespace(namespace);
+                all.push(doc);
+            }
+            unreadable.extend(failed.into_iter().map(|(id, e)| (id.with_namespace(namespace), e)));
+        }
+        all.sort_by(|a, b| a.id.cmp(&b.id));
+        unreadable.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok((all, unreadable))
+    }
+
+    fn cache_version(&self) -> Option<String> {
+        Some(MultiCache::cache_version(self))
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::types::identifiers::DocumentId;
+
+/// Stages of a build, in order. Streaming builds write documents as they
+/// arrive and skip `Planning`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildPhase {
+    /// Sorting, normalizing, summarizing, embedding, and encoding documents.
+    Planning,
+    /// Writing document files.
+    Writing,
+    /// Writing the index, manifest, and signature, then renaming the cache
+    /// into place.
+    Committing,
+}
+
+/// Receives progress from a `CacheBuilder` (see
+/// `CacheBuilder::with_observer`), e.g. to drive a progress bar. Calls are
+/// made on the building thread, so they should return quickly.
+pub trait BuildObserver {
+    fn on_phase_change(&self, _phase: BuildPhase) {}
+
+    /// Document `id` was written, the `written`th of `total` (unknown for a
+    /// streaming build).
+    fn on_document_written(&self, _id: &DocumentId, _written: usize, _total: Option<usize>) {}
+}
+
+/// Stops a build from another thread: checked between documents, after
+/// which the build removes its temp dir and fails with
+/// `CacheBuildError::Cancelled`. Clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::cache::cache::{verify_document, ContextCache, VerificationLevel};
+use crate::cache::invalidation::{CacheBuildError, CacheBuilder};
+use crate::document::Document;
+
+/// Write a new cache generation to `output_dir` holding only the documents
+/// of `cache` that `keep` accepts, e.g. `|doc| !archive.matches(&doc.id)`
+/// for a `PathFilter` `archive` including `archive/**`.
+///
+/// Kept documents are verified, then written as the bytes already in
+/// `cache` rather than encoded again, and keep their vectors. The result
+/// is exactly what a build of the kept documents with `cache`'s config
+/// would give: same files, same `cache_version`, and `cache`'s
+/// `created_at`, so pruning is deterministic. It is written through
+/// `cache`'s store and is not signed.
+pub fn prune(
+    cache: &ContextCache,
+    mut keep: impl FnMut(&Document) -> bool,
+    output_dir: &Path,
+) -> Result<ContextCache, CacheBuildError> {
+    let manifest = &cache.manifest;
+    let mut kept = Vec::new();
+    for entry in &manifest.documents {
+        let bytes = cache.read_document(entry)?;
+        let doc = verify_document(manifest.build_config.document_encoding, entry, &bytes, VerificationLevel::Full)?;
+        if keep(&doc) {
+            kept.push((doc, bytes));
+        }
+    }
+
+    let vectors = cache.load_vectors()?.map(|mut store| {
+        store
+            .vectors
+            .retain(|id, _| kept.binary_search_by(|(doc, _)| doc.id.cmp(id)).is_ok());
+        store
+    });
+
+    let config = manifest.build_config.clone().with_fixed_timestamp(manifest.created_at);
+    CacheBuilder::new(config)
+        .with_store(Arc::clone(cache.store()))
+        .build_encoded(kept, vectors, output_dir)
+}
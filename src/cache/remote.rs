@@ -0,0 +1,339 @@
+//! Caches in object storage (S3, GCS, Azure Blob, ...) through the
+//! `object_store` crate.
+//!
+//! `RemoteStore` is a `CacheStore` over any `object_store::ObjectStore`.
+//! Object stores have no directories, so a directory exists while objects
+//! are stored under it, and renaming one copies every object. Caches are
+//! therefore best published once, under a prefix named after their
+//! `cache_version` (`publish`), and opened from there (`open_published`).
+//!
+//! Files under such a prefix never change, so `RemoteStore` keeps a local
+//! copy of each one it reads and serves later reads from that copy: a
+//! selection downloads a cache once per machine, not once per query.
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectMeta, ObjectStore, PutMode, PutPayload};
+
+use crate::cache::archive::collect_files;
+use crate::cache::cache::ContextCache;
+use crate::cache::store::{CacheStore, FileStamp};
+
+/// Written last when publishing or renaming a cache, so a reader that finds
+/// it finds every other file.
+const MANIFEST: &str = "manifest.json";
+
+/// Distinguishes concurrent downloads of the same file into the local copy.
+static DOWNLOADS: AtomicU64 = AtomicU64::new(0);
+
+/// A `CacheStore` over an object store, with a local read-through copy of
+/// immutable files.
+///
+/// Paths map to object keys by their normal components, so `/caches/a` and
+/// `caches/a` name the same object; `..` is rejected. Files under a
+/// component named like a `cache_version` (`sha256-<hex>` or
+/// `blake3-<hex>`, as `publish` and `CacheRepository` name them) are read
+/// through `local_dir`; other files are always read from the object store.
+#[derive(Debug)]
+pub struct RemoteStore {
+    remote: Arc<dyn ObjectStore>,
+    local: PathBuf,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl RemoteStore {
+    /// Read and write `remote`, keeping local copies under `local_dir`.
+    ///
+    /// Requests run on a runtime owned by the store, so methods must not be
+    /// called from within an async runtime; use `spawn_blocking` there.
+    pub fn new(remote: Arc<dyn ObjectStore>, local_dir: impl AsRef<Path>) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        Ok(Self {
+            remote,
+            local: local_dir.as_ref().to_path_buf(),
+            runtime,
+        })
+    }
+
+    /// The object store behind this store.
+    pub fn remote(&self) -> &Arc<dyn ObjectStore> {
+        &self.remote
+    }
+
+    /// Where local copies are kept.
+    pub fn local_dir(&self) -> &Path {
+        &self.local
+    }
+
+    /// The local copy of `key`, if its contents never change.
+    fn local_copy(&self, key: &ObjectPath) -> Option<PathBuf> {
+        key.parts()
+            .any(|part| is_version_name(part.as_ref()))
+            .then(|| self.local.join(key.as_ref()))
+    }
+
+    /// Drop local copies of `key` and anything under it. Best effort: a
+    /// copy of an immutable file is still correct.
+    fn evict(&self, key: &ObjectPath) {
+        let path = self.local.join(key.as_ref());
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    /// Keys of every object under `prefix`, at any depth.
+    fn objects(&self, prefix: &ObjectPath) -> Result<Vec<ObjectPath>> {
+        let listing = self.block_on(self.remote.list_with_delimiter(Some(prefix)))?;
+        let mut keys: Vec<ObjectPath> = listing.objects.into_iter().map(|meta| meta.location).collect();
+        for dir in &listing.common_prefixes {
+            keys.extend(self.objects(dir)?);
+        }
+        Ok(keys)
+    }
+
+    fn head(&self, key: &ObjectPath) -> Result<ObjectMeta> {
+        self.block_on(self.remote.head(key))
+    }
+
+    fn block_on<T>(&self, request: impl std::future::Future<Output = object_store::Result<T>>) -> Result<T> {
+        self.runtime.block_on(request).map_err(io_error)
+    }
+}
+
+impl CacheStore for RemoteStore {
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let key = object_key(path)?;
+        let local = self.local_copy(&key);
+        if let Some(bytes) = local.as_deref().and_then(|local| std::fs::read(local).ok()) {
+            return Ok(bytes);
+        }
+        let bytes = self.block_on(async { self.remote.get(&key).await?.bytes().await })?.to_vec();
+        if let Some(local) = local {
+            // Best effort: a failed copy means the next read downloads again
+            let _ = write_local(&local, &bytes);
+        }
+        Ok(bytes)
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let key = object_key(path)?;
+        self.block_on(self.remote.put(&key, PutPayload::from(contents.to_vec())))?;
+        self.evict(&key);
+        Ok(())
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let key = object_key(path)?;
+        let payload = PutPayload::from(contents.to_vec());
+        self.block_on(self.remote.put_opts(&key, payload, PutMode::Create.into()))?;
+        Ok(())
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<String>> {
+        let key = object_key(dir)?;
+        let prefix = (!key.as_ref().is_empty()).then_some(&key);
+        let listing = self.block_on(self.remote.list_with_delimiter(prefix))?;
+        let mut names: Vec<String> = listing
+            .objects
+            .iter()
+            .map(|meta| &meta.location)
+            .chain(&listing.common_prefixes)
+            .filter_map(|key| key.filename().map(str::to_string))
+            .collect();
+        // Like a directory that was never created
+        if names.is_empty() {
+            return Err(not_found(dir));
+        }
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from_key, to_key) = (object_key(from)?, object_key(to)?);
+        if self.head(&from_key).is_ok() {
+            self.block_on(self.remote.rename(&from_key, &to_key))?;
+            self.evict(&from_key);
+            self.evict(&to_key);
+            return Ok(());
+        }
+
+        let keys = self.objects(&from_key)?;
+        if keys.is_empty() {
+            return Err(not_found(from));
+        }
+        if !self.objects(&to_key)?.is_empty() {
+            return Err(Error::new(ErrorKind::AlreadyExists, format!("{} already exists", to.display())));
+        }
+        // Object stores cannot move a prefix atomically: copy the manifest
+        // last, so the target is a cache only once it is complete
+        let (manifests, files): (Vec<_>, Vec<_>) = keys.iter().partition(|key| key.filename() == Some(MANIFEST));
+        for key in files.iter().chain(&manifests) {
+            let rel = key.prefix_match(&from_key).expect("listed under from");
+            let target = ObjectPath::from_iter(to_key.parts().chain(rel));
+            self.block_on(self.remote.copy(key, &target))?;
+        }
+        for key in manifests.iter().chain(&files) {
+            self.block_on(self.remote.delete(key))?;
+        }
+        self.evict(&from_key);
+        self.evict(&to_key);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let Ok(key) = object_key(path) else {
+            return false;
+        };
+        if self.head(&key).is_ok() {
+            return true;
+        }
+        let prefix = (!key.as_ref().is_empty()).then_some(&key);
+        self.block_on(self.remote.list_with_delimiter(prefix))
+            .is_ok_and(|listing| !listing.objects.is_empty() || !listing.common_prefixes.is_empty())
+    }
+
+    fn create_dir_all(&self, _path: &Path) -> Result<()> {
+        // Directories exist while objects are stored under them
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        let key = object_key(path)?;
+        let keys = self.objects(&key)?;
+        if keys.is_empty() {
+            return Err(not_found(path));
+        }
+        for key in &keys {
+            self.block_on(self.remote.delete(key))?;
+        }
+        self.evict(&key);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let key = object_key(path)?;
+        // Deleting a missing object succeeds on some stores
+        self.head(&key)?;
+        self.block_on(self.remote.delete(&key))?;
+        self.evict(&key);
+        Ok(())
+    }
+
+    fn link(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from_key, to_key) = (object_key(from)?, object_key(to)?);
+        self.block_on(self.remote.copy(&from_key, &to_key))?;
+        self.evict(&to_key);
+        Ok(())
+    }
+
+    fn stamp(&self, path: &Path) -> Result<Option<FileStamp>> {
+        let meta = self.head(&object_key(path)?)?;
+        let Some(modified_ns) = meta.last_modified.timestamp_nanos_opt().and_then(|ns| u64::try_from(ns).ok()) else {
+            return Ok(None);
+        };
+        Ok(Some(FileStamp {
+            size: meta.size as u64,
+            modified_ns,
+        }))
+    }
+}
+
+/// Copy `cache` into `store` under `prefix/<cache_version>` (with `:` as
+/// `-`, e.g. `sha256-<hex>`), unless it is already there, and open the
+/// copy. The manifest is written last, so a cache that `open_published`
+/// finds is complete.
+pub fn publish(cache: &ContextCache, store: Arc<dyn CacheStore>, prefix: impl AsRef<Path>) -> Result<ContextCache> {
+    let prefix = prefix.as_ref();
+    let root = prefix.join(cache.manifest.cache_version.replace(':', "-"));
+    if !store.exists(&root.join(MANIFEST)) {
+        let mut files = Vec::new();
+        collect_files(cache.store().as_ref(), &cache.root, Path::new(""), &mut files)?;
+        let (manifests, files): (Vec<_>, Vec<_>) = files.iter().partition(|rel| rel.as_os_str() == MANIFEST);
+        for rel in files.iter().chain(&manifests) {
+            store.write_file(&root.join(rel), &cache.store().read_file(&cache.root.join(rel))?)?;
+        }
+    }
+    open_published(store, prefix, &cache.manifest.cache_version)
+}
+
+/// Open the cache `publish` wrote for `cache_version` under `prefix`.
+/// Fails with `ErrorKind::InvalidData` if the manifest found there is for
+/// another version.
+pub fn open_published(store: Arc<dyn CacheStore>, prefix: impl AsRef<Path>, cache_version: &str) -> Result<ContextCache> {
+    let root = prefix.as_ref().join(cache_version.replace(':', "-"));
+    let cache = ContextCache::open_in(store, &root)?;
+    if cache.manifest.cache_version != cache_version {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} holds cache version {}, expected {}",
+                root.display(),
+                cache.manifest.cache_version,
+                cache_version
+            ),
+        ));
+    }
+    Ok(cache)
+}
+
+/// The object key for `path`: its normal components joined by `/`.
+fn object_key(path: &Path) -> Result<ObjectPath> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(
+                part.to_str()
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "non UTF-8 path"))?,
+            ),
+            Component::ParentDir => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} contains ..", path.display()),
+                ));
+            }
+            Component::RootDir | Component::Prefix(_) | Component::CurDir => {}
+        }
+    }
+    Ok(ObjectPath::from_iter(parts))
+}
+
+/// True for `sha256-<hex>` and `blake3-<hex>`.
+fn is_version_name(name: &str) -> bool {
+    let hex = name.strip_prefix("sha256-").or_else(|| name.strip_prefix("blake3-"));
+    hex.is_some_and(|hex| !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Write `local` through a temp file, so readers never see part of it.
+fn write_local(local: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = local.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let n = DOWNLOADS.fetch_add(1, Ordering::Relaxed);
+    let mut temp = local.as_os_str().to_owned();
+    temp.push(format!(".{}-{}.partial", std::process::id(), n));
+    std::fs::write(&temp, bytes)?;
+    let renamed = std::fs::rename(&temp, local);
+    if renamed.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    renamed
+}
+
+fn io_error(error: object_store::Error) -> Error {
+    let kind = match &error {
+        object_store::Error::NotFound { .. } => ErrorKind::NotFound,
+        object_store::Error::AlreadyExists { .. } => ErrorKind::AlreadyExists,
+        object_store::Error::InvalidPath { .. } => ErrorKind::InvalidInput,
+        object_store::Error::NotSupported { .. } | object_store::Error::NotImplemented => ErrorKind::Unsupported,
+        _ => ErrorKind::Other,
+    };
+    Error::new(kind, error)
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::new(ErrorKind::NotFound, format!("{} not found", path.display()))
+}
@@ -0,0 +1,284 @@
+use std::collections::BTreeSet;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::cache::cache::ContextCache;
+use crate::cache::invalidation::{unique_hex, CacheBuildError, CacheBuilder};
+#[cfg(feature = "fs")]
+use crate::cache::store::FsStore;
+use crate::cache::store::CacheStore;
+use crate::document::Document;
+use crate::types::identifiers::HashAlgorithm;
+
+const GENERATIONS: &str = "generations";
+const STAGING: &str = ".staging";
+const CURRENT: &str = "CURRENT";
+const HISTORY: &str = "HISTORY";
+const OBJECTS: &str = "objects";
+const REFS: &str = "refs";
+
+/// A directory of cache generations with an atomically swapped pointer.
+///
+/// ```text
+/// <root>/generations/<cache_version>/   one complete cache per version
+/// <root>/CURRENT                        cache_version of the published one
+/// <root>/HISTORY                        published versions, oldest first
+/// <root>/objects/<ab>/<abcdef...>        shared document files, by SHA-256
+/// <root>/refs/<cache_version>            objects a generation links to
+/// ```
+///
+/// Generation directories are named by cache version with `:` replaced by
+/// `-` (`sha256-<hex>`), so rebuilding identical inputs reuses the existing
+/// generation. `CURRENT` is replaced by rename, so readers see either the
+/// old or the new version, never a partial write.
+///
+/// With `with_shared_objects`, each document file of a new generation is
+/// stored once in `objects/` and linked into the generation (hard-linked
+/// by `FsStore`), so generations sharing documents share their storage.
+/// Generations stay complete caches; `collect_garbage` removes the objects
+/// no generation refers to any more.
+#[derive(Debug)]
+pub struct CacheRepository {
+    root: PathBuf,
+    store: Arc<dyn CacheStore>,
+    shared_objects: bool,
+}
+
+impl CacheRepository {
+    /// Open (or create) a repository on the local filesystem.
+    #[cfg(feature = "fs")]
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        Self::open_in(Arc::new(FsStore), root)
+    }
+
+    /// Open (or create) a repository under `root` in `store`.
+    pub fn open_in(store: Arc<dyn CacheStore>, root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        store.create_dir_all(&root.join(GENERATIONS))?;
+        Ok(Self {
+            root,
+            store,
+            shared_objects: false,
+        })
+    }
+
+    /// Store the document files of generations built from now on in the
+    /// shared `objects/` directory.
+    pub fn with_shared_objects(mut self) -> Self {
+        self.shared_objects = true;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Build `documents` into `generations/<cache_version>/` without
+    /// publishing it. An existing generation with the same version is reused.
+    ///
+    /// Each build stages its cache in a directory of its own
+    /// (`generations/.staging.<random hex>`), so concurrent builds, from
+    /// this process or others, never touch each other's output; when two
+    /// build the same version, one renames its cache into place and the
+    /// other discards its own. A staging directory left by a crash is
+    /// ignored by `generations` and can be removed.
+    pub fn build(
+        &self,
+        builder: CacheBuilder,
+        documents: Vec<Document>,
+    ) -> std::result::Result<ContextCache, CacheBuildError> {
+        let staging = self.staging_dir();
+        let built = builder
+            .with_store(Arc::clone(&self.store))
+            .build(documents, &staging)?;
+        let target = self.generation_dir(&built.manifest.cache_version);
+        let published = self.publish_generation(&built, &staging, &target);
+        if self.store.exists(&staging) {
+            // Best effort: a leftover staging dir is ignored
+            let _ = self.store.remove_dir_all(&staging);
+        }
+        published?;
+        Ok(ContextCache::open_in(Arc::clone(&self.store), target)?)
+    }
+
+    /// Rename the staged cache to `target`, unless a generation with its
+    /// version already exists or is renamed there first.
+    fn publish_generation(&self, built: &ContextCache, staging: &Path, target: &Path) -> Result<()> {
+        if self.store.exists(target) {
+            return Ok(());
+        }
+        if self.shared_objects {
+            self.share_objects(built)?;
+        }
+        match self.store.rename(staging, target) {
+            Err(_) if self.store.exists(target) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// A staging directory no other build uses.
+    fn staging_dir(&self) -> PathBuf {
+        self.root.join(GENERATIONS).join(format!("{}.{}", STAGING, unique_hex()))
+    }
+
+    /// Atomically point `CURRENT` at an existing generation.
+    pub fn publish(&self, cache_version: &str) -> Result<()> {
+        if !self.store.exists(&self.generation_dir(cache_version)) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no generation for cache version {}", cache_version),
+            ));
+        }
+
+        let mut history = self.history()?;
+        history.retain(|v| v != cache_version);
+        history.push(cache_version.to_string());
+        self.replace_file(HISTORY, &history.join("\n"))?;
+
+        self.replace_file(CURRENT, cache_version)
+    }
+
+    /// Version named by `CURRENT`, if anything has been published.
+    pub fn current_version(&self) -> Result<Option<String>> {
+        let path = self.root.join(CURRENT);
+        if !self.store.exists(&path) {
+            return Ok(None);
+        }
+        let bytes = self.store.read_file(&path)?;
+        let version = String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(Some(version.trim().to_string()))
+    }
+
+    /// Open the published generation.
+    pub fn open_current(&self) -> Result<ContextCache> {
+        let version = self
+            .current_version()?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no generation has been published"))?;
+        ContextCache::open_in(Arc::clone(&self.store), self.generation_dir(&version))
+    }
+
+    /// Cache versions of all generations, sorted.
+    pub fn generations(&self) -> Result<Vec<String>> {
+        Ok(self
+            .store
+            .list(&self.root.join(GENERATIONS))?
+            .into_iter()
+            // Skip the staging dirs and the builder's temp dirs
+            .filter(|name| !name.starts_with('.'))
+            .map(|name| name.replacen('-', ":", 1))
+            .collect())
+    }
+
+    /// Remove every generation except the current one and the `retain`
+    /// most recently published before it. Returns the removed versions,
+    /// sorted. Generations that were never published are removed too.
+    pub fn prune(&self, retain: usize) -> Result<Vec<String>> {
+        let mut history = self.history()?;
+        let keep: BTreeSet<String> = history.iter().rev().take(retain + 1).cloned().collect();
+
+        let mut removed = Vec::new();
+        for version in self.generations()? {
+            if !keep.contains(&version) {
+                self.store.remove_dir_all(&self.generation_dir(&version))?;
+                removed.push(version);
+            }
+        }
+
+        history.retain(|v| keep.contains(v));
+        self.replace_file(HISTORY, &history.join("\n"))?;
+        Ok(removed)
+    }
+
+    /// Remove the objects no generation refers to, and the refs of
+    /// generations that no longer exist. Returns the removed objects'
+    /// digests, sorted.
+    pub fn collect_garbage(&self) -> Result<Vec<String>> {
+        let generations: BTreeSet<String> =
+            self.generations()?.iter().map(|version| version.replace(':', "-")).collect();
+        let mut referenced = BTreeSet::new();
+        let refs_dir = self.root.join(REFS);
+        if self.store.exists(&refs_dir) {
+            for name in self.store.list(&refs_dir)? {
+                let path = refs_dir.join(&name);
+                if !generations.contains(&name) {
+                    self.store.remove_file(&path)?;
+                    continue;
+                }
+                let bytes = self.store.read_file(&path)?;
+                let text = String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                referenced.extend(text.lines().filter(|l| !l.is_empty()).map(str::to_string));
+            }
+        }
+
+        let mut removed = Vec::new();
+        let objects_dir = self.root.join(OBJECTS);
+        if self.store.exists(&objects_dir) {
+            for shard in self.store.list(&objects_dir)? {
+                for digest in self.store.list(&objects_dir.join(&shard))? {
+                    if !referenced.contains(&digest) {
+                        self.store.remove_file(&objects_dir.join(&shard).join(&digest))?;
+                        removed.push(digest);
+                    }
+                }
+            }
+        }
+        removed.sort();
+        Ok(removed)
+    }
+
+    /// Move each document file of `built` (still in staging) into
+    /// `objects/`, unless an identical object is already there, and link it
+    /// back; then record the objects in `refs/`.
+    fn share_objects(&self, built: &ContextCache) -> Result<()> {
+        let duplicates = built.root.join(".duplicates");
+        let mut digests = BTreeSet::new();
+        for entry in &built.manifest.documents {
+            let file = built.root.join(&entry.file);
+            let bytes = self.store.read_file(&file)?;
+            let checksum = HashAlgorithm::Sha256.checksum(&bytes);
+            let digest = checksum.split_once(':').map_or(checksum.as_str(), |(_, hex)| hex);
+            let object = self.root.join(OBJECTS).join(&digest[..2]).join(digest);
+
+            if self.store.exists(&object) {
+                self.store.create_dir_all(&duplicates)?;
+                self.store.rename(&file, &duplicates.join(digest))?;
+            } else {
+                self.store.create_dir_all(&self.root.join(OBJECTS).join(&digest[..2]))?;
+                self.store.rename(&file, &object)?;
+            }
+            self.store.link(&object, &file)?;
+            digests.insert(digest.to_string());
+        }
+        if self.store.exists(&duplicates) {
+            self.store.remove_dir_all(&duplicates)?;
+        }
+
+        let refs = digests.into_iter().collect::<Vec<_>>().join("\n");
+        let name = built.manifest.cache_version.replace(':', "-");
+        self.store.write_file(&self.root.join(REFS).join(name), refs.as_bytes())
+    }
+
+    fn generation_dir(&self, cache_version: &str) -> PathBuf {
+        self.root.join(GENERATIONS).join(cache_version.replace(':', "-"))
+    }
+
+    /// Published versions, oldest first; the last one is `CURRENT`.
+    fn history(&self) -> Result<Vec<String>> {
+        let path = self.root.join(HISTORY);
+        if !self.store.exists(&path) {
+            return Ok(Vec::new());
+        }
+        let bytes = self.store.read_file(&path)?;
+        let text = String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(text.lines().filter(|l| !l.is_empty()).map(str::to_string).collect())
+    }
+
+    /// Write `name` via a temp file and rename, so readers never see a
+    /// partial file.
+    fn replace_file(&self, name: &str, contents: &str) -> Result<()> {
+        let temp = self.root.join(format!("{}.tmp", name));
+        self.store.write_file(&temp, contents.as_bytes())?;
+        self.store.rename(&temp, &self.root.join(name))
+    }
+}
@@ -0,0 +1,58 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+use ed25519_dalek::{Signature, Signer, Verifier};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+use crate::cache::cache::ContextCache;
+#[cfg(feature = "fs")]
+use crate::cache::store::FsStore;
+use crate::cache::store::CacheStore;
+
+/// Detached signature file, next to `manifest.json`.
+pub const SIGNATURE_FILE: &str = "manifest.sig";
+
+/// Sign a manifest `tree_hash`. The signature file holds the hex-encoded
+/// 64-byte ed25519 signature over the tree hash string.
+pub(crate) fn sign_tree_hash(key: &SigningKey, tree_hash: &str) -> String {
+    hex::encode(key.sign(tree_hash.as_bytes()).to_bytes())
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+impl ContextCache {
+    /// Open a cache on the local filesystem, refusing it unless
+    /// `manifest.sig` is a valid signature of its `tree_hash` by `key` and
+    /// every file matches that tree hash (see `verify`).
+    #[cfg(feature = "fs")]
+    pub fn open_verified(root: impl AsRef<Path>, key: &VerifyingKey) -> Result<Self> {
+        Self::open_verified_in(Arc::new(FsStore), root, key)
+    }
+
+    /// Like `open_verified`, reading from `store`.
+    pub fn open_verified_in(store: Arc<dyn CacheStore>, root: impl AsRef<Path>, key: &VerifyingKey) -> Result<Self> {
+        let cache = Self::open_in(store, root)?;
+        let tree_hash = cache
+            .manifest
+            .tree_hash
+            .as_deref()
+            .ok_or_else(|| invalid("manifest has no tree_hash to verify a signature against"))?;
+
+        let sig_hex = cache.store().read_file(&cache.root.join(SIGNATURE_FILE))?;
+        let sig_bytes: [u8; 64] = std::str::from_utf8(&sig_hex)
+            .ok()
+            .and_then(|s| hex::decode(s.trim()).ok())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid(format!("malformed {}", SIGNATURE_FILE)))?;
+
+        key.verify(tree_hash.as_bytes(), &Signature::from_bytes(&sig_bytes))
+            .map_err(|_| invalid("cache signature verification failed"))?;
+
+        // The signature only covers the tree hash; the files must match it
+        cache.verify()?;
+        Ok(cache)
+    }
+}
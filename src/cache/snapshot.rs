@@ -0,0 +1,53 @@
+use crate::cache::cache::{CacheReadError, ContextCache, DocumentLoader};
+use crate::cache::versioning::CacheManifest;
+use crate::document::Document;
+
+/// Verified documents of a cache, held in memory.
+///
+/// Loaded once by `ContextCache::load_snapshot`; selecting against it with
+/// `ContextSelector::select_snapshot` performs no I/O. A cache is immutable,
+/// so a snapshot never goes stale.
+#[derive(Debug, Clone)]
+pub struct CacheSnapshot {
+    manifest: CacheManifest,
+    documents: Vec<Document>,
+}
+
+impl CacheSnapshot {
+    pub fn manifest(&self) -> &CacheManifest {
+        &self.manifest
+    }
+
+    /// Documents in manifest order (sorted by ID).
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+}
+
+impl DocumentLoader for CacheSnapshot {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        Ok(self.documents.clone())
+    }
+
+    fn cache_version(&self) -> Option<String> {
+        Some(self.manifest.cache_version.clone())
+    }
+}
+
+impl ContextCache {
+    /// Load and verify every document once, for repeated selection.
+    pub fn load_snapshot(&self) -> Result<CacheSnapshot, std::io::Error> {
+        Ok(CacheSnapshot {
+            manifest: self.manifest.clone(),
+            documents: self.load_documents()?,
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use crate::document::Document;
+use crate::selection::ranking::TokenCounter;
+use crate::types::identifiers::DocumentId;
+
+/// What `CacheStats` aggregates beyond the totals.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StatsOptions {
+    /// How many of the largest documents to list.
+    pub largest: usize,
+    /// Directory levels a prefix keeps: with 1, `docs/api/index.md` counts
+    /// under `docs/`; with 2, under `docs/api/`.
+    pub prefix_depth: usize,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            largest: 10,
+            prefix_depth: 1,
+        }
+    }
+}
+
+/// Size of one document.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DocumentStats {
+    pub id: DocumentId,
+    /// Content length in bytes.
+    pub bytes: usize,
+    pub tokens: u64,
+}
+
+/// Totals for the documents under one prefix.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PrefixStats {
+    pub documents: usize,
+    pub bytes: usize,
+    /// Saturates at `u64::MAX`.
+    pub tokens: u64,
+}
+
+/// Size and shape of a cache's documents, for capacity planning and
+/// pruning. Every map is sorted by key.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CacheStats {
+    pub cache_version: String,
+    pub documents: usize,
+    /// Sum of content lengths in bytes.
+    pub total_bytes: usize,
+    /// Saturates at `u64::MAX`.
+    pub total_tokens: u64,
+    /// Fingerprint of the counter the tokens were counted with.
+    pub token_counter: String,
+    /// The `StatsOptions::largest` largest documents, by bytes descending
+    /// then ID.
+    pub largest: Vec<DocumentStats>,
+    /// How many documents carry each metadata key.
+    pub metadata_keys: BTreeMap<String, usize>,
+    /// Totals per ID prefix (see `StatsOptions::prefix_depth`). Documents
+    /// at the root count under `""`.
+    pub prefixes: BTreeMap<String, PrefixStats>,
+}
+
+impl CacheStats {
+    pub(crate) fn collect(
+        cache_version: &str,
+        documents: &[Document],
+        counter: &dyn TokenCounter,
+        options: &StatsOptions,
+    ) -> Self {
+        let mut sizes = Vec::with_capacity(documents.len());
+        let mut metadata_keys: BTreeMap<String, usize> = BTreeMap::new();
+        let mut prefixes: BTreeMap<String, PrefixStats> = BTreeMap::new();
+
+        for doc in documents {
+            let size = DocumentStats {
+                id: doc.id.clone(),
+                bytes: doc.content.len(),
+                tokens: counter.count_tokens(&doc.content) as u64,
+            };
+            for (key, _) in doc.metadata.iter() {
+                *metadata_keys.entry(key.clone()).or_default() += 1;
+            }
+            let prefix = prefixes.entry(prefix_of(doc.id.as_str(), options.prefix_depth)).or_default();
+            prefix.documents += 1;
+            prefix.bytes += size.bytes;
+            prefix.tokens = prefix.tokens.saturating_add(size.tokens);
+            sizes.push(size);
+        }
+
+        let total_bytes = sizes.iter().map(|size| size.bytes).sum();
+        let total_tokens = sizes.iter().fold(0u64, |total, size| total.saturating_add(size.tokens));
+        sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.id.cmp(&b.id)));
+        sizes.truncate(options.largest);
+
+        CacheStats {
+            cache_version: cache_version.to_string(),
+            documents: documents.len(),
+            total_bytes,
+            total_tokens,
+            token_counter: counter.fingerprint(),
+            largest: sizes,
+            metadata_keys,
+            prefixes,
+        }
+    }
+}
+
+/// The first `depth` directories of `id`, with a trailing `/`.
+fn prefix_of(id: &str, depth: usize) -> String {
+    let dirs = match id.rfind('/') {
+        Some(end) => &id[..end],
+        None => return String::new(),
+    };
+    let kept: Vec<&str> = dirs.split('/').take(depth).collect();
+    if kept.is_empty() {
+        return String::new();
+    }
+    format!("{}/", kept.join("/"))
+}
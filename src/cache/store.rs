@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Storage backend for cache files.
+///
+/// Paths are interpreted by the store: `FsStore` uses them as real
+/// filesystem paths, `MemoryStore` as keys. `CacheBuilder` and
+/// `ContextCache` perform all I/O through this trait, so a cache can be
+/// built and read without touching the filesystem.
+pub trait CacheStore: std::fmt::Debug + Send + Sync {
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>>;
+
+    /// Write a whole file, creating parent directories. Must be durable
+    /// once it returns (the filesystem store fsyncs).
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Like `write_file`, but without waiting for the contents to be
+    /// durable (`FsStore` skips the fsync). The default calls `write_file`.
+    fn write_file_unsynced(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.write_file(path, contents)
+    }
+
+    /// Like `write_file`, but failing with `ErrorKind::AlreadyExists` if
+    /// `path` exists; the check and the creation are one atomic step.
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Names of the entries directly inside `dir`, sorted.
+    fn list(&self, dir: &Path) -> Result<Vec<String>>;
+
+    /// Atomically move a file or directory. A file may replace an existing
+    /// file at `to`; a directory may not replace anything.
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    fn create_dir_all(&self, path: &Path) -> Result<()>;
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()>;
+
+    fn remove_file(&self, path: &Path) -> Result<()>;
+
+    /// Create `to` (which must not exist) with the contents of the file
+    /// `from`, sharing storage where the store can: `FsStore` hard-links.
+    /// The default copies.
+    fn link(&self, from: &Path, to: &Path) -> Result<()> {
+        self.write_file(to, &self.read_file(from)?)
+    }
+
+    /// Size and modification time of a file, if the store tracks them;
+    /// `ContextCache::verify_with` skips files whose stamp is unchanged.
+    /// The default reports none.
+    fn stamp(&self, _path: &Path) -> Result<Option<FileStamp>> {
+        Ok(None)
+    }
+
+    /// True if paths are local filesystem paths. Async loading reads such
+    /// stores with `tokio::fs`; other stores are read directly.
+    fn is_filesystem(&self) -> bool {
+        false
+    }
+}
+
+/// A file's size and modification time (nanoseconds since the Unix epoch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified_ns: u64,
+}
+
+/// Local filesystem store (the default with the `fs` feature).
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsStore;
+
+#[cfg(feature = "fs")]
+impl CacheStore for FsStore {
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let f = fs::File::create(path)?;
+        std::io::Write::write_all(&mut &f, contents)?;
+        f.sync_all()
+    }
+
+    fn write_file_unsynced(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let f = fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+        std::io::Write::write_all(&mut &f, contents)?;
+        f.sync_all()
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let name = name
+                .into_string()
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "non UTF-8 file name"))?;
+            names.push(name);
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn link(&self, from: &Path, to: &Path) -> Result<()> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::hard_link(from, to)
+    }
+
+    fn stamp(&self, path: &Path) -> Result<Option<FileStamp>> {
+        let metadata = fs::metadata(path)?;
+        // Filesystems without modification times get no stamp
+        let Some(modified) = metadata.modified().ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(FileStamp {
+            size: metadata.len(),
+            modified_ns: u64::try_from(modified.as_nanos()).unwrap_or(u64::MAX),
+        }))
+    }
+
+    fn is_filesystem(&self) -> bool {
+        true
+    }
+}
+
+/// In-memory store for tests, WASM, and ephemeral agents.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    inner: Mutex<MemoryStoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStoreInner {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryStoreInner> {
+        // A panic while holding the lock cannot leave the maps half-updated
+        // in a way that matters for reads, so recover from poisoning.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl MemoryStoreInner {
+    fn add_parents(&mut self, path: &Path) {
+        let mut current = path.parent();
+        while let Some(p) = current {
+            if p.as_os_str().is_empty() {
+                break;
+            }
+            self.dirs.insert(p.to_path_buf());
+            current = p.parent();
+        }
+    }
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::new(ErrorKind::NotFound, format!("{} not found", path.display()))
+}
+
+impl CacheStore for MemoryStore {
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        self.lock().files.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut inner = self.lock();
+        inner.add_parents(path);
+        inner.files.insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut inner = self.lock();
+        if inner.files.contains_key(path) || inner.dirs.contains(path) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+        inner.add_parents(path);
+        inner.files.insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<String>> {
+        let inner = self.lock();
+        if !inner.dirs.contains(dir) {
+            return Err(not_found(dir));
+        }
+        let children = inner
+            .files
+            .keys()
+            .chain(inner.dirs.iter())
+            .filter(|p| p.parent() == Some(dir))
+            .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(|n| n.to_string()))
+            .collect::<BTreeSet<_>>();
+        Ok(children.into_iter().collect())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut inner = self.lock();
+        let replaces_file = inner.files.contains_key(from) && inner.files.contains_key(to);
+        if !replaces_file && (inner.files.contains_key(to) || inner.dirs.contains(to)) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", to.display()),
+            ));
+        }
+
+        if let Some(contents) = inner.files.remove(from) {
+            inner.add_parents(to);
+            inner.files.insert(to.to_path_buf(), contents);
+            return Ok(());
+        }
+        if !inner.dirs.contains(from) {
+            return Err(not_found(from));
+        }
+
+        let moved_files: Vec<PathBuf> =
+            inner.files.keys().filter(|p| p.starts_with(from)).cloned().collect();
+        for old in moved_files {
+            let contents = inner.files.remove(&old).expect("key listed above");
+            let new = to.join(old.strip_prefix(from).expect("prefix checked above"));
+            inner.files.insert(new, contents);
+        }
+        let moved_dirs: Vec<PathBuf> =
+            inner.dirs.iter().filter(|p| p.starts_with(from)).cloned().collect();
+        for old in moved_dirs {
+            inner.dirs.remove(&old);
+            let new = to.join(old.strip_prefix(from).expect("prefix checked above"));
+            inner.dirs.insert(new);
+        }
+        inner.add_parents(to);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let inner = self.lock();
+        inner.files.contains_key(path) || inner.dirs.contains(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let mut inner = self.lock();
+        inner.add_parents(path);
+        inner.dirs.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        let mut inner = self.lock();
+        if !inner.dirs.contains(path) {
+            return Err(not_found(path));
+        }
+        inner.files.retain(|p, _| !p.starts_with(path));
+        inner.dirs.retain(|p| !p.starts_with(path));
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.lock().files.remove(path).map(|_| ()).ok_or_else(|| not_found(path))
+    }
+}
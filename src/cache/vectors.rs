@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::types::identifiers::DocumentId;
+
+/// Fixed-point scale applied to embedding components before they are stored.
+/// Vectors are persisted as integers so that similarity arithmetic is exact
+/// and independent of float formatting or platform.
+pub const VECTOR_SCALE: f64 = 65536.0;
+
+/// Produces embedding vectors for documents (at build time) and queries
+/// (at selection time).
+///
+/// Implementations MUST be deterministic: the same text always yields the
+/// same vector. The `id` is recorded in `vectors.json` and folded into the
+/// cache version, so changing embedders produces a new cache.
+pub trait Embedder {
+    fn id(&self) -> String;
+
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+/// v0: Hashed bag-of-words embedder.
+/// Each lowercase whitespace-separated word is hashed (FNV-1a) into one of
+/// `dimensions` signed buckets; the result is L2-normalized.
+#[derive(Debug, Clone, Copy)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn id(&self) -> String {
+        format!("hashing-{}", self.dimensions)
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut v = vec![0.0f32; self.dimensions];
+        for word in text.to_lowercase().split_whitespace() {
+            let h = fnv1a(word.as_bytes());
+            let bucket = (h % self.dimensions as u64) as usize;
+            let sign = if (h >> 63) == 0 { 1.0 } else { -1.0 };
+            v[bucket] += sign;
+        }
+
+        let norm = v.iter().map(|x| (*x as f64) * (*x as f64)).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            for x in &mut v {
+                *x = (*x as f64 / norm) as f32;
+            }
+        }
+        v
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Quantize a float vector to fixed-point integers (`VECTOR_SCALE`).
+pub fn quantize(v: &[f32]) -> Vec<i32> {
+    v.iter()
+        .map(|x| {
+            let scaled = (*x as f64 * VECTOR_SCALE).round();
+            scaled.clamp(i32::MIN as f64, i32::MAX as f64) as i32
+        })
+        .collect()
+}
+
+/// Cosine similarity of two quantized vectors, clamped to [0.0, 1.0] and
+/// rounded to 6 decimal places.
+///
+/// The dot product and norms are accumulated as integers so the result is
+/// exact up to the final (correctly rounded) division and square root.
+pub fn cosine_similarity(a: &[i32], b: &[i32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let mut dot: i128 = 0;
+    let mut norm_a: i128 = 0;
+    let mut norm_b: i128 = 0;
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (*x as i128, *y as i128);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    if norm_a == 0 || norm_b == 0 || dot <= 0 {
+        return 0.0;
+    }
+
+    let cos = dot as f64 / ((norm_a as f64).sqrt() * (norm_b as f64).sqrt());
+    ((cos.min(1.0) * 1_000_000.0).round() / 1_000_000.0) as f32
+}
+
+#[derive(Debug, Error)]
+pub enum VectorError {
+    #[error("Embedder mismatch: cache vectors built with {expected}, scorer uses {found}")]
+    EmbedderMismatch { expected: String, found: String },
+}
+
+/// Contents of `vectors.json`: one quantized vector per document.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VectorStore {
+    pub embedder: String,
+    pub dimensions: usize,
+    pub vectors: BTreeMap<DocumentId, Vec<i32>>,
+}
+
+impl VectorStore {
+    pub fn get(&self, id: &DocumentId) -> Option<&[i32]> {
+        self.vectors.get(id).map(|v| v.as_slice())
+    }
+}
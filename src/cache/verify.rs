@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::cache::cache::{check_file_path, verify_document, CacheReadError, ContextCache, VerificationLevel};
+use crate::cache::store::FileStamp;
+use crate::cache::versioning::{tree_hash, ManifestDocumentEntry};
+
+/// How `ContextCache::verify_with` checks a cache.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VerifyOptions<'a> {
+    /// Threads to hash files on; 0 or 1 hashes on the calling thread.
+    pub threads: usize,
+    /// A report from an earlier verification of the same cache version.
+    /// Files whose size and modification time are unchanged since then are
+    /// trusted instead of re-read.
+    pub previous: Option<&'a VerifyReport>,
+}
+
+/// What `ContextCache::verify_with` checked. Independent of `threads`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VerifyReport {
+    pub cache_version: String,
+    /// Every file covered by the tree hash, by path relative to the root.
+    pub files: BTreeMap<String, VerifiedFile>,
+    /// Files read, hashed and (for documents) re-verified in this run.
+    pub files_hashed: usize,
+    /// Files trusted as unchanged since the previous report.
+    pub files_unchanged: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VerifiedFile {
+    pub checksum: String,
+    /// Absent when the store does not report stamps (see
+    /// `CacheStore::stamp`); such files are always re-read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<FileStamp>,
+}
+
+/// Outcome of checking one file: its record, whether it was read, and the
+/// document check, which is reported after the tree hash.
+type Checked = (VerifiedFile, bool, Option<CacheReadError>);
+
+impl ContextCache {
+    /// `verify`, hashing files on `options.threads` threads and skipping
+    /// files unchanged since `options.previous`, and returning what was
+    /// checked. Errors are those `verify` returns, whatever the threading.
+    ///
+    /// A previous report only counts for the same `cache_version`. The
+    /// size and modification time check is a heuristic: a file rewritten
+    /// with the same size within the filesystem's timestamp resolution is
+    /// not re-read.
+    pub fn verify_with(&self, options: &VerifyOptions) -> Result<VerifyReport> {
+        let mut files: Vec<(&str, Option<&ManifestDocumentEntry>)> =
+            Vec::with_capacity(self.manifest.documents.len() + 2);
+        for entry in &self.manifest.documents {
+            check_file_path(&entry.id, &entry.file)?;
+            files.push((entry.file.as_str(), Some(entry)));
+        }
+        files.push(("index.json", None));
+        // vectors.json is optional; its absence is part of the tree
+        if self.store().exists(&self.root.join("vectors.json")) {
+            files.push(("vectors.json", None));
+        }
+        if self.store().exists(&self.root.join("tantivy.tar")) {
+            files.push(("tantivy.tar", None));
+        }
+        let previous = options
+            .previous
+            .filter(|report| report.cache_version == self.manifest.cache_version);
+
+        let check = |(file, entry): &(&str, Option<&ManifestDocumentEntry>)| -> Result<Checked> {
+            let path = self.root.join(file);
+            let stamp = self.store().stamp(&path)?;
+            if let Some(known) = previous.and_then(|report| report.files.get(*file)) {
+                if stamp.is_some() && known.stamp == stamp {
+                    return Ok((known.clone(), false, None));
+                }
+            }
+            let bytes = self.store().read_file(&path)?;
+            let checksum = self.hash_algorithm()?.checksum(&bytes);
+            let invalid = entry.and_then(|entry| {
+                let encoding = self.manifest.build_config.document_encoding;
+                verify_document(encoding, entry, &bytes, VerificationLevel::Full).err()
+            });
+            Ok((VerifiedFile { checksum, stamp }, true, invalid))
+        };
+
+        let threads = options.threads.clamp(1, files.len().max(1));
+        let checked: Vec<Result<Checked>> = if threads == 1 {
+            files.iter().map(check).collect()
+        } else {
+            let chunk = files.len().div_ceil(threads);
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = files
+                    .chunks(chunk)
+                    .map(|chunk| scope.spawn(|| chunk.iter().map(check).collect::<Vec<_>>()))
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("verification thread panicked"))
+                    .collect()
+            })
+        };
+
+        let mut report = VerifyReport {
+            cache_version: self.manifest.cache_version.clone(),
+            files: BTreeMap::new(),
+            files_hashed: 0,
+            files_unchanged: 0,
+        };
+        let mut invalid_documents = Vec::new();
+        for ((file, _), result) in files.iter().zip(checked) {
+            let (verified, hashed, invalid) = result?;
+            if hashed {
+                report.files_hashed += 1;
+            } else {
+                report.files_unchanged += 1;
+            }
+            invalid_documents.extend(invalid);
+            report.files.insert(file.to_string(), verified);
+        }
+
+        if let Some(expected) = &self.manifest.tree_hash {
+            let checksums = report
+                .files
+                .iter()
+                .map(|(file, verified)| (file.clone(), verified.checksum.clone()))
+                .collect();
+            let actual = tree_hash(self.hash_algorithm()?, &checksums);
+            if &actual != expected {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("tree hash mismatch: manifest says {}, files hash to {}", expected, actual),
+                ));
+            }
+        }
+        match invalid_documents.into_iter().next() {
+            Some(e) => Err(e.into()),
+            None => Ok(report),
+        }
+    }
+}
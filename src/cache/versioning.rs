@@ -2,7 +2,11 @@ use std::collections::BTreeMap;
 
 use chrono::{DateTime, Utc};
 
-use crate::types::identifiers::{DocumentId, DocumentVersion};
+use crate::cache::invalidation::CacheBuildError;
+use crate::compat;
+use crate::compression::Summarizer;
+use crate::document::{Document, Normalization};
+use crate::types::identifiers::{DocumentId, DocumentVersion, HashAlgorithm};
 
 // Key point:
 // Serializable
@@ -12,6 +16,117 @@ use crate::types::identifiers::{DocumentId, DocumentVersion};
 pub struct CacheBuildConfig {
     pub version: String,
     pub hash_algorithm: String,
+    /// Pin the manifest's `created_at` for byte-reproducible builds.
+    /// Not serialized: it never affects `cache_version`.
+    /// When unset, `SOURCE_DATE_EPOCH` is honored, then the wall clock.
+    #[serde(skip)]
+    pub fixed_timestamp: Option<DateTime<Utc>>,
+    /// Whether each written file is fsynced. Not serialized: it never
+    /// affects `cache_version`.
+    #[serde(skip)]
+    pub durability: Durability,
+    /// Name of the analyzer the cache is meant to be scored with (see
+    /// `selection::Analyzer`). Part of `cache_version` when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<String>,
+    /// How document files are written. Part of `cache_version` when not JSON.
+    #[serde(default, skip_serializing_if = "DocumentEncoding::is_json")]
+    pub document_encoding: DocumentEncoding,
+    /// Fills each document's `summary`. Part of `cache_version` when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summarizer: Option<Summarizer>,
+    /// Normalizes each document's content before it is versioned, so
+    /// checkouts differing only in line endings or Unicode composition
+    /// build the same cache. Part of `cache_version` when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<Normalization>,
+    /// Keep document IDs as given instead of lowercasing them, so
+    /// `README.md` and `readme.md` (see `DocumentId::from_path_case_sensitive`)
+    /// are distinct documents rather than duplicates. Part of
+    /// `cache_version` when set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub case_sensitive_ids: bool,
+    /// Write each document file under a subdirectory named by the first two
+    /// characters of its name (`documents/ab/abcdef012345.json`) instead of
+    /// one flat `documents/`. Manifest paths record the layout, so loading
+    /// handles either. Part of `cache_version` when set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sharded_documents: bool,
+    /// Write a tantivy index of the documents to `tantivy.tar`, for
+    /// `selection::TantivyCandidateSource`. Part of `cache_version` when
+    /// set. Building with it fails without the `tantivy` feature.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tantivy_index: bool,
+}
+
+/// Whether a build waits for each file it writes to reach stable storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Fsync every file before the cache is renamed into place.
+    #[default]
+    Durable,
+    /// Skip the fsyncs; the rename into place is still atomic. Much faster
+    /// for test, CI, and other throwaway caches, but NOT safe against power
+    /// loss or an OS crash: the renamed cache may have empty or partial
+    /// files afterwards (which `ContextCache::verify` detects).
+    Fast,
+}
+
+/// Encoding of the files under `documents/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentEncoding {
+    /// `<hash>.json`
+    #[default]
+    Json,
+    /// `<hash>.msgpack`: MessagePack with named fields (see `types::msgpack`).
+    /// Building or reading such documents fails without the `msgpack`
+    /// feature.
+    Msgpack,
+}
+
+impl DocumentEncoding {
+    pub fn is_json(&self) -> bool {
+        *self == DocumentEncoding::Json
+    }
+
+    /// Document file extension.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocumentEncoding::Json => "json",
+            DocumentEncoding::Msgpack => "msgpack",
+        }
+    }
+
+    pub(crate) fn encode(&self, doc: &Document) -> Result<Vec<u8>, CacheBuildError> {
+        match self {
+            DocumentEncoding::Json => Ok(serde_json::to_vec(doc)?),
+            #[cfg(feature = "msgpack")]
+            DocumentEncoding::Msgpack => Ok(crate::types::msgpack::to_msgpack(doc)?),
+            #[cfg(not(feature = "msgpack"))]
+            DocumentEncoding::Msgpack => Err(CacheBuildError::FeatureDisabled {
+                setting: "DocumentEncoding::Msgpack",
+                feature: "msgpack",
+            }),
+        }
+    }
+
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Result<Document, std::io::Error> {
+        let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+        match self {
+            DocumentEncoding::Json => compat::document_from_json(bytes).map_err(|e| invalid(e.to_string())),
+            #[cfg(feature = "msgpack")]
+            DocumentEncoding::Msgpack => crate::types::msgpack::from_msgpack(bytes).map_err(|e| invalid(e.to_string())),
+            #[cfg(not(feature = "msgpack"))]
+            DocumentEncoding::Msgpack => {
+                let _ = bytes;
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "msgpack document files need the `msgpack` feature",
+                ))
+            }
+        }
+    }
 }
 
 impl CacheBuildConfig {
@@ -19,8 +134,62 @@ impl CacheBuildConfig {
         Self {
             version: "1".into(),
             hash_algorithm: "sha256".into(),
+            fixed_timestamp: None,
+            durability: Durability::Durable,
+            analyzer: None,
+            document_encoding: DocumentEncoding::Json,
+            summarizer: None,
+            normalization: None,
+            case_sensitive_ids: false,
+            sharded_documents: false,
+            tantivy_index: false,
         }
     }
+
+    pub fn with_fixed_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.fixed_timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    pub fn with_analyzer(mut self, name: impl Into<String>) -> Self {
+        self.analyzer = Some(name.into());
+        self
+    }
+
+    pub fn with_document_encoding(mut self, encoding: DocumentEncoding) -> Self {
+        self.document_encoding = encoding;
+        self
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Summarizer) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = Some(normalization);
+        self
+    }
+
+    pub fn with_case_sensitive_ids(mut self) -> Self {
+        self.case_sensitive_ids = true;
+        self
+    }
+
+    pub fn with_sharded_documents(mut self) -> Self {
+        self.sharded_documents = true;
+        self
+    }
+
+    pub fn with_tantivy_index(mut self) -> Self {
+        self.tantivy_index = true;
+        self
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -32,11 +201,57 @@ pub struct ManifestDocumentEntry {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CacheManifest {
+    /// See `compat::CACHE_MANIFEST_SCHEMA_VERSION`; 0 when absent.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// See `compat::CACHE_FORMAT_VERSION`; 0 when absent, in caches
+    /// written before it was recorded.
+    #[serde(default)]
+    pub format_version: u32,
+    /// Version of the crate that built the cache; informational only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crate_version: Option<String>,
     pub cache_version: String,
     pub build_config: CacheBuildConfig,
     pub created_at: DateTime<Utc>, // informational only
     pub document_count: usize,
     pub documents: Vec<ManifestDocumentEntry>,
+    /// Checksum of the `index.json` bytes, checked by `ContextCache::open`.
+    /// Absent in manifests written before checksums were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_checksum: Option<String>,
+    /// Hash over every cache file except `manifest.json` (see `tree_hash`),
+    /// checked by `ContextCache::verify`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tree_hash: Option<String>,
+}
+
+/// Combine per-file checksums into a single tree hash.
+///
+/// Input maps relative path to the checksum of that file's bytes; one
+/// `<path>:<checksum>` line per file is hashed in path order.
+pub(crate) fn tree_hash(algorithm: HashAlgorithm, checksums: &BTreeMap<String, String>) -> String {
+    let mut hasher = algorithm.hasher();
+    for (path, checksum) in checksums {
+        hasher.update(format!("{}:{}\n", path, checksum).as_bytes());
+    }
+    hasher.finalize()
+}
+
+/// The `cache_version` `CacheBuilder` computes for `manifest`'s config and
+/// documents, with `embedder` the id `vectors.json` records (if any).
+/// `None` if the manifest names an unknown hash algorithm.
+#[cfg(feature = "http")]
+pub(crate) fn derived_cache_version(manifest: &CacheManifest, embedder: Option<&str>) -> Option<String> {
+    let mut hasher = HashAlgorithm::from_name(&manifest.build_config.hash_algorithm)?.hasher();
+    hasher.update(&serde_json::to_vec(&manifest.build_config).ok()?);
+    for entry in &manifest.documents {
+        hasher.update(format!("{}:{}", entry.id.as_str(), entry.version.as_str()).as_bytes());
+    }
+    if let Some(embedder) = embedder {
+        hasher.update(format!("embedder:{}", embedder).as_bytes());
+    }
+    Some(hasher.finalize())
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -49,4 +264,21 @@ impl CacheIndex {
     pub fn new(entries: BTreeMap<DocumentId, String>) -> Self {
         Self { entries }
     }
+
+    /// Relative file path of a document.
+    pub fn get(&self, id: &DocumentId) -> Option<&str> {
+        self.entries.get(id).map(|s| s.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&DocumentId, &str)> {
+        self.entries.iter().map(|(id, file)| (id, file.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
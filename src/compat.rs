@@ -0,0 +1,90 @@
+//! Schema versions of the serialized contracts, and forward migration of
+//! payloads written with older versions.
+//!
+//! `SelectionResult`, `Document`, and `CacheManifest` carry a
+//! `schema_version`. Payloads written before versioning have none and are
+//! version 0; plain `serde` deserialization reads them as 0, while the
+//! `*_from_json` functions here migrate them to the current version.
+//! Payloads from a newer version are rejected rather than misread.
+//!
+//! A cache manifest also records the `CACHE_FORMAT_VERSION` of the files it
+//! lists, which changes when their layout or encoding does; caches in a
+//! newer format are rejected the same way.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::cache::CacheManifest;
+use crate::document::Document;
+use crate::types::context_bundle::SelectionResult;
+
+pub const SELECTION_RESULT_SCHEMA_VERSION: u32 = 1;
+pub const DOCUMENT_SCHEMA_VERSION: u32 = 1;
+pub const CACHE_MANIFEST_SCHEMA_VERSION: u32 = 1;
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompatError {
+    #[error("Unsupported schema version {found} (newest supported: {supported})")]
+    UnsupportedVersion { found: u64, supported: u32 },
+
+    #[error("Unsupported cache format {found} (newest supported: {supported})")]
+    UnsupportedFormat { found: u64, supported: u32 },
+
+    #[error("Invalid payload: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Rewrites a payload of version `i` (its index) into version `i + 1`.
+type Migration = fn(&mut Value);
+
+/// v1 only introduced `schema_version`; the layout is otherwise unchanged.
+fn v0_to_v1(_value: &mut Value) {}
+
+const SELECTION_RESULT_MIGRATIONS: &[Migration] = &[v0_to_v1];
+const DOCUMENT_MIGRATIONS: &[Migration] = &[v0_to_v1];
+const CACHE_MANIFEST_MIGRATIONS: &[Migration] = &[v0_to_v1];
+
+pub fn selection_result_from_json(bytes: &[u8]) -> Result<SelectionResult, CompatError> {
+    from_json(bytes, SELECTION_RESULT_SCHEMA_VERSION, SELECTION_RESULT_MIGRATIONS, None)
+}
+
+pub fn document_from_json(bytes: &[u8]) -> Result<Document, CompatError> {
+    from_json(bytes, DOCUMENT_SCHEMA_VERSION, DOCUMENT_MIGRATIONS, None)
+}
+
+pub fn cache_manifest_from_json(bytes: &[u8]) -> Result<CacheManifest, CompatError> {
+    from_json(bytes, CACHE_MANIFEST_SCHEMA_VERSION, CACHE_MANIFEST_MIGRATIONS, Some(CACHE_FORMAT_VERSION))
+}
+
+/// `format` is the newest `format_version` supported, for payloads that
+/// carry one. Both versions are checked on the raw JSON, so a newer payload
+/// is reported as such even when its layout no longer deserializes.
+fn from_json<T: DeserializeOwned>(
+    bytes: &[u8],
+    current: u32,
+    migrations: &[Migration],
+    format: Option<u32>,
+) -> Result<T, CompatError> {
+    debug_assert_eq!(migrations.len(), current as usize);
+
+    let mut value: Value = serde_json::from_slice(bytes)?;
+    let found = value.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+    if found > current as u64 {
+        return Err(CompatError::UnsupportedVersion { found, supported: current });
+    }
+    if let Some(supported) = format {
+        let found = value.get("format_version").and_then(Value::as_u64).unwrap_or(0);
+        if found > supported as u64 {
+            return Err(CompatError::UnsupportedFormat { found, supported });
+        }
+    }
+
+    for migrate in &migrations[found as usize..] {
+        migrate(&mut value);
+    }
+    if let Value::Object(map) = &mut value {
+        map.insert("schema_version".to_string(), current.into());
+    }
+    Ok(serde_json::from_value(value)?)
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+
+use sha2::{Digest, Sha256};
+
+/// Replaces leading blocks already seen at the start of an earlier document.
+pub const SHARED_HEADER_MARKER: &str = "[...shared header omitted...]";
+/// Replaces trailing blocks already seen at the end of an earlier document.
+pub const SHARED_FOOTER_MARKER: &str = "[...shared footer omitted...]";
+
+/// Elide boilerplate repeated across `contents`, taken in order.
+///
+/// Content is split into blocks at blank lines. When a content's first
+/// `k` blocks are byte-identical to the first `k` blocks of an earlier
+/// content, the longest such run is replaced with `SHARED_HEADER_MARKER`;
+/// likewise trailing blocks with `SHARED_FOOTER_MARKER`. A run is elided
+/// only when it is longer than its marker, so elision never grows content.
+/// The first occurrence always keeps its text.
+///
+/// Returns the new content for each input, or `None` when unchanged.
+pub fn elide_shared_boilerplate(contents: &[&str]) -> Vec<Option<String>> {
+    let mut headers: BTreeSet<[u8; 32]> = BTreeSet::new();
+    let mut footers: BTreeSet<[u8; 32]> = BTreeSet::new();
+
+    contents
+        .iter()
+        .map(|content| {
+            let blocks = blocks(content);
+            let prefixes = run_hashes(blocks.iter().map(|&(start, end)| &content[start..end]));
+            let suffixes = run_hashes(blocks.iter().rev().map(|&(start, end)| &content[start..end]));
+
+            // Longest runs seen before; the header wins any overlap
+            let header = prefixes.iter().rposition(|hash| headers.contains(hash)).map_or(0, |i| i + 1);
+            let header = if header > 0 && blocks[header - 1].1 > SHARED_HEADER_MARKER.len() {
+                header
+            } else {
+                0
+            };
+            let footer = suffixes.iter().rposition(|hash| footers.contains(hash)).map_or(0, |i| i + 1);
+            let footer = footer.min(blocks.len() - header);
+            let footer = if footer > 0 && content.len() - blocks[blocks.len() - footer].0 > SHARED_FOOTER_MARKER.len() {
+                footer
+            } else {
+                0
+            };
+
+            headers.extend(prefixes);
+            footers.extend(suffixes);
+            if header == 0 && footer == 0 {
+                return None;
+            }
+
+            let mut parts: Vec<&str> = Vec::new();
+            if header > 0 {
+                parts.push(SHARED_HEADER_MARKER);
+            }
+            if header + footer < blocks.len() {
+                let start = if header > 0 { blocks[header].0 } else { 0 };
+                let end = if footer > 0 {
+                    blocks[blocks.len() - footer - 1].1
+                } else {
+                    content.len()
+                };
+                parts.push(&content[start..end]);
+            }
+            if footer > 0 {
+                parts.push(SHARED_FOOTER_MARKER);
+            }
+            Some(parts.join("\n\n"))
+        })
+        .collect()
+}
+
+/// Byte ranges of the runs of non-blank lines, in order.
+fn blocks(content: &str) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        if text.trim().is_empty() {
+            blocks.extend(current.take());
+        } else {
+            let end = offset + text.len();
+            current = Some(current.map_or((offset, end), |(start, _)| (start, end)));
+        }
+        offset += line.len();
+    }
+    blocks.extend(current);
+    blocks
+}
+
+/// Cumulative hashes: entry `i` identifies the first `i + 1` blocks.
+fn run_hashes<'a>(blocks: impl Iterator<Item = &'a str>) -> Vec<[u8; 32]> {
+    let mut previous = [0u8; 32];
+    blocks
+        .map(|block| {
+            let mut hasher = Sha256::new();
+            hasher.update(previous);
+            hasher.update((block.len() as u64).to_le_bytes());
+            hasher.update(block.as_bytes());
+            previous = hasher.finalize().into();
+            previous
+        })
+        .collect()
+}
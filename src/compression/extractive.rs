@@ -0,0 +1,100 @@
+use crate::document::parser::split_sentences;
+use crate::selection::ranking::{term_frequency, TokenCounter};
+use crate::types::query::Query;
+
+/// Reduces a document to its highest-scoring sentences.
+///
+/// Content is split into sentences: a sentence ends after `.`, `!` or `?`
+/// followed by whitespace, and at every line break. Each sentence scores
+/// its query term and phrase matches (lowercased whitespace words, as
+/// `TermFrequencyScorer` matches them). Sentences are taken best first,
+/// earlier sentences winning ties, skipping any that would overflow the
+/// target; the kept sentences are joined with `\n` in document order.
+///
+/// With `ContextSelector::with_compression`, documents that do not fit the
+/// budget are compressed into what is left of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ExtractiveCompressor {
+    /// Results shorter than this many tokens are discarded.
+    pub min_tokens: usize,
+}
+
+impl Default for ExtractiveCompressor {
+    fn default() -> Self {
+        Self { min_tokens: 1 }
+    }
+}
+
+impl ExtractiveCompressor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_tokens(mut self, min_tokens: usize) -> Self {
+        self.min_tokens = min_tokens;
+        self
+    }
+
+    /// `content` cut down to at most `max_tokens` by `counter`. `None` when
+    /// no sentence fits or the result is under `min_tokens`.
+    pub fn compress(
+        &self,
+        content: &str,
+        query: &Query,
+        counter: &(impl TokenCounter + ?Sized),
+        max_tokens: usize,
+    ) -> Option<String> {
+        self.compress_to(content, query, counter, |_, tokens| tokens <= max_tokens)
+            .map(|(text, _)| text)
+    }
+
+    /// Like `compress`, with `fits(text, tokens)` deciding the target.
+    /// Also returns the result's token count.
+    pub(crate) fn compress_to(
+        &self,
+        content: &str,
+        query: &Query,
+        counter: &(impl TokenCounter + ?Sized),
+        fits: impl Fn(&str, usize) -> bool,
+    ) -> Option<(String, usize)> {
+        let sentences = split_sentences(content);
+        let mut order: Vec<(usize, usize)> = sentences
+            .iter()
+            .enumerate()
+            .map(|(i, sentence)| (i, sentence_score(sentence, query)))
+            .collect();
+        // Best first; earlier sentences win ties
+        order.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut kept = vec![false; sentences.len()];
+        let mut best: Option<(String, usize)> = None;
+        for (i, _) in order {
+            kept[i] = true;
+            let text = join_kept(&sentences, &kept);
+            let tokens = counter.count_tokens(&text);
+            if fits(&text, tokens) {
+                best = Some((text, tokens));
+            } else {
+                kept[i] = false;
+            }
+        }
+        best.filter(|(_, tokens)| *tokens >= self.min_tokens)
+    }
+}
+
+fn sentence_score(sentence: &str, query: &Query) -> usize {
+    let lower = sentence.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let details = term_frequency(&words, &query.terms, &query.phrases, None);
+    details.term_matches + details.phrase_matches
+}
+
+fn join_kept(sentences: &[&str], kept: &[bool]) -> String {
+    let parts: Vec<&str> = sentences
+        .iter()
+        .zip(kept)
+        .filter(|(_, &kept)| kept)
+        .map(|(sentence, _)| *sentence)
+        .collect();
+    parts.join("\n")
+}
@@ -1 +1,9 @@
+pub mod boilerplate;
+pub mod extractive;
 pub mod summarizer;
+pub mod truncation;
+
+pub use boilerplate::{elide_shared_boilerplate, SHARED_FOOTER_MARKER, SHARED_HEADER_MARKER};
+pub use extractive::ExtractiveCompressor;
+pub use summarizer::Summarizer;
+pub use truncation::{TruncationStrategy, MIDDLE_OUT_MARKER};
@@ -1,2 +1,31 @@
-// Summarization hooks (v0)
-// Placeholder for future compression strategies.
+use crate::document::parser::split_sentences;
+
+/// Deterministic document summaries, computed once at cache build time.
+///
+/// Set with `CacheBuildConfig::with_summarizer`; each cached document's
+/// summary is stored in `Document::summary`, and
+/// `SelectionOptions::summary_fallback` admits it in place of a document
+/// that does not fit the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Summarizer {
+    /// The first `count` sentences (see `split_sentences`), one per line.
+    FirstSentences { count: usize },
+}
+
+impl Summarizer {
+    pub fn first_sentences(count: usize) -> Self {
+        Summarizer::FirstSentences { count }
+    }
+
+    /// The summary of `content`; `None` when it would be empty.
+    pub fn summarize(&self, content: &str) -> Option<String> {
+        let text = match self {
+            Summarizer::FirstSentences { count } => {
+                let sentences = split_sentences(content);
+                sentences[..sentences.len().min(*count)].join("\n")
+            }
+        };
+        (!text.is_empty()).then_some(text)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::document::parser::markdown_sections;
+use crate::selection::ranking::TokenCounter;
+
+/// Joins the head and tail kept by `TruncationStrategy::MiddleOut`.
+pub const MIDDLE_OUT_MARKER: &str = "\n[...]\n";
+
+/// Which part of a document survives truncation.
+///
+/// With `ContextSelector::with_truncation`, documents that do not fit the
+/// budget are truncated to what is left of it, and the strategy is
+/// recorded in `SelectedDocument::truncation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// The longest prefix that fits.
+    Head,
+    /// The longest suffix that fits.
+    Tail,
+    /// Equal numbers of leading and trailing characters (the head gets the
+    /// odd one), joined by `MIDDLE_OUT_MARKER`.
+    MiddleOut,
+    /// The leading Markdown headings that fit, one per line (see
+    /// `markdown_sections`).
+    HeadingsOnly,
+}
+
+impl TruncationStrategy {
+    /// `content` cut down to at most `max_tokens` by `counter`; `content`
+    /// itself when it fits. `None` when nothing of it fits.
+    pub fn truncate(
+        &self,
+        content: &str,
+        counter: &(impl TokenCounter + ?Sized),
+        max_tokens: usize,
+    ) -> Option<String> {
+        self.truncate_with(content, |text| counter.count_tokens(text) <= max_tokens)
+    }
+
+    /// Like `truncate`, with `fits` deciding the target. Sizes are assumed
+    /// not to decrease as more is kept.
+    pub(crate) fn truncate_with(&self, content: &str, fits: impl Fn(&str) -> bool) -> Option<String> {
+        if fits(content) {
+            return Some(content.to_string());
+        }
+
+        // Char boundaries, so every cut is valid UTF-8
+        let bounds: Vec<usize> = content.char_indices().map(|(i, _)| i).chain([content.len()]).collect();
+        let chars = bounds.len() - 1;
+        let text = match self {
+            TruncationStrategy::Head => {
+                let keep = longest(chars, |k| fits(&content[..bounds[k]]))?;
+                content[..bounds[keep]].to_string()
+            }
+            TruncationStrategy::Tail => {
+                let keep = longest(chars, |k| fits(&content[bounds[chars - k]..]))?;
+                content[bounds[chars - keep]..].to_string()
+            }
+            TruncationStrategy::MiddleOut => {
+                let middle_out = |k: usize| {
+                    let (head, tail) = (k - k / 2, k / 2);
+                    format!(
+                        "{}{}{}",
+                        &content[..bounds[head]],
+                        MIDDLE_OUT_MARKER,
+                        &content[bounds[chars - tail]..]
+                    )
+                };
+                let keep = longest(chars, |k| fits(&middle_out(k)))?;
+                middle_out(keep)
+            }
+            TruncationStrategy::HeadingsOnly => {
+                let headings: Vec<String> = markdown_sections(content)
+                    .iter()
+                    .map(|section| format!("{} {}", "#".repeat(section.level), section.title))
+                    .collect();
+                let keep = longest(headings.len(), |k| fits(&headings[..k].join("\n")))?;
+                headings[..keep].join("\n")
+            }
+        };
+        Some(text)
+    }
+}
+
+/// The largest `k` in `1..=max` with `fits(k)`, by binary search.
+fn longest(max: usize, fits: impl Fn(usize) -> bool) -> Option<usize> {
+    if max == 0 || !fits(1) {
+        return None;
+    }
+    let (mut lo, mut hi) = (1, max);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if fits(mid) {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    Some(lo)
+}
@@ -0,0 +1,212 @@
+//! Binary and oversized file detection for ingestion.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::encoding::ORIGINAL_ENCODING_KEY;
+use super::parser::ORIGINAL_CONTENT_HASH_KEY;
+use super::{Document, DocumentId, DocumentVersion, Metadata, Source};
+
+/// Metadata key of a stub's `Rejection`, as text.
+pub const REJECTION_KEY: &str = "rejection";
+/// Metadata key of a stub's original size in bytes.
+pub const SIZE_BYTES_KEY: &str = "size_bytes";
+
+/// Why a file is not ingested as text.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Rejection {
+    #[error("{bytes} bytes exceeds the {limit} byte limit")]
+    TooLarge { bytes: u64, limit: u64 },
+    #[error("content is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("content contains NUL bytes")]
+    NulBytes,
+    /// Control characters other than tab, newline, carriage return and
+    /// form feed, per thousand bytes.
+    #[error("{per_mille} per mille of the content is control characters (limit {limit})")]
+    ControlCharacters { per_mille: u32, limit: u32 },
+    #[error("a {longest} byte line exceeds the {limit} byte limit")]
+    LongLines { longest: usize, limit: usize },
+}
+
+/// What ingestion does with a file `BinaryDetector` rejects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryPolicy {
+    /// Leave it out and list it in the `IngestionReport`.
+    #[default]
+    Skip,
+    /// Fail ingestion.
+    Error,
+    /// Ingest a metadata-only document in its place (see `stub`), and list
+    /// it in the `IngestionReport`.
+    Stub,
+}
+
+/// Deterministic heuristics for files that should not be ingested as text.
+///
+/// UTF-8 validity alone lets through NUL-padded data, minified bundles
+/// and base64 blobs. A file is rejected, checking in this order, when it
+/// is larger than `max_bytes`, is not UTF-8, contains a NUL byte, has
+/// more than `max_control_per_mille` control characters per thousand
+/// bytes, or has a line longer than `max_line_bytes` (minified code,
+/// inline blobs). `None` disables a limit. The verdict depends only on
+/// the bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryDetector {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_line_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_control_per_mille: Option<u32>,
+}
+
+impl Default for BinaryDetector {
+    /// 10 MiB files, 10 000 byte lines, 1% control characters.
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(10 * 1024 * 1024),
+            max_line_bytes: Some(10_000),
+            max_control_per_mille: Some(10),
+        }
+    }
+}
+
+impl BinaryDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn max_line_bytes(mut self, max_line_bytes: Option<usize>) -> Self {
+        self.max_line_bytes = max_line_bytes;
+        self
+    }
+
+    pub fn max_control_per_mille(mut self, max_control_per_mille: Option<u32>) -> Self {
+        self.max_control_per_mille = max_control_per_mille;
+        self
+    }
+
+    /// The first rule `bytes` breaks, if any.
+    pub fn check(&self, bytes: &[u8]) -> Result<(), Rejection> {
+        self.check_size(bytes.len() as u64)?;
+        self.check_content(bytes)
+    }
+
+    /// The size rule alone.
+    pub fn check_size(&self, bytes: u64) -> Result<(), Rejection> {
+        match self.max_bytes {
+            Some(limit) if bytes > limit => Err(Rejection::TooLarge { bytes, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Every rule but the size rule.
+    pub fn check_content(&self, bytes: &[u8]) -> Result<(), Rejection> {
+        let text = std::str::from_utf8(bytes).map_err(|_| Rejection::InvalidUtf8)?;
+        if bytes.contains(&0) {
+            return Err(Rejection::NulBytes);
+        }
+        if let Some(limit) = self.max_control_per_mille {
+            let controls = bytes
+                .iter()
+                .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c)) || b == 0x7f)
+                .count();
+            // Rounded up, so any control character in a short file counts
+            let per_mille = (controls * 1000).div_ceil(bytes.len().max(1)) as u32;
+            if per_mille > limit {
+                return Err(Rejection::ControlCharacters { per_mille, limit });
+            }
+        }
+        if let Some(limit) = self.max_line_bytes {
+            let longest = text.split('\n').map(str::len).max().unwrap_or(0);
+            if longest > limit {
+                return Err(Rejection::LongLines { longest, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A metadata-only stand-in for a rejected file: empty content, with
+/// `rejection` (as text), `size_bytes` and the hash of the original bytes
+/// under `ORIGINAL_CONTENT_HASH_KEY` added to `metadata`.
+pub fn stub(
+    id: DocumentId,
+    source: impl Into<Source>,
+    raw_content: &[u8],
+    rejection: &Rejection,
+    mut metadata: Metadata,
+) -> Document {
+    let original = DocumentVersion::from_content(raw_content);
+    metadata.insert_string(REJECTION_KEY, rejection.to_string());
+    metadata.insert_number(SIZE_BYTES_KEY, raw_content.len() as i64);
+    metadata.insert_string(ORIGINAL_CONTENT_HASH_KEY, original.as_str());
+    Document::ingest(id, source, Vec::new(), metadata).expect("empty content is valid UTF-8")
+}
+
+/// A file ingestion rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedFile {
+    pub id: DocumentId,
+    pub bytes: u64,
+    pub reason: Rejection,
+}
+
+/// A file ingestion turned into documents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestedFile {
+    pub id: DocumentId,
+    /// Size as read, before transcoding.
+    pub bytes: u64,
+    /// The encoding it was transcoded from (see `IngestOptions::transcode`).
+    /// Absent for UTF-8 files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoded_from: Option<String>,
+    /// How many documents it was split into (see
+    /// `IngestOptions::split_oversize`). Absent when it was not split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parts: Option<usize>,
+}
+
+impl IngestedFile {
+    /// The file `id` of `bytes` bytes, ingested as `documents`.
+    pub fn new(id: DocumentId, bytes: u64, documents: &[Document]) -> Self {
+        let transcoded_from = documents
+            .first()
+            .and_then(|doc| doc.metadata.get(ORIGINAL_ENCODING_KEY))
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        Self {
+            id,
+            bytes,
+            transcoded_from,
+            parts: (documents.len() > 1).then_some(documents.len()),
+        }
+    }
+}
+
+/// What an ingestion run did with the files it saw. Lists are in id order,
+/// so reports of the same files compare and log identically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestionReport {
+    /// Documents ingested, stubs included.
+    pub documents: usize,
+    /// Bytes of every file seen, ingested or rejected.
+    #[serde(default)]
+    pub total_bytes: u64,
+    /// Files ingested as text.
+    #[serde(default)]
+    pub ingested: Vec<IngestedFile>,
+    /// Rejected files left out (`BinaryPolicy::Skip`).
+    pub skipped: Vec<RejectedFile>,
+    /// Rejected files ingested as stubs (`BinaryPolicy::Stub`).
+    pub stubbed: Vec<RejectedFile>,
+}
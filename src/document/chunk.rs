@@ -0,0 +1,82 @@
+use crate::types::context_bundle::Span;
+
+mod code;
+
+pub use code::{code_symbols, is_code_path, CodeChunker};
+
+/// A region of a document produced by a `Chunker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Position within the document, from 0.
+    pub index: usize,
+    pub span: Span,
+    /// The definition the chunk holds, for chunkers that know
+    /// (`CodeChunker`); nested definitions read `Outer.inner`.
+    pub symbol: Option<String>,
+}
+
+impl Chunk {
+    /// The chunk's text within `content` (the content it was made from).
+    pub fn text<'a>(&self, content: &'a str) -> &'a str {
+        &content[self.span.byte_range[0]..self.span.byte_range[1]]
+    }
+}
+
+/// Splits document content into chunks for chunk-level scoring.
+///
+/// Chunkers must be deterministic, and chunks must be in content order
+/// and cut on UTF-8 character boundaries.
+pub trait Chunker {
+    /// Identifies the chunker and its parameters in `SelectionMetadata`
+    /// and memoized selection keys.
+    fn name(&self) -> String;
+
+    fn chunks(&self, content: &str) -> Vec<Chunk>;
+}
+
+/// Fixed-size chunks of up to `max_lines` lines. Empty content has no
+/// chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineChunker {
+    max_lines: usize,
+}
+
+impl LineChunker {
+    /// `max_lines` of 0 is treated as 1.
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            max_lines: max_lines.max(1),
+        }
+    }
+}
+
+impl Chunker for LineChunker {
+    fn name(&self) -> String {
+        format!("lines({})", self.max_lines)
+    }
+
+    fn chunks(&self, content: &str) -> Vec<Chunk> {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let mut start = 0;
+        let mut start_line = 1;
+        lines
+            .chunks(self.max_lines)
+            .enumerate()
+            .map(|(index, group)| {
+                let len: usize = group.iter().map(|line| line.len()).sum();
+                let chunk = Chunk {
+                    index,
+                    span: Span {
+                        start_line,
+                        end_line: start_line + group.len() - 1,
+                        byte_range: [start, start + len],
+                    },
+                    symbol: None,
+                };
+                start += len;
+                start_line += group.len();
+                chunk
+            })
+            .collect()
+    }
+}
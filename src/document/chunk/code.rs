@@ -0,0 +1,343 @@
+use super::{Chunk, Chunker};
+use crate::types::context_bundle::Span;
+
+/// Definition keywords, after any modifiers (`pub`, `async`, `export`, ...).
+const KEYWORDS: &[&str] = &[
+    "class", "def", "enum", "fn", "func", "function", "impl", "interface", "mod", "module", "struct", "trait",
+    "union",
+];
+
+const MODIFIERS: &[&str] = &[
+    "abstract", "async", "const", "default", "export", "extern", "\"C\"", "final", "open", "override", "private",
+    "protected", "pub", "public", "sealed", "static", "unsafe",
+];
+
+/// Chunks source code on definition boundaries.
+///
+/// A heuristic for brace- and indentation-structured languages: a line
+/// starting (after modifiers such as `pub` or `export`) with a keyword
+/// like `fn`, `def`, `class`, `struct`, `impl`, `func` or `function`
+/// opens a definition, which runs over the following more-indented lines
+/// and a closing line (`}`, `end`, ...) at its own indentation. Comments,
+/// attributes and decorators directly above belong to it. Each
+/// definition is a chunk whose `symbol` is its name; code between
+/// definitions forms unnamed chunks. A definition longer than `max_lines`
+/// is split at the definitions nested in it (named `Outer.inner`), and
+/// any remaining run longer than `max_lines` into `max_lines` pieces.
+/// Whitespace-only runs join the chunk before them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeChunker {
+    max_lines: usize,
+}
+
+impl CodeChunker {
+    /// `max_lines` of 0 is treated as 1.
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            max_lines: max_lines.max(1),
+        }
+    }
+}
+
+/// Every definition's symbol in `content`, nested ones as `Outer.inner`, in
+/// content order and without repeats (see `CodeChunker`).
+pub fn code_symbols(content: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for symbol in CodeChunker::new(1).chunks(content).into_iter().filter_map(|chunk| chunk.symbol) {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+/// Whether `id` names a source file `CodeChunker` understands, by
+/// extension.
+pub fn is_code_path(id: &str) -> bool {
+    let Some((_, extension)) = id.rsplit_once('.') else {
+        return false;
+    };
+    matches!(
+        extension.to_ascii_lowercase().as_str(),
+        "c" | "cc" | "cpp" | "cs" | "go" | "h" | "hpp" | "java" | "js" | "jsx" | "kt" | "mjs" | "php" | "py" | "rb"
+            | "rs" | "scala" | "swift" | "ts" | "tsx"
+    )
+}
+
+impl Chunker for CodeChunker {
+    fn name(&self) -> String {
+        format!("code({})", self.max_lines)
+    }
+
+    fn chunks(&self, content: &str) -> Vec<Chunk> {
+        let raw: Vec<&str> = content.split_inclusive('\n').collect();
+        let lines: Vec<Line> = raw.iter().map(|line| Line::new(line)).collect();
+        let mut pieces = Vec::new();
+        self.split(&lines, 0, lines.len(), None, &mut pieces);
+
+        // Whitespace-only pieces join the piece before them (or after, first)
+        let mut merged: Vec<Piece> = Vec::new();
+        let mut leading = None;
+        for piece in pieces {
+            let blank = lines[piece.start..piece.end].iter().all(|line| line.blank);
+            match merged.last_mut() {
+                Some(last) if blank => last.end = piece.end,
+                None if blank => leading = Some(leading.unwrap_or(piece.start)),
+                _ => merged.push(Piece {
+                    start: leading.take().unwrap_or(piece.start),
+                    ..piece
+                }),
+            }
+        }
+        if let (Some(start), None) = (leading, merged.first()) {
+            merged.push(Piece {
+                start,
+                end: lines.len(),
+                symbol: None,
+            });
+        }
+
+        let mut offsets = Vec::with_capacity(raw.len() + 1);
+        offsets.push(0);
+        for line in &raw {
+            offsets.push(offsets.last().copied().unwrap_or(0) + line.len());
+        }
+        merged
+            .into_iter()
+            .enumerate()
+            .map(|(index, piece)| Chunk {
+                index,
+                span: Span {
+                    start_line: piece.start + 1,
+                    end_line: piece.end,
+                    byte_range: [offsets[piece.start], offsets[piece.end]],
+                },
+                symbol: piece.symbol,
+            })
+            .collect()
+    }
+}
+
+struct Line<'a> {
+    /// Leading whitespace width, tabs counting 4.
+    indent: usize,
+    blank: bool,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    fn new(raw: &'a str) -> Self {
+        let text = raw.trim();
+        let indent = raw
+            .chars()
+            .take_while(|c| c.is_whitespace() && *c != '\n')
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        Line {
+            indent,
+            blank: text.is_empty(),
+            text,
+        }
+    }
+}
+
+/// Lines `start..end` (0-based) of a chunk-to-be.
+struct Piece {
+    start: usize,
+    end: usize,
+    symbol: Option<String>,
+}
+
+impl CodeChunker {
+    /// Pieces for lines `lo..hi`, whose definitions are looked for at the
+    /// range's smallest indentation.
+    fn split(&self, lines: &[Line], lo: usize, hi: usize, parent: Option<&str>, out: &mut Vec<Piece>) {
+        let Some(base) = lines[lo..hi].iter().filter(|line| !line.blank).map(|line| line.indent).min() else {
+            self.gap(lo, hi, parent, out);
+            return;
+        };
+
+        let mut gap_start = lo;
+        let mut i = lo;
+        while i < hi {
+            let line = &lines[i];
+            let name = (!line.blank && line.indent == base)
+                .then(|| definition_name(line.text))
+                .flatten();
+            let Some(name) = name else {
+                i += 1;
+                continue;
+            };
+
+            let end = block_end(lines, i, hi, base);
+            let mut start = i;
+            while start > gap_start
+                && !lines[start - 1].blank
+                && lines[start - 1].indent >= base
+                && is_preamble(lines[start - 1].text)
+            {
+                start -= 1;
+            }
+            self.gap(gap_start, start, parent, out);
+
+            let symbol = match parent {
+                Some(parent) => format!("{}.{}", parent, name),
+                None => name,
+            };
+            if end - start <= self.max_lines {
+                out.push(Piece {
+                    start,
+                    end,
+                    symbol: Some(symbol),
+                });
+            } else {
+                // The body without a closing line at the definition's
+                // indentation, which would hide nested definitions
+                let closes = end > i + 1 && !lines[end - 1].blank && lines[end - 1].indent == base;
+                let body_end = if closes { end - 1 } else { end };
+                let mut body = Vec::new();
+                self.split(lines, i + 1, body_end, Some(&symbol), &mut body);
+                // The header and closing line join the body's own first and
+                // last pieces when those fit
+                let own = |piece: &Piece| piece.symbol.as_deref() == Some(symbol.as_str());
+                match body.first_mut() {
+                    Some(first) if own(first) && first.end - start <= self.max_lines => first.start = start,
+                    _ => body.insert(
+                        0,
+                        Piece {
+                            start,
+                            end: i + 1,
+                            symbol: Some(symbol.clone()),
+                        },
+                    ),
+                }
+                if body_end < end {
+                    match body.last_mut() {
+                        Some(last) if own(last) && end - last.start <= self.max_lines => last.end = end,
+                        _ => body.push(Piece {
+                            start: body_end,
+                            end,
+                            symbol: Some(symbol.clone()),
+                        }),
+                    }
+                }
+                out.extend(body);
+            }
+            gap_start = end;
+            i = end;
+        }
+        self.gap(gap_start, hi, parent, out);
+    }
+
+    /// Lines `lo..hi` outside any definition, in `max_lines` pieces.
+    fn gap(&self, lo: usize, hi: usize, parent: Option<&str>, out: &mut Vec<Piece>) {
+        let mut start = lo;
+        while start < hi {
+            let end = hi.min(start + self.max_lines);
+            out.push(Piece {
+                start,
+                end,
+                symbol: parent.map(str::to_string),
+            });
+            start = end;
+        }
+    }
+}
+
+/// One past the last line of the definition opened at `header`.
+fn block_end(lines: &[Line], header: usize, hi: usize, base: usize) -> usize {
+    let mut k = header + 1;
+    loop {
+        while k < hi && (lines[k].blank || lines[k].indent > base) {
+            k += 1;
+        }
+        let Some(line) = lines[k..hi].first() else {
+            break;
+        };
+        let continues = ["}", ")", "]", "{", "where", "end"]
+            .iter()
+            .any(|prefix| line.text.starts_with(prefix));
+        if !continues {
+            break;
+        }
+        k += 1;
+        // A signature's closing `) {` or a `where` clause opens the body
+        if !(line.text.ends_with('{') || line.text.starts_with("where")) {
+            break;
+        }
+    }
+    // Trailing blank lines belong to what follows
+    while k > header + 1 && lines[k - 1].blank {
+        k -= 1;
+    }
+    k
+}
+
+/// Comment, attribute and decorator lines.
+fn is_preamble(text: &str) -> bool {
+    ["//", "#", "/*", "*", "@", "--"].iter().any(|prefix| text.starts_with(prefix))
+}
+
+/// The name a definition line defines, if it is one.
+fn definition_name(text: &str) -> Option<String> {
+    let mut rest = text;
+    loop {
+        if let Some(after) = rest.strip_prefix("pub(") {
+            rest = after.split_once(')')?.1.trim_start();
+            continue;
+        }
+        let word = rest.split_whitespace().next()?;
+        if MODIFIERS.contains(&word) {
+            rest = rest[word.len()..].trim_start();
+        } else {
+            break;
+        }
+    }
+
+    let keyword = rest.split(|c: char| !c.is_ascii_alphanumeric()).next()?;
+    if !KEYWORDS.contains(&keyword) {
+        return None;
+    }
+    let mut rest = rest[keyword.len()..].trim_start();
+    match keyword {
+        // `function* name`
+        "function" => rest = rest.trim_start_matches('*').trim_start(),
+        // `func (r *T) Name(`
+        "func" if rest.starts_with('(') => rest = rest.split_once(')')?.1.trim_start(),
+        // `impl<T> Trait for Type`: the type
+        "impl" => {
+            if rest.starts_with('<') {
+                rest = skip_generics(rest)?.trim_start();
+            }
+            let head = rest.split(['{', '\n']).next().unwrap_or(rest);
+            let head = head.split(" where").next().unwrap_or(head);
+            if let Some((_, ty)) = head.rsplit_once(" for ") {
+                rest = ty.trim_start();
+            }
+        }
+        _ => {}
+    }
+    let name: String = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// `text` after the balanced `<...>` it starts with.
+fn skip_generics(text: &str) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[i + 1..]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -1,23 +1,65 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::compat::DOCUMENT_SCHEMA_VERSION;
 use crate::types::identifiers::{DocumentId, DocumentVersion};
+use super::encoding::{self, Encoding, ORIGINAL_ENCODING_KEY};
 use super::metadata::Metadata;
+use super::parser::ORIGINAL_CONTENT_HASH_KEY;
+use super::source::Source;
 
 #[derive(Debug, Error)]
 pub enum DocumentError {
     #[error("Content must be valid UTF-8")]
     InvalidUtf8(#[from] std::string::FromUtf8Error),
+    #[error("Content is not valid {0}")]
+    InvalidEncoding(Encoding),
+    #[error("Content is {size} bytes, over the {limit} byte limit")]
+    TooLarge { size: u64, limit: u64 },
+}
+
+/// Metadata key of a part's number, from 1 (see `Document::ingest_parts`).
+pub const PART_KEY: &str = "part";
+/// Metadata key of the number of parts a document was split into.
+pub const PARTS_KEY: &str = "parts";
+/// Metadata key of the id of the document a part was split from.
+pub const PART_OF_KEY: &str = "part_of";
+
+/// How `Document::ingest_with` reads raw bytes. `Default` is `ingest`'s
+/// strict UTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestOptions {
+    /// Detect the encoding (see `encoding::detect`: byte order marks,
+    /// UTF-16, UTF-8, else Windows-1252) and transcode to UTF-8, dropping
+    /// any byte order mark. Content that was not plain UTF-8 records its
+    /// encoding under `ORIGINAL_ENCODING_KEY` and the hash of the raw bytes
+    /// under `ORIGINAL_CONTENT_HASH_KEY`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub transcode: bool,
+    /// Largest raw content accepted, in bytes; larger content fails with
+    /// `DocumentError::TooLarge`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    /// Have `Document::ingest_parts` split content over `max_bytes` into
+    /// parts instead of failing.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub split_oversize: bool,
 }
 
 /// The atomic unit of content.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Document {
+    /// See `compat::DOCUMENT_SCHEMA_VERSION`; 0 when absent.
+    #[serde(default)]
+    pub schema_version: u32,
     pub id: DocumentId,
     pub version: DocumentVersion,
-    pub source: String,
+    pub source: Source,
     pub content: String,
     pub metadata: Metadata,
+    /// Set at cache build time by the configured `Summarizer`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
 }
 
 impl Document {
@@ -27,7 +69,7 @@ impl Document {
     /// It enforces all invariants: validation, versioning, and immutability.
     pub fn ingest(
         id: DocumentId,
-        source: String,
+        source: impl Into<Source>,
         raw_content: Vec<u8>,
         metadata: Metadata,
     ) -> Result<Self, DocumentError> {
@@ -37,11 +79,105 @@ impl Document {
         let version = DocumentVersion::from_content(content.as_bytes());
 
         Ok(Document {
+            schema_version: DOCUMENT_SCHEMA_VERSION,
             id,
             version,
-            source,
+            source: source.into(),
             content,
             metadata,
+            summary: None,
         })
     }
+
+    /// `ingest`, reading `raw_content` as `options` say.
+    pub fn ingest_with(
+        id: DocumentId,
+        source: impl Into<Source>,
+        raw_content: Vec<u8>,
+        mut metadata: Metadata,
+        options: &IngestOptions,
+    ) -> Result<Self, DocumentError> {
+        if let Some(limit) = options.max_bytes {
+            let size = raw_content.len() as u64;
+            if size > limit {
+                return Err(DocumentError::TooLarge { size, limit });
+            }
+        }
+        if !options.transcode {
+            return Self::ingest(id, source, raw_content, metadata);
+        }
+        let (content, encoding) = encoding::transcode(&raw_content)
+            .ok_or_else(|| DocumentError::InvalidEncoding(encoding::detect(&raw_content)))?;
+        if content.len() != raw_content.len() || encoding != Encoding::Utf8 {
+            let original = DocumentVersion::from_content(&raw_content);
+            metadata.insert_string(ORIGINAL_ENCODING_KEY, encoding.as_str());
+            metadata.insert_string(ORIGINAL_CONTENT_HASH_KEY, original.as_str());
+        }
+        Self::ingest(id, source, content.into_bytes(), metadata)
+    }
+
+    /// `ingest_with`, except that with `split_oversize`, content over
+    /// `max_bytes` is split into parts of at most `max_bytes` (after any
+    /// transcoding) instead of failing. Parts end at line ends where
+    /// possible; a longer line is cut at a character boundary. Each part is
+    /// a document with id `<id>/part-0001`, `<id>/part-0002`, ... and
+    /// `metadata` plus `PART_KEY`, `PARTS_KEY` and `PART_OF_KEY`.
+    pub fn ingest_parts(
+        id: DocumentId,
+        source: impl Into<Source>,
+        raw_content: Vec<u8>,
+        metadata: Metadata,
+        options: &IngestOptions,
+    ) -> Result<Vec<Self>, DocumentError> {
+        let source = source.into();
+        let limit = match options.max_bytes {
+            Some(limit) if options.split_oversize && raw_content.len() as u64 > limit => limit,
+            _ => return Ok(vec![Self::ingest_with(id, source, raw_content, metadata, options)?]),
+        };
+        let unlimited = IngestOptions {
+            max_bytes: None,
+            ..*options
+        };
+        let whole = Self::ingest_with(id.clone(), source.clone(), raw_content, metadata, &unlimited)?;
+        let parts = split_parts(&whole.content, usize::try_from(limit).unwrap_or(usize::MAX));
+        let count = parts.len();
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, part)| {
+                let mut metadata = whole.metadata.clone();
+                metadata.insert_number(PART_KEY, i as i64 + 1);
+                metadata.insert_number(PARTS_KEY, count as i64);
+                metadata.insert_string(PART_OF_KEY, id.as_str());
+                Self::ingest(id.with_part(i + 1), source.clone(), part.as_bytes().to_vec(), metadata)
+            })
+            .collect()
+    }
+}
+
+/// `text` in pieces of at most `max` bytes (but at least one character),
+/// ending at line ends where possible.
+fn split_parts(text: &str, max: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if rest.len() <= max {
+            parts.push(rest);
+            break;
+        }
+        // The longest whole characters that fit, cut back to a line end
+        let mut end = max;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(newline) = rest[..end].rfind('\n') {
+            end = newline + 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        parts.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    parts
 }
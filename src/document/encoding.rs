@@ -0,0 +1,116 @@
+//! Text encoding detection and transcoding to UTF-8.
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata key of a transcoded document's original `Encoding`.
+pub const ORIGINAL_ENCODING_KEY: &str = "original_encoding";
+
+/// Source encodings `transcode` recognizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Encoding {
+    Utf8,
+    Utf16le,
+    Utf16be,
+    /// Latin-1 with the printable characters Windows puts in 0x80-0x9F
+    /// (curly quotes, dashes, `€`), as browsers read `iso-8859-1`.
+    Windows1252,
+}
+
+impl Encoding {
+    /// The WHATWG label, as recorded under `ORIGINAL_ENCODING_KEY`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16le => "utf-16le",
+            Encoding::Utf16be => "utf-16be",
+            Encoding::Windows1252 => "windows-1252",
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The encoding of `bytes`, by the first rule that applies:
+/// 1. A byte order mark: UTF-8 (`EF BB BF`), UTF-16LE (`FF FE`) or
+///    UTF-16BE (`FE FF`).
+/// 2. UTF-16 without a mark: an even length, and more than half of the
+///    two-byte units with a zero high byte and a non-zero low byte (mostly
+///    ASCII text), outnumbering the other byte order's four to one.
+/// 3. Valid UTF-8.
+/// 4. Otherwise Windows-1252, which decodes any bytes.
+pub fn detect(bytes: &[u8]) -> Encoding {
+    if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+        return Encoding::Utf8;
+    }
+    if bytes.starts_with(&[0xff, 0xfe]) {
+        return Encoding::Utf16le;
+    }
+    if bytes.starts_with(&[0xfe, 0xff]) {
+        return Encoding::Utf16be;
+    }
+    if bytes.len() >= 4 && bytes.len() % 2 == 0 {
+        let units = bytes.len() / 2;
+        let le = bytes.chunks(2).filter(|u| u[0] != 0 && u[1] == 0).count();
+        let be = bytes.chunks(2).filter(|u| u[0] == 0 && u[1] != 0).count();
+        if le * 2 > units && le > be * 4 {
+            return Encoding::Utf16le;
+        }
+        if be * 2 > units && be > le * 4 {
+            return Encoding::Utf16be;
+        }
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        Encoding::Utf8
+    } else {
+        Encoding::Windows1252
+    }
+}
+
+/// `bytes` as UTF-8 text, with their `detect`ed encoding. A byte order
+/// mark is dropped. `None` when the bytes are not valid in the detected
+/// encoding (a UTF-8 or UTF-16 mark followed by invalid data, an odd
+/// UTF-16 length, or an unpaired surrogate).
+pub fn transcode(bytes: &[u8]) -> Option<(String, Encoding)> {
+    let encoding = detect(bytes);
+    let text = match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]).unwrap_or(bytes);
+            String::from_utf8(bytes.to_vec()).ok()?
+        }
+        Encoding::Utf16le | Encoding::Utf16be => {
+            let bytes = bytes
+                .strip_prefix(&[0xff, 0xfe])
+                .or_else(|| bytes.strip_prefix(&[0xfe, 0xff]))
+                .unwrap_or(bytes);
+            if bytes.len() % 2 != 0 {
+                return None;
+            }
+            let units: Vec<u16> = bytes
+                .chunks(2)
+                .map(|u| match encoding {
+                    Encoding::Utf16le => u16::from_le_bytes([u[0], u[1]]),
+                    _ => u16::from_be_bytes([u[0], u[1]]),
+                })
+                .collect();
+            String::from_utf16(&units).ok()?
+        }
+        Encoding::Windows1252 => bytes.iter().map(|&b| windows_1252(b)).collect(),
+    };
+    Some((text, encoding))
+}
+
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}', '\u{90}', '‘',
+        '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9f => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
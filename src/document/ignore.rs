@@ -0,0 +1,161 @@
+//! `.gitignore`-style exclusion of paths from directory ingestion.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::selection::filters::glob_match;
+
+/// Ignore files read in each directory, in order; later rules override
+/// earlier ones, so `.contextignore` can re-include what `.gitignore`
+/// excludes.
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".contextignore"];
+
+/// The ignore rules of a directory tree, with `.gitignore` semantics.
+///
+/// Each directory's rules apply to paths below it, matched relative to
+/// it:
+/// - Blank lines and lines starting with `#` are skipped; trailing
+///   spaces are trimmed unless escaped (`\ `); `\#` and `\!` start a
+///   pattern with a literal `#` or `!`.
+/// - `!pattern` re-includes what an earlier rule excluded.
+/// - A trailing `/` matches directories only.
+/// - A pattern with a `/` before its end is anchored to the directory
+///   (a leading `/` only anchors); any other pattern matches at any
+///   depth. Glob syntax is `glob_match`'s.
+///
+/// The last matching rule decides, and rules of deeper directories come
+/// after those of their ancestors. A path under an ignored directory is
+/// ignored whatever its own rules say. Decisions depend only on the
+/// rules and the path, not on the order directories were loaded in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    /// Rules by directory, relative to the root (`""` for the root).
+    rules: BTreeMap<String, Vec<Rule>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the rules in ignore-file `text` to directory `dir` (relative to
+    /// the root, `/`-separated; `""` for the root), after any it has.
+    pub fn add(&mut self, dir: &str, text: &str) {
+        let dir = dir.trim_matches('/');
+        let rules: Vec<Rule> = text.lines().filter_map(Rule::parse).collect();
+        if !rules.is_empty() {
+            self.rules.entry(dir.to_string()).or_default().extend(rules);
+        }
+    }
+
+    /// Replace the rules of `dir` (under `root`) with those of its
+    /// `IGNORE_FILES`. Missing files add no rules.
+    pub fn load_dir(&mut self, root: &Path, dir: &Path) -> io::Result<()> {
+        let Some(rel) = relative(root, dir) else {
+            return Ok(());
+        };
+        self.rules.remove(&rel);
+        for name in IGNORE_FILES {
+            match fs::read(dir.join(name)) {
+                Ok(bytes) => self.add(&rel, &String::from_utf8_lossy(&bytes)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `path` (relative to the root, `/`-separated) is ignored.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.trim_matches('/');
+        let mut ancestor_end = 0;
+        while let Some(slash) = path[ancestor_end..].find('/') {
+            ancestor_end += slash;
+            if self.decide(&path[..ancestor_end], true) {
+                return true;
+            }
+            ancestor_end += 1;
+        }
+        self.decide(path, is_dir)
+    }
+
+    /// Whether `path` (under `root`) is ignored; paths outside `root` are
+    /// not.
+    pub fn is_ignored_path(&self, root: &Path, path: &Path) -> bool {
+        relative(root, path).is_some_and(|rel| !rel.is_empty() && self.is_ignored(&rel, path.is_dir()))
+    }
+
+    /// The verdict of the rules on `path` itself.
+    fn decide(&self, path: &str, is_dir: bool) -> bool {
+        let mut ignored = false;
+        // Ancestors sort before their descendants
+        for (dir, rules) in &self.rules {
+            let rel = if dir.is_empty() {
+                path
+            } else {
+                match path.strip_prefix(dir.as_str()).and_then(|rest| rest.strip_prefix('/')) {
+                    Some(rel) => rel,
+                    None => continue,
+                }
+            };
+            for rule in rules {
+                if (is_dir || !rule.dir_only) && glob_match(&rule.glob, rel) {
+                    ignored = !rule.negated;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Rule> {
+        let mut line = line.trim_end_matches(['\n', '\r']);
+        // Trailing spaces, unless the last is escaped
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let line = line.strip_prefix('\\').filter(|rest| rest.starts_with(['#', '!'])).unwrap_or(line);
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+        let glob = match line.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if line.contains('/') => line.to_string(),
+            None => format!("**/{}", line),
+        };
+        Some(Rule {
+            glob,
+            negated,
+            dir_only,
+        })
+    }
+}
+
+/// `path` relative to `root`, `/`-separated; `None` outside `root` or if
+/// not UTF-8.
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let parts: Option<Vec<&str>> = rel.components().map(|c| c.as_os_str().to_str()).collect();
+    Some(parts?.join("/"))
+}
@@ -1,11 +1,74 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A metadata value. Deserialization is untagged and tries the variants in
+/// order, so JSON integers read as `Number` and other numbers as `Float`;
+/// caches written before `Bool`, `Float`, `List` and `Map` read unchanged.
+#[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 pub enum MetadataValue {
-    String(String),
+    Bool(bool),
     Number(i64),
+    /// Must be finite: serializing NaN or an infinity is an error. `-0.0`
+    /// is written, and compares equal, as `0.0`.
+    Float(f64),
+    String(String),
+    List(Vec<MetadataValue>),
+    Map(BTreeMap<String, MetadataValue>),
+}
+
+impl MetadataValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetadataValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// `-0.0` as `0.0`, so each value has one representation.
+fn normalize_float(value: f64) -> f64 {
+    if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+impl PartialEq for MetadataValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (MetadataValue::Bool(a), MetadataValue::Bool(b)) => a == b,
+            (MetadataValue::Number(a), MetadataValue::Number(b)) => a == b,
+            // Bitwise, so equality stays reflexive for NaN
+            (MetadataValue::Float(a), MetadataValue::Float(b)) => {
+                normalize_float(*a).to_bits() == normalize_float(*b).to_bits()
+            }
+            (MetadataValue::String(a), MetadataValue::String(b)) => a == b,
+            (MetadataValue::List(a), MetadataValue::List(b)) => a == b,
+            (MetadataValue::Map(a), MetadataValue::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for MetadataValue {}
+
+impl Serialize for MetadataValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MetadataValue::Bool(b) => serializer.serialize_bool(*b),
+            MetadataValue::Number(n) => serializer.serialize_i64(*n),
+            MetadataValue::Float(f) if f.is_finite() => serializer.serialize_f64(normalize_float(*f)),
+            MetadataValue::Float(f) => Err(serde::ser::Error::custom(format!(
+                "metadata float must be finite, got {}",
+                f
+            ))),
+            MetadataValue::String(s) => serializer.serialize_str(s),
+            MetadataValue::List(items) => serializer.collect_seq(items),
+            MetadataValue::Map(entries) => serializer.collect_map(entries),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -21,6 +84,10 @@ impl Metadata {
         }
     }
 
+    pub fn insert(&mut self, key: impl Into<String>, value: MetadataValue) {
+        self.inner.insert(key.into(), value);
+    }
+
     pub fn insert_string(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.inner.insert(key.into(), MetadataValue::String(value.into()));
     }
@@ -28,12 +95,23 @@ impl Metadata {
     pub fn insert_number(&mut self, key: impl Into<String>, value: i64) {
         self.inner.insert(key.into(), MetadataValue::Number(value));
     }
-    
-    // Helper to merge another metadata into this one (overriding common keys)
+
+    pub fn insert_bool(&mut self, key: impl Into<String>, value: bool) {
+        self.inner.insert(key.into(), MetadataValue::Bool(value));
+    }
+
+    pub fn insert_float(&mut self, key: impl Into<String>, value: f64) {
+        self.inner.insert(key.into(), MetadataValue::Float(value));
+    }
+
+    // Helper to merge another metadata into this one (overriding common keys;
+    // maps present on both sides are merged recursively, lists are replaced)
     pub fn merge(&mut self, other: Metadata) {
-        for (k, v) in other.inner {
-            self.inner.insert(k, v);
-        }
+        merge_maps(&mut self.inner, other.inner);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<MetadataValue> {
+        self.inner.remove(key)
     }
 
     pub fn get(&self, key: &str) -> Option<&MetadataValue> {
@@ -45,4 +123,13 @@ impl Metadata {
     }
 }
 
-
+fn merge_maps(into: &mut BTreeMap<String, MetadataValue>, from: BTreeMap<String, MetadataValue>) {
+    for (k, v) in from {
+        match (into.get_mut(&k), v) {
+            (Some(MetadataValue::Map(existing)), MetadataValue::Map(incoming)) => merge_maps(existing, incoming),
+            (_, v) => {
+                into.insert(k, v);
+            }
+        }
+    }
+}
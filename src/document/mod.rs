@@ -1,7 +1,23 @@
 pub mod metadata;
+#[allow(clippy::module_inception)]
 pub mod document;
 pub mod parser;
+pub mod binary;
+pub mod chunk;
+pub mod encoding;
+pub mod ignore;
+pub mod normalize;
+pub mod source;
+pub mod walk;
 
 pub use crate::types::identifiers::{DocumentId, DocumentVersion};
 pub use metadata::Metadata;
-pub use document::{Document, DocumentError};
+pub use document::{Document, DocumentError, IngestOptions};
+pub use chunk::{code_symbols, is_code_path, Chunk, Chunker, CodeChunker, LineChunker};
+pub use binary::{BinaryDetector, BinaryPolicy, IngestedFile, IngestionReport, RejectedFile, Rejection};
+pub use ignore::IgnoreRules;
+pub use normalize::Normalization;
+pub use source::Source;
+#[cfg(feature = "fs")]
+pub use walk::LocalFs;
+pub use walk::{ingest_tree, IngestSource, IngestedTree, MemoryFs, WalkError, WalkOptions};
@@ -0,0 +1,192 @@
+//! Text normalization applied before version hashing, so text that differs
+//! only in line endings or Unicode composition hashes the same.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+mod tables;
+
+/// Opt-in normalization passes, applied in field order. Each is
+/// idempotent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Normalization {
+    /// `\r\n` and lone `\r` become `\n`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub newlines: bool,
+    /// Unicode Normalization Form C (see `nfc`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nfc: bool,
+}
+
+impl Normalization {
+    /// Both passes.
+    pub fn all() -> Self {
+        Self {
+            newlines: true,
+            nfc: true,
+        }
+    }
+
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        if self.newlines {
+            if let Cow::Owned(normalized) = newlines(&text) {
+                text = Cow::Owned(normalized);
+            }
+        }
+        if self.nfc {
+            if let Cow::Owned(normalized) = nfc(&text) {
+                text = Cow::Owned(normalized);
+            }
+        }
+        text
+    }
+}
+
+/// `text` with `\r\n` and lone `\r` as `\n`.
+pub fn newlines(text: &str) -> Cow<'_, str> {
+    if !text.contains('\r') {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+/// `text` in Unicode Normalization Form C: canonically decomposed,
+/// combining marks put in canonical order, then canonically recomposed,
+/// per UAX #15 with the Unicode 14.0 character data.
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    // Below U+0300 every character is a starter with no decomposition
+    // that could compose with its neighbours
+    if text.chars().all(|c| (c as u32) < 0x300) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut chars = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        decompose(c as u32, &mut chars);
+    }
+    reorder(&mut chars);
+    compose(&mut chars);
+
+    let normalized: String = chars.into_iter().filter_map(char::from_u32).collect();
+    if normalized == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+const S_BASE: u32 = 0xac00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11a7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+fn combining_class(c: u32) -> u8 {
+    let table = tables::COMBINING_CLASSES;
+    match table.binary_search_by(|&(lo, hi, _)| {
+        if hi < c {
+            std::cmp::Ordering::Less
+        } else if lo > c {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    }) {
+        Ok(i) => table[i].2,
+        Err(_) => 0,
+    }
+}
+
+/// Full canonical decomposition of `c`, appended to `out`.
+fn decompose(c: u32, out: &mut Vec<u32>) {
+    if (S_BASE..S_BASE + S_COUNT).contains(&c) {
+        let s = c - S_BASE;
+        out.push(L_BASE + s / N_COUNT);
+        out.push(V_BASE + (s % N_COUNT) / T_COUNT);
+        if s % T_COUNT != 0 {
+            out.push(T_BASE + s % T_COUNT);
+        }
+        return;
+    }
+    match tables::DECOMPOSITIONS.binary_search_by_key(&c, |&(cp, _, _)| cp) {
+        Ok(i) => {
+            let (_, first, second) = tables::DECOMPOSITIONS[i];
+            decompose(first, out);
+            if second != 0 {
+                decompose(second, out);
+            }
+        }
+        Err(_) => out.push(c),
+    }
+}
+
+/// Stable-sort each run of non-starters by combining class.
+fn reorder(chars: &mut [u32]) {
+    let mut i = 0;
+    while i < chars.len() {
+        if combining_class(chars[i]) == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && combining_class(chars[i]) != 0 {
+            i += 1;
+        }
+        chars[start..i].sort_by_key(|&c| combining_class(c));
+    }
+}
+
+fn compose_pair(first: u32, second: u32) -> Option<u32> {
+    // Hangul LV and LVT
+    if (L_BASE..L_BASE + L_COUNT).contains(&first) && (V_BASE..V_BASE + V_COUNT).contains(&second) {
+        return Some(S_BASE + ((first - L_BASE) * V_COUNT + (second - V_BASE)) * T_COUNT);
+    }
+    if (S_BASE..S_BASE + S_COUNT).contains(&first)
+        && (first - S_BASE) % T_COUNT == 0
+        && (T_BASE + 1..T_BASE + T_COUNT).contains(&second)
+    {
+        return Some(first + (second - T_BASE));
+    }
+    let table = tables::COMPOSITIONS;
+    table
+        .binary_search_by(|&(a, b, _)| (a, b).cmp(&(first, second)))
+        .ok()
+        .map(|i| table[i].2)
+}
+
+/// Canonical composition of decomposed, reordered `chars`, in place.
+fn compose(chars: &mut Vec<u32>) {
+    let mut starter: Option<usize> = None;
+    // Class of the last character kept since the starter; 0 right after it
+    let mut last_class = 0u8;
+    let mut out = 0;
+    for i in 0..chars.len() {
+        let c = chars[i];
+        let class = combining_class(c);
+        if let Some(s) = starter {
+            // Not blocked: nothing between, or everything between has a
+            // lower class (and isn't a starter)
+            if out == s + 1 || (last_class != 0 && last_class < class) {
+                if let Some(composite) = compose_pair(chars[s], c) {
+                    chars[s] = composite;
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(out);
+            last_class = 0;
+        } else {
+            last_class = class;
+        }
+        chars[out] = c;
+        out += 1;
+    }
+    chars.truncate(out);
+}
@@ -1,2 +1,322 @@
-// Document parser hooks (v0)
-// Placeholder for future parsing and normalization strategies.
+//! Lightweight text structure: sentences, Markdown sections, and the
+//! metadata derived from them; `StructuredParser` for JSON, YAML and CSV
+//! data, and with the `html` feature, `HtmlParser`.
+//!
+//! Only ATX headings (`#` to `######` followed by a space) outside fenced
+//! code blocks are recognized; everything else is section body text.
+
+use super::metadata::{Metadata, MetadataValue};
+
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "html")]
+mod html;
+mod structured;
+#[cfg(feature = "http")]
+pub(crate) mod url;
+#[cfg(feature = "git")]
+pub use git::{ingest_git, GitError, GitIngestOptions, GitSnapshot, COMMIT_KEY};
+#[cfg(feature = "html")]
+pub use html::HtmlParser;
+pub use structured::{StructuredError, StructuredFormat, StructuredParser};
+#[cfg(feature = "http")]
+pub use url::{ingest_url, FetchMode, UrlError, UrlIngestOptions, ETAG_KEY, FETCHED_AT_KEY, URL_KEY};
+
+/// Metadata key the parsers that convert a document's format record the
+/// original content's hash under (a `DocumentVersion` string, e.g.
+/// `sha256:...`).
+pub const ORIGINAL_CONTENT_HASH_KEY: &str = "original_content_hash";
+
+/// Reading speed `markdown_metadata` estimates `reading_time_minutes` with.
+pub const READING_WORDS_PER_MINUTE: usize = 200;
+
+/// A Markdown heading and the section it opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownSection<'a> {
+    /// 1 to 6.
+    pub level: usize,
+    pub title: &'a str,
+    /// Text from the line after the heading up to the next heading.
+    pub body: &'a str,
+}
+
+/// Headings and their sections, in document order. Text before the first
+/// heading belongs to no section.
+pub fn markdown_sections(content: &str) -> Vec<MarkdownSection<'_>> {
+    let mut headings: Vec<(usize, &str, usize, usize)> = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            if let Some((level, title)) = parse_heading(line) {
+                headings.push((level, title, start, offset));
+            }
+        }
+    }
+
+    headings
+        .iter()
+        .enumerate()
+        .map(|(i, &(level, title, _, body_start))| {
+            let body_end = headings.get(i + 1).map_or(content.len(), |next| next.2);
+            MarkdownSection {
+                level,
+                title,
+                body: &content[body_start..body_end],
+            }
+        })
+        .collect()
+}
+
+/// One entry per section: the heading as `## Title`, then the first
+/// sentence of the section's first paragraph (outside code fences) on the
+/// next line, when there is one.
+pub fn markdown_outline(content: &str) -> Vec<String> {
+    markdown_sections(content)
+        .iter()
+        .map(|section| {
+            let heading = format!("{} {}", "#".repeat(section.level), section.title);
+            match first_sentence(section.body) {
+                Some(sentence) => format!("{}\n{}", heading, sentence),
+                None => heading,
+            }
+        })
+        .collect()
+}
+
+/// Metadata extracted from Markdown content:
+///
+/// - `title`: the first level-1 heading, when there is one;
+/// - `headings`: every heading as `## Title`, in document order;
+/// - `word_count`: whitespace-separated words in the whole content;
+/// - `reading_time_minutes`: `word_count` at `READING_WORDS_PER_MINUTE`,
+///   rounded up.
+pub fn markdown_metadata(content: &str) -> Metadata {
+    let sections = markdown_sections(content);
+    let words = content.split_whitespace().count();
+
+    let mut metadata = Metadata::new();
+    if let Some(title) = sections.iter().find(|section| section.level == 1) {
+        metadata.insert_string("title", title.title);
+    }
+    let headings = sections
+        .iter()
+        .map(|section| MetadataValue::String(format!("{} {}", "#".repeat(section.level), section.title)))
+        .collect();
+    metadata.insert("headings", MetadataValue::List(headings));
+    metadata.insert_number("word_count", words as i64);
+    metadata.insert_number("reading_time_minutes", words.div_ceil(READING_WORDS_PER_MINUTE) as i64);
+    metadata
+}
+
+/// Whether `id` names a Markdown file: it ends in `.md` or `.markdown`,
+/// in any case.
+pub fn is_markdown_path(id: &str) -> bool {
+    let id = id.to_ascii_lowercase();
+    id.ends_with(".md") || id.ends_with(".markdown")
+}
+
+/// Metadata key of the ids of the documents a Markdown document links to
+/// (see `markdown_links`).
+pub const LINKS_KEY: &str = "links";
+
+/// The documents that Markdown `content`, the content of document `id`,
+/// links to, as document ids in order of first appearance: the targets of
+/// inline links (`[text](target)`, not images) and link reference
+/// definitions (`[label]: target`) outside code fences.
+///
+/// Targets resolve against `id`'s directory, or against the root when they
+/// start with `/`, keeping their case (selection falls back to the
+/// lowercased target); `%XX` escapes are decoded and any `#fragment` or
+/// `?query` is dropped. URLs with a scheme (`https:`, `mailto:`), fragment-only
+/// links, links to `id` itself and targets above the root are skipped.
+pub fn markdown_links(id: &str, content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(target) = reference_target(line) {
+            targets.push(target);
+            continue;
+        }
+        let mut rest = line;
+        while let Some(pos) = rest.find("](") {
+            let before = &rest[..pos];
+            let image = before.rfind('[').is_some_and(|open| before[..open].ends_with('!'));
+            let after = &rest[pos + 2..];
+            let (target, end) = match after.strip_prefix('<') {
+                Some(angled) => (angled.split('>').next().unwrap_or(""), 0),
+                None => {
+                    let end = after.find([' ', ')']).unwrap_or(after.len());
+                    (&after[..end], end)
+                }
+            };
+            if !image {
+                targets.push(target);
+            }
+            rest = &after[end..];
+        }
+    }
+
+    let mut links: Vec<String> = Vec::new();
+    for target in targets {
+        if let Some(link) = resolve_link(id, target) {
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
+/// Record `markdown_links` under `LINKS_KEY`, when there are any.
+pub(crate) fn insert_links(metadata: &mut Metadata, id: &str, content: &str) {
+    let links = markdown_links(id, content);
+    if !links.is_empty() {
+        metadata.insert(LINKS_KEY, MetadataValue::List(links.into_iter().map(MetadataValue::String).collect()));
+    }
+}
+
+/// The target of a link reference definition line: up to 3 spaces of
+/// indent, `[label]:`, then the target. Footnotes (`[^1]:`) are not links.
+fn reference_target(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = line[indent..].strip_prefix('[')?;
+    if rest.starts_with('^') {
+        return None;
+    }
+    let (_, after) = rest.split_once("]:")?;
+    let target = after.split_whitespace().next()?;
+    Some(target.strip_prefix('<').and_then(|t| t.strip_suffix('>')).unwrap_or(target))
+}
+
+/// Link `target` in document `id`, as a document id.
+fn resolve_link(id: &str, target: &str) -> Option<String> {
+    let path = target.split(['#', '?']).next().unwrap_or("");
+    let scheme = path.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if path.is_empty() || scheme {
+        return None;
+    }
+
+    let path = percent_decode(path);
+    let mut parts: Vec<String> = match id.rsplit_once('/') {
+        Some((dir, _)) if !path.starts_with('/') => dir.split('/').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            segment => parts.push(segment.to_string()),
+        }
+    }
+    let resolved = parts.join("/");
+    (!resolved.is_empty() && resolved != id).then_some(resolved)
+}
+
+/// `text` with `%XX` escapes decoded (invalid UTF-8 replaced).
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Trimmed, non-empty sentences in document order. A sentence ends after
+/// `.`, `!` or `?` followed by whitespace, and at every line break.
+pub fn split_sentences(content: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = content.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = match c {
+            '\n' => Some(i),
+            '.' | '!' | '?' if chars.peek().map_or(true, |&(_, next)| next.is_whitespace()) => Some(i + c.len_utf8()),
+            _ => None,
+        };
+        if let Some(end) = end {
+            sentences.push(&content[start..end]);
+            start = end;
+        }
+    }
+    sentences.push(&content[start..]);
+    sentences.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+/// `(level, title)` of an ATX heading line: up to 3 spaces of indent,
+/// 1 to 6 `#`, then a space; a closing `#` sequence is dropped.
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let level = rest.len() - rest.trim_start_matches('#').len();
+    if !(1..=6).contains(&level) || !rest[level..].starts_with([' ', '\t']) {
+        return None;
+    }
+    let title = rest[level..].trim().trim_end_matches('#').trim_end();
+    (!title.is_empty()).then_some((level, title))
+}
+
+fn first_sentence(body: &str) -> Option<String> {
+    // The first paragraph outside code fences, as one line
+    let mut in_fence = false;
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            if !paragraph.is_empty() {
+                break;
+            }
+        } else if in_fence {
+            continue;
+        } else if trimmed.is_empty() {
+            if !paragraph.is_empty() {
+                break;
+            }
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    let paragraph = paragraph.join(" ");
+    split_sentences(&paragraph).first().map(|sentence| sentence.to_string())
+}
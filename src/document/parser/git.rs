@@ -0,0 +1,294 @@
+//! Ingestion of the files of a git commit, read from the object database
+//! rather than the working tree. Runs the `git` command-line tool.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, TimeZone, Utc};
+use thiserror::Error;
+
+use super::{insert_links, is_markdown_path, markdown_metadata};
+use crate::document::binary::{BinaryDetector, IngestedFile, IngestionReport, RejectedFile, Rejection};
+use crate::document::ignore::{IgnoreRules, IGNORE_FILES};
+use crate::document::{Document, DocumentError, DocumentId, IngestOptions, Metadata, Source};
+
+/// Metadata key of the full id of the commit a document was read from.
+pub const COMMIT_KEY: &str = "commit";
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("Failed to run git: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("git {command} failed: {stderr}")]
+    Command { command: String, stderr: String },
+    #[error("Unexpected git output: {0}")]
+    Output(String),
+}
+
+/// How `ingest_git` turns a commit's files into documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitIngestOptions {
+    /// Recorded as the `repo` of each document's `Source::Git`, such as the
+    /// repository's URL. Empty by default. The local path of the repository
+    /// is never recorded, so every clone builds the same documents.
+    pub repo: String,
+    /// How file bytes are read, as for `Document::ingest_parts`.
+    pub ingest: IngestOptions,
+    /// Heuristics for files not to ingest as text; rejected files are left
+    /// out and listed in the report.
+    pub binary_detection: Option<BinaryDetector>,
+    /// Skip what the commit's `.gitignore` and `.contextignore` files
+    /// exclude. On by default.
+    pub ignore_files: bool,
+    /// Record `markdown_metadata` and `links` (see `markdown_links`) for
+    /// Markdown files. On by default.
+    pub markdown_metadata: bool,
+}
+
+impl Default for GitIngestOptions {
+    fn default() -> Self {
+        Self {
+            repo: String::new(),
+            ingest: IngestOptions::default(),
+            binary_detection: Some(BinaryDetector::default()),
+            ignore_files: true,
+            markdown_metadata: true,
+        }
+    }
+}
+
+/// The documents of one commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitSnapshot {
+    /// The full id of the commit.
+    pub commit: String,
+    /// Its committer date: pass it to `CacheBuildConfig::with_fixed_timestamp`
+    /// for a cache that depends only on the commit.
+    pub committed_at: DateTime<Utc>,
+    /// In id order.
+    pub documents: Vec<Document>,
+    pub report: IngestionReport,
+}
+
+/// A file of the commit's tree.
+struct Entry {
+    path: String,
+    oid: String,
+}
+
+/// Ingest the files of `commit` (any revision `git rev-parse` accepts) in
+/// the repository at `repo_path`, as they are in the commit whatever the
+/// working tree holds.
+///
+/// Files whose path has a component starting with `.`, symbolic links
+/// and submodules are skipped. Each document's source is a `Source::Git`
+/// with the file's path, and its metadata records the commit under
+/// `COMMIT_KEY`. The documents depend only on the commit's content and
+/// `options`, so identical commits give identical documents.
+pub fn ingest_git(repo_path: &Path, commit: &str, options: &GitIngestOptions) -> Result<GitSnapshot, GitError> {
+    let revision = format!("{}^{{commit}}", commit);
+    let commit = text(git(repo_path, &["rev-parse", "--verify", "--end-of-options", &revision])?)?;
+    let timestamp = text(git(repo_path, &["log", "-1", "--format=%ct", &commit])?)?;
+    let committed_at = timestamp
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+        .ok_or_else(|| GitError::Output(timestamp.clone()))?;
+
+    let entries = tree_entries(repo_path, &commit)?;
+    let mut ignore = IgnoreRules::new();
+    if options.ignore_files {
+        // Per directory, in `IGNORE_FILES` order
+        let mut ignore_files: Vec<(&str, usize, &Entry)> = entries
+            .iter()
+            .filter_map(|entry| {
+                let (dir, name) = entry.path.rsplit_once('/').unwrap_or(("", &entry.path));
+                let rank = IGNORE_FILES.iter().position(|file| *file == name)?;
+                Some((dir, rank, entry))
+            })
+            .collect();
+        ignore_files.sort_by_key(|&(dir, rank, _)| (dir, rank));
+        let oids: Vec<&str> = ignore_files.iter().map(|(_, _, entry)| entry.oid.as_str()).collect();
+        for ((dir, _, _), blob) in ignore_files.iter().zip(read_blobs(repo_path, &oids)?) {
+            ignore.add(dir, &String::from_utf8_lossy(&blob));
+        }
+    }
+
+    let files: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| !entry.path.split('/').any(|part| part.starts_with('.')))
+        .filter(|entry| !ignore.is_ignored(&entry.path, false))
+        .collect();
+    let oids: Vec<&str> = files.iter().map(|entry| entry.oid.as_str()).collect();
+    let blobs = read_blobs(repo_path, &oids)?;
+
+    let mut documents = Vec::new();
+    let mut report = IngestionReport::default();
+    for (entry, blob) in files.into_iter().zip(blobs) {
+        let Ok(id) = DocumentId::from_path(Path::new(""), Path::new(&entry.path)) else {
+            continue;
+        };
+        let source = Source::git(options.repo.as_str(), commit.as_str(), entry.path.as_str());
+        let bytes = blob.len() as u64;
+        report.total_bytes += bytes;
+        match ingest_blob(id.clone(), source, blob, &commit, options) {
+            Ok(parts) => {
+                report.ingested.push(IngestedFile::new(id, bytes, &parts));
+                documents.extend(parts);
+            }
+            Err(reason) => report.skipped.push(RejectedFile { id, bytes, reason }),
+        }
+    }
+    documents.sort_by(|a, b| a.id.cmp(&b.id));
+    report.documents = documents.len();
+    report.ingested.sort_by(|a, b| a.id.cmp(&b.id));
+    report.skipped.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(GitSnapshot {
+        commit,
+        committed_at,
+        documents,
+        report,
+    })
+}
+
+/// The documents of one file, or why it was rejected.
+fn ingest_blob(
+    id: DocumentId,
+    source: Source,
+    blob: Vec<u8>,
+    commit: &str,
+    options: &GitIngestOptions,
+) -> Result<Vec<Document>, Rejection> {
+    let size = blob.len() as u64;
+    let detector = options.binary_detection;
+    if let Some(detector) = detector {
+        detector.check_size(size)?;
+    }
+    let ingest = options.ingest;
+    if let Some(limit) = ingest.max_bytes.filter(|&limit| size > limit && !ingest.split_oversize) {
+        return Err(Rejection::TooLarge { bytes: size, limit });
+    }
+
+    let unlimited = IngestOptions {
+        max_bytes: None,
+        ..ingest
+    };
+    let decoded = Document::ingest_with(id.clone(), source.clone(), blob, Metadata::default(), &unlimited)
+        .map_err(|_| Rejection::InvalidUtf8)?;
+    if let Some(detector) = detector {
+        detector.check_content(decoded.content.as_bytes())?;
+    }
+
+    let mut metadata = if options.markdown_metadata && is_markdown_path(id.as_str()) {
+        let mut metadata = markdown_metadata(&decoded.content);
+        insert_links(&mut metadata, id.as_str(), &decoded.content);
+        metadata
+    } else {
+        Metadata::default()
+    };
+    metadata.merge(decoded.metadata);
+    metadata.insert_string(COMMIT_KEY, commit);
+    // Already transcoded, so only a limit the text outgrew can fail
+    let options = IngestOptions {
+        transcode: false,
+        ..ingest
+    };
+    Document::ingest_parts(id, source, decoded.content.into_bytes(), metadata, &options).map_err(|e| match e {
+        DocumentError::TooLarge { size, limit } => Rejection::TooLarge { bytes: size, limit },
+        _ => Rejection::InvalidUtf8,
+    })
+}
+
+/// The regular files of `commit`'s tree, in tree order.
+fn tree_entries(repo_path: &Path, commit: &str) -> Result<Vec<Entry>, GitError> {
+    let listing = git(repo_path, &["ls-tree", "-r", "-z", "--full-tree", commit])?;
+    let mut entries = Vec::new();
+    for record in listing.split(|&b| b == 0).filter(|record| !record.is_empty()) {
+        // `<mode> <type> <oid>\t<path>`
+        let record = String::from_utf8_lossy(record);
+        let (info, path) = record
+            .split_once('\t')
+            .ok_or_else(|| GitError::Output(record.to_string()))?;
+        let mut fields = info.split(' ');
+        let (Some(mode), Some(kind), Some(oid)) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(GitError::Output(record.to_string()));
+        };
+        // Symbolic links are blobs too, holding their target
+        if kind != "blob" || mode == "120000" || path.contains('\u{fffd}') {
+            continue;
+        }
+        entries.push(Entry {
+            path: path.to_string(),
+            oid: oid.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// The contents of the blobs `oids`, in order.
+fn read_blobs(repo_path: &Path, oids: &[&str]) -> Result<Vec<Vec<u8>>, GitError> {
+    if oids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Written from another thread, so neither pipe fills up while git
+    // waits on the other
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input: String = oids.iter().map(|oid| format!("{}\n", oid)).collect();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut blobs = Vec::with_capacity(oids.len());
+    for _ in oids {
+        // `<oid> blob <size>`, the content, then a newline
+        let mut header = String::new();
+        stdout.read_line(&mut header)?;
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let size = match fields[..] {
+            [_, "blob", size] => size.parse::<usize>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| GitError::Output(header.trim_end().to_string()))?;
+        let mut blob = vec![0; size + 1];
+        stdout.read_exact(&mut blob)?;
+        blob.pop();
+        blobs.push(blob);
+    }
+    writer.join().expect("writer thread panicked")?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(GitError::Command {
+            command: "cat-file --batch".to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(blobs)
+}
+
+/// The standard output of `git -C repo_path args`.
+fn git(repo_path: &Path, args: &[&str]) -> Result<Vec<u8>, GitError> {
+    let output = Command::new("git").arg("-C").arg(repo_path).args(args).output()?;
+    if !output.status.success() {
+        return Err(GitError::Command {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// `output` as trimmed text.
+fn text(output: Vec<u8>) -> Result<String, GitError> {
+    String::from_utf8(output)
+        .map(|text| text.trim().to_string())
+        .map_err(|e| GitError::Output(e.to_string()))
+}
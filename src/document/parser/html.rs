@@ -0,0 +1,290 @@
+use std::borrow::Cow;
+
+use super::ORIGINAL_CONTENT_HASH_KEY;
+use crate::document::{Document, DocumentError, DocumentId, DocumentVersion, Metadata, Source};
+
+/// Elements dropped with everything inside them.
+const SKIPPED: &[&str] = &["head", "noscript", "script", "style", "svg", "template"];
+
+/// Elements that start and end a paragraph.
+const BLOCKS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure", "footer", "form",
+    "header", "hr", "main", "nav", "ol", "p", "section", "table", "tr", "ul",
+];
+
+/// Converts HTML into Markdown-ish plain text.
+///
+/// Tags are stripped and `head`, `script`, `style`, `noscript`, `svg` and
+/// `template` elements dropped with their contents. Headings become `#`
+/// lines, list items `- ` lines, `pre` blocks ``` fences, and block
+/// elements paragraphs; link text is kept, link targets are not. Common
+/// named and all numeric character references are decoded. Whitespace is
+/// collapsed outside `pre`. The same input always gives the same text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HtmlParser;
+
+impl HtmlParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Ingest HTML bytes as a document holding their text (see `to_text`).
+    /// The hash of the HTML is recorded in `metadata` under
+    /// `ORIGINAL_CONTENT_HASH_KEY`; the document's own version hashes the
+    /// text.
+    pub fn parse(
+        &self,
+        id: DocumentId,
+        source: impl Into<Source>,
+        raw_content: Vec<u8>,
+        mut metadata: Metadata,
+    ) -> Result<Document, DocumentError> {
+        let html = String::from_utf8(raw_content)?;
+        let original = DocumentVersion::from_content(html.as_bytes());
+        metadata.insert_string(ORIGINAL_CONTENT_HASH_KEY, original.as_str());
+        Document::ingest(id, source, self.to_text(&html).into_bytes(), metadata)
+    }
+
+    pub fn to_text(&self, html: &str) -> String {
+        let mut out = Writer::default();
+        let mut pre = 0usize;
+        let mut rest = html;
+
+        while let Some(lt) = rest.find('<') {
+            out.text(&decode(&rest[..lt]), pre > 0);
+            rest = &rest[lt..];
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+                continue;
+            }
+            let Some(tag) = Tag::parse(rest) else {
+                // A lone `<` is text
+                out.text("<", pre > 0);
+                rest = &rest[1..];
+                continue;
+            };
+            rest = &rest[tag.len..];
+            let name = tag.name.as_str();
+
+            if !tag.closing && !tag.self_closing && SKIPPED.contains(&name) {
+                // Up to and past the matching closing tag
+                let lower = rest.to_ascii_lowercase();
+                rest = match lower.find(&format!("</{}", name)) {
+                    Some(end) => rest[end..].find('>').map_or("", |gt| &rest[end + gt + 1..]),
+                    None => "",
+                };
+                continue;
+            }
+
+            match (name, tag.closing) {
+                ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                    out.block();
+                    out.raw(&"#".repeat(usize::from(name.as_bytes()[1] - b'0')));
+                    out.raw(" ");
+                }
+                ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => out.block(),
+                ("li", false) => {
+                    out.line();
+                    out.raw("- ");
+                }
+                ("br", _) => out.line(),
+                ("pre", false) => {
+                    out.block();
+                    out.raw("```\n");
+                    pre += 1;
+                }
+                ("pre", true) if pre > 0 => {
+                    pre -= 1;
+                    out.line();
+                    out.raw("```");
+                    out.block();
+                }
+                ("td" | "th", _) => out.space = true,
+                _ if BLOCKS.contains(&name) => out.block(),
+                _ => {}
+            }
+        }
+        out.text(&decode(rest), pre > 0);
+        out.finish()
+    }
+}
+
+struct Tag {
+    /// Lowercased.
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    /// Bytes up to and including the closing `>`.
+    len: usize,
+}
+
+impl Tag {
+    /// The tag `s` starts with; `None` when `<` does not open one.
+    fn parse(s: &str) -> Option<Tag> {
+        let body = &s[1..];
+        let closing = body.starts_with('/');
+        let body = if closing { &body[1..] } else { body };
+        let name_len = body
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(body.len());
+        if name_len == 0 || !body.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        // The first `>` outside a quoted attribute value
+        let mut quote = None;
+        let mut end = None;
+        for (i, c) in s.char_indices().skip(1) {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => {
+                    end = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let end = end?;
+        Some(Tag {
+            name: body[..name_len].to_ascii_lowercase(),
+            closing,
+            self_closing: s[..end].ends_with('/'),
+            len: end + 1,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    /// Whitespace is pending before the next word.
+    space: bool,
+}
+
+impl Writer {
+    fn text(&mut self, text: &str, verbatim: bool) {
+        if text.is_empty() {
+            return;
+        }
+        if verbatim {
+            // As in HTML, a newline right after `<pre>` is dropped
+            let text = if self.out.ends_with("```\n") {
+                text.strip_prefix('\n').unwrap_or(text)
+            } else {
+                text
+            };
+            self.raw(text);
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+        for word in text.split_whitespace() {
+            if self.space && !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+            self.space = true;
+        }
+        self.space = text.ends_with(char::is_whitespace);
+    }
+
+    fn raw(&mut self, text: &str) {
+        self.space = false;
+        self.out.push_str(text);
+    }
+
+    fn line(&mut self) {
+        self.space = false;
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn block(&mut self) {
+        self.line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Trailing spaces trimmed and blank-line runs collapsed, except inside
+    /// fences.
+    fn finish(self) -> String {
+        let mut lines: Vec<&str> = Vec::new();
+        let mut in_fence = false;
+        for line in self.out.lines() {
+            if line.starts_with("```") {
+                in_fence = !in_fence;
+            }
+            let line = if in_fence { line } else { line.trim_end() };
+            if !in_fence && line.is_empty() && lines.last().map_or(true, |last| last.is_empty()) {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines.last().is_some_and(|last| last.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+}
+
+/// `text` with character references decoded; unknown ones are kept as is.
+fn decode(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&semi| semi <= 10)
+            .and_then(|semi| Some((entity(&rest[1..semi + 1])?, semi + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "copy" => '©',
+        _ => return None,
+    })
+}
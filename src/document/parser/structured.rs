@@ -0,0 +1,540 @@
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::ORIGINAL_CONTENT_HASH_KEY;
+use crate::document::{Document, DocumentError, DocumentId, DocumentVersion, Metadata, Source};
+
+#[derive(Debug, Error)]
+pub enum StructuredError {
+    #[error("Content must be valid UTF-8")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+    #[error("No structured format for {0}")]
+    UnknownFormat(String),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid YAML at line {line}: {message}")]
+    Yaml { line: usize, message: String },
+    #[error("Invalid CSV at line {line}: {message}")]
+    Csv { line: usize, message: String },
+    #[error("Document error: {0}")]
+    Document(#[from] DocumentError),
+}
+
+/// A data file format `StructuredParser` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StructuredFormat {
+    Json,
+    /// Block-style YAML: mappings, sequences, plain and quoted scalars,
+    /// `|` / `>` block scalars, and flow collections of scalars. Anchors,
+    /// aliases and tags are read as plain text; multi-document streams
+    /// are not supported.
+    Yaml,
+    /// RFC 4180, with a header row naming the columns.
+    Csv,
+}
+
+impl StructuredFormat {
+    /// The format of `id` by extension (`.json`, `.yaml` / `.yml`, `.csv`,
+    /// in any case).
+    pub fn from_path(id: &str) -> Option<Self> {
+        let (_, extension) = id.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(StructuredFormat::Json),
+            "yaml" | "yml" => Some(StructuredFormat::Yaml),
+            "csv" => Some(StructuredFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Renders JSON, YAML and CSV as flattened key/value text.
+///
+/// Each scalar becomes one line: its key path, segments separated by
+/// spaces (array positions as `[0]`), then ` = ` and the value, so field
+/// names are words of their own for term matching. Object keys are sorted;
+/// CSV rows are an array of objects keyed by the header. Newlines in
+/// values are written as `\n`. Empty collections are written as `[]` and
+/// `{}`. With `max_rows` / `max_keys`, arrays and objects beyond the cap
+/// end in a `(N more items)` / `(N more keys)` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StructuredParser {
+    /// Elements rendered per array (including CSV rows).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rows: Option<usize>,
+    /// Keys rendered per object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_keys: Option<usize>,
+}
+
+impl StructuredParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    /// Ingest a data file as a document holding its flattened text, in the
+    /// format given by `id`'s extension. The hash of the original bytes is
+    /// recorded in `metadata` under `ORIGINAL_CONTENT_HASH_KEY`.
+    pub fn parse(
+        &self,
+        id: DocumentId,
+        source: impl Into<Source>,
+        raw_content: Vec<u8>,
+        mut metadata: Metadata,
+    ) -> Result<Document, StructuredError> {
+        let format = StructuredFormat::from_path(id.as_str())
+            .ok_or_else(|| StructuredError::UnknownFormat(id.as_str().to_string()))?;
+        let content = String::from_utf8(raw_content)?;
+        let text = self.to_text(format, &content)?;
+        let original = DocumentVersion::from_content(content.as_bytes());
+        metadata.insert_string(ORIGINAL_CONTENT_HASH_KEY, original.as_str());
+        Ok(Document::ingest(id, source, text.into_bytes(), metadata)?)
+    }
+
+    pub fn to_text(&self, format: StructuredFormat, content: &str) -> Result<String, StructuredError> {
+        let value = match format {
+            StructuredFormat::Json => serde_json::from_str(content)?,
+            StructuredFormat::Yaml => yaml::parse(content)?,
+            StructuredFormat::Csv => csv::parse(content)?,
+        };
+        let mut lines = Vec::new();
+        self.flatten(&value, &mut Vec::new(), &mut lines);
+        Ok(lines.join("\n"))
+    }
+
+    fn flatten(&self, value: &Value, path: &mut Vec<String>, lines: &mut Vec<String>) {
+        let line = |path: &[String], text: &str| {
+            if path.is_empty() {
+                text.to_string()
+            } else {
+                format!("{} {}", path.join(" "), text)
+            }
+        };
+        match value {
+            Value::Object(map) if map.is_empty() => lines.push(line(path, "= {}")),
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                let shown = keys.len().min(self.max_keys.unwrap_or(usize::MAX));
+                for key in &keys[..shown] {
+                    path.push(key.to_string());
+                    self.flatten(&map[key.as_str()], path, lines);
+                    path.pop();
+                }
+                if shown < keys.len() {
+                    lines.push(line(path, &format!("({} more keys)", keys.len() - shown)));
+                }
+            }
+            Value::Array(items) if items.is_empty() => lines.push(line(path, "= []")),
+            Value::Array(items) => {
+                let shown = items.len().min(self.max_rows.unwrap_or(usize::MAX));
+                for (i, item) in items[..shown].iter().enumerate() {
+                    path.push(format!("[{}]", i));
+                    self.flatten(item, path, lines);
+                    path.pop();
+                }
+                if shown < items.len() {
+                    lines.push(line(path, &format!("({} more items)", items.len() - shown)));
+                }
+            }
+            Value::String(s) => lines.push(line(path, &format!("= {}", s.replace('\n', "\\n")))),
+            scalar => lines.push(line(path, &format!("= {}", scalar))),
+        }
+    }
+}
+
+mod csv {
+    use super::*;
+
+    pub(super) fn parse(content: &str) -> Result<Value, StructuredError> {
+        let mut records = records(content)?.into_iter();
+        let Some((_, header)) = records.next() else {
+            return Ok(Value::Array(Vec::new()));
+        };
+        let mut rows = Vec::new();
+        for (line, record) in records {
+            if record.len() != header.len() {
+                return Err(StructuredError::Csv {
+                    line,
+                    message: format!("{} fields, the header has {}", record.len(), header.len()),
+                });
+            }
+            let row: Map<String, Value> = header.iter().cloned().zip(record.into_iter().map(Value::String)).collect();
+            rows.push(Value::Object(row));
+        }
+        Ok(Value::Array(rows))
+    }
+
+    /// Records with the line each starts on; blank lines are skipped.
+    fn records(content: &str) -> Result<Vec<(usize, Vec<String>)>, StructuredError> {
+        let mut records = Vec::new();
+        let mut chars = content.chars().peekable();
+        let mut line = 1;
+        while chars.peek().is_some() {
+            let start = line;
+            let mut record = Vec::new();
+            let mut field = String::new();
+            let mut quoted = false;
+            loop {
+                match chars.next() {
+                    Some('"') if quoted => {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                            field.push('"');
+                        } else {
+                            quoted = false;
+                        }
+                    }
+                    Some('"') if field.is_empty() => quoted = true,
+                    Some(',') if !quoted => record.push(std::mem::take(&mut field)),
+                    Some('\r') if !quoted && chars.peek() == Some(&'\n') => {}
+                    Some('\n') if !quoted => {
+                        line += 1;
+                        break;
+                    }
+                    Some(c) => {
+                        if c == '\n' {
+                            line += 1;
+                        }
+                        field.push(c);
+                    }
+                    None if quoted => {
+                        return Err(StructuredError::Csv {
+                            line: start,
+                            message: "unterminated quoted field".to_string(),
+                        })
+                    }
+                    None => break,
+                }
+            }
+            record.push(field);
+            if record.len() > 1 || !record[0].is_empty() {
+                records.push((start, record));
+            }
+        }
+        Ok(records)
+    }
+}
+
+mod yaml {
+    use super::*;
+
+    pub(super) fn parse(content: &str) -> Result<Value, StructuredError> {
+        let mut lines: Vec<Line> = content
+            .lines()
+            .enumerate()
+            .map(|(i, raw)| Line::new(i + 1, raw))
+            .collect();
+        // A leading document marker and a trailing end marker
+        if let Some(first) = lines.iter_mut().find(|line| !line.content.is_empty()) {
+            if first.indent == 0 && first.content == "---" {
+                first.content = "";
+            }
+        }
+        if let Some(end) = lines.iter().position(|line| line.indent == 0 && line.content == "...") {
+            lines.truncate(end);
+        }
+
+        let mut parser = Parser { lines, pos: 0 };
+        let Some(line) = parser.peek() else {
+            return Ok(Value::Null);
+        };
+        let value = parser.node(line.indent)?;
+        match parser.peek() {
+            Some(line) => Err(parser.error(line.number, "unexpected content")),
+            None => Ok(value),
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct Line<'a> {
+        number: usize,
+        indent: usize,
+        /// Without indentation, comment, or trailing whitespace; empty for
+        /// blank and comment lines.
+        content: &'a str,
+        raw: &'a str,
+    }
+
+    impl<'a> Line<'a> {
+        fn new(number: usize, raw: &'a str) -> Self {
+            let indent = raw.len() - raw.trim_start_matches(' ').len();
+            Line {
+                number,
+                indent,
+                content: strip_comment(&raw[indent..]).trim_end(),
+                raw,
+            }
+        }
+    }
+
+    struct Parser<'a> {
+        lines: Vec<Line<'a>>,
+        pos: usize,
+    }
+
+    impl<'a> Parser<'a> {
+        /// The next significant line.
+        fn peek(&mut self) -> Option<Line<'a>> {
+            while self.pos < self.lines.len() && self.lines[self.pos].content.is_empty() {
+                self.pos += 1;
+            }
+            self.lines.get(self.pos).copied()
+        }
+
+        fn error(&self, line: usize, message: &str) -> StructuredError {
+            StructuredError::Yaml {
+                line,
+                message: message.to_string(),
+            }
+        }
+
+        /// The node starting at the next significant line, at `indent`.
+        fn node(&mut self, indent: usize) -> Result<Value, StructuredError> {
+            let Some(line) = self.peek() else {
+                return Ok(Value::Null);
+            };
+            if is_item(line.content) {
+                self.sequence(indent)
+            } else if split_key(line.content).is_some() {
+                self.mapping(indent)
+            } else {
+                self.pos += 1;
+                scalar(line.content).map_err(|message| self.error(line.number, message))
+            }
+        }
+
+        fn mapping(&mut self, indent: usize) -> Result<Value, StructuredError> {
+            let mut map = Map::new();
+            while let Some(line) = self.peek() {
+                if line.indent < indent {
+                    break;
+                }
+                if line.indent > indent {
+                    return Err(self.error(line.number, "unexpected indentation"));
+                }
+                let Some((key, rest)) = split_key(line.content) else {
+                    return Err(self.error(line.number, "expected a `key: value` line"));
+                };
+                let key = unquote(key).map_err(|message| self.error(line.number, message))?;
+                if map.contains_key(&key) {
+                    return Err(self.error(line.number, "duplicate key"));
+                }
+                self.pos += 1;
+                let value = self.value(indent, rest, line.number)?;
+                map.insert(key, value);
+            }
+            Ok(Value::Object(map))
+        }
+
+        fn sequence(&mut self, indent: usize) -> Result<Value, StructuredError> {
+            let mut items = Vec::new();
+            while let Some(line) = self.peek() {
+                if line.indent < indent || (line.indent == indent && !is_item(line.content)) {
+                    break;
+                }
+                if line.indent > indent {
+                    return Err(self.error(line.number, "unexpected indentation"));
+                }
+                let item = line.content[1..].trim_start();
+                let item_indent = indent + (line.content.len() - item.len());
+                if split_key(item).is_some() || is_item(item) {
+                    // A nested node starting on the item's own line
+                    self.lines[self.pos] = Line {
+                        indent: item_indent,
+                        content: item,
+                        ..line
+                    };
+                    items.push(self.node(item_indent)?);
+                } else {
+                    self.pos += 1;
+                    items.push(self.value(indent, item, line.number)?);
+                }
+            }
+            Ok(Value::Array(items))
+        }
+
+        /// The value after `key:` or `-` on a line at `indent`: `rest`, a
+        /// block scalar, or a nested node on the following lines.
+        fn value(&mut self, indent: usize, rest: &str, number: usize) -> Result<Value, StructuredError> {
+            if rest.starts_with('|') || rest.starts_with('>') {
+                return Ok(Value::String(self.block_scalar(indent, rest.starts_with('>'))));
+            }
+            if !rest.is_empty() {
+                return scalar(rest).map_err(|message| self.error(number, message));
+            }
+            match self.peek() {
+                Some(next) if next.indent > indent => self.node(next.indent),
+                // A sequence may sit at its key's indentation
+                Some(next) if next.indent == indent && is_item(next.content) => self.sequence(indent),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        /// The lines indented past `indent`, with the first line's
+        /// indentation removed; folded lines are joined with spaces.
+        fn block_scalar(&mut self, indent: usize, folded: bool) -> String {
+            let start = self.pos;
+            while let Some(line) = self.lines.get(self.pos) {
+                if !line.raw.trim().is_empty() && line.indent <= indent {
+                    break;
+                }
+                self.pos += 1;
+            }
+            let block = &self.lines[start..self.pos];
+            let blank = |line: &Line| line.raw.trim().is_empty();
+            let strip = block.iter().find(|line| !blank(line)).map_or(0, |line| line.indent);
+            let mut body: Vec<&str> = block
+                .iter()
+                .map(|line| if blank(line) { "" } else { &line.raw[strip.min(line.indent)..] })
+                .collect();
+            while body.last() == Some(&"") {
+                body.pop();
+            }
+            body.join(if folded { " " } else { "\n" })
+        }
+    }
+
+    fn is_item(content: &str) -> bool {
+        content == "-" || content.starts_with("- ")
+    }
+
+    /// `(key, rest)` of a `key: rest` line; the colon must be outside
+    /// quotes and followed by a space or the line end.
+    fn split_key(content: &str) -> Option<(&str, &str)> {
+        if content.starts_with(['[', '{', '|', '>']) || is_item(content) {
+            return None;
+        }
+        let mut quote = None;
+        for (i, c) in content.char_indices() {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') if i == 0 => quote = Some(c),
+                (None, ':') => {
+                    let rest = &content[i + 1..];
+                    if rest.is_empty() || rest.starts_with(' ') {
+                        return Some((content[..i].trim_end(), rest.trim_start()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// `text` up to a `#` comment outside quotes.
+    fn strip_comment(text: &str) -> &str {
+        let mut quote = None;
+        let mut previous = ' ';
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') if previous == ' ' || previous == ':' || i == 0 => quote = Some(c),
+                (None, '#') if previous.is_whitespace() => return &text[..i],
+                _ => {}
+            }
+            previous = c;
+        }
+        text
+    }
+
+    fn scalar(text: &str) -> Result<Value, &'static str> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix('[') {
+            let inner = inner.strip_suffix(']').ok_or("unterminated flow sequence")?;
+            let items = split_flow(inner)?
+                .into_iter()
+                .map(|item| flow_scalar(item).map(Value::String))
+                .collect::<Result<_, _>>()?;
+            return Ok(Value::Array(items));
+        }
+        if let Some(inner) = text.strip_prefix('{') {
+            let inner = inner.strip_suffix('}').ok_or("unterminated flow mapping")?;
+            let mut map = Map::new();
+            for entry in split_flow(inner)? {
+                let (key, value) = split_key(entry).ok_or("expected `key: value` in flow mapping")?;
+                map.insert(unquote(key)?, Value::String(flow_scalar(value)?));
+            }
+            return Ok(Value::Object(map));
+        }
+        match text {
+            "" | "~" | "null" | "Null" | "NULL" => Ok(Value::Null),
+            _ => unquote(text).map(Value::String),
+        }
+    }
+
+    fn flow_scalar(text: &str) -> Result<String, &'static str> {
+        if text.starts_with(['[', '{']) {
+            return Err("nested flow collections are not supported");
+        }
+        unquote(text)
+    }
+
+    /// Comma-separated entries outside quotes; a trailing comma is allowed.
+    fn split_flow(inner: &str) -> Result<Vec<&str>, &'static str> {
+        let mut entries = Vec::new();
+        let mut quote = None;
+        let mut start = 0;
+        for (i, c) in inner.char_indices() {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, ',') => {
+                    entries.push(inner[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if quote.is_some() {
+            return Err("unterminated quoted scalar");
+        }
+        entries.push(inner[start..].trim());
+        if entries.last() == Some(&"") {
+            entries.pop();
+        }
+        Ok(entries)
+    }
+
+    /// A plain, `'single'` or `"double"` quoted scalar's text.
+    fn unquote(text: &str) -> Result<String, &'static str> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix('\'') {
+            let inner = inner.strip_suffix('\'').ok_or("unterminated quoted scalar")?;
+            return Ok(inner.replace("''", "'"));
+        }
+        if let Some(inner) = text.strip_prefix('"') {
+            let inner = inner.strip_suffix('"').ok_or("unterminated quoted scalar")?;
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('0') => out.push('\0'),
+                    Some(c @ ('"' | '\\' | '/')) => out.push(c),
+                    _ => return Err("unsupported escape in double-quoted scalar"),
+                }
+            }
+            return Ok(out);
+        }
+        Ok(text.to_string())
+    }
+}
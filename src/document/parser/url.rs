@@ -0,0 +1,243 @@
+//! Ingestion of fetched web resources, with fetched responses pinned on
+//! disk so builds can be repeated offline. Runs the `curl` command-line
+//! tool.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::document::{Document, DocumentError, DocumentId, IngestOptions, Metadata, Source};
+
+/// Metadata key of the fetched URL.
+pub const URL_KEY: &str = "url";
+/// Metadata key of the response's `ETag`, when it had one.
+pub const ETAG_KEY: &str = "etag";
+/// Metadata key of when the content was fetched (RFC 3339).
+pub const FETCHED_AT_KEY: &str = "fetched_at";
+
+#[derive(Debug, Error)]
+pub enum UrlError {
+    #[error("Not an http(s) URL: {0}")]
+    InvalidUrl(String),
+    #[error("{0} is not pinned and fetching is disabled")]
+    NotCached(String),
+    #[error("{url} returned HTTP {status}")]
+    Status { url: String, status: u16 },
+    #[error("curl failed: {0}")]
+    Fetch(String),
+    #[error("Unexpected response: {0}")]
+    Response(String),
+    #[error("Pin store error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid pin: {0}")]
+    Pin(#[from] serde_json::Error),
+    #[error(transparent)]
+    Document(#[from] DocumentError),
+}
+
+/// When `ingest_url` goes to the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchMode {
+    /// Always fetch, sending the pinned `ETag` (if any) so an unchanged
+    /// resource is not downloaded again.
+    #[default]
+    Revalidate,
+    /// Use the pinned response if there is one; fetch otherwise.
+    CacheFirst,
+    /// Use the pinned response only, failing with `UrlError::NotCached`
+    /// without one. Builds in this mode never touch the network.
+    Offline,
+}
+
+/// How `ingest_url` fetches and reads a resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlIngestOptions {
+    /// Directory the last response for each URL is pinned in. Without it,
+    /// nothing is pinned and `Offline` always fails.
+    pub pin_dir: Option<PathBuf>,
+    pub mode: FetchMode,
+    /// How the fetched bytes are read, as for `Document::ingest_with`.
+    pub ingest: IngestOptions,
+    /// Longest a fetch may take.
+    pub timeout: Duration,
+}
+
+impl Default for UrlIngestOptions {
+    fn default() -> Self {
+        Self {
+            pin_dir: None,
+            mode: FetchMode::default(),
+            ingest: IngestOptions::default(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A response pinned under `UrlIngestOptions::pin_dir`: `<key>.json` holds
+/// this, `<key>.body` the content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Pin {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    fetched_at: DateTime<Utc>,
+}
+
+/// A final HTTP response.
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) etag: Option<String>,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Fetch `url` (per `options.mode`) and ingest its content.
+///
+/// The document's id is the URL without its scheme, fragment and trailing
+/// `/` (`https://example.com/docs/` is `example.com/docs`), its source a
+/// `Source::Url`, and its metadata records the URL, `ETag` and fetch time
+/// under `URL_KEY`, `ETAG_KEY` and `FETCHED_AT_KEY`. Its version hashes the
+/// content, as always. A document built from a pinned response records
+/// when that response was fetched, so offline builds are deterministic.
+pub fn ingest_url(url: &str, options: &UrlIngestOptions) -> Result<Document, UrlError> {
+    let id = url_id(url).ok_or_else(|| UrlError::InvalidUrl(url.to_string()))?;
+    let pinned = match &options.pin_dir {
+        Some(dir) => read_pin(dir, url)?,
+        None => None,
+    };
+
+    let (pin, body) = match (options.mode, pinned) {
+        (FetchMode::Offline, None) => return Err(UrlError::NotCached(url.to_string())),
+        (FetchMode::Offline | FetchMode::CacheFirst, Some(pinned)) => pinned,
+        (_, pinned) => {
+            let etag = pinned.as_ref().and_then(|(pin, _)| pin.etag.as_deref());
+            let response = fetch(url, etag, options.timeout)?;
+            let fetched_at = Utc::now();
+            let (pin, body) = match (response.status, pinned) {
+                // Unchanged since it was pinned
+                (304, Some((pin, body))) => (Pin { fetched_at, ..pin }, body),
+                (200..=299, _) => {
+                    let pin = Pin {
+                        url: url.to_string(),
+                        etag: response.etag,
+                        fetched_at,
+                    };
+                    (pin, response.body)
+                }
+                (status, _) => {
+                    return Err(UrlError::Status {
+                        url: url.to_string(),
+                        status,
+                    })
+                }
+            };
+            if let Some(dir) = &options.pin_dir {
+                write_pin(dir, &pin, &body)?;
+            }
+            (pin, body)
+        }
+    };
+
+    let mut metadata = Metadata::default();
+    metadata.insert_string(URL_KEY, url);
+    if let Some(etag) = &pin.etag {
+        metadata.insert_string(ETAG_KEY, etag.as_str());
+    }
+    metadata.insert_string(FETCHED_AT_KEY, pin.fetched_at.to_rfc3339());
+    let source = Source::url(url, pin.fetched_at);
+    Ok(Document::ingest_with(id, source, body, metadata, &options.ingest)?)
+}
+
+/// The document id of `url`; `None` unless it is http(s) with a host.
+fn url_id(url: &str) -> Option<DocumentId> {
+    let lower = url.to_ascii_lowercase();
+    let scheme = ["https://", "http://"].into_iter().find(|scheme| lower.starts_with(scheme))?;
+    let rest = &url[scheme.len()..];
+    let rest = rest.split_once('#').map_or(rest, |(rest, _)| rest).trim_end_matches('/');
+    if rest.is_empty() || rest.starts_with('/') {
+        return None;
+    }
+    DocumentId::from_path(Path::new(""), Path::new(rest)).ok()
+}
+
+/// The pin files of `url` under `dir`, without extension.
+fn pin_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(hex::encode(Sha256::digest(url.as_bytes())))
+}
+
+fn read_pin(dir: &Path, url: &str) -> Result<Option<(Pin, Vec<u8>)>, UrlError> {
+    let path = pin_path(dir, url);
+    let meta = match fs::read(path.with_extension("json")) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let pin: Pin = serde_json::from_slice(&meta)?;
+    let body = fs::read(path.with_extension("body"))?;
+    Ok(Some((pin, body)))
+}
+
+/// Pin `body`; the metadata is written last, so a pin is never read with
+/// a partly written body.
+fn write_pin(dir: &Path, pin: &Pin, body: &[u8]) -> Result<(), UrlError> {
+    fs::create_dir_all(dir)?;
+    let path = pin_path(dir, &pin.url);
+    let _ = fs::remove_file(path.with_extension("json"));
+    fs::write(path.with_extension("body"), body)?;
+    fs::write(path.with_extension("json"), serde_json::to_vec_pretty(pin)?)?;
+    Ok(())
+}
+
+/// GET `url`, following redirects, conditionally on `etag`.
+pub(crate) fn fetch(url: &str, etag: Option<&str>, timeout: Duration) -> Result<Response, UrlError> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--location", "--include"])
+        .args(["--proto", "=http,https", "--proto-redir", "=http,https"])
+        .arg("--max-time")
+        .arg(timeout.as_secs_f64().to_string());
+    if let Some(etag) = etag {
+        command.arg("--header").arg(format!("If-None-Match: {}", etag));
+    }
+    let output = command.arg("--url").arg(url).output()?;
+    if !output.status.success() {
+        return Err(UrlError::Fetch(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    parse_response(&output.stdout).ok_or_else(|| UrlError::Response(url.to_string()))
+}
+
+/// The last response in curl `--include` output, which holds the headers
+/// of every response (informational ones and redirects first).
+fn parse_response(mut raw: &[u8]) -> Option<Response> {
+    loop {
+        let end = raw.windows(4).position(|w| w == b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&raw[..end]);
+        let body = &raw[end + 4..];
+        let mut lines = head.split("\r\n");
+        let status_line = lines.next()?;
+        let status: u16 = status_line.split(' ').nth(1)?.parse().ok()?;
+        let interim = (100..200).contains(&status)
+            || (300..400).contains(&status)
+            || status_line.to_ascii_lowercase().contains("connection established");
+        if interim && body.starts_with(b"HTTP/") {
+            raw = body;
+            continue;
+        }
+        let etag = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("etag"))
+            .map(|(_, value)| value.trim().to_string());
+        return Some(Response {
+            status,
+            etag,
+            body: body.to_vec(),
+        });
+    }
+}
@@ -0,0 +1,75 @@
+//! Where a document's content came from.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The origin of a document, precise enough to go back to the exact bytes
+/// that were ingested.
+///
+/// Typed origins serialize as objects tagged by `kind`
+/// (`{"kind":"git","repo":...,"commit":...,"path":...}`); `Text` serializes
+/// as a plain string, which is also how caches written before sources were
+/// typed read back.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Source {
+    /// A file under a local ingestion root; `rel` is `/`-separated.
+    LocalPath { root: String, rel: String },
+    /// A fetched resource, with when it was fetched.
+    Url { url: String, fetched_at: DateTime<Utc> },
+    /// A file in a git repository at a commit.
+    Git { repo: String, commit: String, path: String },
+    /// A free-form description.
+    #[serde(untagged)]
+    Text(String),
+}
+
+impl Source {
+    /// A file under `root`, at `rel` relative to it.
+    pub fn local_path(root: impl Into<String>, rel: impl Into<String>) -> Self {
+        Source::LocalPath {
+            root: root.into(),
+            rel: rel.into(),
+        }
+    }
+
+    pub fn url(url: impl Into<String>, fetched_at: DateTime<Utc>) -> Self {
+        Source::Url {
+            url: url.into(),
+            fetched_at,
+        }
+    }
+
+    pub fn git(repo: impl Into<String>, commit: impl Into<String>, path: impl Into<String>) -> Self {
+        Source::Git {
+            repo: repo.into(),
+            commit: commit.into(),
+            path: path.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Source {
+    /// `root/rel`, the URL, `repo@commit:path`, or the text.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::LocalPath { root, rel } if root.is_empty() => f.write_str(rel),
+            Source::LocalPath { root, rel } => write!(f, "{}/{}", root.trim_end_matches('/'), rel),
+            Source::Url { url, .. } => f.write_str(url),
+            Source::Git { repo, commit, path } => write!(f, "{}@{}:{}", repo, commit, path),
+            Source::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+impl From<String> for Source {
+    fn from(text: String) -> Self {
+        Source::Text(text)
+    }
+}
+
+impl From<&str> for Source {
+    fn from(text: &str) -> Self {
+        Source::Text(text.to_string())
+    }
+}
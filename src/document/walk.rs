@@ -0,0 +1,443 @@
+//! Directory ingestion over an `IngestSource`: the local filesystem, or an
+//! in-memory `MemoryFs` for hermetic tests and targets without one.
+
+use std::collections::BTreeMap;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+use std::path::Path;
+
+use thiserror::Error;
+
+use super::binary::{self, BinaryDetector, BinaryPolicy, IngestedFile, IngestionReport, RejectedFile, Rejection};
+use super::ignore::{IgnoreRules, IGNORE_FILES};
+use super::metadata::MetadataValue;
+use super::parser::{self, StructuredFormat, StructuredParser};
+use super::{chunk, Document, DocumentId, IngestOptions, Metadata, Source};
+
+#[derive(Debug, Error)]
+pub enum WalkError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    /// A file was rejected under `BinaryPolicy::Error`.
+    #[error("Rejected {}: {reason}", id.as_str())]
+    Rejected { id: DocumentId, reason: Rejection },
+}
+
+/// An entry of an `IngestSource` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// A tree of files to ingest. Paths are relative to its root,
+/// `/`-separated, with `""` for the root itself.
+pub trait IngestSource {
+    /// The entries directly under directory `dir`, in any order.
+    fn read_dir(&self, dir: &str) -> io::Result<Vec<SourceEntry>>;
+
+    /// The bytes of file `path`; `NotFound` when there is none.
+    fn read(&self, path: &str) -> io::Result<Vec<u8>>;
+
+    /// What the documents of file `path` record as their source.
+    fn source(&self, path: &str) -> Source;
+}
+
+/// Files held in memory, by path. Directories are implied by the paths of
+/// the files under them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryFs {
+    root: String,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `root` in documents' `Source::LocalPath`, so they match those
+    /// ingested from a directory there. Empty by default.
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    pub fn with_file(mut self, path: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, bytes);
+        self
+    }
+
+    /// Add or replace file `path`; leading and trailing `/` are dropped.
+    pub fn insert(&mut self, path: impl Into<String>, bytes: impl Into<Vec<u8>>) {
+        let path: String = path.into();
+        self.files.insert(path.trim_matches('/').to_string(), bytes.into());
+    }
+
+    pub fn remove(&mut self, path: &str) -> Option<Vec<u8>> {
+        self.files.remove(path.trim_matches('/'))
+    }
+}
+
+impl IngestSource for MemoryFs {
+    fn read_dir(&self, dir: &str) -> io::Result<Vec<SourceEntry>> {
+        let prefix = match dir.trim_matches('/') {
+            "" => String::new(),
+            dir => format!("{}/", dir),
+        };
+        let mut entries: Vec<SourceEntry> = Vec::new();
+        for path in self.files.keys() {
+            let Some(rest) = path.strip_prefix(&prefix) else {
+                continue;
+            };
+            let entry = match rest.split_once('/') {
+                Some((name, _)) => SourceEntry {
+                    name: name.to_string(),
+                    is_dir: true,
+                },
+                None => SourceEntry {
+                    name: rest.to_string(),
+                    is_dir: false,
+                },
+            };
+            // Paths are sorted, so a directory's files are adjacent
+            if entries.last() != Some(&entry) {
+                entries.push(entry);
+            }
+        }
+        if entries.is_empty() && !prefix.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, dir.to_string()));
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        self.files
+            .get(path.trim_matches('/'))
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.to_string()))
+    }
+
+    fn source(&self, path: &str) -> Source {
+        Source::local_path(self.root.as_str(), path)
+    }
+}
+
+/// A directory on the local filesystem.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalFs {
+    root: PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl LocalFs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl IngestSource for LocalFs {
+    fn read_dir(&self, dir: &str) -> io::Result<Vec<SourceEntry>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(self.root.join(dir))? {
+            let entry = entry?;
+            // Names that are not UTF-8 cannot become document ids
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            let path = entry.path();
+            if path.is_dir() || path.is_file() {
+                entries.push(SourceEntry {
+                    name,
+                    is_dir: path.is_dir(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+
+    fn source(&self, path: &str) -> Source {
+        Source::local_path(self.root.to_string_lossy(), path)
+    }
+}
+
+/// How `ingest_tree` turns files into documents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WalkOptions {
+    /// Record `markdown_metadata` (title, headings, word count, reading
+    /// time) and the `links` to other documents (see
+    /// `parser::markdown_links`) for Markdown files. On by default.
+    pub markdown_metadata: bool,
+    /// Ingest JSON, YAML and CSV files as flattened key/value text (see
+    /// `StructuredParser`); files that fail to parse keep their raw text.
+    /// On by default.
+    pub structured_data: Option<StructuredParser>,
+    /// Record the definitions in source files as a `symbols` list (see
+    /// `code_symbols`), for `symbol:` queries. On by default.
+    pub code_symbols: bool,
+    /// Skip what `.gitignore` and `.contextignore` files exclude (see
+    /// `IgnoreRules`). On by default.
+    pub ignore_files: bool,
+    /// Heuristics for files not to ingest as text (see `BinaryDetector`).
+    /// Data files `structured_data` parses are only held to its size limit.
+    /// With `None`, only files that are not UTF-8 are rejected.
+    pub binary_detection: Option<BinaryDetector>,
+    /// What to do with rejected files. `Skip` by default.
+    pub binary_policy: BinaryPolicy,
+    /// How file bytes are read; with `transcode`, Latin-1 and UTF-16 files
+    /// are converted to UTF-8 before any other step. A file over
+    /// `max_bytes` is rejected as `TooLarge` (see `binary_policy`), or with
+    /// `split_oversize` ingested as parts (see `Document::ingest_parts`);
+    /// structured files are not parsed then. Strict UTF-8 and no limit by
+    /// default.
+    pub ingest: IngestOptions,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            markdown_metadata: true,
+            structured_data: Some(StructuredParser::new()),
+            code_symbols: true,
+            ignore_files: true,
+            binary_detection: Some(BinaryDetector::new()),
+            binary_policy: BinaryPolicy::Skip,
+            ingest: IngestOptions::default(),
+        }
+    }
+}
+
+/// The documents of a tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestedTree {
+    /// In id order.
+    pub documents: Vec<Document>,
+    pub report: IngestionReport,
+}
+
+/// Ingest every file of `source`. Entries whose name starts with `.` are
+/// skipped, as are those ignore files exclude when `ignore_files` is set.
+/// Document ids keep the paths' case (see
+/// `DocumentId::from_path_case_sensitive`). The result depends only on the
+/// files and `options`, whatever order `source` lists them in.
+pub fn ingest_tree(source: &dyn IngestSource, options: &WalkOptions) -> Result<IngestedTree, WalkError> {
+    let mut state = IngestState::default();
+    let mut ignore = IgnoreRules::new();
+    walk_dir(source, options, "", &mut ignore, &mut state)?;
+    Ok(IngestedTree {
+        report: state.report(options.binary_policy),
+        documents: state.documents.into_values().collect(),
+    })
+}
+
+fn walk_dir(
+    source: &dyn IngestSource,
+    options: &WalkOptions,
+    dir: &str,
+    ignore: &mut IgnoreRules,
+    state: &mut IngestState,
+) -> Result<(), WalkError> {
+    let join = |name: &str| match dir {
+        "" => name.to_string(),
+        dir => format!("{}/{}", dir, name),
+    };
+    if options.ignore_files {
+        for name in IGNORE_FILES {
+            match source.read(&join(name)) {
+                Ok(bytes) => ignore.add(dir, &String::from_utf8_lossy(&bytes)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    let mut entries = source.read_dir(dir)?;
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    for entry in entries {
+        let path = join(&entry.name);
+        if entry.name.starts_with('.') || ignore.is_ignored(&path, entry.is_dir) {
+            continue;
+        }
+        if entry.is_dir {
+            walk_dir(source, options, &path, ignore, state)?;
+            continue;
+        }
+        let Ok(id) = DocumentId::from_path_case_sensitive(Path::new(""), Path::new(&path)) else {
+            continue;
+        };
+        let bytes = match source.read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        state.add(options, id, source.source(&path), bytes)?;
+    }
+    Ok(())
+}
+
+/// Documents ingested so far, and the outcome of each file.
+#[derive(Debug, Default)]
+pub(crate) struct IngestState {
+    pub(crate) documents: BTreeMap<DocumentId, Document>,
+    /// Files rejected by `binary_detection` (or as not UTF-8), skipped or
+    /// stubbed.
+    rejected: BTreeMap<DocumentId, RejectedFile>,
+    /// Files ingested as text, by the id of the file (not of its parts).
+    ingested: BTreeMap<DocumentId, IngestedFile>,
+}
+
+impl IngestState {
+    /// Ingest file `id` as `options` say. Files `binary_detection`
+    /// rejects, and files that are not valid UTF-8, are handled by
+    /// `binary_policy`.
+    pub(crate) fn add(
+        &mut self,
+        options: &WalkOptions,
+        id: DocumentId,
+        source: Source,
+        bytes: Vec<u8>,
+    ) -> Result<(), WalkError> {
+        let size = bytes.len() as u64;
+        match ingest_file(options, &id, &source, bytes.clone()) {
+            Ok(documents) if documents.is_empty() => {}
+            Ok(documents) => {
+                self.ingested.insert(id.clone(), IngestedFile::new(id, size, &documents));
+                for doc in documents {
+                    self.documents.insert(doc.id.clone(), doc);
+                }
+            }
+            Err(reason) => {
+                match options.binary_policy {
+                    BinaryPolicy::Error => return Err(WalkError::Rejected { id, reason }),
+                    BinaryPolicy::Skip => {}
+                    BinaryPolicy::Stub => {
+                        let doc = binary::stub(id.clone(), source, &bytes, &reason, Metadata::default());
+                        self.documents.insert(id.clone(), doc);
+                    }
+                }
+                let file = RejectedFile {
+                    id: id.clone(),
+                    bytes: size,
+                    reason,
+                };
+                self.rejected.insert(id, file);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget file or directory `id` and everything below it.
+    #[cfg_attr(not(feature = "watch"), allow(dead_code))]
+    pub(crate) fn remove_under(&mut self, id: &DocumentId) {
+        // The root's id is empty, and everything is below it
+        let prefix = match id.as_str() {
+            "" => String::new(),
+            id => format!("{}/", id),
+        };
+        let keep = |doc_id: &DocumentId| doc_id != id && !doc_id.as_str().starts_with(&prefix);
+        self.documents.retain(|doc_id, _| keep(doc_id));
+        self.rejected.retain(|doc_id, _| keep(doc_id));
+        self.ingested.retain(|doc_id, _| keep(doc_id));
+    }
+
+    pub(crate) fn report(&self, policy: BinaryPolicy) -> IngestionReport {
+        let rejected = self.rejected.values().cloned().collect();
+        let (skipped, stubbed) = match policy {
+            BinaryPolicy::Stub => (Vec::new(), rejected),
+            _ => (rejected, Vec::new()),
+        };
+        let total_bytes = self.ingested.values().map(|file| file.bytes).sum::<u64>()
+            + self.rejected.values().map(|file| file.bytes).sum::<u64>();
+        IngestionReport {
+            documents: self.documents.len(),
+            total_bytes,
+            ingested: self.ingested.values().cloned().collect(),
+            skipped,
+            stubbed,
+        }
+    }
+}
+
+/// The documents of one file, or why it was rejected. No documents when
+/// it could not be ingested otherwise.
+fn ingest_file(
+    options: &WalkOptions,
+    id: &DocumentId,
+    source: &Source,
+    bytes: Vec<u8>,
+) -> Result<Vec<Document>, Rejection> {
+    let detector = options.binary_detection;
+    if let Some(detector) = detector {
+        detector.check_size(bytes.len() as u64)?;
+    }
+    let ingest = options.ingest;
+    let size = bytes.len() as u64;
+    // Over `max_bytes`, the file is either rejected or split into parts
+    let oversize = ingest.max_bytes.filter(|&limit| size > limit);
+    if let (Some(limit), false) = (oversize, ingest.split_oversize) {
+        return Err(Rejection::TooLarge { bytes: size, limit });
+    }
+    // Transcoding records the original encoding in `base`
+    let raw = bytes;
+    let unlimited = IngestOptions {
+        max_bytes: None,
+        ..ingest
+    };
+    let decoded = ingest
+        .transcode
+        .then(|| Document::ingest_with(id.clone(), source.clone(), raw.clone(), Metadata::default(), &unlimited))
+        .and_then(Result::ok);
+    let (bytes, base) = match decoded {
+        Some(decoded) => (decoded.content.into_bytes(), decoded.metadata),
+        None => (raw, Metadata::default()),
+    };
+    let structured = options
+        .structured_data
+        .filter(|_| oversize.is_none() && StructuredFormat::from_path(id.as_str()).is_some());
+    if let Some(parser) = structured {
+        if let Ok(mut doc) = parser.parse(id.clone(), source.clone(), bytes.clone(), Metadata::default()) {
+            doc.metadata.merge(base);
+            return Ok(vec![doc]);
+        }
+    }
+    match detector {
+        Some(detector) => detector.check_content(&bytes)?,
+        None => {
+            std::str::from_utf8(&bytes).map_err(|_| Rejection::InvalidUtf8)?;
+        }
+    }
+    let mut metadata = match std::str::from_utf8(&bytes) {
+        Ok(content) if options.markdown_metadata && parser::is_markdown_path(id.as_str()) => {
+            let mut metadata = parser::markdown_metadata(content);
+            parser::insert_links(&mut metadata, id.as_str(), content);
+            metadata
+        }
+        Ok(content) if options.code_symbols && chunk::is_code_path(id.as_str()) => {
+            let mut metadata = Metadata::default();
+            let symbols: Vec<MetadataValue> =
+                chunk::code_symbols(content).into_iter().map(MetadataValue::String).collect();
+            if !symbols.is_empty() {
+                metadata.insert("symbols", MetadataValue::List(symbols));
+            }
+            metadata
+        }
+        _ => Metadata::default(),
+    };
+    metadata.merge(base);
+    if oversize.is_some() {
+        // Already transcoded
+        let options = IngestOptions {
+            transcode: false,
+            ..ingest
+        };
+        return Ok(Document::ingest_parts(id.clone(), source.clone(), bytes, metadata, &options).unwrap_or_default());
+    }
+    Ok(Document::ingest(id.clone(), source.clone(), bytes, metadata).map_or_else(|_| Vec::new(), |doc| vec![doc]))
+}
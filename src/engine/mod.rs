@@ -0,0 +1,5 @@
+//! Long-running services built on the cache layer.
+
+pub mod watch;
+
+pub use watch::{CacheUpdated, WatchConfig, WatchError, Watcher};
@@ -0,0 +1,338 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+use thiserror::Error;
+
+use crate::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder, ContextCache};
+use crate::document::binary::{IngestionReport, Rejection};
+use crate::document::ignore::{IgnoreRules, IGNORE_FILES};
+use crate::document::walk::{IngestState, WalkError, WalkOptions};
+use crate::document::{DocumentId, Source};
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("Watch error: {0}")]
+    Notify(#[from] notify::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Build error: {0}")]
+    Build(#[from] CacheBuildError),
+    /// A file was rejected under `BinaryPolicy::Error`.
+    #[error("Rejected {}: {reason}", id.as_str())]
+    Rejected { id: DocumentId, reason: Rejection },
+}
+
+impl From<WalkError> for WatchError {
+    fn from(error: WalkError) -> Self {
+        match error {
+            WalkError::Io(e) => WatchError::Io(e),
+            WalkError::Rejected { id, reason } => WatchError::Rejected { id, reason },
+        }
+    }
+}
+
+/// A new cache generation was built after a change under the watched root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheUpdated {
+    pub old_version: String,
+    pub new_version: String,
+    /// Directory of the new generation.
+    pub path: PathBuf,
+    /// The files in the new generation and those rejected.
+    pub report: IngestionReport,
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Source tree to ingest. Entries whose name starts with `.` are skipped,
+    /// as are those excluded by ignore files when `walk.ignore_files` is set.
+    /// Documents record it and their path below it as a
+    /// `Source::LocalPath`.
+    pub root: PathBuf,
+    /// Parent of the generation directories (`000001`, `000002`, ...).
+    /// Ignored by the watcher if it lies under `root`.
+    pub generations_dir: PathBuf,
+    /// Documents are ingested with their paths' case
+    /// (`DocumentId::from_path_case_sensitive`); unless
+    /// `case_sensitive_ids` is set, the build lowercases them and fails on
+    /// paths that differ only in case.
+    pub build_config: CacheBuildConfig,
+    /// Quiet period after the last change before rebuilding.
+    pub debounce: Duration,
+    /// How files are ingested, as by `ingest_tree`: Markdown metadata,
+    /// structured data and code symbols are extracted, ignore files honored
+    /// and binary files skipped by default (see `WalkOptions`).
+    pub walk: WalkOptions,
+}
+
+impl WatchConfig {
+    pub fn new(root: impl Into<PathBuf>, generations_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            generations_dir: generations_dir.into(),
+            build_config: CacheBuildConfig::v0(),
+            debounce: Duration::from_millis(200),
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// Keeps a cache in step with a directory tree.
+///
+/// `start` ingests the whole root and builds the first generation. After
+/// that, only changed paths are re-ingested; each batch of changes (after
+/// `debounce`) is built into a new generation directory and announced on
+/// `events`. Batches that leave the cache version unchanged produce no
+/// generation. Old generations are left in place for readers still using
+/// them. Dropping the `Watcher` stops watching.
+pub struct Watcher {
+    initial: ContextCache,
+    report: IngestionReport,
+    events: Receiver<Result<CacheUpdated, WatchError>>,
+    watcher: Option<RecommendedWatcher>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    pub fn start(config: WatchConfig) -> Result<Self, WatchError> {
+        fs::create_dir_all(&config.generations_dir)?;
+        let config = WatchConfig {
+            root: config.root.canonicalize()?,
+            generations_dir: config.generations_dir.canonicalize()?,
+            ..config
+        };
+
+        let mut tree = Tree {
+            state: IngestState::default(),
+            ignore: IgnoreRules::new(),
+            generation: 0,
+            version: String::new(),
+            config,
+        };
+        let root = tree.config.root.clone();
+        tree.ingest_dir(&root)?;
+        let initial = tree.build()?;
+        let report = tree.report();
+        tree.version = initial.manifest.cache_version.clone();
+
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(raw_tx)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        let (events_tx, events) = mpsc::channel();
+        let worker = thread::spawn(move || tree.run(raw_rx, events_tx));
+
+        Ok(Self {
+            initial,
+            report,
+            events,
+            watcher: Some(watcher),
+            worker: Some(worker),
+        })
+    }
+
+    /// The generation built by `start`.
+    pub fn initial(&self) -> &ContextCache {
+        &self.initial
+    }
+
+    /// What `start` ingested and rejected.
+    pub fn report(&self) -> &IngestionReport {
+        &self.report
+    }
+
+    /// One item per rebuilt generation, or per failed watch/rebuild.
+    pub fn events(&self) -> &Receiver<Result<CacheUpdated, WatchError>> {
+        &self.events
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        // Dropping the notify watcher closes the worker's input channel
+        self.watcher.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Worker-side state: the ingested documents and the latest generation.
+struct Tree {
+    config: WatchConfig,
+    state: IngestState,
+    /// Rules of the ignore files seen, kept empty unless `walk.ignore_files`.
+    ignore: IgnoreRules,
+    generation: u64,
+    version: String,
+}
+
+type RawEvent = notify::Result<notify::Event>;
+
+impl Tree {
+    fn run(mut self, raw: Receiver<RawEvent>, events: Sender<Result<CacheUpdated, WatchError>>) {
+        while let Ok(first) = raw.recv() {
+            let mut batch = vec![first];
+            loop {
+                match raw.recv_timeout(self.config.debounce) {
+                    Ok(event) => batch.push(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let mut paths = BTreeSet::new();
+            for event in batch {
+                match event {
+                    Ok(event) => {
+                        for path in event.paths {
+                            // A changed ignore file re-ingests its directory
+                            match path.parent().filter(|_| self.is_ignore_file(&path)) {
+                                Some(dir) => {
+                                    paths.insert(dir.to_path_buf());
+                                }
+                                None if self.is_source(&path) => {
+                                    paths.insert(path);
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        if events.send(Err(e.into())).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            if paths.is_empty() {
+                continue;
+            }
+
+            let outcome = self.apply(&paths).transpose();
+            if let Some(outcome) = outcome {
+                if events.send(outcome).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Re-ingest `paths` and build a new generation if the version changed.
+    fn apply(&mut self, paths: &BTreeSet<PathBuf>) -> Result<Option<CacheUpdated>, WatchError> {
+        for path in paths {
+            self.remove_under(path);
+            if path.is_dir() {
+                self.ingest_dir(path)?;
+            } else if path.is_file() {
+                self.ingest_file(path)?;
+            }
+        }
+
+        let cache = self.build()?;
+        let new_version = cache.manifest.cache_version.clone();
+        if new_version == self.version {
+            fs::remove_dir_all(&cache.root)?;
+            self.generation -= 1;
+            return Ok(None);
+        }
+
+        let old_version = std::mem::replace(&mut self.version, new_version.clone());
+        Ok(Some(CacheUpdated {
+            old_version,
+            new_version,
+            path: cache.root,
+            report: self.report(),
+        }))
+    }
+
+    /// Build the current documents into the next free generation directory.
+    fn build(&mut self) -> Result<ContextCache, WatchError> {
+        let mut output = self.next_generation();
+        while output.exists() {
+            output = self.next_generation();
+        }
+        let documents = self.state.documents.values().cloned().collect();
+        Ok(CacheBuilder::new(self.config.build_config.clone()).build(documents, &output)?)
+    }
+
+    fn report(&self) -> IngestionReport {
+        self.state.report(self.config.walk.binary_policy)
+    }
+
+    fn next_generation(&mut self) -> PathBuf {
+        self.generation += 1;
+        self.config.generations_dir.join(format!("{:06}", self.generation))
+    }
+
+    /// Hidden entries, ignored entries and the generations directory are not
+    /// source files.
+    fn is_source(&self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.config.root) else {
+            return false;
+        };
+        !path.starts_with(&self.config.generations_dir)
+            && !rel
+                .components()
+                .any(|c| c.as_os_str().to_str().map_or(true, |s| s.starts_with('.')))
+            && !self.ignore.is_ignored_path(&self.config.root, path)
+    }
+
+    /// An ignore file in the root or a source directory.
+    fn is_ignore_file(&self, path: &Path) -> bool {
+        self.config.walk.ignore_files
+            && path
+                .parent()
+                .is_some_and(|dir| dir == self.config.root || self.is_source(dir))
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| IGNORE_FILES.contains(&name))
+    }
+
+    fn ingest_dir(&mut self, dir: &Path) -> Result<(), WatchError> {
+        if self.config.walk.ignore_files {
+            self.ignore.load_dir(&self.config.root, dir)?;
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !self.is_source(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                self.ingest_dir(&path)?;
+            } else if path.is_file() {
+                self.ingest_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Files `walk.binary_detection` rejects, and files that are not valid
+    /// UTF-8, are handled by `walk.binary_policy`.
+    fn ingest_file(&mut self, path: &Path) -> Result<(), WatchError> {
+        let Ok(id) = DocumentId::from_path_case_sensitive(&self.config.root, path) else {
+            return Ok(());
+        };
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            // Removed between the event and now; the next event handles it
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let source = Source::local_path(self.config.root.to_string_lossy(), id.as_str());
+        Ok(self.state.add(&self.config.walk, id, source, bytes)?)
+    }
+
+    /// Forget the document at `path` and everything below it.
+    fn remove_under(&mut self, path: &Path) {
+        if let Ok(id) = DocumentId::from_path_case_sensitive(&self.config.root, path) {
+            self.state.remove_under(&id);
+        }
+    }
+}
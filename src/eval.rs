@@ -0,0 +1,157 @@
+//! Offline evaluation of a selector against saved requests.
+//!
+//! An eval file is JSONL, one `EvalCase` per line:
+//!
+//! ```text
+//! {"name":"deploy","request":{"query":"deploy","budget":{"total":4000}},"expected":["docs/deploy.md"]}
+//! ```
+//!
+//! `run` replays every request against a cache and scores the selected ids
+//! against `expected` (binary relevance):
+//!
+//! - precision: expected ids selected / ids selected (0.0 when nothing was
+//!   selected);
+//! - recall: expected ids selected / expected ids (1.0 when nothing is
+//!   expected);
+//! - NDCG: `sum(1 / log2(rank + 2))` over expected ids at their selected
+//!   rank (from 0), divided by the same sum for all expected ids ranked
+//!   first (1.0 when nothing is expected).
+//!
+//! Metrics are rounded to 6 decimal places, so reports are byte-identical
+//! across runs and platforms.
+
+use std::collections::BTreeSet;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use crate::cache::DocumentLoader;
+use crate::selection::{ContextSelector, Scorer, SelectionRequest, TokenCounter};
+use crate::types::context_bundle::SelectionError;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EvalCase {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub request: SelectionRequest,
+    /// Relevant document ids; order does not matter.
+    pub expected: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CaseReport {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Selected ids, in result order.
+    pub selected: Vec<String>,
+    pub precision: f64,
+    pub recall: f64,
+    pub ndcg: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EvalReport {
+    /// One report per case, in case order.
+    pub cases: Vec<CaseReport>,
+    /// Means over all cases; 0.0 when there are none.
+    pub precision: f64,
+    pub recall: f64,
+    pub ndcg: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid eval case on line {line}: {source}")]
+    Parse { line: usize, source: serde_json::Error },
+
+    #[error("Case {case} failed: {source}")]
+    Selection { case: usize, source: SelectionError },
+}
+
+/// Parse JSONL eval cases. Blank lines are skipped; `line` in errors is
+/// 1-based.
+pub fn parse_cases(jsonl: &str) -> Result<Vec<EvalCase>, EvalError> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|source| EvalError::Parse { line: i + 1, source }))
+        .collect()
+}
+
+#[cfg(feature = "fs")]
+pub fn load_cases(path: impl AsRef<Path>) -> Result<Vec<EvalCase>, EvalError> {
+    parse_cases(&std::fs::read_to_string(path)?)
+}
+
+/// Run every case with `selector` against `cache`. `case` in errors is the
+/// 0-based case index.
+pub fn run<S: Scorer, T: TokenCounter>(
+    selector: &ContextSelector<S, T>,
+    cache: &(impl DocumentLoader + ?Sized),
+    cases: &[EvalCase],
+) -> Result<EvalReport, EvalError> {
+    let cases = cases
+        .iter()
+        .enumerate()
+        .map(|(i, case)| {
+            let result = selector
+                .select_request(cache, &case.request)
+                .map_err(|source| EvalError::Selection { case: i, source })?;
+            let selected: Vec<String> = result.documents.into_iter().map(|doc| doc.id).collect();
+            Ok(score_case(case, selected))
+        })
+        .collect::<Result<Vec<_>, EvalError>>()?;
+
+    let mean = |metric: fn(&CaseReport) -> f64| {
+        if cases.is_empty() {
+            0.0
+        } else {
+            round6(cases.iter().map(metric).sum::<f64>() / cases.len() as f64)
+        }
+    };
+    Ok(EvalReport {
+        precision: mean(|c| c.precision),
+        recall: mean(|c| c.recall),
+        ndcg: mean(|c| c.ndcg),
+        cases,
+    })
+}
+
+fn score_case(case: &EvalCase, selected: Vec<String>) -> CaseReport {
+    let expected: BTreeSet<&str> = case.expected.iter().map(String::as_str).collect();
+    let hits: Vec<usize> = selected
+        .iter()
+        .enumerate()
+        .filter(|(_, id)| expected.contains(id.as_str()))
+        .map(|(rank, _)| rank)
+        .collect();
+
+    let precision = if selected.is_empty() {
+        0.0
+    } else {
+        hits.len() as f64 / selected.len() as f64
+    };
+    let (recall, ndcg) = if expected.is_empty() {
+        (1.0, 1.0)
+    } else {
+        let gain = |rank: usize| 1.0 / ((rank + 2) as f64).log2();
+        let dcg: f64 = hits.iter().map(|&rank| gain(rank)).sum();
+        let ideal: f64 = (0..expected.len()).map(gain).sum();
+        (hits.len() as f64 / expected.len() as f64, dcg / ideal)
+    };
+
+    CaseReport {
+        name: case.name.clone(),
+        selected,
+        precision: round6(precision),
+        recall: round6(recall),
+        ndcg: round6(ndcg),
+    }
+}
+
+fn round6(value: f64) -> f64 {
+    (value * 1_000_000.0).round() / 1_000_000.0
+}
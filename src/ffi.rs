@@ -0,0 +1,172 @@
+//! C ABI for hosts that embed the engine (Node, Go, Python, ...).
+//!
+//! Build a shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! ```c
+//! typedef struct ContextCache ContextCache;
+//! typedef struct { uint8_t *ptr; size_t len; } ContextCoreBuffer;
+//!
+//! ContextCache *context_core_cache_open(const char *path, ContextCoreBuffer *error);
+//! void context_core_cache_free(ContextCache *cache);
+//! int32_t context_core_select(const ContextCache *cache, const char *query,
+//!                             size_t budget, ContextCoreBuffer *out);
+//! void context_core_buffer_free(ContextCoreBuffer buffer);
+//! ```
+//!
+//! Every buffer handed out is UTF-8 (JSON on success, an error message on
+//! failure), owned by the caller, and must be released with
+//! `context_core_buffer_free`. Panics never cross the boundary.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::cache::ContextCache;
+use crate::selection::ContextSelector;
+use crate::types::{Query, SelectionError};
+
+/// Success.
+pub const CONTEXT_CORE_OK: i32 = 0;
+/// A pointer was null or a string was not UTF-8.
+pub const CONTEXT_CORE_INVALID_ARGUMENT: i32 = 1;
+/// The cache could not be read or failed verification.
+pub const CONTEXT_CORE_CACHE_ERROR: i32 = 2;
+/// Selection failed, or the engine panicked.
+pub const CONTEXT_CORE_SELECTION_ERROR: i32 = 3;
+
+/// A byte buffer owned by the caller once returned.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ContextCoreBuffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+impl ContextCoreBuffer {
+    fn empty() -> Self {
+        Self {
+            ptr: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn from_string(s: String) -> Self {
+        let bytes = s.into_bytes().into_boxed_slice();
+        let len = bytes.len();
+        Self {
+            ptr: Box::into_raw(bytes) as *mut u8,
+            len,
+        }
+    }
+}
+
+/// Write `value` to `out` if `out` is non-null.
+unsafe fn set(out: *mut ContextCoreBuffer, value: ContextCoreBuffer) {
+    if let Some(out) = out.as_mut() {
+        *out = value;
+    } else {
+        context_core_buffer_free(value);
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Open a cache directory. Returns null on failure and, if `error` is
+/// non-null, stores the error message in it.
+///
+/// # Safety
+///
+/// `path` must be null or a valid NUL-terminated string; `error` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn context_core_cache_open(
+    path: *const c_char,
+    error: *mut ContextCoreBuffer,
+) -> *mut ContextCache {
+    let Some(path) = read_str(path) else {
+        set(error, ContextCoreBuffer::from_string("path is null or not UTF-8".into()));
+        return ptr::null_mut();
+    };
+    match catch_unwind(|| ContextCache::open(path)) {
+        Ok(Ok(cache)) => {
+            set(error, ContextCoreBuffer::empty());
+            Box::into_raw(Box::new(cache))
+        }
+        Ok(Err(e)) => {
+            set(error, ContextCoreBuffer::from_string(e.to_string()));
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set(error, ContextCoreBuffer::from_string("panic while opening cache".into()));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a cache returned by `context_core_cache_open`. Null is ignored.
+///
+/// # Safety
+///
+/// `cache` must be null or a pointer from `context_core_cache_open` that
+/// has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn context_core_cache_free(cache: *mut ContextCache) {
+    if !cache.is_null() {
+        drop(Box::from_raw(cache));
+    }
+}
+
+/// Run the default selector. On `CONTEXT_CORE_OK`, `out` holds the
+/// `SelectionResult` JSON; otherwise it holds an error message.
+///
+/// # Safety
+///
+/// `cache` must be null or a live pointer from `context_core_cache_open`;
+/// `query` must be null or a valid NUL-terminated string; `out` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn context_core_select(
+    cache: *const ContextCache,
+    query: *const c_char,
+    budget: usize,
+    out: *mut ContextCoreBuffer,
+) -> i32 {
+    let (Some(cache), Some(query)) = (cache.as_ref(), read_str(query)) else {
+        set(out, ContextCoreBuffer::from_string("cache or query is null, or query is not UTF-8".into()));
+        return CONTEXT_CORE_INVALID_ARGUMENT;
+    };
+
+    let outcome = catch_unwind(AssertUnwindSafe(|| {
+        ContextSelector::default().select(cache, Query::new(query), budget as u64)
+    }));
+    let (status, message) = match outcome {
+        Ok(Ok(result)) => match serde_json::to_string(&result) {
+            Ok(json) => (CONTEXT_CORE_OK, json),
+            Err(e) => (CONTEXT_CORE_SELECTION_ERROR, e.to_string()),
+        },
+        Ok(Err(e @ SelectionError::CacheError(_))) => (CONTEXT_CORE_CACHE_ERROR, e.to_string()),
+        Ok(Err(e)) => (CONTEXT_CORE_SELECTION_ERROR, e.to_string()),
+        Err(_) => (CONTEXT_CORE_SELECTION_ERROR, "panic during selection".to_string()),
+    };
+    set(out, ContextCoreBuffer::from_string(message));
+    status
+}
+
+/// Release a buffer returned through any out-parameter. Empty buffers are
+/// ignored.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn context_core_buffer_free(buffer: ContextCoreBuffer) {
+    if !buffer.ptr.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.ptr, buffer.len)));
+    }
+}
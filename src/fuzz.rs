@@ -0,0 +1,194 @@
+//! `Arbitrary` implementations for fuzzing integrations.
+//!
+//! Generated values hold the invariants the crate's constructors enforce:
+//! document ids are normalized lowercase relative paths, documents are
+//! built with `Document::ingest` (so content is UTF-8 and `version` is its
+//! hash), queries are parsed by `Query::new`, metadata floats are finite,
+//! and budgets pass `BudgetConfig::validate` with `DEFAULT_MAX_BUDGET`.
+//! Text is drawn mostly from a small vocabulary, so generated queries match
+//! generated documents.
+//!
+//! `cache_from_seed` materializes the same cache for the same seed on every
+//! platform, for reproducible failures without keeping the fuzzer's input.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use chrono::DateTime;
+
+use crate::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder, MemoryCache};
+use crate::document::metadata::MetadataValue;
+use crate::document::{Document, DocumentId, Metadata};
+use crate::selection::{BudgetConfig, BudgetStrategy, BudgetUnit, DEFAULT_MAX_BUDGET};
+use crate::types::Query;
+
+const WORDS: &[&str] = &[
+    "deploy", "rollback", "cache", "query", "budget", "token", "alpha", "beta", "service", "config", "error", "index",
+];
+const SEGMENT_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-_";
+const EXTENSIONS: &[&str] = &["md", "txt", "rs", "json"];
+const METADATA_KEYS: &[&str] = &["title", "tags", "author", "draft", "priority"];
+
+/// Bytes of generator input behind a seeded cache.
+const SEED_BYTES: usize = 16 * 1024;
+
+/// A vocabulary word, or one time in eight arbitrary text without
+/// whitespace or quotes.
+fn word(u: &mut Unstructured) -> Result<String> {
+    if u.ratio(1, 8)? {
+        let text: String = u.arbitrary()?;
+        let text: String = text.chars().filter(|c| !c.is_whitespace() && *c != '"').take(16).collect();
+        if !text.is_empty() {
+            return Ok(text);
+        }
+    }
+    Ok(u.choose(WORDS)?.to_string())
+}
+
+fn words(u: &mut Unstructured, max: usize) -> Result<Vec<String>> {
+    let count = u.int_in_range(0..=max)?;
+    (0..count).map(|_| word(u)).collect()
+}
+
+fn segment(u: &mut Unstructured) -> Result<String> {
+    let len = u.int_in_range(1..=8)?;
+    (0..len).map(|_| Ok(char::from(*u.choose(SEGMENT_CHARS)?))).collect()
+}
+
+impl<'a> Arbitrary<'a> for DocumentId {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let depth = u.int_in_range(1..=3)?;
+        let parts = (0..depth).map(|_| segment(u)).collect::<Result<Vec<_>>>()?;
+        let rel = format!("{}.{}", parts.join("/"), u.choose(EXTENSIONS)?);
+        let root = Path::new("/");
+        Ok(DocumentId::from_path(root, &root.join(rel)).expect("generated paths are relative UTF-8"))
+    }
+}
+
+/// A value other than a list or map.
+fn scalar(u: &mut Unstructured) -> Result<MetadataValue> {
+    Ok(match u.int_in_range(0..=3)? {
+        0 => MetadataValue::Bool(u.arbitrary()?),
+        1 => MetadataValue::Number(u.arbitrary()?),
+        2 => MetadataValue::Float(f64::from(u.arbitrary::<i32>()?) / 8.0),
+        _ => MetadataValue::String(words(u, 4)?.join(" ")),
+    })
+}
+
+impl<'a> Arbitrary<'a> for MetadataValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=5)? {
+            4 => MetadataValue::List((0..u.int_in_range(0..=3)?).map(|_| scalar(u)).collect::<Result<_>>()?),
+            5 => MetadataValue::Map(
+                (0..u.int_in_range(0..=2)?)
+                    .map(|_| Ok((word(u)?, scalar(u)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            _ => scalar(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Metadata {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut metadata = Metadata::new();
+        for _ in 0..u.int_in_range(0..=3)? {
+            let key = *u.choose(METADATA_KEYS)?;
+            let value = match key {
+                "title" => MetadataValue::String(words(u, 4)?.join(" ")),
+                "tags" => MetadataValue::List(words(u, 3)?.into_iter().map(MetadataValue::String).collect()),
+                _ => u.arbitrary()?,
+            };
+            metadata.insert(key, value);
+        }
+        Ok(metadata)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Document {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let id: DocumentId = u.arbitrary()?;
+        let mut content = String::new();
+        for (i, word) in words(u, 64)?.into_iter().enumerate() {
+            if i > 0 {
+                content.push(if u.ratio(1, 10)? { '\n' } else { ' ' });
+            }
+            content.push_str(&word);
+        }
+        let metadata = u.arbitrary()?;
+        Ok(Document::ingest(id.clone(), id.as_str().to_string(), content.into_bytes(), metadata)
+            .expect("generated content is UTF-8"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Query {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut parts = words(u, 4)?;
+        if u.ratio(1, 4)? {
+            parts.push(format!("\"{} {}\"", word(u)?, word(u)?));
+        }
+        if u.ratio(1, 4)? {
+            parts.push(match u.int_in_range(0..=1)? {
+                0 => format!("path:{}/**", segment(u)?),
+                _ => format!("tag:{}", u.choose(WORDS)?),
+            });
+        }
+        Ok(Query::new(parts.join(" ")))
+    }
+}
+
+impl<'a> Arbitrary<'a> for BudgetConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // Mostly prompt-sized budgets, sometimes up to the maximum
+        let max = if u.ratio(3, 4)? { 4096 } else { DEFAULT_MAX_BUDGET };
+        let total = u.int_in_range(1..=max)?;
+        let reserved = if u.ratio(1, 4)? { u.int_in_range(0..=total)? } else { 0 };
+        Ok(BudgetConfig::new(total)
+            .reserve(reserved)
+            .per_document_overhead(u.int_in_range(0..=16)?)
+            .unit(*u.choose(&[BudgetUnit::Tokens, BudgetUnit::Chars, BudgetUnit::Bytes, BudgetUnit::Words])?)
+            .strategy(*u.choose(&[BudgetStrategy::Greedy, BudgetStrategy::StopAtFirstExclusion])?))
+    }
+}
+
+/// Documents with distinct ids, as a cache build requires; the first
+/// generated for an id is kept. In id order.
+pub fn arbitrary_documents(u: &mut Unstructured) -> Result<Vec<Document>> {
+    let mut by_id = BTreeMap::new();
+    for document in u.arbitrary_iter::<Document>()? {
+        let document = document?;
+        by_id.entry(document.id.clone()).or_insert(document);
+    }
+    Ok(by_id.into_values().collect())
+}
+
+/// The documents `cache_from_seed` builds from.
+pub fn documents_from_seed(seed: u64) -> Vec<Document> {
+    let bytes = seed_bytes(seed, SEED_BYTES);
+    // Unstructured falls back to defaults rather than failing once its
+    // input runs out, and every choice above is from a non-empty list
+    arbitrary_documents(&mut Unstructured::new(&bytes)).expect("generation from seed bytes cannot fail")
+}
+
+/// An in-memory cache of `documents_from_seed(seed)`, built with a fixed
+/// timestamp so the same seed gives the same `cache_version`.
+pub fn cache_from_seed(seed: u64) -> std::result::Result<MemoryCache, CacheBuildError> {
+    CacheBuilder::new(CacheBuildConfig::v0().with_fixed_timestamp(DateTime::UNIX_EPOCH))
+        .build_in_memory(documents_from_seed(seed))
+}
+
+/// `len` bytes of splitmix64 output, the same on every platform.
+fn seed_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    let mut bytes = Vec::with_capacity(len + 8);
+    while bytes.len() < len {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        bytes.extend_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+    bytes.truncate(len);
+    bytes
+}
@@ -8,7 +8,18 @@
 //! See <https://github.com/contextenginehq/context-engine> for the full platform.
 
 pub mod cache;
+pub mod compat;
 pub mod compression;
 pub mod document;
+#[cfg(feature = "watch")]
+pub mod engine;
+pub mod eval;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod metrics;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod render;
+pub mod report;
 pub mod selection;
 pub mod types;
@@ -0,0 +1,71 @@
+//! Hooks for exporting what selections and builds do, without depending on
+//! an exporter: implement `Metrics` over a Prometheus registry or an
+//! OpenTelemetry meter and pass it to `ContextSelector::with_metrics` and
+//! `CacheBuilder::with_metrics`.
+//!
+//! Metric names are stable. Durations are recorded only with the `clock`
+//! feature, as reading a clock panics on `wasm32-unknown-unknown`.
+
+use std::sync::Arc;
+
+/// Counter: candidates scored by a selection, after filtering.
+pub const DOCUMENTS_SCORED: &str = "documents_scored";
+/// Counter: budget units a selection spent (`SelectionMetadata::tokens_used`).
+pub const TOKENS_SELECTED: &str = "tokens_selected";
+/// Histogram: share of the available budget a selection spent, from 0 to 1.
+/// Not recorded when nothing was available (a reservation of the whole
+/// budget).
+pub const BUDGET_UTILIZATION: &str = "budget_utilization";
+/// Histogram: milliseconds a selection spent loading documents from its
+/// cache.
+pub const CACHE_LOAD_MS: &str = "cache_load_ms";
+/// Counter: documents in caches built.
+pub const DOCUMENTS_BUILT: &str = "documents_built";
+/// Histogram: milliseconds a successful build took.
+pub const CACHE_BUILD_MS: &str = "cache_build_ms";
+
+/// Receives metrics as they are recorded, on the selecting or building
+/// thread, so calls should return quickly. Both methods do nothing by
+/// default.
+pub trait Metrics: Send + Sync {
+    /// Add `value` to the counter `name`.
+    fn counter(&self, _name: &'static str, _value: u64) {}
+
+    /// Record `value` in the histogram `name`.
+    fn histogram(&self, _name: &'static str, _value: f64) {}
+}
+
+/// Share one sink between a selector and a builder.
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+    fn counter(&self, name: &'static str, value: u64) {
+        (**self).counter(name, value);
+    }
+
+    fn histogram(&self, name: &'static str, value: f64) {
+        (**self).histogram(name, value);
+    }
+}
+
+/// Measures a duration for a `_MS` histogram; a no-op without the `clock`
+/// feature.
+pub(crate) struct Timer {
+    #[cfg(feature = "clock")]
+    start: std::time::Instant,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "clock")]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Record the milliseconds since `start` in `name`.
+    pub(crate) fn record(self, metrics: &dyn Metrics, name: &'static str) {
+        #[cfg(feature = "clock")]
+        metrics.histogram(name, self.start.elapsed().as_secs_f64() * 1000.0);
+        #[cfg(not(feature = "clock"))]
+        let _ = (metrics, name);
+    }
+}
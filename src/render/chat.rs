@@ -0,0 +1,65 @@
+use crate::render::xml::write_document;
+use crate::types::context_bundle::SelectionResult;
+
+/// Role of a chat message, serialized as OpenAI-compatible APIs expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+/// How `to_chat_messages` lays selected documents out across messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoleStrategy {
+    /// One system message holding every document.
+    #[default]
+    System,
+    /// One user message holding every document.
+    User,
+    /// One user message per document.
+    UserPerDocument,
+}
+
+/// Turn a selection into chat messages, documents in result order.
+///
+/// Documents use the `XmlRenderer` `<document>` element; a message holding
+/// every document wraps them in `<documents>`. No messages are produced
+/// when nothing was selected.
+pub fn to_chat_messages(result: &SelectionResult, strategy: RoleStrategy) -> Vec<ChatMessage> {
+    if result.documents.is_empty() {
+        return Vec::new();
+    }
+
+    let combined = |role| {
+        let mut content = String::from("<documents>\n");
+        for doc in &result.documents {
+            write_document(&mut content, doc);
+        }
+        content.push_str("</documents>\n");
+        vec![ChatMessage { role, content }]
+    };
+
+    match strategy {
+        RoleStrategy::System => combined(ChatRole::System),
+        RoleStrategy::User => combined(ChatRole::User),
+        RoleStrategy::UserPerDocument => result
+            .documents
+            .iter()
+            .map(|doc| {
+                let mut content = String::new();
+                write_document(&mut content, doc);
+                ChatMessage {
+                    role: ChatRole::User,
+                    content,
+                }
+            })
+            .collect(),
+    }
+}
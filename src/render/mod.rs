@@ -0,0 +1,15 @@
+pub mod chat;
+pub mod xml;
+
+pub use chat::{to_chat_messages, ChatMessage, ChatRole, RoleStrategy};
+pub use xml::XmlRenderer;
+
+use crate::types::context_bundle::SelectionResult;
+
+/// Turns a `SelectionResult` into prompt-ready text.
+///
+/// Implementations must be byte-deterministic: the same result always
+/// renders to the same string.
+pub trait Renderer {
+    fn render(&self, result: &SelectionResult) -> String;
+}
@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use crate::render::Renderer;
+use crate::types::context_bundle::{SelectedDocument, SelectionResult};
+
+/// Renders selected documents as XML-delimited blocks.
+///
+/// Output layout (one element per line, content escaped):
+///
+/// ```text
+/// <documents>
+/// <document id="docs/a.md" version="sha256:..." score="0.75" tokens="10">
+/// ...content...
+/// </document>
+/// </documents>
+/// <selection query="..." budget="4000" tokens_used="10" ... />
+/// ```
+///
+/// A document that holds only part of its source (`span`) also gets a
+/// `lines="start-end"` attribute.
+///
+/// Documents appear in result order; the trailing `<selection>` element
+/// carries the selection metadata.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlRenderer;
+
+impl Renderer for XmlRenderer {
+    fn render(&self, result: &SelectionResult) -> String {
+        let mut out = String::new();
+
+        out.push_str("<documents>\n");
+        for doc in &result.documents {
+            write_document(&mut out, doc);
+        }
+        out.push_str("</documents>\n");
+
+        let meta = &result.selection;
+        let _ = writeln!(
+            out,
+            "<selection query=\"{}\" budget=\"{}\" tokens_used=\"{}\" documents_considered=\"{}\" documents_selected=\"{}\" documents_excluded_by_budget=\"{}\" />",
+            escape(&meta.query),
+            meta.budget,
+            meta.tokens_used,
+            meta.documents_considered,
+            meta.documents_selected,
+            meta.documents_excluded_by_budget,
+        );
+
+        out
+    }
+}
+
+/// One `<document>` element, newline-terminated.
+pub(crate) fn write_document(out: &mut String, doc: &SelectedDocument) {
+    // Writing to a String never fails
+    let _ = write!(
+        out,
+        "<document id=\"{}\" version=\"{}\" score=\"{}\" tokens=\"{}\"",
+        escape(&doc.id),
+        escape(&doc.version),
+        doc.score,
+        doc.tokens,
+    );
+    if let Some(span) = &doc.span {
+        let _ = write!(out, " lines=\"{}-{}\"", span.start_line, span.end_line);
+    }
+    out.push_str(">\n");
+    out.push_str(&escape(&doc.content));
+    if !doc.content.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str("</document>\n");
+}
+
+/// Escape the five XML special characters.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,197 @@
+//! Human-readable reports of a selection, for reviewing and tuning scorers.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::render::xml::escape;
+use crate::types::context_bundle::{
+    round_score, BudgetVerdict, FilterVerdict, Representation, ScoreTrace, SelectedDocument, SelectionResult, SelectionTrace,
+    SCORE_DECIMALS,
+};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}\
+td.num{text-align:right;font-variant-numeric:tabular-nums}\
+tr.selected{background:#eef8ee}\
+.bar{width:40em;height:1.2em;border:1px solid #888;background:#f4f4f4}\
+.bar div{height:100%;background:#4a8}\
+details{margin:.5em 0;border:1px solid #ddd;padding:.4em .8em}\
+pre{background:#f6f6f6;padding:.5em;overflow:auto}";
+
+/// Render `result` and its `trace` as a standalone HTML page.
+///
+/// The page has a summary with a budget utilization bar, the ranked table
+/// (every ranked document with its raw score, boost, final score, tokens,
+/// and budget verdict), a "why" panel per selected document, and the
+/// documents excluded before scoring. It uses no scripts or external
+/// resources, and the same inputs always render to the same bytes.
+pub fn render_html(result: &SelectionResult, trace: &SelectionTrace) -> String {
+    let meta = &result.selection;
+    let mut out = String::new();
+
+    // Writing to a String never fails
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>Selection report: {}</title>", escape(&meta.query));
+    let _ = writeln!(out, "<style>{}</style>", STYLE);
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>Selection report: {}</h1>", escape(&meta.query));
+
+    // Summary and budget utilization
+    let available = meta.budget.saturating_sub(meta.budget_reserved.unwrap_or(0));
+    let percent = if available == 0 {
+        0.0
+    } else {
+        meta.tokens_used as f64 * 100.0 / available as f64
+    };
+    out.push_str("<h2>Budget</h2>\n");
+    let _ = writeln!(
+        out,
+        "<p>{} of {} available used ({:.1}%); {} of {} documents selected.</p>",
+        meta.tokens_used, available, percent, meta.documents_selected, meta.documents_considered,
+    );
+    let _ = writeln!(out, "<div class=\"bar\"><div style=\"width:{:.1}%\"></div></div>", percent.min(100.0));
+
+    // Ranked table
+    let scores: BTreeMap<&str, &ScoreTrace> = trace.scores.iter().map(|s| (s.id.as_str(), s)).collect();
+    out.push_str("<h2>Ranking</h2>\n<table>\n");
+    out.push_str(
+        "<tr><th>Rank</th><th>Document</th><th>Raw score</th><th>Boost</th><th>Score</th><th>Tokens</th><th>Verdict</th></tr>\n",
+    );
+    for entry in &trace.budget {
+        let selected = entry.verdict.is_selected();
+        let score = scores.get(entry.id.as_str());
+        let _ = writeln!(
+            out,
+            "<tr{}><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+            if selected { " class=\"selected\"" } else { "" },
+            entry.rank + 1,
+            escape(&entry.id),
+            score.map(|s| format_score(s.raw_score)).unwrap_or_default(),
+            score.and_then(|s| s.boost).map(format_score).unwrap_or_default(),
+            format_score(entry.score),
+            entry.tokens,
+            budget_verdict_label(entry.verdict),
+        );
+    }
+    out.push_str("</table>\n");
+
+    // Why panels
+    out.push_str("<h2>Selected documents</h2>\n");
+    for doc in &result.documents {
+        write_why_panel(&mut out, doc);
+    }
+
+    // Excluded before budgeting
+    let below_threshold: Vec<&str> = trace
+        .scores
+        .iter()
+        .filter(|s| !s.passed_threshold)
+        .map(|s| s.id.as_str())
+        .collect();
+    let filtered: Vec<_> = trace.filter.iter().filter(|f| f.verdict != FilterVerdict::Passed).collect();
+    if !filtered.is_empty() || !below_threshold.is_empty() {
+        out.push_str("<h2>Excluded before ranking</h2>\n<table>\n<tr><th>Document</th><th>Reason</th></tr>\n");
+        for entry in filtered {
+            let reason = match entry.verdict {
+                FilterVerdict::ExcludedByPath => "excluded by path filter",
+                _ => "excluded by field clauses",
+            };
+            let _ = writeln!(out, "<tr><td>{}</td><td>{}</td></tr>", escape(&entry.id), reason);
+        }
+        for id in below_threshold {
+            let _ = writeln!(out, "<tr><td>{}</td><td>below score threshold</td></tr>", escape(id));
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn write_why_panel(out: &mut String, doc: &SelectedDocument) {
+    let why = &doc.why;
+    let _ = writeln!(
+        out,
+        "<details>\n<summary>{} &mdash; score {}, {} tokens</summary>",
+        escape(&doc.id),
+        format_score(doc.score),
+        doc.tokens,
+    );
+    out.push_str("<table>\n");
+    if let Some(original_tokens) = doc.original_tokens {
+        let label = if doc.truncation.is_some() {
+            "Truncated from"
+        } else if doc.representation == Some(Representation::Summary) {
+            "Summarized from"
+        } else if doc.representation.is_some() {
+            "Outlined from"
+        } else {
+            "Compressed from"
+        };
+        let _ = writeln!(out, "<tr><th>{}</th><td class=\"num\">{} tokens</td></tr>", label, original_tokens);
+    }
+    if let Some(linked_from) = &why.linked_from {
+        let _ = writeln!(out, "<tr><th>Linked from</th><td>{}</td></tr>", escape(linked_from));
+    }
+    let _ = writeln!(out, "<tr><th>Query terms</th><td>{}</td></tr>", escape(&why.query_terms.join(" ")));
+    let _ = writeln!(
+        out,
+        "<tr><th>Term matches</th><td class=\"num\">{} of {} words</td></tr>",
+        why.term_matches, why.total_words,
+    );
+    if let Some(phrase_matches) = why.phrase_matches {
+        let phrases: Vec<String> = why.query_phrases.iter().map(|p| format!("\"{}\"", p)).collect();
+        let _ = writeln!(
+            out,
+            "<tr><th>Phrase matches</th><td>{} words ({})</td></tr>",
+            phrase_matches,
+            escape(&phrases.join(", ")),
+        );
+    }
+    for (name, score) in &why.components {
+        let _ = writeln!(
+            out,
+            "<tr><th>Component {}</th><td class=\"num\">{}</td></tr>",
+            escape(name),
+            format_score(*score),
+        );
+    }
+    for chunk in &why.chunks {
+        let _ = writeln!(
+            out,
+            "<tr><th>Chunk {} (lines {}-{})</th><td class=\"num\">{}</td></tr>",
+            chunk.index,
+            chunk.span.start_line,
+            chunk.span.end_line,
+            format_score(chunk.score),
+        );
+    }
+    out.push_str("</table>\n");
+    if let Some(explanation) = &why.explanation {
+        // Explanation maps are BTreeMaps, so this is deterministic
+        let json = serde_json::to_string_pretty(explanation).unwrap_or_default();
+        let _ = writeln!(out, "<pre>{}</pre>", escape(&json));
+    }
+    out.push_str("</details>\n");
+}
+
+/// A score as it is serialized: rounded to `SCORE_DECIMALS`.
+fn format_score(score: f32) -> String {
+    round_score(score, SCORE_DECIMALS).to_string()
+}
+
+fn budget_verdict_label(verdict: BudgetVerdict) -> &'static str {
+    match verdict {
+        BudgetVerdict::Selected => "selected",
+        BudgetVerdict::SelectedForQuota => "selected for quota",
+        BudgetVerdict::Compressed => "selected, compressed",
+        BudgetVerdict::Summarized => "selected, summarized",
+        BudgetVerdict::Outlined => "selected, outlined",
+        BudgetVerdict::Truncated => "selected, truncated",
+        BudgetVerdict::Linked => "selected, linked",
+        BudgetVerdict::ExcludedByBudget => "excluded by budget",
+        BudgetVerdict::ExcludedByLimit => "excluded by limit",
+        BudgetVerdict::ExcludedByQuota => "excluded by quota",
+    }
+}
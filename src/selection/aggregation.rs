@@ -0,0 +1,164 @@
+use crate::document::{Chunker, Document};
+use crate::selection::fusion::round6;
+use crate::selection::ranking::Scorer;
+use crate::types::context_bundle::{AggregationStrategy, ChunkRef, ChunkingInfo, Query, ScoreDetails};
+use crate::types::QueryField;
+
+/// Chunk-level scoring: each document is split by a `Chunker`, every chunk
+/// is scored as if it were a document, and chunk scores are aggregated back
+/// to the document, so a long file with one relevant section still ranks.
+///
+/// All chunks of all candidates form the corpus passed to
+/// `Scorer::finalize`. The aggregated document details sum the chunks'
+/// term statistics and list the contributing chunks in `chunks`.
+pub struct ChunkScoring {
+    chunker: Box<dyn Chunker + Send + Sync>,
+    aggregation: AggregationStrategy,
+    best_chunk_only: bool,
+}
+
+impl ChunkScoring {
+    pub fn new(chunker: impl Chunker + Send + Sync + 'static, aggregation: AggregationStrategy) -> Self {
+        Self {
+            chunker: Box::new(chunker),
+            aggregation,
+            best_chunk_only: false,
+        }
+    }
+
+    /// Select only each document's best chunk instead of the whole
+    /// document. Its span is reported in `SelectedDocument::span`, and only
+    /// its tokens count against the budget.
+    pub fn best_chunk_only(mut self) -> Self {
+        self.best_chunk_only = true;
+        self
+    }
+
+    pub(crate) fn is_best_chunk_only(&self) -> bool {
+        self.best_chunk_only
+    }
+
+    pub fn info(&self, chunks_scored: usize) -> ChunkingInfo {
+        ChunkingInfo {
+            chunker: self.chunker.name(),
+            aggregation: self.aggregation,
+            chunks_scored,
+            best_chunk_only: self.best_chunk_only,
+        }
+    }
+
+    /// Details and score for every document in `docs`, index-aligned, and
+    /// the number of chunks scored.
+    ///
+    /// When a document's chunks name symbols, each chunk carries its own as
+    /// `symbol` metadata in place of the document's `symbols`. When the query has `symbol:` clauses and a
+    /// document's chunks name symbols, only the chunks matching a clause
+    /// are scored.
+    pub(crate) fn score<S: Scorer + ?Sized>(
+        &self,
+        scorer: &S,
+        docs: &[&Document],
+        query: &Query,
+    ) -> (Vec<(ScoreDetails, f32)>, usize) {
+        let mut chunk_docs = Vec::new();
+        let mut owners = Vec::new();
+        let symbol_clauses: Vec<&QueryField> = query
+            .fields
+            .iter()
+            .filter(|field| matches!(field, QueryField::Symbol(_)))
+            .collect();
+        for (i, doc) in docs.iter().enumerate() {
+            let chunks = self.chunker.chunks(&doc.content);
+            let named = chunks.iter().any(|chunk| chunk.symbol.is_some());
+            for chunk in chunks {
+                let mut metadata = doc.metadata.clone();
+                if named {
+                    metadata.remove("symbols");
+                }
+                if let Some(symbol) = &chunk.symbol {
+                    metadata.insert_string("symbol", symbol.as_str());
+                }
+                let chunk_doc = Document {
+                    schema_version: doc.schema_version,
+                    id: doc.id.clone(),
+                    version: doc.version.clone(),
+                    source: doc.source.clone(),
+                    content: chunk.text(&doc.content).to_string(),
+                    metadata,
+                    summary: None,
+                };
+                let off_symbol = !symbol_clauses.iter().any(|field| field.matches(&chunk_doc));
+                if named && !symbol_clauses.is_empty() && off_symbol {
+                    continue;
+                }
+                chunk_docs.push(chunk_doc);
+                owners.push((i, chunk));
+            }
+        }
+
+        let chunk_refs: Vec<&Document> = chunk_docs.iter().collect();
+        let mut chunk_details: Vec<ScoreDetails> = chunk_refs.iter().map(|doc| scorer.score(doc, query)).collect();
+        scorer.finalize(&chunk_refs, &mut chunk_details);
+
+        let mut per_doc: Vec<Vec<(ChunkRef, ScoreDetails)>> = vec![Vec::new(); docs.len()];
+        for ((owner, chunk), details) in owners.into_iter().zip(chunk_details) {
+            let chunk_ref = ChunkRef {
+                index: chunk.index,
+                span: chunk.span,
+                score: scorer.score_value(&details),
+                symbol: chunk.symbol,
+            };
+            per_doc[owner].push((chunk_ref, details));
+        }
+
+        let scored = per_doc
+            .into_iter()
+            .map(|chunks| self.aggregate(chunks, query))
+            .collect();
+        (scored, chunk_docs.len())
+    }
+
+    fn aggregate(&self, mut chunks: Vec<(ChunkRef, ScoreDetails)>, query: &Query) -> (ScoreDetails, f32) {
+        let mut details = match chunks.first() {
+            Some((_, first)) => ScoreDetails {
+                query_terms: first.query_terms.clone(),
+                query_phrases: first.query_phrases.clone(),
+                ..Default::default()
+            },
+            None => ScoreDetails {
+                query_terms: query.terms.clone(),
+                query_phrases: query.phrases.iter().map(|p| p.join(" ")).collect(),
+                ..Default::default()
+            },
+        };
+        for (_, chunk) in &chunks {
+            details.term_matches += chunk.term_matches;
+            details.phrase_matches += chunk.phrase_matches;
+            details.total_words += chunk.total_words;
+        }
+
+        // Best first, then document order
+        chunks.sort_by(|(a, _), (b, _)| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+        let scores: Vec<f32> = chunks.iter().map(|(c, _)| c.score).collect();
+        let (score, contributing) = match self.aggregation {
+            AggregationStrategy::Max => (scores.first().copied().unwrap_or(0.0), 1),
+            AggregationStrategy::Sum => {
+                let sum: f64 = scores.iter().map(|&s| s as f64).sum();
+                (round6(sum), chunks.iter().filter(|(c, _)| c.score > 0.0).count())
+            }
+            AggregationStrategy::TopNMean { n } => {
+                let top = &scores[..n.max(1).min(scores.len())];
+                let mean = if top.is_empty() {
+                    0.0
+                } else {
+                    top.iter().map(|&s| s as f64).sum::<f64>() / top.len() as f64
+                };
+                (round6(mean), top.len())
+            }
+        };
+
+        details.chunks = chunks.into_iter().take(contributing).map(|(c, _)| c).collect();
+        details.score = Some(score);
+        (details, score)
+    }
+}
@@ -0,0 +1,129 @@
+use crate::document::Document;
+use crate::selection::ranking::{term_frequency, PatternCache, Scorer, TermMatching};
+use crate::types::context_bundle::{Query, ScoreDetails};
+
+/// Splits lowercased text into the words scorers match against.
+///
+/// Analyzers must be pure functions of the text: the same input yields the
+/// same words on every platform. `name` identifies the analyzer in
+/// `SelectionMetadata::analyzer`, memo keys and `CacheBuildConfig::analyzer`.
+pub trait Analyzer {
+    fn name(&self) -> &str;
+
+    /// Words of `text`, which the caller has already lowercased.
+    fn words<'a>(&self, text: &'a str) -> Vec<&'a str>;
+}
+
+/// v0 segmentation: split on Unicode whitespace, keeping punctuation.
+/// This is what `TermFrequencyScorer` uses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhitespaceAnalyzer;
+
+impl Analyzer for WhitespaceAnalyzer {
+    fn name(&self) -> &str {
+        "whitespace"
+    }
+
+    fn words<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        text.split_whitespace().collect()
+    }
+}
+
+/// UAX#29 word segmentation: punctuation is dropped (`deployment.` is
+/// `deployment`) and CJK ideographs are separate words.
+#[cfg(feature = "unicode-segmentation")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeAnalyzer;
+
+#[cfg(feature = "unicode-segmentation")]
+impl Analyzer for UnicodeAnalyzer {
+    fn name(&self) -> &str {
+        "uax29"
+    }
+
+    fn words<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        unicode_segmentation::UnicodeSegmentation::unicode_words(text).collect()
+    }
+}
+
+/// Term frequency scoring (as `TermFrequencyScorer`) with a configurable
+/// analyzer.
+///
+/// Document content and the query's terms and phrases are segmented by the
+/// same analyzer. A query term that segments into several words (`部署`,
+/// `foo-bar`) is matched as a phrase.
+///
+/// With `with_matching`, query terms may be wildcards or regexes (see
+/// `TermMatching`). Terms are then taken as written, without
+/// re-segmentation, so the pattern reaches the matcher intact.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzedScorer<A> {
+    analyzer: A,
+    patterns: PatternCache,
+}
+
+impl<A: Analyzer> AnalyzedScorer<A> {
+    pub fn new(analyzer: A) -> Self {
+        Self {
+            analyzer,
+            patterns: PatternCache::default(),
+        }
+    }
+
+    /// Match query terms with `matching` instead of word equality.
+    pub fn with_matching(mut self, matching: TermMatching) -> Self {
+        self.patterns = PatternCache::new(matching);
+        self
+    }
+
+    pub fn analyzer(&self) -> &A {
+        &self.analyzer
+    }
+
+    /// Re-segment query terms and phrases with the analyzer. Anything
+    /// that yields one word is a term; two or more, a phrase. Pattern
+    /// terms are kept as written.
+    fn analyze_query(&self, query: &Query) -> (Vec<String>, Vec<Vec<String>>) {
+        let mut terms = Vec::new();
+        let mut phrases = Vec::new();
+        let mut texts: Vec<String> = query.phrases.iter().map(|p| p.join(" ")).collect();
+        if self.patterns.mode() == TermMatching::Exact {
+            texts.splice(0..0, query.terms.iter().cloned());
+        } else {
+            terms.extend(query.terms.iter().cloned());
+        }
+        for text in texts {
+            let mut words: Vec<String> = self.analyzer.words(&text).into_iter().map(str::to_string).collect();
+            if words.len() > 1 {
+                phrases.push(words);
+            } else {
+                terms.append(&mut words);
+            }
+        }
+        (terms, phrases)
+    }
+}
+
+impl<A: Analyzer> Scorer for AnalyzedScorer<A> {
+    fn score(&self, doc: &Document, query: &Query) -> ScoreDetails {
+        let content_lower = doc.content.to_lowercase();
+        let words = self.analyzer.words(&content_lower);
+        let (terms, phrases) = self.analyze_query(query);
+        if self.patterns.mode() == TermMatching::Exact {
+            return term_frequency(&words, &terms, &phrases, None);
+        }
+        let patterns = self.patterns.patterns(&terms);
+        term_frequency(&words, &terms, &phrases, Some(&patterns))
+    }
+
+    fn analyzer(&self) -> Option<String> {
+        Some(self.analyzer.name().to_string())
+    }
+
+    fn fingerprint(&self) -> String {
+        match self.patterns.mode() {
+            TermMatching::Exact => format!("analyzed({})", self.analyzer.name()),
+            mode => format!("analyzed({},{})", self.analyzer.name(), mode.name()),
+        }
+    }
+}
@@ -1,44 +1,445 @@
-use crate::types::context_bundle::{ScoredDocument, SelectedDocument, SelectionWhy};
+use std::borrow::Cow;
 
-pub struct BudgetResult {
-    pub selected: Vec<SelectedDocument>,
-    pub tokens_used: usize,
+use crate::document::Document;
+use crate::selection::filters::glob_match;
+use crate::types::context_bundle::{
+    BudgetVerdict, QuotaReport, ScoredDocument, SelectedDocument, SelectedDocumentRef, SelectionError, SelectionWhy,
+};
+use crate::types::query::QueryField;
+
+/// Largest `BudgetConfig::total` a `ContextSelector` accepts unless
+/// configured otherwise (see `ContextSelector::with_max_budget`): far
+/// beyond any model's context window.
+pub const DEFAULT_MAX_BUDGET: u64 = 1 << 30;
+
+/// Token budget for a selection.
+///
+/// `reserved` tokens are set aside for prompt text the caller wraps around
+/// the selected content; `per_document_overhead` is charged for every
+/// selected document (e.g. per-document delimiters). `From<u64>` gives a
+/// plain budget with no reservations.
+///
+/// All amounts are in `unit`, tokens by default. They are `u64` on every
+/// target, and sums of them saturate rather than overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct BudgetConfig {
+    pub total: u64,
+    #[serde(default)]
+    pub reserved: u64,
+    #[serde(default)]
+    pub per_document_overhead: u64,
+    #[serde(default, skip_serializing_if = "BudgetUnit::is_tokens")]
+    pub unit: BudgetUnit,
+    #[serde(default, skip_serializing_if = "BudgetStrategy::is_greedy")]
+    pub strategy: BudgetStrategy,
+}
+
+/// What happens when a ranked document does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetStrategy {
+    /// Skip it and keep trying lower-ranked documents.
+    #[default]
+    Greedy,
+    /// End selection, so the result is a contiguous top of the ranking.
+    /// The document and everything after it count as excluded by budget
+    /// (or by quota, for the document itself). Documents admitted for a
+    /// quota's `min_documents` are kept.
+    StopAtFirstExclusion,
+}
+
+impl BudgetStrategy {
+    pub fn is_greedy(&self) -> bool {
+        *self == BudgetStrategy::Greedy
+    }
+}
+
+/// What a budget counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetUnit {
+    /// The selector's `TokenCounter`.
+    #[default]
+    Tokens,
+    /// Unicode scalar values.
+    Chars,
+    /// UTF-8 bytes.
+    Bytes,
+    /// Whitespace-separated words.
+    Words,
+}
+
+impl BudgetUnit {
+    pub fn is_tokens(&self) -> bool {
+        *self == BudgetUnit::Tokens
+    }
+
+    /// Size of a scored document's selectable content in this unit.
+    pub fn measure(&self, sdoc: &ScoredDocument) -> u64 {
+        self.measure_text(sdoc.content(), sdoc.token_count)
+    }
+
+    /// Size of `content`, which counts `tokens` tokens, in this unit.
+    pub fn measure_text(&self, content: &str, tokens: u64) -> u64 {
+        match self {
+            BudgetUnit::Tokens => tokens,
+            BudgetUnit::Chars => content.chars().count() as u64,
+            BudgetUnit::Bytes => content.len() as u64,
+            BudgetUnit::Words => content.split_whitespace().count() as u64,
+        }
+    }
+}
+
+impl BudgetConfig {
+    pub fn new(total: u64) -> Self {
+        Self {
+            total,
+            reserved: 0,
+            per_document_overhead: 0,
+            unit: BudgetUnit::Tokens,
+            strategy: BudgetStrategy::Greedy,
+        }
+    }
+
+    pub fn strategy(mut self, strategy: BudgetStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Count the budget in `unit` instead of tokens.
+    pub fn unit(mut self, unit: BudgetUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn reserve(mut self, reserved: u64) -> Self {
+        self.reserved = reserved;
+        self
+    }
+
+    pub fn per_document_overhead(mut self, overhead: u64) -> Self {
+        self.per_document_overhead = overhead;
+        self
+    }
+
+    /// Amount available to document content and per-document overhead.
+    pub fn available(&self) -> u64 {
+        self.total.saturating_sub(self.reserved)
+    }
+
+    /// Fails with `SelectionError::InvalidBudget` unless `total` is between
+    /// 1 and `max`. A reservation larger than the total is allowed, and
+    /// leaves nothing for documents.
+    pub fn validate(&self, max: u64) -> Result<(), SelectionError> {
+        if self.total == 0 || self.total > max {
+            return Err(SelectionError::InvalidBudget(self.total));
+        }
+        Ok(())
+    }
+}
+
+impl From<u64> for BudgetConfig {
+    fn from(total: u64) -> Self {
+        Self::new(total)
+    }
+}
+
+/// Cap on the number of selected documents, applied alongside the token budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionLimit {
+    #[default]
+    Unlimited,
+    /// Select at most `k` documents, in ranking order.
+    TopK(usize),
+}
+
+impl SelectionLimit {
+    pub fn top_k(&self) -> Option<usize> {
+        match self {
+            SelectionLimit::Unlimited => None,
+            SelectionLimit::TopK(k) => Some(*k),
+        }
+    }
+}
+
+/// Which documents a `BudgetQuota` applies to.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaScope {
+    /// Documents whose id matches the glob (see `PathFilter`).
+    Path(String),
+    /// Documents whose `tags` metadata (comma-separated, or a list of
+    /// strings) contains the tag (case-insensitive).
+    Tag(String),
+}
+
+impl QuotaScope {
+    pub fn matches(&self, doc: &Document) -> bool {
+        match self {
+            QuotaScope::Path(pattern) => glob_match(pattern, doc.id.as_str()),
+            QuotaScope::Tag(tag) => QueryField::Tag(tag.to_lowercase()).matches(doc),
+        }
+    }
+}
+
+/// Constraint on the share of the budget spent on one scope.
+///
+/// `max_percent` caps the budget units (cost including overhead) spent on
+/// matching documents at `available * max_percent / 100`, rounded down.
+/// `min_documents` admits the best-ranked matching documents that fit
+/// before anything else is considered, so they cannot be crowded out.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BudgetQuota {
+    pub scope: QuotaScope,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_percent: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_documents: Option<usize>,
+}
+
+impl BudgetQuota {
+    pub fn new(scope: QuotaScope) -> Self {
+        Self {
+            scope,
+            max_percent: None,
+            min_documents: None,
+        }
+    }
+
+    /// Spend at most `percent` (capped at 100) of the available budget here.
+    pub fn max_percent(mut self, percent: u32) -> Self {
+        self.max_percent = Some(percent.min(100));
+        self
+    }
+
+    /// Select at least `count` matching documents when they fit.
+    pub fn min_documents(mut self, count: usize) -> Self {
+        self.min_documents = Some(count);
+        self
+    }
+}
+
+pub struct BudgetResult<D = SelectedDocument> {
+    pub selected: Vec<D>,
+    pub tokens_used: u64,
     pub documents_selected: usize,
     pub documents_excluded_by_budget: usize,
+    pub documents_excluded_by_limit: usize,
+    pub documents_excluded_by_quota: usize,
+    /// One report per quota, in configuration order.
+    pub quotas: Vec<QuotaReport>,
+    /// One verdict per scored document, in input order.
+    pub verdicts: Vec<BudgetVerdict>,
 }
 
-pub fn apply_budget(scored_docs: Vec<ScoredDocument>, budget: usize) -> BudgetResult {
-    let mut selected = Vec::new();
-    let mut tokens_used = 0;
-    let mut documents_selected = 0;
-    let mut documents_excluded_by_budget = 0;
+/// Greedily admit documents in order while they fit `budget.available()`.
+///
+/// Documents are measured in `budget.unit`, and so is `tokens_used`, which
+/// includes `per_document_overhead` for each selected document.
+/// Once `limit` documents are selected, every remaining document is counted
+/// as excluded by limit rather than by budget.
+pub fn apply_budget(
+    scored_docs: Vec<ScoredDocument>,
+    budget: &BudgetConfig,
+    limit: SelectionLimit,
+) -> BudgetResult {
+    let result = apply_budget_ref(scored_docs, budget, limit);
+    BudgetResult {
+        selected: result.selected.into_iter().map(SelectedDocumentRef::into_owned).collect(),
+        tokens_used: result.tokens_used,
+        documents_selected: result.documents_selected,
+        documents_excluded_by_budget: result.documents_excluded_by_budget,
+        documents_excluded_by_limit: result.documents_excluded_by_limit,
+        documents_excluded_by_quota: result.documents_excluded_by_quota,
+        quotas: result.quotas,
+        verdicts: result.verdicts,
+    }
+}
 
-    for sdoc in scored_docs {
-        // Spec: "Documents with score 0.0 MAY be selected if budget allows."
-        if tokens_used + sdoc.token_count <= budget {
-            selected.push(SelectedDocument {
-                id: sdoc.document.id.as_str().to_string(),
-                version: sdoc.document.version.as_str().to_string(),
-                content: sdoc.document.content.clone(),
-                score: sdoc.score,
-                tokens: sdoc.token_count,
-                why: SelectionWhy {
-                    query_terms: sdoc.score_details.query_terms,
-                    term_matches: sdoc.score_details.term_matches,
-                    total_words: sdoc.score_details.total_words,
-                },
-            });
-            tokens_used += sdoc.token_count;
-            documents_selected += 1;
-        } else {
+/// Like `apply_budget`, but selected documents borrow from the scored
+/// documents instead of copying their content.
+pub fn apply_budget_ref<'a>(
+    scored_docs: Vec<ScoredDocument<'a>>,
+    budget: &BudgetConfig,
+    limit: SelectionLimit,
+) -> BudgetResult<SelectedDocumentRef<'a>> {
+    apply_budget_with_quotas_ref(scored_docs, budget, limit, &[])
+}
+
+/// `apply_budget_ref` under `quotas`.
+///
+/// First, for each quota with `min_documents` (in order), the best-ranked
+/// matching documents that fit are admitted. Then the remaining documents
+/// are admitted greedily in rank order. A document that fits the budget but
+/// would exceed a `max_percent` cap is counted as excluded by quota.
+/// Selected documents keep their rank order.
+pub fn apply_budget_with_quotas_ref<'a>(
+    scored_docs: Vec<ScoredDocument<'a>>,
+    budget: &BudgetConfig,
+    limit: SelectionLimit,
+    quotas: &[BudgetQuota],
+) -> BudgetResult<SelectedDocumentRef<'a>> {
+    let available = budget.available();
+    let max_documents = limit.top_k().unwrap_or(usize::MAX);
+
+    // Spec: "Documents with score 0.0 MAY be selected if budget allows."
+    let costs: Vec<u64> = scored_docs
+        .iter()
+        .map(|sdoc| budget.unit.measure(sdoc).saturating_add(budget.per_document_overhead))
+        .collect();
+    let in_scope: Vec<Vec<bool>> = scored_docs
+        .iter()
+        .map(|sdoc| quotas.iter().map(|q| q.scope.matches(sdoc.document)).collect())
+        .collect();
+    let caps: Vec<u64> = quotas
+        .iter()
+        .map(|q| q.max_percent.map_or(u64::MAX, |p| percent_of(available, u64::from(p.min(100)))))
+        .collect();
+
+    let mut state = QuotaState {
+        admitted: vec![false; scored_docs.len()],
+        used: vec![0; quotas.len()],
+        documents: vec![0; quotas.len()],
+        tokens_used: 0,
+        documents_selected: 0,
+    };
+    let mut verdicts = vec![BudgetVerdict::Selected; scored_docs.len()];
+
+    for (q, quota) in quotas.iter().enumerate() {
+        let Some(min) = quota.min_documents else {
+            continue;
+        };
+        for i in 0..scored_docs.len() {
+            if state.documents[q] >= min || state.documents_selected >= max_documents {
+                break;
+            }
+            if in_scope[i][q] && !state.admitted[i] && state.fits(costs[i], available) && state.within_caps(&in_scope[i], costs[i], &caps) {
+                state.admit(i, costs[i], &in_scope[i]);
+                verdicts[i] = BudgetVerdict::SelectedForQuota;
+            }
+        }
+    }
+
+    let mut documents_excluded_by_budget = 0;
+    let mut documents_excluded_by_limit = 0;
+    let mut documents_excluded_by_quota = 0;
+    let mut stopped = false;
+    for i in 0..scored_docs.len() {
+        if state.admitted[i] {
+            continue;
+        }
+        if state.documents_selected >= max_documents {
+            documents_excluded_by_limit += 1;
+            verdicts[i] = BudgetVerdict::ExcludedByLimit;
+        } else if stopped || !state.fits(costs[i], available) {
             documents_excluded_by_budget += 1;
+            verdicts[i] = BudgetVerdict::ExcludedByBudget;
+            stopped = budget.strategy == BudgetStrategy::StopAtFirstExclusion;
+        } else if !state.within_caps(&in_scope[i], costs[i], &caps) {
+            documents_excluded_by_quota += 1;
+            verdicts[i] = BudgetVerdict::ExcludedByQuota;
+            stopped = budget.strategy == BudgetStrategy::StopAtFirstExclusion;
+        } else {
+            state.admit(i, costs[i], &in_scope[i]);
         }
     }
 
+    let selected = scored_docs
+        .into_iter()
+        .zip(&state.admitted)
+        .filter(|(_, &admitted)| admitted)
+        .map(|(sdoc, _)| select_ref(sdoc))
+        .collect();
+
+    let quotas = quotas
+        .iter()
+        .enumerate()
+        .map(|(q, quota)| QuotaReport {
+            scope: quota.scope.clone(),
+            max_percent: quota.max_percent,
+            min_documents: quota.min_documents,
+            used: state.used[q],
+            documents: state.documents[q],
+            satisfied: state.documents[q] >= quota.min_documents.unwrap_or(0),
+        })
+        .collect();
+
     BudgetResult {
         selected,
-        tokens_used,
-        documents_selected,
+        tokens_used: state.tokens_used,
+        documents_selected: state.documents_selected,
         documents_excluded_by_budget,
+        documents_excluded_by_limit,
+        documents_excluded_by_quota,
+        quotas,
+        verdicts,
+    }
+}
+
+/// `percent`% of `amount`, rounded down, without overflowing.
+fn percent_of(amount: u64, percent: u64) -> u64 {
+    amount / 100 * percent + amount % 100 * percent / 100
+}
+
+struct QuotaState {
+    admitted: Vec<bool>,
+    /// Budget units spent per quota.
+    used: Vec<u64>,
+    /// Documents selected per quota.
+    documents: Vec<usize>,
+    tokens_used: u64,
+    documents_selected: usize,
+}
+
+impl QuotaState {
+    fn fits(&self, cost: u64, available: u64) -> bool {
+        cost <= available.saturating_sub(self.tokens_used)
+    }
+
+    fn within_caps(&self, in_scope: &[bool], cost: u64, caps: &[u64]) -> bool {
+        in_scope
+            .iter()
+            .zip(&self.used)
+            .zip(caps)
+            .all(|((&matched, &used), &cap)| !matched || cost <= cap.saturating_sub(used))
+    }
+
+    fn admit(&mut self, i: usize, cost: u64, in_scope: &[bool]) {
+        self.admitted[i] = true;
+        self.tokens_used = self.tokens_used.saturating_add(cost);
+        self.documents_selected += 1;
+        for (q, _) in in_scope.iter().enumerate().filter(|(_, &matched)| matched) {
+            self.used[q] = self.used[q].saturating_add(cost);
+            self.documents[q] += 1;
+        }
+    }
+}
+
+pub(crate) fn select_ref(sdoc: ScoredDocument<'_>) -> SelectedDocumentRef<'_> {
+    SelectedDocumentRef {
+        id: sdoc.document.id.as_str(),
+        version: sdoc.document.version.as_str(),
+        content: Cow::Borrowed(sdoc.content()),
+        span: sdoc.span,
+        metadata: None,
+        score: sdoc.score,
+        tokens: sdoc.token_count,
+        original_tokens: None,
+        truncation: None,
+        representation: None,
+        why: SelectionWhy {
+            query_terms: sdoc.score_details.query_terms,
+            term_matches: sdoc.score_details.term_matches,
+            total_words: sdoc.score_details.total_words,
+            phrase_matches: (!sdoc.score_details.query_phrases.is_empty())
+                .then_some(sdoc.score_details.phrase_matches),
+            query_phrases: sdoc.score_details.query_phrases,
+            components: sdoc.score_details.components,
+            explanation: sdoc.score_details.explanation,
+            chunks: sdoc.score_details.chunks,
+            reason: None,
+            linked_from: None,
+        },
     }
 }
@@ -0,0 +1,26 @@
+use std::collections::BTreeSet;
+
+use crate::types::identifiers::DocumentId;
+use crate::types::Query;
+
+/// Narrows the documents a selection scores, before the scoring phase, to
+/// those an index (an inverted index, tantivy, a vector database, ...)
+/// finds worth scoring for the query. Set with
+/// `ContextSelector::with_candidate_source`.
+///
+/// Documents outside the candidates are not scored, so they are not
+/// selected even when the budget has room for zero-score documents.
+/// Pinned documents are always scored. When a source returns `None`, every
+/// document is scored, exactly as without a source.
+pub trait CandidateSource: Send + Sync {
+    /// Ids of the documents to score for `query`, selecting from the cache
+    /// at `cache_version` (when the loader reports one). Must be
+    /// deterministic for a given query and cache version, and return
+    /// `None` rather than a partial answer when the source cannot answer
+    /// (it failed, or was built for another cache).
+    fn narrow(&self, query: &Query, cache_version: Option<&str>) -> Option<BTreeSet<DocumentId>>;
+
+    /// Identifies this source and its parameters in memoized selection
+    /// keys.
+    fn fingerprint(&self) -> String;
+}
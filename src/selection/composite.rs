@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use crate::document::Document;
+use crate::selection::fusion::round6;
+use crate::selection::ranking::Scorer;
+use crate::types::context_bundle::{Explanation, Query, ScoreDetails};
+
+struct Component {
+    name: String,
+    weight: f32,
+    scorer: Box<dyn Scorer + Send + Sync>,
+}
+
+/// Weighted combination of any number of named scorers.
+///
+/// The score is `sum(w_i * s_i) / sum(w_i)`, where `s_i` is component `i`'s
+/// `score_value`, rounded to 6 decimal places. Each `s_i` is recorded in
+/// `ScoreDetails::components` (and so in `why.components`) under its name.
+/// Term statistics are passed through from the first component, and
+/// component explanations are collected into `Explanation::Composite`.
+///
+/// Components are finalized in order, each over its own details, so
+/// rank-based scorers such as reciprocal rank fusion compose correctly.
+#[derive(Default)]
+pub struct CompositeScorer {
+    components: Vec<Component>,
+}
+
+impl CompositeScorer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a component. Negative weights count as zero; a component with
+    /// an existing name replaces it in place.
+    pub fn with(mut self, name: impl Into<String>, weight: f32, scorer: impl Scorer + Send + Sync + 'static) -> Self {
+        let component = Component {
+            name: name.into(),
+            weight: weight.max(0.0),
+            scorer: Box::new(scorer),
+        };
+        match self.components.iter_mut().find(|c| c.name == component.name) {
+            Some(existing) => *existing = component,
+            None => self.components.push(component),
+        }
+        self
+    }
+
+    /// Fill `components`, `score` and `explanation` from the finished `breakdown`.
+    fn combine(&self, details: &mut ScoreDetails) {
+        let total: f32 = self.components.iter().map(|c| c.weight).sum();
+        let mut sum = 0.0f64;
+        for (component, sub) in self.components.iter().zip(&details.breakdown) {
+            let value = component.scorer.score_value(sub);
+            details.components.insert(component.name.clone(), value);
+            sum += component.weight as f64 * value as f64;
+        }
+        details.score = Some(if total > 0.0 { round6(sum / total as f64) } else { 0.0 });
+
+        let explained: BTreeMap<String, Explanation> = self
+            .components
+            .iter()
+            .zip(&details.breakdown)
+            .filter_map(|(component, sub)| Some((component.name.clone(), sub.explanation.clone()?)))
+            .collect();
+        details.explanation = (!explained.is_empty()).then_some(Explanation::Composite { components: explained });
+    }
+}
+
+impl Scorer for CompositeScorer {
+    fn score(&self, doc: &Document, query: &Query) -> ScoreDetails {
+        let breakdown: Vec<ScoreDetails> = self.components.iter().map(|c| c.scorer.score(doc, query)).collect();
+
+        let mut details = match breakdown.first() {
+            Some(first) => ScoreDetails {
+                query_terms: first.query_terms.clone(),
+                term_matches: first.term_matches,
+                total_words: first.total_words,
+                query_phrases: first.query_phrases.clone(),
+                phrase_matches: first.phrase_matches,
+                ..Default::default()
+            },
+            None => ScoreDetails {
+                query_terms: query.terms.clone(),
+                total_words: doc.content.split_whitespace().count(),
+                ..Default::default()
+            },
+        };
+        details.breakdown = breakdown;
+        self.combine(&mut details);
+        details
+    }
+
+    fn finalize(&self, docs: &[&Document], details: &mut [ScoreDetails]) {
+        for (i, component) in self.components.iter().enumerate() {
+            let mut column: Vec<ScoreDetails> = details.iter_mut().map(|d| std::mem::take(&mut d.breakdown[i])).collect();
+            component.scorer.finalize(docs, &mut column);
+            for (d, sub) in details.iter_mut().zip(column) {
+                d.breakdown[i] = sub;
+            }
+        }
+        for d in details.iter_mut() {
+            d.components = BTreeMap::new();
+            self.combine(d);
+        }
+    }
+
+    fn score_value(&self, details: &ScoreDetails) -> f32 {
+        let score = details.score.unwrap_or(0.0);
+        debug_assert!((0.0..=1.0).contains(&score), "score {score} out of range [0.0, 1.0]");
+        score
+    }
+
+    fn analyzer(&self) -> Option<String> {
+        self.components.iter().find_map(|c| c.scorer.analyzer())
+    }
+
+    fn fingerprint(&self) -> String {
+        let parts: Vec<String> = self
+            .components
+            .iter()
+            .map(|c| format!("{}:{}:{}", c.name, c.weight, c.scorer.fingerprint()))
+            .collect();
+        format!("composite({})", parts.join(","))
+    }
+}
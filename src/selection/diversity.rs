@@ -0,0 +1,132 @@
+use std::collections::BTreeSet;
+
+use crate::cache::vectors::{cosine_similarity, VectorStore};
+use crate::types::context_bundle::{MmrInfo, ScoredDocument};
+
+/// Similarity measure used to penalize redundant documents.
+#[derive(Debug, Clone)]
+pub enum Similarity {
+    /// Jaccard overlap of lowercase whitespace-separated word sets.
+    TermOverlap,
+    /// Cosine similarity of the cache's stored vectors.
+    Vectors(VectorStore),
+}
+
+impl Similarity {
+    fn name(&self) -> &'static str {
+        match self {
+            Similarity::TermOverlap => "term_overlap",
+            Similarity::Vectors(_) => "vectors",
+        }
+    }
+}
+
+/// Maximal marginal relevance re-ranking.
+///
+/// Documents are picked one at a time by
+/// `lambda * score - (1 - lambda) * max_similarity_to_already_picked`.
+/// `lambda = 1.0` keeps the relevance order; lower values favor diversity.
+#[derive(Debug, Clone)]
+pub struct Mmr {
+    pub lambda: f32,
+    pub similarity: Similarity,
+}
+
+impl Mmr {
+    pub fn new(lambda: f32, similarity: Similarity) -> Self {
+        Self {
+            lambda: lambda.clamp(0.0, 1.0),
+            similarity,
+        }
+    }
+
+    pub fn info(&self) -> MmrInfo {
+        MmrInfo {
+            lambda: self.lambda,
+            similarity: self.similarity.name().to_string(),
+        }
+    }
+}
+
+/// Re-rank `scored_docs` (already in score desc, id asc order) by MMR.
+///
+/// Ties on the MMR objective keep the incoming order, so the result is
+/// fully deterministic. Scores themselves are not modified.
+pub fn apply_mmr<'a>(scored_docs: Vec<ScoredDocument<'a>>, mmr: &Mmr) -> Vec<ScoredDocument<'a>> {
+    let n = scored_docs.len();
+    if n < 2 {
+        return scored_docs;
+    }
+
+    let word_sets: Vec<BTreeSet<String>> = match mmr.similarity {
+        Similarity::TermOverlap => scored_docs
+            .iter()
+            .map(|d| {
+                d.content()
+                    .to_lowercase()
+                    .split_whitespace()
+                    .map(|w| w.to_string())
+                    .collect()
+            })
+            .collect(),
+        Similarity::Vectors(_) => Vec::new(),
+    };
+
+    let similarity = |a: usize, b: usize| -> f64 {
+        match &mmr.similarity {
+            Similarity::TermOverlap => jaccard(&word_sets[a], &word_sets[b]),
+            Similarity::Vectors(store) => {
+                match (
+                    store.get(&scored_docs[a].document.id),
+                    store.get(&scored_docs[b].document.id),
+                ) {
+                    (Some(va), Some(vb)) => cosine_similarity(va, vb) as f64,
+                    _ => 0.0,
+                }
+            }
+        }
+    };
+
+    let lambda = mmr.lambda as f64;
+    let mut picked: Vec<usize> = Vec::with_capacity(n);
+    let mut remaining: Vec<usize> = (0..n).collect();
+    // max similarity of each candidate to the picked set
+    let mut max_sim = vec![0.0f64; n];
+
+    while !remaining.is_empty() {
+        let mut best_pos = 0;
+        let mut best_value = f64::NEG_INFINITY;
+        for (pos, &idx) in remaining.iter().enumerate() {
+            let value = lambda * scored_docs[idx].score as f64 - (1.0 - lambda) * max_sim[idx];
+            // Strict comparison: earlier (higher-ranked) candidates win ties
+            if value > best_value {
+                best_value = value;
+                best_pos = pos;
+            }
+        }
+
+        let chosen = remaining.remove(best_pos);
+        for &idx in &remaining {
+            let sim = similarity(chosen, idx);
+            if sim > max_sim[idx] {
+                max_sim[idx] = sim;
+            }
+        }
+        picked.push(chosen);
+    }
+
+    let mut slots: Vec<Option<ScoredDocument<'a>>> = scored_docs.into_iter().map(Some).collect();
+    picked
+        .into_iter()
+        .map(|idx| slots[idx].take().expect("each document is picked once"))
+        .collect()
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count();
+    let union = a.len() + b.len() - intersection;
+    intersection as f64 / union as f64
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::cache::vectors::{cosine_similarity, quantize, Embedder, VectorError, VectorStore};
+use crate::document::Document;
+use crate::selection::ranking::Scorer;
+use crate::types::context_bundle::{Explanation, Query, ScoreDetails};
+
+/// Scores documents by cosine similarity between the query embedding and the
+/// document vectors stored in the cache (`vectors.json`).
+///
+/// Document vectors are never recomputed at selection time; only the query is
+/// embedded, once per query text (the last query's vector is kept for every
+/// document of a selection). Similarity is computed in fixed-point arithmetic, so identical
+/// caches always rank identically.
+pub struct EmbeddingScorer<E> {
+    embedder: E,
+    vectors: VectorStore,
+    /// The last query text embedded, and its quantized vector.
+    query_vec: Mutex<Option<(String, Arc<Vec<i32>>)>>,
+}
+
+impl<E: Embedder> EmbeddingScorer<E> {
+    /// Fails if `vectors` were produced by a different embedder.
+    pub fn new(embedder: E, vectors: VectorStore) -> Result<Self, VectorError> {
+        let found = embedder.id();
+        if found != vectors.embedder {
+            return Err(VectorError::EmbedderMismatch {
+                expected: vectors.embedder,
+                found,
+            });
+        }
+        Ok(Self {
+            embedder,
+            vectors,
+            query_vec: Mutex::new(None),
+        })
+    }
+
+    /// The quantized embedding of `text`, computed once per query text.
+    fn query_vector(&self, text: &str) -> Arc<Vec<i32>> {
+        let mut last = self.query_vec.lock().unwrap_or_else(|e| e.into_inner());
+        match &*last {
+            Some((cached, vector)) if cached == text => Arc::clone(vector),
+            _ => {
+                let vector = Arc::new(quantize(&self.embedder.embed(text)));
+                *last = Some((text.to_string(), Arc::clone(&vector)));
+                vector
+            }
+        }
+    }
+}
+
+impl<E: Embedder> Scorer for EmbeddingScorer<E> {
+    fn score(&self, doc: &Document, query: &Query) -> ScoreDetails {
+        let total_words = doc.content.split_whitespace().count();
+
+        // Documents without a stored vector score 0.0
+        let doc_vec = self.vectors.get(&doc.id);
+        let similarity = match doc_vec {
+            Some(doc_vec) if !query.terms.is_empty() || !query.phrases.is_empty() => {
+                let text: Vec<&str> = query
+                    .terms
+                    .iter()
+                    .chain(query.phrases.iter().flatten())
+                    .map(String::as_str)
+                    .collect();
+                cosine_similarity(&self.query_vector(&text.join(" ")), doc_vec)
+            }
+            _ => 0.0,
+        };
+
+        ScoreDetails {
+            query_terms: query.terms.clone(),
+            term_matches: 0,
+            total_words,
+            query_phrases: query.phrases.iter().map(|p| p.join(" ")).collect(),
+            phrase_matches: 0,
+            score: Some(similarity),
+            components: BTreeMap::new(),
+            explanation: Some(Explanation::Embedding {
+                similarity: doc_vec.map(|_| similarity),
+            }),
+            chunks: Vec::new(),
+            breakdown: Vec::new(),
+        }
+    }
+
+    fn score_value(&self, details: &ScoreDetails) -> f32 {
+        let score = details.score.unwrap_or(0.0);
+        debug_assert!((0.0..=1.0).contains(&score), "score {score} out of range [0.0, 1.0]");
+        score
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("embedding({})", self.embedder.id())
+    }
+}
@@ -1,2 +1,129 @@
-// Selection filters (v0)
-// Placeholder for future filtering stages.
+use crate::types::identifiers::DocumentId;
+
+/// Include/exclude glob filter over `DocumentId`s.
+///
+/// Glob syntax:
+/// - `*` matches any run of characters except `/`
+/// - `**` matches any run of characters including `/` (any depth)
+/// - `?` matches one character except `/`
+/// - `[abc]`, `[a-z]` match one listed character, `[!abc]` (or `[^abc]`) one
+///   unlisted character, never `/`; a `[` without a closing `]` is literal
+/// - `\` makes the next character literal
+///
+/// A document passes if it matches at least one `include` pattern (or
+/// `include` is empty) and matches no `exclude` pattern. Matching is a pure
+/// function of the id string, so filtering is deterministic.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PathFilter {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    pub fn matches(&self, id: &DocumentId) -> bool {
+        let id = id.as_str();
+        let included = self.include.is_empty() || self.include.iter().any(|p| glob_match(p, id));
+        included && !self.exclude.iter().any(|p| glob_match(p, id))
+    }
+}
+
+/// Match `text` against a glob `pattern` (see `PathFilter` for syntax).
+///
+/// Runs in O(|pattern| * |text|) steps whatever the pattern: whether each
+/// pattern suffix matches each text suffix is filled in from the end of the
+/// text, one text position at a time.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let n = p.len();
+    let class_ends: Vec<Option<usize>> = (0..n)
+        .map(|i| if p[i] == '[' { class_end(&p[i..]).map(|end| i + end) } else { None })
+        .collect();
+
+    // `cur[i]`: `p[i..]` matches `t[j..]`; `next[i]`: it matches `t[j + 1..]`.
+    // For a `*` or `**` at `i`, `run[i]` is whether the rest of the pattern
+    // matches after the star consumes some of `t[j..]`.
+    let mut cur = vec![false; n + 1];
+    let mut next = vec![false; n + 1];
+    let mut run = vec![false; n + 1];
+    let mut next_run = vec![false; n + 1];
+    for j in (0..=t.len()).rev() {
+        let c = t.get(j).copied();
+        cur[n] = c.is_none();
+        for i in (0..n).rev() {
+            cur[i] = match p[i] {
+                '*' if p.get(i + 1) == Some(&'*') => {
+                    // `**/` also matches zero directories
+                    let rest = i + 2;
+                    run[i] = cur[rest] || (c.is_some() && next_run[i]);
+                    (p.get(rest) == Some(&'/') && cur[rest + 1]) || run[i]
+                }
+                '*' => {
+                    run[i] = cur[i + 1] || (c.is_some_and(|c| c != '/') && next_run[i]);
+                    run[i]
+                }
+                '?' => c.is_some_and(|c| c != '/') && next[i + 1],
+                '\\' if i + 1 < n => c == Some(p[i + 1]) && next[i + 2],
+                '[' => match class_ends[i] {
+                    Some(end) => {
+                        c.is_some_and(|c| c != '/' && class_matches(&p[i + 1..end], c)) && next[end + 1]
+                    }
+                    None => c == Some('[') && next[i + 1],
+                },
+                pc => c == Some(pc) && next[i + 1],
+            };
+        }
+        std::mem::swap(&mut cur, &mut next);
+        std::mem::swap(&mut run, &mut next_run);
+    }
+    next[0]
+}
+
+/// Index of the `]` closing the class `p` starts with. A `]` first in the
+/// class (after any `!` or `^`) is a member.
+fn class_end(p: &[char]) -> Option<usize> {
+    let mut i = 1;
+    if matches!(p.get(i), Some('!' | '^')) {
+        i += 1;
+    }
+    if p.get(i) == Some(&']') {
+        i += 1;
+    }
+    p[i..].iter().position(|&c| c == ']').map(|pos| i + pos)
+}
+
+/// Whether `c` is in the class `body` (between the brackets).
+fn class_matches(body: &[char], c: char) -> bool {
+    let (negated, body) = match body.first() {
+        Some('!' | '^') => (true, &body[1..]),
+        _ => (false, body),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            found |= body[i] <= c && c <= body[i + 2];
+            i += 3;
+        } else {
+            found |= body[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+
+use crate::document::metadata::MetadataValue;
+use crate::document::Document;
+use crate::selection::fusion::round6;
+use crate::types::context_bundle::ScoredDocument;
+
+/// Metadata key marking a document deprecated: `true` (or `"true"`).
+pub const DEPRECATED_KEY: &str = "deprecated";
+/// Metadata key of the RFC 3339 time a document expires at.
+pub const EXPIRES_AT_KEY: &str = "expires_at";
+
+/// Down-rank or exclude stale documents: those marked deprecated, and
+/// those whose `expires_at` is not after `now`.
+///
+/// `now` is supplied by the caller rather than read from the clock, so a
+/// selection replays identically. An `expires_at` that does not parse as
+/// RFC 3339 is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Freshness {
+    pub now: DateTime<Utc>,
+    pub action: StaleAction,
+}
+
+/// What happens to a stale document.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StaleAction {
+    /// Drop it with the filters, before scoring.
+    Exclude,
+    /// Multiply its score by `factor` (rounded to 6 decimal places) after
+    /// the score thresholds, before ordering.
+    Penalize { factor: f32 },
+}
+
+impl Freshness {
+    pub fn new(now: DateTime<Utc>, action: StaleAction) -> Self {
+        Self { now, action }
+    }
+
+    /// Whether `doc` is deprecated or expired at `now`.
+    pub fn is_stale(&self, doc: &Document) -> bool {
+        let deprecated = match doc.metadata.get(DEPRECATED_KEY) {
+            Some(MetadataValue::Bool(deprecated)) => *deprecated,
+            Some(MetadataValue::String(text)) => text == "true",
+            _ => false,
+        };
+        let expired = doc
+            .metadata
+            .get(EXPIRES_AT_KEY)
+            .and_then(MetadataValue::as_str)
+            .and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+            .is_some_and(|expires_at| expires_at <= self.now);
+        deprecated || expired
+    }
+
+    /// Whether `doc` is dropped by the filters.
+    pub(crate) fn excludes(&self, doc: &Document) -> bool {
+        self.action == StaleAction::Exclude && self.is_stale(doc)
+    }
+
+    /// The factor `doc`'s score is multiplied by, when it is penalized.
+    pub(crate) fn penalty(&self, doc: &Document) -> Option<f32> {
+        match self.action {
+            StaleAction::Penalize { factor } if self.is_stale(doc) => Some(factor),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn apply(&self, scored_docs: &mut [ScoredDocument]) {
+        for sdoc in scored_docs {
+            if let Some(factor) = self.penalty(sdoc.document) {
+                sdoc.score = round6(sdoc.score as f64 * factor as f64);
+            }
+        }
+    }
+}
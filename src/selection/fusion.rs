@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::document::Document;
+use crate::selection::ranking::Scorer;
+use crate::types::context_bundle::{Explanation, FusionConfig, FusionMode, Query, ScoreDetails};
+
+const LEXICAL: &str = "lexical";
+const VECTOR: &str = "vector";
+
+/// Combines a lexical scorer and a vector scorer into one score.
+///
+/// Both component scores are kept in `ScoreDetails::components`; the lexical
+/// scorer's term statistics are passed through so `why` stays meaningful.
+/// Fused scores are rounded to 6 decimal places.
+///
+/// Both inner scorers are finalized over all documents before their scores
+/// are fused, so rank-based scorers such as this one nest correctly.
+pub struct FusionScorer<L, V> {
+    lexical: L,
+    vector: V,
+    config: FusionConfig,
+}
+
+impl<L, V> FusionScorer<L, V>
+where
+    L: Scorer,
+    V: Scorer,
+{
+    pub fn new(lexical: L, vector: V, config: FusionConfig) -> Self {
+        Self {
+            lexical,
+            vector,
+            config,
+        }
+    }
+
+    fn weighted_sum(&self, lexical: f32, vector: f32) -> f32 {
+        let total = self.config.lexical_weight + self.config.vector_weight;
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let sum = self.config.lexical_weight * lexical + self.config.vector_weight * vector;
+        round6((sum / total) as f64)
+    }
+
+    /// Fill `components`, and `score` for a weighted sum, from the finished
+    /// `breakdown`. Rank-based scores are filled in by `finalize`.
+    fn combine(&self, details: &mut ScoreDetails) {
+        let lexical = self.lexical.score_value(&details.breakdown[0]);
+        let vector = self.vector.score_value(&details.breakdown[1]);
+        details.components = BTreeMap::new();
+        details.components.insert(LEXICAL.to_string(), lexical);
+        details.components.insert(VECTOR.to_string(), vector);
+        details.score = match self.config.mode {
+            FusionMode::WeightedSum => Some(self.weighted_sum(lexical, vector)),
+            FusionMode::ReciprocalRank { .. } => None,
+        };
+    }
+}
+
+impl<L, V> Scorer for FusionScorer<L, V>
+where
+    L: Scorer,
+    V: Scorer,
+{
+    fn score(&self, doc: &Document, query: &Query) -> ScoreDetails {
+        let lexical_details = self.lexical.score(doc, query);
+        let vector_details = self.vector.score(doc, query);
+
+        let mut details = ScoreDetails {
+            query_terms: lexical_details.query_terms.clone(),
+            term_matches: lexical_details.term_matches,
+            total_words: lexical_details.total_words,
+            query_phrases: lexical_details.query_phrases.clone(),
+            phrase_matches: lexical_details.phrase_matches,
+            breakdown: vec![lexical_details, vector_details],
+            ..Default::default()
+        };
+        self.combine(&mut details);
+        details
+    }
+
+    fn finalize(&self, docs: &[&Document], details: &mut [ScoreDetails]) {
+        let mut lexical: Vec<ScoreDetails> =
+            details.iter_mut().map(|d| std::mem::take(&mut d.breakdown[0])).collect();
+        self.lexical.finalize(docs, &mut lexical);
+        let mut vector: Vec<ScoreDetails> =
+            details.iter_mut().map(|d| std::mem::take(&mut d.breakdown[1])).collect();
+        self.vector.finalize(docs, &mut vector);
+        for ((d, l), v) in details.iter_mut().zip(lexical).zip(vector) {
+            d.breakdown = vec![l, v];
+            self.combine(d);
+        }
+
+        let FusionMode::ReciprocalRank { k } = self.config.mode else {
+            return;
+        };
+        let k = k as f64;
+        let w_l = self.config.lexical_weight as f64;
+        let w_v = self.config.vector_weight as f64;
+        let max = (w_l + w_v) / (k + 1.0);
+
+        let lexical_ranks = ranks(docs, details, LEXICAL);
+        let vector_ranks = ranks(docs, details, VECTOR);
+
+        for (i, d) in details.iter_mut().enumerate() {
+            let fused = w_l / (k + lexical_ranks[i] as f64) + w_v / (k + vector_ranks[i] as f64);
+            d.score = Some(if max > 0.0 { round6(fused / max) } else { 0.0 });
+            d.explanation = Some(Explanation::Fusion {
+                lexical_rank: lexical_ranks[i],
+                vector_rank: vector_ranks[i],
+            });
+        }
+    }
+
+    fn score_value(&self, details: &ScoreDetails) -> f32 {
+        let score = details.score.unwrap_or(0.0);
+        debug_assert!((0.0..=1.0).contains(&score), "score {score} out of range [0.0, 1.0]");
+        score
+    }
+
+    fn fusion(&self) -> Option<FusionConfig> {
+        Some(self.config)
+    }
+
+    fn analyzer(&self) -> Option<String> {
+        self.lexical.analyzer().or_else(|| self.vector.analyzer())
+    }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "fusion({:?},{},{})",
+            self.config,
+            self.lexical.fingerprint(),
+            self.vector.fingerprint()
+        )
+    }
+}
+
+/// 1-based rank of every document by one component (score desc, id asc).
+fn ranks(docs: &[&Document], details: &[ScoreDetails], component: &str) -> Vec<usize> {
+    let value = |i: usize| details[i].components.get(component).copied().unwrap_or(0.0);
+
+    let mut order: Vec<usize> = (0..docs.len()).collect();
+    order.sort_by(|&a, &b| {
+        value(b)
+            .partial_cmp(&value(a))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| docs[a].id.cmp(&docs[b].id))
+    });
+
+    let mut ranks = vec![0; docs.len()];
+    for (rank, idx) in order.into_iter().enumerate() {
+        ranks[idx] = rank + 1;
+    }
+    ranks
+}
+
+/// Clamp to [0, 1] and round to 6 decimal places.
+pub(crate) fn round6(v: f64) -> f32 {
+    ((v.clamp(0.0, 1.0) * 1_000_000.0).round() / 1_000_000.0) as f32
+}
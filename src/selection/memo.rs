@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+
+use crate::selection::budgeting::BudgetConfig;
+use crate::selection::options::SelectionOptions;
+use crate::types::context_bundle::{Query, SelectionResult};
+
+/// Bounded memo of serialized selection results.
+///
+/// Selection is deterministic, so a result is fully determined by the
+/// cache version, query, budget, options, and pipeline fingerprint; `key`
+/// hashes exactly those. When full, the least recently used entry is
+/// evicted. Safe to share between threads.
+#[derive(Debug)]
+pub struct SelectionCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// key -> (last use, result JSON)
+    entries: HashMap<String, (u64, String)>,
+    /// last use -> key, oldest first
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Persisted {
+    /// (key, result JSON), least recently used first
+    entries: Vec<(String, String)>,
+}
+
+impl SelectionCache {
+    /// A memo holding at most `capacity` results (0 disables memoization).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// Hash of every input that determines a selection result.
+    pub fn key(
+        cache_version: &str,
+        query: &Query,
+        budget: &BudgetConfig,
+        options: &SelectionOptions,
+        pipeline: &str,
+    ) -> String {
+        let inputs = serde_json::json!([cache_version, query.raw, budget, options, pipeline]);
+        format!("sha256:{}", hex::encode(Sha256::digest(inputs.to_string().as_bytes())))
+    }
+
+    pub fn get(&self, key: &str) -> Option<SelectionResult> {
+        let mut inner = self.lock();
+        let json = inner.touch(key)?;
+        serde_json::from_str(&json).ok()
+    }
+
+    pub fn insert(&self, key: String, result: &SelectionResult) {
+        let Ok(json) = serde_json::to_string(result) else {
+            return;
+        };
+        self.insert_json(key, json);
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        *self.lock() = Inner::default();
+    }
+
+    /// Serialize all entries, preserving recency order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let inner = self.lock();
+        let entries = inner
+            .recency
+            .values()
+            .map(|key| (key.clone(), inner.entries[key].1.clone()))
+            .collect();
+        serde_json::to_vec(&Persisted { entries }).expect("strings always serialize")
+    }
+
+    /// Restore entries written by `to_bytes`. If there are more than
+    /// `capacity`, the least recently used are dropped.
+    pub fn from_bytes(capacity: usize, bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        let persisted: Persisted = serde_json::from_slice(bytes)?;
+        let cache = Self::new(capacity);
+        for (key, json) in persisted.entries {
+            cache.insert_json(key, json);
+        }
+        Ok(cache)
+    }
+
+    fn insert_json(&self, key: String, json: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.lock();
+        if inner.touch(&key).is_none() {
+            while inner.entries.len() >= self.capacity {
+                inner.evict_oldest();
+            }
+            let tick = inner.tick();
+            inner.recency.insert(tick, key.clone());
+            inner.entries.insert(key, (tick, json));
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // Entries are replaced whole, so a poisoned lock is still consistent
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Inner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Mark `key` as most recently used and return its JSON.
+    fn touch(&mut self, key: &str) -> Option<String> {
+        let tick = self.tick();
+        let (last, json) = self.entries.get_mut(key)?;
+        let previous = std::mem::replace(last, tick);
+        let json = json.clone();
+        self.recency.remove(&previous);
+        self.recency.insert(tick, key.to_string());
+        Some(json)
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            self.entries.remove(&key);
+        }
+    }
+}
@@ -1,19 +1,79 @@
+pub mod aggregation;
+pub mod analysis;
 pub mod filters;
 pub mod ranking;
+pub mod options;
 pub mod budgeting;
+pub mod candidates;
+pub mod composite;
+pub mod diversity;
+pub mod embedding;
+pub mod fusion;
+pub mod memo;
+pub mod stability;
+pub mod freshness;
+pub mod request;
+mod multi;
+pub mod tokenizers;
+#[cfg(feature = "tantivy")]
+pub mod tantivy_index;
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 
+#[cfg(feature = "async")]
 use crate::cache::ContextCache;
+use crate::cache::{CacheReadError, CacheSnapshot, DocumentLoader, UnreadableDocuments};
+use crate::compat::SELECTION_RESULT_SCHEMA_VERSION;
+use crate::compression::{elide_shared_boilerplate, ExtractiveCompressor, TruncationStrategy};
+use crate::document::metadata::MetadataValue;
+use crate::document::{parser, Document, DocumentId, Metadata};
+use crate::metrics::{self, Metrics, Timer};
 use crate::types::context_bundle::{
-	Query, ScoredDocument, SelectionError, SelectionMetadata, SelectionResult,
+	round_score, BudgetTrace, BudgetVerdict, FilterTrace, FilterVerdict, Query, QueryFusion, ScoreDetails, ScoreTrace,
+	Representation, ScoredDocument, SelectedDocumentRef, SelectionError, SelectionMetadata, SelectionReason,
+	SelectionResult, SelectionResultRef, SelectionTrace, UnreadableTrace, SCORE_DECIMALS,
+};
+#[cfg(feature = "unicode-segmentation")]
+pub use analysis::UnicodeAnalyzer;
+pub use aggregation::ChunkScoring;
+pub use analysis::{AnalyzedScorer, Analyzer, WhitespaceAnalyzer};
+pub use ranking::{
+	ApproxTokenCounter, PatternCache, Scorer, TermFrequencyScorer, TermMatching, TermPattern, TokenCounter,
+};
+pub use budgeting::{
+	apply_budget, apply_budget_ref, apply_budget_with_quotas_ref, BudgetConfig, BudgetQuota, BudgetResult, BudgetStrategy,
+	BudgetUnit, QuotaScope, SelectionLimit, DEFAULT_MAX_BUDGET,
+};
+pub use candidates::CandidateSource;
+pub use filters::PathFilter;
+pub use options::{OnCorrupt, SelectionOptions};
+pub use diversity::{apply_mmr, Mmr, Similarity};
+pub use embedding::EmbeddingScorer;
+pub use composite::CompositeScorer;
+pub use fusion::FusionScorer;
+pub use memo::SelectionCache;
+pub use stability::Stickiness;
+pub use freshness::{Freshness, StaleAction};
+pub use request::SelectionRequest;
+pub use tokenizers::{BytesPerTokenCounter, TokenizerRegistry};
+#[cfg(feature = "tantivy")]
+pub use tantivy_index::{
+	CandidateDocuments, CandidateError, TantivyCandidateSource, DEFAULT_CANDIDATE_LIMIT, TANTIVY_INDEX_FILE,
 };
-pub use ranking::{ApproxTokenCounter, Scorer, TermFrequencyScorer, TokenCounter};
-pub use budgeting::{apply_budget, BudgetResult};
 
 pub struct ContextSelector<S, T> {
 	scorer: S,
 	tokenizer: T,
+	mmr: Option<Mmr>,
+	chunking: Option<ChunkScoring>,
+	query_fusion: QueryFusion,
+	compression: Option<ExtractiveCompressor>,
+	truncation: Option<TruncationStrategy>,
+	max_budget: u64,
+	metrics: Option<Box<dyn Metrics>>,
+	candidates: Option<Box<dyn CandidateSource>>,
 }
 
 impl Default for ContextSelector<TermFrequencyScorer, ApproxTokenCounter> {
@@ -21,6 +81,14 @@ impl Default for ContextSelector<TermFrequencyScorer, ApproxTokenCounter> {
 		Self {
 			scorer: TermFrequencyScorer,
 			tokenizer: ApproxTokenCounter,
+			mmr: None,
+			chunking: None,
+			query_fusion: QueryFusion::Max,
+			compression: None,
+			truncation: None,
+			max_budget: DEFAULT_MAX_BUDGET,
+			metrics: None,
+			candidates: None,
 		}
 	}
 }
@@ -31,75 +99,969 @@ where
 	T: TokenCounter,
 {
 	pub fn new(scorer: S, tokenizer: T) -> Self {
-		Self { scorer, tokenizer }
+		Self {
+			scorer,
+			tokenizer,
+			mmr: None,
+			chunking: None,
+			query_fusion: QueryFusion::Max,
+			compression: None,
+			truncation: None,
+			max_budget: DEFAULT_MAX_BUDGET,
+			metrics: None,
+			candidates: None,
+		}
+	}
+
+	/// Enable maximal-marginal-relevance re-ranking between ordering and budgeting.
+	pub fn with_mmr(mut self, mmr: Mmr) -> Self {
+		self.mmr = Some(mmr);
+		self
+	}
+
+	/// Score chunks instead of whole documents and aggregate to the document.
+	pub fn with_chunk_scoring(mut self, chunking: ChunkScoring) -> Self {
+		self.chunking = Some(chunking);
+		self
+	}
+
+	/// How `select_multi` fuses per-query rankings. Defaults to `Max`.
+	pub fn with_query_fusion(mut self, fusion: QueryFusion) -> Self {
+		self.query_fusion = fusion;
+		self
+	}
+
+	/// After budgeting, admit documents excluded by budget, in rank order,
+	/// reduced by `compressor` to what is left of the budget. Not applied
+	/// when the options set quotas.
+	pub fn with_compression(mut self, compressor: ExtractiveCompressor) -> Self {
+		self.compression = Some(compressor);
+		self
+	}
+
+	/// After budgeting, admit documents excluded by budget, in rank order,
+	/// truncated by `strategy` to what is left of the budget. Tried after
+	/// compression when both are enabled. Not applied when the options set
+	/// quotas.
+	pub fn with_truncation(mut self, strategy: TruncationStrategy) -> Self {
+		self.truncation = Some(strategy);
+		self
+	}
+
+	/// Largest budget total accepted; larger ones (and 0) fail with
+	/// `SelectionError::InvalidBudget`. Defaults to `DEFAULT_MAX_BUDGET`.
+	pub fn with_max_budget(mut self, max_budget: u64) -> Self {
+		self.max_budget = max_budget;
+		self
+	}
+
+	/// Report each selection to `metrics` (see `metrics` for the names).
+	pub fn with_metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+		self.metrics = Some(Box::new(metrics));
+		self
+	}
+
+	/// Score only the documents `source` narrows each query to, falling
+	/// back to every document when it returns `None` (see
+	/// `CandidateSource`).
+	pub fn with_candidate_source(mut self, source: impl CandidateSource + 'static) -> Self {
+		self.candidates = Some(Box::new(source));
+		self
 	}
 
 	pub fn select(
 		&self,
-		cache: &ContextCache,
+		cache: &(impl DocumentLoader + ?Sized),
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+	) -> Result<SelectionResult, SelectionError> {
+		self.select_with(cache, query, budget, &SelectionOptions::default())
+	}
+
+	pub fn select_with(
+		&self,
+		cache: &(impl DocumentLoader + ?Sized),
 		query: Query,
-		budget: usize,
+		budget: impl Into<BudgetConfig>,
+		options: &SelectionOptions,
 	) -> Result<SelectionResult, SelectionError> {
+		let budget = budget.into();
+		budget.validate(self.max_budget)?;
 		// 0. Load documents strictly from manifest to ensure authoritativeness
-		let loaded_docs = cache.load_documents().map_err(|_| SelectionError::CacheError)?;
+		let (loaded_docs, unreadable) = load(cache, options, self.metrics.as_deref())?;
+		let mut result = self.select_from(&loaded_docs, cache.cache_version(), query, budget, options).into_owned();
+		record_unreadable(&mut result.selection, unreadable, options);
+		Ok(result)
+	}
+
+	/// Run a `SelectionRequest`; equivalent to `select_with` on its parsed
+	/// query, budget, and options.
+	pub fn select_request(
+		&self,
+		cache: &(impl DocumentLoader + ?Sized),
+		request: &SelectionRequest,
+	) -> Result<SelectionResult, SelectionError> {
+		self.select_with(cache, request.to_query()?, request.budget, &request.to_options())
+	}
+
+	/// `select_with`, memoized in `memo` when `cache` reports a cache
+	/// version. Returns exactly what `select_with` would.
+	pub fn select_cached(
+		&self,
+		memo: &SelectionCache,
+		cache: &(impl DocumentLoader + ?Sized),
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+		options: &SelectionOptions,
+	) -> Result<SelectionResult, SelectionError> {
+		let budget = budget.into();
+		let Some(cache_version) = cache.cache_version() else {
+			return self.select_with(cache, query, budget, options);
+		};
+
+		let key = SelectionCache::key(&cache_version, &query, &budget, options, &self.fingerprint());
+		if let Some(result) = memo.get(&key) {
+			return Ok(result);
+		}
+		let result = self.select_with(cache, query, budget, options)?;
+		memo.insert(key, &result);
+		Ok(result)
+	}
+
+	/// Identifies the scorer, token counter, MMR settings, and candidate
+	/// source.
+	pub fn fingerprint(&self) -> String {
+		let mut fingerprint = format!(
+			"scorer={};tokenizer={};mmr={:?};chunking={:?};compression={:?};truncation={:?}",
+			self.scorer.fingerprint(),
+			self.tokenizer.fingerprint(),
+			self.mmr.as_ref().map(Mmr::info),
+			self.chunking.as_ref().map(|c| c.info(0)),
+			self.compression,
+			self.truncation
+		);
+		if let Some(source) = &self.candidates {
+			fingerprint.push_str(&format!(";candidates={}", source.fingerprint()));
+		}
+		fingerprint
+	}
+
+	/// Async `select`: documents are loaded with `load_documents_async`.
+	#[cfg(feature = "async")]
+	pub async fn select_async(
+		&self,
+		cache: &ContextCache,
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+	) -> Result<SelectionResult, SelectionError> {
+		self.select_with_async(cache, query, budget, &SelectionOptions::default()).await
+	}
+
+	#[cfg(feature = "async")]
+	pub async fn select_with_async(
+		&self,
+		cache: &ContextCache,
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+		options: &SelectionOptions,
+	) -> Result<SelectionResult, SelectionError> {
+		let budget = budget.into();
+		budget.validate(self.max_budget)?;
+		let timer = self.metrics.as_ref().map(|_| Timer::start());
+		let (loaded_docs, unreadable) = match options.on_corrupt {
+			OnCorrupt::Fail => (cache.load_documents_async().await?, Vec::new()),
+			OnCorrupt::Skip => cache.load_readable_documents_async().await,
+		};
+		if let (Some(metrics), Some(timer)) = (self.metrics.as_deref(), timer) {
+			timer.record(metrics, metrics::CACHE_LOAD_MS);
+		}
+		let cache_version = Some(cache.manifest.cache_version.clone());
+		let mut result = self.select_from(&loaded_docs, cache_version, query, budget, options).into_owned();
+		record_unreadable(&mut result.selection, unreadable, options);
+		Ok(result)
+	}
+
+	/// Select from a preloaded snapshot. No I/O and no re-verification;
+	/// results are identical to `select` on the cache it was loaded from.
+	pub fn select_snapshot(
+		&self,
+		snapshot: &CacheSnapshot,
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+	) -> Result<SelectionResult, SelectionError> {
+		self.select_snapshot_with(snapshot, query, budget, &SelectionOptions::default())
+	}
+
+	pub fn select_snapshot_with(
+		&self,
+		snapshot: &CacheSnapshot,
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+		options: &SelectionOptions,
+	) -> Result<SelectionResult, SelectionError> {
+		Ok(self.select_snapshot_ref(snapshot, query, budget, options)?.into_owned())
+	}
+
+	/// Like `select_snapshot_with`, but selected documents borrow their
+	/// content from `snapshot` instead of copying it.
+	pub fn select_snapshot_ref<'a>(
+		&self,
+		snapshot: &'a CacheSnapshot,
+		query: Query,
+		budget: impl Into<BudgetConfig>,
+		options: &SelectionOptions,
+	) -> Result<SelectionResultRef<'a>, SelectionError> {
+		let budget = budget.into();
+		budget.validate(self.max_budget)?;
+		Ok(self.select_from(snapshot.documents(), snapshot.cache_version(), query, budget, options))
+	}
+
+	/// Select for several queries at once: each query is scored on its own,
+	/// the rankings are fused (see `with_query_fusion`), and the fused
+	/// ranking is budgeted once. A document is a candidate when it passes
+	/// the path filter and the field clauses of at least one query.
+	pub fn select_multi(
+		&self,
+		cache: &(impl DocumentLoader + ?Sized),
+		queries: &[Query],
+		budget: impl Into<BudgetConfig>,
+	) -> Result<SelectionResult, SelectionError> {
+		self.select_multi_with(cache, queries, budget, &SelectionOptions::default())
+	}
+
+	pub fn select_multi_with(
+		&self,
+		cache: &(impl DocumentLoader + ?Sized),
+		queries: &[Query],
+		budget: impl Into<BudgetConfig>,
+		options: &SelectionOptions,
+	) -> Result<SelectionResult, SelectionError> {
+		let budget = budget.into();
+		budget.validate(self.max_budget)?;
+		let (loaded_docs, unreadable) = load(cache, options, self.metrics.as_deref())?;
+		let mut result = self.select_multi_from(&loaded_docs, cache.cache_version(), queries, budget, options).into_owned();
+		record_unreadable(&mut result.selection, unreadable, options);
+		Ok(result)
+	}
+
+	fn select_from<'a>(
+		&self,
+		documents: &'a [Document],
+		cache_version: Option<String>,
+		query: Query,
+		budget: BudgetConfig,
+		options: &SelectionOptions,
+	) -> SelectionResultRef<'a> {
+		let mut loaded_docs: Vec<&Document> = documents.iter().collect();
+		let documents_considered = loaded_docs.len();
+
+		// 0b. Filtering Phase (optional): path filter, query field clauses, then staleness
+		if let Some(filter) = &options.path_filter {
+			loaded_docs.retain(|doc| filter.matches(&doc.id));
+		}
+		if !query.fields.is_empty() {
+			loaded_docs.retain(|doc| query.matches_fields(doc));
+		}
+		loaded_docs.retain(|doc| !options.excludes_stale(doc));
+		let filtered = options.path_filter.is_some() || !query.fields.is_empty() || excludes_stale(options);
+		let documents_excluded_by_filter = filtered.then(|| documents_considered - loaded_docs.len());
+		let filter_trace = options.trace.then(|| trace_filter(documents, options, |doc| query.matches_fields(doc)));
+
+		// 0c. Candidate Phase (optional): keep the source's candidates and pinned documents
+		let narrowed = self.narrow(&query, cache_version.as_deref());
+		if let Some(ids) = &narrowed {
+			loaded_docs.retain(|doc| is_candidate(ids, doc, options));
+		}
+		let candidate_count = self.candidates.is_some().then_some(loaded_docs.len());
+		let candidate_fallback = self.candidates.is_some() && narrowed.is_none();
 
 		// 1. Scoring Phase
-		let mut scored_docs: Vec<ScoredDocument> = loaded_docs
+		let (scored_docs, chunks_scored) = self.score_documents(&loaded_docs, &query);
+
+		self.rank_and_budget(
+			scored_docs,
+			Candidates {
+				documents,
+				query: query.raw,
+				cache_version,
+				documents_considered,
+				documents_excluded_by_filter,
+				chunks_scored,
+				filter_trace,
+				candidate_count,
+				candidate_fallback,
+			},
+			budget,
+			options,
+		)
+	}
+
+	fn select_multi_from<'a>(
+		&self,
+		documents: &'a [Document],
+		cache_version: Option<String>,
+		queries: &[Query],
+		budget: BudgetConfig,
+		options: &SelectionOptions,
+	) -> SelectionResultRef<'a> {
+		let mut loaded_docs: Vec<&Document> = documents.iter().collect();
+		let documents_considered = loaded_docs.len();
+
+		if let Some(filter) = &options.path_filter {
+			loaded_docs.retain(|doc| filter.matches(&doc.id));
+		}
+		loaded_docs.retain(|doc| !options.excludes_stale(doc));
+		let has_fields = queries.iter().any(|query| !query.fields.is_empty());
+		let filter_trace = options.trace.then(|| {
+			trace_filter(documents, options, |doc| !has_fields || queries.iter().any(|query| query.matches_fields(doc)))
+		});
+		let passed_filter = if has_fields {
+			loaded_docs.iter().filter(|doc| queries.iter().any(|query| query.matches_fields(doc))).count()
+		} else {
+			loaded_docs.len()
+		};
+
+		// Candidates per query (optional): `None` scores every document
+		let narrowed: Vec<Option<BTreeSet<DocumentId>>> =
+			queries.iter().map(|query| self.narrow(query, cache_version.as_deref())).collect();
+		let scored_for = |query: &Query, ids: &Option<BTreeSet<DocumentId>>, doc: &Document| {
+			query.matches_fields(doc) && ids.as_ref().map_or(true, |ids| is_candidate(ids, doc, options))
+		};
+		let candidate_count = self.candidates.is_some().then(|| {
+			let scored = |doc: &Document| queries.iter().zip(&narrowed).any(|(query, ids)| scored_for(query, ids, doc));
+			loaded_docs.iter().filter(|doc| scored(doc)).count()
+		});
+		let candidate_fallback = self.candidates.is_some() && narrowed.iter().any(Option::is_none);
+
+		// Score and order per query, then fuse
+		let mut chunks_scored: Option<usize> = None;
+		let rankings = queries
 			.iter()
-			.map(|doc| {
-				let details = self.scorer.score(doc, &query);
-				let score = self.scorer.score_value(&details);
-				let token_count = self.tokenizer.count_tokens(&doc.content);
-				ScoredDocument {
+			.zip(&narrowed)
+			.map(|(query, ids)| {
+				let query_docs: Vec<&Document> =
+					loaded_docs.iter().copied().filter(|doc| scored_for(query, ids, doc)).collect();
+				let (mut scored_docs, chunks) = self.score_documents(&query_docs, query);
+				chunks_scored = chunks.map(|n| chunks_scored.unwrap_or(0) + n);
+				sort_ranked(&mut scored_docs);
+				scored_docs
+			})
+			.collect();
+		let mut fused = multi::fuse(rankings, self.query_fusion);
+		let filtered = options.path_filter.is_some() || has_fields || excludes_stale(options);
+		let documents_excluded_by_filter = filtered.then(|| documents_considered - passed_filter);
+
+		let raw: Vec<&str> = queries.iter().map(|query| query.raw.as_str()).collect();
+		let mut result = self.rank_and_budget(
+			std::mem::take(&mut fused.scored),
+			Candidates {
+				documents,
+				query: raw.join(" | "),
+				cache_version,
+				documents_considered,
+				documents_excluded_by_filter,
+				chunks_scored,
+				filter_trace,
+				candidate_count,
+				candidate_fallback,
+			},
+			budget,
+			options,
+		);
+		result.selection.query_fusion = Some(self.query_fusion);
+		result.selection.queries = fused.contributions(queries, &result.documents);
+		result
+	}
+
+	/// The candidate source's ids for `query`, or `None` (no source, or it
+	/// fell back) to score every document.
+	fn narrow(&self, query: &Query, cache_version: Option<&str>) -> Option<BTreeSet<DocumentId>> {
+		self.candidates.as_ref()?.narrow(query, cache_version)
+	}
+
+	/// Score `docs` (per chunk, then aggregated, when chunking is enabled)
+	/// and count tokens. Also returns the number of chunks scored.
+	fn score_documents<'a>(&self, docs: &[&'a Document], query: &Query) -> (Vec<ScoredDocument<'a>>, Option<usize>) {
+		let (scored, chunks_scored) = match &self.chunking {
+			Some(chunking) => {
+				let (scored, chunks_scored) = chunking.score(&self.scorer, docs, query);
+				(scored, Some(chunks_scored))
+			}
+			None => {
+				let mut all_details: Vec<ScoreDetails> = docs
+					.iter()
+					.map(|doc| self.scorer.score(doc, query))
+					.collect();
+				self.scorer.finalize(docs, &mut all_details);
+				let scored = all_details
+					.into_iter()
+					.map(|details| {
+						let score = self.scorer.score_value(&details);
+						(details, score)
+					})
+					.collect();
+				(scored, None)
+			}
+		};
+
+		let scored_docs = docs
+			.iter()
+			.zip(scored)
+			.map(|(doc, (details, score))| {
+				let span = match &self.chunking {
+					Some(chunking) if chunking.is_best_chunk_only() => details.chunks.first().map(|c| c.span),
+					_ => None,
+				};
+				let mut sdoc = ScoredDocument {
 					document: doc,
 					score,
 					score_details: details,
-					token_count,
-				}
+					span,
+					token_count: 0,
+				};
+				sdoc.token_count = self.tokenizer.count_tokens(sdoc.content()) as u64;
+				sdoc
 			})
 			.collect();
+		(scored_docs, chunks_scored)
+	}
 
-		// 2. Ordering Phase
-		// Sort globally by (score desc, id asc)
-		scored_docs.sort_by(|a, b| {
-			// Descending score
-			let score_cmp = b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal);
-			if score_cmp != Ordering::Equal {
-				score_cmp
-			} else {
-				// Ascending ID
-				a.document.id.cmp(&b.document.id)
+	/// Threshold, order, diversify, and budget scored candidates.
+	fn rank_and_budget<'a>(
+		&self,
+		mut scored_docs: Vec<ScoredDocument<'a>>,
+		candidates: Candidates<'a>,
+		budget: BudgetConfig,
+		options: &SelectionOptions,
+	) -> SelectionResultRef<'a> {
+		let documents_scored = scored_docs.len();
+		let mut score_trace: Option<Vec<ScoreTrace>> = options.trace.then(|| {
+			scored_docs
+				.iter()
+				.map(|sdoc| ScoreTrace {
+					id: sdoc.document.id.as_str().to_string(),
+					raw_score: sdoc.score,
+					passed_threshold: options.is_pinned(sdoc.document.id.as_str())
+						|| options.passes_score_threshold(sdoc.score),
+					boost: None,
+					penalty: None,
+				})
+				.collect()
+		});
+
+		// 1b. Score Threshold Phase (optional)
+		let documents_excluded_by_score = options.has_score_threshold().then(|| {
+			let before = scored_docs.len();
+			scored_docs.retain(|sdoc| {
+				options.is_pinned(sdoc.document.id.as_str()) || options.passes_score_threshold(sdoc.score)
+			});
+			before - scored_docs.len()
+		});
+
+		// 1c. Stability Phase (optional)
+		if let Some(stickiness) = &options.stickiness {
+			stickiness.apply(&mut scored_docs);
+		}
+		if let (Some(score_trace), Some(stickiness)) = (&mut score_trace, &options.stickiness) {
+			// Thresholding keeps order, and ordering has not happened yet
+			let passed = score_trace.iter_mut().filter(|entry| entry.passed_threshold);
+			for (entry, sdoc) in passed.zip(&scored_docs) {
+				entry.boost = (sdoc.score != entry.raw_score).then_some(stickiness.bonus);
 			}
+		}
+
+		// 1d. Freshness Phase (optional): penalize deprecated and expired documents
+		if let Some(freshness) = &options.freshness {
+			freshness.apply(&mut scored_docs);
+			if let Some(score_trace) = &mut score_trace {
+				let passed = score_trace.iter_mut().filter(|entry| entry.passed_threshold);
+				for (entry, sdoc) in passed.zip(&scored_docs) {
+					entry.penalty = freshness.penalty(sdoc.document);
+				}
+			}
+		}
+
+		// 2. Ordering Phase
+		sort_ranked(&mut scored_docs);
+
+		// 2b. Diversification Phase (optional)
+		if let Some(mmr) = &self.mmr {
+			scored_docs = apply_mmr(scored_docs, mmr);
+		}
+
+		// 2c. Pinning Phase (optional); the sort is stable
+		if !options.pinned.is_empty() {
+			scored_docs.sort_by_key(|sdoc| {
+				options.pinned.iter().position(|id| id == sdoc.document.id.as_str()).unwrap_or(usize::MAX)
+			});
+		}
+
+		// Every document, as linked documents need not be candidates
+		let metadata_by_id: BTreeMap<&str, &Metadata> = if options.include_metadata {
+			candidates
+				.documents
+				.iter()
+				.map(|doc| (doc.id.as_str(), &doc.metadata))
+				.collect()
+		} else {
+			BTreeMap::new()
+		};
+
+		let ranked: Option<Vec<(&str, f32, u64)>> = options.trace.then(|| {
+			scored_docs
+				.iter()
+				.map(|sdoc| (sdoc.document.id.as_str(), sdoc.score, sdoc.token_count))
+				.collect()
 		});
 
-		debug_assert!(
-			scored_docs.windows(2).all(|w| {
-				let a = &w[0];
-				let b = &w[1];
-				a.score > b.score || (a.score == b.score && a.document.id <= b.document.id)
-			})
-		);
+		let reduce = (self.compression.is_some()
+			|| options.summary_fallback
+			|| options.outline_fallback
+			|| self.truncation.is_some())
+			&& options.quotas.is_empty();
+		let fallback = reduce.then(|| scored_docs.clone());
+		let link_pool = (options.include_linked && options.quotas.is_empty()).then(|| scored_docs.clone());
 
 		// 3. Budgeting Phase
+		let mut budgeted = apply_budget_with_quotas_ref(scored_docs, &budget, options.limit, &options.quotas);
+
+		// 3b. Compression / Summary / Outline / Truncation Phase (optional)
+		let mut reduced = ReducedCounts::default();
+		if let Some(fallback) = fallback {
+			// Re-parsing the raw text gives the query's terms and phrases
+			// (for a multi-query selection, those of every query)
+			let query = Query::new(candidates.query.as_str());
+			reduced = self.reduce_excluded(&query, fallback, &budget, options, &mut budgeted);
+		}
+
+		// 3c. Link Phase (optional)
+		let documents_linked = options.include_linked.then(|| match link_pool {
+			Some(ranked) => self.include_linked(ranked, candidates.documents, &budget, options, &mut budgeted),
+			None => 0,
+		});
+
 		let BudgetResult {
-			selected,
-			tokens_used,
+			mut selected,
+			mut tokens_used,
 			documents_selected,
 			documents_excluded_by_budget,
-		} = apply_budget(scored_docs, budget);
+			documents_excluded_by_limit,
+			documents_excluded_by_quota,
+			quotas,
+			verdicts,
+		} = budgeted;
+		for doc in &mut selected {
+			doc.metadata = metadata_by_id.get(doc.id).copied();
+		}
+
+		// 3d. Boilerplate Phase (optional)
+		let boilerplate_tokens_saved = options.dedupe_boilerplate.then(|| {
+			let saved = self.dedupe_boilerplate(&mut selected, &budget);
+			tokens_used = tokens_used.saturating_sub(saved);
+			saved
+		});
+		if options.omit_content {
+			selected.iter_mut().for_each(|doc| doc.content = Cow::Borrowed(""));
+		}
+		let score_precision = options.score_precision.filter(|&p| p < SCORE_DECIMALS);
+		if let Some(decimals) = score_precision {
+			selected.iter_mut().for_each(|doc| doc.score = round_score(doc.score, decimals) as f32);
+		}
+
+		let trace = ranked.map(|ranked| SelectionTrace {
+			filter: candidates.filter_trace.unwrap_or_default(),
+			scores: score_trace.unwrap_or_default(),
+			budget: ranked
+				.into_iter()
+				.zip(verdicts)
+				.enumerate()
+				.map(|(rank, ((id, score, tokens), verdict))| BudgetTrace {
+					id: id.to_string(),
+					rank,
+					score,
+					tokens,
+					verdict,
+				})
+				.collect(),
+			unreadable: Vec::new(),
+		});
 
 		let metadata = SelectionMetadata {
-			query: query.raw,
-			budget,
+			query: candidates.query,
+			budget: budget.total,
+			budget_unit: (!budget.unit.is_tokens()).then_some(budget.unit),
+			budget_reserved: (budget.reserved > 0).then_some(budget.reserved),
+			budget_per_document_overhead: (budget.per_document_overhead > 0)
+				.then_some(budget.per_document_overhead),
+			budget_strategy: (!budget.strategy.is_greedy()).then_some(budget.strategy),
 			tokens_used,
-			documents_considered: loaded_docs.len(),
+			documents_considered: candidates.documents_considered,
 			documents_selected,
 			documents_excluded_by_budget,
+			limit: options.limit.top_k(),
+			documents_excluded_by_limit: options.limit.top_k().map(|_| documents_excluded_by_limit),
+			documents_excluded_by_filter: candidates.documents_excluded_by_filter,
+			documents_excluded_by_score,
+			documents_excluded_by_quota: (!options.quotas.is_empty()).then_some(documents_excluded_by_quota),
+			quotas,
+			documents_unreadable: None,
+			documents_compressed: self.compression.is_some().then_some(reduced.compressed),
+			documents_summarized: options.summary_fallback.then_some(reduced.summarized),
+			documents_outlined: options.outline_fallback.then_some(reduced.outlined),
+			documents_truncated: self.truncation.is_some().then_some(reduced.truncated),
+			documents_linked,
+			boilerplate_tokens_saved,
+			content_omitted: options.omit_content,
+			score_precision,
+			churn: options.stickiness.as_ref().map(|stickiness| stickiness.churn(&selected)),
+			query_fusion: None,
+			queries: Vec::new(),
+			fusion: self.scorer.fusion(),
+			analyzer: self.scorer.analyzer(),
+			tokenizer: self.tokenizer.name(),
+			mmr: self.mmr.as_ref().map(Mmr::info),
+			chunking: self.chunking.as_ref().zip(candidates.chunks_scored).map(|(c, n)| c.info(n)),
+			trace,
+			cache_version: candidates.cache_version,
+			document_count_in_cache: Some(candidates.documents_considered),
+			scorer: Some(self.scorer.fingerprint()),
+			token_counter: Some(self.tokenizer.fingerprint()),
+			candidate_count: candidates.candidate_count,
+			candidate_fallback: candidates.candidate_fallback,
 		};
 
-		Ok(SelectionResult {
+		if let Some(metrics) = &self.metrics {
+			metrics.counter(metrics::DOCUMENTS_SCORED, documents_scored as u64);
+			metrics.counter(metrics::TOKENS_SELECTED, metadata.tokens_used);
+			let available = budget.available();
+			if available > 0 {
+				metrics.histogram(metrics::BUDGET_UTILIZATION, metadata.tokens_used as f64 / available as f64);
+			}
+		}
+
+		SelectionResultRef {
+			schema_version: SELECTION_RESULT_SCHEMA_VERSION,
 			documents: selected,
 			selection: metadata,
+		}
+	}
+
+	/// Elide boilerplate shared across `selected` (in rank order) and
+	/// recount tokens. Returns the budget units saved.
+	fn dedupe_boilerplate(&self, selected: &mut [SelectedDocumentRef], budget: &BudgetConfig) -> u64 {
+		let contents: Vec<&str> = selected.iter().map(|doc| doc.content.as_ref()).collect();
+		let elided = elide_shared_boilerplate(&contents);
+
+		let mut saved: u64 = 0;
+		for (doc, content) in selected.iter_mut().zip(elided) {
+			let Some(content) = content else {
+				continue;
+			};
+			let tokens = self.tokenizer.count_tokens(&content) as u64;
+			saved = saved.saturating_add(budget
+				.unit
+				.measure_text(&doc.content, doc.tokens)
+				.saturating_sub(budget.unit.measure_text(&content, tokens)));
+			doc.content = Cow::Owned(content);
+			doc.tokens = tokens;
+		}
+		saved
+	}
+
+	/// Admit `budgeted`'s documents excluded by budget, reduced to fit what
+	/// is left of the budget, in rank order: compressed, else summarized
+	/// (with `summary_fallback`), else outlined (with `outline_fallback`,
+	/// Markdown only), else truncated. `ranked` is the
+	/// budgeted ranking. With `StopAtFirstExclusion`, only the document that
+	/// stopped selection is tried.
+	fn reduce_excluded<'a>(
+		&self,
+		query: &Query,
+		ranked: Vec<ScoredDocument<'a>>,
+		budget: &BudgetConfig,
+		options: &SelectionOptions,
+		budgeted: &mut BudgetResult<SelectedDocumentRef<'a>>,
+	) -> ReducedCounts {
+		let available = budget.available();
+		let max_documents = options.limit.top_k().unwrap_or(usize::MAX);
+		let mut counts = ReducedCounts::default();
+
+		for (i, sdoc) in ranked.into_iter().enumerate() {
+			if budgeted.verdicts[i] != BudgetVerdict::ExcludedByBudget {
+				continue;
+			}
+			if budgeted.documents_selected >= max_documents {
+				break;
+			}
+
+			let remaining = available.saturating_sub(budgeted.tokens_used);
+			// The token counter counts in usize, budgets in u64
+			let cost =
+				|text: &str, tokens: usize| budget.unit.measure_text(text, tokens as u64).saturating_add(budget.per_document_overhead);
+			let fits = |text: &str, tokens: usize| cost(text, tokens) <= remaining;
+			let fits_text = |text: &str| fits(text, self.tokenizer.count_tokens(text));
+			let content = sdoc.content();
+			let reduced = self
+				.compression
+				.and_then(|compressor| compressor.compress_to(content, query, &self.tokenizer, fits))
+				.map(|(text, tokens)| (text, tokens, BudgetVerdict::Compressed))
+				.or_else(|| {
+					let summary = sdoc.document.summary.as_deref().filter(|_| options.summary_fallback)?;
+					let tokens = self.tokenizer.count_tokens(summary);
+					fits(summary, tokens).then(|| (summary.to_string(), tokens, BudgetVerdict::Summarized))
+				})
+				.or_else(|| {
+					if !options.outline_fallback || !parser::is_markdown_path(sdoc.document.id.as_str()) {
+						return None;
+					}
+					// The longest run of leading outline entries that fits
+					let outline = parser::markdown_outline(content);
+					let keep = (1..=outline.len()).take_while(|&k| fits_text(&outline[..k].join("\n"))).last()?;
+					let text = outline[..keep].join("\n");
+					let tokens = self.tokenizer.count_tokens(&text);
+					Some((text, tokens, BudgetVerdict::Outlined))
+				})
+				.or_else(|| {
+					let text = self.truncation?.truncate_with(content, fits_text)?;
+					let tokens = self.tokenizer.count_tokens(&text);
+					Some((text, tokens, BudgetVerdict::Truncated))
+				});
+			if let Some((text, tokens, verdict)) = reduced {
+				// Keep rank order among the selected documents
+				let position = budgeted.verdicts[..i].iter().filter(|verdict| verdict.is_selected()).count();
+				let original_tokens = sdoc.token_count;
+				let mut doc = budgeting::select_ref(sdoc);
+				budgeted.tokens_used = budgeted.tokens_used.saturating_add(cost(&text, tokens));
+				doc.content = Cow::Owned(text);
+				doc.tokens = tokens as u64;
+				doc.original_tokens = Some(original_tokens);
+				match verdict {
+					BudgetVerdict::Compressed => counts.compressed += 1,
+					BudgetVerdict::Summarized => {
+						doc.representation = Some(Representation::Summary);
+						counts.summarized += 1;
+					}
+					BudgetVerdict::Outlined => {
+						doc.representation = Some(Representation::Outline);
+						counts.outlined += 1;
+					}
+					_ => {
+						doc.truncation = self.truncation;
+						counts.truncated += 1;
+					}
+				}
+				budgeted.selected.insert(position, doc);
+				budgeted.documents_selected += 1;
+				budgeted.documents_excluded_by_budget -= 1;
+				budgeted.verdicts[i] = verdict;
+			}
+			if budget.strategy == BudgetStrategy::StopAtFirstExclusion {
+				break;
+			}
+		}
+		counts
+	}
+
+	/// Admit the documents that `budgeted`'s selected documents link to
+	/// (`parser::LINKS_KEY`), one hop: by the linking document's rank, then
+	/// in link order, each while it fits what is left of the budget and the
+	/// limit. `ranked` is the budgeted ranking; linked documents outside it
+	/// are scored 0. Returns the number admitted.
+	fn include_linked<'a>(
+		&self,
+		ranked: Vec<ScoredDocument<'a>>,
+		documents: &'a [Document],
+		budget: &BudgetConfig,
+		options: &SelectionOptions,
+		budgeted: &mut BudgetResult<SelectedDocumentRef<'a>>,
+	) -> usize {
+		let available = budget.available();
+		let max_documents = options.limit.top_k().unwrap_or(usize::MAX);
+		let by_id: BTreeMap<&str, &'a Document> = documents.iter().map(|doc| (doc.id.as_str(), doc)).collect();
+		let linking: Vec<&'a str> = budgeted.selected.iter().map(|doc| doc.id).collect();
+		let mut linked = 0;
+
+		for source in linking {
+			let Some(MetadataValue::List(links)) = by_id.get(source).and_then(|doc| doc.metadata.get(parser::LINKS_KEY))
+			else {
+				continue;
+			};
+			for target in links.iter().filter_map(MetadataValue::as_str) {
+				// Links keep the case they were written in; IDs usually do not
+				let found = by_id.get(target).or_else(|| by_id.get(target.to_lowercase().as_str()));
+				let Some(&document) = found else {
+					continue;
+				};
+				if budgeted.selected.iter().any(|doc| doc.id == document.id.as_str())
+					|| options.path_filter.as_ref().is_some_and(|filter| !filter.matches(&document.id))
+					|| options.excludes_stale(document)
+				{
+					continue;
+				}
+				if budgeted.documents_selected >= max_documents {
+					return linked;
+				}
+
+				let rank = ranked.iter().position(|sdoc| sdoc.document.id == document.id);
+				let sdoc = match rank {
+					Some(i) => ranked[i].clone(),
+					None => ScoredDocument {
+						document,
+						score: 0.0,
+						score_details: ScoreDetails::default(),
+						span: None,
+						token_count: self.tokenizer.count_tokens(&document.content) as u64,
+					},
+				};
+				let cost = budget
+					.unit
+					.measure_text(sdoc.content(), sdoc.token_count)
+					.saturating_add(budget.per_document_overhead);
+				if cost > available.saturating_sub(budgeted.tokens_used) {
+					continue;
+				}
+				let mut doc = budgeting::select_ref(sdoc);
+				doc.why.reason = Some(SelectionReason::Linked);
+				doc.why.linked_from = Some(source.to_string());
+				budgeted.selected.push(doc);
+				budgeted.tokens_used = budgeted.tokens_used.saturating_add(cost);
+				budgeted.documents_selected += 1;
+				if let Some(i) = rank {
+					if budgeted.verdicts[i] == BudgetVerdict::ExcludedByBudget {
+						budgeted.documents_excluded_by_budget -= 1;
+					}
+					budgeted.verdicts[i] = BudgetVerdict::Linked;
+				}
+				linked += 1;
+			}
+		}
+		linked
+	}
+}
+
+/// Documents `reduce_excluded` admitted, by how they were reduced.
+#[derive(Default)]
+struct ReducedCounts {
+	compressed: usize,
+	summarized: usize,
+	outlined: usize,
+	truncated: usize,
+}
+
+/// What the filtering and scoring phases report into `SelectionMetadata`,
+/// and the documents they started from.
+struct Candidates<'a> {
+	documents: &'a [Document],
+	query: String,
+	cache_version: Option<String>,
+	documents_considered: usize,
+	documents_excluded_by_filter: Option<usize>,
+	chunks_scored: Option<usize>,
+	filter_trace: Option<Vec<FilterTrace>>,
+	candidate_count: Option<usize>,
+	candidate_fallback: bool,
+}
+
+/// Whether `doc` is among `ids`, or pinned (and so always scored).
+fn is_candidate(ids: &BTreeSet<DocumentId>, doc: &Document, options: &SelectionOptions) -> bool {
+	ids.contains(&doc.id) || options.is_pinned(doc.id.as_str())
+}
+
+/// Filter verdicts for `documents`, in order. `matches_fields` applies the
+/// query field clauses.
+fn trace_filter(
+	documents: &[Document],
+	options: &SelectionOptions,
+	matches_fields: impl Fn(&Document) -> bool,
+) -> Vec<FilterTrace> {
+	documents
+		.iter()
+		.map(|doc| {
+			let verdict = if options.path_filter.as_ref().is_some_and(|filter| !filter.matches(&doc.id)) {
+				FilterVerdict::ExcludedByPath
+			} else if !matches_fields(doc) {
+				FilterVerdict::ExcludedByFields
+			} else if options.excludes_stale(doc) {
+				FilterVerdict::ExcludedAsStale
+			} else {
+				FilterVerdict::Passed
+			};
+			FilterTrace {
+				id: doc.id.as_str().to_string(),
+				verdict,
+			}
 		})
+		.collect()
+}
+
+/// Load `cache`'s documents as `options.on_corrupt` says, with the
+/// documents that could not be read when skipping them. The time taken is
+/// reported to `metrics`.
+fn load(
+	cache: &(impl DocumentLoader + ?Sized),
+	options: &SelectionOptions,
+	metrics: Option<&dyn Metrics>,
+) -> Result<(Vec<Document>, UnreadableDocuments), CacheReadError> {
+	let timer = metrics.map(|_| Timer::start());
+	let loaded = match options.on_corrupt {
+		OnCorrupt::Fail => (cache.load_documents()?, Vec::new()),
+		OnCorrupt::Skip => cache.load_readable_documents()?,
+	};
+	if let (Some(metrics), Some(timer)) = (metrics, timer) {
+		timer.record(metrics, metrics::CACHE_LOAD_MS);
+	}
+	Ok(loaded)
+}
+
+/// Count (and trace) the documents left out under `OnCorrupt::Skip`. They
+/// are part of the cache, but were never considered.
+fn record_unreadable(
+	selection: &mut SelectionMetadata,
+	unreadable: UnreadableDocuments,
+	options: &SelectionOptions,
+) {
+	if options.on_corrupt != OnCorrupt::Skip {
+		return;
 	}
+	selection.documents_unreadable = Some(unreadable.len());
+	if let Some(count) = &mut selection.document_count_in_cache {
+		*count += unreadable.len();
+	}
+	if let Some(trace) = &mut selection.trace {
+		trace.unreadable = unreadable
+			.into_iter()
+			.map(|(id, e)| UnreadableTrace {
+				id: id.as_str().to_string(),
+				error: e.to_string(),
+			})
+			.collect();
+	}
+}
+
+/// Whether `options.freshness` excludes stale documents rather than
+/// penalizing them.
+fn excludes_stale(options: &SelectionOptions) -> bool {
+	options.freshness.is_some_and(|freshness| freshness.action == StaleAction::Exclude)
+}
+
+/// Sort globally by (score desc, id asc).
+fn sort_ranked(scored_docs: &mut [ScoredDocument]) {
+	scored_docs.sort_by(|a, b| {
+		// Descending score
+		let score_cmp = b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal);
+		if score_cmp != Ordering::Equal {
+			score_cmp
+		} else {
+			// Ascending ID
+			a.document.id.cmp(&b.document.id)
+		}
+	});
+
+	debug_assert!(
+		scored_docs.windows(2).all(|w| {
+			let a = &w[0];
+			let b = &w[1];
+			a.score > b.score || (a.score == b.score && a.document.id <= b.document.id)
+		})
+	);
 }
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use crate::selection::fusion::round6;
+use crate::types::context_bundle::{
+    Query, QueryContribution, QueryFusion, ScoredDocument, SelectedDocumentRef,
+};
+
+/// Per-query rankings fused into one scored list, with what each query
+/// contributed.
+pub(crate) struct FusedRanking<'a> {
+    /// One entry per document, in id order.
+    pub scored: Vec<ScoredDocument<'a>>,
+    /// Per document id: the contributing query and the queries that matched.
+    attribution: BTreeMap<&'a str, (usize, Vec<bool>)>,
+}
+
+struct Entry<'a> {
+    /// Contributing query, its contribution, and its scored document.
+    best: Option<(usize, f64, ScoredDocument<'a>)>,
+    fused: f64,
+    matched: Vec<bool>,
+}
+
+/// Fuse `rankings` (one per query, each sorted score desc, id asc).
+///
+/// A document's `why`, span, and token count are taken from the query that
+/// contributed most to it; ties go to the earlier query. With
+/// `ReciprocalRank`, only documents a query scored above zero are ranked.
+pub(crate) fn fuse(rankings: Vec<Vec<ScoredDocument<'_>>>, fusion: QueryFusion) -> FusedRanking<'_> {
+    let n = rankings.len();
+    let mut entries: BTreeMap<&str, Entry> = BTreeMap::new();
+
+    for (q, ranking) in rankings.into_iter().enumerate() {
+        let mut rank = 0;
+        for sdoc in ranking {
+            let matched = sdoc.score > 0.0;
+            let contribution = match fusion {
+                QueryFusion::Max => sdoc.score as f64,
+                QueryFusion::ReciprocalRank { k } if matched => {
+                    rank += 1;
+                    1.0 / (k as f64 + rank as f64)
+                }
+                QueryFusion::ReciprocalRank { .. } => 0.0,
+            };
+
+            let entry = entries.entry(sdoc.document.id.as_str()).or_insert_with(|| Entry {
+                best: None,
+                fused: 0.0,
+                matched: vec![false; n],
+            });
+            entry.matched[q] = matched;
+            match fusion {
+                QueryFusion::Max => entry.fused = entry.fused.max(contribution),
+                QueryFusion::ReciprocalRank { .. } => entry.fused += contribution,
+            }
+            if entry.best.as_ref().map_or(true, |(_, best, _)| contribution > *best) {
+                entry.best = Some((q, contribution, sdoc));
+            }
+        }
+    }
+
+    let mut scored = Vec::with_capacity(entries.len());
+    let mut attribution = BTreeMap::new();
+    for (id, entry) in entries {
+        let Some((q, _, mut sdoc)) = entry.best else {
+            continue;
+        };
+        sdoc.score = match fusion {
+            QueryFusion::Max => entry.fused as f32,
+            QueryFusion::ReciprocalRank { k } => round6(entry.fused * (k as f64 + 1.0) / n as f64),
+        };
+        scored.push(sdoc);
+        attribution.insert(id, (q, entry.matched));
+    }
+
+    FusedRanking { scored, attribution }
+}
+
+impl FusedRanking<'_> {
+    pub(crate) fn contributions(&self, queries: &[Query], selected: &[SelectedDocumentRef]) -> Vec<QueryContribution> {
+        queries
+            .iter()
+            .enumerate()
+            .map(|(q, query)| {
+                let selected_attribution = || selected.iter().filter_map(|doc| self.attribution.get(doc.id));
+                QueryContribution {
+                    query: query.raw.clone(),
+                    documents_matched: self.attribution.values().filter(|(_, matched)| matched[q]).count(),
+                    documents_selected: selected_attribution().filter(|(_, matched)| matched[q]).count(),
+                    documents_contributed: selected_attribution().filter(|(best, _)| *best == q).count(),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,135 @@
+use crate::document::Document;
+use crate::selection::budgeting::{BudgetQuota, SelectionLimit};
+use crate::selection::filters::PathFilter;
+use crate::selection::freshness::Freshness;
+use crate::selection::stability::Stickiness;
+
+/// Per-call selection options. `Default` reproduces plain `select()`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SelectionOptions {
+    /// Restrict candidates by document id before scoring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_filter: Option<PathFilter>,
+
+    /// Cap on the number of selected documents, combined with the token budget.
+    #[serde(default)]
+    pub limit: SelectionLimit,
+
+    /// Drop documents scoring below this after scoring, before budgeting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+
+    /// Drop documents scoring exactly 0.0 after scoring, before budgeting.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exclude_zero_score: bool,
+
+    /// Per-scope budget constraints, applied during budgeting.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotas: Vec<BudgetQuota>,
+
+    /// Favor a previous selection's documents, after the score thresholds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stickiness: Option<Stickiness>,
+
+    /// Exclude or down-rank deprecated and expired documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<Freshness>,
+
+    /// Leave selected documents' `content` empty; ids, scores, token
+    /// counts, and all metadata counts are unchanged.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub omit_content: bool,
+
+    /// Elide leading and trailing blocks a higher-ranked selected document
+    /// already holds (see `elide_shared_boilerplate`), after budgeting.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dedupe_boilerplate: bool,
+
+    /// Admit documents that do not fit the budget as the summary stored at
+    /// cache build time (see `CacheBuildConfig::with_summarizer`), when it
+    /// fits. Tried after compression and before the outline.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub summary_fallback: bool,
+
+    /// Admit Markdown documents (`.md` or `.markdown` ids) that do not fit
+    /// the budget as their outline (see `markdown_outline`), cut to what is
+    /// left of it. Tried after compression and before truncation.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub outline_fallback: bool,
+
+    /// After budgeting, admit documents that selected documents link to
+    /// (their `links` metadata, see `markdown_links`), one hop, while they
+    /// fit what is left of the budget and the limit. Linked documents pass
+    /// the path filter but skip the field clauses and score thresholds. Not
+    /// applied when quotas are set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_linked: bool,
+
+    /// Round selected scores to this many decimal places (at most
+    /// `SCORE_DECIMALS`, which scores are always written with) after
+    /// budgeting, so ranking is unaffected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_precision: Option<u32>,
+
+    /// Copy each selected document's metadata into the result.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_metadata: bool,
+
+    /// Whether a document that cannot be read fails the selection or is
+    /// left out. Applies to selections that load documents.
+    #[serde(default, skip_serializing_if = "OnCorrupt::is_fail")]
+    pub on_corrupt: OnCorrupt,
+
+    /// Record every stage decision in `SelectionMetadata::trace`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trace: bool,
+
+    /// Document ids ranked ahead of every other candidate, in this order.
+    /// Pinned documents skip the score thresholds but not filtering, and
+    /// are still subject to the budget, limit, and quotas. Unknown ids are
+    /// ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<String>,
+}
+
+/// What selection does with a document that fails to load (a
+/// `CacheReadError`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnCorrupt {
+    /// Fail the selection with `SelectionError::CacheError`.
+    #[default]
+    Fail,
+    /// Leave the document out, counting it in
+    /// `SelectionMetadata::documents_unreadable` and listing it, with its
+    /// error, in the trace.
+    Skip,
+}
+
+impl OnCorrupt {
+    fn is_fail(&self) -> bool {
+        *self == OnCorrupt::Fail
+    }
+}
+
+impl SelectionOptions {
+    /// Whether either score threshold is set.
+    pub(crate) fn has_score_threshold(&self) -> bool {
+        self.min_score.is_some() || self.exclude_zero_score
+    }
+
+    /// Whether `freshness` excludes `doc`.
+    pub(crate) fn excludes_stale(&self, doc: &Document) -> bool {
+        self.freshness.as_ref().is_some_and(|freshness| freshness.excludes(doc))
+    }
+
+    pub(crate) fn is_pinned(&self, id: &str) -> bool {
+        self.pinned.iter().any(|pinned| pinned == id)
+    }
+
+    /// Whether a document with `score` passes the score thresholds.
+    pub(crate) fn passes_score_threshold(&self, score: f32) -> bool {
+        let min_ok = self.min_score.map_or(true, |min| score >= min);
+        min_ok && !(self.exclude_zero_score && score == 0.0)
+    }
+}
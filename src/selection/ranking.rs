@@ -1,14 +1,39 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
 use crate::document::Document;
-use crate::types::context_bundle::{Query, ScoreDetails};
+use crate::types::context_bundle::{FusionConfig, Query, ScoreDetails};
 
 pub trait Scorer {
     fn score(&self, doc: &Document, query: &Query) -> ScoreDetails;
 
+    /// Corpus-level pass over every document's details, run after `score`
+    /// and before `score_value`. `docs` and `details` are index-aligned.
+    /// Used by scorers whose result depends on rank (e.g. reciprocal rank fusion).
+    fn finalize(&self, _docs: &[&Document], _details: &mut [ScoreDetails]) {}
+
+    /// Fusion parameters to record in `SelectionMetadata`, if any.
+    fn fusion(&self) -> Option<FusionConfig> {
+        None
+    }
+
+    /// Name of the word analyzer this scorer segments text with, recorded
+    /// in `SelectionMetadata::analyzer`. `None` for the v0 whitespace split.
+    fn analyzer(&self) -> Option<String> {
+        None
+    }
+
+    /// Identifies this scorer and its parameters in memoized selection
+    /// keys. Scorers with parameters must include them.
+    fn fingerprint(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
     fn score_value(&self, details: &ScoreDetails) -> f32 {
         let score = if details.total_words == 0 {
             0.0
         } else {
-            details.term_matches as f32 / details.total_words as f32
+            (details.term_matches + details.phrase_matches) as f32 / details.total_words as f32
         };
         debug_assert!((0.0..=1.0).contains(&score), "score {score} out of range [0.0, 1.0]");
         score
@@ -24,33 +49,304 @@ impl Scorer for TermFrequencyScorer {
         // Spec: total_words is defined as split(content, whitespace).len() after lowercasing.
         let content_lower = doc.content.to_lowercase();
         let words: Vec<&str> = content_lower.split_whitespace().collect();
-        let total_words = words.len();
+        term_frequency(&words, &query.terms, &query.phrases, None)
+    }
 
-        let term_matches = if total_words == 0 || query.terms.is_empty() {
-            0
-        } else {
-            let mut count = 0;
-            // Naive count: occurrences of ANY query term
-            for word in &words {
-                for term in &query.terms {
-                    if word == term {
-                        count += 1;
-                    }
-                }
+    fn fingerprint(&self) -> String {
+        "term_frequency".to_string()
+    }
+}
+
+/// Term frequency over pre-segmented, lowercased `words`. With
+/// `patterns` (index-aligned with `terms`), terms are matched by pattern
+/// instead of equality.
+pub(crate) fn term_frequency(
+    words: &[&str],
+    terms: &[String],
+    phrases: &[Vec<String>],
+    patterns: Option<&[Arc<TermPattern>]>,
+) -> ScoreDetails {
+    let total_words = words.len();
+
+    // Positions covered by a phrase occurrence count once, as phrase
+    // matches, and are not counted again as term matches
+    let in_phrase = phrase_positions(words, phrases);
+    let phrase_matches = in_phrase.iter().filter(|&&covered| covered).count();
+
+    let term_matches = if total_words == 0 || terms.is_empty() {
+        0
+    } else {
+        let mut count = 0;
+        // Naive count: occurrences of ANY query term
+        for (word, _) in words.iter().zip(&in_phrase).filter(|(_, &covered)| !covered) {
+            match patterns {
+                None => count += terms.iter().filter(|term| word == term).count(),
+                Some(patterns) => count += patterns.iter().filter(|p| p.matches(word)).count(),
             }
-            count
-        };
+        }
+        count
+    };
+
+    ScoreDetails {
+        query_terms: terms.to_vec(),
+        term_matches,
+        total_words,
+        query_phrases: phrases.iter().map(|p| p.join(" ")).collect(),
+        phrase_matches,
+        score: None,
+        components: BTreeMap::new(),
+        explanation: None,
+        chunks: Vec::new(),
+        breakdown: Vec::new(),
+    }
+}
+
+/// How query terms match document words. Phrases always match exactly.
+/// Non-exhaustive, as `Regex` exists only with the `regex` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TermMatching {
+    /// Word equality (the fast path).
+    #[default]
+    Exact,
+    /// `*` matches any run of characters and `?` exactly one; terms without
+    /// either match exactly.
+    Wildcard,
+    /// As `Wildcard`, and terms written `/pattern/` are regular expressions
+    /// that must match the whole word. An invalid pattern matches literally.
+    #[cfg(feature = "regex")]
+    Regex,
+}
+
+impl TermMatching {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TermMatching::Exact => "exact",
+            TermMatching::Wildcard => "wildcard",
+            #[cfg(feature = "regex")]
+            TermMatching::Regex => "regex",
+        }
+    }
+}
+
+/// A query term compiled for a `TermMatching` mode.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TermPattern {
+    Exact(String),
+    Wildcard(Vec<char>),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl TermPattern {
+    pub fn compile(term: &str, mode: TermMatching) -> Self {
+        #[cfg(feature = "regex")]
+        if mode == TermMatching::Regex && term.len() > 2 && term.starts_with('/') && term.ends_with('/') {
+            if let Ok(re) = regex::Regex::new(&format!("^(?:{})$", &term[1..term.len() - 1])) {
+                return TermPattern::Regex(re);
+            }
+        }
+        if mode != TermMatching::Exact && term.contains(['*', '?']) {
+            return TermPattern::Wildcard(term.chars().collect());
+        }
+        TermPattern::Exact(term.to_string())
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        match self {
+            TermPattern::Exact(term) => word == term,
+            TermPattern::Wildcard(pattern) => {
+                let word: Vec<char> = word.chars().collect();
+                wildcard_match(pattern, &word)
+            }
+            #[cfg(feature = "regex")]
+            TermPattern::Regex(re) => re.is_match(word),
+        }
+    }
+}
+
+/// Iterative `*`/`?` matcher with single-star backtracking: linear in
+/// practice and never exponential.
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Compiled `TermPattern`s by term, shared across the documents of a
+/// selection and across selections. Cleared when it reaches `capacity`.
+#[derive(Debug)]
+pub struct PatternCache {
+    mode: TermMatching,
+    capacity: usize,
+    compiled: Mutex<HashMap<String, Arc<TermPattern>>>,
+}
+
+impl PatternCache {
+    pub fn new(mode: TermMatching) -> Self {
+        Self {
+            mode,
+            capacity: 1024,
+            compiled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn mode(&self) -> TermMatching {
+        self.mode
+    }
 
-        ScoreDetails {
-            query_terms: query.terms.clone(),
-            term_matches,
-            total_words,
+    /// Compiled patterns for `terms`, index-aligned.
+    pub fn patterns(&self, terms: &[String]) -> Vec<Arc<TermPattern>> {
+        // Compiled patterns are immutable, so a poisoned map is still valid
+        let mut compiled = self.compiled.lock().unwrap_or_else(|e| e.into_inner());
+        if compiled.len() + terms.len() > self.capacity {
+            compiled.clear();
         }
+        terms
+            .iter()
+            .map(|term| {
+                let pattern = compiled
+                    .entry(term.clone())
+                    .or_insert_with(|| Arc::new(TermPattern::compile(term, self.mode)));
+                Arc::clone(pattern)
+            })
+            .collect()
     }
 }
 
+impl Clone for PatternCache {
+    fn clone(&self) -> Self {
+        Self::new(self.mode)
+    }
+}
+
+impl Default for PatternCache {
+    fn default() -> Self {
+        Self::new(TermMatching::Exact)
+    }
+}
+
+/// For each word position, whether it falls inside an occurrence of any
+/// phrase (the phrase's words adjacent and in order).
+fn phrase_positions(words: &[&str], phrases: &[Vec<String>]) -> Vec<bool> {
+    let mut covered = vec![false; words.len()];
+    if phrases.is_empty() {
+        return covered;
+    }
+
+    let mut positions: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, word) in words.iter().enumerate() {
+        positions.entry(word).or_default().push(i);
+    }
+
+    for phrase in phrases {
+        let Some(starts) = positions.get(phrase[0].as_str()) else {
+            continue;
+        };
+        for &start in starts {
+            let end = start + phrase.len();
+            if end <= words.len() && words[start..end].iter().zip(phrase).all(|(w, p)| w == p) {
+                covered[start..end].iter_mut().for_each(|c| *c = true);
+            }
+        }
+    }
+    covered
+}
+
 pub trait TokenCounter {
     fn count_tokens(&self, content: &str) -> usize;
+
+    /// Identifies this counter in memoized selection keys.
+    fn fingerprint(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    /// Name recorded in `SelectionMetadata::tokenizer`. `None` for counters
+    /// not obtained from a `TokenizerRegistry`.
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    /// Longest prefix of `content` that counts at most `max_tokens`, and its
+    /// count. The prefix always ends on a UTF-8 boundary.
+    ///
+    /// The default binary-searches prefix lengths, assuming counts never
+    /// decrease as a prefix grows. Counters with token boundaries (BPE)
+    /// should override it so a prefix never ends inside a token.
+    fn truncate_to<'a>(&self, content: &'a str, max_tokens: usize) -> (&'a str, usize) {
+        let total = self.count_tokens(content);
+        if total <= max_tokens {
+            return (content, total);
+        }
+
+        // Invariant: prefix(lo) fits, prefix(hi) does not
+        let (mut lo, mut hi) = (0, content.len());
+        while hi - lo > 1 {
+            let half = lo + (hi - lo) / 2;
+            let mut mid = floor_char_boundary(content, half);
+            if mid == lo {
+                mid = (half..hi).find(|&i| content.is_char_boundary(i)).unwrap_or(hi);
+            }
+            if mid == hi {
+                // No char boundary strictly between lo and hi
+                break;
+            }
+            if self.count_tokens(&content[..mid]) <= max_tokens {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let prefix = &content[..lo];
+        (prefix, self.count_tokens(prefix))
+    }
+}
+
+/// Largest char boundary of `s` at or below `index` (`index <= s.len()`).
+pub(crate) fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+impl<T: TokenCounter + ?Sized> TokenCounter for Box<T> {
+    fn count_tokens(&self, content: &str) -> usize {
+        (**self).count_tokens(content)
+    }
+
+    fn fingerprint(&self) -> String {
+        (**self).fingerprint()
+    }
+
+    fn name(&self) -> Option<String> {
+        (**self).name()
+    }
+
+    fn truncate_to<'a>(&self, content: &'a str, max_tokens: usize) -> (&'a str, usize) {
+        (**self).truncate_to(content, max_tokens)
+    }
 }
 
 /// v0: Approximate GPT-style tokenization
@@ -60,11 +356,16 @@ pub struct ApproxTokenCounter;
 
 impl TokenCounter for ApproxTokenCounter {
     fn count_tokens(&self, content: &str) -> usize {
-        // Integer division ceil(len / 4) equivalent to (len + 4 - 1) / 4
-        if content.is_empty() {
-            0
-        } else {
-            (content.len() + 3) / 4
-        }
+        // Integer division ceil(len / 4)
+        content.len().div_ceil(4)
+    }
+
+    fn fingerprint(&self) -> String {
+        "approx(4)".to_string()
+    }
+
+    fn truncate_to<'a>(&self, content: &'a str, max_tokens: usize) -> (&'a str, usize) {
+        let prefix = &content[..floor_char_boundary(content, content.len().min(max_tokens.saturating_mul(4)))];
+        (prefix, self.count_tokens(prefix))
     }
 }
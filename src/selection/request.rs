@@ -0,0 +1,73 @@
+use crate::selection::budgeting::BudgetConfig;
+use crate::selection::options::SelectionOptions;
+use crate::types::context_bundle::SelectionError;
+use crate::types::query::Query;
+
+/// Everything one selection takes, in a form that can be sent over the
+/// wire, logged, and replayed with `ContextSelector::select_request`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SelectionRequest {
+    /// Query text, parsed with `Query::new`.
+    pub query: String,
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub options: SelectionOptions,
+    /// Field clauses (`tag:runbook`, `path:docs/**`, `title:deploy`) added
+    /// to those in `query`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+    /// Document ids pinned ahead of `options.pinned` (see
+    /// `SelectionOptions::pinned`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<String>,
+}
+
+impl SelectionRequest {
+    pub fn new(query: impl Into<String>, budget: impl Into<BudgetConfig>) -> Self {
+        Self {
+            query: query.into(),
+            budget: budget.into(),
+            options: SelectionOptions::default(),
+            filters: Vec::new(),
+            pinned: Vec::new(),
+        }
+    }
+
+    pub fn options(mut self, options: SelectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn filter(mut self, clause: impl Into<String>) -> Self {
+        self.filters.push(clause.into());
+        self
+    }
+
+    pub fn pin(mut self, id: impl Into<String>) -> Self {
+        self.pinned.push(id.into());
+        self
+    }
+
+    /// The parsed query, with `filters` added to its field clauses. The
+    /// query's `raw` text is `query` alone.
+    pub fn to_query(&self) -> Result<Query, SelectionError> {
+        let mut query = Query::new(self.query.as_str());
+        for clause in &self.filters {
+            let parsed = Query::new(clause.as_str());
+            if parsed.fields.len() != 1 || !parsed.terms.is_empty() || !parsed.phrases.is_empty() {
+                return Err(SelectionError::InvalidFilter(clause.clone()));
+            }
+            query.fields.extend(parsed.fields);
+        }
+        Ok(query)
+    }
+
+    /// `options` with `pinned` prepended to its pinned ids.
+    pub fn to_options(&self) -> SelectionOptions {
+        let mut options = self.options.clone();
+        if !self.pinned.is_empty() {
+            options.pinned = self.pinned.iter().chain(&self.options.pinned).cloned().collect();
+        }
+        options
+    }
+}
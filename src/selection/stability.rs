@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use crate::selection::fusion::round6;
+use crate::types::context_bundle::{ChurnStats, ScoredDocument, SelectedDocumentRef, SelectionResult};
+
+/// Prefer the documents a previous selection chose, so context does not
+/// churn between turns.
+///
+/// A candidate whose id and version match a previous document gets `bonus`
+/// added to its score (rounded to 6 decimal places, capped at 1.0) before
+/// ordering. A newcomer therefore displaces it only by outscoring it by
+/// more than `bonus`. Documents scoring 0.0 get no bonus, and a changed
+/// version counts as a new document.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Stickiness {
+    /// Previously selected document versions, by id.
+    pub previous: BTreeMap<String, String>,
+    pub bonus: f32,
+}
+
+impl Stickiness {
+    pub fn new<I, S>(previous: I, bonus: f32) -> Self
+    where
+        I: IntoIterator<Item = (S, S)>,
+        S: Into<String>,
+    {
+        Self {
+            previous: previous.into_iter().map(|(id, version)| (id.into(), version.into())).collect(),
+            bonus,
+        }
+    }
+
+    /// Stick to the documents `result` selected.
+    pub fn from_result(result: &SelectionResult, bonus: f32) -> Self {
+        Self::new(
+            result.documents.iter().map(|d| (d.id.as_str(), d.version.as_str())),
+            bonus,
+        )
+    }
+
+    fn is_unchanged(&self, id: &str, version: &str) -> bool {
+        self.previous.get(id).is_some_and(|v| v == version)
+    }
+
+    pub(crate) fn apply(&self, scored_docs: &mut [ScoredDocument]) {
+        for sdoc in scored_docs {
+            if sdoc.score > 0.0 && self.is_unchanged(sdoc.document.id.as_str(), sdoc.document.version.as_str()) {
+                sdoc.score = round6(sdoc.score as f64 + self.bonus as f64);
+            }
+        }
+    }
+
+    pub(crate) fn churn(&self, selected: &[SelectedDocumentRef]) -> ChurnStats {
+        let mut stats = ChurnStats {
+            bonus: self.bonus,
+            retained: 0,
+            changed: 0,
+            added: 0,
+            dropped: 0,
+        };
+        for doc in selected {
+            match self.previous.get(doc.id) {
+                Some(version) if version == doc.version => stats.retained += 1,
+                Some(_) => stats.changed += 1,
+                None => stats.added += 1,
+            }
+        }
+        stats.dropped = self.previous.len() - stats.retained - stats.changed;
+        stats
+    }
+}
@@ -0,0 +1,304 @@
+//! Candidate generation from a tantivy index, for corpora too large to
+//! score every document per query.
+//!
+//! With `CacheBuildConfig::with_tantivy_index`, a build writes an index of
+//! every document's content to `tantivy.tar`. `TantivyCandidateSource`
+//! ranks a query's top-N documents with it, and `load` reads only those
+//! documents from the cache, so a selection over them re-scores N
+//! documents with this crate's scorers rather than the whole cache. Only
+//! the candidate set comes from tantivy: the final ranking is the
+//! selector's, and as deterministic as on any other input. As a
+//! `CandidateSource`, it narrows a `ContextSelector`'s selections the same
+//! way, for loaders that already hold every document.
+//!
+//! The index holds one segment, with documents added in id order on one
+//! thread and a fixed segment id, so the same documents give the same
+//! bytes (and the same `tree_hash`) on every build.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+
+use tantivy::collector::TopDocs;
+use tantivy::directory::RamDirectory;
+use tantivy::merge_policy::NoMergePolicy;
+use tantivy::query::{BooleanQuery, Occur, Query as TantivyQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{Directory, Index, IndexReader, ReloadPolicy, TantivyDocument, Term};
+use thiserror::Error;
+
+use crate::cache::archive::{read_entries, write_entry};
+use crate::cache::{CacheReadError, ContextCache, DocumentLoader, MemoryCache};
+use crate::document::Document;
+use crate::selection::CandidateSource;
+use crate::types::identifiers::DocumentId;
+use crate::types::Query;
+
+/// Name of the index file in a cache directory.
+pub const TANTIVY_INDEX_FILE: &str = "tantivy.tar";
+
+/// Candidates per query `CandidateSource::narrow` returns, unless set with
+/// `TantivyCandidateSource::with_limit`.
+pub const DEFAULT_CANDIDATE_LIMIT: usize = 1000;
+
+/// Indexing memory for the single writer thread. Larger corpora are
+/// written as several segments and merged into one.
+const WRITER_MEMORY: usize = 64 * 1024 * 1024;
+
+/// Replaces tantivy's random segment id (as written in file names, and in
+/// `meta.json`).
+const SEGMENT_ID: &str = "00000000000000000000000000000000";
+const SEGMENT_ID_HYPHENATED: &str = "00000000-0000-0000-0000-000000000000";
+
+#[derive(Debug, Error)]
+pub enum CandidateError {
+    #[error("Cache has no tantivy index (see CacheBuildConfig::with_tantivy_index)")]
+    MissingIndex,
+    #[error("Tantivy error: {0}")]
+    Tantivy(#[from] tantivy::TantivyError),
+    #[error("Invalid tantivy index: {0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Read(#[from] CacheReadError),
+}
+
+/// The `id` and `content` fields every index is built with.
+fn schema() -> (Schema, Field, Field) {
+    let mut builder = Schema::builder();
+    let id = builder.add_text_field("id", STRING | STORED);
+    let content = builder.add_text_field("content", TEXT);
+    (builder.build(), id, content)
+}
+
+/// Index `documents`, which must be sorted by id, into the bytes of
+/// `TANTIVY_INDEX_FILE`.
+pub(crate) fn build_index<'a>(documents: impl IntoIterator<Item = &'a Document>) -> Result<Vec<u8>, CandidateError> {
+    let (schema, id, content) = schema();
+    let index = Index::create_in_ram(schema);
+    let mut writer = index.writer_with_num_threads::<TantivyDocument>(1, WRITER_MEMORY)?;
+    writer.set_merge_policy(Box::new(NoMergePolicy));
+    for document in documents {
+        let mut doc = TantivyDocument::new();
+        doc.add_text(id, document.id.as_str());
+        doc.add_text(content, &document.content);
+        writer.add_document(doc)?;
+    }
+    writer.commit()?;
+    let segments = index.searchable_segment_ids()?;
+    if segments.len() > 1 {
+        writer.merge(&segments).wait()?;
+    }
+    writer.wait_merging_threads()?;
+
+    // Name the segment (at most one) the same on every build
+    let metas = index.load_metas()?;
+    let directory = index.directory();
+    let read = |name: &Path| directory.atomic_read(name).map_err(io::Error::other);
+    let mut meta = String::from_utf8_lossy(&read(Path::new("meta.json"))?).into_owned();
+    let mut files = Vec::new();
+    for segment in &metas.segments {
+        let random = segment.id().uuid_string();
+        let hyphenated = [&random[..8], &random[8..12], &random[12..16], &random[16..20], &random[20..]].join("-");
+        meta = meta.replace(&hyphenated, SEGMENT_ID_HYPHENATED).replace(&random, SEGMENT_ID);
+        // Lists every component a segment may have, not only those written
+        for name in segment.list_files() {
+            if !directory.exists(&name).map_err(io::Error::other)? {
+                continue;
+            }
+            let renamed = name.to_str().expect("tantivy file names are ASCII").replace(&random, SEGMENT_ID);
+            files.push((renamed, read(&name)?));
+        }
+    }
+    files.push(("meta.json".to_string(), meta.into_bytes()));
+    files.sort();
+
+    let mut out = Vec::new();
+    for (name, bytes) in &files {
+        write_entry(&mut out, Path::new(name), bytes)?;
+    }
+    out.extend_from_slice(&[0u8; 1024]);
+    Ok(out)
+}
+
+/// Ranks a cache's documents for a query with its tantivy index (BM25 over
+/// document content), as a cheap first pass before this crate's scorers.
+pub struct TantivyCandidateSource {
+    index: Index,
+    reader: IndexReader,
+    id: Field,
+    content: Field,
+    limit: usize,
+    cache_version: Option<String>,
+}
+
+impl std::fmt::Debug for TantivyCandidateSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TantivyCandidateSource")
+            .field("limit", &self.limit)
+            .field("cache_version", &self.cache_version)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TantivyCandidateSource {
+    /// Read `cache`'s index into memory. Fails with
+    /// `CandidateError::MissingIndex` if it was built without one.
+    pub fn open(cache: &ContextCache) -> Result<Self, CandidateError> {
+        let path = cache.root.join(TANTIVY_INDEX_FILE);
+        if !cache.store().exists(&path) {
+            return Err(CandidateError::MissingIndex);
+        }
+        let mut source = Self::from_bytes(&cache.store().read_file(&path)?)?;
+        source.cache_version = Some(cache.manifest.cache_version.clone());
+        Ok(source)
+    }
+
+    /// Like `open`, for a cache built in memory.
+    pub fn from_memory(cache: &MemoryCache) -> Result<Self, CandidateError> {
+        let mut source = Self::from_bytes(cache.tantivy_index().ok_or(CandidateError::MissingIndex)?)?;
+        source.cache_version = Some(cache.manifest().cache_version.clone());
+        Ok(source)
+    }
+
+    /// Read an index from the bytes of a `TANTIVY_INDEX_FILE`. Unlike
+    /// `open`, the source is not tied to a cache version, so `narrow`
+    /// answers for any cache.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CandidateError> {
+        let directory = RamDirectory::create();
+        for (name, contents) in read_entries(bytes)? {
+            directory.atomic_write(&name, contents)?;
+        }
+        let index = Index::open(directory)?;
+        let (expected, _, _) = schema();
+        if index.schema() != expected {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected tantivy schema").into());
+        }
+        let reader = index.reader_builder().reload_policy(ReloadPolicy::Manual).try_into()?;
+        let (_, id, content) = schema();
+        Ok(Self {
+            index,
+            reader,
+            id,
+            content,
+            limit: DEFAULT_CANDIDATE_LIMIT,
+            cache_version: None,
+        })
+    }
+
+    /// Candidates per query `narrow` returns. Defaults to
+    /// `DEFAULT_CANDIDATE_LIMIT`.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Ids of the (at most) `limit` documents that best match `query`'s
+    /// terms and phrase words, any of them, by BM25; ties go to the smaller
+    /// id. Field clauses are left to the selector. A query without terms
+    /// has no candidates.
+    pub fn candidates(&self, query: &Query, limit: usize) -> Result<Vec<DocumentId>, CandidateError> {
+        let terms = self.terms(query)?;
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let clauses = terms
+            .into_iter()
+            .map(|text| {
+                let term = Term::from_field_text(self.content, &text);
+                (Occur::Should, Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)) as Box<dyn TantivyQuery>)
+            })
+            .collect();
+
+        let searcher = self.reader.searcher();
+        let top = searcher.search(&BooleanQuery::new(clauses), &TopDocs::with_limit(limit))?;
+        let mut ranked = Vec::with_capacity(top.len());
+        for (score, address) in top {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let id = doc
+                .get_first(self.id)
+                .and_then(|value| value.as_str())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "indexed document without id"))?;
+            ranked.push((score, DocumentId::from_stored(id.to_string())));
+        }
+        // TopDocs breaks ties by index order, which is id order; make it
+        // explicit
+        ranked.sort_by(|(a_score, a_id), (b_score, b_id)| b_score.total_cmp(a_score).then_with(|| a_id.cmp(b_id)));
+        Ok(ranked.into_iter().map(|(_, id)| id).collect())
+    }
+
+    /// `query`'s terms and phrase words, as the index tokenized content.
+    fn terms(&self, query: &Query) -> Result<BTreeSet<String>, CandidateError> {
+        let mut analyzer = self.index.tokenizer_for_field(self.content)?;
+        let mut terms = BTreeSet::new();
+        for word in query.terms.iter().chain(query.phrases.iter().flatten()) {
+            analyzer.token_stream(word).process(&mut |token| {
+                terms.insert(token.text.clone());
+            });
+        }
+        Ok(terms)
+    }
+
+    /// Load `query`'s candidates from `cache`, reading only their files.
+    pub fn load(&self, cache: &ContextCache, query: &Query, limit: usize) -> Result<CandidateDocuments, CandidateError> {
+        let ids: BTreeSet<DocumentId> = self.candidates(query, limit)?.into_iter().collect();
+        let documents = cache.load_documents_where(|id| ids.contains(id))?;
+        Ok(CandidateDocuments { documents })
+    }
+
+    /// Like `load`, for a cache built in memory.
+    pub fn load_memory(&self, cache: &MemoryCache, query: &Query, limit: usize) -> Result<CandidateDocuments, CandidateError> {
+        let mut ids = self.candidates(query, limit)?;
+        ids.sort();
+        let documents = ids.iter().filter_map(|id| cache.get(id).cloned()).collect();
+        Ok(CandidateDocuments { documents })
+    }
+}
+
+/// Falls back (`None`) for queries without terms (field clauses alone),
+/// for caches other than the one the index was opened from, and when
+/// tantivy fails.
+impl CandidateSource for TantivyCandidateSource {
+    fn narrow(&self, query: &Query, cache_version: Option<&str>) -> Option<BTreeSet<DocumentId>> {
+        if let (Some(expected), Some(found)) = (&self.cache_version, cache_version) {
+            if expected != found {
+                return None;
+            }
+        }
+        if self.terms(query).ok()?.is_empty() {
+            return None;
+        }
+        Some(self.candidates(query, self.limit).ok()?.into_iter().collect())
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("tantivy(limit={})", self.limit)
+    }
+}
+
+/// The candidates `TantivyCandidateSource::load` read for a query, in id
+/// order. Select from it like from a cache: only these documents are
+/// scored.
+#[derive(Debug, Clone)]
+pub struct CandidateDocuments {
+    documents: Vec<Document>,
+}
+
+impl CandidateDocuments {
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+}
+
+impl DocumentLoader for CandidateDocuments {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        Ok(self.documents.clone())
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::selection::ranking::{floor_char_boundary, ApproxTokenCounter, TokenCounter};
+
+/// Estimates tokens as `ceil(bytes * den / num)`, i.e. `num / den` bytes
+/// per token. Integer arithmetic keeps counts identical on every platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BytesPerTokenCounter {
+    num: usize,
+    den: usize,
+}
+
+impl BytesPerTokenCounter {
+    /// `num / den` bytes per token. Zero parts are treated as 1.
+    pub fn new(num: usize, den: usize) -> Self {
+        Self {
+            num: num.max(1),
+            den: den.max(1),
+        }
+    }
+}
+
+impl TokenCounter for BytesPerTokenCounter {
+    fn count_tokens(&self, content: &str) -> usize {
+        (content.len() * self.den).div_ceil(self.num)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("bytes_per_token({}/{})", self.num, self.den)
+    }
+
+    fn truncate_to<'a>(&self, content: &'a str, max_tokens: usize) -> (&'a str, usize) {
+        let max_bytes = max_tokens.saturating_mul(self.num) / self.den;
+        let prefix = &content[..floor_char_boundary(content, content.len().min(max_bytes))];
+        (prefix, self.count_tokens(prefix))
+    }
+}
+
+/// A counter from a `TokenizerRegistry`, which reports the name it was
+/// registered under in `SelectionMetadata::tokenizer`.
+struct NamedTokenCounter {
+    name: String,
+    inner: Arc<dyn TokenCounter + Send + Sync>,
+}
+
+impl TokenCounter for NamedTokenCounter {
+    fn count_tokens(&self, content: &str) -> usize {
+        self.inner.count_tokens(content)
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("{}={}", self.name, self.inner.fingerprint())
+    }
+
+    fn truncate_to<'a>(&self, content: &'a str, max_tokens: usize) -> (&'a str, usize) {
+        self.inner.truncate_to(content, max_tokens)
+    }
+}
+
+/// Token counters by target model name.
+///
+/// Built in (all deterministic estimates, not exact model tokenizers):
+///
+/// | Name | Counter |
+/// |------|---------|
+/// | `approx` | `ApproxTokenCounter` (4 bytes per token, the v0 rule) |
+/// | `gpt-4o` | 4 bytes per token |
+/// | `claude-3` | 3.5 bytes per token |
+///
+/// Exact tokenizers can be plugged in with `register`, which also replaces
+/// built-ins.
+#[derive(Clone)]
+pub struct TokenizerRegistry {
+    counters: BTreeMap<String, Arc<dyn TokenCounter + Send + Sync>>,
+}
+
+impl Default for TokenizerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenizerRegistry {
+    /// A registry with the built-in counters.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("approx", ApproxTokenCounter);
+        registry.register("gpt-4o", BytesPerTokenCounter::new(4, 1));
+        registry.register("claude-3", BytesPerTokenCounter::new(7, 2));
+        registry
+    }
+
+    /// A registry with no counters.
+    pub fn empty() -> Self {
+        Self {
+            counters: BTreeMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: impl Into<String>, counter: impl TokenCounter + Send + Sync + 'static) {
+        self.counters.insert(name.into(), Arc::new(counter));
+    }
+
+    /// The counter registered as `name`. It reports `name` in
+    /// `SelectionMetadata::tokenizer`.
+    pub fn get(&self, name: &str) -> Option<Box<dyn TokenCounter + Send + Sync>> {
+        self.counters.get(name).map(|inner| {
+            Box::new(NamedTokenCounter {
+                name: name.to_string(),
+                inner: Arc::clone(inner),
+            }) as Box<dyn TokenCounter + Send + Sync>
+        })
+    }
+
+    /// Registered names, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.counters.keys().map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for TokenizerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
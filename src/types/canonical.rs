@@ -0,0 +1,171 @@
+//! Canonical JSON for hashing and byte-for-byte comparison.
+//!
+//! `canonical_json` output is stable across platforms and releases:
+//!
+//! - object keys are sorted by their Unicode code points, at every level;
+//! - no whitespace outside strings;
+//! - strings are escaped as `serde_json` escapes them (only `"`, `\`, and
+//!   control characters; everything else is written as UTF-8);
+//! - floats are written in the shortest form that reads back to the same
+//!   value at the field's own precision (an `f32` score of 0.1 is `0.1`,
+//!   not `0.10000000149011612`), with a `.0` suffix for whole numbers;
+//!   non-finite floats are `null`.
+//!
+//! Field presence follows each type's serde attributes; canonicalization
+//! only fixes the layout. A change to this format is a breaking change.
+
+use serde::Serialize;
+
+/// Serialize `value` as canonical JSON (see the module docs).
+pub fn canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String, serde_json::Error> {
+    // serde_json writes floats in their shortest round-trip form for their
+    // own width; going through `serde_json::Value` would widen f32 to f64,
+    // so the compact text is re-laid-out with number tokens kept verbatim
+    let compact = serde_json::to_string(value)?;
+    let mut parser = Parser {
+        text: &compact,
+        pos: 0,
+    };
+    let node = parser.value()?;
+    let mut out = String::with_capacity(compact.len());
+    node.write(&mut out);
+    Ok(out)
+}
+
+enum Node<'a> {
+    /// A string, number, `true`, `false`, or `null`, as written.
+    Scalar(&'a str),
+    Array(Vec<Node<'a>>),
+    /// Decoded key, raw key, value.
+    Object(Vec<(String, &'a str, Node<'a>)>),
+}
+
+impl Node<'_> {
+    fn write(&self, out: &mut String) {
+        match self {
+            Node::Scalar(raw) => out.push_str(raw),
+            Node::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    item.write(out);
+                }
+                out.push(']');
+            }
+            Node::Object(entries) => {
+                out.push('{');
+                for (i, (_, raw_key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(raw_key);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Parser for the JSON `serde_json` itself just wrote.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), serde_json::Error> {
+        if self.peek() != Some(byte) {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn error(&self) -> serde_json::Error {
+        serde::de::Error::custom(format!("malformed JSON at byte {}", self.pos))
+    }
+
+    fn value(&mut self) -> Result<Node<'a>, serde_json::Error> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Node::Scalar(self.string()?)),
+            Some(_) => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b) if !matches!(b, b',' | b']' | b'}')) {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(self.error());
+                }
+                Ok(Node::Scalar(&self.text[start..self.pos]))
+            }
+            None => Err(self.error()),
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a str, serde_json::Error> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.peek() {
+                Some(b'"') => break,
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => return Err(self.error()),
+            }
+        }
+        self.pos += 1;
+        Ok(&self.text[start..self.pos])
+    }
+
+    fn array(&mut self) -> Result<Node<'a>, serde_json::Error> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Node::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Node::Array(items));
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Node<'a>, serde_json::Error> {
+        self.expect(b'{')?;
+        let mut entries = Vec::new();
+        if self.peek() != Some(b'}') {
+            loop {
+                let raw_key = self.string()?;
+                let key: String = serde_json::from_str(raw_key)?;
+                self.expect(b':')?;
+                entries.push((key, raw_key, self.value()?));
+                match self.peek() {
+                    Some(b',') => self.pos += 1,
+                    Some(b'}') => break,
+                    _ => return Err(self.error()),
+                }
+            }
+        }
+        self.pos += 1;
+        // Code-point order; `str` ordering is byte order, which is the same
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(Node::Object(entries))
+    }
+}
@@ -1,30 +1,13 @@
-use serde::Serialize;
-
-use crate::document::Document;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 
-/// A fully qualified, normalized query.
-/// Normalization rules:
-/// - Lowercase
-/// - Split on whitespace
-/// - Empty terms handled by scorer (score 0.0)
-#[derive(Debug, Clone)]
-pub struct Query {
-    pub raw: String,
-    pub terms: Vec<String>,
-}
+use serde::Serialize;
 
-impl Query {
-    pub fn new(raw: impl Into<String>) -> Self {
-        let raw = raw.into();
-        let terms = raw
-            .to_lowercase()
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect();
-
-        Self { raw, terms }
-    }
-}
+use crate::cache::CacheReadError;
+use crate::compression::TruncationStrategy;
+use crate::document::{Document, Metadata};
+use crate::selection::budgeting::{BudgetStrategy, BudgetUnit, QuotaScope};
+pub use crate::types::query::{Query, QueryField};
 
 /// A selected document returned in the output.
 /// Fully self-contained and serializable.
@@ -34,41 +17,576 @@ pub struct SelectedDocument {
     pub version: String,
     /// We own the content here because it's part of the final output payload
     pub content: String,
+    /// The region of the source document `content` holds. Present only when
+    /// a part of the document was selected (see `ChunkScoring`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// The document's metadata, when requested with
+    /// `SelectionOptions::include_metadata`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
 
+    #[serde(serialize_with = "serialize_score")]
     pub score: f32,
-    pub tokens: usize,
+    pub tokens: u64,
+    /// Token count before `content` was compressed, summarized, outlined or
+    /// truncated. Present only for such documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_tokens: Option<u64>,
+    /// How `content` was truncated. Present only for truncated documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<TruncationStrategy>,
+    /// What `content` holds in place of the document's text. Present only
+    /// when it is not the text (or a part of it).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub representation: Option<Representation>,
 
     pub why: SelectionWhy,
 }
 
+/// Borrowed form of `SelectedDocument`, pointing into the documents it was
+/// selected from (except compressed content). Serializes identically.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectedDocumentRef<'a> {
+    pub id: &'a str,
+    pub version: &'a str,
+    pub content: Cow<'a, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<&'a Metadata>,
+
+    #[serde(serialize_with = "serialize_score")]
+    pub score: f32,
+    pub tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<TruncationStrategy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub representation: Option<Representation>,
+
+    pub why: SelectionWhy,
+}
+
+/// A stand-in for a document's text in `SelectedDocument::content`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Representation {
+    /// The summary stored at cache build time (see `Summarizer`).
+    Summary,
+    /// The Markdown heading hierarchy, each heading followed by the first
+    /// sentence of its section (see `markdown_outline`).
+    Outline,
+}
+
+/// Decimal places selected-document scores are written with.
+pub const SCORE_DECIMALS: u32 = 6;
+
+/// Round `score` to `decimals` places (at most `SCORE_DECIMALS`), half
+/// away from zero, computed in f64.
+pub fn round_score(score: f32, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals.min(SCORE_DECIMALS) as i32);
+    (score as f64 * scale).round() / scale
+}
+
+/// Writes a score rounded to `SCORE_DECIMALS` places in its shortest form,
+/// so an f32 `0.92` is `0.92` whatever its binary expansion. Reading back
+/// accepts any number.
+fn serialize_score<S: serde::Serializer>(score: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round_score(*score, SCORE_DECIMALS))
+}
+
+impl SelectedDocumentRef<'_> {
+    pub fn into_owned(self) -> SelectedDocument {
+        SelectedDocument {
+            id: self.id.to_string(),
+            version: self.version.to_string(),
+            content: self.content.into_owned(),
+            span: self.span,
+            metadata: self.metadata.cloned(),
+            score: self.score,
+            tokens: self.tokens,
+            original_tokens: self.original_tokens,
+            truncation: self.truncation,
+            representation: self.representation,
+            why: self.why,
+        }
+    }
+}
+
 /// Explanation for why a document received its score.
-#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, serde::Deserialize)]
 pub struct SelectionWhy {
     pub query_terms: Vec<String>,
     pub term_matches: usize,
     pub total_words: usize,
+    /// Present only when the query has phrases.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query_phrases: Vec<String>,
+    /// Words matched as part of a phrase occurrence. Present only when the
+    /// query has phrases.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phrase_matches: Option<usize>,
+    /// Named component scores of a composed scorer. Present only when the
+    /// scorer reports components.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, f32>,
+    /// Scorer-specific explanation. Absent for `TermFrequencyScorer`, whose
+    /// explanation is the term statistics above.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+    /// Chunks behind an aggregated score, best first. Present only when
+    /// chunk scoring was enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<ChunkRef>,
+    /// Why the document was selected other than for its rank. Absent for
+    /// documents selected by rank.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<SelectionReason>,
+    /// The selected document that links to this one. Present only when
+    /// `reason` is `Linked`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linked_from: Option<String>,
+}
+
+/// A reason other than rank a document was selected for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionReason {
+    /// A selected document links to it (see
+    /// `SelectionOptions::include_linked`).
+    Linked,
+}
+
+/// Scorer-specific explanation of a score, tagged by `kind`.
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Explanation {
+    /// Cosine similarity between the query and the document vector;
+    /// `None` when the document has no stored vector.
+    Embedding { similarity: Option<f32> },
+    /// 1-based component ranks used by reciprocal rank fusion.
+    Fusion { lexical_rank: usize, vector_rank: usize },
+    /// Explanations of a composite scorer's components, by name; components
+    /// without one are omitted.
+    Composite { components: BTreeMap<String, Explanation> },
+    /// Free-form explanation for scorers outside this crate.
+    Custom {
+        scorer: String,
+        values: BTreeMap<String, serde_json::Value>,
+    },
+}
+
+/// How a fused scorer combines its lexical and vector components.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FusionMode {
+    /// `(w_l * lexical + w_v * vector) / (w_l + w_v)`
+    WeightedSum,
+    /// `sum(w / (k + rank))`, normalized so rank 1 in both lists scores 1.0
+    ReciprocalRank { k: u32 },
+}
+
+/// Fusion parameters, echoed into `SelectionMetadata` for reproducibility.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+pub struct FusionConfig {
+    pub mode: FusionMode,
+    pub lexical_weight: f32,
+    pub vector_weight: f32,
+}
+
+/// How `ContextSelector::select_multi` combines per-query rankings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryFusion {
+    /// A document's best score across queries.
+    #[default]
+    Max,
+    /// `sum(1 / (k + rank))` over the queries that match the document,
+    /// normalized so rank 1 for every query scores 1.0.
+    ReciprocalRank { k: u32 },
+}
+
+/// A contiguous region of a document's content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct Span {
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    /// 1-based, inclusive.
+    pub end_line: usize,
+    /// Byte offsets `[start, end)` into the content.
+    pub byte_range: [usize; 2],
+}
+
+/// How chunk scores roll up to their document.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationStrategy {
+    /// Best chunk score.
+    Max,
+    /// Sum of chunk scores, capped at 1.0.
+    Sum,
+    /// Mean of the `n` best chunk scores (all chunks if fewer).
+    TopNMean { n: usize },
+}
+
+/// A chunk that contributed to its document's aggregated score.
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct ChunkRef {
+    /// Position of the chunk within the document, from 0.
+    pub index: usize,
+    #[serde(flatten)]
+    pub span: Span,
+    pub score: f32,
+    /// The chunk's definition, when the chunker names one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// Chunk scoring parameters, echoed into `SelectionMetadata`.
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct ChunkingInfo {
+    pub chunker: String,
+    pub aggregation: AggregationStrategy,
+    pub chunks_scored: usize,
+    /// Only the best chunk of each document was selected.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub best_chunk_only: bool,
+}
+
+/// Diversification parameters, echoed into `SelectionMetadata`.
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct MmrInfo {
+    pub lambda: f32,
+    pub similarity: String,
+}
+
+/// How a `BudgetQuota` was honored, echoed into `SelectionMetadata`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct QuotaReport {
+    pub scope: QuotaScope,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_percent: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_documents: Option<usize>,
+    /// Budget units spent on matching documents.
+    pub used: u64,
+    /// Matching documents selected.
+    pub documents: usize,
+    /// Whether `min_documents` was met (caps always hold).
+    pub satisfied: bool,
+}
+
+/// One query's part in a multi-query selection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct QueryContribution {
+    pub query: String,
+    /// Candidates this query scored above zero.
+    pub documents_matched: usize,
+    /// Selected documents this query scored above zero.
+    pub documents_selected: usize,
+    /// Selected documents whose fused score came mostly from this query
+    /// (best score, or best rank); their `why` is this query's.
+    pub documents_contributed: usize,
+}
+
+/// How a selection differs from the previous one given as `Stickiness`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+pub struct ChurnStats {
+    /// The stickiness bonus applied.
+    pub bonus: f32,
+    /// Previous documents selected again, same version.
+    pub retained: usize,
+    /// Previous documents selected again with a new version.
+    pub changed: usize,
+    /// Selected documents that were not previously selected.
+    pub added: usize,
+    /// Previous documents no longer selected.
+    pub dropped: usize,
+}
+
+/// Every stage decision of one selection, for offline inspection.
+/// Collected only when `SelectionOptions::trace` is set.
+#[derive(Debug, Clone, Default, Serialize, serde::Deserialize)]
+pub struct SelectionTrace {
+    /// One entry per document considered, in cache order.
+    pub filter: Vec<FilterTrace>,
+    /// One entry per document that passed filtering, in scoring order.
+    pub scores: Vec<ScoreTrace>,
+    /// One entry per ranked document, in rank order.
+    pub budget: Vec<BudgetTrace>,
+    /// Documents skipped under `OnCorrupt::Skip`, in ID order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unreadable: Vec<UnreadableTrace>,
+}
+
+/// A document that could not be read, and why (a `CacheReadError`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct UnreadableTrace {
+    pub id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct FilterTrace {
+    pub id: String,
+    pub verdict: FilterVerdict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterVerdict {
+    Passed,
+    /// Rejected by `SelectionOptions::path_filter`.
+    ExcludedByPath,
+    /// Rejected by the query's field clauses (by every query's, for a
+    /// multi-query selection).
+    ExcludedByFields,
+    /// Deprecated or expired, under `SelectionOptions::freshness` with
+    /// `StaleAction::Exclude`.
+    ExcludedAsStale,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct ScoreTrace {
+    pub id: String,
+    /// The scorer's score (the fused score, for a multi-query selection).
+    #[serde(serialize_with = "serialize_score")]
+    pub raw_score: f32,
+    /// Whether `raw_score` passed `min_score` and `exclude_zero_score`
+    /// (always true for pinned documents).
+    pub passed_threshold: bool,
+    /// Added by `SelectionOptions::stickiness`. Present only when applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boost: Option<f32>,
+    /// Factor applied by `SelectionOptions::freshness`. Present only when
+    /// applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub penalty: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct BudgetTrace {
+    pub id: String,
+    /// Position in the final ranking (after MMR and pinning), from 0.
+    pub rank: usize,
+    #[serde(serialize_with = "serialize_score")]
+    pub score: f32,
+    pub tokens: u64,
+    pub verdict: BudgetVerdict,
+}
+
+/// What budgeting decided for one ranked document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetVerdict {
+    Selected,
+    /// Admitted ahead of rank order for a quota's `min_documents`.
+    SelectedForQuota,
+    /// Did not fit, and was admitted after extractive compression.
+    Compressed,
+    /// Did not fit, and was admitted as its stored summary.
+    Summarized,
+    /// Did not fit, and was admitted as an outline.
+    Outlined,
+    /// Did not fit, and was admitted after truncation.
+    Truncated,
+    /// Not selected by rank, and admitted as linked from a selected
+    /// document.
+    Linked,
+    ExcludedByBudget,
+    ExcludedByLimit,
+    ExcludedByQuota,
+}
+
+impl BudgetVerdict {
+    pub fn is_selected(&self) -> bool {
+        matches!(
+            self,
+            BudgetVerdict::Selected
+                | BudgetVerdict::SelectedForQuota
+                | BudgetVerdict::Compressed
+                | BudgetVerdict::Summarized
+                | BudgetVerdict::Outlined
+                | BudgetVerdict::Truncated
+                | BudgetVerdict::Linked
+        )
+    }
 }
 
 /// Metadata describing the outcome of the selection process.
-#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, serde::Deserialize)]
 pub struct SelectionMetadata {
     pub query: String,
-    pub budget: usize,
+    pub budget: u64,
+    /// Unit of `budget`, `budget_reserved`, `budget_per_document_overhead`
+    /// and `tokens_used`. Present only when not tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_unit: Option<BudgetUnit>,
+    /// Present only when part of the budget was reserved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_reserved: Option<u64>,
+    /// Present only when a per-document overhead was charged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_per_document_overhead: Option<u64>,
+    /// Present only when not `Greedy`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_strategy: Option<BudgetStrategy>,
 
-    pub tokens_used: usize,
+    pub tokens_used: u64,
 
     pub documents_considered: usize,
     pub documents_selected: usize,
     pub documents_excluded_by_budget: usize,
+    /// Present only when a top-k limit was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Present only when a top-k limit was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_excluded_by_limit: Option<usize>,
+    /// Present only when a path filter was applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_excluded_by_filter: Option<usize>,
+    /// Present only when `min_score` or `exclude_zero_score` was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_excluded_by_score: Option<usize>,
+    /// Present only when quotas were configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_excluded_by_quota: Option<usize>,
+    /// Present only when quotas were configured.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotas: Vec<QuotaReport>,
+    /// Documents that could not be read and were left out. Present only
+    /// when `SelectionOptions::on_corrupt` was `Skip`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_unreadable: Option<usize>,
+    /// Documents admitted after extractive compression. Present only when
+    /// compression was enabled; they are not counted as excluded by budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_compressed: Option<usize>,
+    /// Documents admitted as their stored summary. Present only when
+    /// `SelectionOptions::summary_fallback` was set; they are not counted
+    /// as excluded by budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_summarized: Option<usize>,
+    /// Documents admitted as outlines. Present only when
+    /// `SelectionOptions::outline_fallback` was set; they are not counted
+    /// as excluded by budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_outlined: Option<usize>,
+    /// Documents admitted after truncation. Present only when truncation
+    /// was enabled; they are not counted as excluded by budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_truncated: Option<usize>,
+    /// Documents admitted as linked from selected documents. Present only
+    /// when `SelectionOptions::include_linked` was set; they are not
+    /// counted as excluded by budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents_linked: Option<usize>,
+    /// Budget units freed by `SelectionOptions::dedupe_boilerplate`, and
+    /// no longer counted in `tokens_used`. Present only when it was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boilerplate_tokens_saved: Option<u64>,
+
+    /// Whether selected documents' content was left out (see
+    /// `SelectionOptions::omit_content`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_omitted: bool,
+
+    /// Decimal places selected scores were rounded to. Present only when
+    /// set below `SCORE_DECIMALS` (see `SelectionOptions::score_precision`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_precision: Option<u32>,
+
+    /// Present only when a previous selection was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub churn: Option<ChurnStats>,
+
+    /// Present only for multi-query selections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_fusion: Option<QueryFusion>,
+    /// Per-query contributions, in query order. Present only for
+    /// multi-query selections.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queries: Vec<QueryContribution>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fusion: Option<FusionConfig>,
+
+    /// Word analyzer name. Present only when the scorer is not using the v0
+    /// whitespace split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<String>,
+
+    /// Token counter name, when it came from a `TokenizerRegistry`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokenizer: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mmr: Option<MmrInfo>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunking: Option<ChunkingInfo>,
+
+    /// Present only when `SelectionOptions::trace` was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<SelectionTrace>,
+
+    // Provenance, kept last so earlier fields keep their positions.
+    /// Version of the cache selected from, when the loader reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_version: Option<String>,
+    /// Documents in the cache, before any filtering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_count_in_cache: Option<usize>,
+    /// `Scorer::fingerprint` of the scorer used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scorer: Option<String>,
+    /// `TokenCounter::fingerprint` of the token counter used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_counter: Option<String>,
+
+    /// Documents scored after the candidate source narrowed them (for a
+    /// multi-query selection, by any query). Present only when
+    /// `ContextSelector::with_candidate_source` was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidate_count: Option<usize>,
+    /// Whether the candidate source returned `None` (for any query), so
+    /// every document passing the filters was scored.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub candidate_fallback: bool,
 }
 
 /// The final result of a context resolution operation.
 #[derive(Debug, Clone, Serialize, serde::Deserialize)]
 pub struct SelectionResult {
+    /// See `compat::SELECTION_RESULT_SCHEMA_VERSION`; 0 when absent.
+    #[serde(default)]
+    pub schema_version: u32,
     pub documents: Vec<SelectedDocument>,
     pub selection: SelectionMetadata,
 }
 
+/// Borrowed form of `SelectionResult`; selected content is not copied.
+/// Serializes identically.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionResultRef<'a> {
+    pub schema_version: u32,
+    pub documents: Vec<SelectedDocumentRef<'a>>,
+    pub selection: SelectionMetadata,
+}
+
+impl SelectionResultRef<'_> {
+    pub fn into_owned(self) -> SelectionResult {
+        SelectionResult {
+            schema_version: self.schema_version,
+            documents: self.documents.into_iter().map(SelectedDocumentRef::into_owned).collect(),
+            selection: self.selection,
+        }
+    }
+}
+
 /// Internal: A document that has been scored and tokenized but not yet selected.
 /// Holds a reference to the original document to avoid cloning content prematurely.
 #[derive(Debug, Clone)]
@@ -78,22 +596,52 @@ pub struct ScoredDocument<'a> {
     pub score: f32,
     pub score_details: ScoreDetails,
 
-    pub token_count: usize,
+    /// Region of `document` to select, when not the whole content.
+    pub span: Option<Span>,
+    pub token_count: u64,
+}
+
+impl<'a> ScoredDocument<'a> {
+    /// The content to select: the span's text, or the whole document.
+    pub fn content(&self) -> &'a str {
+        match self.span {
+            Some(span) => &self.document.content[span.byte_range[0]..span.byte_range[1]],
+            None => &self.document.content,
+        }
+    }
 }
 
 /// Internal: Detailed scoring components before serialization.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ScoreDetails {
     pub query_terms: Vec<String>,
     pub term_matches: usize,
     pub total_words: usize,
+    /// Query phrases, space-joined.
+    pub query_phrases: Vec<String>,
+    /// Words matched as part of a phrase occurrence.
+    pub phrase_matches: usize,
+    /// Explicit score, for scorers that do not score by term frequency.
+    pub score: Option<f32>,
+    /// Named component scores, for scorers composed of other scorers.
+    pub components: BTreeMap<String, f32>,
+    /// Scorer-specific explanation, copied to `SelectionWhy::explanation`.
+    pub explanation: Option<Explanation>,
+    /// Chunks behind an aggregated score, copied to `SelectionWhy::chunks`.
+    pub chunks: Vec<ChunkRef>,
+    /// Each component scorer's own details, in component order, for
+    /// scorers that need them after `score` (see `CompositeScorer`).
+    pub breakdown: Vec<ScoreDetails>,
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum SelectionError {
     #[error("Invalid budget: {0}")]
-    InvalidBudget(usize),
+    InvalidBudget(u64),
+
+    #[error("Cache integrity error: {0}")]
+    CacheError(#[from] CacheReadError),
 
-    #[error("Cache integrity error")]
-    CacheError,
+    #[error("Invalid filter (expected a title:, path: or tag: clause): {0}")]
+    InvalidFilter(String),
 }
@@ -13,6 +13,8 @@ pub enum DocumentIdError {
     OutsideRoot,
     #[error("Path involves invalid UTF-8")]
     InvalidUtf8,
+    #[error("Invalid namespace: {0:?}")]
+    InvalidNamespace(String),
 }
 
 impl DocumentId {
@@ -24,26 +26,142 @@ impl DocumentId {
 
         let normalized = normalize_path(rel)?;
 
-        Ok(DocumentId(normalized))
+        Ok(DocumentId(normalized.to_lowercase()))
+    }
+
+    /// Like `from_path`, but keeping the path's case, so `README.md` and
+    /// `readme.md` stay distinct. `CacheBuildConfig::case_sensitive_ids`
+    /// decides whether a build keeps it.
+    pub fn from_path_case_sensitive(root: &Path, source: &Path) -> Result<Self, DocumentIdError> {
+        let rel = source
+            .strip_prefix(root)
+            .map_err(|_| DocumentIdError::OutsideRoot)?;
+
+        Ok(DocumentId(normalize_path(rel)?))
+    }
+
+    /// Create a DocumentId `namespace/relative/path`, so documents from
+    /// several roots keep distinct IDs. The namespace is lowercased like the
+    /// path, and may not be empty or contain `/`.
+    pub fn from_path_namespaced(namespace: &str, root: &Path, source: &Path) -> Result<Self, DocumentIdError> {
+        let namespace = normalize_namespace(namespace)?;
+        Ok(Self::from_path(root, source)?.with_namespace(&namespace))
     }
 
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Prefix this ID with `namespace/`.
+    /// An id read back from a cache file that stored one; not normalized
+    /// again.
+    #[cfg(feature = "tantivy")]
+    pub(crate) fn from_stored(id: String) -> Self {
+        Self(id)
+    }
+
+    pub(crate) fn with_namespace(&self, namespace: &str) -> Self {
+        DocumentId(format!("{}/{}", namespace, self.0))
+    }
+
+    /// This ID in lowercase, as `from_path` would have built it.
+    pub(crate) fn to_lowercase(&self) -> Self {
+        DocumentId(self.0.to_lowercase())
+    }
+
+    /// The id of part `part` (from 1) of this document: `<id>/part-0001`.
+    pub(crate) fn with_part(&self, part: usize) -> Self {
+        DocumentId(format!("{}/part-{:04}", self.0, part))
+    }
 }
 
-/// Normalize path according to spec rules
+/// Lowercase `namespace`, rejecting empty namespaces and those with `/`.
+pub(crate) fn normalize_namespace(namespace: &str) -> Result<String, DocumentIdError> {
+    if namespace.is_empty() || namespace.contains('/') {
+        return Err(DocumentIdError::InvalidNamespace(namespace.to_string()));
+    }
+    Ok(namespace.to_lowercase())
+}
+
+/// Normalize path according to spec rules, except case folding, which
+/// callers apply.
 fn normalize_path(path: &Path) -> Result<String, DocumentIdError> {
     let s = path.to_str().ok_or(DocumentIdError::InvalidUtf8)?;
 
     let normalized = s
         .replace('\\', "/")
         .trim_start_matches("./")
-        .to_lowercase();
+        .to_string();
 
     Ok(normalized)
 }
 
+/// Hash algorithm used for document and cache versions.
+/// The algorithm name is the version prefix (`sha256:`, `blake3:`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Parse a `CacheBuildConfig::hash_algorithm` value.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "blake3" => Some(HashAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub fn hasher(&self) -> VersionHasher {
+        match self {
+            HashAlgorithm::Sha256 => VersionHasher::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => VersionHasher::Blake3(Box::default()),
+        }
+    }
+
+    /// One-shot hash of `bytes` as `<algorithm>:<hex>`.
+    pub fn checksum(&self, bytes: &[u8]) -> String {
+        let mut hasher = self.hasher();
+        hasher.update(bytes);
+        hasher.finalize()
+    }
+}
+
+/// Incremental hasher producing `<algorithm>:<hex>` version strings.
+pub enum VersionHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl VersionHasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            VersionHasher::Sha256(h) => h.update(bytes),
+            VersionHasher::Blake3(h) => {
+                h.update(bytes);
+            }
+        }
+    }
+
+    /// Finish and format as `<algorithm>:<hex>`.
+    pub fn finalize(self) -> String {
+        match self {
+            VersionHasher::Sha256(h) => format!("sha256:{}", hex::encode(h.finalize())),
+            VersionHasher::Blake3(h) => format!("blake3:{}", h.finalize().to_hex()),
+        }
+    }
+}
+
 /// Content hash version.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -51,13 +169,24 @@ pub struct DocumentVersion(String);
 
 impl DocumentVersion {
     pub fn from_content(content: &[u8]) -> Self {
-        let mut hasher = Sha256::new();
+        Self::from_content_with(HashAlgorithm::Sha256, content)
+    }
+
+    pub fn from_content_with(algorithm: HashAlgorithm, content: &[u8]) -> Self {
+        let mut hasher = algorithm.hasher();
         hasher.update(content);
+        DocumentVersion(hasher.finalize())
+    }
 
-        let hash = hasher.finalize();
-        let hex = hex::encode(hash);
+    /// Algorithm named by the version prefix, if recognized.
+    pub fn algorithm(&self) -> Option<HashAlgorithm> {
+        let (name, _) = self.0.split_once(':')?;
+        HashAlgorithm::from_name(name)
+    }
 
-        DocumentVersion(format!("sha256:{hex}"))
+    /// Hex digest without the algorithm prefix.
+    pub fn digest(&self) -> Option<&str> {
+        self.0.split_once(':').map(|(_, hex)| hex)
     }
 
     pub fn as_str(&self) -> &str {
@@ -1,5 +1,12 @@
+pub mod canonical;
 pub mod context_bundle;
 pub mod identifiers;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod query;
 
+pub use canonical::canonical_json;
 pub use context_bundle::*;
 pub use identifiers::*;
+#[cfg(feature = "msgpack")]
+pub use msgpack::{from_msgpack, to_msgpack};
@@ -0,0 +1,16 @@
+//! MessagePack encoding of the serialized contracts.
+//!
+//! Structs are written as maps with named fields, in declaration order, so
+//! fields skipped when empty read back as their defaults and identical
+//! values always encode to identical bytes.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub fn to_msgpack<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec_named(value)
+}
+
+pub fn from_msgpack<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, rmp_serde::decode::Error> {
+    rmp_serde::from_slice(bytes)
+}
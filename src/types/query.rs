@@ -0,0 +1,141 @@
+use std::mem::discriminant;
+
+use crate::document::metadata::MetadataValue;
+use crate::document::Document;
+use crate::selection::filters::glob_match;
+
+/// A fully qualified, normalized query.
+/// Normalization rules:
+/// - Lowercase (ids are lowercase too, so `path:` globs are case-insensitive)
+/// - Split on whitespace
+/// - Text in double quotes is a phrase: its words must appear adjacent and
+///   in order. A single quoted word is an ordinary term; an unmatched
+///   quote is ignored.
+/// - Unquoted `title:`, `path:`, `tag:` and `symbol:` words are field clauses, not
+///   terms (see `QueryField`)
+/// - Empty terms handled by scorer (score 0.0)
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub raw: String,
+    pub terms: Vec<String>,
+    /// Quoted phrases of two or more words, in query order.
+    pub phrases: Vec<Vec<String>>,
+    /// Field clauses, in query order.
+    pub fields: Vec<QueryField>,
+}
+
+/// A field-scoped clause restricting which documents a query can select.
+///
+/// Clauses of the same kind are alternatives; clauses of different kinds
+/// must all hold, so `path:docs/** path:src/** tag:runbook` selects runbooks
+/// under either directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryField {
+    /// `title:<word>`: the `title` metadata contains the word (case-insensitive).
+    Title(String),
+    /// `path:<glob>`: the document id matches the glob (see `PathFilter`).
+    Path(String),
+    /// `tag:<tag>`: the `tags` metadata (comma-separated, or a list of
+    /// strings) contains the tag (case-insensitive).
+    Tag(String),
+    /// `symbol:<name>`: the `symbol` metadata, or an entry of the `symbols`
+    /// list, is the name or ends in `.<name>` (case-insensitive). Code
+    /// chunks carry their definition as `symbol` (see `CodeChunker`), so
+    /// with chunk scoring only the matching chunks score.
+    Symbol(String),
+}
+
+impl QueryField {
+    /// Parse `title:x`, `path:x`, `tag:x` or `symbol:x`; anything else is
+    /// not a field.
+    fn parse(word: &str) -> Option<Self> {
+        let (name, value) = word.split_once(':')?;
+        if value.is_empty() {
+            return None;
+        }
+        match name.to_lowercase().as_str() {
+            "title" => Some(QueryField::Title(value.to_lowercase())),
+            "path" => Some(QueryField::Path(value.to_lowercase())),
+            "tag" => Some(QueryField::Tag(value.to_lowercase())),
+            "symbol" => Some(QueryField::Symbol(value.to_lowercase())),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, doc: &Document) -> bool {
+        match self {
+            QueryField::Title(word) => metadata_str(doc, "title")
+                .is_some_and(|title| title.to_lowercase().split_whitespace().any(|w| w == word)),
+            QueryField::Path(pattern) => glob_match(pattern, doc.id.as_str()),
+            QueryField::Tag(tag) => match doc.metadata.get("tags") {
+                Some(MetadataValue::String(tags)) => tags.split(',').any(|t| t.trim().to_lowercase() == *tag),
+                Some(MetadataValue::List(tags)) => tags
+                    .iter()
+                    .filter_map(MetadataValue::as_str)
+                    .any(|t| t.trim().to_lowercase() == *tag),
+                _ => false,
+            },
+            QueryField::Symbol(name) => {
+                let is_match = |symbol: &str| {
+                    let symbol = symbol.to_lowercase();
+                    symbol == *name || symbol.rsplit('.').next() == Some(name.as_str())
+                };
+                metadata_str(doc, "symbol").is_some_and(is_match)
+                    || match doc.metadata.get("symbols") {
+                        Some(MetadataValue::List(symbols)) => {
+                            symbols.iter().filter_map(MetadataValue::as_str).any(is_match)
+                        }
+                        _ => false,
+                    }
+            }
+        }
+    }
+}
+
+fn metadata_str<'a>(doc: &'a Document, key: &str) -> Option<&'a str> {
+    doc.metadata.get(key)?.as_str()
+}
+
+impl Query {
+    pub fn new(raw: impl Into<String>) -> Self {
+        let raw = raw.into();
+        let mut terms = Vec::new();
+        let mut phrases = Vec::new();
+        let mut fields = Vec::new();
+
+        // Segments alternate unquoted / quoted; a trailing unclosed quote
+        // leaves an odd segment count, and its text is read as plain terms
+        let segments: Vec<&str> = raw.split('"').collect();
+        let closed = segments.len() - (segments.len() + 1) % 2;
+        for (i, segment) in segments.iter().enumerate() {
+            let quoted = i % 2 == 1 && i < closed;
+            let words: Vec<&str> = segment.split_whitespace().collect();
+            if quoted && words.len() > 1 {
+                phrases.push(words.iter().map(|w| w.to_lowercase()).collect());
+                continue;
+            }
+            for word in words {
+                match QueryField::parse(word).filter(|_| !quoted) {
+                    Some(field) => fields.push(field),
+                    None => terms.push(word.to_lowercase()),
+                }
+            }
+        }
+
+        Self {
+            raw,
+            terms,
+            phrases,
+            fields,
+        }
+    }
+
+    /// Whether `doc` satisfies every field clause (true when there are none).
+    pub fn matches_fields(&self, doc: &Document) -> bool {
+        self.fields.iter().all(|field| {
+            self.fields
+                .iter()
+                .any(|alt| discriminant(alt) == discriminant(field) && alt.matches(doc))
+        })
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    AnalyzedScorer, Analyzer, ApproxTokenCounter, ContextSelector, Scorer, TermFrequencyScorer,
+    WhitespaceAnalyzer,
+};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn whitespace_analyzer_matches_term_frequency_scorer() {
+    let scorer = AnalyzedScorer::new(WhitespaceAnalyzer);
+    for (content, query) in [
+        ("Deploy the deployment. deploy", "deploy"),
+        ("the context window is full", "\"context window\" full"),
+        ("", "deploy"),
+    ] {
+        let doc = make_doc("a.md", content);
+        let query = Query::new(query);
+        let expected = TermFrequencyScorer.score(&doc, &query);
+        let actual = scorer.score(&doc, &query);
+        assert_eq!(actual.term_matches, expected.term_matches);
+        assert_eq!(actual.phrase_matches, expected.phrase_matches);
+        assert_eq!(actual.total_words, expected.total_words);
+    }
+    assert_eq!(WhitespaceAnalyzer.words("a  b\tc"), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn analyzer_is_recorded_in_metadata_and_config() {
+    let dir = tempdir().unwrap();
+    let docs = vec![make_doc("a.md", "deploy now")];
+    let plain = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs.clone(), &dir.path().join("plain"))
+        .unwrap();
+    let tagged = CacheBuilder::new(CacheBuildConfig::v0().with_analyzer("whitespace"))
+        .build(docs, &dir.path().join("tagged"))
+        .unwrap();
+    assert_ne!(plain.manifest.cache_version, tagged.manifest.cache_version);
+    assert_eq!(tagged.manifest.build_config.analyzer.as_deref(), Some("whitespace"));
+
+    let result = ContextSelector::new(AnalyzedScorer::new(WhitespaceAnalyzer), ApproxTokenCounter)
+        .select(&tagged, Query::new("deploy"), 100)
+        .unwrap();
+    assert_eq!(result.selection.analyzer.as_deref(), Some("whitespace"));
+
+    let result = ContextSelector::default().select(&plain, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.selection.analyzer, None);
+    assert!(!serde_json::to_string(&result).unwrap().contains("analyzer"));
+}
+
+#[cfg(feature = "unicode-segmentation")]
+mod unicode {
+    use super::*;
+    use context_core::selection::UnicodeAnalyzer;
+
+    #[test]
+    fn punctuation_is_dropped() {
+        let doc = make_doc("a.md", "Ship the deployment. Then verify (deployment)!");
+        let query = Query::new("deployment");
+        assert_eq!(TermFrequencyScorer.score(&doc, &query).term_matches, 0);
+
+        let details = AnalyzedScorer::new(UnicodeAnalyzer).score(&doc, &query);
+        assert_eq!(details.term_matches, 2);
+        assert_eq!(details.total_words, 6);
+    }
+
+    #[test]
+    fn cjk_text_is_segmented() {
+        assert_eq!(UnicodeAnalyzer.words("部署服务"), vec!["部", "署", "服", "务"]);
+
+        let scorer = AnalyzedScorer::new(UnicodeAnalyzer);
+        let adjacent = scorer.score(&make_doc("a.md", "如何部署服务"), &Query::new("部署"));
+        assert_eq!(adjacent.phrase_matches, 2);
+        assert_eq!(adjacent.query_phrases, vec!["部 署"]);
+
+        let separate = scorer.score(&make_doc("b.md", "署名和部分"), &Query::new("部署"));
+        assert_eq!(separate.phrase_matches, 0);
+        assert_eq!(scorer.fingerprint(), "analyzed(uax29)");
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "arbitrary")]
+
+use std::collections::BTreeSet;
+
+use arbitrary::{Arbitrary, Unstructured};
+use context_core::document::{Document, DocumentVersion};
+use context_core::fuzz::{arbitrary_documents, cache_from_seed, documents_from_seed};
+use context_core::selection::{BudgetConfig, ContextSelector, DEFAULT_MAX_BUDGET};
+use context_core::types::Query;
+
+/// Deterministic bytes for `Unstructured`.
+fn bytes(seed: u8, len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+}
+
+#[test]
+fn generated_values_hold_the_invariants() {
+    for seed in 0..32 {
+        let data = bytes(seed, 4096);
+        let mut u = Unstructured::new(&data);
+
+        let documents = arbitrary_documents(&mut u).unwrap();
+        let ids: BTreeSet<&str> = documents.iter().map(|doc| doc.id.as_str()).collect();
+        assert_eq!(ids.len(), documents.len());
+        for doc in &documents {
+            assert_eq!(doc.version, DocumentVersion::from_content(doc.content.as_bytes()));
+            assert_eq!(doc.id.as_str(), doc.id.as_str().to_lowercase());
+            assert!(!doc.id.as_str().starts_with('/') && !doc.id.as_str().contains(".."));
+            // Round-trips through the cache's serialized form
+            let json = serde_json::to_string(doc).unwrap();
+            assert_eq!(&serde_json::from_str::<Document>(&json).unwrap(), doc);
+        }
+
+        let query = Query::arbitrary(&mut u).unwrap();
+        let parsed = Query::new(query.raw.clone());
+        assert_eq!((parsed.terms, parsed.phrases, parsed.fields), (query.terms, query.phrases, query.fields));
+
+        let budget = BudgetConfig::arbitrary(&mut u).unwrap();
+        budget.validate(DEFAULT_MAX_BUDGET).unwrap();
+    }
+}
+
+#[test]
+fn seeded_caches_are_deterministic() {
+    let first = cache_from_seed(7).unwrap();
+    let second = cache_from_seed(7).unwrap();
+    assert_eq!(first.manifest().cache_version, second.manifest().cache_version);
+    assert_eq!(documents_from_seed(7), documents_from_seed(7));
+    assert!(!documents_from_seed(7).is_empty());
+    assert_ne!(documents_from_seed(7), documents_from_seed(8));
+
+    // Selection over a seeded cache runs like any other
+    let result = ContextSelector::default().select(&first, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(result.selection.document_count_in_cache, Some(first.manifest().documents.len()));
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "async")]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "beta deploy deploy")]
+}
+
+#[tokio::test]
+async fn async_api_matches_sync_api() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    let sync_cache = ContextCache::open(&path).unwrap();
+    let async_cache = ContextCache::open_async(&path).await.unwrap();
+    assert_eq!(sync_cache.manifest.cache_version, async_cache.manifest.cache_version);
+    assert_eq!(
+        sync_cache.load_documents().unwrap(),
+        async_cache.load_documents_async().await.unwrap()
+    );
+
+    let selector = ContextSelector::default();
+    let sync_result = selector.select(&sync_cache, Query::new("deploy"), 100).unwrap();
+    let async_result = selector.select_async(&async_cache, Query::new("deploy"), 100).await.unwrap();
+    assert_eq!(
+        serde_json::to_string(&sync_result).unwrap(),
+        serde_json::to_string(&async_result).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn async_load_verifies_documents() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    let file = path.join(&cache.manifest.documents[0].file);
+    let doc = std::fs::read_to_string(&file).unwrap();
+    std::fs::write(&file, doc.replace("deploy alpha", "tampered")).unwrap();
+
+    let reopened = ContextCache::open_async(&path).await.unwrap();
+    assert!(reopened.load_documents_async().await.is_err());
+}
+
+#[tokio::test]
+async fn async_load_reads_non_filesystem_stores() {
+    let store = Arc::new(MemoryStore::new());
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store)
+        .build(docs(), Path::new("/mem/cache"))
+        .unwrap();
+    assert_eq!(cache.load_documents_async().await.unwrap().len(), 2);
+}
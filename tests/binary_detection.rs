@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use context_core::document::binary::{stub, REJECTION_KEY, SIZE_BYTES_KEY};
+use context_core::document::metadata::MetadataValue;
+use context_core::document::parser::ORIGINAL_CONTENT_HASH_KEY;
+use context_core::document::{BinaryDetector, BinaryPolicy, DocumentId, DocumentVersion, Metadata, Rejection};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+#[test]
+fn text_passes() {
+    let detector = BinaryDetector::new();
+    assert_eq!(detector.check(b"# Guide\n\nPlain\ttext,\r\nwith a form feed \x0c.\n"), Ok(()));
+    assert_eq!(detector.check(b""), Ok(()));
+}
+
+#[test]
+fn binary_content_is_rejected() {
+    let detector = BinaryDetector::new();
+    assert_eq!(detector.check(&[0xff, 0xfe, 0x00]), Err(Rejection::InvalidUtf8));
+    assert_eq!(detector.check(b"PK\0\x03"), Err(Rejection::NulBytes));
+
+    // 2 control characters in 100 bytes is 20 per mille
+    let mut noisy = vec![b'a'; 98];
+    noisy.extend_from_slice(b"\x1b\x07");
+    assert_eq!(
+        detector.check(&noisy),
+        Err(Rejection::ControlCharacters { per_mille: 20, limit: 10 })
+    );
+    assert_eq!(detector.max_control_per_mille(None).check(&noisy), Ok(()));
+}
+
+#[test]
+fn long_lines_are_rejected() {
+    // A minified bundle or inline base64 blob is valid UTF-8 on one line
+    let blob = format!("data = \"{}\"\n", "QUJD".repeat(3000));
+    let detector = BinaryDetector::new();
+    assert_eq!(
+        detector.check(blob.as_bytes()),
+        Err(Rejection::LongLines {
+            longest: 12_009,
+            limit: 10_000
+        })
+    );
+    assert_eq!(detector.max_line_bytes(Some(20_000)).check(blob.as_bytes()), Ok(()));
+}
+
+#[test]
+fn size_is_checked_first() {
+    let detector = BinaryDetector::new().max_bytes(Some(4));
+    assert_eq!(detector.check(b"\0\0\0\0\0"), Err(Rejection::TooLarge { bytes: 5, limit: 4 }));
+    assert_eq!(detector.check_size(4), Ok(()));
+    assert_eq!(detector.max_bytes(None).check_size(u64::MAX), Ok(()));
+}
+
+#[test]
+fn stubs_keep_only_metadata() {
+    let raw = b"\x89PNG\r\n\x1a\n\0\0";
+    let reason = BinaryDetector::new().check(raw).unwrap_err();
+    let doc = stub(make_id("logo.png"), "logo.png".to_string(), raw, &reason, Metadata::new());
+    assert_eq!(doc.content, "");
+    assert_eq!(
+        doc.metadata.get(REJECTION_KEY),
+        Some(&MetadataValue::String("content is not valid UTF-8".to_string()))
+    );
+    assert_eq!(doc.metadata.get(SIZE_BYTES_KEY), Some(&MetadataValue::Number(10)));
+    assert_eq!(
+        doc.metadata.get(ORIGINAL_CONTENT_HASH_KEY).and_then(|v| v.as_str()),
+        Some(DocumentVersion::from_content(raw).as_str())
+    );
+}
+
+#[test]
+fn config_round_trips() {
+    let detector = BinaryDetector::new().max_line_bytes(None);
+    let json = serde_json::to_string(&detector).unwrap();
+    assert_eq!(json, r#"{"max_bytes":10485760,"max_control_per_mille":10}"#);
+    assert_eq!(serde_json::from_str::<BinaryDetector>(&json).unwrap(), detector);
+    assert_eq!(serde_json::to_string(&BinaryPolicy::Stub).unwrap(), r#""stub""#);
+    assert_eq!(
+        serde_json::to_string(&Rejection::LongLines { longest: 9, limit: 8 }).unwrap(),
+        r#"{"kind":"long_lines","longest":9,"limit":8}"#
+    );
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::compression::{elide_shared_boilerplate, SHARED_FOOTER_MARKER, SHARED_HEADER_MARKER};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+const HEADER: &str = "Copyright (c) Example Corp. Licensed under the Apache License 2.0.\nSee LICENSE for details.";
+const FOOTER: &str = "Home | Docs | Blog | Contact us | Privacy policy";
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn page(body: &str) -> String {
+    format!("{}\n\n{}\n\n{}\n", HEADER, body, FOOTER)
+}
+
+#[test]
+fn repeats_are_elided_after_the_first_occurrence() {
+    let a = page("deploy the service");
+    let b = page("rollback the service");
+    let c = format!("{}\n\nstandalone notes", HEADER);
+    let elided = elide_shared_boilerplate(&[&a, &b, &c, "short\n\nshort"]);
+
+    assert_eq!(elided[0], None);
+    assert_eq!(
+        elided[1].as_deref(),
+        Some(format!("{}\n\nrollback the service\n\n{}", SHARED_HEADER_MARKER, SHARED_FOOTER_MARKER).as_str())
+    );
+    assert_eq!(
+        elided[2].as_deref(),
+        Some(format!("{}\n\nstandalone notes", SHARED_HEADER_MARKER).as_str())
+    );
+    // Nothing earlier matches
+    assert_eq!(elided[3], None);
+}
+
+#[test]
+fn short_runs_are_kept() {
+    // A repeated block no longer than the marker is not worth eliding
+    let elided = elide_shared_boilerplate(&["---\n\none", "---\n\ntwo"]);
+    assert_eq!(elided, vec![None, None]);
+}
+
+#[test]
+fn identical_documents_collapse_to_a_marker() {
+    let a = page("same body");
+    let elided = elide_shared_boilerplate(&[&a, &a]);
+    assert_eq!(elided[1].as_deref(), Some(SHARED_HEADER_MARKER));
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", &page("deploy deploy deploy")),
+        make_doc("b.md", &page("deploy the service")),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn selection_reports_tokens_saved() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let plain = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(plain.selection.boilerplate_tokens_saved, None);
+
+    let options = SelectionOptions {
+        dedupe_boilerplate: true,
+        ..Default::default()
+    };
+    let result = selector.select_with(&cache, Query::new("deploy"), 1000, &options).unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md"]);
+
+    // The top-ranked document keeps its boilerplate
+    assert_eq!(result.documents[0].content, plain.documents[0].content);
+    assert_eq!(
+        result.documents[1].content,
+        format!("{}\n\ndeploy the service\n\n{}", SHARED_HEADER_MARKER, SHARED_FOOTER_MARKER)
+    );
+
+    let saved = plain.documents[1].tokens - result.documents[1].tokens;
+    assert!(saved > 0);
+    assert_eq!(result.selection.boilerplate_tokens_saved, Some(saved));
+    assert_eq!(result.selection.tokens_used, plain.selection.tokens_used - saved);
+}
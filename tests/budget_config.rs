@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetConfig, ContextSelector};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+// Each document is 8 bytes -> 2 tokens
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![
+                make_doc("a.md", "query aa"),
+                make_doc("b.md", "query bb"),
+                make_doc("c.md", "query cc"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn reserved_tokens_reduce_available_budget() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+
+    let full = selector.select(&cache, Query::new("query"), 6).unwrap();
+    assert_eq!(full.documents.len(), 3);
+
+    let reserved = selector
+        .select(&cache, Query::new("query"), BudgetConfig::new(6).reserve(2))
+        .unwrap();
+    assert_eq!(reserved.documents.len(), 2);
+    assert_eq!(reserved.selection.budget, 6);
+    assert_eq!(reserved.selection.budget_reserved, Some(2));
+    assert_eq!(reserved.selection.tokens_used, 4);
+    assert_eq!(reserved.selection.documents_excluded_by_budget, 1);
+}
+
+#[test]
+fn per_document_overhead_is_charged() {
+    let (_dir, cache) = build_cache();
+    let budget = BudgetConfig::new(6).per_document_overhead(1);
+
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("query"), budget)
+        .unwrap();
+
+    // 3 tokens per document including overhead
+    assert_eq!(result.documents.len(), 2);
+    assert_eq!(result.selection.tokens_used, 6);
+    assert_eq!(result.selection.budget_per_document_overhead, Some(1));
+}
+
+#[test]
+fn reservation_larger_than_total_selects_nothing() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("query"), BudgetConfig::new(4).reserve(10))
+        .unwrap();
+    assert!(result.documents.is_empty());
+    assert_eq!(result.selection.documents_excluded_by_budget, 3);
+}
+
+#[test]
+fn plain_budget_omits_reservation_fields() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("query"), 100)
+        .unwrap();
+    let json = serde_json::to_string(&result.selection).unwrap();
+    assert!(!json.contains("budget_reserved"));
+    assert!(!json.contains("budget_per_document_overhead"));
+}
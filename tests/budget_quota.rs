@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetQuota, ContextSelector, QuotaScope, SelectionOptions};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str, tags: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::new();
+    if !tags.is_empty() {
+        metadata.insert_string("tags", tags);
+    }
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("api/a.md", "deploy deploy deploy", ""),                // 5 tokens, score 1.0
+        make_doc("api/b.md", "deploy deploy deploy x", ""),              // 6 tokens, score 0.75
+        make_doc("api/c.md", "deploy deploy xx", ""),                    // 4 tokens, score 0.67
+        make_doc("docs/other.md", "deploy one two three", ""),           // 5 tokens, score 0.25
+        make_doc("docs/runbook.md", "deploy runbook steps here now", "ops, Runbook"), // 8 tokens, score 0.2
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn quotas() -> Vec<BudgetQuota> {
+    vec![
+        BudgetQuota::new(QuotaScope::Path("api/**".into())).max_percent(40),
+        BudgetQuota::new(QuotaScope::Tag("runbook".into())).min_documents(1),
+    ]
+}
+
+#[test]
+fn quotas_cap_and_reserve_budget() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+
+    let plain = selector.select(&cache, Query::new("deploy"), 20).unwrap();
+    let ids: Vec<&str> = plain.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["api/a.md", "api/b.md", "api/c.md", "docs/other.md"]);
+    assert_eq!(plain.selection.documents_excluded_by_quota, None);
+    assert!(plain.selection.quotas.is_empty());
+
+    let options = SelectionOptions {
+        quotas: quotas(),
+        ..Default::default()
+    };
+    let result = selector.select_with(&cache, Query::new("deploy"), 20, &options).unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    // Rank order is kept even though the runbook was admitted first
+    assert_eq!(ids, vec!["api/a.md", "docs/other.md", "docs/runbook.md"]);
+    assert_eq!(result.selection.tokens_used, 18);
+    assert_eq!(result.selection.documents_excluded_by_quota, Some(2));
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+
+    let api = &result.selection.quotas[0];
+    assert_eq!((api.used, api.documents, api.satisfied), (5, 1, true));
+    let runbook = &result.selection.quotas[1];
+    assert_eq!((runbook.used, runbook.documents, runbook.satisfied), (8, 1, true));
+
+    let again = selector.select_with(&cache, Query::new("deploy"), 20, &options).unwrap();
+    assert_eq!(serde_json::to_string(&result).unwrap(), serde_json::to_string(&again).unwrap());
+}
+
+#[test]
+fn unmet_minimum_is_reported() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        quotas: quotas(),
+        ..Default::default()
+    };
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 6, &options)
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    // 40% of 6 leaves no room for any api/ document
+    assert_eq!(ids, vec!["docs/other.md"]);
+    assert_eq!(result.selection.documents_excluded_by_quota, Some(3));
+    assert!(!result.selection.quotas[1].satisfied);
+
+    let json = serde_json::to_value(&result.selection.quotas).unwrap();
+    assert_eq!(
+        json[1],
+        serde_json::json!({"scope": {"tag": "runbook"}, "min_documents": 1, "used": 0, "documents": 0, "satisfied": false})
+    );
+}
+
+#[test]
+fn quotas_round_trip_in_options() {
+    let options = SelectionOptions {
+        quotas: quotas(),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&options).unwrap();
+    assert!(json.contains(r#""quotas":[{"scope":{"path":"api/**"},"max_percent":40}"#));
+    let back: SelectionOptions = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.quotas, options.quotas);
+    assert_eq!(serde_json::to_string(&SelectionOptions::default()).unwrap(), r#"{"limit":"unlimited"}"#);
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetConfig, BudgetStrategy, ContextSelector};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy"), // 4 tokens, score 1.0
+        make_doc("b.md", "deploy deploy filler filler filler filler filler filler"), // 14 tokens, score 0.25
+        make_doc("c.md", "deploy x y z w"), // 4 tokens, score 0.2
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn greedy_skips_and_continues() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), BudgetConfig::new(10))
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "c.md"]);
+    assert_eq!(result.selection.budget_strategy, None);
+    assert!(!serde_json::to_string(&result).unwrap().contains("budget_strategy"));
+}
+
+#[test]
+fn stop_at_first_exclusion_keeps_contiguous_top() {
+    let (_dir, cache) = build_cache();
+    let budget = BudgetConfig::new(10).strategy(BudgetStrategy::StopAtFirstExclusion);
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), budget)
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md"]);
+    assert_eq!(result.selection.tokens_used, 4);
+    assert_eq!(result.selection.documents_excluded_by_budget, 2);
+    assert_eq!(result.selection.budget_strategy, Some(BudgetStrategy::StopAtFirstExclusion));
+
+    let json = serde_json::to_value(budget).unwrap();
+    assert_eq!(json["strategy"], "stop_at_first_exclusion");
+    assert_eq!(serde_json::from_value::<BudgetConfig>(json).unwrap(), budget);
+}
+
+#[test]
+fn strategies_agree_when_everything_fits() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let greedy = selector.select(&cache, Query::new("deploy"), BudgetConfig::new(100)).unwrap();
+    let stop = selector
+        .select(
+            &cache,
+            Query::new("deploy"),
+            BudgetConfig::new(100).strategy(BudgetStrategy::StopAtFirstExclusion),
+        )
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&greedy.documents).unwrap(),
+        serde_json::to_string(&stop.documents).unwrap()
+    );
+}
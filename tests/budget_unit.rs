@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetConfig, BudgetUnit, ContextSelector};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy"),   // 13 chars, 13 bytes, 2 words
+        make_doc("b.md", "deploy 部署 now"), // 13 chars, 17 bytes, 3 words
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn select(cache: &ContextCache, budget: BudgetConfig) -> (Vec<String>, u64) {
+    let result = ContextSelector::default()
+        .select(cache, Query::new("deploy"), budget)
+        .unwrap();
+    assert_eq!(result.selection.budget_unit, (!budget.unit.is_tokens()).then_some(budget.unit));
+    let ids = result.documents.iter().map(|d| d.id.clone()).collect();
+    (ids, result.selection.tokens_used)
+}
+
+#[test]
+fn budgets_are_measured_in_their_unit() {
+    let (_dir, cache) = build_cache();
+
+    assert_eq!(select(&cache, BudgetConfig::new(26).unit(BudgetUnit::Chars)), (vec!["a.md".into(), "b.md".into()], 26));
+    assert_eq!(select(&cache, BudgetConfig::new(26).unit(BudgetUnit::Bytes)).0, vec!["a.md"]);
+    assert_eq!(select(&cache, BudgetConfig::new(30).unit(BudgetUnit::Bytes)).1, 30);
+    assert_eq!(select(&cache, BudgetConfig::new(4).unit(BudgetUnit::Words)), (vec!["a.md".into()], 2));
+    assert_eq!(
+        select(&cache, BudgetConfig::new(9).unit(BudgetUnit::Words).per_document_overhead(2)).1,
+        9
+    );
+}
+
+#[test]
+fn tokens_remain_the_default() {
+    let (_dir, cache) = build_cache();
+    assert_eq!(BudgetConfig::new(10).unit, BudgetUnit::Tokens);
+    assert_eq!(select(&cache, BudgetConfig::new(4)).0, vec!["a.md"]);
+
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert!(!serde_json::to_string(&result).unwrap().contains("budget_unit"));
+    assert_eq!(serde_json::to_string(&BudgetConfig::new(5)).unwrap(), r#"{"total":5,"reserved":0,"per_document_overhead":0}"#);
+
+    let words = serde_json::to_value(BudgetConfig::new(5).unit(BudgetUnit::Words)).unwrap();
+    assert_eq!(words["unit"], "words");
+}
@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetConfig, ContextSelector, SelectionOptions, DEFAULT_MAX_BUDGET};
+use context_core::types::{Query, SelectionError};
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn build(path: &Path) -> ContextCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "deploy beta")], path)
+        .unwrap()
+}
+
+fn invalid(result: Result<impl std::fmt::Debug, SelectionError>) -> u64 {
+    match result {
+        Err(SelectionError::InvalidBudget(total)) => total,
+        other => panic!("expected an invalid budget, got {other:?}"),
+    }
+}
+
+#[test]
+fn out_of_range_totals_are_rejected() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let selector = ContextSelector::default();
+
+    assert_eq!(invalid(selector.select(&cache, Query::new("deploy"), 0)), 0);
+    assert_eq!(invalid(selector.select(&cache, Query::new("deploy"), u64::MAX)), u64::MAX);
+    assert_eq!(
+        invalid(selector.select(&cache, Query::new("deploy"), DEFAULT_MAX_BUDGET + 1)),
+        DEFAULT_MAX_BUDGET + 1
+    );
+    assert!(selector.select(&cache, Query::new("deploy"), DEFAULT_MAX_BUDGET).is_ok());
+    assert_eq!(invalid(selector.select_multi(&cache, &[Query::new("alpha"), Query::new("beta")], 0)), 0);
+    assert_eq!(SelectionError::InvalidBudget(0).to_string(), "Invalid budget: 0");
+}
+
+#[test]
+fn the_maximum_is_configurable() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+
+    let strict = ContextSelector::default().with_max_budget(100);
+    assert!(strict.select(&cache, Query::new("deploy"), 100).is_ok());
+    assert_eq!(invalid(strict.select(&cache, Query::new("deploy"), 101)), 101);
+
+    let open = ContextSelector::default().with_max_budget(u64::MAX);
+    let result = open.select(&cache, Query::new("deploy"), u64::MAX).unwrap();
+    assert_eq!(result.documents.len(), 2);
+}
+
+#[test]
+fn snapshots_validate_too() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let snapshot = cache.load_snapshot().unwrap();
+    let selector = ContextSelector::default();
+
+    assert_eq!(invalid(selector.select_snapshot(&snapshot, Query::new("deploy"), 0)), 0);
+    assert_eq!(
+        invalid(selector.select_snapshot_ref(&snapshot, Query::new("deploy"), u64::MAX, &SelectionOptions::default())),
+        u64::MAX
+    );
+}
+
+#[test]
+fn extreme_reservations_and_overheads_do_not_overflow() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let selector = ContextSelector::default().with_max_budget(u64::MAX);
+
+    // A reservation beyond the total leaves nothing for documents
+    let budget = BudgetConfig::new(100).reserve(1000);
+    let result = selector.select(&cache, Query::new("deploy"), budget).unwrap();
+    assert!(result.documents.is_empty());
+
+    // Costs that would overflow saturate rather than wrap: the first
+    // document uses the whole budget
+    let budget = BudgetConfig::new(u64::MAX).per_document_overhead(u64::MAX);
+    let result = selector.select(&cache, Query::new("deploy"), budget).unwrap();
+    assert_eq!(result.documents.len(), 1);
+    assert_eq!(result.selection.tokens_used, u64::MAX);
+}
@@ -0,0 +1,109 @@
+use std::io::Result;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheStore, Durability, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "one"), make_doc("b.md", "two")]
+}
+
+/// A `MemoryStore` counting synced and unsynced writes.
+#[derive(Debug, Default)]
+struct CountingStore {
+    inner: MemoryStore,
+    synced: AtomicUsize,
+    unsynced: AtomicUsize,
+}
+
+impl CacheStore for CountingStore {
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        self.inner.read_file(path)
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.synced.fetch_add(1, Ordering::Relaxed);
+        self.inner.write_file(path, contents)
+    }
+
+    fn write_file_unsynced(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.unsynced.fetch_add(1, Ordering::Relaxed);
+        self.inner.write_file(path, contents)
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.inner.create_new(path, contents)
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<String>> {
+        self.inner.list(dir)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename(from, to)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        self.inner.remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.inner.remove_file(path)
+    }
+}
+
+#[test]
+fn fast_builds_skip_fsync() {
+    let store = Arc::new(CountingStore::default());
+    let config = CacheBuildConfig::v0().with_durability(Durability::Fast);
+    CacheBuilder::new(config)
+        .with_store(store.clone())
+        .build(docs(), Path::new("/caches/fast"))
+        .unwrap();
+    // Two documents, index.json, manifest.json
+    assert_eq!(store.unsynced.load(Ordering::Relaxed), 4);
+    assert_eq!(store.synced.load(Ordering::Relaxed), 0);
+
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .build(docs(), Path::new("/caches/durable"))
+        .unwrap();
+    assert_eq!(store.synced.load(Ordering::Relaxed), 4);
+}
+
+#[test]
+fn fast_builds_are_identical() {
+    let dir = tempdir().unwrap();
+    let durable = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("durable"))
+        .unwrap();
+    let fast = CacheBuilder::new(CacheBuildConfig::v0().with_durability(Durability::Fast))
+        .build(docs(), &dir.path().join("fast"))
+        .unwrap();
+
+    fast.verify().unwrap();
+    assert_eq!(fast.manifest.cache_version, durable.manifest.cache_version);
+    assert_eq!(fast.manifest.tree_hash, durable.manifest.tree_hash);
+    // Not recorded in the manifest
+    assert_eq!(
+        serde_json::to_value(&fast.manifest.build_config).unwrap(),
+        serde_json::to_value(&durable.manifest.build_config).unwrap()
+    );
+}
@@ -0,0 +1,203 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::lock::{lock_path, LockHolder};
+use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder, CacheStore, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn builder(store: &Arc<MemoryStore>) -> CacheBuilder {
+    CacheBuilder::new(CacheBuildConfig::v0()).with_store(store.clone())
+}
+
+fn hold_lock(store: &MemoryStore, holder: &LockHolder) {
+    let lock = lock_path(Path::new("/caches/a"));
+    store.write_file(&lock, &serde_json::to_vec(holder).unwrap()).unwrap();
+}
+
+#[test]
+fn lock_is_released_after_a_build() {
+    let store = Arc::new(MemoryStore::new());
+    builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")).unwrap();
+    assert!(!store.exists(&lock_path(Path::new("/caches/a"))));
+
+    // A failed build releases it too
+    let err = builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")).unwrap_err();
+    assert!(matches!(err, CacheBuildError::OutputExists(_)));
+    builder(&store).build(vec![make_doc("b.md", "two")], Path::new("/caches/b")).unwrap();
+}
+
+#[test]
+fn locks_are_per_output() {
+    let store = Arc::new(MemoryStore::new());
+    let holder = LockHolder {
+        pid: Some(4242),
+        acquired_at: None,
+    };
+    hold_lock(&store, &holder);
+
+    // A sibling output, or one differing only in extension, is not blocked
+    builder(&store).build(vec![make_doc("b.md", "two")], Path::new("/caches/b")).unwrap();
+    builder(&store).build(vec![make_doc("b.md", "two")], Path::new("/caches/a.v2")).unwrap();
+    assert!(matches!(
+        builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")),
+        Err(CacheBuildError::Locked { .. })
+    ));
+
+    // A bare relative output locks next to itself, not a shared cwd file
+    assert_eq!(lock_path(Path::new("cache")), Path::new("cache.lock"));
+    assert_eq!(lock_path(Path::new("caches/a/")), Path::new("caches/a.lock"));
+}
+
+#[test]
+fn held_lock_fails_the_build() {
+    let store = Arc::new(MemoryStore::new());
+    let holder = LockHolder {
+        pid: Some(4242),
+        acquired_at: None,
+    };
+    hold_lock(&store, &holder);
+
+    match builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")) {
+        Err(CacheBuildError::Locked { path, holder: Some(found) }) => {
+            assert_eq!(path, lock_path(Path::new("/caches/a")));
+            assert_eq!(found, holder);
+        }
+        other => panic!("expected a locked build, got {other:?}"),
+    }
+    assert!(!store.exists(Path::new("/caches/a")));
+
+    let err = builder(&store)
+        .build(vec![make_doc("a.md", "one")], Path::new("/caches/a"))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Cache build locked by process 4242: /caches/a.lock");
+
+    // An unreadable lock is held, not stale
+    store.write_file(&lock_path(Path::new("/caches/a")), b"").unwrap();
+    assert!(matches!(
+        builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")),
+        Err(CacheBuildError::Locked { holder: None, .. })
+    ));
+}
+
+#[cfg(feature = "clock")]
+#[test]
+fn stale_lock_is_taken_over() {
+    use std::time::Duration;
+
+    let store = Arc::new(MemoryStore::new());
+    let now = chrono::Utc::now().timestamp();
+    let recent = LockHolder {
+        pid: Some(1),
+        acquired_at: Some(now - 60),
+    };
+    hold_lock(&store, &recent);
+    assert!(matches!(
+        builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")),
+        Err(CacheBuildError::Locked { .. })
+    ));
+
+    // Older than the threshold: its build is assumed to have crashed
+    let cache = builder(&store)
+        .with_stale_lock_after(Duration::from_secs(30))
+        .build(vec![make_doc("a.md", "one")], Path::new("/caches/a"))
+        .unwrap();
+    assert_eq!(cache.load_documents().unwrap().len(), 1);
+    assert!(!store.exists(&lock_path(Path::new("/caches/a"))));
+}
+
+#[test]
+fn locks_are_not_taken_over_by_default() {
+    let store = Arc::new(MemoryStore::new());
+    let ancient = LockHolder {
+        pid: Some(1),
+        acquired_at: Some(0),
+    };
+    hold_lock(&store, &ancient);
+    assert!(matches!(
+        builder(&store).build(vec![make_doc("a.md", "one")], Path::new("/caches/a")),
+        Err(CacheBuildError::Locked { .. })
+    ));
+}
+
+/// A `MemoryStore` where another builder takes over the stale lock right
+/// before this one renames it aside.
+#[cfg(feature = "clock")]
+#[derive(Debug)]
+struct RacingStore {
+    inner: MemoryStore,
+    fresh: Vec<u8>,
+}
+
+#[cfg(feature = "clock")]
+impl CacheStore for RacingStore {
+    fn read_file(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        self.inner.read_file(path)
+    }
+    fn write_file(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        self.inner.write_file(path, contents)
+    }
+    fn create_new(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        self.inner.create_new(path, contents)
+    }
+    fn list(&self, dir: &Path) -> std::io::Result<Vec<String>> {
+        self.inner.list(dir)
+    }
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        if from == lock_path(Path::new("/caches/a")) {
+            self.inner.remove_file(from)?;
+            self.inner.create_new(from, &self.fresh)?;
+        }
+        self.inner.rename(from, to)
+    }
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+    fn remove_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.remove_dir_all(path)
+    }
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.remove_file(path)
+    }
+}
+
+#[cfg(feature = "clock")]
+#[test]
+fn a_lock_taken_over_by_another_builder_is_left_to_it() {
+    use std::time::Duration;
+
+    let fresh = LockHolder {
+        pid: Some(2),
+        acquired_at: Some(chrono::Utc::now().timestamp()),
+    };
+    let store = Arc::new(RacingStore {
+        inner: MemoryStore::new(),
+        fresh: serde_json::to_vec(&fresh).unwrap(),
+    });
+    let lock = lock_path(Path::new("/caches/a"));
+    let stale = LockHolder {
+        pid: Some(1),
+        acquired_at: Some(0),
+    };
+    store.write_file(&lock, &serde_json::to_vec(&stale).unwrap()).unwrap();
+
+    let result = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .with_stale_lock_after(Duration::from_secs(30))
+        .build(vec![make_doc("a.md", "one")], Path::new("/caches/a"));
+    match result {
+        Err(CacheBuildError::Locked { holder: Some(holder), .. }) => assert_eq!(holder, fresh),
+        other => panic!("expected a locked build, got {other:?}"),
+    }
+    // The other builder's lock is back in place, and nothing is left aside
+    assert_eq!(store.read_file(&lock).unwrap(), serde_json::to_vec(&fresh).unwrap());
+    assert_eq!(store.list(Path::new("/caches")).unwrap(), vec!["a.lock".to_string()]);
+}
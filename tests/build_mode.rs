@@ -0,0 +1,177 @@
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use context_core::cache::{
+    BuildMode, CacheBuildConfig, CacheBuildError, CacheBuilder, CacheStore, ContextCache, MemoryStore,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+#[test]
+fn existing_output_is_an_error_by_default() {
+    let dir = tempdir().unwrap();
+    let output = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "one")], &output)
+        .unwrap();
+
+    let builder = CacheBuilder::new(CacheBuildConfig::v0()).with_build_mode(BuildMode::ErrorIfExists);
+    assert!(matches!(
+        builder.build(vec![make_doc("a.md", "two")], &output),
+        Err(CacheBuildError::OutputExists(path)) if path == output
+    ));
+    let cache = ContextCache::open(&output).unwrap();
+    assert_eq!(cache.load_documents().unwrap()[0].content, "one");
+}
+
+#[test]
+fn overwrite_replaces_the_output() {
+    let dir = tempdir().unwrap();
+    let output = dir.path().join("cache");
+    let builder = CacheBuilder::new(CacheBuildConfig::v0()).with_build_mode(BuildMode::Overwrite);
+
+    // Overwriting nothing is a plain build
+    builder.build(vec![make_doc("a.md", "one"), make_doc("b.md", "gone")], &output).unwrap();
+    let rebuilt = builder.build(vec![make_doc("a.md", "two")], &output).unwrap();
+
+    let cache = ContextCache::open(&output).unwrap();
+    cache.verify().unwrap();
+    assert_eq!(cache.manifest.cache_version, rebuilt.manifest.cache_version);
+    let docs = cache.load_documents().unwrap();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].content, "two");
+
+    // Nothing is left beside the output
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["cache"]);
+}
+
+#[test]
+fn overwrite_never_removes_directories_it_did_not_create() {
+    let store = Arc::new(MemoryStore::new());
+    let output = Path::new("/caches/current");
+    let builder = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .with_build_mode(BuildMode::Overwrite);
+    builder.build(vec![make_doc("a.md", "one")], output).unwrap();
+    let expected = builder.build_in_memory(vec![make_doc("a.md", "two")]).unwrap();
+    let hex = &expected.manifest().cache_version["sha256:".len()..][..12];
+    // A user's directory, and one left by an overwrite that crashed
+    store.write_file(Path::new("/caches/current.old/notes"), b"mine").unwrap();
+    let crashed = format!("/caches/current.old.{}/stale", hex);
+    store.write_file(Path::new(&crashed), b"left by a crash").unwrap();
+
+    let streamed = builder
+        .build_from_iter(vec![Ok::<_, Infallible>(make_doc("a.md", "two"))], output)
+        .unwrap();
+    assert_eq!(streamed.load_documents().unwrap()[0].content, "two");
+    assert_eq!(store.read_file(Path::new("/caches/current.old/notes")).unwrap(), b"mine");
+    assert!(store.exists(Path::new(&crashed)));
+    let mut names = store.list(Path::new("/caches")).unwrap();
+    names.sort();
+    assert_eq!(names, vec!["current".to_string(), "current.old".to_string(), format!("current.old.{}", hex)]);
+}
+
+#[test]
+fn overwrites_of_different_outputs_move_aside_to_different_names() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0()).with_build_mode(BuildMode::Overwrite);
+    let (v1, v2) = (dir.path().join("cache.v1"), dir.path().join("cache.v2"));
+    builder.build(vec![make_doc("a.md", "one")], &v1).unwrap();
+    builder.build(vec![make_doc("a.md", "one")], &v2).unwrap();
+    // cache.v2's backup would have been cache.old, like cache.v1's
+    std::fs::create_dir(dir.path().join("cache.old")).unwrap();
+
+    builder.build(vec![make_doc("a.md", "two")], &v1).unwrap();
+    builder.build(vec![make_doc("a.md", "two")], &v2).unwrap();
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["cache.old", "cache.v1", "cache.v2"]);
+}
+
+/// A `MemoryStore` that fails to remove old caches, or to rename new ones
+/// into place.
+#[derive(Debug, Default)]
+struct FailingStore {
+    inner: MemoryStore,
+    fail_removal: AtomicBool,
+    fail_swap: AtomicBool,
+}
+
+impl CacheStore for FailingStore {
+    fn read_file(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        self.inner.read_file(path)
+    }
+    fn write_file(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        self.inner.write_file(path, contents)
+    }
+    fn create_new(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        self.inner.create_new(path, contents)
+    }
+    fn list(&self, dir: &Path) -> std::io::Result<Vec<String>> {
+        self.inner.list(dir)
+    }
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        if self.fail_swap.load(Ordering::Relaxed) && from.to_string_lossy().contains(".tmp.") {
+            return Err(std::io::Error::other("rename failed"));
+        }
+        self.inner.rename(from, to)
+    }
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+    fn remove_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        if self.fail_removal.load(Ordering::Relaxed) && path.to_string_lossy().contains(".old.") {
+            return Err(std::io::Error::other("removal failed"));
+        }
+        self.inner.remove_dir_all(path)
+    }
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.remove_file(path)
+    }
+}
+
+#[test]
+fn overwrite_survives_failures_around_the_swap() {
+    let store = Arc::new(FailingStore::default());
+    let output = Path::new("/caches/current");
+    let builder = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .with_build_mode(BuildMode::Overwrite);
+    builder.build(vec![make_doc("a.md", "one")], output).unwrap();
+
+    // A failed swap puts the old cache back
+    store.fail_swap.store(true, Ordering::Relaxed);
+    assert!(builder.build(vec![make_doc("a.md", "two")], output).is_err());
+    let cache = ContextCache::open_in(store.clone(), output).unwrap();
+    assert_eq!(cache.load_documents().unwrap()[0].content, "one");
+    assert_eq!(store.list(Path::new("/caches")).unwrap(), vec!["current".to_string()]);
+
+    // Once the new cache is in place, an old one that cannot be removed
+    // does not fail the build
+    store.fail_swap.store(false, Ordering::Relaxed);
+    store.fail_removal.store(true, Ordering::Relaxed);
+    let cache = builder.build(vec![make_doc("a.md", "two")], output).unwrap();
+    assert_eq!(cache.load_documents().unwrap()[0].content, "two");
+    let names = store.list(Path::new("/caches")).unwrap();
+    assert_eq!(names.len(), 2);
+    assert!(names[1].starts_with("current.old."), "{names:?}");
+}
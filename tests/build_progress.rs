@@ -0,0 +1,98 @@
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use context_core::cache::{
+    BuildObserver, BuildPhase, CacheBuildConfig, CacheBuildError, CacheBuilder, CacheStore, CancellationToken,
+    MemoryStore,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("b.md", "two"), make_doc("a.md", "one"), make_doc("c.md", "three")]
+}
+
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+    cancel_after: Option<(usize, CancellationToken)>,
+}
+
+impl BuildObserver for Recorder {
+    fn on_phase_change(&self, phase: BuildPhase) {
+        self.events.lock().unwrap().push(format!("{:?}", phase));
+    }
+
+    fn on_document_written(&self, id: &DocumentId, written: usize, total: Option<usize>) {
+        self.events.lock().unwrap().push(format!("{} {}/{:?}", id.as_str(), written, total));
+        if let Some((after, token)) = &self.cancel_after {
+            if written == *after {
+                token.cancel();
+            }
+        }
+    }
+}
+
+fn events(recorder: &Recorder) -> Vec<String> {
+    recorder.events.lock().unwrap().clone()
+}
+
+#[test]
+fn observer_sees_phases_and_documents() {
+    let store = Arc::new(MemoryStore::new());
+    let recorder = Recorder::default();
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .with_observer(recorder.clone())
+        .build(docs(), Path::new("/caches/built"))
+        .unwrap();
+    assert_eq!(
+        events(&recorder),
+        vec!["Planning", "Writing", "a.md 1/Some(3)", "b.md 2/Some(3)", "c.md 3/Some(3)", "Committing"]
+    );
+
+    // Streaming builds write in arrival order, without a total
+    let recorder = Recorder::default();
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store)
+        .with_observer(recorder.clone())
+        .build_from_iter(docs().into_iter().map(Ok::<_, Infallible>), Path::new("/caches/streamed"))
+        .unwrap();
+    assert_eq!(
+        events(&recorder),
+        vec!["Writing", "b.md 1/None", "a.md 2/None", "c.md 3/None", "Committing"]
+    );
+}
+
+#[test]
+fn cancelled_builds_stop_and_clean_up() {
+    let store = Arc::new(MemoryStore::new());
+    let token = CancellationToken::new();
+    let recorder = Recorder {
+        cancel_after: Some((1, token.clone())),
+        ..Recorder::default()
+    };
+    let builder = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .with_observer(recorder.clone())
+        .with_cancellation(token);
+
+    let result = builder.build(docs(), Path::new("/caches/built"));
+    assert!(matches!(result, Err(CacheBuildError::Cancelled)));
+    assert_eq!(events(&recorder).last().unwrap(), "a.md 1/Some(3)");
+
+    let result = builder.build_from_iter(docs().into_iter().map(Ok::<_, Infallible>), Path::new("/caches/streamed"));
+    assert!(matches!(result, Err(CacheBuildError::Cancelled)));
+
+    // Nothing but the (empty) parent is left behind
+    assert_eq!(store.list(Path::new("/caches")).unwrap(), Vec::<String>::new());
+
+    // A cancelled token fails in-memory builds too
+    assert!(matches!(builder.build_in_memory(docs()), Err(CacheBuildError::Cancelled)));
+}
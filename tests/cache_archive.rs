@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, FsStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn archive_round_trip_preserves_cache() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![make_doc("a.md", "alpha deploy"), make_doc("b.md", "beta")],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+
+    let archive = dir.path().join("cache.tar");
+    cache.export_archive(&archive).unwrap();
+
+    let imported = ContextCache::import_archive(&archive, dir.path().join("imported")).unwrap();
+    assert_eq!(imported.manifest.cache_version, cache.manifest.cache_version);
+
+    for name in ["manifest.json", "index.json"] {
+        assert_eq!(
+            fs::read(dir.path().join("cache").join(name)).unwrap(),
+            fs::read(dir.path().join("imported").join(name)).unwrap()
+        );
+    }
+
+    let selector = ContextSelector::default();
+    let before = selector.select(&cache, Query::new("deploy"), 100).unwrap();
+    let after = selector.select(&imported, Query::new("deploy"), 100).unwrap();
+    assert_eq!(
+        serde_json::to_string(&before).unwrap(),
+        serde_json::to_string(&after).unwrap()
+    );
+}
+
+#[test]
+fn archive_bytes_are_reproducible() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &dir.path().join("cache"))
+        .unwrap();
+
+    cache.export_archive(dir.path().join("one.tar")).unwrap();
+    cache.export_archive(dir.path().join("two.tar")).unwrap();
+
+    let one = fs::read(dir.path().join("one.tar")).unwrap();
+    let two = fs::read(dir.path().join("two.tar")).unwrap();
+    assert_eq!(one, two);
+    assert_eq!(one.len() % 512, 0);
+
+    // Sorted entries: documents/ before index.json before manifest.json
+    let text = String::from_utf8_lossy(&one);
+    let docs = text.find("documents/").unwrap();
+    let index = text.find("index.json").unwrap();
+    let manifest = text.find("manifest.json").unwrap();
+    assert!(docs < index && index < manifest);
+}
+
+#[test]
+fn import_rejects_corrupted_archive() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &dir.path().join("cache"))
+        .unwrap();
+    let archive = dir.path().join("cache.tar");
+    cache.export_archive(&archive).unwrap();
+
+    let mut bytes = fs::read(&archive).unwrap();
+    bytes[0] ^= 0x01; // break the first header checksum
+    fs::write(&archive, bytes).unwrap();
+
+    assert!(ContextCache::import_archive(&archive, dir.path().join("out")).is_err());
+    assert!(!dir.path().join("out").exists());
+}
+
+#[test]
+fn import_rejects_tampered_document_without_creating_output() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &dir.path().join("cache"))
+        .unwrap();
+    let mut bytes = cache.to_archive_bytes().unwrap();
+
+    // Change the document's content inside its entry, keeping the tar valid
+    let name = cache.manifest.documents[0].file.as_bytes();
+    let header = (0..bytes.len()).step_by(512).find(|&at| bytes[at..].starts_with(name)).unwrap();
+    let content = header + 512 + bytes[header + 512..].windows(5).position(|w| w == b"alpha").unwrap();
+    bytes[content] = b'A';
+
+    let out = dir.path().join("out");
+    let err = ContextCache::import_archive_bytes_in(Arc::new(FsStore), &bytes, &out).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{}", err);
+    assert!(!out.exists());
+    let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert_eq!(names, vec!["cache"], "the temp dir is removed");
+
+    // A retry with the genuine archive is not blocked by the failed one
+    ContextCache::import_archive_bytes_in(Arc::new(FsStore), &cache.to_archive_bytes().unwrap(), &out).unwrap();
+}
+
+#[test]
+fn concurrent_imports_into_outputs_differing_in_extension() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &dir.path().join("cache"))
+        .unwrap();
+    let bytes = cache.to_archive_bytes().unwrap();
+
+    let outputs: Vec<_> = (0..8).map(|i| dir.path().join(format!("out.{}", i))).collect();
+    std::thread::scope(|scope| {
+        for out in &outputs {
+            let bytes = &bytes;
+            scope.spawn(move || ContextCache::import_archive_bytes_in(Arc::new(FsStore), bytes, out).unwrap());
+        }
+    });
+    for out in &outputs {
+        let imported = ContextCache::open(out).unwrap();
+        assert_eq!(imported.manifest.cache_version, cache.manifest.cache_version);
+    }
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 9);
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use context_core::cache::{diff, CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn diff_reports_added_removed_modified() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+
+    let old = builder
+        .build(
+            vec![
+                make_doc("keep.md", "same"),
+                make_doc("change.md", "before"),
+                make_doc("gone.md", "removed"),
+            ],
+            &dir.path().join("old"),
+        )
+        .unwrap();
+    let new = builder
+        .build(
+            vec![
+                make_doc("keep.md", "same"),
+                make_doc("change.md", "after"),
+                make_doc("fresh.md", "added"),
+            ],
+            &dir.path().join("new"),
+        )
+        .unwrap();
+
+    let d = diff(&old.manifest, &new.manifest);
+
+    assert_eq!(d.old_cache_version, old.manifest.cache_version);
+    assert_eq!(d.new_cache_version, new.manifest.cache_version);
+    assert_eq!(d.added.len(), 1);
+    assert_eq!(d.added[0].id.as_str(), "fresh.md");
+    assert_eq!(d.removed.len(), 1);
+    assert_eq!(d.removed[0].id.as_str(), "gone.md");
+    assert_eq!(d.modified.len(), 1);
+    assert_eq!(d.modified[0].id.as_str(), "change.md");
+    assert_ne!(d.modified[0].old_version, d.modified[0].new_version);
+    assert_eq!(d.unchanged, 1);
+    assert!(!d.is_empty());
+
+    let json = serde_json::to_value(&d).unwrap();
+    assert_eq!(json["modified"][0]["id"], "change.md");
+}
+
+#[test]
+fn diff_of_identical_manifests_is_empty() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &dir.path().join("cache"))
+        .unwrap();
+
+    let d = diff(&cache.manifest, &cache.manifest);
+    assert!(d.is_empty());
+    assert_eq!(d.unchanged, 1);
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "http")]
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use context_core::cache::{
+    fetch_http, CacheBuildConfig, CacheBuilder, ContextCache, FetchError, HashingEmbedder,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+// Served files by URL path (`/cache/manifest.json`)
+type Files = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+struct Server {
+    base: String,
+    requests: Arc<AtomicUsize>,
+    files: Files,
+}
+
+// Serves `files`, and 404 for anything else
+fn serve() -> Server {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let files: Files = Arc::default();
+
+    let (count, served) = (requests.clone(), files.clone());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            count.fetch_add(1, Ordering::SeqCst);
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+            let path = request_line.split(' ').nth(1).unwrap_or("/");
+            let response = match served.lock().unwrap().get(path) {
+                Some(body) => {
+                    let mut head =
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len())
+                            .into_bytes();
+                    head.extend_from_slice(body);
+                    head
+                }
+                None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+            };
+            stream.write_all(&response).unwrap();
+        }
+    });
+
+    Server { base, requests, files }
+}
+
+impl Server {
+    /// Serve every file of `cache` under `/<prefix>/`.
+    fn publish(&self, cache: &ContextCache, prefix: &str) {
+        let mut files = self.files.lock().unwrap();
+        let mut pending = vec![cache.root.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let rel = path.strip_prefix(&cache.root).unwrap().to_str().unwrap().replace('\\', "/");
+                files.insert(format!("/{}/{}", prefix, rel), std::fs::read(&path).unwrap());
+            }
+        }
+    }
+
+    fn set(&self, path: &str, body: Vec<u8>) {
+        self.files.lock().unwrap().insert(path.to_string(), body);
+    }
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn build(dir: &Path, content: &str) -> ContextCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::new(16))
+        .build(
+            vec![make_doc("docs/a.md", content), make_doc("docs/b.md", "rollback beta")],
+            dir,
+        )
+        .unwrap()
+}
+
+#[test]
+fn pulls_verifies_and_selects() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let source = build(&dir.path().join("source"), "deploy alpha");
+    server.publish(&source, "cache");
+    let version = &source.manifest.cache_version;
+
+    let dest = dir.path().join("pulled");
+    let cache = fetch_http(&format!("{}/cache/", server.base), version, &dest).unwrap();
+    assert_eq!(&cache.manifest.cache_version, version);
+    assert_eq!(cache.root, dest);
+    assert!(cache.load_vectors().unwrap().is_some());
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.documents[0].id.as_str(), "docs/a.md");
+
+    // The destination is never overwritten
+    let err = fetch_http(&format!("{}/cache", server.base), version, &dest).unwrap_err();
+    assert!(matches!(err, FetchError::Io(e) if e.kind() == std::io::ErrorKind::AlreadyExists));
+}
+
+#[test]
+fn pulls_archives() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let source = build(&dir.path().join("source"), "deploy alpha");
+    server.set("/cache.tar", source.to_archive_bytes().unwrap());
+
+    let dest = dir.path().join("pulled");
+    let cache = fetch_http(&format!("{}/cache.tar", server.base), &source.manifest.cache_version, &dest).unwrap();
+    assert_eq!(cache.manifest.cache_version, source.manifest.cache_version);
+    cache.verify().unwrap();
+
+    let err = fetch_http(&format!("{}/cache.tar", server.base), "sha256:00", dir.path().join("other")).unwrap_err();
+    assert!(matches!(err, FetchError::VersionMismatch { .. }), "{}", err);
+    assert!(!dir.path().join("other").exists());
+}
+
+#[test]
+fn refuses_another_version_before_downloading_documents() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let source = build(&dir.path().join("source"), "deploy alpha");
+    server.publish(&source, "cache");
+
+    let dest = dir.path().join("pulled");
+    let err = fetch_http(&format!("{}/cache", server.base), "sha256:1234", &dest).unwrap_err();
+    match err {
+        FetchError::VersionMismatch { expected, found } => {
+            assert_eq!(expected, "sha256:1234");
+            assert_eq!(found, source.manifest.cache_version);
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+    assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+    assert!(!dest.exists());
+}
+
+#[test]
+fn refuses_files_that_do_not_match_the_manifest() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let source = build(&dir.path().join("source"), "deploy alpha");
+    server.publish(&source, "cache");
+    let file = &source.manifest.documents[0].file;
+    server.set(&format!("/cache/{}", file), b"{}".to_vec());
+
+    let dest = dir.path().join("pulled");
+    let err = fetch_http(&format!("{}/cache", server.base), &source.manifest.cache_version, &dest).unwrap_err();
+    assert!(matches!(err, FetchError::Verification(_)), "{}", err);
+    assert!(!dest.exists());
+    assert!(!dest.with_extension("fetch.tmp").exists());
+
+    // A missing document is an HTTP error
+    server.files.lock().unwrap().remove(&format!("/cache/{}", file));
+    let err = fetch_http(&format!("{}/cache", server.base), &source.manifest.cache_version, &dest).unwrap_err();
+    assert!(matches!(err, FetchError::Http(_)), "{}", err);
+}
+
+#[test]
+fn refuses_a_cache_relabeled_with_the_expected_version() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let expected = build(&dir.path().join("expected"), "deploy alpha");
+    let other = build(&dir.path().join("other"), "deploy something else");
+    server.publish(&other, "cache");
+
+    // Every file matches the manifest, but the manifest lies about its version
+    let mut manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(other.root.join("manifest.json")).unwrap()).unwrap();
+    manifest["cache_version"] = expected.manifest.cache_version.clone().into();
+    server.set("/cache/manifest.json", serde_json::to_vec(&manifest).unwrap());
+
+    let dest = dir.path().join("pulled");
+    let err = fetch_http(&format!("{}/cache", server.base), &expected.manifest.cache_version, &dest).unwrap_err();
+    assert!(matches!(err, FetchError::Verification(_)), "{}", err);
+    assert!(err.to_string().contains(&other.manifest.cache_version), "{}", err);
+    assert!(!dest.exists());
+}
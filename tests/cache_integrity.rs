@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, HashingEmbedder};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "alpha"), make_doc("b.md", "beta")]
+}
+
+#[test]
+fn manifest_records_checksums_and_fresh_cache_verifies() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    let index_checksum = cache.manifest.index_checksum.as_deref().unwrap();
+    assert!(index_checksum.starts_with("sha256:"));
+    assert!(cache.manifest.tree_hash.as_deref().unwrap().starts_with("sha256:"));
+
+    ContextCache::open(&path).unwrap().verify().unwrap();
+}
+
+#[test]
+fn tree_hash_is_deterministic() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+    let a = builder.build(docs(), &dir.path().join("a")).unwrap();
+    let b = builder.build(docs(), &dir.path().join("b")).unwrap();
+    assert_eq!(a.manifest.tree_hash, b.manifest.tree_hash);
+    assert_eq!(a.manifest.index_checksum, b.manifest.index_checksum);
+}
+
+#[test]
+fn open_rejects_tampered_index() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    let index = fs::read_to_string(path.join("index.json")).unwrap();
+    fs::write(path.join("index.json"), index.replace("b.md", "c.md")).unwrap();
+
+    let err = ContextCache::open(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("index.json checksum mismatch"));
+}
+
+#[test]
+fn verify_rejects_tampered_document_metadata() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    // Content (and so the document version) is untouched; only the file differs
+    let file = path.join(&cache.manifest.documents[0].file);
+    let doc = fs::read_to_string(&file).unwrap();
+    fs::write(&file, doc.replace("\"source\":\"a.md\"", "\"source\":\"evil.md\"")).unwrap();
+
+    let reopened = ContextCache::open(&path).unwrap();
+    assert!(reopened.load_documents().is_ok());
+    let err = reopened.verify().unwrap_err();
+    assert!(err.to_string().contains("tree hash mismatch"));
+}
+
+#[test]
+fn verify_rejects_removed_vectors() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::new(16))
+        .build(docs(), &path)
+        .unwrap();
+
+    fs::remove_file(path.join("vectors.json")).unwrap();
+    assert!(ContextCache::open(&path).unwrap().verify().is_err());
+}
+
+#[test]
+fn manifest_without_checksums_still_opens() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    let mut manifest: serde_json::Value =
+        serde_json::from_slice(&fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    let obj = manifest.as_object_mut().unwrap();
+    obj.remove("index_checksum");
+    obj.remove("tree_hash");
+    fs::write(path.join("manifest.json"), serde_json::to_vec_pretty(&manifest).unwrap()).unwrap();
+
+    let cache = ContextCache::open(&path).unwrap();
+    assert!(cache.manifest.tree_hash.is_none());
+    cache.verify().unwrap();
+}
@@ -1,7 +1,7 @@
 use std::fs;
 use std::path::Path;
 
-use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder};
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
 use context_core::document::{Document, DocumentId, Metadata};
 use tempfile::tempdir;
 
@@ -48,9 +48,9 @@ fn invariant_index_keys_are_sorted() {
 }
 
 #[test]
-fn invariant_filename_collision_is_fatal() {
+fn invariant_identical_content_is_stored_per_document() {
     let dir = tempdir().unwrap();
-    let cache_dir = dir.path().join("cache_collision");
+    let cache_dir = dir.path().join("cache_identical_content");
 
     let doc_a = make_doc("a.md", "same-content");
     let doc_b = make_doc("b.md", "same-content");
@@ -58,12 +58,43 @@ fn invariant_filename_collision_is_fatal() {
     let config = CacheBuildConfig::v0();
     let builder = CacheBuilder::new(config);
 
-    let result = builder.build(vec![doc_a, doc_b], &cache_dir);
+    let cache = builder.build(vec![doc_a, doc_b], &cache_dir).unwrap();
 
-    match result {
-        Err(CacheBuildError::FilenameCollision(_)) => {}
-        other => panic!("expected filename collision error, got {other:?}"),
-    }
+    let files: Vec<&str> = cache.manifest.documents.iter().map(|e| e.file.as_str()).collect();
+    let stem = &cache.manifest.documents[0].version.as_str()["sha256:".len()..][..12];
 
-    assert!(!cache_dir.exists(), "cache output must not be created on failure");
+    // First ID in sorted order keeps the plain content-hash name
+    assert_eq!(files[0], format!("documents/{stem}.json"));
+    assert!(files[1].starts_with(&format!("documents/{stem}-")));
+    assert_ne!(files[0], files[1]);
+
+    let loaded = cache.load_documents().unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded[0].id.as_str(), "a.md");
+    assert_eq!(loaded[1].id.as_str(), "b.md");
+}
+
+#[test]
+fn invariant_identical_content_filenames_are_deterministic() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+
+    let first = builder
+        .build(
+            vec![make_doc("b.md", "same-content"), make_doc("a.md", "same-content")],
+            &dir.path().join("first"),
+        )
+        .unwrap();
+    let second = builder
+        .build(
+            vec![make_doc("a.md", "same-content"), make_doc("b.md", "same-content")],
+            &dir.path().join("second"),
+        )
+        .unwrap();
+
+    assert_eq!(first.manifest.cache_version, second.manifest.cache_version);
+    assert_eq!(
+        fs::read(dir.path().join("first/index.json")).unwrap(),
+        fs::read(dir.path().join("second/index.json")).unwrap()
+    );
 }
@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use context_core::cache::{prune, CacheBuildConfig, CacheBuildError, CacheBuilder, HashingEmbedder};
+use context_core::compression::Summarizer;
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::PathFilter;
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str, status: Option<&str>) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    let mut metadata = Metadata::new();
+    if let Some(status) = status {
+        metadata.insert_string("status", status);
+    }
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        // Same content: archive/a.md takes the plain file name
+        make_doc("archive/a.md", "Shared text. More.", None),
+        make_doc("docs/a.md", "Shared text. More.", None),
+        make_doc("docs/b.md", "Guide text. Details.", Some("draft")),
+        make_doc("archive/old.md", "Old text.", None),
+    ]
+}
+
+fn builder() -> CacheBuilder {
+    let config = CacheBuildConfig::v0()
+        .with_fixed_timestamp(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap())
+        .with_summarizer(Summarizer::first_sentences(1));
+    CacheBuilder::new(config).with_embedder(HashingEmbedder::default())
+}
+
+fn file(root: &Path, relative: &str) -> Vec<u8> {
+    std::fs::read(root.join(relative)).unwrap()
+}
+
+#[test]
+fn pruned_cache_matches_a_fresh_build() {
+    let dir = tempdir().unwrap();
+    let cache = builder().build(docs(), &dir.path().join("full")).unwrap();
+
+    let archive = PathFilter::new().include("archive/**");
+    let pruned = prune(&cache, |doc| !archive.matches(&doc.id), &dir.path().join("pruned")).unwrap();
+    pruned.verify().unwrap();
+
+    let kept: Vec<Document> = docs().into_iter().filter(|doc| !doc.id.as_str().starts_with("archive/")).collect();
+    let fresh = builder().build(kept, &dir.path().join("fresh")).unwrap();
+
+    assert_eq!(pruned.manifest.cache_version, fresh.manifest.cache_version);
+    assert_eq!(pruned.manifest.tree_hash, fresh.manifest.tree_hash);
+    assert_eq!(pruned.manifest.created_at, cache.manifest.created_at);
+    for entry in &fresh.manifest.documents {
+        assert_eq!(file(&pruned.root, &entry.file), file(&fresh.root, &entry.file));
+    }
+    assert_eq!(file(&pruned.root, "manifest.json"), file(&fresh.root, "manifest.json"));
+    assert_eq!(file(&pruned.root, "vectors.json"), file(&fresh.root, "vectors.json"));
+
+    // docs/a.md no longer shares its content, so it gets the plain name
+    let full_a = cache.manifest.documents.iter().find(|e| e.id.as_str() == "docs/a.md").unwrap();
+    let pruned_a = pruned.manifest.documents.iter().find(|e| e.id.as_str() == "docs/a.md").unwrap();
+    assert!(full_a.file.contains('-'));
+    assert!(!pruned_a.file.contains('-'));
+}
+
+#[test]
+fn prune_by_metadata() {
+    let dir = tempdir().unwrap();
+    let cache = builder().build(docs(), &dir.path().join("full")).unwrap();
+
+    let pruned = prune(
+        &cache,
+        |doc| doc.metadata.get("status").and_then(|v| v.as_str()) != Some("draft"),
+        &dir.path().join("pruned"),
+    )
+    .unwrap();
+    let ids: Vec<&str> = pruned.manifest.documents.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, vec!["archive/a.md", "archive/old.md", "docs/a.md"]);
+    assert_eq!(pruned.load_vectors().unwrap().unwrap().vectors.len(), 3);
+
+    // Keeping everything reproduces the cache
+    let all = prune(&cache, |_| true, &dir.path().join("all")).unwrap();
+    assert_eq!(all.manifest.cache_version, cache.manifest.cache_version);
+
+    assert!(matches!(
+        prune(&cache, |_| true, &dir.path().join("all")),
+        Err(CacheBuildError::OutputExists(_))
+    ));
+}
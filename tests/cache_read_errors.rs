@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheReadError, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::{Query, SelectionError};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    DocumentId::from_path(root, &root.join(s)).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    Document::ingest(make_id(id_str), id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+/// A built cache and the paths of its `a.md` and `b.md` files.
+fn build() -> (TempDir, ContextCache, PathBuf, PathBuf) {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha"), make_doc("b.md", "beta")], &path)
+        .unwrap();
+    let a = path.join(&cache.manifest.documents[0].file);
+    let b = path.join(&cache.manifest.documents[1].file);
+    (dir, cache, a, b)
+}
+
+#[test]
+fn missing_files_name_the_document_and_path() {
+    let (_dir, cache, a, _) = build();
+    fs::remove_file(&a).unwrap();
+
+    match cache.load_documents() {
+        Err(CacheReadError::MissingFile { id, path }) => {
+            assert_eq!(id, make_id("a.md"));
+            assert_eq!(path, a);
+        }
+        other => panic!("expected a missing file, got {other:?}"),
+    }
+
+    let err: std::io::Error = cache.load_documents().unwrap_err().into();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn mismatches_are_typed() {
+    let (_dir, cache, a, b) = build();
+
+    let original = fs::read_to_string(&a).unwrap();
+    fs::write(&a, original.replace("alpha", "omega")).unwrap();
+    match cache.load_documents() {
+        Err(CacheReadError::VersionMismatch { id, expected, actual }) => {
+            assert_eq!(id, make_id("a.md"));
+            assert_eq!(expected, cache.manifest.documents[0].version);
+            assert_ne!(actual, expected);
+        }
+        other => panic!("expected a version mismatch, got {other:?}"),
+    }
+
+    fs::copy(&b, &a).unwrap();
+    match cache.load_documents() {
+        Err(CacheReadError::IdMismatch { expected, actual }) => {
+            assert_eq!(expected, make_id("a.md"));
+            assert_eq!(actual, make_id("b.md"));
+        }
+        other => panic!("expected an ID mismatch, got {other:?}"),
+    }
+
+    fs::write(&a, b"not a document").unwrap();
+    let err = cache.load_documents().unwrap_err();
+    assert!(matches!(&err, CacheReadError::Corrupt { id, .. } if *id == make_id("a.md")));
+    assert!(err.to_string().starts_with("Corrupt file for a.md: "));
+}
+
+#[test]
+fn selection_passes_read_errors_through() {
+    let (_dir, cache, _, b) = build();
+    fs::remove_file(&b).unwrap();
+
+    let err = ContextSelector::default().select(&cache, Query::new("alpha"), 100).unwrap_err();
+    match err {
+        SelectionError::CacheError(CacheReadError::MissingFile { id, .. }) => assert_eq!(id, make_id("b.md")),
+        other => panic!("expected a cache error, got {other:?}"),
+    }
+}
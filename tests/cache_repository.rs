@@ -0,0 +1,208 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheRepository, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn builder() -> CacheBuilder {
+    CacheBuilder::new(CacheBuildConfig::v0())
+}
+
+#[test]
+fn publish_and_open_current() {
+    let dir = tempdir().unwrap();
+    let repo = CacheRepository::open(dir.path().join("repo")).unwrap();
+    assert_eq!(repo.current_version().unwrap(), None);
+    assert!(repo.open_current().is_err());
+
+    let v1 = repo.build(builder(), vec![make_doc("a.md", "one")]).unwrap();
+    // Building does not publish
+    assert_eq!(repo.current_version().unwrap(), None);
+    assert!(v1.root.ends_with(v1.manifest.cache_version.replace(':', "-")));
+
+    repo.publish(&v1.manifest.cache_version).unwrap();
+    assert_eq!(repo.open_current().unwrap().manifest.cache_version, v1.manifest.cache_version);
+
+    let v2 = repo.build(builder(), vec![make_doc("a.md", "two")]).unwrap();
+    repo.publish(&v2.manifest.cache_version).unwrap();
+    let current = repo.open_current().unwrap();
+    assert_eq!(current.manifest.cache_version, v2.manifest.cache_version);
+    assert_eq!(current.load_documents().unwrap()[0].content, "two");
+
+    let mut expected = vec![v1.manifest.cache_version, v2.manifest.cache_version];
+    expected.sort();
+    assert_eq!(repo.generations().unwrap(), expected);
+}
+
+#[test]
+fn identical_inputs_reuse_generation() {
+    let dir = tempdir().unwrap();
+    let repo = CacheRepository::open(dir.path()).unwrap();
+    let a = repo.build(builder(), vec![make_doc("a.md", "same")]).unwrap();
+    let b = repo.build(builder(), vec![make_doc("a.md", "same")]).unwrap();
+    assert_eq!(a.root, b.root);
+    assert_eq!(repo.generations().unwrap().len(), 1);
+}
+
+#[test]
+fn concurrent_builds_stage_separately() {
+    let dir = tempdir().unwrap();
+    let repo = CacheRepository::open(dir.path()).unwrap();
+    // Another build's staging dir is left alone
+    let other = dir.path().join("generations").join(".staging");
+    std::fs::create_dir_all(&other).unwrap();
+
+    let built: Vec<String> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let repo = &repo;
+                scope.spawn(move || {
+                    let content = if i % 2 == 0 { "even" } else { "odd" };
+                    repo.build(builder(), vec![make_doc("a.md", content)]).unwrap().manifest.cache_version
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut expected = built.clone();
+    expected.sort();
+    expected.dedup();
+    assert_eq!(repo.generations().unwrap(), expected);
+    assert_eq!(expected.len(), 2);
+    assert!(other.exists());
+    // Nothing but the two generations and the other build's dir is left
+    let names: Vec<_> = std::fs::read_dir(dir.path().join("generations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names.len(), 3, "{names:?}");
+}
+
+#[test]
+fn publish_unknown_version_fails() {
+    let dir = tempdir().unwrap();
+    let repo = CacheRepository::open(dir.path()).unwrap();
+    assert!(repo.publish("sha256:missing").is_err());
+    assert_eq!(repo.current_version().unwrap(), None);
+}
+
+#[test]
+fn prune_keeps_current_and_recent_history() {
+    let store = Arc::new(MemoryStore::new());
+    let repo = CacheRepository::open_in(store, "/repo").unwrap();
+
+    let mut versions = Vec::new();
+    for content in ["one", "two", "three", "four"] {
+        let cache = repo.build(builder(), vec![make_doc("a.md", content)]).unwrap();
+        repo.publish(&cache.manifest.cache_version).unwrap();
+        versions.push(cache.manifest.cache_version.clone());
+    }
+    // Built but never published
+    let unpublished = repo.build(builder(), vec![make_doc("a.md", "five")]).unwrap();
+
+    let removed = repo.prune(1).unwrap();
+    let mut expected = vec![
+        versions[0].clone(),
+        versions[1].clone(),
+        unpublished.manifest.cache_version.clone(),
+    ];
+    expected.sort();
+    assert_eq!(removed, expected);
+
+    let mut kept = vec![versions[2].clone(), versions[3].clone()];
+    kept.sort();
+    assert_eq!(repo.generations().unwrap(), kept);
+    assert_eq!(repo.current_version().unwrap().as_deref(), Some(versions[3].as_str()));
+    repo.open_current().unwrap().load_documents().unwrap();
+
+    // Pruning again is a no-op
+    assert!(repo.prune(1).unwrap().is_empty());
+}
+
+#[test]
+fn republishing_an_older_generation_rolls_back() {
+    let dir = tempdir().unwrap();
+    let repo = CacheRepository::open(dir.path()).unwrap();
+    let v1 = repo.build(builder(), vec![make_doc("a.md", "one")]).unwrap();
+    let v2 = repo.build(builder(), vec![make_doc("a.md", "two")]).unwrap();
+    repo.publish(&v1.manifest.cache_version).unwrap();
+    repo.publish(&v2.manifest.cache_version).unwrap();
+    repo.publish(&v1.manifest.cache_version).unwrap();
+
+    assert_eq!(repo.open_current().unwrap().manifest.cache_version, v1.manifest.cache_version);
+    // v1 is current; v2 is the most recent before it
+    assert_eq!(repo.prune(0).unwrap(), vec![v2.manifest.cache_version]);
+}
+
+#[test]
+fn shared_objects_store_each_document_once() {
+    let dir = tempdir().unwrap();
+    let repo = CacheRepository::open(dir.path()).unwrap().with_shared_objects();
+    let shared = make_doc("shared.md", "unchanged between builds");
+    let v1 = repo.build(builder(), vec![shared.clone(), make_doc("a.md", "one")]).unwrap();
+    let v2 = repo.build(builder(), vec![shared, make_doc("a.md", "two")]).unwrap();
+    repo.publish(&v1.manifest.cache_version).unwrap();
+    repo.publish(&v2.manifest.cache_version).unwrap();
+
+    let objects = |repo_root: &Path| -> usize {
+        std::fs::read_dir(repo_root.join("objects"))
+            .unwrap()
+            .map(|shard| std::fs::read_dir(shard.unwrap().path()).unwrap().count())
+            .sum()
+    };
+    // shared.md once, a.md twice
+    assert_eq!(objects(dir.path()), 3);
+    v1.verify().unwrap();
+    v2.verify().unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let entry = v2.manifest.documents.iter().find(|e| e.id.as_str() == "shared.md").unwrap();
+        // The object and a link from each generation
+        assert_eq!(std::fs::metadata(v2.root.join(&entry.file)).unwrap().nlink(), 3);
+    }
+
+    // Nothing is garbage while both generations exist
+    assert!(repo.collect_garbage().unwrap().is_empty());
+    assert_eq!(repo.prune(0).unwrap(), vec![v1.manifest.cache_version.clone()]);
+    let removed = repo.collect_garbage().unwrap();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(objects(dir.path()), 2);
+    assert!(!dir.path().join("refs").join(v1.manifest.cache_version.replace(':', "-")).exists());
+    repo.open_current().unwrap().verify().unwrap();
+}
+
+#[test]
+fn shared_objects_in_memory() {
+    let store = Arc::new(MemoryStore::new());
+    let repo = CacheRepository::open_in(store, "/repo").unwrap().with_shared_objects();
+    let v1 = repo.build(builder(), vec![make_doc("a.md", "one")]).unwrap();
+    // Rebuilding identical inputs reuses the generation and its objects
+    let again = repo.build(builder(), vec![make_doc("a.md", "one")]).unwrap();
+    assert_eq!(again.root, v1.root);
+    assert_eq!(again.load_documents().unwrap()[0].content, "one");
+
+    repo.prune(0).unwrap();
+    assert_eq!(repo.collect_garbage().unwrap().len(), 1);
+    assert!(repo.generations().unwrap().is_empty());
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, MemoryStore, SigningKey};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "alpha"), make_doc("b.md", "beta")]
+}
+
+fn key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32])
+}
+
+#[test]
+fn signed_cache_opens_with_matching_key() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_signing_key(key(1))
+        .build(docs(), &path)
+        .unwrap();
+
+    assert!(path.join("manifest.sig").exists());
+    let cache = ContextCache::open_verified(&path, &key(1).verifying_key()).unwrap();
+    assert_eq!(cache.load_documents().unwrap().len(), 2);
+}
+
+#[test]
+fn signature_is_deterministic() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0()).with_signing_key(key(1));
+    builder.build(docs(), &dir.path().join("a")).unwrap();
+    builder.build(docs(), &dir.path().join("b")).unwrap();
+    assert_eq!(
+        fs::read(dir.path().join("a/manifest.sig")).unwrap(),
+        fs::read(dir.path().join("b/manifest.sig")).unwrap()
+    );
+}
+
+#[test]
+fn wrong_key_is_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_signing_key(key(1))
+        .build(docs(), &path)
+        .unwrap();
+
+    let err = ContextCache::open_verified(&path, &key(2).verifying_key()).unwrap_err();
+    assert!(err.to_string().contains("signature verification failed"));
+}
+
+#[test]
+fn unsigned_cache_is_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &path).unwrap();
+
+    assert!(!path.join("manifest.sig").exists());
+    assert!(ContextCache::open_verified(&path, &key(1).verifying_key()).is_err());
+    // Plain open is unaffected
+    ContextCache::open(&path).unwrap();
+}
+
+#[test]
+fn tampered_document_file_is_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_signing_key(key(1))
+        .build(docs(), &path)
+        .unwrap();
+
+    let file = path.join(&cache.manifest.documents[1].file);
+    let doc = fs::read_to_string(&file).unwrap();
+    fs::write(&file, doc.replace("\"source\":\"b.md\"", "\"source\":\"x.md\"")).unwrap();
+
+    assert!(ContextCache::open_verified(&path, &key(1).verifying_key()).is_err());
+}
+
+#[test]
+fn signing_works_with_memory_store() {
+    let store = Arc::new(MemoryStore::new());
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .with_signing_key(key(3))
+        .build(docs(), Path::new("/caches/signed"))
+        .unwrap();
+
+    ContextCache::open_verified_in(store, "/caches/signed", &key(3).verifying_key()).unwrap();
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, PathFilter, SelectionOptions};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build(path: &Path) -> ContextCache {
+    let docs = vec![
+        make_doc("docs/deploy.md", "deploy the service with deploy scripts"),
+        make_doc("docs/intro.md", "introduction to the service"),
+        make_doc("src/deploy.rs", "fn deploy() {}"),
+    ];
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs, path).unwrap()
+}
+
+#[test]
+fn snapshot_selection_matches_cache_selection() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let snapshot = cache.load_snapshot().unwrap();
+    assert_eq!(snapshot.len(), 3);
+    assert_eq!(snapshot.manifest().cache_version, cache.manifest.cache_version);
+
+    let selector = ContextSelector::default();
+    let from_cache = selector.select(&cache, Query::new("deploy"), 40).unwrap();
+    let from_snapshot = selector.select_snapshot(&snapshot, Query::new("deploy"), 40).unwrap();
+    assert_eq!(
+        serde_json::to_string(&from_cache).unwrap(),
+        serde_json::to_string(&from_snapshot).unwrap()
+    );
+}
+
+#[test]
+fn snapshot_selection_with_options_matches() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let snapshot = cache.load_snapshot().unwrap();
+
+    let options = SelectionOptions {
+        path_filter: Some(PathFilter::default().include("docs/**")),
+        ..Default::default()
+    };
+    let selector = ContextSelector::default();
+    let from_cache = selector.select_with(&cache, Query::new("deploy"), 100, &options).unwrap();
+    let from_snapshot = selector.select_snapshot_with(&snapshot, Query::new("deploy"), 100, &options).unwrap();
+    assert_eq!(
+        serde_json::to_string(&from_cache).unwrap(),
+        serde_json::to_string(&from_snapshot).unwrap()
+    );
+    assert_eq!(from_snapshot.selection.documents_excluded_by_filter, Some(1));
+}
+
+#[test]
+fn snapshot_does_not_touch_disk_after_loading() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = build(&path);
+    let snapshot = cache.load_snapshot().unwrap();
+
+    fs::remove_dir_all(&path).unwrap();
+    assert!(ContextSelector::default().select(&cache, Query::new("deploy"), 100).is_err());
+
+    let result = ContextSelector::default().select_snapshot(&snapshot, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.documents.len(), 3);
+}
+
+#[test]
+fn load_snapshot_verifies_documents() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = build(&path);
+
+    let file = path.join(&cache.manifest.documents[0].file);
+    let doc = fs::read_to_string(&file).unwrap();
+    fs::write(&file, doc.replace("deploy the service", "drop the database")).unwrap();
+
+    assert!(cache.load_snapshot().is_err());
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, PrefixStats, StatsOptions};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::BytesPerTokenCounter;
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str, keys: &[&str]) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    let mut metadata = Metadata::new();
+    for key in keys {
+        metadata.insert_string(*key, "x");
+    }
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("readme.md", "hello", &["title"]),
+        make_doc("docs/guide.md", "a longer guide text", &["title", "owner"]),
+        make_doc("docs/api/index.md", "api docs", &[]),
+        make_doc("src/lib.rs", "fn main() {}", &["lang"]),
+    ]
+}
+
+#[test]
+fn stats_report_totals_and_aggregates() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+    let stats = cache.stats().unwrap();
+
+    assert_eq!(stats.cache_version, cache.manifest.cache_version);
+    assert_eq!(stats.documents, 4);
+    assert_eq!(stats.total_bytes, 5 + 19 + 8 + 12);
+    // ceil(bytes / 4) per document
+    assert_eq!(stats.total_tokens, 2 + 5 + 2 + 3);
+    assert_eq!(stats.token_counter, "approx(4)");
+
+    let largest: Vec<(&str, usize)> = stats.largest.iter().map(|d| (d.id.as_str(), d.bytes)).collect();
+    assert_eq!(
+        largest,
+        vec![("docs/guide.md", 19), ("src/lib.rs", 12), ("docs/api/index.md", 8), ("readme.md", 5)]
+    );
+
+    let keys: Vec<(&str, usize)> = stats.metadata_keys.iter().map(|(k, n)| (k.as_str(), *n)).collect();
+    assert_eq!(keys, vec![("lang", 1), ("owner", 1), ("title", 2)]);
+
+    assert_eq!(stats.prefixes.keys().collect::<Vec<_>>(), vec!["", "docs/", "src/"]);
+    assert_eq!(
+        stats.prefixes["docs/"],
+        PrefixStats {
+            documents: 2,
+            bytes: 27,
+            tokens: 7
+        }
+    );
+
+    // Serializable, and the same for an in-memory build
+    let json = serde_json::to_value(&stats).unwrap();
+    assert_eq!(json["prefixes"]["src/"]["bytes"], 12);
+    let memory = CacheBuilder::new(CacheBuildConfig::v0()).build_in_memory(docs()).unwrap();
+    assert_eq!(memory.stats(), stats);
+}
+
+#[test]
+fn stats_options_and_counter() {
+    let memory = CacheBuilder::new(CacheBuildConfig::v0()).build_in_memory(docs()).unwrap();
+    let options = StatsOptions {
+        largest: 1,
+        prefix_depth: 2,
+    };
+    let stats = memory.stats_with(&BytesPerTokenCounter::new(1, 1), &options);
+    assert_eq!(stats.largest.len(), 1);
+    assert_eq!(stats.largest[0].tokens, 19);
+    assert_eq!(stats.total_tokens, stats.total_bytes as u64);
+    assert_eq!(
+        stats.prefixes.keys().collect::<Vec<_>>(),
+        vec!["", "docs/", "docs/api/", "src/"]
+    );
+}
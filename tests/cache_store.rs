@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheStore, ContextCache, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "alpha deploy"), make_doc("b.md", "beta")]
+}
+
+#[test]
+fn memory_store_build_matches_filesystem_build() {
+    let dir = tempdir().unwrap();
+    let fs_path = dir.path().join("cache");
+    let fs_cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &fs_path)
+        .unwrap();
+
+    let store = Arc::new(MemoryStore::new());
+    let mem_path = Path::new("caches/cache");
+    let mem_cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .build(docs(), mem_path)
+        .unwrap();
+
+    assert!(!mem_path.exists(), "memory build must not touch the filesystem");
+    assert_eq!(fs_cache.manifest.cache_version, mem_cache.manifest.cache_version);
+
+    // Same artifacts, same bytes (manifest differs only by created_at)
+    assert_eq!(
+        store.list(mem_path).unwrap(),
+        vec!["documents", "index.json", "manifest.json"]
+    );
+    assert_eq!(
+        store.read_file(&mem_path.join("index.json")).unwrap(),
+        fs::read(fs_path.join("index.json")).unwrap()
+    );
+    for entry in &fs_cache.manifest.documents {
+        assert_eq!(
+            store.read_file(&mem_path.join(&entry.file)).unwrap(),
+            fs::read(fs_path.join(&entry.file)).unwrap()
+        );
+    }
+
+    let selector = ContextSelector::default();
+    let from_fs = selector.select(&fs_cache, Query::new("deploy"), 100).unwrap();
+    let from_mem = selector.select(&mem_cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(
+        serde_json::to_string(&from_fs).unwrap(),
+        serde_json::to_string(&from_mem).unwrap()
+    );
+}
+
+#[test]
+fn open_reads_manifest_from_store() {
+    let store = Arc::new(MemoryStore::new());
+    let built = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .build(docs(), Path::new("cache"))
+        .unwrap();
+
+    let opened = ContextCache::open_in(store.clone(), "cache").unwrap();
+    assert_eq!(opened.manifest.cache_version, built.manifest.cache_version);
+    assert_eq!(opened.load_documents().unwrap().len(), 2);
+
+    // Builds never overwrite an existing output
+    let again = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store)
+        .build(docs(), Path::new("cache"));
+    assert!(again.is_err());
+}
+
+#[test]
+fn open_reads_filesystem_cache() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let built = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &path)
+        .unwrap();
+
+    let opened = ContextCache::open(&path).unwrap();
+    assert_eq!(opened.manifest.cache_version, built.manifest.cache_version);
+    assert!(ContextCache::open(dir.path().join("missing")).is_err());
+}
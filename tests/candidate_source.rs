@@ -0,0 +1,125 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, MemoryCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{CandidateSource, ContextSelector, SelectionOptions};
+use context_core::types::Query;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    DocumentId::from_path(root, &root.join(s)).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    Document::ingest(make_id(id_str), id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn cache() -> MemoryCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build_in_memory(vec![
+            make_doc("deploy.md", "deploy deploy rollout"),
+            make_doc("rollback.md", "rollback steps"),
+            make_doc("both.md", "deploy rollback plan"),
+            make_doc("none.md", "unrelated text"),
+        ])
+        .unwrap()
+}
+
+/// A term -> ids index; queries with an unindexed term fall back.
+struct TermIndex(BTreeMap<&'static str, Vec<&'static str>>);
+
+impl TermIndex {
+    fn new() -> Self {
+        Self(BTreeMap::from([
+            ("deploy", vec!["deploy.md", "both.md"]),
+            ("rollback", vec!["rollback.md", "both.md"]),
+        ]))
+    }
+}
+
+impl CandidateSource for TermIndex {
+    fn narrow(&self, query: &Query, _cache_version: Option<&str>) -> Option<BTreeSet<DocumentId>> {
+        let mut ids = BTreeSet::new();
+        for term in &query.terms {
+            ids.extend(self.0.get(term.as_str())?.iter().map(|id| make_id(id)));
+        }
+        Some(ids)
+    }
+
+    fn fingerprint(&self) -> String {
+        "term-index".to_string()
+    }
+}
+
+fn ids(documents: &[context_core::types::SelectedDocument]) -> Vec<&str> {
+    documents.iter().map(|doc| doc.id.as_str()).collect()
+}
+
+#[test]
+fn scores_only_candidates() {
+    let cache = cache();
+    let selector = ContextSelector::default().with_candidate_source(TermIndex::new());
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(ids(&result.documents), vec!["deploy.md", "both.md"]);
+    assert_eq!(result.selection.candidate_count, Some(2));
+    assert!(!result.selection.candidate_fallback);
+    assert_eq!(result.selection.documents_considered, 4);
+
+    // The candidates rank as they do in a full scan
+    let full = ContextSelector::default().select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(ids(&full.documents)[..2], ids(&result.documents)[..]);
+    assert_eq!(full.selection.candidate_count, None);
+}
+
+#[test]
+fn falls_back_to_a_full_scan() {
+    let cache = cache();
+    let selector = ContextSelector::default().with_candidate_source(TermIndex::new());
+    let narrowed = selector.select(&cache, Query::new("unrelated"), 1000).unwrap();
+    let full = ContextSelector::default().select(&cache, Query::new("unrelated"), 1000).unwrap();
+    assert_eq!(ids(&narrowed.documents), ids(&full.documents));
+    assert_eq!(narrowed.selection.candidate_count, Some(4));
+    assert!(narrowed.selection.candidate_fallback);
+
+    let json = serde_json::to_value(&full.selection).unwrap();
+    assert!(json.get("candidate_count").is_none());
+    assert!(json.get("candidate_fallback").is_none());
+}
+
+#[test]
+fn pinned_documents_are_always_scored() {
+    let cache = cache();
+    let selector = ContextSelector::default().with_candidate_source(TermIndex::new());
+    let options = SelectionOptions {
+        pinned: vec!["none.md".to_string()],
+        ..Default::default()
+    };
+    let result = selector.select_with(&cache, Query::new("rollback"), 1000, &options).unwrap();
+    assert_eq!(ids(&result.documents), vec!["none.md", "rollback.md", "both.md"]);
+    assert_eq!(result.selection.candidate_count, Some(3));
+}
+
+#[test]
+fn multi_query_selections_narrow_each_query() {
+    let cache = cache();
+    let selector = ContextSelector::default().with_candidate_source(TermIndex::new());
+    let result = selector.select_multi(&cache, &[Query::new("deploy"), Query::new("rollback")], 1000).unwrap();
+    let mut selected = ids(&result.documents);
+    selected.sort();
+    assert_eq!(selected, vec!["both.md", "deploy.md", "rollback.md"]);
+    assert_eq!(result.selection.candidate_count, Some(3));
+    assert!(!result.selection.candidate_fallback);
+
+    let result = selector.select_multi(&cache, &[Query::new("deploy"), Query::new("text")], 1000).unwrap();
+    assert_eq!(result.selection.candidate_count, Some(4));
+    assert!(result.selection.candidate_fallback);
+}
+
+#[test]
+fn the_source_is_part_of_the_fingerprint() {
+    let plain = ContextSelector::default();
+    let narrowed = ContextSelector::default().with_candidate_source(TermIndex::new());
+    assert_ne!(plain.fingerprint(), narrowed.fingerprint());
+    assert!(narrowed.fingerprint().ends_with(";candidates=term-index"));
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use context_core::compat::{SELECTION_RESULT_SCHEMA_VERSION, CACHE_FORMAT_VERSION, CACHE_MANIFEST_SCHEMA_VERSION};
+use context_core::cache::{CacheBuildConfig, CacheManifest, ManifestDocumentEntry};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::types::{
+    canonical_json, DocumentVersion, SelectedDocument, SelectionMetadata, SelectionResult, SelectionWhy,
+};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::new();
+    metadata.insert_string("title", "Déploiement \"prod\"");
+    metadata.insert_number("priority", 2);
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+#[test]
+fn selection_result_golden() {
+    let result = SelectionResult {
+        schema_version: SELECTION_RESULT_SCHEMA_VERSION,
+        documents: vec![SelectedDocument {
+            id: "docs/a.md".to_string(),
+            version: "sha256:mock".to_string(),
+            content: "line\n".to_string(),
+            span: None,
+            metadata: None,
+            score: 0.1,
+            tokens: 2,
+            original_tokens: None,
+            truncation: None,
+            representation: None,
+            why: SelectionWhy {
+                query_terms: vec!["deploy".to_string()],
+                term_matches: 1,
+                total_words: 1,
+                components: BTreeMap::from([("vector".to_string(), 1.0), ("lexical".to_string(), 0.583333)]),
+                ..Default::default()
+            },
+        }],
+        selection: SelectionMetadata {
+            query: "deploy".to_string(),
+            budget: 10,
+            tokens_used: 2,
+            documents_considered: 1,
+            documents_selected: 1,
+            ..Default::default()
+        },
+    };
+
+    let expected = concat!(
+        r#"{"documents":[{"content":"line\n","id":"docs/a.md","score":0.1,"tokens":2,"#,
+        r#""version":"sha256:mock","why":{"components":{"lexical":0.583333,"vector":1.0},"#,
+        r#""query_terms":["deploy"],"term_matches":1,"total_words":1}}],"#,
+        r#""schema_version":1,"selection":{"budget":10,"documents_considered":1,"documents_excluded_by_budget":0,"#,
+        r#""documents_selected":1,"query":"deploy","tokens_used":2}}"#
+    );
+    assert_eq!(canonical_json(&result).unwrap(), expected);
+}
+
+#[test]
+fn document_golden() {
+    let doc = make_doc("docs/a.md", "héllo\tworld");
+    let expected = format!(
+        r#"{{"content":"héllo\tworld","id":"docs/a.md","metadata":{{"priority":2,"title":"Déploiement \"prod\""}},"schema_version":1,"source":"docs/a.md","version":"{}"}}"#,
+        doc.version.as_str()
+    );
+    assert_eq!(canonical_json(&doc).unwrap(), expected);
+}
+
+#[test]
+fn manifest_golden() {
+    let manifest = CacheManifest {
+        schema_version: CACHE_MANIFEST_SCHEMA_VERSION,
+        format_version: CACHE_FORMAT_VERSION,
+        crate_version: None,
+        cache_version: "sha256:cache".to_string(),
+        build_config: CacheBuildConfig::v0(),
+        created_at: Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+        document_count: 1,
+        documents: vec![ManifestDocumentEntry {
+            id: make_id("a.md"),
+            version: DocumentVersion::from_content(b"a"),
+            file: "documents/a.json".to_string(),
+        }],
+        index_checksum: None,
+        tree_hash: None,
+    };
+    let json = canonical_json(&manifest).unwrap();
+    assert!(json.starts_with(
+        r#"{"build_config":{"hash_algorithm":"sha256","version":"1"},"cache_version":"sha256:cache","created_at":"2024-01-02T03:04:05Z","document_count":1,"documents":[{"file":"documents/a.json","id":"a.md","version":"#
+    ));
+    assert!(!json.contains(' '));
+}
+
+#[test]
+fn canonical_json_is_stable() {
+    let doc = make_doc("b.md", "content");
+    let once = canonical_json(&doc).unwrap();
+    let reparsed: Document = serde_json::from_str(&once).unwrap();
+    assert_eq!(canonical_json(&reparsed).unwrap(), once);
+    assert_eq!(canonical_json(&[1.5f64, f64::NAN]).unwrap(), "[1.5,null]");
+    assert_eq!(canonical_json(&BTreeMap::<String, u8>::new()).unwrap(), "{}");
+}
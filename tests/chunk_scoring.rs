@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Chunker, Document, DocumentId, LineChunker, Metadata};
+use context_core::selection::{ChunkScoring, ContextSelector};
+use context_core::types::{AggregationStrategy, Query, Span};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    // long.md has one relevant section among many unrelated lines
+    let mut long = String::new();
+    for i in 0..8 {
+        long.push_str(&format!("unrelated filler line number {}\n", i));
+    }
+    long.push_str("deploy deploy\n");
+    let docs = vec![
+        make_doc("long.md", &long),
+        make_doc("short.md", "how to deploy the service safely today\n"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn line_chunker_spans() {
+    let chunks = LineChunker::new(2).chunks("a\nb\nc\n");
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(
+        chunks[0].span,
+        Span {
+            start_line: 1,
+            end_line: 2,
+            byte_range: [0, 4]
+        }
+    );
+    assert_eq!(chunks[1].span.start_line, 3);
+    assert_eq!(chunks[1].text("a\nb\nc\n"), "c\n");
+
+    assert_eq!(LineChunker::new(2).chunks("a\nb\nc").last().unwrap().span.byte_range, [4, 5]);
+    assert!(LineChunker::new(2).chunks("").is_empty());
+    assert_eq!(LineChunker::new(0).name(), "lines(1)");
+}
+
+#[test]
+fn max_aggregation_ranks_relevant_section() {
+    let (_dir, cache) = build_cache();
+
+    // Whole-document scoring buries long.md
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(plain.documents[0].id, "short.md");
+
+    let selector = ContextSelector::default()
+        .with_chunk_scoring(ChunkScoring::new(LineChunker::new(1), AggregationStrategy::Max));
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(result.documents[0].id, "long.md");
+    assert_eq!(result.documents[0].score, 1.0);
+
+    let chunks = &result.documents[0].why.chunks;
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].index, 8);
+    assert_eq!(chunks[0].span.start_line, 9);
+    assert_eq!(result.documents[0].why.term_matches, 2);
+    assert_eq!(result.documents[0].why.total_words, 42);
+
+    let chunking = result.selection.chunking.as_ref().unwrap();
+    assert_eq!(chunking.chunker, "lines(1)");
+    assert_eq!(chunking.chunks_scored, 10);
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["documents"][0]["why"]["chunks"][0]["byte_range"][1], json["documents"][0]["content"].as_str().unwrap().len());
+    assert!(!serde_json::to_string(&plain).unwrap().contains("chunk"));
+}
+
+#[test]
+fn sum_and_top_n_mean_aggregation() {
+    let (_dir, cache) = build_cache();
+    let select = |aggregation| {
+        ContextSelector::default()
+            .with_chunk_scoring(ChunkScoring::new(LineChunker::new(1), aggregation))
+            .select(&cache, Query::new("deploy filler"), 1000)
+            .unwrap()
+    };
+
+    // long.md chunks: 8 x 0.2 (filler) + 1.0 (deploy deploy)
+    let sum = select(AggregationStrategy::Sum);
+    assert_eq!(sum.documents[0].id, "long.md");
+    assert_eq!(sum.documents[0].score, 1.0);
+    assert_eq!(sum.documents[0].why.chunks.len(), 9);
+
+    let top2 = select(AggregationStrategy::TopNMean { n: 2 });
+    assert_eq!(top2.documents[0].score, 0.6);
+    assert_eq!(top2.documents[0].why.chunks.len(), 2);
+    assert_eq!(top2.documents[0].why.chunks[1].index, 0);
+}
@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::metadata::MetadataValue;
+use context_core::document::{code_symbols, is_code_path, Chunker, CodeChunker, Document, DocumentId, Metadata};
+use context_core::selection::{ChunkScoring, ContextSelector};
+use context_core::types::{AggregationStrategy, Query};
+use tempfile::tempdir;
+
+const RUST: &str = "\
+use std::fmt;
+
+/// A cache.
+#[derive(Debug)]
+pub struct Cache {
+    size: usize,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Cache { size: 0 }
+    }
+
+    fn evict(&mut self) {
+        self.size = 0;
+    }
+}
+
+pub(crate) async fn load<T>(path: &str) -> T
+where
+    T: Default,
+{
+    T::default()
+}
+";
+
+const PYTHON: &str = "\
+import os
+
+@dataclass
+class Loader:
+    def load(self):
+        return os.getcwd()
+
+    def save(self):
+        pass
+
+def main():
+    Loader().load()
+";
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::default();
+    let symbols = code_symbols(content).into_iter().map(MetadataValue::String).collect();
+    metadata.insert("symbols", MetadataValue::List(symbols));
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn summary(chunker: &CodeChunker, content: &str) -> Vec<(Option<String>, usize, usize)> {
+    chunker
+        .chunks(content)
+        .into_iter()
+        .map(|chunk| (chunk.symbol, chunk.span.start_line, chunk.span.end_line))
+        .collect()
+}
+
+#[test]
+fn definitions_are_whole_chunks() {
+    let chunks = summary(&CodeChunker::new(40), RUST);
+    assert_eq!(
+        chunks,
+        vec![
+            (None, 1, 2),
+            // Doc comments and attributes belong to the definition
+            (Some("Cache".to_string()), 3, 8),
+            (Some("Cache".to_string()), 9, 18),
+            // A `where` clause and `{` on their own lines continue it
+            (Some("load".to_string()), 19, 24),
+        ]
+    );
+}
+
+#[test]
+fn chunks_cover_the_content_in_order() {
+    let chunks = CodeChunker::new(3).chunks(RUST);
+    let text: String = chunks.iter().map(|chunk| chunk.text(RUST)).collect();
+    assert_eq!(text, RUST);
+    for (i, chunk) in chunks.iter().enumerate() {
+        assert_eq!(chunk.index, i);
+    }
+}
+
+#[test]
+fn oversized_definitions_split_at_nested_ones() {
+    let chunks = summary(&CodeChunker::new(4), RUST);
+    let symbols: Vec<&str> = chunks.iter().filter_map(|(symbol, _, _)| symbol.as_deref()).collect();
+    assert!(symbols.contains(&"Cache.new"));
+    assert!(symbols.contains(&"Cache.evict"));
+    assert!(chunks.iter().all(|(_, start, end)| end - start < 4));
+}
+
+#[test]
+fn indentation_blocks_chunk_python() {
+    let chunks = summary(&CodeChunker::new(40), PYTHON);
+    assert_eq!(
+        chunks,
+        vec![
+            (None, 1, 2),
+            // Decorators belong to the definition
+            (Some("Loader".to_string()), 3, 10),
+            (Some("main".to_string()), 11, 12),
+        ]
+    );
+    assert_eq!(code_symbols(PYTHON), vec!["Loader", "Loader.load", "Loader.save", "main"]);
+}
+
+#[test]
+fn other_languages() {
+    let go = "func (s *Server) Start() error {\n\treturn nil\n}\n";
+    assert_eq!(code_symbols(go), vec!["Start"]);
+    let ts = "export default async function handler(req) {\n  return 1;\n}\n";
+    assert_eq!(code_symbols(ts), vec!["handler"]);
+    let rust = "impl<T: Clone> From<T> for Wrapper<T> {\n    fn from(t: T) -> Self { Wrapper(t) }\n}\n";
+    assert_eq!(code_symbols(rust), vec!["Wrapper", "Wrapper.from"]);
+}
+
+#[test]
+fn code_without_definitions_splits_by_lines() {
+    let chunks = summary(&CodeChunker::new(2), "a = 1\nb = 2\nc = 3\n");
+    assert_eq!(chunks, vec![(None, 1, 2), (None, 3, 3)]);
+    assert!(CodeChunker::new(2).chunks("").is_empty());
+    assert_eq!(CodeChunker::new(0).name(), "code(1)");
+}
+
+#[test]
+fn code_paths() {
+    assert!(is_code_path("src/lib.rs"));
+    assert!(is_code_path("app/Main.JAVA"));
+    assert!(!is_code_path("README.md"));
+    assert!(!is_code_path("Makefile"));
+}
+
+#[test]
+fn symbol_clauses_score_matching_chunks() {
+    let dir = tempdir().unwrap();
+    let docs = vec![make_doc("cache.rs", RUST), make_doc("loader.py", PYTHON)];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    let selector = ContextSelector::default()
+        .with_chunk_scoring(ChunkScoring::new(CodeChunker::new(4), AggregationStrategy::Max));
+
+    // Only documents defining the symbol are candidates
+    let result = selector.select(&cache, Query::new("symbol:evict size"), 1000).unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["cache.rs"]);
+
+    // And only the symbol's chunks score
+    let chunks = &result.documents[0].why.chunks;
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].symbol.as_deref(), Some("Cache.evict"));
+}
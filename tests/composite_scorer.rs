@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, HashingEmbedder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    ApproxTokenCounter, CompositeScorer, ContextSelector, EmbeddingScorer, FusionScorer, Scorer,
+    TermFrequencyScorer,
+};
+use context_core::types::{FusionConfig, FusionMode, Query, ScoreDetails};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+/// 1.0 for documents under `docs/`, else 0.0.
+struct DocsBoost;
+
+impl Scorer for DocsBoost {
+    fn score(&self, doc: &Document, _query: &Query) -> ScoreDetails {
+        ScoreDetails {
+            score: Some(if doc.id.as_str().starts_with("docs/") { 1.0 } else { 0.0 }),
+            ..Default::default()
+        }
+    }
+
+    fn score_value(&self, details: &ScoreDetails) -> f32 {
+        details.score.unwrap_or(0.0)
+    }
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(
+            vec![
+                make_doc("docs/guide.md", "deploy the service to production"),
+                make_doc("src/deploy.rs", "deploy deploy"),
+                make_doc("src/notes.md", "unrelated notes"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn weighted_components_are_combined_and_explained() {
+    let (_dir, cache) = build_cache();
+    let scorer = CompositeScorer::new()
+        .with("lexical", 0.7, TermFrequencyScorer)
+        .with("path", 0.3, DocsBoost);
+    let result = ContextSelector::new(scorer, ApproxTokenCounter)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    // src/deploy.rs: 0.7 * 1.0 + 0.3 * 0.0; docs/guide.md: 0.7 * 0.2 + 0.3 * 1.0
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["src/deploy.rs", "docs/guide.md", "src/notes.md"]);
+    assert_eq!(result.documents[0].score, 0.7);
+    assert_eq!(result.documents[1].score, 0.44);
+
+    let why = &result.documents[1].why;
+    assert_eq!(why.components["lexical"], 0.2);
+    assert_eq!(why.components["path"], 1.0);
+    assert_eq!(why.term_matches, 1);
+    assert_eq!(why.total_words, 5);
+
+    let json = serde_json::to_value(&result.documents[1].why).unwrap();
+    assert_eq!(json["components"]["path"], 1.0);
+}
+
+#[test]
+fn weights_are_normalized_and_names_replace() {
+    let doc = make_doc("docs/guide.md", "deploy guide");
+    let query = Query::new("deploy");
+
+    let scaled = CompositeScorer::new().with("lexical", 7.0, TermFrequencyScorer).with("path", 3.0, DocsBoost);
+    let unit = CompositeScorer::new().with("lexical", 0.7, TermFrequencyScorer).with("path", 0.3, DocsBoost);
+    assert_eq!(scaled.score_value(&scaled.score(&doc, &query)), unit.score_value(&unit.score(&doc, &query)));
+
+    let replaced = CompositeScorer::new()
+        .with("path", 1.0, TermFrequencyScorer)
+        .with("path", 1.0, DocsBoost);
+    let details = replaced.score(&doc, &query);
+    assert_eq!(details.components.len(), 1);
+    assert_eq!(replaced.score_value(&details), 1.0);
+
+    let empty = CompositeScorer::new();
+    assert_eq!(empty.score_value(&empty.score(&doc, &query)), 0.0);
+    assert_ne!(scaled.fingerprint(), unit.fingerprint());
+}
+
+#[test]
+fn rank_based_components_are_finalized() {
+    let (_dir, cache) = build_cache();
+    let vectors = cache.load_vectors().unwrap().unwrap();
+    let config = FusionConfig {
+        mode: FusionMode::ReciprocalRank { k: 60 },
+        lexical_weight: 1.0,
+        vector_weight: 1.0,
+    };
+    let fusion = FusionScorer::new(
+        TermFrequencyScorer,
+        EmbeddingScorer::new(HashingEmbedder::default(), vectors.clone()).unwrap(),
+        config,
+    );
+    let expected = ContextSelector::new(fusion, ApproxTokenCounter)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    let fusion = FusionScorer::new(
+        TermFrequencyScorer,
+        EmbeddingScorer::new(HashingEmbedder::default(), vectors).unwrap(),
+        config,
+    );
+    let composite = CompositeScorer::new().with("rrf", 1.0, fusion);
+    let result = ContextSelector::new(composite, ApproxTokenCounter)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    for (a, b) in expected.documents.iter().zip(&result.documents) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.score, b.score);
+        assert_eq!(b.why.components["rrf"], a.score);
+    }
+}
@@ -1,6 +1,6 @@
 use context_core::cache::{CacheBuilder, CacheBuildConfig};
 use context_core::selection::ContextSelector;
-use context_core::types::Query;
+use context_core::types::{Query, SelectionError};
 use context_core::document::{Document, DocumentId, Metadata};
 use tempfile::tempdir;
 use std::path::Path;
@@ -36,12 +36,9 @@ fn test_selection_budget_zero() {
     let selector = ContextSelector::default();
     let query = Query::new("hello");
 
-    let result = selector.select(&cache, query, 0).unwrap();
+    let err = selector.select(&cache, query, 0).unwrap_err();
 
-    assert_eq!(result.documents.len(), 0, "No documents should be selected with budget 0");
-    assert_eq!(result.selection.tokens_used, 0);
-    assert_eq!(result.selection.documents_considered, 1);
-    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+    assert!(matches!(err, SelectionError::InvalidBudget(0)), "A budget of 0 is rejected");
 }
 
 #[test]
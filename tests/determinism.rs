@@ -2,6 +2,7 @@ use std::fs;
 use std::path::Path;
 
 use chrono::{TimeZone, Utc};
+use context_core::compat::{SELECTION_RESULT_SCHEMA_VERSION, CACHE_FORMAT_VERSION, CACHE_MANIFEST_SCHEMA_VERSION};
 use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheManifest, ManifestDocumentEntry};
 use context_core::document::{Document, DocumentId, Metadata};
 use context_core::selection::ContextSelector;
@@ -33,14 +34,20 @@ fn golden_selection_output_serialization() {
         query_terms: vec!["deployment".to_string()],
         term_matches: 12,
         total_words: 156,
+        ..Default::default()
     };
 
     let doc = SelectedDocument {
         id: "docs/deployment.md".to_string(),
         version: "sha256:mock".to_string(),
         content: "Content...".to_string(),
+        span: None,
+        metadata: None,
         score: 0.92,
         tokens: 847,
+        original_tokens: None,
+        truncation: None,
+        representation: None,
         why,
     };
 
@@ -52,10 +59,12 @@ fn golden_selection_output_serialization() {
         documents_considered: 42,
         documents_selected: 3,
         documents_excluded_by_budget: 9,
+        ..Default::default()
     };
 
     // 3. Construct SelectionResult
     let result = SelectionResult {
+        schema_version: SELECTION_RESULT_SCHEMA_VERSION,
         documents: vec![doc],
         selection: meta,
     };
@@ -78,6 +87,7 @@ fn golden_selection_output_serialization() {
 
     // 6. JSON Snapshot Check
     const EXPECTED_JSON: &str = r#"{
+      "schema_version": 1,
       "documents": [
         {
           "id": "docs/deployment.md",
@@ -220,6 +230,7 @@ fn golden_end_to_end_selection_is_deterministic() {
     // 6. Snapshot assertion (freeze contract)
     // ------------------------------------------------------------
     let expected = r#"{
+  "schema_version": 1,
   "documents": [
     {
       "id": "docs/deployment.md",
@@ -270,7 +281,11 @@ fn golden_end_to_end_selection_is_deterministic() {
     "tokens_used": 23,
     "documents_considered": 3,
     "documents_selected": 3,
-    "documents_excluded_by_budget": 0
+    "documents_excluded_by_budget": 0,
+    "cache_version": "sha256:a5c3068b60c0706aab154adf5312bac293ecf8121d2cae8acb7ccc9ef3a320d6",
+    "document_count_in_cache": 3,
+    "scorer": "term_frequency",
+    "token_counter": "approx(4)"
   }
 }"#;
 
@@ -320,6 +335,7 @@ fn golden_manifest_serialization() {
     let config = CacheBuildConfig {
         version: "1".to_string(),
         hash_algorithm: "sha256".to_string(),
+        ..CacheBuildConfig::v0()
     };
 
     let id_str = "docs/deployment.md";
@@ -340,11 +356,16 @@ fn golden_manifest_serialization() {
     };
 
     let manifest = CacheManifest {
+        schema_version: CACHE_MANIFEST_SCHEMA_VERSION,
+        format_version: CACHE_FORMAT_VERSION,
+        crate_version: None,
         cache_version: "sha256:mock".to_string(),
         build_config: config,
         created_at: chrono::Utc::now(),
         document_count: 1,
         documents: vec![entry],
+        index_checksum: None,
+        tree_hash: None,
     };
 
     let json_str = serde_json::to_string(&manifest).unwrap();
@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use context_core::document::document::{PARTS_KEY, PART_KEY, PART_OF_KEY};
+use context_core::document::metadata::MetadataValue;
+use context_core::document::{Document, DocumentError, DocumentId, IngestOptions, Metadata, Source};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn limited(max_bytes: u64, split_oversize: bool) -> IngestOptions {
+    IngestOptions {
+        max_bytes: Some(max_bytes),
+        split_oversize,
+        ..IngestOptions::default()
+    }
+}
+
+fn contents(parts: &[Document]) -> Vec<&str> {
+    parts.iter().map(|p| p.content.as_str()).collect()
+}
+
+#[test]
+fn unlimited_by_default() {
+    let content = "x".repeat(100_000).into_bytes();
+    let options = IngestOptions::default();
+    let doc = Document::ingest_with(make_id("big.txt"), "big.txt", content, Metadata::default(), &options);
+    let doc = doc.unwrap();
+    assert_eq!(doc.content.len(), 100_000);
+}
+
+#[test]
+fn oversize_content_is_a_structured_error() {
+    let options = limited(10, false);
+    let ingest = |content: &[u8]| {
+        Document::ingest_with(make_id("a.txt"), "a.txt", content.to_vec(), Metadata::default(), &options)
+    };
+    let result = ingest(b"eleven byte");
+    let error = result.unwrap_err();
+    assert!(matches!(error, DocumentError::TooLarge { size: 11, limit: 10 }));
+    assert_eq!(error.to_string(), "Content is 11 bytes, over the 10 byte limit");
+
+    // At the limit is fine
+    assert!(ingest(b"ten bytes!").is_ok());
+}
+
+#[test]
+fn ingest_parts_without_split_fails_like_ingest_with() {
+    let result = Document::ingest_parts(
+        make_id("a.txt"),
+        "a.txt",
+        b"eleven byte".to_vec(),
+        Metadata::default(),
+        &limited(10, false),
+    );
+    assert!(matches!(result, Err(DocumentError::TooLarge { size: 11, limit: 10 })));
+}
+
+#[test]
+fn content_within_the_limit_is_one_document() {
+    let parts = Document::ingest_parts(
+        make_id("a.txt"),
+        "a.txt",
+        b"short".to_vec(),
+        Metadata::default(),
+        &limited(10, true),
+    )
+    .unwrap();
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].id.as_str(), "a.txt");
+    assert!(parts[0].metadata.get(PART_KEY).is_none());
+}
+
+#[test]
+fn oversize_content_splits_on_line_ends() {
+    let content = b"alpha\nbeta\ngamma\ndelta\n".to_vec();
+    let parts =
+        Document::ingest_parts(make_id("log.txt"), "log.txt", content, Metadata::default(), &limited(12, true))
+            .unwrap();
+    assert_eq!(contents(&parts), vec!["alpha\nbeta\n", "gamma\ndelta\n"]);
+}
+
+#[test]
+fn long_lines_are_cut_at_character_boundaries() {
+    // Each `é` is two bytes, so five bytes cannot end mid-character
+    let content = "éééééé".as_bytes().to_vec();
+    let parts =
+        Document::ingest_parts(make_id("a.txt"), "a.txt", content, Metadata::default(), &limited(5, true))
+            .unwrap();
+    assert_eq!(contents(&parts), vec!["éé", "éé", "éé"]);
+    assert!(parts.iter().all(|p| p.content.len() <= 5));
+}
+
+#[test]
+fn parts_have_ids_and_metadata() {
+    let mut metadata = Metadata::default();
+    metadata.insert_string("title", "Log");
+    let parts = Document::ingest_parts(
+        make_id("logs/app.log"),
+        "logs/app.log",
+        b"one\ntwo\nthree\n".to_vec(),
+        metadata,
+        &limited(8, true),
+    )
+    .unwrap();
+    let ids: Vec<_> = parts.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, vec!["logs/app.log/part-0001", "logs/app.log/part-0002"]);
+    for (i, part) in parts.iter().enumerate() {
+        assert_eq!(part.metadata.get(PART_KEY), Some(&MetadataValue::Number(i as i64 + 1)));
+        assert_eq!(part.metadata.get(PARTS_KEY), Some(&MetadataValue::Number(2)));
+        assert_eq!(part.metadata.get(PART_OF_KEY).and_then(|v| v.as_str()), Some("logs/app.log"));
+        assert_eq!(part.metadata.get("title").and_then(|v| v.as_str()), Some("Log"));
+        assert_eq!(part.source, Source::from("logs/app.log"));
+    }
+}
+
+#[test]
+fn splitting_is_deterministic() {
+    let content = "line of text\n".repeat(50).into_bytes();
+    let options = limited(64, true);
+    let id = make_id("a.txt");
+    let ingest = || {
+        Document::ingest_parts(id.clone(), "a.txt", content.clone(), Metadata::default(), &options).unwrap()
+    };
+    let first = ingest();
+    let second = ingest();
+    assert_eq!(first.len(), 13);
+    for (a, b) in first.iter().zip(&second) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.version, b.version);
+    }
+}
+
+#[test]
+fn options_round_trip_through_serde() {
+    let options = limited(1024, true);
+    let json = serde_json::to_string(&options).unwrap();
+    assert_eq!(json, r#"{"max_bytes":1024,"split_oversize":true}"#);
+    assert_eq!(serde_json::from_str::<IngestOptions>(&json).unwrap(), options);
+    assert_eq!(serde_json::to_string(&IngestOptions::default()).unwrap(), "{}");
+}
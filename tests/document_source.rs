@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata, Source};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, source: Source) -> Document {
+    Document::ingest(make_id(id_str), source, b"deploy".to_vec(), Metadata::default()).unwrap()
+}
+
+#[test]
+fn typed_sources_serialize_tagged_by_kind() {
+    let local = Source::local_path("/srv/docs", "guides/deploy.md");
+    assert_eq!(
+        serde_json::to_string(&local).unwrap(),
+        r#"{"kind":"local_path","root":"/srv/docs","rel":"guides/deploy.md"}"#
+    );
+
+    let fetched_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let url = Source::url("https://example.com/runbook", fetched_at);
+    assert_eq!(
+        serde_json::to_string(&url).unwrap(),
+        r#"{"kind":"url","url":"https://example.com/runbook","fetched_at":"2024-05-01T12:00:00Z"}"#
+    );
+
+    let git = Source::git("https://github.com/acme/app", "3f2c1e0", "docs/deploy.md");
+    assert_eq!(
+        serde_json::to_string(&git).unwrap(),
+        r#"{"kind":"git","repo":"https://github.com/acme/app","commit":"3f2c1e0","path":"docs/deploy.md"}"#
+    );
+
+    for source in [local, url, git] {
+        let json = serde_json::to_string(&source).unwrap();
+        assert_eq!(serde_json::from_str::<Source>(&json).unwrap(), source);
+    }
+}
+
+#[test]
+fn text_sources_stay_plain_strings() {
+    let source = Source::from("docs/a.md");
+    assert_eq!(serde_json::to_string(&source).unwrap(), r#""docs/a.md""#);
+    assert_eq!(serde_json::from_str::<Source>(r#""docs/a.md""#).unwrap(), source);
+}
+
+#[test]
+fn documents_from_old_caches_read_back() {
+    let json = r#"{"schema_version":1,"id":"a.md","version":"sha256:00","source":"a.md","content":"x","metadata":{}}"#;
+    let doc: Document = serde_json::from_str(json).unwrap();
+    assert_eq!(doc.source, Source::Text("a.md".to_string()));
+}
+
+#[test]
+fn documents_round_trip_their_source() {
+    let doc = make_doc("a.md", Source::git("acme/app", "3f2c1e0", "a.md"));
+    let json = serde_json::to_string(&doc).unwrap();
+    assert!(json.contains(r#""source":{"kind":"git","#));
+    let read: Document = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, doc);
+}
+
+#[test]
+fn sources_survive_a_cache_build() {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", Source::local_path("/srv/docs", "a.md")),
+        make_doc("b.md", "b.md".into()),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0()).build(docs.clone(), &dir.path().join("cache")).unwrap();
+    let loaded = cache.load_documents().unwrap();
+    assert_eq!(loaded[0].source, docs[0].source);
+    assert_eq!(loaded[1].source, docs[1].source);
+}
+
+#[test]
+fn source_does_not_affect_the_cache_version() {
+    let dir = tempdir().unwrap();
+    let build = |source: Source, name: &str| {
+        CacheBuilder::new(CacheBuildConfig::v0())
+            .build(vec![make_doc("a.md", source)], &dir.path().join(name))
+            .unwrap()
+            .manifest
+            .cache_version
+    };
+    let text = build("a.md".into(), "text");
+    let local = build(Source::local_path("/srv/docs", "a.md"), "local");
+    assert_eq!(text, local);
+}
+
+#[test]
+fn display_names_the_origin() {
+    assert_eq!(Source::local_path("/srv/docs/", "a.md").to_string(), "/srv/docs/a.md");
+    assert_eq!(Source::local_path("", "a.md").to_string(), "a.md");
+    let fetched_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    assert_eq!(Source::url("https://example.com/a", fetched_at).to_string(), "https://example.com/a");
+    assert_eq!(Source::git("acme/app", "3f2c1e0", "a.md").to_string(), "acme/app@3f2c1e0:a.md");
+    assert_eq!(Source::from("notes").to_string(), "notes");
+}
@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::compression::{Summarizer, TruncationStrategy};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::{BudgetVerdict, Query, Representation};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn long_doc() -> String {
+    format!("Deploy with the script. Then wait. {}", "filler ".repeat(40))
+}
+
+fn build_cache(config: CacheBuildConfig) -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.txt", "deploy deploy deploy"), // 5 tokens, score 1.0
+        make_doc("b.txt", &long_doc()),
+    ];
+    let cache = CacheBuilder::new(config).build(docs, &dir.path().join("cache")).unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn first_sentences_summary() {
+    let summarizer = Summarizer::first_sentences(2);
+    assert_eq!(summarizer.summarize("One. Two!\nThree.").as_deref(), Some("One.\nTwo!"));
+    assert_eq!(summarizer.summarize("Only one").as_deref(), Some("Only one"));
+    assert_eq!(summarizer.summarize("  \n"), None);
+}
+
+#[test]
+fn summaries_are_stored_at_build_time() {
+    let (_dir, plain) = build_cache(CacheBuildConfig::v0());
+    assert!(plain.load_documents().unwrap().iter().all(|doc| doc.summary.is_none()));
+
+    let config = CacheBuildConfig::v0().with_summarizer(Summarizer::first_sentences(1));
+    let (_dir2, cache) = build_cache(config);
+    let docs = cache.load_documents().unwrap();
+    assert_eq!(docs[1].summary.as_deref(), Some("Deploy with the script."));
+
+    // The summarizer is part of the cache version
+    assert_ne!(cache.manifest.cache_version, plain.manifest.cache_version);
+}
+
+#[test]
+fn budgeting_falls_back_to_summary() {
+    let config = CacheBuildConfig::v0().with_summarizer(Summarizer::first_sentences(1));
+    let (_dir, cache) = build_cache(config);
+    let options = SelectionOptions {
+        summary_fallback: true,
+        trace: true,
+        ..Default::default()
+    };
+
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 12).unwrap();
+    assert_eq!(plain.documents.len(), 1);
+    assert_eq!(plain.selection.documents_summarized, None);
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 12, &options)
+        .unwrap();
+    let b = &result.documents[1];
+    assert_eq!(b.id, "b.txt");
+    assert_eq!(b.content, "Deploy with the script.");
+    assert_eq!(b.tokens, 6);
+    assert_eq!(b.representation, Some(Representation::Summary));
+    assert!(b.original_tokens.unwrap() > 6);
+
+    assert_eq!(result.selection.tokens_used, 11);
+    assert_eq!(result.selection.documents_summarized, Some(1));
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+    assert_eq!(result.selection.trace.unwrap().budget[1].verdict, BudgetVerdict::Summarized);
+    assert!(serde_json::to_string(b).unwrap().contains(r#""representation":"summary","#));
+}
+
+#[test]
+fn summary_that_does_not_fit_falls_through_to_truncation() {
+    let config = CacheBuildConfig::v0().with_summarizer(Summarizer::first_sentences(1));
+    let (_dir, cache) = build_cache(config);
+    let options = SelectionOptions {
+        summary_fallback: true,
+        ..Default::default()
+    };
+    let selector = ContextSelector::default().with_truncation(TruncationStrategy::Head);
+    let result = selector.select_with(&cache, Query::new("deploy"), 9, &options).unwrap();
+
+    assert_eq!(result.documents[1].representation, None);
+    assert_eq!(result.documents[1].truncation, Some(TruncationStrategy::Head));
+    assert_eq!(result.selection.documents_summarized, Some(0));
+    assert_eq!(result.selection.documents_truncated, Some(1));
+}
@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, Embedder, HashingEmbedder, VectorError};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ApproxTokenCounter, ContextSelector, EmbeddingScorer};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("deploy.md", "deploy the service to production"),
+        make_doc("security.md", "security hardening checklist"),
+        make_doc("overview.md", "overview of the system"),
+    ]
+}
+
+#[test]
+fn embedding_vectors_are_written_deterministically() {
+    let dir = tempdir().unwrap();
+    let path_a = dir.path().join("a");
+    let path_b = dir.path().join("b");
+
+    let cache_a = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(docs(), &path_a)
+        .unwrap();
+    let cache_b = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(docs(), &path_b)
+        .unwrap();
+
+    assert_eq!(
+        fs::read(path_a.join("vectors.json")).unwrap(),
+        fs::read(path_b.join("vectors.json")).unwrap()
+    );
+    assert_eq!(cache_a.manifest.cache_version, cache_b.manifest.cache_version);
+
+    let store = cache_a.load_vectors().unwrap().expect("vectors.json present");
+    assert_eq!(store.embedder, "hashing-256");
+    assert_eq!(store.vectors.len(), 3);
+}
+
+#[test]
+fn embedder_changes_cache_version() {
+    let dir = tempdir().unwrap();
+
+    let plain = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("plain"))
+        .unwrap();
+    let embedded = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(docs(), &dir.path().join("embedded"))
+        .unwrap();
+
+    assert_ne!(plain.manifest.cache_version, embedded.manifest.cache_version);
+    assert!(plain.load_vectors().unwrap().is_none());
+}
+
+#[test]
+fn embedding_scorer_ranks_by_similarity() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+
+    let vectors = cache.load_vectors().unwrap().unwrap();
+    let scorer = EmbeddingScorer::new(HashingEmbedder::default(), vectors).unwrap();
+    let selector = ContextSelector::new(scorer, ApproxTokenCounter);
+
+    let result = selector.select(&cache, Query::new("security checklist"), 1000).unwrap();
+    assert_eq!(result.documents[0].id, "security.md");
+    assert!(result.documents[0].score > 0.0);
+    assert!(result.documents.iter().all(|d| (0.0..=1.0).contains(&d.score)));
+
+    let again = selector.select(&cache, Query::new("security checklist"), 1000).unwrap();
+    assert_eq!(
+        serde_json::to_string(&result).unwrap(),
+        serde_json::to_string(&again).unwrap()
+    );
+}
+
+#[test]
+fn embedding_scorer_rejects_mismatched_embedder() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::new(64))
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+
+    let vectors = cache.load_vectors().unwrap().unwrap();
+    match EmbeddingScorer::new(HashingEmbedder::new(128), vectors) {
+        Err(VectorError::EmbedderMismatch { expected, found }) => {
+            assert_eq!(expected, "hashing-64");
+            assert_eq!(found, "hashing-128");
+        }
+        Ok(_) => panic!("expected embedder mismatch"),
+    }
+}
+
+/// Counts `embed` calls.
+struct CountingEmbedder {
+    inner: HashingEmbedder,
+    calls: Arc<AtomicUsize>,
+}
+
+impl Embedder for CountingEmbedder {
+    fn id(&self) -> String {
+        self.inner.id()
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.inner.embed(text)
+    }
+}
+
+#[test]
+fn query_is_embedded_once_per_query() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let embedder = CountingEmbedder {
+        inner: HashingEmbedder::default(),
+        calls: calls.clone(),
+    };
+    let scorer = EmbeddingScorer::new(embedder, cache.load_vectors().unwrap().unwrap()).unwrap();
+    let selector = ContextSelector::new(scorer, ApproxTokenCounter);
+
+    let first = selector.select(&cache, Query::new("security checklist"), 1000).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    let again = selector.select(&cache, Query::new("security checklist"), 1000).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first.documents[0].score, again.documents[0].score);
+
+    selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use context_core::document::encoding::{detect, transcode, Encoding, ORIGINAL_ENCODING_KEY};
+use context_core::document::parser::ORIGINAL_CONTENT_HASH_KEY;
+use context_core::document::{Document, DocumentError, DocumentId, DocumentVersion, IngestOptions, Metadata};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+    let mut bytes = if bom { vec![0xff, 0xfe] } else { Vec::new() };
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    bytes
+}
+
+fn utf16be(text: &str, bom: bool) -> Vec<u8> {
+    let mut bytes = if bom { vec![0xfe, 0xff] } else { Vec::new() };
+    bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    bytes
+}
+
+fn transcoding() -> IngestOptions {
+    IngestOptions {
+        transcode: true,
+        ..IngestOptions::default()
+    }
+}
+
+#[test]
+fn detects_byte_order_marks() {
+    assert_eq!(detect(b"\xef\xbb\xbfhi"), Encoding::Utf8);
+    assert_eq!(detect(&utf16le("hi", true)), Encoding::Utf16le);
+    assert_eq!(detect(&utf16be("hi", true)), Encoding::Utf16be);
+}
+
+#[test]
+fn detects_unmarked_utf16() {
+    assert_eq!(detect(&utf16le("Deploy guide", false)), Encoding::Utf16le);
+    assert_eq!(detect(&utf16be("Deploy guide", false)), Encoding::Utf16be);
+    assert_eq!(detect("Déploiement".as_bytes()), Encoding::Utf8);
+    assert_eq!(detect(b"caf\xe9"), Encoding::Windows1252);
+}
+
+#[test]
+fn transcodes_to_utf8() {
+    assert_eq!(transcode(b"\xef\xbb\xbfhi"), Some(("hi".to_string(), Encoding::Utf8)));
+    assert_eq!(
+        transcode(&utf16le("naïve ✓", true)),
+        Some(("naïve ✓".to_string(), Encoding::Utf16le))
+    );
+    assert_eq!(transcode(&utf16be("résumé", false)).unwrap().0, "résumé");
+    // 0x93/0x94 are Windows-1252 curly quotes, 0xe9 is Latin-1 é
+    assert_eq!(
+        transcode(b"\x93caf\xe9\x94"),
+        Some(("“café”".to_string(), Encoding::Windows1252))
+    );
+    // A mark followed by data invalid in its encoding
+    assert_eq!(transcode(b"\xff\xfe\x00"), None);
+    assert_eq!(transcode(b"\xef\xbb\xbf\xff"), None);
+}
+
+#[test]
+fn strict_ingestion_is_the_default() {
+    let result = Document::ingest_with(
+        make_id("legacy.txt"),
+        "legacy.txt".to_string(),
+        b"caf\xe9".to_vec(),
+        Metadata::new(),
+        &IngestOptions::default(),
+    );
+    assert!(matches!(result, Err(DocumentError::InvalidUtf8(_))));
+}
+
+#[test]
+fn transcoding_records_the_original_encoding() {
+    let raw = utf16le("Deploy guide", true);
+    let id = make_id("guide.txt");
+    let doc = Document::ingest_with(id, "guide.txt".to_string(), raw.clone(), Metadata::new(), &transcoding()).unwrap();
+    assert_eq!(doc.content, "Deploy guide");
+    assert_eq!(doc.version, DocumentVersion::from_content(b"Deploy guide"));
+    assert_eq!(doc.metadata.get(ORIGINAL_ENCODING_KEY).and_then(|v| v.as_str()), Some("utf-16le"));
+    assert_eq!(
+        doc.metadata.get(ORIGINAL_CONTENT_HASH_KEY).and_then(|v| v.as_str()),
+        Some(DocumentVersion::from_content(&raw).as_str())
+    );
+
+    // Plain UTF-8 is ingested unchanged, with no extra metadata
+    let plain = Document::ingest(make_id("a.md"), "a.md".to_string(), b"alpha".to_vec(), Metadata::new()).unwrap();
+    let doc = Document::ingest_with(make_id("a.md"), "a.md".to_string(), b"alpha".to_vec(), Metadata::new(), &transcoding())
+        .unwrap();
+    assert_eq!(doc, plain);
+}
+
+#[test]
+fn invalid_marked_content_is_an_error() {
+    let result = Document::ingest_with(
+        make_id("bad.txt"),
+        "bad.txt".to_string(),
+        b"\xff\xfe\x00".to_vec(),
+        Metadata::new(),
+        &transcoding(),
+    );
+    assert!(matches!(result, Err(DocumentError::InvalidEncoding(Encoding::Utf16le))));
+}
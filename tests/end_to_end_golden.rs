@@ -6,11 +6,7 @@ use std::io::Read;
 
 fn make_doc(root: &str, source: &str, content: &str) -> Document {
     let root_path = Path::new(root);
-    let source_path = if root == "." && !source.starts_with("./") {
-        Path::new(source)
-    } else {
-        Path::new(source)
-    };
+    let source_path = Path::new(source);
     
     let id = DocumentId::from_path(root_path, source_path).unwrap();
     Document::ingest(id, source.to_string(), content.as_bytes().to_vec(), Metadata::new()).unwrap()
@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::eval::{load_cases, parse_cases, run, EvalError};
+use context_core::selection::ContextSelector;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy"),   // score 1.0
+        make_doc("b.md", "deploy deploy deploy x"), // score 0.75
+        make_doc("c.md", "rollback notes"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+const CASES: &str = r#"{"name":"perfect","request":{"query":"rollback","budget":{"total":100},"options":{"exclude_zero_score":true}},"expected":["c.md"]}
+
+{"name":"second","request":{"query":"deploy","budget":{"total":100},"options":{"exclude_zero_score":true}},"expected":["b.md","c.md"]}
+{"request":{"query":"nothing","budget":{"total":100},"options":{"exclude_zero_score":true}},"expected":[]}
+"#;
+
+#[test]
+fn metrics_per_case_and_mean() {
+    let (_dir, cache) = build_cache();
+    let cases = parse_cases(CASES).unwrap();
+    assert_eq!(cases.len(), 3);
+
+    let report = run(&ContextSelector::default(), &cache, &cases).unwrap();
+
+    let perfect = &report.cases[0];
+    assert_eq!(perfect.name.as_deref(), Some("perfect"));
+    assert_eq!(perfect.selected, vec!["c.md"]);
+    assert_eq!((perfect.precision, perfect.recall, perfect.ndcg), (1.0, 1.0, 1.0));
+
+    // Selected a.md, b.md; b.md relevant at rank 1 of expected {b, c}
+    let second = &report.cases[1];
+    assert_eq!(second.selected, vec!["a.md", "b.md"]);
+    assert_eq!(second.precision, 0.5);
+    assert_eq!(second.recall, 0.5);
+    // (1 / log2(3)) / (1 + 1 / log2(3))
+    assert_eq!(second.ndcg, 0.386853);
+
+    // Nothing selected, nothing expected
+    let empty = &report.cases[2];
+    assert!(empty.selected.is_empty());
+    assert_eq!((empty.precision, empty.recall, empty.ndcg), (0.0, 1.0, 1.0));
+
+    assert_eq!(report.precision, 0.5);
+    assert_eq!(report.recall, 0.833333);
+    assert_eq!(report.ndcg, 0.795618);
+}
+
+#[test]
+fn reports_are_deterministic() {
+    let (_dir, cache) = build_cache();
+    let cases = parse_cases(CASES).unwrap();
+    let selector = ContextSelector::default();
+    let first = serde_json::to_string(&run(&selector, &cache, &cases).unwrap()).unwrap();
+    let second = serde_json::to_string(&run(&selector, &cache, &cases).unwrap()).unwrap();
+    assert_eq!(first, second);
+    assert!(first.contains(r#""precision":0.5,"recall":0.833333,"ndcg":0.795618}"#));
+}
+
+#[test]
+fn cases_load_from_a_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cases.jsonl");
+    std::fs::write(&path, CASES).unwrap();
+    assert_eq!(load_cases(&path).unwrap(), parse_cases(CASES).unwrap());
+}
+
+#[test]
+fn errors_name_the_line_and_case() {
+    let err = parse_cases("\n{\"request\":{}}\n").unwrap_err();
+    assert!(matches!(err, EvalError::Parse { line: 2, .. }));
+
+    let (_dir, cache) = build_cache();
+    let cases = parse_cases(
+        r#"{"request":{"query":"deploy","budget":{"total":10}},"expected":[]}
+{"request":{"query":"deploy","budget":{"total":10},"filters":["nope"]},"expected":[]}"#,
+    )
+    .unwrap();
+    let err = run(&ContextSelector::default(), &cache, &cases).unwrap_err();
+    assert!(matches!(err, EvalError::Selection { case: 1, .. }));
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::compression::ExtractiveCompressor;
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ApproxTokenCounter, BudgetConfig, BudgetStrategy, ContextSelector, SelectionOptions};
+use context_core::types::{BudgetVerdict, Query};
+use tempfile::{tempdir, TempDir};
+
+// Sentences (ApproxTokenCounter tokens, "deploy" matches):
+// "Deploy the service with care." (8, 1), "Lunch is at noon." (5, 0),
+// "Run deploy then deploy again." (8, 2), "Notes" (2, 0)
+const CONTENT: &str = "Deploy the service with care. Lunch is at noon. Run deploy then deploy again.\nNotes";
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy"),                // 5 tokens, score 1.0
+        make_doc("b.md", CONTENT),                               // 21 tokens, score 0.2
+        make_doc("c.md", "deploy one two three four five six"), // 9 tokens, score 0.14
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn keeps_best_sentences_in_document_order() {
+    let compressor = ExtractiveCompressor::new();
+    let query = Query::new("deploy");
+
+    assert_eq!(
+        compressor.compress(CONTENT, &query, &ApproxTokenCounter, 8).as_deref(),
+        Some("Run deploy then deploy again.")
+    );
+    assert_eq!(
+        compressor.compress(CONTENT, &query, &ApproxTokenCounter, 15).as_deref(),
+        Some("Deploy the service with care.\nRun deploy then deploy again.")
+    );
+    // Nothing fits
+    assert_eq!(compressor.compress(CONTENT, &query, &ApproxTokenCounter, 1), None);
+    // Too short to keep
+    assert_eq!(compressor.min_tokens(9).compress(CONTENT, &query, &ApproxTokenCounter, 8), None);
+}
+
+#[test]
+fn ties_keep_earlier_sentences() {
+    // No sentence matches, so the leading ones win
+    let compressed = ExtractiveCompressor::new().compress(CONTENT, &Query::new("rollback"), &ApproxTokenCounter, 8);
+    assert_eq!(compressed.as_deref(), Some("Deploy the service with care."));
+}
+
+#[test]
+fn budgeting_falls_back_to_compression() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        trace: true,
+        ..Default::default()
+    };
+
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 25).unwrap();
+    let ids: Vec<&str> = plain.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "c.md"]);
+    assert_eq!(plain.selection.documents_compressed, None);
+
+    let selector = ContextSelector::default().with_compression(ExtractiveCompressor::new());
+    let result = selector.select_with(&cache, Query::new("deploy"), 25, &options).unwrap();
+
+    // b.md is admitted in rank order, cut down to the 11 tokens left
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md", "c.md"]);
+    let b = &result.documents[1];
+    assert_eq!(b.content, "Run deploy then deploy again.\nNotes");
+    assert_eq!(b.tokens, 9);
+    assert_eq!(b.original_tokens, Some(21));
+    assert_eq!(result.documents[0].original_tokens, None);
+
+    assert_eq!(result.selection.tokens_used, 23);
+    assert_eq!(result.selection.documents_selected, 3);
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+    assert_eq!(result.selection.documents_compressed, Some(1));
+    let verdicts: Vec<BudgetVerdict> = result.selection.trace.unwrap().budget.iter().map(|b| b.verdict).collect();
+    assert_eq!(
+        verdicts,
+        vec![BudgetVerdict::Selected, BudgetVerdict::Compressed, BudgetVerdict::Selected]
+    );
+
+    let json = serde_json::to_string(&result.documents[1]).unwrap();
+    assert!(json.contains(r#""tokens":9,"original_tokens":21,"#));
+}
+
+#[test]
+fn stop_at_first_exclusion_compresses_only_that_document() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default().with_compression(ExtractiveCompressor::new());
+    let budget = BudgetConfig::new(25).strategy(BudgetStrategy::StopAtFirstExclusion);
+    let result = selector.select(&cache, Query::new("deploy"), budget).unwrap();
+
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md"]);
+    assert_eq!(
+        result.documents[1].content,
+        "Deploy the service with care.\nLunch is at noon.\nRun deploy then deploy again."
+    );
+    assert_eq!(result.selection.tokens_used, 25);
+    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+}
@@ -0,0 +1,65 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{
+    CacheBuildConfig, CacheBuildError, CacheBuilder, CacheStore, DocumentEncoding, MemoryStore,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+#[test]
+fn settings_of_disabled_features_survive_a_round_trip() {
+    // A manifest written by a build with the feature keeps its config, and
+    // so its derived cache version, when read by one without it
+    let mut json = serde_json::to_value(CacheBuildConfig::v0()).unwrap();
+    json["tantivy_index"] = true.into();
+    json["document_encoding"] = "msgpack".into();
+    let config: CacheBuildConfig = serde_json::from_value(json.clone()).unwrap();
+    assert!(config.tantivy_index);
+    assert_eq!(config.document_encoding, DocumentEncoding::Msgpack);
+    assert_eq!(serde_json::to_value(&config).unwrap(), json);
+}
+
+#[test]
+fn tantivy_index_needs_the_feature() {
+    let store = Arc::new(MemoryStore::new());
+    let builder = CacheBuilder::new(CacheBuildConfig::v0().with_tantivy_index()).with_store(store.clone());
+    let result = builder.build(vec![make_doc("a.md", "alpha")], Path::new("/caches/a"));
+    if cfg!(feature = "tantivy") {
+        result.unwrap();
+        return;
+    }
+    let err = result.unwrap_err();
+    assert!(
+        matches!(err, CacheBuildError::FeatureDisabled { feature: "tantivy", .. }),
+        "{}",
+        err
+    );
+    assert!(!store.exists(Path::new("/caches/a")));
+    assert!(builder.build_in_memory(vec![make_doc("a.md", "alpha")]).is_err());
+}
+
+#[test]
+fn msgpack_documents_need_the_feature() {
+    let store = Arc::new(MemoryStore::new());
+    let config = CacheBuildConfig::v0().with_document_encoding(DocumentEncoding::Msgpack);
+    let result = CacheBuilder::new(config)
+        .with_store(store.clone())
+        .build(vec![make_doc("a.md", "alpha")], Path::new("/caches/a"));
+    if cfg!(feature = "msgpack") {
+        result.unwrap();
+        return;
+    }
+    let err = result.unwrap_err();
+    assert!(
+        matches!(err, CacheBuildError::FeatureDisabled { feature: "msgpack", .. }),
+        "{}",
+        err
+    );
+    assert!(!store.exists(Path::new("/caches/a")));
+}
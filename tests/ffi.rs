@@ -0,0 +1,116 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::ffi::*;
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn empty() -> ContextCoreBuffer {
+    ContextCoreBuffer {
+        ptr: ptr::null_mut(),
+        len: 0,
+    }
+}
+
+/// Copy a buffer into a String and free it.
+fn take(buffer: ContextCoreBuffer) -> String {
+    let s = if buffer.ptr.is_null() {
+        String::new()
+    } else {
+        let bytes = unsafe { std::slice::from_raw_parts(buffer.ptr, buffer.len) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    };
+    unsafe { context_core_buffer_free(buffer) };
+    s
+}
+
+#[test]
+fn ffi_selection_matches_rust_api() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "beta")], &path)
+        .unwrap();
+
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let c_query = CString::new("deploy").unwrap();
+
+    let mut error = empty();
+    let cache = unsafe { context_core_cache_open(c_path.as_ptr(), &mut error) };
+    assert!(!cache.is_null());
+    assert_eq!(take(error), "");
+
+    let mut out = empty();
+    let status = unsafe { context_core_select(cache, c_query.as_ptr(), 100, &mut out) };
+    assert_eq!(status, CONTEXT_CORE_OK);
+    let json = take(out);
+    unsafe { context_core_cache_free(cache) };
+
+    let expected = ContextSelector::default()
+        .select(&ContextCache::open(&path).unwrap(), Query::new("deploy"), 100)
+        .unwrap();
+    assert_eq!(json, serde_json::to_string(&expected).unwrap());
+}
+
+#[test]
+fn ffi_open_reports_errors() {
+    let c_path = CString::new("/nonexistent/cache").unwrap();
+    let mut error = empty();
+    let cache = unsafe { context_core_cache_open(c_path.as_ptr(), &mut error) };
+    assert!(cache.is_null());
+    assert!(!take(error).is_empty());
+
+    let cache = unsafe { context_core_cache_open(ptr::null(), ptr::null_mut()) };
+    assert!(cache.is_null());
+}
+
+#[test]
+fn ffi_select_rejects_null_arguments() {
+    let mut out = empty();
+    let status = unsafe { context_core_select(ptr::null(), ptr::null(), 10, &mut out) };
+    assert_eq!(status, CONTEXT_CORE_INVALID_ARGUMENT);
+    assert!(take(out).contains("null"));
+}
+
+#[test]
+fn ffi_select_reports_cache_errors() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let built = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &path)
+        .unwrap();
+
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let cache = unsafe { context_core_cache_open(c_path.as_ptr(), ptr::null_mut()) };
+    std::fs::remove_file(path.join(&built.manifest.documents[0].file)).unwrap();
+
+    let c_query = CString::new("alpha").unwrap();
+    let mut out = empty();
+    let status = unsafe { context_core_select(cache, c_query.as_ptr(), 10, &mut out) };
+    assert_eq!(status, CONTEXT_CORE_CACHE_ERROR);
+    assert!(take(out).starts_with("Cache integrity error: Missing file for a.md: "));
+    unsafe { context_core_cache_free(cache) };
+}
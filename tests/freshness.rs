@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, Freshness, SelectionOptions, StaleAction};
+use context_core::types::{FilterVerdict, Query, SelectionResult};
+use tempfile::{tempdir, TempDir};
+
+fn make_doc(id_str: &str, content: &str, metadata: Metadata) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn with(key: &str, value: &str) -> Metadata {
+    let mut metadata = Metadata::new();
+    metadata.insert_string(key, value);
+    metadata
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let mut deprecated = Metadata::new();
+    deprecated.insert_bool("deprecated", true);
+    let docs = vec![
+        make_doc("current.md", "deploy a", Metadata::new()),
+        make_doc("old.md", "deploy b", deprecated),
+        make_doc("expired.md", "deploy c", with("expires_at", "2026-01-01T00:00:00Z")),
+        make_doc("later.md", "deploy d", with("expires_at", "2027-01-01T00:00:00+02:00")),
+        make_doc("garbled.md", "deploy e", with("expires_at", "next spring")),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn at(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+}
+
+fn select(cache: &ContextCache, action: StaleAction) -> SelectionResult {
+    let options = SelectionOptions {
+        freshness: Some(Freshness::new(at("2026-06-01T00:00:00Z"), action)),
+        trace: true,
+        ..Default::default()
+    };
+    ContextSelector::default()
+        .select_with(cache, Query::new("deploy"), 100, &options)
+        .unwrap()
+}
+
+fn ids(result: &SelectionResult) -> Vec<&str> {
+    result.documents.iter().map(|d| d.id.as_str()).collect()
+}
+
+#[test]
+fn stale_documents_are_excluded() {
+    let (_dir, cache) = build_cache();
+    let result = select(&cache, StaleAction::Exclude);
+    assert_eq!(ids(&result), vec!["current.md", "garbled.md", "later.md"]);
+    assert_eq!(result.selection.documents_excluded_by_filter, Some(2));
+
+    let trace = result.selection.trace.as_ref().unwrap();
+    let stale: Vec<&str> = trace
+        .filter
+        .iter()
+        .filter(|f| f.verdict == FilterVerdict::ExcludedAsStale)
+        .map(|f| f.id.as_str())
+        .collect();
+    assert_eq!(stale, vec!["expired.md", "old.md"]);
+}
+
+#[test]
+fn stale_documents_are_penalized() {
+    let (_dir, cache) = build_cache();
+    let result = select(&cache, StaleAction::Penalize { factor: 0.5 });
+    assert_eq!(
+        ids(&result),
+        vec!["current.md", "garbled.md", "later.md", "expired.md", "old.md"]
+    );
+    assert_eq!(result.documents[0].score, 0.5);
+    assert_eq!(result.documents[3].score, 0.25);
+    assert_eq!(result.selection.documents_excluded_by_filter, None);
+
+    let trace = result.selection.trace.as_ref().unwrap();
+    let penalized: Vec<(&str, f32)> = trace
+        .scores
+        .iter()
+        .filter_map(|s| s.penalty.map(|penalty| (s.id.as_str(), penalty)))
+        .collect();
+    assert_eq!(penalized, vec![("expired.md", 0.5), ("old.md", 0.5)]);
+}
+
+#[test]
+fn expiry_is_judged_against_the_reference_time() {
+    let (_dir, cache) = build_cache();
+    let docs = cache.load_documents().unwrap();
+    let later = docs.iter().find(|d| d.id.as_str() == "later.md").unwrap();
+
+    // Expiry is inclusive
+    let freshness = |now| Freshness::new(at(now), StaleAction::Exclude);
+    assert!(!freshness("2026-12-31T21:59:59Z").is_stale(later));
+    assert!(freshness("2026-12-31T22:00:00Z").is_stale(later));
+
+    let json = serde_json::to_string(&freshness("2026-06-01T00:00:00Z")).unwrap();
+    assert_eq!(json, r#"{"now":"2026-06-01T00:00:00Z","action":{"kind":"exclude"}}"#);
+}
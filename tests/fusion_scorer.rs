@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, HashingEmbedder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    ApproxTokenCounter, ContextSelector, EmbeddingScorer, FusionScorer, TermFrequencyScorer,
+};
+use context_core::types::{FusionConfig, FusionMode, Query};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(
+            vec![
+                make_doc("a.md", "deploy deploy guide"),
+                make_doc("b.md", "deploy the service to production servers today"),
+                make_doc("c.md", "unrelated notes"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    (dir, cache)
+}
+
+fn fusion_selector(
+    cache: &ContextCache,
+    config: FusionConfig,
+) -> ContextSelector<FusionScorer<TermFrequencyScorer, EmbeddingScorer<HashingEmbedder>>, ApproxTokenCounter>
+{
+    let vectors = cache.load_vectors().unwrap().unwrap();
+    let vector = EmbeddingScorer::new(HashingEmbedder::default(), vectors).unwrap();
+    ContextSelector::new(
+        FusionScorer::new(TermFrequencyScorer, vector, config),
+        ApproxTokenCounter,
+    )
+}
+
+#[test]
+fn weighted_sum_fusion_is_recorded_and_deterministic() {
+    let (_dir, cache) = build_cache();
+    let config = FusionConfig {
+        mode: FusionMode::WeightedSum,
+        lexical_weight: 0.7,
+        vector_weight: 0.3,
+    };
+    let selector = fusion_selector(&cache, config);
+
+    let r1 = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    let r2 = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(
+        serde_json::to_string(&r1).unwrap(),
+        serde_json::to_string(&r2).unwrap()
+    );
+
+    assert_eq!(r1.selection.fusion, Some(config));
+    assert_eq!(r1.documents[0].id, "a.md");
+    assert_eq!(r1.documents[2].id, "c.md");
+    assert_eq!(r1.documents[2].score, 0.0);
+
+    let json = serde_json::to_value(&r1.selection).unwrap();
+    assert_eq!(json["fusion"]["mode"], "weighted_sum");
+}
+
+#[test]
+fn reciprocal_rank_fusion_normalizes_top_rank() {
+    let (_dir, cache) = build_cache();
+    let config = FusionConfig {
+        mode: FusionMode::ReciprocalRank { k: 60 },
+        lexical_weight: 1.0,
+        vector_weight: 1.0,
+    };
+    let selector = fusion_selector(&cache, config);
+
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+
+    // a.md ranks first in both component lists
+    assert_eq!(result.documents[0].id, "a.md");
+    assert_eq!(result.documents[0].score, 1.0);
+    assert!(result.documents.windows(2).all(|w| w[0].score >= w[1].score));
+
+    let json = serde_json::to_value(&result.selection).unwrap();
+    assert_eq!(json["fusion"]["mode"]["reciprocal_rank"]["k"], 60);
+}
+
+#[test]
+fn plain_selection_omits_fusion_metadata() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    assert!(result.selection.fusion.is_none());
+    let json = serde_json::to_string(&result.selection).unwrap();
+    assert!(!json.contains("fusion"));
+}
+
+#[test]
+fn nested_rank_based_scorers_are_finalized_before_fusing() {
+    let (_dir, cache) = build_cache();
+    let vectors = cache.load_vectors().unwrap().unwrap();
+    let rrf = FusionConfig {
+        mode: FusionMode::ReciprocalRank { k: 60 },
+        lexical_weight: 1.0,
+        vector_weight: 1.0,
+    };
+    let expected = fusion_selector(&cache, rrf)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    let inner = FusionScorer::new(
+        TermFrequencyScorer,
+        EmbeddingScorer::new(HashingEmbedder::default(), vectors.clone()).unwrap(),
+        rrf,
+    );
+    let outer = FusionScorer::new(
+        inner,
+        EmbeddingScorer::new(HashingEmbedder::default(), vectors).unwrap(),
+        FusionConfig {
+            mode: FusionMode::WeightedSum,
+            lexical_weight: 1.0,
+            vector_weight: 0.0,
+        },
+    );
+    let result = ContextSelector::new(outer, ApproxTokenCounter)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    assert_eq!(result.documents[0].score, 1.0);
+    for (a, b) in expected.documents.iter().zip(&result.documents) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.score, b.score);
+        assert_eq!(b.why.components["lexical"], a.score);
+    }
+}
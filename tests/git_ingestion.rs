@@ -0,0 +1,178 @@
+#![cfg(feature = "git")]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::parser::{ingest_git, GitError, GitIngestOptions, COMMIT_KEY};
+use context_core::document::{IngestOptions, Rejection, Source};
+use tempfile::tempdir;
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "-c", "commit.gpgsign=false"])
+        .args(args)
+        .env("GIT_AUTHOR_DATE", "2024-05-01T12:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2024-05-01T12:00:00Z")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+fn write(repo: &Path, path: &str, content: &[u8]) {
+    let path = repo.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+// A repository with one commit holding `files`; returns the commit id
+fn make_repo(repo: &Path, files: &[(&str, &[u8])]) -> String {
+    fs::create_dir_all(repo).unwrap();
+    git(repo, &["init", "-q"]);
+    commit(repo, files)
+}
+
+fn commit(repo: &Path, files: &[(&str, &[u8])]) -> String {
+    for (path, content) in files {
+        write(repo, path, content);
+    }
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-q", "-m", "files"]);
+    git(repo, &["rev-parse", "HEAD"])
+}
+
+fn ids(snapshot: &context_core::document::parser::GitSnapshot) -> Vec<&str> {
+    snapshot.documents.iter().map(|d| d.id.as_str()).collect()
+}
+
+#[test]
+fn reads_the_commit_not_the_working_tree() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let first = make_repo(&repo, &[("README.md", b"# Guide\n\nfirst\n"), ("docs/deploy.md", b"deploy")]);
+    commit(&repo, &[("README.md", b"# Guide\n\nsecond\n")]);
+    write(&repo, "README.md", b"uncommitted");
+    write(&repo, "untracked.md", b"untracked");
+
+    let snapshot = ingest_git(&repo, &first, &GitIngestOptions::default()).unwrap();
+    assert_eq!(snapshot.commit, first);
+    assert_eq!(ids(&snapshot), vec!["docs/deploy.md", "readme.md"]);
+    assert_eq!(snapshot.documents[1].content, "# Guide\n\nfirst\n");
+    assert_eq!(snapshot.documents[1].metadata.get("title").and_then(|v| v.as_str()), Some("Guide"));
+
+    let head = ingest_git(&repo, "HEAD", &GitIngestOptions::default()).unwrap();
+    assert_ne!(head.commit, first);
+    assert_eq!(head.documents[1].content, "# Guide\n\nsecond\n");
+}
+
+#[test]
+fn documents_record_the_commit() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let sha = make_repo(&repo, &[("Docs/Guide.md", b"guide")]);
+
+    let options = GitIngestOptions {
+        repo: "https://github.com/acme/app".to_string(),
+        ..GitIngestOptions::default()
+    };
+    // Abbreviated ids resolve to the full one
+    let snapshot = ingest_git(&repo, &sha[..10], &options).unwrap();
+    let doc = &snapshot.documents[0];
+    assert_eq!(doc.source, Source::git("https://github.com/acme/app", sha.as_str(), "Docs/Guide.md"));
+    assert_eq!(doc.metadata.get(COMMIT_KEY).and_then(|v| v.as_str()), Some(sha.as_str()));
+    assert_eq!(snapshot.committed_at.to_rfc3339(), "2024-05-01T12:00:00+00:00");
+}
+
+#[test]
+fn honors_ignore_files_and_skips_hidden_and_binary_files() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let sha = make_repo(
+        &repo,
+        &[
+            (".contextignore", b"generated/\n"),
+            (".github/workflow.yml", b"on: push"),
+            ("generated/api.md", b"generated"),
+            ("guide.md", b"guide"),
+            ("logo.png", b"\x89PNG\r\n\x1a\n\0\0"),
+        ],
+    );
+
+    let snapshot = ingest_git(&repo, &sha, &GitIngestOptions::default()).unwrap();
+    assert_eq!(ids(&snapshot), vec!["guide.md"]);
+    assert_eq!(snapshot.report.documents, 1);
+    assert_eq!(snapshot.report.skipped.len(), 1);
+    assert_eq!(snapshot.report.skipped[0].id.as_str(), "logo.png");
+
+    let everything = GitIngestOptions {
+        ignore_files: false,
+        ..GitIngestOptions::default()
+    };
+    let snapshot = ingest_git(&repo, &sha, &everything).unwrap();
+    assert_eq!(ids(&snapshot), vec!["generated/api.md", "guide.md"]);
+}
+
+#[test]
+fn applies_size_limits() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let sha = make_repo(&repo, &[("log.txt", b"first line\nsecond line\n"), ("small.txt", b"ok")]);
+
+    let limited = GitIngestOptions {
+        ingest: IngestOptions {
+            max_bytes: Some(12),
+            ..IngestOptions::default()
+        },
+        ..GitIngestOptions::default()
+    };
+    let snapshot = ingest_git(&repo, &sha, &limited).unwrap();
+    assert_eq!(ids(&snapshot), vec!["small.txt"]);
+    assert!(matches!(snapshot.report.skipped[0].reason, Rejection::TooLarge { bytes: 23, limit: 12 }));
+
+    let splitting = GitIngestOptions {
+        ingest: IngestOptions {
+            max_bytes: Some(12),
+            split_oversize: true,
+            ..IngestOptions::default()
+        },
+        ..GitIngestOptions::default()
+    };
+    let snapshot = ingest_git(&repo, &sha, &splitting).unwrap();
+    assert_eq!(ids(&snapshot), vec!["log.txt/part-0001", "log.txt/part-0002", "small.txt"]);
+}
+
+#[test]
+fn identical_commits_build_identical_caches() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let sha = make_repo(&repo, &[("a.md", b"# A\n\nalpha\n"), ("b/c.md", b"gamma")]);
+    let clone = dir.path().join("clone");
+    git(dir.path(), &["clone", "-q", repo.to_str().unwrap(), clone.to_str().unwrap()]);
+    write(&clone, "a.md", b"edited in the working tree");
+
+    let build = |repo: &Path, output: &str| {
+        let snapshot = ingest_git(repo, &sha, &GitIngestOptions::default()).unwrap();
+        let config = CacheBuildConfig::v0().with_fixed_timestamp(snapshot.committed_at);
+        let cache = CacheBuilder::new(config).build(snapshot.documents, &dir.path().join(output)).unwrap();
+        fs::read(cache.root.join("manifest.json")).unwrap()
+    };
+    let original = build(&repo, "original");
+    assert_eq!(build(&repo, "again"), original);
+    assert_eq!(build(&clone, "cloned"), original);
+}
+
+#[test]
+fn unknown_commits_are_errors() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    make_repo(&repo, &[("a.md", b"alpha")]);
+
+    let result = ingest_git(&repo, "no-such-branch", &GitIngestOptions::default());
+    assert!(matches!(result, Err(GitError::Command { .. })));
+    let result = ingest_git(&dir.path().join("missing"), "HEAD", &GitIngestOptions::default());
+    assert!(matches!(result, Err(GitError::Command { .. })));
+}
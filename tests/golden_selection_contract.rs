@@ -1,3 +1,4 @@
+use context_core::compat::SELECTION_RESULT_SCHEMA_VERSION;
 use context_core::types::{
   SelectedDocument, SelectionMetadata, SelectionResult, SelectionWhy
 };
@@ -13,14 +14,20 @@ fn golden_selection_output_serialization() {
         query_terms: vec!["deployment".to_string()],
         term_matches: 12,
         total_words: 156,
+        ..Default::default()
     };
 
     let doc = SelectedDocument {
         id: "docs/deployment.md".to_string(),
         version: "sha256:mock".to_string(),
         content: "Content...".to_string(),
+        span: None,
+        metadata: None,
         score: 0.92,
         tokens: 847,
+        original_tokens: None,
+        truncation: None,
+        representation: None,
         why,
     };
 
@@ -32,10 +39,12 @@ fn golden_selection_output_serialization() {
         documents_considered: 42,
         documents_selected: 3,
         documents_excluded_by_budget: 9,
+        ..Default::default()
     };
 
     // 3. Construct SelectionResult
     let result = SelectionResult {
+        schema_version: SELECTION_RESULT_SCHEMA_VERSION,
         documents: vec![doc],
         selection: meta,
     };
@@ -53,7 +62,7 @@ fn golden_selection_output_serialization() {
 
     // The spec example shows documents array first, then selection metadata.
     // Let's verify our struct order produces this.
-    // struct SelectionResult { documents: ..., selection: ... }
+    // struct SelectionResult { schema_version, documents: ..., selection: ... }
     assert!(doc_start < sel_start, "documents should appear before selection metadata");
 
     // Verify fields inside a document
@@ -69,6 +78,7 @@ fn golden_selection_output_serialization() {
     // 6. JSON Snapshot Check
     // Enforce byte-level determinism (ignoring whitespace differences in formatting)
     const EXPECTED_JSON: &str = r#"{
+      "schema_version": 1,
       "documents": [
         {
           "id": "docs/deployment.md",
@@ -112,6 +112,7 @@ fn golden_end_to_end_selection_is_deterministic() {
     // 6. Snapshot assertion (freeze contract)
     // ------------------------------------------------------------
     let expected = r#"{
+  "schema_version": 1,
   "documents": [
     {
       "id": "docs/deployment.md",
@@ -162,7 +163,11 @@ fn golden_end_to_end_selection_is_deterministic() {
     "tokens_used": 23,
     "documents_considered": 3,
     "documents_selected": 3,
-    "documents_excluded_by_budget": 0
+    "documents_excluded_by_budget": 0,
+    "cache_version": "sha256:a5c3068b60c0706aab154adf5312bac293ecf8121d2cae8acb7ccc9ef3a320d6",
+    "document_count_in_cache": 3,
+    "scorer": "term_frequency",
+    "token_counter": "approx(4)"
   }
 }"#;
 
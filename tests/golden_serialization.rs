@@ -1,3 +1,4 @@
+use context_core::compat::{CACHE_FORMAT_VERSION, CACHE_MANIFEST_SCHEMA_VERSION};
 use context_core::document::{Document, DocumentId, Metadata};
 use context_core::cache::{CacheManifest, CacheBuildConfig, ManifestDocumentEntry};
 use serde_json::Value;
@@ -63,6 +64,7 @@ fn golden_manifest_serialization() {
     let config = CacheBuildConfig {
         version: "1".to_string(),
         hash_algorithm: "sha256".to_string(),
+        ..CacheBuildConfig::v0()
     };
     
     // Mock entry
@@ -80,11 +82,16 @@ fn golden_manifest_serialization() {
     };
 
     let manifest = CacheManifest {
+        schema_version: CACHE_MANIFEST_SCHEMA_VERSION,
+        format_version: CACHE_FORMAT_VERSION,
+        crate_version: None,
         cache_version: "sha256:mock".to_string(),
         build_config: config,
         created_at: chrono::Utc::now(),
         document_count: 1,
         documents: vec![entry],
+        index_checksum: None,
+        tree_hash: None,
     };
     
     let json_str = serde_json::to_string(&manifest).unwrap();
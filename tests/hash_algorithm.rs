@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, DocumentVersion, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::{HashAlgorithm, Query};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn blake3_config() -> CacheBuildConfig {
+    CacheBuildConfig {
+        hash_algorithm: "blake3".to_string(),
+        ..CacheBuildConfig::v0()
+    }
+}
+
+#[test]
+fn blake3_document_version_has_prefix() {
+    let v = DocumentVersion::from_content_with(HashAlgorithm::Blake3, b"hello");
+    assert_eq!(
+        v.as_str(),
+        "blake3:ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f"
+    );
+    assert_eq!(v.algorithm(), Some(HashAlgorithm::Blake3));
+    assert_eq!(
+        DocumentVersion::from_content(b"hello").algorithm(),
+        Some(HashAlgorithm::Sha256)
+    );
+}
+
+#[test]
+fn blake3_cache_build_and_load() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(blake3_config())
+        .build(vec![make_doc("a.md", "alpha deploy"), make_doc("b.md", "beta")], &path)
+        .unwrap();
+
+    assert!(cache.manifest.cache_version.starts_with("blake3:"));
+    for entry in &cache.manifest.documents {
+        assert!(entry.version.as_str().starts_with("blake3:"));
+        let stem = &entry.version.digest().unwrap()[..12];
+        assert_eq!(entry.file, format!("documents/{stem}.json"));
+    }
+
+    let reopened = ContextCache::open(&path).unwrap();
+    let docs = reopened.load_documents().unwrap();
+    assert!(docs.iter().all(|d| d.version.algorithm() == Some(HashAlgorithm::Blake3)));
+
+    let result = ContextSelector::default()
+        .select(&reopened, Query::new("deploy"), 100)
+        .unwrap();
+    assert_eq!(result.documents[0].id, "a.md");
+    assert!(result.documents[0].version.starts_with("blake3:"));
+}
+
+#[test]
+fn blake3_cache_version_is_deterministic_and_differs_from_sha256() {
+    let dir = tempdir().unwrap();
+    let docs = || vec![make_doc("a.md", "alpha")];
+
+    let b1 = CacheBuilder::new(blake3_config()).build(docs(), &dir.path().join("b1")).unwrap();
+    let b2 = CacheBuilder::new(blake3_config()).build(docs(), &dir.path().join("b2")).unwrap();
+    let s = CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &dir.path().join("s")).unwrap();
+
+    assert_eq!(b1.manifest.cache_version, b2.manifest.cache_version);
+    assert_ne!(b1.manifest.cache_version, s.manifest.cache_version);
+}
+
+#[test]
+fn unknown_hash_algorithm_is_rejected() {
+    let dir = tempdir().unwrap();
+    let config = CacheBuildConfig {
+        hash_algorithm: "md5".to_string(),
+        ..CacheBuildConfig::v0()
+    };
+    match CacheBuilder::new(config).build(vec![make_doc("a.md", "alpha")], &dir.path().join("c")) {
+        Err(CacheBuildError::UnsupportedHashAlgorithm(name)) => assert_eq!(name, "md5"),
+        other => panic!("expected UnsupportedHashAlgorithm, got {other:?}"),
+    }
+}
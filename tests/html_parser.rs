@@ -0,0 +1,62 @@
+#![cfg(feature = "html")]
+
+use std::path::Path;
+
+use context_core::document::parser::{HtmlParser, ORIGINAL_CONTENT_HASH_KEY};
+use context_core::document::{DocumentId, DocumentVersion, Metadata};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Ignored</title><style>h1 { color: red; }</style></head>
+<body>
+  <!-- nav -->
+  <h1 class="title">Deploy <em>Guide</em></h1>
+  <p>Run the <a href="/scripts/deploy.sh">deploy script</a>,
+     then   wait.</p>
+  <script>if (a < b) { alert("x"); }</script>
+  <h2>Steps</h2>
+  <ul>
+    <li>Build &amp; test</li>
+    <li>Ship&nbsp;it &#8212; carefully</li>
+  </ul>
+  <pre>
+make deploy
+  --env prod</pre>
+  <p>Done.<br>Really.</p>
+</body>
+</html>"#;
+
+#[test]
+fn converts_to_markdownish_text() {
+    let text = HtmlParser::new().to_text(PAGE);
+    assert_eq!(
+        text,
+        "# Deploy Guide\n\nRun the deploy script, then wait.\n\n## Steps\n\n- Build & test\n- Ship it — carefully\n\n```\nmake deploy\n  --env prod\n```\n\nDone.\nReally."
+    );
+}
+
+#[test]
+fn tolerates_malformed_markup() {
+    let parser = HtmlParser::new();
+    assert_eq!(parser.to_text("a < b and <b>bold</b> &unknown; &amp"), "a < b and bold &unknown; &amp");
+    assert_eq!(parser.to_text("<p title='x > y'>quoted</p>"), "quoted");
+    assert_eq!(parser.to_text("unclosed <script>secret"), "unclosed");
+    assert_eq!(parser.to_text("<td>a</td><td>b</td>"), "a b");
+}
+
+#[test]
+fn parse_records_the_original_hash() {
+    let doc = HtmlParser::new()
+        .parse(make_id("wiki/deploy.html"), "wiki/deploy.html".to_string(), PAGE.as_bytes().to_vec(), Metadata::default())
+        .unwrap();
+    assert!(doc.content.starts_with("# Deploy Guide"));
+    let original = DocumentVersion::from_content(PAGE.as_bytes());
+    assert_eq!(doc.metadata.get(ORIGINAL_CONTENT_HASH_KEY).and_then(|v| v.as_str()), Some(original.as_str()));
+    assert_eq!(doc.version, DocumentVersion::from_content(doc.content.as_bytes()));
+}
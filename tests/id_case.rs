@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata, Source};
+use tempfile::tempdir;
+
+fn make_doc(rel: &str, content: &str) -> Document {
+    let root = Path::new("/repo");
+    let id = DocumentId::from_path_case_sensitive(root, &root.join(rel)).unwrap();
+    Document::ingest(id, Source::local_path("/repo", rel), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+#[test]
+fn case_sensitive_ids_keep_the_path_case() {
+    let root = Path::new("/repo");
+    let path = root.join("Docs/README.md");
+    assert_eq!(DocumentId::from_path(root, &path).unwrap().as_str(), "docs/readme.md");
+    assert_eq!(DocumentId::from_path_case_sensitive(root, &path).unwrap().as_str(), "Docs/README.md");
+}
+
+#[test]
+fn folded_ids_that_collide_name_their_sources() {
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+    let result = builder.build_in_memory(vec![make_doc("readme.md", "lower"), make_doc("README.md", "upper")]);
+    match result {
+        Err(CacheBuildError::DuplicateDocumentId { id, sources }) => {
+            assert_eq!(id, "readme.md");
+            assert_eq!(sources, vec!["/repo/README.md", "/repo/readme.md"]);
+        }
+        other => panic!("expected duplicate id error, got {other:?}"),
+    }
+
+    let err = builder
+        .build_in_memory(vec![make_doc("a.md", "one"), make_doc("A.md", "two")])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Duplicate document ID: a.md (from /repo/A.md and /repo/a.md)");
+}
+
+#[test]
+fn case_sensitive_build_keeps_both_documents() {
+    let dir = tempdir().unwrap();
+    let docs = || vec![make_doc("readme.md", "lower"), make_doc("README.md", "upper")];
+    let config = CacheBuildConfig::v0().with_case_sensitive_ids();
+    let cache = CacheBuilder::new(config).build(docs(), &dir.path().join("cache")).unwrap();
+    let ids: Vec<&str> = cache.manifest.documents.iter().map(|entry| entry.id.as_str()).collect();
+    assert_eq!(ids, vec!["README.md", "readme.md"]);
+
+    // The setting is part of the cache version
+    let folded = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("Guide.md", "text")], &dir.path().join("folded"))
+        .unwrap();
+    let sensitive = CacheBuilder::new(CacheBuildConfig::v0().with_case_sensitive_ids())
+        .build(vec![make_doc("guide.md", "text")], &dir.path().join("sensitive"))
+        .unwrap();
+    assert_eq!(folded.manifest.documents[0].id.as_str(), "guide.md");
+    assert_ne!(folded.manifest.cache_version, sensitive.manifest.cache_version);
+}
@@ -0,0 +1,94 @@
+use std::fs;
+
+use context_core::document::IgnoreRules;
+use tempfile::tempdir;
+
+fn rules(dir: &str, text: &str) -> IgnoreRules {
+    let mut rules = IgnoreRules::new();
+    rules.add(dir, text);
+    rules
+}
+
+fn rules_with(dirs: &[(&str, &str)]) -> IgnoreRules {
+    let mut rules = IgnoreRules::new();
+    for (dir, text) in dirs {
+        rules.add(dir, text);
+    }
+    rules
+}
+
+#[test]
+fn unanchored_patterns_match_at_any_depth() {
+    let rules = rules("", "*.log\nnode_modules/\n");
+    assert!(rules.is_ignored("build.log", false));
+    assert!(rules.is_ignored("a/b/build.log", false));
+    assert!(rules.is_ignored("node_modules", true));
+    assert!(rules.is_ignored("web/node_modules", true));
+    // Everything below an ignored directory is ignored
+    assert!(rules.is_ignored("web/node_modules/pkg/readme.md", false));
+    assert!(!rules.is_ignored("src/main.rs", false));
+}
+
+#[test]
+fn slashes_anchor_patterns() {
+    let rules = rules("", "/target\ndocs/*.tmp\n");
+    assert!(rules.is_ignored("target", true));
+    assert!(!rules.is_ignored("crates/x/target", true));
+    assert!(rules.is_ignored("docs/a.tmp", false));
+    assert!(!rules.is_ignored("src/docs/a.tmp", false));
+}
+
+#[test]
+fn directory_only_patterns_skip_files() {
+    let rules = rules("", "build/\n");
+    assert!(rules.is_ignored("build", true));
+    assert!(!rules.is_ignored("build", false));
+}
+
+#[test]
+fn negation_and_last_match_wins() {
+    let rules = rules("", "*.md\n!keep.md\n");
+    assert!(rules.is_ignored("notes.md", false));
+    assert!(!rules.is_ignored("keep.md", false));
+    // A negation cannot re-include a file under an ignored directory
+    let rules = rules_with(&[("", "out/\n!out/report.md\n")]);
+    assert!(rules.is_ignored("out/report.md", false));
+}
+
+#[test]
+fn comments_blank_lines_and_escapes() {
+    let rules = rules("", "# comment\n\n\\#hash\n\\!bang\ntrailing   \nspace\\ \n");
+    assert!(!rules.is_ignored("comment", false));
+    assert!(rules.is_ignored("#hash", false));
+    assert!(rules.is_ignored("!bang", false));
+    assert!(rules.is_ignored("trailing", false));
+    assert!(rules.is_ignored("space ", false));
+}
+
+#[test]
+fn deeper_directories_take_precedence() {
+    let rules = rules_with(&[("docs", "!draft.md\n"), ("", "draft.md\n")]);
+    assert!(rules.is_ignored("draft.md", false));
+    assert!(!rules.is_ignored("docs/draft.md", false));
+    // A directory's rules match relative to it
+    let rules = rules_with(&[("docs", "/a.md\n")]);
+    assert!(rules.is_ignored("docs/a.md", false));
+    assert!(!rules.is_ignored("a.md", false));
+    assert!(!rules.is_ignored("docs/sub/a.md", false));
+}
+
+#[test]
+fn contextignore_overrides_gitignore() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".gitignore"), "*.md\n").unwrap();
+    fs::write(dir.path().join(".contextignore"), "!readme.md\n").unwrap();
+    let mut rules = IgnoreRules::new();
+    rules.load_dir(dir.path(), dir.path()).unwrap();
+    assert!(rules.is_ignored("notes.md", false));
+    assert!(!rules.is_ignored("readme.md", false));
+
+    // Reloading replaces the directory's rules
+    fs::remove_file(dir.path().join(".gitignore")).unwrap();
+    rules.load_dir(dir.path(), dir.path()).unwrap();
+    assert!(!rules.is_ignored("notes.md", false));
+}
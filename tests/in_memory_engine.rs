@@ -0,0 +1,60 @@
+// The score-and-budget path using only APIs available without the `fs`
+// and `clock` features (the wasm32 configuration).
+
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "beta")]
+}
+
+#[test]
+fn memory_cache_selection() {
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build_in_memory(docs())
+        .unwrap();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 100)
+        .unwrap();
+    assert_eq!(result.documents[0].id, "a.md");
+}
+
+#[test]
+fn archive_bytes_round_trip_through_memory_store() {
+    let source = Arc::new(MemoryStore::new());
+    let built = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(source)
+        .build(docs(), Path::new("/built"))
+        .unwrap();
+    let bytes = built.to_archive_bytes().unwrap();
+
+    let target = Arc::new(MemoryStore::new());
+    let imported = ContextCache::import_archive_bytes_in(target, &bytes, "/imported").unwrap();
+    assert_eq!(imported.manifest.cache_version, built.manifest.cache_version);
+
+    let snapshot = imported.load_snapshot().unwrap();
+    let result = ContextSelector::default().select_snapshot(&snapshot, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.documents[0].id, "a.md");
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::metadata::MetadataValue;
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str, title: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::new();
+    metadata.insert_string("title", title);
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy the service with the deploy tool", "Deploying"),
+        make_doc("b.md", "rollback the deploy when health checks fail", "Rollbacks"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn metadata_is_copied_when_requested() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        include_metadata: true,
+        ..Default::default()
+    };
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 100, &options)
+        .unwrap();
+
+    assert_eq!(result.documents.len(), 2);
+    for doc in &result.documents {
+        let expected = if doc.id == "a.md" { "Deploying" } else { "Rollbacks" };
+        let metadata = doc.metadata.as_ref().expect("metadata requested");
+        assert_eq!(metadata.get("title"), Some(&MetadataValue::String(expected.to_string())));
+    }
+
+    // Survives a serialization round trip
+    let json = serde_json::to_string(&result).unwrap();
+    assert!(json.contains(r#""title":"Deploying""#));
+    let back: context_core::types::SelectionResult = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.documents[0].metadata, result.documents[0].metadata);
+}
+
+#[test]
+fn metadata_is_absent_by_default() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert!(result.documents.iter().all(|d| d.metadata.is_none()));
+    assert!(!serde_json::to_string(&result).unwrap().contains("metadata"));
+}
@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::metadata::MetadataValue;
+use context_core::document::parser::{markdown_links, LINKS_KEY};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetConfig, BudgetStrategy, ContextSelector, SelectionOptions};
+use context_core::types::{BudgetVerdict, Query, SelectionReason};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::default();
+    let links = markdown_links(id.as_str(), content);
+    if !links.is_empty() {
+        metadata.insert(LINKS_KEY, MetadataValue::List(links.into_iter().map(MetadataValue::String).collect()));
+    }
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+#[test]
+fn links_resolve_relative_to_the_document() {
+    let content = "\
+See the [glossary](../glossary.md#terms) and [setup](./Setup%20Guide.md?plain).
+![diagram](img/arch.md) [site](https://example.com/a.md) [mail](mailto:a@b.c)
+[top](#intro) [self](deploy.md) [root](/README.md) [escape](../../../out.md)
+
+```
+[fenced](fenced.md)
+```
+
+[ref]: <api/index.md> \"API\"
+[^1]: footnote.md
+    [indented]: code.md
+";
+    assert_eq!(
+        markdown_links("docs/deploy.md", content),
+        vec!["glossary.md", "docs/Setup Guide.md", "README.md", "docs/api/index.md"]
+    );
+    // Duplicates keep their first position
+    assert_eq!(markdown_links("a.md", "[x](b.md) [y](c.md) [z](b.md#end)"), vec!["b.md", "c.md"]);
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("deploy.md", "deploy deploy deploy, see the [glossary](Glossary.md) and [missing](missing.md)"),
+        make_doc("glossary.md", "canary: a partial rollout"),
+        make_doc("other.md", "deploy once"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn linked_documents_fill_remaining_budget() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        include_linked: true,
+        exclude_zero_score: true,
+        ..Default::default()
+    };
+
+    // The glossary scores 0, and is only selected as linked
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 1000, &options)
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|doc| doc.id.as_str()).collect();
+    assert_eq!(ids, vec!["other.md", "deploy.md", "glossary.md"]);
+
+    let glossary = &result.documents[2];
+    assert_eq!(glossary.why.reason, Some(SelectionReason::Linked));
+    assert_eq!(glossary.why.linked_from.as_deref(), Some("deploy.md"));
+    assert_eq!(glossary.score, 0.0);
+    assert_eq!(result.documents[1].why.reason, None);
+    assert_eq!(result.selection.documents_linked, Some(1));
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["documents"][2]["why"]["reason"], "linked");
+    assert!(json["documents"][0]["why"].get("reason").is_none());
+
+    // Off by default: no linked documents and no count
+    let options = SelectionOptions {
+        exclude_zero_score: true,
+        ..Default::default()
+    };
+    let plain = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 1000, &options)
+        .unwrap();
+    assert_eq!(plain.documents.len(), 2);
+    assert_eq!(plain.selection.documents_linked, None);
+}
+
+#[test]
+fn linked_documents_respect_budget() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        include_linked: true,
+        exclude_zero_score: true,
+        ..Default::default()
+    };
+
+    // other.md (3 tokens) and deploy.md (20) by rank; the glossary needs 7
+    let selector = ContextSelector::default();
+    let result = selector.select_with(&cache, Query::new("deploy"), 29, &options).unwrap();
+    assert_eq!(result.documents.len(), 2);
+    assert_eq!(result.selection.documents_linked, Some(0));
+
+    let result = selector.select_with(&cache, Query::new("deploy"), 30, &options).unwrap();
+    assert_eq!(result.documents.len(), 3);
+    assert_eq!(result.selection.tokens_used, 30);
+}
+
+#[test]
+fn ranked_document_excluded_by_budget_can_be_linked() {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("deploy.md", "deploy deploy deploy, see the [glossary](Glossary.md) and [missing](missing.md)"),
+        make_doc("glossary.md", "canary: a partial rollout, one deploy step at a time"),
+        make_doc("big.md", &format!("{}{}", "deploy ".repeat(5), "filler ".repeat(20))),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    let options = SelectionOptions {
+        include_linked: true,
+        trace: true,
+        ..Default::default()
+    };
+
+    // Budgeting stops at big.md, ahead of the glossary
+    let budget = BudgetConfig::new(40).strategy(BudgetStrategy::StopAtFirstExclusion);
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), budget, &options)
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|doc| doc.id.as_str()).collect();
+    assert_eq!(ids, vec!["deploy.md", "glossary.md"]);
+    assert!(result.documents[1].score > 0.0);
+    assert_eq!(result.documents[1].why.reason, Some(SelectionReason::Linked));
+
+    let trace = result.selection.trace.as_ref().unwrap();
+    let verdicts: Vec<(&str, BudgetVerdict)> = trace.budget.iter().map(|b| (b.id.as_str(), b.verdict)).collect();
+    assert_eq!(
+        verdicts,
+        vec![
+            ("deploy.md", BudgetVerdict::Selected),
+            ("big.md", BudgetVerdict::ExcludedByBudget),
+            ("glossary.md", BudgetVerdict::Linked),
+        ]
+    );
+    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+}
@@ -0,0 +1,33 @@
+use context_core::document::metadata::MetadataValue;
+use context_core::document::parser::{markdown_metadata, READING_WORDS_PER_MINUTE};
+
+fn strings(values: &[&str]) -> MetadataValue {
+    MetadataValue::List(values.iter().map(|v| MetadataValue::String(v.to_string())).collect())
+}
+
+#[test]
+fn records_title_headings_and_counts() {
+    let content = "Intro line.\n\n# Deploy Guide\nRun it.\n\n```\n# not a heading\n```\n\n## Rollback\nUndo.\n\n# Appendix\n";
+    let metadata = markdown_metadata(content);
+
+    // The first level-1 heading, not the first heading
+    assert_eq!(metadata.get("title"), Some(&MetadataValue::String("Deploy Guide".to_string())));
+    assert_eq!(metadata.get("headings"), Some(&strings(&["# Deploy Guide", "## Rollback", "# Appendix"])));
+    assert_eq!(metadata.get("word_count"), Some(&MetadataValue::Number(18)));
+    assert_eq!(metadata.get("reading_time_minutes"), Some(&MetadataValue::Number(1)));
+}
+
+#[test]
+fn no_h1_means_no_title() {
+    let metadata = markdown_metadata("## Only a section\ntext");
+    assert_eq!(metadata.get("title"), None);
+    assert_eq!(metadata.get("headings"), Some(&strings(&["## Only a section"])));
+}
+
+#[test]
+fn reading_time_rounds_up() {
+    let words = "word ".repeat(READING_WORDS_PER_MINUTE + 1);
+    let metadata = markdown_metadata(&words);
+    assert_eq!(metadata.get("reading_time_minutes"), Some(&MetadataValue::Number(2)));
+    assert_eq!(markdown_metadata("").get("reading_time_minutes"), Some(&MetadataValue::Number(0)));
+}
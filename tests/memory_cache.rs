@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder, HashingEmbedder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("b.md", "beta deploy"),
+        make_doc("a.md", "alpha deploy deploy"),
+        make_doc("c.md", "gamma"),
+    ]
+}
+
+#[test]
+fn memory_cache_matches_disk_versioning_and_selection() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+
+    let disk = builder.build(docs(), &dir.path().join("cache")).unwrap();
+    let memory = builder.build_in_memory(docs()).unwrap();
+
+    assert_eq!(memory.manifest().cache_version, disk.manifest.cache_version);
+    assert_eq!(memory.manifest().document_count, 3);
+    assert_eq!(memory.index().len(), 3);
+    assert_eq!(memory.index().get(&make_id("a.md")), Some(disk.manifest.documents[0].file.as_str()));
+
+    let ids: Vec<&str> = memory.documents().iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md", "c.md"]);
+    assert_eq!(memory.get(&make_id("c.md")).unwrap().content, "gamma");
+
+    let selector = ContextSelector::default();
+    let from_disk = selector.select(&disk, Query::new("deploy"), 100).unwrap();
+    let from_memory = selector.select(&memory, Query::new("deploy"), 100).unwrap();
+    assert_eq!(
+        serde_json::to_string(&from_disk).unwrap(),
+        serde_json::to_string(&from_memory).unwrap()
+    );
+}
+
+#[test]
+fn memory_cache_applies_build_validation() {
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+    let result = builder.build_in_memory(vec![make_doc("a.md", "x"), make_doc("a.md", "y")]);
+    match result {
+        Err(CacheBuildError::DuplicateDocumentId { id, .. }) => assert_eq!(id, "a.md"),
+        other => panic!("expected duplicate id error, got {other:?}"),
+    }
+}
+
+#[test]
+fn memory_cache_keeps_vectors() {
+    let memory = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build_in_memory(docs())
+        .unwrap();
+    assert_eq!(memory.vectors().unwrap().vectors.len(), 3);
+}
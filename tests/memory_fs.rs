@@ -0,0 +1,86 @@
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::walk::SourceEntry;
+use context_core::document::{
+    ingest_tree, BinaryPolicy, IngestSource, MemoryFs, Rejection, Source, WalkError, WalkOptions,
+};
+
+fn tree() -> MemoryFs {
+    MemoryFs::new()
+        .with_root("/repo")
+        .with_file("README.md", "# Readme\nSee the [guide](docs/guide.md).\n")
+        .with_file("docs/guide.md", "# Guide\ndeploy steps\n")
+        .with_file("docs/api/index.md", "api")
+        .with_file("build/out.txt", "generated")
+        .with_file(".gitignore", "build/\n")
+        .with_file(".git/HEAD", "ref")
+        .with_file("logo.png", b"\x89PNG\r\n\x1a\n\0\0".to_vec())
+}
+
+#[test]
+fn memory_fs_lists_implied_directories() {
+    let fs = tree();
+    let names = |dir: &str| -> Vec<SourceEntry> { fs.read_dir(dir).unwrap() };
+    assert_eq!(
+        names("docs"),
+        vec![
+            SourceEntry { name: "api".into(), is_dir: true },
+            SourceEntry { name: "guide.md".into(), is_dir: false },
+        ]
+    );
+    assert_eq!(fs.read("/docs/guide.md/").unwrap(), b"# Guide\ndeploy steps\n");
+    assert_eq!(fs.read_dir("missing").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(fs.source("docs/guide.md"), Source::local_path("/repo", "docs/guide.md"));
+}
+
+#[test]
+fn tree_ingestion_is_hermetic() {
+    let ingested = ingest_tree(&tree(), &WalkOptions::default()).unwrap();
+    let ids: Vec<&str> = ingested.documents.iter().map(|doc| doc.id.as_str()).collect();
+    // Hidden entries and what .gitignore excludes are skipped
+    assert_eq!(ids, vec!["README.md", "docs/api/index.md", "docs/guide.md"]);
+    assert_eq!(ingested.documents[0].metadata.get("title").and_then(|v| v.as_str()), Some("Readme"));
+    assert_eq!(ingested.report.documents, 3);
+    assert_eq!(ingested.report.skipped[0].id.as_str(), "logo.png");
+    assert_eq!(ingested.report.skipped[0].reason, Rejection::InvalidUtf8);
+
+    // Builds like any other documents
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build_in_memory(ingested.documents)
+        .unwrap();
+    let ids: Vec<&str> = cache.manifest().documents.iter().map(|entry| entry.id.as_str()).collect();
+    assert_eq!(ids, vec!["docs/api/index.md", "docs/guide.md", "readme.md"]);
+
+    let options = WalkOptions {
+        ignore_files: false,
+        binary_policy: BinaryPolicy::Error,
+        ..WalkOptions::default()
+    };
+    assert!(matches!(
+        ingest_tree(&tree(), &options),
+        Err(WalkError::Rejected { id, .. }) if id.as_str() == "logo.png"
+    ));
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn memory_fs_matches_local_fs() {
+    use context_core::document::LocalFs;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("docs/api")).unwrap();
+    std::fs::write(root.join("README.md"), "# Readme\nSee the [guide](docs/guide.md).\n").unwrap();
+    std::fs::write(root.join("docs/guide.md"), "# Guide\ndeploy steps\n").unwrap();
+    std::fs::write(root.join("docs/api/index.md"), "api").unwrap();
+
+    let memory = MemoryFs::new()
+        .with_root(root.to_string_lossy())
+        .with_file("README.md", "# Readme\nSee the [guide](docs/guide.md).\n")
+        .with_file("docs/guide.md", "# Guide\ndeploy steps\n")
+        .with_file("docs/api/index.md", "api");
+    let options = WalkOptions::default();
+    assert_eq!(
+        ingest_tree(&memory, &options).unwrap(),
+        ingest_tree(&LocalFs::new(root), &options).unwrap()
+    );
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::metadata::MetadataValue;
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str, metadata: Metadata) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn strings(values: &[&str]) -> MetadataValue {
+    MetadataValue::List(values.iter().map(|v| MetadataValue::String(v.to_string())).collect())
+}
+
+fn rich_metadata() -> Metadata {
+    let mut metadata = Metadata::new();
+    metadata.insert_string("title", "Deploy guide");
+    metadata.insert_number("revision", 3);
+    metadata.insert_bool("draft", false);
+    metadata.insert_float("score", 0.1);
+    metadata.insert("tags", strings(&["ops", "runbook"]));
+    let mut owner = BTreeMap::new();
+    owner.insert("team".to_string(), MetadataValue::String("infra".to_string()));
+    metadata.insert("owner", MetadataValue::Map(owner));
+    metadata
+}
+
+#[test]
+fn values_round_trip_through_json() {
+    let metadata = rich_metadata();
+    let json = serde_json::to_string(&metadata).unwrap();
+    assert_eq!(
+        json,
+        r#"{"draft":false,"owner":{"team":"infra"},"revision":3,"score":0.1,"tags":["ops","runbook"],"title":"Deploy guide"}"#
+    );
+    let back: Metadata = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, metadata);
+}
+
+#[test]
+fn integers_and_floats_stay_distinct() {
+    let back: Metadata = serde_json::from_str(r#"{"a":1,"b":1.0,"c":-0.0}"#).unwrap();
+    assert_eq!(back.get("a"), Some(&MetadataValue::Number(1)));
+    assert_eq!(back.get("b"), Some(&MetadataValue::Float(1.0)));
+    // -0.0 is written, and compares equal, as 0.0
+    assert_eq!(back.get("c"), Some(&MetadataValue::Float(0.0)));
+    assert_eq!(serde_json::to_string(&back).unwrap(), r#"{"a":1,"b":1.0,"c":0.0}"#);
+}
+
+#[test]
+fn non_finite_floats_do_not_serialize() {
+    let mut metadata = Metadata::new();
+    metadata.insert_float("x", f64::NAN);
+    assert!(serde_json::to_string(&metadata).is_err());
+}
+
+#[test]
+fn legacy_metadata_reads_unchanged() {
+    let back: Metadata = serde_json::from_str(r#"{"title":"Old","revision":7}"#).unwrap();
+    assert_eq!(back.get("title"), Some(&MetadataValue::String("Old".to_string())));
+    assert_eq!(back.get("revision"), Some(&MetadataValue::Number(7)));
+}
+
+#[test]
+fn merge_recurses_into_maps_and_replaces_lists() {
+    let mut base = rich_metadata();
+    let mut other = Metadata::new();
+    let mut owner = BTreeMap::new();
+    owner.insert("oncall".to_string(), MetadataValue::String("alice".to_string()));
+    other.insert("owner", MetadataValue::Map(owner));
+    other.insert("tags", strings(&["archive"]));
+    base.merge(other);
+
+    let Some(MetadataValue::Map(owner)) = base.get("owner") else {
+        panic!("owner is not a map");
+    };
+    let keys: Vec<&str> = owner.keys().map(String::as_str).collect();
+    assert_eq!(keys, vec!["oncall", "team"]);
+    assert_eq!(base.get("tags"), Some(&strings(&["archive"])));
+}
+
+#[test]
+fn tag_clauses_match_tag_lists() {
+    let doc = make_doc("a.md", "text", rich_metadata());
+    assert!(Query::new("tag:runbook").matches_fields(&doc));
+    assert!(!Query::new("tag:archive").matches_fields(&doc));
+}
+
+#[test]
+fn rich_metadata_survives_a_cache_build() {
+    let dir = tempdir().unwrap();
+    let docs = vec![make_doc("a.md", "text", rich_metadata())];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    assert_eq!(cache.load_documents().unwrap()[0].metadata, rich_metadata());
+}
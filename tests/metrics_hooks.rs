@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::metrics::{self, Metrics};
+use context_core::selection::{BudgetConfig, ContextSelector, PathFilter, SelectionOptions};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+/// Every call, in order.
+#[derive(Default)]
+struct Recorder {
+    counters: Mutex<Vec<(&'static str, u64)>>,
+    histograms: Mutex<Vec<(&'static str, f64)>>,
+}
+
+impl Metrics for Recorder {
+    fn counter(&self, name: &'static str, value: u64) {
+        self.counters.lock().unwrap().push((name, value));
+    }
+
+    fn histogram(&self, name: &'static str, value: f64) {
+        self.histograms.lock().unwrap().push((name, value));
+    }
+}
+
+impl Recorder {
+    fn counters(&self) -> Vec<(&'static str, u64)> {
+        std::mem::take(&mut *self.counters.lock().unwrap())
+    }
+
+    fn histograms(&self) -> Vec<(&'static str, f64)> {
+        std::mem::take(&mut *self.histograms.lock().unwrap())
+    }
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("docs/a.md", "deploy alpha"),
+        make_doc("docs/b.md", "deploy beta"),
+        make_doc("src/c.rs", "deploy gamma"),
+    ]
+}
+
+#[test]
+fn selections_report_scoring_and_budget() {
+    let recorder = Arc::new(Recorder::default());
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+    let selector = ContextSelector::default().with_metrics(Arc::clone(&recorder));
+
+    let options = SelectionOptions {
+        path_filter: Some(PathFilter::new().include("docs/**")),
+        ..SelectionOptions::default()
+    };
+    let result = selector.select_with(&cache, Query::new("deploy"), 10, &options).unwrap();
+    assert_eq!(
+        recorder.counters(),
+        vec![(metrics::DOCUMENTS_SCORED, 2), (metrics::TOKENS_SELECTED, result.selection.tokens_used)]
+    );
+    let histograms = recorder.histograms();
+    let names: Vec<&str> = histograms.iter().map(|(name, _)| *name).collect();
+    if cfg!(feature = "clock") {
+        assert_eq!(names, vec![metrics::CACHE_LOAD_MS, metrics::BUDGET_UTILIZATION]);
+        assert!(histograms[0].1 >= 0.0);
+    } else {
+        assert_eq!(names, vec![metrics::BUDGET_UTILIZATION]);
+    }
+    let utilization = histograms.last().unwrap().1;
+    assert_eq!(utilization, result.selection.tokens_used as f64 / 10.0);
+
+    // Snapshots skip loading; a fully reserved budget has no utilization
+    let snapshot = cache.load_snapshot().unwrap();
+    selector
+        .select_snapshot(&snapshot, Query::new("deploy"), BudgetConfig::new(10).reserve(10))
+        .unwrap();
+    assert_eq!(recorder.counters(), vec![(metrics::DOCUMENTS_SCORED, 3), (metrics::TOKENS_SELECTED, 0)]);
+    assert!(recorder.histograms().is_empty());
+
+    // Multi-query selections report once
+    selector
+        .select_multi(&cache, &[Query::new("alpha"), Query::new("beta")], 100)
+        .unwrap();
+    assert_eq!(recorder.counters().len(), 2);
+}
+
+#[test]
+fn builds_report_documents_and_duration() {
+    let recorder = Arc::new(Recorder::default());
+    let builder = CacheBuilder::new(CacheBuildConfig::v0()).with_metrics(Arc::clone(&recorder));
+
+    builder.build_in_memory(docs()).unwrap();
+    let dir = tempdir().unwrap();
+    builder.build(docs(), &dir.path().join("cache")).unwrap();
+    let streamed = docs().into_iter().map(Ok::<_, std::io::Error>);
+    builder.build_from_iter(streamed, &dir.path().join("streamed")).unwrap();
+    assert_eq!(recorder.counters(), vec![(metrics::DOCUMENTS_BUILT, 3); 3]);
+    let builds = recorder.histograms();
+    assert_eq!(builds.len(), if cfg!(feature = "clock") { 3 } else { 0 });
+    assert!(builds.iter().all(|(name, ms)| *name == metrics::CACHE_BUILD_MS && *ms >= 0.0));
+
+    // Failed builds report nothing
+    assert!(builder.build(docs(), &dir.path().join("cache")).is_err());
+    assert!(recorder.counters().is_empty());
+}
+
+#[test]
+fn default_methods_do_nothing() {
+    struct Silent;
+    impl Metrics for Silent {}
+
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_metrics(Silent)
+        .build_in_memory(docs())
+        .unwrap();
+    let result = ContextSelector::default()
+        .with_metrics(Silent)
+        .select(&cache, Query::new("deploy"), 100)
+        .unwrap();
+    assert_eq!(result.documents.len(), 3);
+}
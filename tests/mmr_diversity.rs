@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, HashingEmbedder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, Mmr, Similarity};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+// a.md and b.md are near-duplicates; c.md is relevant but different.
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(
+            vec![
+                make_doc("a.md", "deploy deploy service guide"),
+                make_doc("b.md", "deploy deploy service guide v2"),
+                make_doc("c.md", "deploy rollback runbook notes"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    (dir, cache)
+}
+
+fn ids(result: &context_core::types::SelectionResult) -> Vec<&str> {
+    result.documents.iter().map(|d| d.id.as_str()).collect()
+}
+
+#[test]
+fn without_mmr_near_duplicates_lead() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "b.md", "c.md"]);
+    assert!(result.selection.mmr.is_none());
+}
+
+#[test]
+fn mmr_term_overlap_promotes_diverse_document() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default().with_mmr(Mmr::new(0.5, Similarity::TermOverlap));
+
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "c.md", "b.md"]);
+
+    let mmr = result.selection.mmr.as_ref().unwrap();
+    assert_eq!(mmr.lambda, 0.5);
+    assert_eq!(mmr.similarity, "term_overlap");
+
+    let again = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(
+        serde_json::to_string(&result).unwrap(),
+        serde_json::to_string(&again).unwrap()
+    );
+}
+
+#[test]
+fn mmr_lambda_one_keeps_relevance_order() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default().with_mmr(Mmr::new(1.0, Similarity::TermOverlap));
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "b.md", "c.md"]);
+}
+
+#[test]
+fn mmr_with_cached_vectors() {
+    let (_dir, cache) = build_cache();
+    let vectors = cache.load_vectors().unwrap().unwrap();
+    let selector = ContextSelector::default().with_mmr(Mmr::new(0.5, Similarity::Vectors(vectors)));
+
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "c.md", "b.md"]);
+    assert_eq!(result.selection.mmr.as_ref().unwrap().similarity, "vectors");
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "msgpack")]
+
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, DocumentEncoding};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::{from_msgpack, to_msgpack, Query, SelectionResult};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::new();
+    metadata.insert_string("title", "Deploy");
+    metadata.insert_number("priority", 3);
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("a.md", "deploy the service"),
+        make_doc("b.md", "rollback the deploy"),
+    ]
+}
+
+fn msgpack_config() -> CacheBuildConfig {
+    CacheBuildConfig::v0().with_document_encoding(DocumentEncoding::Msgpack)
+}
+
+#[test]
+fn selection_result_round_trips() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+
+    let bytes = to_msgpack(&result).unwrap();
+    assert_eq!(bytes, to_msgpack(&result).unwrap());
+    assert!(bytes.len() < serde_json::to_vec(&result).unwrap().len());
+
+    let back: SelectionResult = from_msgpack(&bytes).unwrap();
+    assert_eq!(serde_json::to_string(&back).unwrap(), serde_json::to_string(&result).unwrap());
+}
+
+#[test]
+fn msgpack_caches_select_like_json_caches() {
+    let dir = tempdir().unwrap();
+    let json = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("json"))
+        .unwrap();
+    let path = dir.path().join("msgpack");
+    let msgpack = CacheBuilder::new(msgpack_config()).build(docs(), &path).unwrap();
+
+    assert_eq!(msgpack.manifest.build_config.document_encoding, DocumentEncoding::Msgpack);
+    assert_ne!(msgpack.manifest.cache_version, json.manifest.cache_version);
+    assert!(msgpack.manifest.documents.iter().all(|e| e.file.ends_with(".msgpack")));
+    let manifest = fs::read_to_string(path.join("manifest.json")).unwrap();
+    assert!(manifest.contains(r#""document_encoding": "msgpack""#));
+
+    let reopened = ContextCache::open(&path).unwrap();
+    reopened.verify().unwrap();
+    assert_eq!(reopened.load_documents().unwrap(), json.load_documents().unwrap());
+
+    let selector = ContextSelector::default();
+    let a = selector.select(&json, Query::new("deploy"), 100).unwrap();
+    let b = selector.select(&reopened, Query::new("deploy"), 100).unwrap();
+    assert_eq!(serde_json::to_string(&a.documents).unwrap(), serde_json::to_string(&b.documents).unwrap());
+}
+
+#[test]
+fn msgpack_builds_are_byte_identical() {
+    let dir = tempdir().unwrap();
+    let a = CacheBuilder::new(msgpack_config())
+        .build(docs(), &dir.path().join("a"))
+        .unwrap();
+    let b = CacheBuilder::new(msgpack_config())
+        .build(docs(), &dir.path().join("b"))
+        .unwrap();
+    assert_eq!(a.manifest.tree_hash, b.manifest.tree_hash);
+    for entry in &a.manifest.documents {
+        assert_eq!(
+            fs::read(dir.path().join("a").join(&entry.file)).unwrap(),
+            fs::read(dir.path().join("b").join(&entry.file)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn json_remains_the_default_encoding() {
+    let config = CacheBuildConfig::v0();
+    assert!(config.document_encoding.is_json());
+    assert!(!serde_json::to_string(&config).unwrap().contains("document_encoding"));
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use context_core::cache::{
+    CacheBuildConfig, CacheBuilder, ContextCache, DocumentLoader, MultiCache, MultiCacheError,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::{DocumentIdError, Query};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build(dir: &Path, name: &str, docs: Vec<Document>) -> ContextCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.join(name))
+        .unwrap()
+}
+
+#[test]
+fn multi_cache_namespaces_ids_and_selects() {
+    let dir = tempdir().unwrap();
+    let api = build(dir.path(), "api", vec![make_doc("readme.md", "api deploy notes")]);
+    let web = build(dir.path(), "web", vec![make_doc("readme.md", "web deploy deploy")]);
+
+    let multi = MultiCache::new(vec![("web".to_string(), web), ("api".to_string(), api)]).unwrap();
+    assert_eq!(multi.namespaces().collect::<Vec<_>>(), vec!["api", "web"]);
+
+    let docs = multi.load_documents().unwrap();
+    let ids: Vec<&str> = docs.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["api/readme.md", "web/readme.md"]);
+
+    let result = ContextSelector::default()
+        .select(&multi, Query::new("deploy"), 1000)
+        .unwrap();
+    assert_eq!(result.documents[0].id, "web/readme.md");
+    assert_eq!(result.documents[1].id, "api/readme.md");
+    assert_eq!(result.selection.documents_considered, 2);
+}
+
+#[test]
+fn multi_cache_version_is_stable_and_order_independent() {
+    let dir = tempdir().unwrap();
+    let a1 = build(dir.path(), "a1", vec![make_doc("x.md", "alpha")]);
+    let b1 = build(dir.path(), "b1", vec![make_doc("y.md", "beta")]);
+    let a2 = build(dir.path(), "a2", vec![make_doc("x.md", "alpha")]);
+    let b2 = build(dir.path(), "b2", vec![make_doc("y.md", "beta")]);
+
+    let m1 = MultiCache::new(vec![("a".to_string(), a1), ("b".to_string(), b1)]).unwrap();
+    let m2 = MultiCache::new(vec![("b".to_string(), b2), ("a".to_string(), a2)]).unwrap();
+    assert_eq!(m1.cache_version(), m2.cache_version());
+}
+
+#[test]
+fn multi_cache_rejects_bad_namespaces() {
+    let dir = tempdir().unwrap();
+    let a = build(dir.path(), "a", vec![make_doc("x.md", "alpha")]);
+    let b = build(dir.path(), "b", vec![make_doc("x.md", "alpha")]);
+    let c = build(dir.path(), "c", vec![make_doc("x.md", "alpha")]);
+
+    match MultiCache::new(vec![("Pkg".to_string(), a), ("pkg".to_string(), b)]) {
+        Err(MultiCacheError::DuplicateNamespace(ns)) => assert_eq!(ns, "pkg"),
+        other => panic!("expected duplicate namespace, got {other:?}"),
+    }
+    match MultiCache::new(vec![("a/b".to_string(), c)]) {
+        Err(MultiCacheError::InvalidNamespace(_)) => {}
+        other => panic!("expected invalid namespace, got {other:?}"),
+    }
+}
+
+#[test]
+fn namespaced_ids_keep_roots_apart() {
+    let id = |namespace: &str, root: &str, source: &str| {
+        DocumentId::from_path_namespaced(namespace, Path::new(root), &Path::new(root).join(source))
+    };
+    let api = id("API", "/repos/api", "README.md").unwrap();
+    let web = id("web", "/repos/web", "README.md").unwrap();
+    assert_eq!(api.as_str(), "api/readme.md");
+    assert_eq!(web.as_str(), "web/readme.md");
+
+    // The same ids MultiCache gives documents of caches mounted there
+    let dir = tempdir().unwrap();
+    let multi = MultiCache::new(vec![
+        ("web".to_string(), build(dir.path(), "web", vec![make_doc("README.md", "web")])),
+        ("api".to_string(), build(dir.path(), "api", vec![make_doc("README.md", "api")])),
+    ])
+    .unwrap();
+    let ids: Vec<DocumentId> = multi.load_documents().unwrap().into_iter().map(|doc| doc.id).collect();
+    assert_eq!(ids, vec![api, web]);
+
+    assert!(matches!(
+        id("a/b", "/repos/api", "README.md"),
+        Err(DocumentIdError::InvalidNamespace(_))
+    ));
+    assert!(matches!(id("", "/repos/api", "README.md"), Err(DocumentIdError::InvalidNamespace(_))));
+}
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, PathFilter, SelectionOptions};
+use context_core::types::{Query, QueryFusion};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("deploy.md", "deploy deploy rollout"),
+        make_doc("rollback.md", "rollback steps"),
+        make_doc("both.md", "deploy rollback plan notes"),
+        make_doc("none.md", "unrelated text"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn queries() -> Vec<Query> {
+    vec![Query::new("deploy"), Query::new("rollback")]
+}
+
+#[test]
+fn max_fusion_keeps_best_score_per_document() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default().select_multi(&cache, &queries(), 1000).unwrap();
+
+    let ranked: Vec<(&str, f32)> = result.documents.iter().map(|d| (d.id.as_str(), d.score)).collect();
+    assert_eq!(
+        ranked,
+        vec![("deploy.md", 2.0 / 3.0), ("rollback.md", 0.5), ("both.md", 0.25), ("none.md", 0.0)]
+    );
+    // Each document is selected once, explained by its contributing query
+    assert_eq!(result.documents[1].why.query_terms, vec!["rollback"]);
+    assert_eq!(result.documents[2].why.query_terms, vec!["deploy"]);
+
+    assert_eq!(result.selection.query, "deploy | rollback");
+    assert_eq!(result.selection.query_fusion, Some(QueryFusion::Max));
+    let contributions: Vec<(usize, usize, usize)> = result
+        .selection
+        .queries
+        .iter()
+        .map(|q| (q.documents_matched, q.documents_selected, q.documents_contributed))
+        .collect();
+    assert_eq!(contributions, vec![(2, 2, 3), (2, 2, 1)]);
+}
+
+#[test]
+fn reciprocal_rank_fusion_rewards_agreement() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .with_query_fusion(QueryFusion::ReciprocalRank { k: 60 })
+        .select_multi(&cache, &queries(), 1000)
+        .unwrap();
+
+    let ranked: Vec<(&str, f32)> = result.documents.iter().map(|d| (d.id.as_str(), d.score)).collect();
+    assert_eq!(
+        ranked,
+        vec![("both.md", 0.983871), ("deploy.md", 0.5), ("rollback.md", 0.5), ("none.md", 0.0)]
+    );
+
+    let json = serde_json::to_value(&result.selection).unwrap();
+    assert_eq!(json["query_fusion"], serde_json::json!({"reciprocal_rank": {"k": 60}}));
+    assert_eq!(json["queries"][1]["query"], "rollback");
+}
+
+#[test]
+fn budgets_once_across_queries() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let result = selector.select_multi(&cache, &queries(), 10).unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["deploy.md", "rollback.md"]);
+    assert_eq!(result.selection.tokens_used, 10);
+    assert_eq!(result.selection.documents_excluded_by_budget, 2);
+
+    let again = selector.select_multi(&cache, &queries(), 10).unwrap();
+    assert_eq!(serde_json::to_string(&result).unwrap(), serde_json::to_string(&again).unwrap());
+}
+
+#[test]
+fn single_query_matches_select() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let options = SelectionOptions {
+        path_filter: Some(PathFilter::new().include("*.md").exclude("none.md")),
+        ..Default::default()
+    };
+    let single = selector.select_with(&cache, Query::new("deploy"), 10, &options).unwrap();
+    let multi = selector
+        .select_multi_with(&cache, &[Query::new("deploy")], 10, &options)
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&single.documents).unwrap(),
+        serde_json::to_string(&multi.documents).unwrap()
+    );
+    assert_eq!(multi.selection.documents_excluded_by_filter, Some(1));
+    assert!(!serde_json::to_string(&single).unwrap().contains("query_fusion"));
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy the service with the deploy tool"),
+        make_doc("b.md", "rollback the deploy when health checks fail"),
+        make_doc("c.md", "unrelated notes"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn omitting_content_keeps_everything_else() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let full = selector.select(&cache, Query::new("deploy"), 15).unwrap();
+    let options = SelectionOptions {
+        omit_content: true,
+        ..Default::default()
+    };
+    let summary = selector.select_with(&cache, Query::new("deploy"), 15, &options).unwrap();
+
+    assert!(summary.documents.iter().all(|d| d.content.is_empty()));
+    assert!(summary.selection.content_omitted);
+    assert!(!full.selection.content_omitted);
+
+    let strip = |result: &context_core::types::SelectionResult| {
+        let mut json = serde_json::to_value(result).unwrap();
+        for doc in json["documents"].as_array_mut().unwrap() {
+            doc["content"] = serde_json::Value::Null;
+        }
+        json["selection"].as_object_mut().unwrap().remove("content_omitted");
+        json
+    };
+    assert_eq!(strip(&full), strip(&summary));
+    assert!(serde_json::to_string(&summary).unwrap().len() < serde_json::to_string(&full).unwrap().len());
+}
+
+#[test]
+fn content_is_kept_by_default() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert!(result.documents.iter().all(|d| !d.content.is_empty()));
+    assert!(!serde_json::to_string(&result).unwrap().contains("content_omitted"));
+    assert_eq!(serde_json::to_string(&SelectionOptions::default()).unwrap(), r#"{"limit":"unlimited"}"#);
+}
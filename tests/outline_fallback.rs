@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::compression::TruncationStrategy;
+use context_core::document::parser::{is_markdown_path, markdown_outline, markdown_sections, split_sentences};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::{BudgetVerdict, Query, Representation};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn guide() -> String {
+    format!(
+        "Preamble.\n\n# Deploy\nRun the deploy script. Then wait.\n\n```\n# not a heading\n```\n\n## Rollback\n\nUse the previous\ntag. It is kept.\n\n{}\n",
+        "filler ".repeat(40)
+    )
+}
+
+#[test]
+fn sections_skip_fenced_code() {
+    let guide = guide();
+    let sections = markdown_sections(&guide);
+    let headings: Vec<(usize, &str)> = sections.iter().map(|s| (s.level, s.title)).collect();
+    assert_eq!(headings, vec![(1, "Deploy"), (2, "Rollback")]);
+    assert!(sections[0].body.contains("# not a heading"));
+
+    // Not headings: no space after the hashes, too deep, or indented code
+    assert!(markdown_sections("#tag\n####### deep\n    # code\n").is_empty());
+    assert_eq!(markdown_sections("## Closed ##\n")[0].title, "Closed");
+}
+
+#[test]
+fn outline_has_headings_and_first_sentences() {
+    // The first paragraph's lines are joined before splitting sentences
+    assert_eq!(
+        markdown_outline(&guide()),
+        vec!["# Deploy\nRun the deploy script.", "## Rollback\nUse the previous tag."]
+    );
+    assert_eq!(markdown_outline("# Empty\n\n```\ncode.\n```\n"), vec!["# Empty"]);
+    assert_eq!(split_sentences("One. Two!\nThree"), vec!["One.", "Two!", "Three"]);
+}
+
+#[test]
+fn markdown_paths() {
+    assert!(is_markdown_path("docs/a.md"));
+    assert!(is_markdown_path("README.MARKDOWN"));
+    assert!(!is_markdown_path("notes.txt"));
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let guide = guide();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy"), // 5 tokens, score 1.0
+        make_doc("b.md", &guide),
+        make_doc("c.txt", &guide.replace("deploy", "deplo")),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn budgeting_falls_back_to_outline() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        outline_fallback: true,
+        trace: true,
+        ..Default::default()
+    };
+
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 22).unwrap();
+    assert_eq!(plain.documents.len(), 1);
+    assert_eq!(plain.selection.documents_outlined, None);
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 22, &options)
+        .unwrap();
+    let b = &result.documents[1];
+    assert_eq!(b.id, "b.md");
+    assert_eq!(b.content, "# Deploy\nRun the deploy script.\n## Rollback\nUse the previous tag.");
+    assert_eq!(b.tokens, 17);
+    assert_eq!(b.representation, Some(Representation::Outline));
+    assert!(b.original_tokens.unwrap() > 17);
+    assert_eq!(result.documents[0].representation, None);
+
+    assert_eq!(result.selection.tokens_used, 22);
+    assert_eq!(result.selection.documents_outlined, Some(1));
+    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+    assert_eq!(result.selection.trace.unwrap().budget[1].verdict, BudgetVerdict::Outlined);
+    assert!(serde_json::to_string(b).unwrap().contains(r#""representation":"outline","#));
+}
+
+#[test]
+fn outline_keeps_the_leading_entries_that_fit() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        outline_fallback: true,
+        ..Default::default()
+    };
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 14, &options)
+        .unwrap();
+    assert_eq!(result.documents[1].content, "# Deploy\nRun the deploy script.");
+    assert_eq!(result.selection.tokens_used, 13);
+}
+
+#[test]
+fn non_markdown_documents_fall_through_to_truncation() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        outline_fallback: true,
+        ..Default::default()
+    };
+    let selector = ContextSelector::default().with_truncation(TruncationStrategy::Head);
+    let result = selector.select_with(&cache, Query::new("deploy deplo"), 40, &options).unwrap();
+
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md", "c.txt"]);
+    assert_eq!(result.documents[1].representation, Some(Representation::Outline));
+    assert_eq!(result.documents[2].representation, None);
+    assert_eq!(result.documents[2].truncation, Some(TruncationStrategy::Head));
+    assert_eq!(result.selection.documents_outlined, Some(1));
+    assert_eq!(result.selection.documents_truncated, Some(1));
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::filters::glob_match;
+use context_core::selection::{ContextSelector, PathFilter, SelectionOptions};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn glob_semantics() {
+    assert!(glob_match("docs/**", "docs/a.md"));
+    assert!(glob_match("docs/**", "docs/archive/old/a.md"));
+    assert!(glob_match("docs/*.md", "docs/a.md"));
+    assert!(!glob_match("docs/*.md", "docs/archive/a.md"));
+    assert!(glob_match("**/readme.md", "readme.md"));
+    assert!(glob_match("**/readme.md", "a/b/readme.md"));
+    assert!(glob_match("docs/?.md", "docs/a.md"));
+    assert!(!glob_match("docs/?.md", "docs/ab.md"));
+    assert!(!glob_match("docs/**", "src/docs/a.md"));
+}
+
+#[test]
+fn glob_classes_and_escapes() {
+    assert!(glob_match("log[0-9].txt", "log7.txt"));
+    assert!(!glob_match("log[0-9].txt", "logx.txt"));
+    assert!(glob_match("[!.]*", "a.md"));
+    assert!(!glob_match("[!.]*", ".env"));
+    assert!(glob_match("[]x]", "]"));
+    // `[` without a closing `]` is literal, as is an escaped character
+    assert!(glob_match("a[b", "a[b"));
+    assert!(glob_match("\\*.md", "*.md"));
+    assert!(!glob_match("\\*.md", "a.md"));
+    assert!(!glob_match("a[/]b", "a/b"));
+}
+
+#[test]
+fn glob_matching_is_not_exponential() {
+    // Backtracking over every star takes exponential time on these
+    let text = "a".repeat(200);
+    let started = Instant::now();
+    assert!(!glob_match(&format!("{}b", "*a".repeat(30)), &text));
+    assert!(!glob_match(&format!("{}b", "**a".repeat(30)), &text));
+    assert!(!glob_match(&format!("{}b", "**/a".repeat(30)), &"a/".repeat(100)));
+    assert!(glob_match(&"*a".repeat(30), &text));
+    assert!(started.elapsed() < Duration::from_secs(1), "took {:?}", started.elapsed());
+}
+
+#[test]
+fn path_filter_include_and_exclude() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![
+                make_doc("docs/guide.md", "deploy guide"),
+                make_doc("docs/archive/old.md", "deploy legacy"),
+                make_doc("src/main.rs", "deploy code"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+
+    let options = SelectionOptions {
+        path_filter: Some(PathFilter::new().include("docs/**").exclude("docs/archive/**")),
+        ..Default::default()
+    };
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 1000, &options)
+        .unwrap();
+
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["docs/guide.md"]);
+    assert_eq!(result.selection.documents_considered, 3);
+    assert_eq!(result.selection.documents_excluded_by_filter, Some(2));
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+
+    // Unfiltered selection does not emit the field
+    let plain = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+    assert_eq!(plain.documents.len(), 3);
+    assert!(!serde_json::to_string(&plain).unwrap().contains("documents_excluded_by_filter"));
+}
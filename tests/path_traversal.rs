@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheReadError, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::types::HashAlgorithm;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn build(path: &Path) {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha"), make_doc("b.md", "beta")], path)
+        .unwrap();
+}
+
+fn unsafe_path(err: &std::io::Error) -> &str {
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    match err.get_ref().and_then(|e| e.downcast_ref::<CacheReadError>()) {
+        Some(CacheReadError::UnsafePath { path, .. }) => path,
+        other => panic!("expected an unsafe path, got {other:?}"),
+    }
+}
+
+fn rewrite_manifest(path: &Path, edit: impl FnOnce(&mut Value)) {
+    let mut manifest: Value = serde_json::from_slice(&fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    edit(&mut manifest);
+    fs::write(path.join("manifest.json"), serde_json::to_vec(&manifest).unwrap()).unwrap();
+}
+
+#[test]
+fn open_rejects_manifest_paths_outside_the_root() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    build(&path);
+
+    for file in ["../../etc/passwd", "/etc/passwd", "documents/../../secret.json", "./documents/a.json", ""] {
+        rewrite_manifest(&path, |manifest| manifest["documents"][0]["file"] = file.into());
+        let err = ContextCache::open(&path).unwrap_err();
+        assert_eq!(unsafe_path(&err), file);
+    }
+}
+
+#[test]
+fn load_documents_rejects_edited_manifests() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    build(&path);
+
+    let mut cache = ContextCache::open(&path).unwrap();
+    cache.manifest.documents[1].file = "../b.json".to_string();
+    let err = cache.load_documents().unwrap_err();
+    assert!(matches!(&err, CacheReadError::UnsafePath { path, .. } if path == "../b.json"));
+    assert_eq!(err.to_string(), r#"Unsafe file path for b.md: "../b.json""#);
+    assert!(cache.verify().is_err());
+}
+
+#[test]
+fn open_rejects_index_paths_outside_the_root() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    build(&path);
+
+    // A tampered index with a matching checksum
+    let mut index: Value = serde_json::from_slice(&fs::read(path.join("index.json")).unwrap()).unwrap();
+    index["a.md"] = "../../etc/passwd".into();
+    let bytes = serde_json::to_vec_pretty(&index).unwrap();
+    fs::write(path.join("index.json"), &bytes).unwrap();
+    rewrite_manifest(&path, |manifest| {
+        manifest["index_checksum"] = HashAlgorithm::Sha256.checksum(&bytes).into();
+    });
+
+    let err = ContextCache::open(&path).unwrap_err();
+    assert_eq!(unsafe_path(&err), "../../etc/passwd");
+}
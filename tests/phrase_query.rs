@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, Scorer, TermFrequencyScorer};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn query_parses_quoted_phrases() {
+    let query = Query::new("limit \"Context Window\" size");
+    assert_eq!(query.terms, vec!["limit", "size"]);
+    assert_eq!(query.phrases, vec![vec!["context".to_string(), "window".to_string()]]);
+
+    // Single quoted words are terms; unmatched quotes are ignored
+    let query = Query::new("\"deploy\" \"context window");
+    assert_eq!(query.terms, vec!["deploy", "context", "window"]);
+    assert!(query.phrases.is_empty());
+
+    // Unquoted queries parse exactly as before
+    let query = Query::new("Context Window");
+    assert_eq!(query.terms, vec!["context", "window"]);
+    assert!(query.phrases.is_empty());
+}
+
+#[test]
+fn phrase_requires_adjacent_words_in_order() {
+    let scorer = TermFrequencyScorer;
+    let query = Query::new("\"context window\"");
+
+    let adjacent = scorer.score(&make_doc("a.md", "the context window is full"), &query);
+    assert_eq!(adjacent.phrase_matches, 2);
+    assert_eq!(adjacent.term_matches, 0);
+    assert_eq!(scorer.score_value(&adjacent), 2.0 / 5.0);
+
+    for content in ["the window of context", "context and window", "window context"] {
+        let details = scorer.score(&make_doc("b.md", content), &query);
+        assert_eq!(details.phrase_matches, 0, "{content}");
+        assert_eq!(scorer.score_value(&details), 0.0);
+    }
+}
+
+#[test]
+fn phrase_words_are_not_counted_twice() {
+    let scorer = TermFrequencyScorer;
+    let query = Query::new("context \"context window\"");
+    let details = scorer.score(&make_doc("a.md", "context window then context"), &query);
+    assert_eq!(details.phrase_matches, 2);
+    assert_eq!(details.term_matches, 1);
+    assert_eq!(scorer.score_value(&details), 3.0 / 4.0);
+}
+
+#[test]
+fn selection_reports_phrase_matches() {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("docs/adjacent.md", "the context window limit"),
+        make_doc("docs/separate.md", "the window limit and context"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("\"context window\""), 1000)
+        .unwrap();
+    assert_eq!(result.documents[0].id, "docs/adjacent.md");
+    assert_eq!(result.documents[0].why.query_phrases, vec!["context window"]);
+    assert_eq!(result.documents[0].why.phrase_matches, Some(2));
+    assert_eq!(result.documents[1].score, 0.0);
+    assert_eq!(result.documents[1].why.phrase_matches, Some(0));
+
+    // Without phrases the explanation serializes as before
+    let plain = ContextSelector::default()
+        .select(&cache, Query::new("context"), 1000)
+        .unwrap();
+    let json = serde_json::to_string(&plain.documents[0].why).unwrap();
+    assert!(!json.contains("phrase"));
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::{Query, QueryField};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str, title: &str, tags: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::new();
+    metadata.insert_string("title", title);
+    metadata.insert_string("tags", tags);
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("docs/deploy.md", "deploy the service", "Deployment Runbook", "runbook, ops"),
+        make_doc("docs/intro.md", "deploy overview", "Introduction", "guide"),
+        make_doc("src/deploy.rs", "fn deploy() {}", "Deployment code", "code"),
+    ]
+}
+
+#[test]
+fn query_parses_field_clauses() {
+    let query = Query::new("Title:Deployment path:docs/** deploy tag:RunBook http://x \"path:a b\"");
+    assert_eq!(
+        query.fields,
+        vec![
+            QueryField::Title("deployment".into()),
+            QueryField::Path("docs/**".into()),
+            QueryField::Tag("runbook".into()),
+        ]
+    );
+    assert_eq!(query.terms, vec!["deploy", "http://x"]);
+    assert_eq!(query.phrases, vec![vec!["path:a".to_string(), "b".to_string()]]);
+
+    // Empty values are plain terms
+    assert_eq!(Query::new("tag:").terms, vec!["tag:"]);
+}
+
+#[test]
+fn field_clauses_match_metadata_and_ids() {
+    let docs = docs();
+    let matching = |q: &str| -> Vec<&str> {
+        let query = Query::new(q);
+        docs.iter().filter(|d| query.matches_fields(d)).map(|d| d.id.as_str()).collect()
+    };
+
+    assert_eq!(matching("deploy"), vec!["docs/deploy.md", "docs/intro.md", "src/deploy.rs"]);
+    assert_eq!(matching("title:deployment"), vec!["docs/deploy.md", "src/deploy.rs"]);
+    assert_eq!(matching("tag:ops"), vec!["docs/deploy.md"]);
+    assert_eq!(matching("path:SRC/*.rs"), vec!["src/deploy.rs"]);
+    assert!(matching("path:src/*.md").is_empty());
+
+    // Same field: any; different fields: all
+    assert_eq!(matching("tag:guide tag:code"), vec!["docs/intro.md", "src/deploy.rs"]);
+    assert_eq!(matching("title:deployment path:docs/**"), vec!["docs/deploy.md"]);
+}
+
+#[test]
+fn selection_applies_field_clauses() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("title:deployment path:docs/** deploy"), 1000)
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["docs/deploy.md"]);
+    assert_eq!(result.selection.documents_excluded_by_filter, Some(2));
+    assert_eq!(result.documents[0].why.query_terms, vec!["deploy"]);
+
+    let plain = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+    assert_eq!(plain.selection.documents_excluded_by_filter, None);
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "object-store")]
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{
+    open_published, publish, CacheBuildConfig, CacheBuilder, CacheStore, ContextCache, RemoteStore,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use object_store::memory::InMemory;
+use object_store::ObjectStore;
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("docs/a.md", "deploy alpha"), make_doc("docs/b.md", "rollback beta")]
+}
+
+/// Every object key in `remote`, sorted.
+fn keys(store: &RemoteStore) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut pending = vec![None];
+    while let Some(prefix) = pending.pop() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let listing = runtime.block_on(store.remote().list_with_delimiter(prefix.as_ref())).unwrap();
+        keys.extend(listing.objects.into_iter().map(|meta| meta.location.to_string()));
+        pending.extend(listing.common_prefixes.into_iter().map(Some));
+    }
+    keys.sort();
+    keys
+}
+
+#[test]
+fn builds_and_selects_in_object_storage() {
+    let local = tempdir().unwrap();
+    let store = Arc::new(RemoteStore::new(Arc::new(InMemory::new()), local.path()).unwrap());
+    let built = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .build(docs(), Path::new("/caches/main"))
+        .unwrap();
+
+    // The temp dir was moved into place and removed
+    let keys = keys(&store);
+    assert!(keys.iter().all(|key| key.starts_with("caches/main/")), "{:?}", keys);
+    assert!(keys.contains(&"caches/main/manifest.json".to_string()));
+    assert_eq!(store.list(Path::new("/caches")).unwrap(), vec!["main"]);
+    assert!(store.exists(Path::new("/caches/main")));
+    assert!(!store.exists(Path::new("/caches/other")));
+    assert!(store.stamp(Path::new("/caches/main/manifest.json")).unwrap().is_some());
+
+    let cache = ContextCache::open_in(store.clone(), "/caches/main").unwrap();
+    assert_eq!(cache.manifest.cache_version, built.manifest.cache_version);
+    cache.verify().unwrap();
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.documents[0].id.as_str(), "docs/a.md");
+
+    // A second build into the same directory is refused
+    let err = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store.clone())
+        .build(docs(), Path::new("/caches/main"));
+    assert!(err.is_err());
+}
+
+#[test]
+fn published_caches_are_read_through_a_local_copy() {
+    let dir = tempdir().unwrap();
+    let source = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+    let version = source.manifest.cache_version.clone();
+
+    let remote = Arc::new(InMemory::new());
+    let local = tempdir().unwrap();
+    let store = Arc::new(RemoteStore::new(remote.clone(), local.path()).unwrap());
+    let published = publish(&source, store.clone(), "caches").unwrap();
+    assert_eq!(published.root, Path::new("caches").join(version.replace(':', "-")));
+    assert_eq!(keys(&store).len(), source.manifest.documents.len() + 2);
+    // Publishing again finds the manifest and writes nothing
+    publish(&source, store.clone(), "caches").unwrap();
+
+    // Another machine downloads the cache once
+    let reader = Arc::new(RemoteStore::new(remote.clone(), local.path().join("reader")).unwrap());
+    let cache = open_published(reader.clone(), "caches", &version).unwrap();
+    let before = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(before.documents[0].id.as_str(), "docs/a.md");
+    for key in keys(&reader) {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(remote.delete(&key.into())).unwrap();
+    }
+
+    // ...and selects from its copy once the objects are gone
+    let after = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(after.documents[0].id, before.documents[0].id);
+    assert_eq!(after.documents[0].content, before.documents[0].content);
+    assert_eq!(open_published(reader, "caches", &version).unwrap().manifest.cache_version, version);
+
+    let err = open_published(store, "caches", "sha256:0000").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn mutable_files_are_always_read_from_the_remote() {
+    let local = tempdir().unwrap();
+    let store = RemoteStore::new(Arc::new(InMemory::new()), local.path()).unwrap();
+
+    store.write_file(Path::new("state/CURRENT"), b"one").unwrap();
+    assert_eq!(store.read_file(Path::new("state/CURRENT")).unwrap(), b"one");
+    store.write_file(Path::new("state/CURRENT"), b"two").unwrap();
+    assert_eq!(store.read_file(Path::new("state/CURRENT")).unwrap(), b"two");
+    assert!(!local.path().join("state").exists());
+
+    let err = store.create_new(Path::new("state/CURRENT"), b"three").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    store.rename(Path::new("state/CURRENT"), Path::new("state/PREVIOUS")).unwrap();
+    assert_eq!(store.list(Path::new("state")).unwrap(), vec!["PREVIOUS"]);
+    store.remove_file(Path::new("state/PREVIOUS")).unwrap();
+    assert_eq!(store.list(Path::new("state")).unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(store.read_file(Path::new("state/../x")).unwrap_err().kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,79 @@
+use context_core::compat::SELECTION_RESULT_SCHEMA_VERSION;
+use context_core::render::{to_chat_messages, ChatMessage, ChatRole, RoleStrategy};
+use context_core::types::{SelectedDocument, SelectionMetadata, SelectionResult, SelectionWhy};
+
+fn make_doc(id: &str, content: &str) -> SelectedDocument {
+    SelectedDocument {
+        id: id.to_string(),
+        version: "sha256:mock".to_string(),
+        content: content.to_string(),
+        span: None,
+        metadata: None,
+        score: 0.5,
+        tokens: 2,
+        original_tokens: None,
+        truncation: None,
+        representation: None,
+        why: SelectionWhy::default(),
+    }
+}
+
+fn make_result(documents: Vec<SelectedDocument>) -> SelectionResult {
+    SelectionResult {
+        schema_version: SELECTION_RESULT_SCHEMA_VERSION,
+        documents,
+        selection: SelectionMetadata {
+            query: "deploy".to_string(),
+            budget: 100,
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn single_message_layouts() {
+    let result = make_result(vec![make_doc("a.md", "a < b"), make_doc("b.md", "second\n")]);
+    let expected = r#"<documents>
+<document id="a.md" version="sha256:mock" score="0.5" tokens="2">
+a &lt; b
+</document>
+<document id="b.md" version="sha256:mock" score="0.5" tokens="2">
+second
+</document>
+</documents>
+"#;
+
+    let system = to_chat_messages(&result, RoleStrategy::System);
+    assert_eq!(
+        system,
+        vec![ChatMessage {
+            role: ChatRole::System,
+            content: expected.to_string()
+        }]
+    );
+    assert_eq!(to_chat_messages(&result, RoleStrategy::default()), system);
+
+    let user = to_chat_messages(&result, RoleStrategy::User);
+    assert_eq!(user[0].role, ChatRole::User);
+    assert_eq!(user[0].content, expected);
+}
+
+#[test]
+fn one_user_message_per_document() {
+    let result = make_result(vec![make_doc("a.md", "first"), make_doc("b.md", "second")]);
+    let messages = to_chat_messages(&result, RoleStrategy::UserPerDocument);
+    assert_eq!(messages.len(), 2);
+    assert!(messages.iter().all(|m| m.role == ChatRole::User));
+    assert_eq!(
+        messages[1].content,
+        "<document id=\"b.md\" version=\"sha256:mock\" score=\"0.5\" tokens=\"2\">\nsecond\n</document>\n"
+    );
+
+    let json = serde_json::to_string(&messages[0]).unwrap();
+    assert!(json.starts_with(r#"{"role":"user","content":"<document id=\"a.md\""#));
+}
+
+#[test]
+fn empty_selection_has_no_messages() {
+    assert!(to_chat_messages(&make_result(Vec::new()), RoleStrategy::System).is_empty());
+}
@@ -0,0 +1,60 @@
+use context_core::compat::SELECTION_RESULT_SCHEMA_VERSION;
+use context_core::render::{Renderer, XmlRenderer};
+use context_core::types::{SelectedDocument, SelectionMetadata, SelectionResult, SelectionWhy};
+
+fn make_result(content: &str) -> SelectionResult {
+    SelectionResult {
+        schema_version: SELECTION_RESULT_SCHEMA_VERSION,
+        documents: vec![SelectedDocument {
+            id: "docs/a&b.md".to_string(),
+            version: "sha256:mock".to_string(),
+            content: content.to_string(),
+            span: None,
+            metadata: None,
+            score: 0.75,
+            tokens: 10,
+            original_tokens: None,
+            truncation: None,
+            representation: None,
+            why: SelectionWhy {
+                query_terms: vec!["deploy".to_string()],
+                term_matches: 3,
+                total_words: 4,
+                ..Default::default()
+            },
+        }],
+        selection: SelectionMetadata {
+            query: "deploy \"prod\"".to_string(),
+            budget: 4000,
+            tokens_used: 10,
+            documents_considered: 2,
+            documents_selected: 1,
+            documents_excluded_by_budget: 0,
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn xml_render_golden() {
+    let result = make_result("if a < b && c > d { 'x' }");
+    let rendered = XmlRenderer.render(&result);
+
+    let expected = r#"<documents>
+<document id="docs/a&amp;b.md" version="sha256:mock" score="0.75" tokens="10">
+if a &lt; b &amp;&amp; c &gt; d { &apos;x&apos; }
+</document>
+</documents>
+<selection query="deploy &quot;prod&quot;" budget="4000" tokens_used="10" documents_considered="2" documents_selected="1" documents_excluded_by_budget="0" />
+"#;
+
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn xml_render_is_deterministic() {
+    let result = make_result("line one\nline two\n");
+    assert_eq!(XmlRenderer.render(&result), XmlRenderer.render(&result.clone()));
+    // Content already ending in a newline is not padded with a second one
+    assert!(XmlRenderer.render(&result).contains("line two\n</document>"));
+}
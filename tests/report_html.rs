@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::report::render_html;
+use context_core::selection::{ContextSelector, PathFilter, SelectionOptions};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy"),   // 5 tokens, score 1.0
+        make_doc("b.md", "deploy deploy deploy x"), // 6 tokens, score 0.75
+        make_doc("c&d.md", "unrelated"),
+        make_doc("skip/e.md", "deploy"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn options() -> SelectionOptions {
+    SelectionOptions {
+        path_filter: Some(PathFilter::new().exclude("skip/**")),
+        exclude_zero_score: true,
+        trace: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn report_shows_ranking_budget_and_why() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy <script>"), 10, &options())
+        .unwrap();
+    let html = render_html(&result, result.selection.trace.as_ref().unwrap());
+
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.ends_with("</html>\n"));
+    // Query text is escaped
+    assert!(html.contains("<title>Selection report: deploy &lt;script&gt;</title>"));
+    assert!(!html.contains("<script>"));
+
+    // Budget utilization: 5 of 10 tokens
+    assert!(html.contains("<p>5 of 10 available used (50.0%); 1 of 4 documents selected.</p>"));
+    assert!(html.contains("<div style=\"width:50.0%\"></div>"));
+
+    // Ranked table, in rank order
+    let a = html.find("<td>a.md</td>").unwrap();
+    let b = html.find("<td>b.md</td>").unwrap();
+    assert!(a < b);
+    assert!(html.contains("<tr class=\"selected\"><td class=\"num\">1</td><td>a.md</td>"));
+    assert!(html.contains("<td class=\"num\">0.75</td><td class=\"num\"></td><td class=\"num\">0.75</td><td class=\"num\">6</td><td>excluded by budget</td>"));
+
+    // Why panel for the selected document only
+    assert_eq!(html.matches("<details>").count(), 1);
+    assert!(html.contains("<summary>a.md &mdash; score 1, 5 tokens</summary>"));
+    assert!(html.contains("<tr><th>Term matches</th><td class=\"num\">3 of 3 words</td></tr>"));
+
+    // Documents excluded before ranking
+    assert!(html.contains("<tr><td>skip/e.md</td><td>excluded by path filter</td></tr>"));
+    assert!(html.contains("<tr><td>c&amp;d.md</td><td>below score threshold</td></tr>"));
+}
+
+#[test]
+fn report_is_deterministic() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let render = || {
+        let result = selector.select_with(&cache, Query::new("deploy"), 10, &options()).unwrap();
+        render_html(&result, result.selection.trace.as_ref().unwrap())
+    };
+    assert_eq!(render(), render());
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn fixed_timestamp_makes_manifest_byte_identical() {
+    let dir = tempdir().unwrap();
+    let ts = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    let config = CacheBuildConfig::v0().with_fixed_timestamp(ts);
+    let builder = CacheBuilder::new(config);
+
+    let a = builder.build(vec![make_doc("a.md", "alpha")], &dir.path().join("a")).unwrap();
+    let b = builder.build(vec![make_doc("a.md", "alpha")], &dir.path().join("b")).unwrap();
+
+    assert_eq!(a.manifest.created_at, ts);
+    assert_eq!(b.manifest.created_at, ts);
+    assert_eq!(
+        fs::read(dir.path().join("a/manifest.json")).unwrap(),
+        fs::read(dir.path().join("b/manifest.json")).unwrap()
+    );
+
+    // The pinned timestamp is not part of the cache version
+    let unpinned = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "alpha")], &dir.path().join("c"))
+        .unwrap();
+    assert_eq!(a.manifest.cache_version, unpinned.manifest.cache_version);
+}
+
+#[test]
+fn source_date_epoch_is_honored() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(CacheBuildConfig::v0());
+
+    std::env::set_var("SOURCE_DATE_EPOCH", "1700000000");
+    let cache = builder.build(vec![make_doc("a.md", "alpha")], &dir.path().join("a"));
+
+    std::env::set_var("SOURCE_DATE_EPOCH", "not-a-number");
+    let invalid = builder.build(vec![make_doc("a.md", "alpha")], &dir.path().join("b"));
+    std::env::remove_var("SOURCE_DATE_EPOCH");
+
+    assert_eq!(
+        cache.unwrap().manifest.created_at,
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    );
+    match invalid {
+        Err(CacheBuildError::InvalidSourceDateEpoch(raw)) => assert_eq!(raw, "not-a-number"),
+        other => panic!("expected InvalidSourceDateEpoch, got {other:?}"),
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::compat::{
+    self, CompatError, CACHE_FORMAT_VERSION, CACHE_MANIFEST_SCHEMA_VERSION, DOCUMENT_SCHEMA_VERSION,
+    SELECTION_RESULT_SCHEMA_VERSION,
+};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn without_schema_version<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    let mut json = serde_json::to_value(value).unwrap();
+    json.as_object_mut().unwrap().remove("schema_version");
+    serde_json::to_vec(&json).unwrap()
+}
+
+#[test]
+fn current_outputs_carry_their_schema_version() {
+    let doc = make_doc("a.md", "deploy");
+    assert_eq!(doc.schema_version, DOCUMENT_SCHEMA_VERSION);
+
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![doc], &dir.path().join("cache"))
+        .unwrap();
+    assert_eq!(cache.manifest.schema_version, CACHE_MANIFEST_SCHEMA_VERSION);
+
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.schema_version, SELECTION_RESULT_SCHEMA_VERSION);
+    assert!(serde_json::to_string(&result).unwrap().starts_with(r#"{"schema_version":1,"#));
+}
+
+#[test]
+fn legacy_payloads_are_mapped_forward() {
+    let doc = make_doc("a.md", "deploy");
+    let legacy = without_schema_version(&doc);
+
+    // Plain deserialization reports the payload as unversioned
+    let plain: Document = serde_json::from_slice(&legacy).unwrap();
+    assert_eq!(plain.schema_version, 0);
+
+    let upgraded = compat::document_from_json(&legacy).unwrap();
+    assert_eq!(upgraded, doc);
+
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![doc], &dir.path().join("cache"))
+        .unwrap();
+    let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    let upgraded = compat::selection_result_from_json(&without_schema_version(&result)).unwrap();
+    assert_eq!(upgraded.schema_version, SELECTION_RESULT_SCHEMA_VERSION);
+    assert_eq!(serde_json::to_string(&upgraded).unwrap(), serde_json::to_string(&result).unwrap());
+}
+
+#[test]
+fn caches_written_before_versioning_still_open() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy")], &path)
+        .unwrap();
+
+    let manifest: serde_json::Value = serde_json::from_slice(&fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    fs::write(path.join("manifest.json"), without_schema_version(&manifest)).unwrap();
+
+    let cache = ContextCache::open(&path).unwrap();
+    assert_eq!(cache.manifest.schema_version, CACHE_MANIFEST_SCHEMA_VERSION);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut json = serde_json::to_value(make_doc("a.md", "deploy")).unwrap();
+    json["schema_version"] = 99.into();
+    let err = compat::document_from_json(&serde_json::to_vec(&json).unwrap()).unwrap_err();
+    assert!(matches!(err, CompatError::UnsupportedVersion { found: 99, supported: 1 }));
+
+    assert!(matches!(compat::cache_manifest_from_json(b"not json"), Err(CompatError::Json(_))));
+}
+
+#[test]
+fn manifests_record_their_format() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy")], &path)
+        .unwrap();
+    assert_eq!(cache.manifest.format_version, CACHE_FORMAT_VERSION);
+    assert_eq!(cache.manifest.crate_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+
+    // Caches written before the format was recorded are format 0
+    let mut manifest: serde_json::Value = serde_json::from_slice(&fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    let fields = manifest.as_object_mut().unwrap();
+    fields.remove("format_version");
+    fields.remove("crate_version");
+    fs::write(path.join("manifest.json"), serde_json::to_vec(&manifest).unwrap()).unwrap();
+    assert_eq!(ContextCache::open(&path).unwrap().manifest.format_version, 0);
+}
+
+#[test]
+fn newer_cache_formats_are_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy")], &path)
+        .unwrap();
+
+    let mut manifest: serde_json::Value = serde_json::from_slice(&fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    manifest["format_version"] = 99.into();
+    fs::write(path.join("manifest.json"), serde_json::to_vec(&manifest).unwrap()).unwrap();
+
+    let err = ContextCache::open(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let compat = err.get_ref().and_then(|e| e.downcast_ref::<CompatError>()).unwrap();
+    assert!(matches!(compat, CompatError::UnsupportedFormat { found: 99, supported: 1 }));
+    assert_eq!(err.to_string(), "Unsupported cache format 99 (newest supported: 1)");
+}
+
+#[test]
+fn newer_cache_formats_are_rejected_even_when_their_layout_changed() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy")], &path)
+        .unwrap();
+
+    // A future format that, say, keys documents by id
+    let mut manifest: serde_json::Value = serde_json::from_slice(&fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    manifest["format_version"] = 2.into();
+    manifest["documents"] = serde_json::json!({ "a.md": { "file": "documents/a.json" } });
+    let bytes = serde_json::to_vec(&manifest).unwrap();
+
+    let err = compat::cache_manifest_from_json(&bytes).unwrap_err();
+    assert!(matches!(err, CompatError::UnsupportedFormat { found: 2, supported: 1 }), "{}", err);
+}
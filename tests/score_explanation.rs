@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, HashingEmbedder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    ApproxTokenCounter, CompositeScorer, ContextSelector, EmbeddingScorer, FusionScorer, Scorer,
+    TermFrequencyScorer,
+};
+use context_core::types::{Explanation, FusionConfig, FusionMode, Query, ScoreDetails, SelectionWhy};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::default())
+        .build(
+            vec![
+                make_doc("a.md", "deploy deploy guide"),
+                make_doc("b.md", "deploy the service"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    (dir, cache)
+}
+
+fn embedding(cache: &ContextCache) -> EmbeddingScorer<HashingEmbedder> {
+    EmbeddingScorer::new(HashingEmbedder::default(), cache.load_vectors().unwrap().unwrap()).unwrap()
+}
+
+#[test]
+fn term_frequency_keeps_golden_shape() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+    assert!(result.documents[0].why.explanation.is_none());
+    assert_eq!(
+        serde_json::to_string(&result.documents[0].why).unwrap(),
+        r#"{"query_terms":["deploy"],"term_matches":2,"total_words":3}"#
+    );
+}
+
+#[test]
+fn embedding_and_fusion_explain_themselves() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::new(embedding(&cache), ApproxTokenCounter)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+    let Some(Explanation::Embedding { similarity: Some(similarity) }) = result.documents[0].why.explanation else {
+        panic!("expected an embedding explanation");
+    };
+    assert_eq!(similarity, result.documents[0].score);
+    let json = serde_json::to_value(&result.documents[0].why).unwrap();
+    assert_eq!(json["explanation"]["kind"], "embedding");
+
+    let config = FusionConfig {
+        mode: FusionMode::ReciprocalRank { k: 60 },
+        lexical_weight: 1.0,
+        vector_weight: 1.0,
+    };
+    let fusion = FusionScorer::new(TermFrequencyScorer, embedding(&cache), config);
+    let composite = CompositeScorer::new().with("rrf", 1.0, fusion).with("tf", 1.0, TermFrequencyScorer);
+    let result = ContextSelector::new(composite, ApproxTokenCounter)
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+
+    let Some(Explanation::Composite { components }) = &result.documents[0].why.explanation else {
+        panic!("expected a composite explanation");
+    };
+    assert_eq!(components.len(), 1);
+    assert!(matches!(components["rrf"], Explanation::Fusion { lexical_rank: 1, .. }));
+}
+
+struct Explained;
+
+impl Scorer for Explained {
+    fn score(&self, _doc: &Document, _query: &Query) -> ScoreDetails {
+        let mut values = BTreeMap::new();
+        values.insert("idf".to_string(), serde_json::json!(1.5));
+        ScoreDetails {
+            explanation: Some(Explanation::Custom {
+                scorer: "bm25-test".to_string(),
+                values,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+#[test]
+fn custom_explanations_round_trip() {
+    let details = Explained.score(&make_doc("a.md", "x"), &Query::new("x"));
+    let why = SelectionWhy {
+        explanation: details.explanation,
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&why).unwrap();
+    assert!(json.contains(r#""explanation":{"kind":"custom","scorer":"bm25-test","values":{"idf":1.5}}"#));
+
+    let back: SelectionWhy = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.explanation, why.explanation);
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::{Query, SelectedDocument, SelectionWhy};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![make_doc("a.md", "deploy deploy x")]; // score 2/3
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn score_json(cache: &ContextCache, score_precision: Option<u32>) -> serde_json::Value {
+    let options = SelectionOptions {
+        score_precision,
+        ..Default::default()
+    };
+    let result = ContextSelector::default()
+        .select_with(cache, Query::new("deploy"), 100, &options)
+        .unwrap();
+    serde_json::to_value(&result).unwrap()
+}
+
+#[test]
+fn scores_serialize_with_six_decimals() {
+    let (_dir, cache) = build_cache();
+    let json = score_json(&cache, None);
+    assert_eq!(json["documents"][0]["score"].to_string(), "0.666667");
+    assert!(json["selection"].get("score_precision").is_none());
+
+    let doc = SelectedDocument {
+        id: "a.md".to_string(),
+        version: "sha256:mock".to_string(),
+        content: String::new(),
+        span: None,
+        metadata: None,
+        score: 0.92,
+        tokens: 0,
+        original_tokens: None,
+        truncation: None,
+        representation: None,
+        why: SelectionWhy::default(),
+    };
+    assert!(serde_json::to_string(&doc).unwrap().contains(r#""score":0.92,"#));
+}
+
+#[test]
+fn precision_is_configurable() {
+    let (_dir, cache) = build_cache();
+    let json = score_json(&cache, Some(2));
+    assert_eq!(json["documents"][0]["score"].to_string(), "0.67");
+    assert_eq!(json["selection"]["score_precision"], 2);
+
+    // Precision beyond the serialized six places changes nothing
+    assert_eq!(score_json(&cache, Some(9)), score_json(&cache, None));
+}
+
+#[test]
+fn deserialization_accepts_any_precision() {
+    let doc: SelectedDocument = serde_json::from_str(
+        r#"{"id":"a.md","version":"v","content":"","score":0.123456789,"tokens":1,"why":{"query_terms":[],"term_matches":0,"total_words":0}}"#,
+    )
+    .unwrap();
+    assert_eq!(doc.score, 0.123_456_79);
+    assert!(serde_json::to_string(&doc).unwrap().contains(r#""score":0.123457,"#));
+}
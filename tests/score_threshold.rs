@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build(path: &Path) -> ContextCache {
+    let docs = vec![
+        make_doc("a.md", "deploy deploy"),          // 1.0
+        make_doc("b.md", "deploy the service now"), // 0.25
+        make_doc("c.md", "unrelated text"),         // 0.0
+    ];
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs, path).unwrap()
+}
+
+fn ids(result: &context_core::types::SelectionResult) -> Vec<&str> {
+    result.documents.iter().map(|d| d.id.as_str()).collect()
+}
+
+#[test]
+fn zero_score_documents_can_be_excluded() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let options = SelectionOptions {
+        exclude_zero_score: true,
+        ..Default::default()
+    };
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 1000, &options)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "b.md"]);
+    assert_eq!(result.selection.documents_excluded_by_score, Some(1));
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+    assert_eq!(result.selection.documents_considered, 3);
+}
+
+#[test]
+fn min_score_is_inclusive_and_counted_apart_from_budget() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let options = SelectionOptions {
+        min_score: Some(0.25),
+        ..Default::default()
+    };
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 1000, &options)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "b.md"]);
+    assert_eq!(result.selection.documents_excluded_by_score, Some(1));
+
+    // a.md costs 4 tokens, b.md 6: only a.md fits
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 5, &options)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["a.md"]);
+    assert_eq!(result.selection.documents_excluded_by_score, Some(1));
+    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+}
+
+#[test]
+fn defaults_serialize_as_before() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("deploy"), 1000)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["a.md", "b.md", "c.md"]);
+    assert_eq!(result.selection.documents_excluded_by_score, None);
+    assert!(!serde_json::to_string(&result).unwrap().contains("excluded_by_score"));
+    assert_eq!(serde_json::to_string(&SelectionOptions::default()).unwrap(), r#"{"limit":"unlimited"}"#);
+}
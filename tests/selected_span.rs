@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, LineChunker, Metadata};
+use context_core::render::{Renderer, XmlRenderer};
+use context_core::selection::{ChunkScoring, ContextSelector};
+use context_core::types::{AggregationStrategy, Query, SelectionResult, Span};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+const CONTENT: &str = "intro line one\nintro line two\nhow to deploy\ndeploy steps\noutro\n";
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("guide.md", CONTENT)], &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn select(cache: &ContextCache, chunking: ChunkScoring, budget: u64) -> SelectionResult {
+    ContextSelector::default()
+        .with_chunk_scoring(chunking)
+        .select(cache, Query::new("deploy"), budget)
+        .unwrap()
+}
+
+#[test]
+fn best_chunk_only_selects_and_reports_span() {
+    let (_dir, cache) = build_cache();
+    let chunking = ChunkScoring::new(LineChunker::new(2), AggregationStrategy::Max).best_chunk_only();
+    let result = select(&cache, chunking, 1000);
+
+    let doc = &result.documents[0];
+    assert_eq!(doc.content, "how to deploy\ndeploy steps\n");
+    assert_eq!(
+        doc.span,
+        Some(Span {
+            start_line: 3,
+            end_line: 4,
+            byte_range: [30, 57]
+        })
+    );
+    assert_eq!(&CONTENT[30..57], doc.content);
+    // Tokens are counted on the chunk only
+    assert_eq!(doc.tokens, 7);
+    assert_eq!(result.selection.tokens_used, 7);
+    assert!(result.selection.chunking.as_ref().unwrap().best_chunk_only);
+
+    // A budget that fits the chunk but not the whole document
+    assert_eq!(select(&cache, ChunkScoring::new(LineChunker::new(2), AggregationStrategy::Max).best_chunk_only(), 10).documents.len(), 1);
+    assert!(select(&cache, ChunkScoring::new(LineChunker::new(2), AggregationStrategy::Max), 10).documents.is_empty());
+
+    let json = serde_json::to_value(doc).unwrap();
+    assert_eq!(json["span"]["start_line"], 3);
+    assert_eq!(json["span"]["byte_range"], serde_json::json!([30, 57]));
+    assert!(XmlRenderer.render(&result).contains(r#"tokens="7" lines="3-4">"#));
+}
+
+#[test]
+fn span_is_omitted_for_whole_documents() {
+    let (_dir, cache) = build_cache();
+    let result = select(&cache, ChunkScoring::new(LineChunker::new(2), AggregationStrategy::Max), 1000);
+    assert_eq!(result.documents[0].content, CONTENT);
+    assert_eq!(result.documents[0].span, None);
+
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 1000).unwrap();
+    let json = serde_json::to_string(&plain).unwrap();
+    assert!(!json.contains("span"));
+    assert!(!json.contains("null"));
+}
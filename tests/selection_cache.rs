@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    BudgetConfig, ContextSelector, Mmr, SelectionCache, SelectionLimit, SelectionOptions, Similarity,
+};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build(path: &Path) -> ContextCache {
+    let docs = vec![
+        make_doc("docs/deploy.md", "deploy the service with deploy scripts"),
+        make_doc("docs/intro.md", "introduction to the service"),
+        make_doc("src/deploy.rs", "fn deploy() {}"),
+    ];
+    CacheBuilder::new(CacheBuildConfig::v0()).build(docs, path).unwrap()
+}
+
+#[test]
+fn cached_selection_matches_uncached() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let selector = ContextSelector::default();
+    let memo = SelectionCache::new(8);
+    let options = SelectionOptions::default();
+
+    let expected = selector.select(&cache, Query::new("deploy"), 40).unwrap();
+    let first = selector
+        .select_cached(&memo, &cache, Query::new("deploy"), 40, &options)
+        .unwrap();
+    assert_eq!(memo.len(), 1);
+
+    // Second call is served from the memo even though the files are gone
+    fs::remove_dir_all(dir.path().join("cache")).unwrap();
+    let second = selector
+        .select_cached(&memo, &cache, Query::new("deploy"), 40, &options)
+        .unwrap();
+
+    let expected = serde_json::to_string(&expected).unwrap();
+    assert_eq!(serde_json::to_string(&first).unwrap(), expected);
+    assert_eq!(serde_json::to_string(&second).unwrap(), expected);
+}
+
+#[test]
+fn keys_cover_every_input() {
+    let default_options = SelectionOptions::default();
+    let top_one = SelectionOptions {
+        limit: SelectionLimit::TopK(1),
+        ..Default::default()
+    };
+    let plain = ContextSelector::default().fingerprint();
+    let diverse = ContextSelector::default()
+        .with_mmr(Mmr::new(0.5, Similarity::TermOverlap))
+        .fingerprint();
+    assert_ne!(plain, diverse);
+
+    let base = SelectionCache::key("sha256:a", &Query::new("deploy"), &BudgetConfig::new(40), &default_options, &plain);
+    let variants = [
+        SelectionCache::key("sha256:b", &Query::new("deploy"), &BudgetConfig::new(40), &default_options, &plain),
+        SelectionCache::key("sha256:a", &Query::new("intro"), &BudgetConfig::new(40), &default_options, &plain),
+        SelectionCache::key("sha256:a", &Query::new("deploy"), &BudgetConfig::new(41), &default_options, &plain),
+        SelectionCache::key("sha256:a", &Query::new("deploy"), &BudgetConfig::new(40).reserve(1), &default_options, &plain),
+        SelectionCache::key("sha256:a", &Query::new("deploy"), &BudgetConfig::new(40), &top_one, &plain),
+        SelectionCache::key("sha256:a", &Query::new("deploy"), &BudgetConfig::new(40), &default_options, &diverse),
+    ];
+    for variant in variants {
+        assert_ne!(base, variant);
+    }
+    assert_eq!(
+        base,
+        SelectionCache::key("sha256:a", &Query::new("deploy"), &BudgetConfig::new(40), &default_options, &plain)
+    );
+}
+
+#[test]
+fn capacity_evicts_least_recently_used() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let selector = ContextSelector::default();
+    let result = selector.select(&cache, Query::new("deploy"), 40).unwrap();
+
+    let memo = SelectionCache::new(2);
+    memo.insert("a".into(), &result);
+    memo.insert("b".into(), &result);
+    assert!(memo.get("a").is_some());
+    memo.insert("c".into(), &result);
+
+    assert_eq!(memo.len(), 2);
+    assert!(memo.get("a").is_some());
+    assert!(memo.get("b").is_none());
+    assert!(memo.get("c").is_some());
+
+    memo.clear();
+    assert!(memo.is_empty());
+    SelectionCache::new(0).insert("a".into(), &result);
+}
+
+#[test]
+fn persisted_memo_round_trips() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let selector = ContextSelector::default();
+    let options = SelectionOptions::default();
+    let memo = SelectionCache::new(4);
+    let result = selector
+        .select_cached(&memo, &cache, Query::new("deploy"), 40, &options)
+        .unwrap();
+    memo.insert("older".into(), &result);
+
+    let restored = SelectionCache::from_bytes(4, &memo.to_bytes()).unwrap();
+    assert_eq!(restored.len(), 2);
+    let key = SelectionCache::key(
+        &cache.manifest.cache_version,
+        &Query::new("deploy"),
+        &BudgetConfig::new(40),
+        &options,
+        &selector.fingerprint(),
+    );
+    assert_eq!(
+        serde_json::to_string(&restored.get(&key).unwrap()).unwrap(),
+        serde_json::to_string(&result).unwrap()
+    );
+
+    // Restoring into a smaller memo keeps the most recently used entries
+    let smaller = SelectionCache::from_bytes(1, &memo.to_bytes()).unwrap();
+    assert!(smaller.get("older").is_some());
+    assert!(smaller.get(&key).is_none());
+}
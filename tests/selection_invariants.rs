@@ -44,7 +44,7 @@ fn invariant_selection_bounded_explainable_complete() {
 
     let result = selector.select(&cache, query.clone(), budget).unwrap();
 
-    let tokens_sum: usize = result.documents.iter().map(|doc| doc.tokens).sum();
+    let tokens_sum: u64 = result.documents.iter().map(|doc| doc.tokens).sum();
     assert_eq!(tokens_sum, result.selection.tokens_used, "tokens_used must equal sum of selected tokens");
     assert!(result.selection.tokens_used <= budget, "token usage must never exceed budget");
 
@@ -64,7 +64,7 @@ fn invariant_selection_bounded_explainable_complete() {
 
         assert_eq!(selected.content, original.content, "selected document must include full content");
         assert_eq!(selected.version, original.version.as_str());
-        assert_eq!(selected.tokens, token_count);
+        assert_eq!(selected.tokens, token_count as u64);
         assert!((selected.score - score).abs() < f32::EPSILON);
         assert_eq!(selected.why.query_terms, details.query_terms);
         assert_eq!(selected.why.term_matches, details.term_matches);
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionLimit, SelectionOptions};
+use context_core::types::Query;
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![
+                make_doc("a.md", "apple apple apple"),
+                make_doc("b.md", "apple apple pear"),
+                make_doc("c.md", "apple pear pear"),
+                make_doc("d.md", "pear pear pear"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    (dir, cache)
+}
+
+fn top_k(k: usize) -> SelectionOptions {
+    SelectionOptions {
+        limit: SelectionLimit::TopK(k),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn top_k_keeps_best_documents() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("apple"), 10_000, &top_k(2))
+        .unwrap();
+
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md"]);
+    assert_eq!(result.selection.limit, Some(2));
+    assert_eq!(result.selection.documents_excluded_by_limit, Some(2));
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+}
+
+#[test]
+fn top_k_combines_with_token_budget() {
+    let (_dir, cache) = build_cache();
+    // Each document is 5 tokens; budget admits two, k admits three.
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("apple"), 10, &top_k(3))
+        .unwrap();
+
+    assert_eq!(result.documents.len(), 2);
+    assert_eq!(result.selection.documents_excluded_by_budget, 2);
+    assert_eq!(result.selection.documents_excluded_by_limit, Some(0));
+}
+
+#[test]
+fn unlimited_omits_limit_fields() {
+    let (_dir, cache) = build_cache();
+    let result = ContextSelector::default()
+        .select(&cache, Query::new("apple"), 10_000)
+        .unwrap();
+    assert_eq!(result.documents.len(), 4);
+    let json = serde_json::to_string(&result.selection).unwrap();
+    assert!(!json.contains("limit"));
+}
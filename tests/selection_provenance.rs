@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheReadError, DocumentLoader};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BytesPerTokenCounter, ContextSelector, PathFilter, SelectionOptions, TermFrequencyScorer};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![make_doc("a.md", "deploy"), make_doc("b.md", "other")]
+}
+
+struct Unversioned(Vec<Document>);
+
+impl DocumentLoader for Unversioned {
+    fn load_documents(&self) -> Result<Vec<Document>, CacheReadError> {
+        Ok(self.0.clone())
+    }
+}
+
+#[test]
+fn results_name_their_cache_and_components() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs(), &dir.path().join("cache"))
+        .unwrap();
+    let options = SelectionOptions {
+        path_filter: Some(PathFilter::new().include("a.md")),
+        ..Default::default()
+    };
+    let selector = ContextSelector::new(TermFrequencyScorer, BytesPerTokenCounter::new(4, 1));
+    let result = selector.select_with(&cache, Query::new("deploy"), 100, &options).unwrap();
+
+    let meta = &result.selection;
+    assert_eq!(meta.cache_version.as_deref(), Some(cache.manifest.cache_version.as_str()));
+    assert_eq!(meta.document_count_in_cache, Some(2));
+    assert_eq!(meta.scorer.as_deref(), Some("term_frequency"));
+    assert_eq!(meta.token_counter.as_deref(), Some("bytes_per_token(4/1)"));
+
+    let snapshot = cache.load_snapshot().unwrap();
+    let from_snapshot = selector.select_snapshot_with(&snapshot, Query::new("deploy"), 100, &options).unwrap();
+    assert_eq!(serde_json::to_string(&from_snapshot).unwrap(), serde_json::to_string(&result).unwrap());
+
+    // Provenance fields trail the selection metadata
+    let json = serde_json::to_string(&result.selection).unwrap();
+    assert!(json.ends_with(r#""token_counter":"bytes_per_token(4/1)"}"#));
+}
+
+#[test]
+fn unversioned_loaders_omit_the_cache_version() {
+    let result = ContextSelector::default()
+        .select(&Unversioned(docs()), Query::new("deploy"), 100)
+        .unwrap();
+    assert_eq!(result.selection.cache_version, None);
+    assert_eq!(result.selection.document_count_in_cache, Some(2));
+    assert!(!serde_json::to_string(&result).unwrap().contains("cache_version"));
+}
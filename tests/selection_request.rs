@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BudgetConfig, ContextSelector, SelectionOptions, SelectionRequest};
+use context_core::types::{Query, SelectionError};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str, tags: &str) -> Document {
+    let id = make_id(id_str);
+    let mut metadata = Metadata::new();
+    if !tags.is_empty() {
+        metadata.insert_string("tags", tags);
+    }
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), metadata).unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy", ""),           // 5 tokens, score 1.0
+        make_doc("b.md", "deploy deploy deploy x", "runbook"),  // 6 tokens, score 0.75
+        make_doc("c.md", "unrelated notes", ""),                // 4 tokens, score 0.0
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn ids(result: &context_core::types::SelectionResult) -> Vec<&str> {
+    result.documents.iter().map(|d| d.id.as_str()).collect()
+}
+
+#[test]
+fn request_round_trips_and_replays() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let request = SelectionRequest::new("deploy", BudgetConfig::new(100).reserve(10)).options(SelectionOptions {
+        exclude_zero_score: true,
+        ..Default::default()
+    });
+
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(
+        json,
+        r#"{"query":"deploy","budget":{"total":100,"reserved":10,"per_document_overhead":0},"options":{"limit":"unlimited","exclude_zero_score":true}}"#
+    );
+    let replayed: SelectionRequest = serde_json::from_str(&json).unwrap();
+    assert_eq!(replayed, request);
+
+    // Same result as select_with on the same inputs
+    let direct = selector
+        .select_with(&cache, Query::new("deploy"), BudgetConfig::new(100).reserve(10), &request.options)
+        .unwrap();
+    let via_request = selector.select_request(&cache, &replayed).unwrap();
+    assert_eq!(
+        serde_json::to_string(&via_request).unwrap(),
+        serde_json::to_string(&direct).unwrap()
+    );
+}
+
+#[test]
+fn minimal_request_uses_defaults() {
+    let request: SelectionRequest = serde_json::from_str(r#"{"query":"deploy","budget":{"total":100}}"#).unwrap();
+    assert_eq!(request, SelectionRequest::new("deploy", 100));
+}
+
+#[test]
+fn filters_add_field_clauses() {
+    let (_dir, cache) = build_cache();
+    let request = SelectionRequest::new("deploy", 100).filter("tag:runbook");
+    let result = ContextSelector::default().select_request(&cache, &request).unwrap();
+    assert_eq!(ids(&result), vec!["b.md"]);
+    assert_eq!(result.selection.query, "deploy");
+    assert_eq!(result.selection.documents_excluded_by_filter, Some(2));
+
+    let bad = SelectionRequest::new("deploy", 100).filter("runbook");
+    assert!(matches!(
+        ContextSelector::default().select_request(&cache, &bad),
+        Err(SelectionError::InvalidFilter(clause)) if clause == "runbook"
+    ));
+}
+
+#[test]
+fn pinned_documents_rank_first() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+
+    // c.md scores 0.0 but is pinned past the threshold, ahead of a.md
+    let request = SelectionRequest::new("deploy", 9)
+        .options(SelectionOptions {
+            exclude_zero_score: true,
+            ..Default::default()
+        })
+        .pin("c.md")
+        .pin("missing.md");
+    let result = selector.select_request(&cache, &request).unwrap();
+    assert_eq!(ids(&result), vec!["c.md", "a.md"]);
+    assert_eq!(result.selection.documents_excluded_by_score, Some(0));
+    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+
+    // Request pins come before option pins; both still face the budget
+    let request = SelectionRequest::new("deploy", 10)
+        .options(SelectionOptions {
+            pinned: vec!["a.md".into()],
+            ..Default::default()
+        })
+        .pin("b.md");
+    let result = selector.select_request(&cache, &request).unwrap();
+    // b.md (6) then a.md (5) overflows; c.md (4) still fits
+    assert_eq!(ids(&result), vec!["b.md", "c.md"]);
+    assert_eq!(result.selection.documents_excluded_by_budget, 1);
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionLimit, SelectionOptions};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn borrowed_result_points_into_snapshot() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "beta")],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    let snapshot = cache.load_snapshot().unwrap();
+
+    let result = ContextSelector::default().select_snapshot_ref(
+        &snapshot,
+        Query::new("deploy"),
+        100,
+        &SelectionOptions::default(),
+    )
+    .unwrap();
+    let doc = &snapshot.documents()[0];
+    assert_eq!(result.documents[0].id, "a.md");
+    assert!(std::ptr::eq(result.documents[0].content.as_ref(), doc.content.as_str()));
+}
+
+#[test]
+fn borrowed_result_serializes_like_owned() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![
+                make_doc("a.md", "deploy alpha"),
+                make_doc("b.md", "deploy deploy beta"),
+                make_doc("c.md", "gamma"),
+            ],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+    let snapshot = cache.load_snapshot().unwrap();
+    let options = SelectionOptions {
+        limit: SelectionLimit::TopK(2),
+        ..Default::default()
+    };
+
+    let selector = ContextSelector::default();
+    let owned = selector.select_with(&cache, Query::new("deploy"), 100, &options).unwrap();
+    let borrowed = selector.select_snapshot_ref(&snapshot, Query::new("deploy"), 100, &options).unwrap();
+
+    let borrowed_json = serde_json::to_string(&borrowed).unwrap();
+    assert_eq!(serde_json::to_string(&owned).unwrap(), borrowed_json);
+    assert_eq!(serde_json::to_string(&borrowed.into_owned()).unwrap(), borrowed_json);
+}
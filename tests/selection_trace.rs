@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, PathFilter, SelectionOptions, Stickiness};
+use context_core::types::{BudgetVerdict, FilterVerdict, Query};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy"),   // 5 tokens, score 1.0
+        make_doc("b.md", "deploy deploy deploy x"), // 6 tokens, score 0.75
+        make_doc("c.md", "unrelated"),              // score 0.0
+        make_doc("skip/d.md", "deploy"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn traced_options(cache: &ContextCache) -> SelectionOptions {
+    // Stick to the current version of b.md
+    let docs = cache.load_documents().unwrap();
+    let b = docs.iter().find(|d| d.id.as_str() == "b.md").unwrap();
+    SelectionOptions {
+        path_filter: Some(PathFilter::new().exclude("skip/**")),
+        exclude_zero_score: true,
+        stickiness: Some(Stickiness::new([("b.md", b.version.as_str())], 0.1)),
+        trace: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn trace_records_every_stage() {
+    let (_dir, cache) = build_cache();
+    let options = traced_options(&cache);
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 8, &options)
+        .unwrap();
+    let trace = result.selection.trace.as_ref().expect("trace requested");
+
+    // Filter: every document, with its verdict
+    let filter: Vec<(&str, FilterVerdict)> = trace.filter.iter().map(|f| (f.id.as_str(), f.verdict)).collect();
+    assert_eq!(filter.len(), 4);
+    assert!(filter.contains(&("skip/d.md", FilterVerdict::ExcludedByPath)));
+    assert!(filter.contains(&("a.md", FilterVerdict::Passed)));
+
+    // Scores: raw scores, threshold verdicts, and the stickiness boost
+    assert_eq!(trace.scores.len(), 3);
+    let score = |id: &str| trace.scores.iter().find(|s| s.id == id).unwrap();
+    assert_eq!(score("a.md").raw_score, 1.0);
+    assert!(score("a.md").passed_threshold);
+    assert_eq!(score("a.md").boost, None);
+    assert_eq!(score("b.md").raw_score, 0.75);
+    assert_eq!(score("b.md").boost, Some(0.1));
+    assert!(!score("c.md").passed_threshold);
+
+    // Budget: rank order, admissions and exclusions
+    let budget: Vec<(&str, usize, BudgetVerdict)> =
+        trace.budget.iter().map(|b| (b.id.as_str(), b.rank, b.verdict)).collect();
+    assert_eq!(
+        budget,
+        vec![("a.md", 0, BudgetVerdict::Selected), ("b.md", 1, BudgetVerdict::ExcludedByBudget)]
+    );
+    assert_eq!(trace.budget[1].tokens, 6);
+    assert!((trace.budget[1].score - 0.85).abs() < 1e-6);
+
+    // Serializable for offline inspection
+    let json = serde_json::to_value(&result.selection.trace).unwrap();
+    assert_eq!(json["filter"].as_array().unwrap().len(), 4);
+    assert_eq!(json["budget"][1]["verdict"], "excluded_by_budget");
+    assert_eq!(json["scores"][0]["raw_score"], 1.0);
+}
+
+#[test]
+fn trace_does_not_change_the_selection() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default();
+    let traced = selector.select_with(&cache, Query::new("deploy"), 8, &traced_options(&cache)).unwrap();
+    let plain_options = SelectionOptions {
+        trace: false,
+        ..traced_options(&cache)
+    };
+    let plain = selector.select_with(&cache, Query::new("deploy"), 8, &plain_options).unwrap();
+
+    assert!(plain.selection.trace.is_none());
+    assert!(!serde_json::to_string(&plain).unwrap().contains("trace"));
+    let mut traced = serde_json::to_value(&traced).unwrap();
+    traced["selection"].as_object_mut().unwrap().remove("trace");
+    assert_eq!(traced, serde_json::to_value(&plain).unwrap());
+}
+
+#[test]
+fn multi_query_trace_uses_fused_scores() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        trace: true,
+        ..Default::default()
+    };
+    let result = ContextSelector::default()
+        .select_multi_with(&cache, &[Query::new("deploy"), Query::new("unrelated")], 100, &options)
+        .unwrap();
+    let trace = result.selection.trace.unwrap();
+
+    assert!(trace.filter.iter().all(|f| f.verdict == FilterVerdict::Passed));
+    let c = trace.scores.iter().find(|s| s.id == "c.md").unwrap();
+    assert_eq!(c.raw_score, 1.0);
+    assert_eq!(trace.budget.len(), 4);
+    assert!(trace.budget.iter().all(|b| b.verdict == BudgetVerdict::Selected));
+}
@@ -0,0 +1,65 @@
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, MemoryStore};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("a.md", "alpha"),
+        make_doc("b.md", "beta"),
+        make_doc("c.md", "alpha"),
+    ]
+}
+
+#[test]
+fn sharded_files_live_under_their_prefix() {
+    let dir = tempdir().unwrap();
+    let config = CacheBuildConfig::v0().with_sharded_documents();
+    let cache = CacheBuilder::new(config).build(docs(), &dir.path().join("cache")).unwrap();
+
+    for entry in &cache.manifest.documents {
+        let (shard, name) = entry.file.strip_prefix("documents/").unwrap().split_once('/').unwrap();
+        assert_eq!(shard, &name[..2]);
+        assert!(cache.root.join(&entry.file).is_file());
+    }
+
+    let reopened = ContextCache::open(dir.path().join("cache")).unwrap();
+    reopened.verify().unwrap();
+    let ids: Vec<String> = reopened.load_documents().unwrap().iter().map(|d| d.id.as_str().to_string()).collect();
+    assert_eq!(ids, vec!["a.md", "b.md", "c.md"]);
+}
+
+#[test]
+fn layout_is_part_of_the_cache_version() {
+    let flat = CacheBuilder::new(CacheBuildConfig::v0()).build_in_memory(docs()).unwrap();
+    let sharded = CacheBuilder::new(CacheBuildConfig::v0().with_sharded_documents())
+        .build_in_memory(docs())
+        .unwrap();
+    assert_ne!(flat.manifest().cache_version, sharded.manifest().cache_version);
+    assert!(flat.manifest().documents.iter().all(|e| e.file.matches('/').count() == 1));
+    assert!(sharded.manifest().documents.iter().all(|e| e.file.matches('/').count() == 2));
+}
+
+#[test]
+fn streaming_build_shards_the_same_way() {
+    let config = CacheBuildConfig::v0().with_sharded_documents();
+    let store = Arc::new(MemoryStore::new());
+    let builder = CacheBuilder::new(config).with_store(store);
+    let built = builder.build(docs(), Path::new("/caches/built")).unwrap();
+    let streamed = builder
+        .build_from_iter(docs().into_iter().map(Ok::<_, Infallible>), Path::new("/caches/streamed"))
+        .unwrap();
+
+    assert_eq!(built.manifest.cache_version, streamed.manifest.cache_version);
+    assert_eq!(built.manifest.tree_hash, streamed.manifest.tree_hash);
+    streamed.verify().unwrap();
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheReadError, ContextCache, MultiCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, OnCorrupt, SelectionOptions};
+use context_core::types::{Query, SelectionError};
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+/// A cache of `a.md`, `b.md` and `c.md` with `b.md`'s file removed.
+fn damaged(dir: &Path) -> ContextCache {
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(
+            vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "deploy beta"), make_doc("c.md", "deploy gamma")],
+            dir,
+        )
+        .unwrap();
+    fs::remove_file(dir.join(&cache.manifest.documents[1].file)).unwrap();
+    cache
+}
+
+fn skip() -> SelectionOptions {
+    SelectionOptions {
+        on_corrupt: OnCorrupt::Skip,
+        trace: true,
+        ..SelectionOptions::default()
+    }
+}
+
+#[test]
+fn corrupt_documents_fail_by_default() {
+    let dir = tempdir().unwrap();
+    let cache = damaged(&dir.path().join("cache"));
+    let err = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap_err();
+    assert!(matches!(err, SelectionError::CacheError(CacheReadError::MissingFile { .. })));
+}
+
+#[test]
+fn skipped_documents_are_counted_and_traced() {
+    let dir = tempdir().unwrap();
+    let cache = damaged(&dir.path().join("cache"));
+
+    let result = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 100, &skip())
+        .unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["a.md", "c.md"]);
+    assert_eq!(result.selection.documents_unreadable, Some(1));
+    assert_eq!(result.selection.documents_considered, 2);
+    assert_eq!(result.selection.document_count_in_cache, Some(3));
+
+    let unreadable = &result.selection.trace.as_ref().unwrap().unreadable;
+    assert_eq!(unreadable.len(), 1);
+    assert_eq!(unreadable[0].id, "b.md");
+    assert!(unreadable[0].error.starts_with("Missing file for b.md: "));
+
+    // Multi-query selections skip the same way
+    let result = ContextSelector::default()
+        .select_multi_with(&cache, &[Query::new("alpha"), Query::new("gamma")], 100, &skip())
+        .unwrap();
+    assert_eq!(result.selection.documents_unreadable, Some(1));
+}
+
+#[test]
+fn skipping_a_healthy_cache_reports_zero() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy")], &dir.path().join("cache"))
+        .unwrap();
+
+    let options = skip();
+    let skipped = ContextSelector::default()
+        .select_with(&cache, Query::new("deploy"), 100, &options)
+        .unwrap();
+    assert_eq!(skipped.selection.documents_unreadable, Some(0));
+
+    let json = serde_json::to_value(&skipped).unwrap();
+    assert_eq!(json["selection"]["documents_unreadable"], 0);
+    assert!(json["selection"]["trace"].get("unreadable").is_none());
+    assert_eq!(serde_json::to_value(&options).unwrap()["on_corrupt"], "skip");
+
+    // Absent unless skipping
+    let failing = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    let json = serde_json::to_value(&failing).unwrap();
+    assert!(json["selection"].get("documents_unreadable").is_none());
+    assert!(serde_json::to_value(SelectionOptions::default()).unwrap().get("on_corrupt").is_none());
+}
+
+#[test]
+fn multi_caches_skip_with_namespaced_ids() {
+    let dir = tempdir().unwrap();
+    let docs = damaged(&dir.path().join("docs"));
+    let api = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy api")], &dir.path().join("api"))
+        .unwrap();
+    let multi = MultiCache::new(vec![("docs".to_string(), docs), ("api".to_string(), api)]).unwrap();
+
+    let result = ContextSelector::default()
+        .select_with(&multi, Query::new("deploy"), 100, &skip())
+        .unwrap();
+    assert_eq!(result.documents.len(), 3);
+    assert_eq!(result.selection.trace.unwrap().unreadable[0].id, "docs/b.md");
+}
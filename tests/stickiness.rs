@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, SelectionOptions, Stickiness};
+use context_core::types::{ChurnStats, Query, SelectionResult};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deploy x"),   // 2 tokens, score 0.5
+        make_doc("b.md", "deploy y z"), // 3 tokens, score 0.33
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+fn select(cache: &ContextCache, stickiness: Option<Stickiness>) -> SelectionResult {
+    let options = SelectionOptions {
+        stickiness,
+        ..Default::default()
+    };
+    ContextSelector::default()
+        .select_with(cache, Query::new("deploy"), 3, &options)
+        .unwrap()
+}
+
+fn version_of(cache: &ContextCache, id: &str) -> String {
+    let all = ContextSelector::default().select(cache, Query::new("deploy"), 100).unwrap();
+    all.documents.into_iter().find(|d| d.id == id).unwrap().version
+}
+
+fn ids(result: &SelectionResult) -> Vec<&str> {
+    result.documents.iter().map(|d| d.id.as_str()).collect()
+}
+
+#[test]
+fn previous_documents_keep_their_slots() {
+    let (_dir, cache) = build_cache();
+    let plain = select(&cache, None);
+    assert_eq!(ids(&plain), vec!["a.md"]);
+    assert_eq!(plain.selection.churn, None);
+
+    let b_version = version_of(&cache, "b.md");
+
+    let sticky = select(&cache, Some(Stickiness::new([("b.md", b_version.as_str())], 0.25)));
+    assert_eq!(ids(&sticky), vec!["b.md"]);
+    assert_eq!(sticky.documents[0].score, 0.583333);
+    assert_eq!(
+        sticky.selection.churn,
+        Some(ChurnStats { bonus: 0.25, retained: 1, changed: 0, added: 0, dropped: 0 })
+    );
+}
+
+#[test]
+fn significant_score_shifts_still_win() {
+    let (_dir, cache) = build_cache();
+    let b_version = version_of(&cache, "b.md");
+
+    let result = select(&cache, Some(Stickiness::new([("b.md", b_version.as_str())], 0.1)));
+    assert_eq!(ids(&result), vec!["a.md"]);
+    let churn = result.selection.churn.unwrap();
+    assert_eq!((churn.retained, churn.added, churn.dropped), (0, 1, 1));
+
+    // A changed version gets no bonus
+    let result = select(&cache, Some(Stickiness::new([("b.md", "stale")], 0.25)));
+    assert_eq!(ids(&result), vec!["a.md"]);
+    assert_eq!(result.selection.churn.unwrap().dropped, 1);
+}
+
+#[test]
+fn stickiness_from_a_previous_result() {
+    let (_dir, cache) = build_cache();
+    let first = select(&cache, None);
+    let second = select(&cache, Some(Stickiness::from_result(&first, 0.25)));
+    assert_eq!(ids(&second), ids(&first));
+    assert_eq!(second.documents[0].score, 0.75);
+    assert_eq!(second.selection.churn.unwrap().retained, 1);
+
+    let json = serde_json::to_value(&second.selection).unwrap();
+    assert_eq!(json["churn"]["retained"], 1);
+}
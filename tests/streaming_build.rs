@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use context_core::cache::{CacheBuildConfig, CacheBuildError, CacheBuilder, HashingEmbedder};
+use context_core::document::{Document, DocumentError, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("c.md", "charlie"),
+        make_doc("a.md", "same"),
+        make_doc("b.md", "same"),
+    ]
+}
+
+fn pinned() -> CacheBuildConfig {
+    CacheBuildConfig::v0().with_fixed_timestamp(Utc.timestamp_opt(0, 0).unwrap())
+}
+
+fn read_tree(root: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut out = Vec::new();
+    for dir in [root.to_path_buf(), root.join("documents")] {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_file() {
+                let rel = path.strip_prefix(root).unwrap().to_string_lossy().into_owned();
+                out.push((rel, fs::read(&path).unwrap()));
+            }
+        }
+    }
+    out.sort();
+    out
+}
+
+#[test]
+fn streamed_build_matches_batch_build() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(pinned()).with_embedder(HashingEmbedder::new(8));
+
+    let batch = builder.build(docs(), &dir.path().join("batch")).unwrap();
+    let streamed = builder
+        .build_from_iter(docs().into_iter().map(Ok::<_, DocumentError>), &dir.path().join("stream"))
+        .unwrap();
+
+    assert_eq!(batch.manifest.cache_version, streamed.manifest.cache_version);
+    assert_eq!(read_tree(&dir.path().join("batch")), read_tree(&dir.path().join("stream")));
+    assert!(!dir.path().join("stream/spool").exists());
+    assert_eq!(streamed.load_documents().unwrap().len(), 3);
+}
+
+#[test]
+fn streamed_empty_build() {
+    let dir = tempdir().unwrap();
+    let builder = CacheBuilder::new(pinned());
+
+    let batch = builder.build(Vec::new(), &dir.path().join("batch")).unwrap();
+    let streamed = builder
+        .build_from_iter(std::iter::empty::<Result<Document, DocumentError>>(), &dir.path().join("stream"))
+        .unwrap();
+    assert_eq!(batch.manifest.cache_version, streamed.manifest.cache_version);
+    assert_eq!(streamed.manifest.document_count, 0);
+}
+
+#[test]
+fn source_error_aborts_and_cleans_up() {
+    let dir = tempdir().unwrap();
+    let output = dir.path().join("cache");
+
+    let bad = Document::ingest(make_id("x.md"), "x.md", vec![0xff], Metadata::default());
+    let items = vec![Ok(make_doc("a.md", "alpha")), bad];
+
+    let err = CacheBuilder::new(CacheBuildConfig::v0())
+        .build_from_iter(items, &output)
+        .unwrap_err();
+    assert!(matches!(err, CacheBuildError::Source(_)));
+    assert!(!output.exists());
+    assert!(!output.with_extension("tmp.stream").exists());
+}
+
+#[test]
+fn streamed_duplicate_ids_are_rejected() {
+    let dir = tempdir().unwrap();
+    let output = dir.path().join("cache");
+    let items = vec![make_doc("a.md", "one"), make_doc("a.md", "two")];
+
+    let err = CacheBuilder::new(CacheBuildConfig::v0())
+        .build_from_iter(items.into_iter().map(Ok::<_, DocumentError>), &output)
+        .unwrap_err();
+    assert!(matches!(err, CacheBuildError::DuplicateDocumentId { id, .. } if id == "a.md"));
+    assert!(!output.exists());
+}
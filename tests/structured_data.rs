@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use context_core::document::parser::{StructuredError, StructuredFormat, StructuredParser, ORIGINAL_CONTENT_HASH_KEY};
+use context_core::document::{DocumentId, DocumentVersion, Metadata};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn text(format: StructuredFormat, content: &str) -> String {
+    StructuredParser::new().to_text(format, content).unwrap()
+}
+
+#[test]
+fn json_flattens_to_sorted_key_paths() {
+    let json = r#"{"server": {"port": 8080, "host": "db.internal"}, "tags": ["a", "b"], "debug": false, "extra": {}, "note": "two\nlines"}"#;
+    assert_eq!(
+        text(StructuredFormat::Json, json),
+        "debug = false\nextra = {}\nnote = two\\nlines\nserver host = db.internal\nserver port = 8080\ntags [0] = a\ntags [1] = b"
+    );
+}
+
+#[test]
+fn yaml_matches_the_equivalent_json() {
+    let yaml = r#"---
+# service config
+server:
+  host: db.internal   # primary
+  port: 8080
+tags:
+- a
+- 'b'
+debug: false
+extra: {}
+note: |
+  two
+  lines
+"#;
+    assert_eq!(
+        text(StructuredFormat::Yaml, yaml),
+        text(StructuredFormat::Json, r#"{"server": {"host": "db.internal", "port": "8080"}, "tags": ["a", "b"], "debug": "false", "extra": {}, "note": "two\nlines"}"#)
+    );
+}
+
+#[test]
+fn yaml_sequences_of_mappings_and_flow_collections() {
+    let yaml = "jobs:\n  - name: build\n    needs: [lint, \"test\"]\n  - name: deploy\n    env: {stage: prod}\n    empty:\n";
+    assert_eq!(
+        text(StructuredFormat::Yaml, yaml),
+        "jobs [0] name = build\njobs [0] needs [0] = lint\njobs [0] needs [1] = test\n\
+         jobs [1] empty = null\njobs [1] env stage = prod\njobs [1] name = deploy"
+    );
+}
+
+#[test]
+fn yaml_errors_carry_line_numbers() {
+    let err = StructuredParser::new()
+        .to_text(StructuredFormat::Yaml, "a: 1\n    b: 2\n")
+        .unwrap_err();
+    assert!(matches!(err, StructuredError::Yaml { line: 2, .. }), "{err}");
+    let err = StructuredParser::new()
+        .to_text(StructuredFormat::Yaml, "a: 1\na: 2\n")
+        .unwrap_err();
+    assert!(matches!(err, StructuredError::Yaml { line: 2, .. }), "{err}");
+}
+
+#[test]
+fn csv_rows_are_keyed_by_header() {
+    let csv = "name,role\r\nalice,\"admin, ops\"\r\n\r\nbob,\"says \"\"hi\"\"\"\r\n";
+    assert_eq!(
+        text(StructuredFormat::Csv, csv),
+        "[0] name = alice\n[0] role = admin, ops\n[1] name = bob\n[1] role = says \"hi\""
+    );
+    let err = StructuredParser::new()
+        .to_text(StructuredFormat::Csv, "a,b\n1,2\n3\n")
+        .unwrap_err();
+    assert!(matches!(err, StructuredError::Csv { line: 3, .. }), "{err}");
+}
+
+#[test]
+fn caps_limit_rows_and_keys() {
+    let parser = StructuredParser::new().max_rows(2).max_keys(1);
+    let csv = "name,role\na,x\nb,y\nc,z\n";
+    assert_eq!(
+        parser.to_text(StructuredFormat::Csv, csv).unwrap(),
+        "[0] name = a\n[0] (1 more keys)\n[1] name = b\n[1] (1 more keys)\n(1 more items)"
+    );
+}
+
+#[test]
+fn formats_by_extension() {
+    assert_eq!(StructuredFormat::from_path("conf/app.YML"), Some(StructuredFormat::Yaml));
+    assert_eq!(StructuredFormat::from_path("data.csv"), Some(StructuredFormat::Csv));
+    assert_eq!(StructuredFormat::from_path("README.md"), None);
+}
+
+#[test]
+fn parse_records_the_original_hash() {
+    let raw = br#"{"b": 1, "a": 2}"#;
+    let doc = StructuredParser::new()
+        .parse(make_id("conf/app.json"), "conf/app.json".to_string(), raw.to_vec(), Metadata::default())
+        .unwrap();
+    assert_eq!(doc.content, "a = 2\nb = 1");
+    let original = DocumentVersion::from_content(raw);
+    assert_eq!(doc.metadata.get(ORIGINAL_CONTENT_HASH_KEY).and_then(|v| v.as_str()), Some(original.as_str()));
+
+    let err = StructuredParser::new()
+        .parse(make_id("notes.txt"), "notes.txt".to_string(), b"x".to_vec(), Metadata::default())
+        .unwrap_err();
+    assert!(matches!(err, StructuredError::UnknownFormat(_)));
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "tantivy")]
+
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentError, DocumentId, Metadata};
+use context_core::selection::{CandidateError, ContextSelector, TantivyCandidateSource, TANTIVY_INDEX_FILE};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    vec![
+        make_doc("docs/deploy.md", "deploy deploy the service with the deploy script"),
+        make_doc("docs/rollback.md", "rollback a failed deploy"),
+        make_doc("docs/intro.md", "an introduction to the project"),
+        make_doc("docs/faq.md", "frequently asked questions"),
+    ]
+}
+
+fn config() -> CacheBuildConfig {
+    CacheBuildConfig::v0().with_tantivy_index()
+}
+
+#[test]
+fn index_is_reproducible() {
+    let dir = tempdir().unwrap();
+    let first = CacheBuilder::new(config()).build(docs(), &dir.path().join("one")).unwrap();
+    let mut reversed = docs();
+    reversed.reverse();
+    let second = CacheBuilder::new(config()).build(reversed, &dir.path().join("two")).unwrap();
+
+    let bytes = fs::read(first.root.join(TANTIVY_INDEX_FILE)).unwrap();
+    assert_eq!(bytes, fs::read(second.root.join(TANTIVY_INDEX_FILE)).unwrap());
+    assert_eq!(first.manifest.cache_version, second.manifest.cache_version);
+    assert_eq!(first.manifest.tree_hash, second.manifest.tree_hash);
+
+    // The index is part of the cache version
+    let plain = CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &dir.path().join("plain")).unwrap();
+    assert_ne!(plain.manifest.cache_version, first.manifest.cache_version);
+    assert!(!plain.root.join(TANTIVY_INDEX_FILE).exists());
+}
+
+#[test]
+fn ranks_candidates_and_selects_from_them() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(config()).build(docs(), &dir.path().join("cache")).unwrap();
+    let source = TantivyCandidateSource::open(&cache).unwrap();
+
+    let ids = source.candidates(&Query::new("deploy"), 10).unwrap();
+    let ids: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
+    assert_eq!(ids, vec!["docs/deploy.md", "docs/rollback.md"]);
+    assert_eq!(source.candidates(&Query::new("deploy"), 1).unwrap().len(), 1);
+    assert!(source.candidates(&Query::new("nothing"), 10).unwrap().is_empty());
+
+    let candidates = source.load(&cache, &Query::new("deploy"), 10).unwrap();
+    assert_eq!(candidates.len(), 2);
+    let result = ContextSelector::default().select(&candidates, Query::new("deploy"), 1000).unwrap();
+    let full = ContextSelector::default().select(&cache, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(result.documents[0].id, full.documents[0].id);
+    assert_eq!(result.documents[0].score, full.documents[0].score);
+    assert!(result.documents.len() <= 2);
+}
+
+#[test]
+fn memory_caches_carry_the_index() {
+    let cache = CacheBuilder::new(config()).build_in_memory(docs()).unwrap();
+    let source = TantivyCandidateSource::from_memory(&cache).unwrap();
+    let candidates = source.load_memory(&cache, &Query::new("rollback"), 10).unwrap();
+    assert_eq!(candidates.documents()[0].id.as_str(), "docs/rollback.md");
+
+    let plain = CacheBuilder::new(CacheBuildConfig::v0()).build_in_memory(docs()).unwrap();
+    assert!(matches!(TantivyCandidateSource::from_memory(&plain), Err(CandidateError::MissingIndex)));
+}
+
+#[test]
+fn missing_and_tampered_indexes_are_errors() {
+    let dir = tempdir().unwrap();
+    let plain = CacheBuilder::new(CacheBuildConfig::v0()).build(docs(), &dir.path().join("plain")).unwrap();
+    assert!(matches!(TantivyCandidateSource::open(&plain), Err(CandidateError::MissingIndex)));
+
+    let cache = CacheBuilder::new(config()).build(docs(), &dir.path().join("cache")).unwrap();
+    cache.verify().unwrap();
+    let path = cache.root.join(TANTIVY_INDEX_FILE);
+    let mut bytes = fs::read(&path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    fs::write(&path, bytes).unwrap();
+    assert!(cache.verify().is_err());
+}
+
+#[test]
+fn streaming_builds_write_the_same_index() {
+    let dir = tempdir().unwrap();
+    let built = CacheBuilder::new(config()).build(docs(), &dir.path().join("built")).unwrap();
+    let streamed = CacheBuilder::new(config())
+        .build_from_iter(docs().into_iter().map(Ok::<_, DocumentError>), &dir.path().join("streamed"))
+        .unwrap();
+    assert_eq!(streamed.manifest.cache_version, built.manifest.cache_version);
+    assert_eq!(
+        fs::read(streamed.root.join(TANTIVY_INDEX_FILE)).unwrap(),
+        fs::read(built.root.join(TANTIVY_INDEX_FILE)).unwrap()
+    );
+}
+
+#[test]
+fn narrows_selections_as_a_candidate_source() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(config()).build(docs(), &dir.path().join("cache")).unwrap();
+    let selector = ContextSelector::default().with_candidate_source(TantivyCandidateSource::open(&cache).unwrap());
+    let result = selector.select(&cache, Query::new("deploy"), 1000).unwrap();
+    let ids: Vec<&str> = result.documents.iter().map(|doc| doc.id.as_str()).collect();
+    assert_eq!(ids, vec!["docs/deploy.md", "docs/rollback.md"]);
+    assert_eq!(result.selection.candidate_count, Some(2));
+    assert!(!result.selection.candidate_fallback);
+
+    // Another cache, or a query without terms, is a full scan
+    let other = CacheBuilder::new(config()).build(docs()[..3].to_vec(), &dir.path().join("other")).unwrap();
+    let result = selector.select(&other, Query::new("deploy"), 1000).unwrap();
+    assert_eq!(result.selection.candidate_count, Some(3));
+    assert!(result.selection.candidate_fallback);
+    let result = selector.select(&cache, Query::new(""), 1000).unwrap();
+    assert!(result.selection.candidate_fallback);
+}
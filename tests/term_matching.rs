@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    AnalyzedScorer, ApproxTokenCounter, ContextSelector, Scorer, TermMatching, TermPattern, WhitespaceAnalyzer,
+};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn wildcard_patterns() {
+    let matches = |pattern: &str, word: &str| TermPattern::compile(pattern, TermMatching::Wildcard).matches(word);
+    assert!(matches("deploy*", "deploy"));
+    assert!(matches("deploy*", "deployment"));
+    assert!(matches("d?ploy", "deploy"));
+    assert!(matches("*ploy*", "redeployed"));
+    assert!(matches("*a*b*c", "xaxxbxc"));
+    assert!(!matches("deploy*", "redeploy"));
+    assert!(!matches("d?ploy", "dploy"));
+
+    // Exact mode never interprets wildcards
+    assert!(!TermPattern::compile("deploy*", TermMatching::Exact).matches("deployment"));
+    assert!(TermPattern::compile("deploy*", TermMatching::Exact).matches("deploy*"));
+}
+
+#[test]
+fn exact_matching_is_the_default() {
+    let doc = make_doc("a.md", "deploy deployment deployed");
+    let query = Query::new("deploy*");
+    let exact = AnalyzedScorer::new(WhitespaceAnalyzer);
+    assert_eq!(exact.score(&doc, &query).term_matches, 0);
+
+    let wildcard = AnalyzedScorer::new(WhitespaceAnalyzer).with_matching(TermMatching::Wildcard);
+    assert_eq!(wildcard.score(&doc, &query).term_matches, 3);
+    assert_ne!(exact.fingerprint(), wildcard.fingerprint());
+}
+
+#[test]
+fn wildcard_selection_is_deterministic() {
+    let dir = tempdir().unwrap();
+    let docs = vec![
+        make_doc("a.md", "deployment guide"),
+        make_doc("b.md", "redeploy steps"),
+        make_doc("c.md", "deploys deployed"),
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    let selector = ContextSelector::new(
+        AnalyzedScorer::new(WhitespaceAnalyzer).with_matching(TermMatching::Wildcard),
+        ApproxTokenCounter,
+    );
+
+    let r1 = selector.select(&cache, Query::new("deploy*"), 1000).unwrap();
+    let r2 = selector.select(&cache, Query::new("deploy*"), 1000).unwrap();
+    assert_eq!(serde_json::to_string(&r1).unwrap(), serde_json::to_string(&r2).unwrap());
+    let ids: Vec<&str> = r1.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["c.md", "a.md", "b.md"]);
+    assert_eq!(r1.documents[2].score, 0.0);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_terms_match_whole_words() {
+    let matches = |pattern: &str, word: &str| TermPattern::compile(pattern, TermMatching::Regex).matches(word);
+    assert!(matches("/deploy(ment)?/", "deploy"));
+    assert!(matches("/deploy(ment)?/", "deployment"));
+    assert!(!matches("/deploy(ment)?/", "deployments"));
+    assert!(!matches("/deploy/", "redeploy"));
+    // Wildcards still work, and invalid patterns are literal
+    assert!(matches("deploy*", "deployed"));
+    assert!(matches("/(/", "/(/"));
+
+    let scorer = AnalyzedScorer::new(WhitespaceAnalyzer).with_matching(TermMatching::Regex);
+    let doc = make_doc("a.md", "deploy the deployment, deployments wait");
+    assert_eq!(scorer.score(&doc, &Query::new("/deploy(ment)?/")).term_matches, 1);
+    assert_eq!(scorer.score(&doc, &Query::new("/deploy(ment)?,?/")).term_matches, 2);
+}
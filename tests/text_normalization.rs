@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::document::normalize::{newlines, nfc};
+use context_core::document::{Document, DocumentId, DocumentVersion, Metadata, Normalization};
+use tempfile::{tempdir, TempDir};
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn build(config: CacheBuildConfig, content: &str) -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(config)
+        .build(vec![make_doc("a.md", content)], &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn newlines_become_lf() {
+    assert_eq!(newlines("a\r\nb\rc\n"), "a\nb\nc\n");
+    assert_eq!(newlines("\r\r\n"), "\n\n");
+    assert!(matches!(newlines("a\nb"), std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn nfc_composes_and_orders_marks() {
+    // e + combining acute
+    assert_eq!(nfc("cafe\u{301}"), "café");
+    // Marks are ordered by combining class: dot below (220) before
+    // circumflex (230), then both compose into one character
+    assert_eq!(nfc("a\u{302}\u{323}"), "\u{1ead}");
+    // A blocked mark stays separate
+    assert_eq!(nfc("a\u{301}\u{301}"), "á\u{301}");
+    // Singletons decompose: the Ohm sign is Omega
+    assert_eq!(nfc("\u{2126}"), "\u{3a9}");
+    // Hangul jamo compose algorithmically
+    assert_eq!(nfc("\u{1100}\u{1161}\u{11a8}"), "각");
+    // Composition exclusions stay decomposed
+    assert_eq!(nfc("\u{915}\u{93c}"), "\u{915}\u{93c}");
+    assert!(matches!(nfc("plain ascii"), std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn passes_are_idempotent() {
+    let normalization = Normalization::all();
+    let once = normalization.apply("Cafe\u{301}\r\nline\r").into_owned();
+    assert_eq!(once, "Café\nline\n");
+    assert_eq!(normalization.apply(&once), once.as_str());
+}
+
+#[test]
+fn crlf_checkouts_build_the_same_cache() {
+    let config = CacheBuildConfig::v0().with_normalization(Normalization {
+        newlines: true,
+        nfc: false,
+    });
+    let (_unix, unix) = build(config.clone(), "# Guide\nDeploy.\n");
+    let (_windows, windows) = build(config, "# Guide\r\nDeploy.\r\n");
+    assert_eq!(unix.manifest.cache_version, windows.manifest.cache_version);
+
+    let docs = windows.load_documents().unwrap();
+    assert_eq!(docs[0].content, "# Guide\nDeploy.\n");
+    assert_eq!(docs[0].version, DocumentVersion::from_content(b"# Guide\nDeploy.\n"));
+
+    // Without normalization they differ
+    let (_unix, unix) = build(CacheBuildConfig::v0(), "# Guide\nDeploy.\n");
+    let (_windows, windows) = build(CacheBuildConfig::v0(), "# Guide\r\nDeploy.\r\n");
+    assert_ne!(unix.manifest.cache_version, windows.manifest.cache_version);
+}
+
+#[test]
+fn decomposed_text_builds_the_same_cache() {
+    let config = CacheBuildConfig::v0().with_normalization(Normalization {
+        newlines: false,
+        nfc: true,
+    });
+    let (_composed, composed) = build(config.clone(), "café");
+    let (_decomposed, decomposed) = build(config, "cafe\u{301}");
+    assert_eq!(composed.manifest.cache_version, decomposed.manifest.cache_version);
+}
+
+#[test]
+fn settings_are_part_of_the_cache_version() {
+    let (_plain, plain) = build(CacheBuildConfig::v0(), "text\n");
+    let (_none, none) = build(CacheBuildConfig::v0().with_normalization(Normalization::default()), "text\n");
+    let (_all, all) = build(CacheBuildConfig::v0().with_normalization(Normalization::all()), "text\n");
+    assert_ne!(plain.manifest.cache_version, none.manifest.cache_version);
+    assert_ne!(none.manifest.cache_version, all.manifest.cache_version);
+
+    let json = serde_json::to_string(&Normalization::all()).unwrap();
+    assert_eq!(json, r#"{"newlines":true,"nfc":true}"#);
+    assert_eq!(serde_json::to_string(&Normalization::default()).unwrap(), "{}");
+}
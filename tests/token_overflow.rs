@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, MemoryCache, StatsOptions};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{
+    BudgetConfig, BudgetQuota, ContextSelector, QuotaScope, SelectionOptions, TermFrequencyScorer, TokenCounter,
+};
+use context_core::types::Query;
+
+/// Counts every text as `tokens` tokens, however short.
+struct Fixed {
+    tokens: usize,
+}
+
+impl TokenCounter for Fixed {
+    fn count_tokens(&self, _content: &str) -> usize {
+        self.tokens
+    }
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn cache() -> MemoryCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build_in_memory(vec![
+            make_doc("a.md", "deploy alpha"),
+            make_doc("b.md", "deploy beta"),
+            make_doc("c.md", "deploy gamma"),
+        ])
+        .unwrap()
+}
+
+fn selector(tokens: usize) -> ContextSelector<TermFrequencyScorer, Fixed> {
+    ContextSelector::new(TermFrequencyScorer, Fixed { tokens }).with_max_budget(u64::MAX)
+}
+
+/// How many of the three documents, each costing `cost`, fit `u64::MAX`.
+fn fitting(cost: u64) -> usize {
+    (u64::MAX / cost).min(3) as usize
+}
+
+#[test]
+fn sums_beyond_usize_do_not_wrap() {
+    let cache = cache();
+    // Two of these overflow usize on every target
+    let half = usize::MAX / 2 + 1;
+    let result = selector(half).select(&cache, Query::new("deploy"), u64::MAX).unwrap();
+
+    let selected = fitting(half as u64);
+    assert_eq!(result.documents.len(), selected);
+    assert!(result.documents.iter().all(|doc| doc.tokens == half as u64));
+    assert_eq!(result.selection.tokens_used, half as u64 * selected as u64);
+    assert_eq!(result.selection.documents_excluded_by_budget, 3 - selected);
+}
+
+#[test]
+fn maximal_counts_and_overheads_saturate() {
+    let cache = cache();
+    let result = selector(usize::MAX).select(&cache, Query::new("deploy"), u64::MAX).unwrap();
+    let selected = fitting(usize::MAX as u64);
+    assert_eq!(result.documents.len(), selected);
+    assert_eq!(result.selection.tokens_used, usize::MAX as u64 * selected as u64);
+
+    let budget = BudgetConfig::new(u64::MAX).per_document_overhead(u64::MAX - 1);
+    let result = selector(usize::MAX).select(&cache, Query::new("deploy"), budget).unwrap();
+    assert_eq!(result.documents.len(), 1);
+    assert_eq!(result.selection.tokens_used, u64::MAX);
+    assert_eq!(result.selection.documents_excluded_by_budget, 2);
+}
+
+#[test]
+fn quotas_of_huge_budgets_are_exact() {
+    let cache = cache();
+    let options = SelectionOptions {
+        quotas: vec![BudgetQuota::new(QuotaScope::Path("*.md".into())).max_percent(50)],
+        ..SelectionOptions::default()
+    };
+    let half = u64::MAX / 2;
+    let result = selector(1).select_with(&cache, Query::new("deploy"), u64::MAX, &options).unwrap();
+    assert_eq!(result.documents.len(), 3);
+    assert_eq!(result.selection.quotas[0].used, 3);
+
+    // 50% of u64::MAX is exactly u64::MAX / 2: two documents costing a
+    // quarter of the budget each fit under the cap, a third does not
+    let budget = BudgetConfig::new(u64::MAX).per_document_overhead(half / 2 - 1);
+    let result = selector(1).select_with(&cache, Query::new("deploy"), budget, &options).unwrap();
+    assert_eq!(result.documents.len(), 2);
+    assert_eq!(result.selection.documents_excluded_by_quota, Some(1));
+    assert_eq!(result.selection.tokens_used, half / 2 * 2);
+    assert_eq!(result.selection.quotas[0].used, half / 2 * 2);
+}
+
+#[test]
+fn stats_totals_saturate() {
+    let stats = cache().stats_with(&Fixed { tokens: usize::MAX }, &StatsOptions::default());
+    assert_eq!(stats.documents, 3);
+    assert_eq!(stats.total_tokens, (usize::MAX as u64).saturating_mul(3));
+    assert_eq!(stats.prefixes[""].tokens, stats.total_tokens);
+}
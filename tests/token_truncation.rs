@@ -0,0 +1,53 @@
+use context_core::selection::{ApproxTokenCounter, BytesPerTokenCounter, TokenCounter, TokenizerRegistry};
+
+/// One token per whitespace-separated word; relies on the default
+/// `truncate_to`.
+struct WordCounter;
+
+impl TokenCounter for WordCounter {
+    fn count_tokens(&self, content: &str) -> usize {
+        content.split_whitespace().count()
+    }
+}
+
+fn check(counter: &dyn TokenCounter, content: &str, max_tokens: usize) -> (String, usize) {
+    let (prefix, tokens) = counter.truncate_to(content, max_tokens);
+    assert!(content.starts_with(prefix));
+    assert!(tokens <= max_tokens || prefix == content);
+    assert_eq!(tokens, counter.count_tokens(prefix));
+    (prefix.to_string(), tokens)
+}
+
+#[test]
+fn approx_truncation_respects_char_boundaries() {
+    let counter = ApproxTokenCounter;
+    assert_eq!(check(&counter, "abcdefghij", 2), ("abcdefgh".to_string(), 2));
+    assert_eq!(check(&counter, "abc", 5), ("abc".to_string(), 1));
+    assert_eq!(check(&counter, "abc", 0), (String::new(), 0));
+
+    // "ab" + 3-byte char: cutting at byte 4 would split it
+    assert_eq!(check(&counter, "ab部署", 1), ("ab".to_string(), 1));
+    assert_eq!(check(&counter, "ab部署服", 2), ("ab部署".to_string(), 2));
+}
+
+#[test]
+fn bytes_per_token_truncation() {
+    let counter = BytesPerTokenCounter::new(7, 2);
+    let text = "x".repeat(100);
+    let (prefix, tokens) = check(&counter, &text, 10);
+    assert_eq!(prefix.len(), 35);
+    assert_eq!(tokens, 10);
+
+    let registry = TokenizerRegistry::new();
+    let claude = registry.get("claude-3").unwrap();
+    assert_eq!(claude.truncate_to(&text, 10), counter.truncate_to(&text, 10));
+}
+
+#[test]
+fn default_truncation_finds_longest_prefix() {
+    let counter = WordCounter;
+    assert_eq!(check(&counter, "one two three four", 2).0, "one two ");
+    assert_eq!(check(&counter, "one two three four", 4).0, "one two three four");
+    assert_eq!(check(&counter, "部署 服务 测试", 1).0, "部署 ");
+    assert_eq!(check(&counter, "é", 0).0, "");
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{ContextSelector, TermFrequencyScorer, TokenCounter, TokenizerRegistry};
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+/// One token per whitespace-separated word.
+struct WordCounter;
+
+impl TokenCounter for WordCounter {
+    fn count_tokens(&self, content: &str) -> usize {
+        content.split_whitespace().count()
+    }
+}
+
+#[test]
+fn built_in_counters() {
+    let registry = TokenizerRegistry::new();
+    assert_eq!(registry.names().collect::<Vec<_>>(), vec!["approx", "claude-3", "gpt-4o"]);
+
+    let text = "0123456789"; // 10 bytes
+    assert_eq!(registry.get("approx").unwrap().count_tokens(text), 3);
+    assert_eq!(registry.get("gpt-4o").unwrap().count_tokens(text), 3);
+    assert_eq!(registry.get("claude-3").unwrap().count_tokens(text), 3);
+    assert_eq!(registry.get("claude-3").unwrap().count_tokens(&text.repeat(10)), 29);
+    assert!(registry.get("unknown").is_none());
+    assert!(TokenizerRegistry::empty().get("approx").is_none());
+}
+
+#[test]
+fn custom_counters_can_be_registered() {
+    let mut registry = TokenizerRegistry::new();
+    registry.register("words", WordCounter);
+    registry.register("approx", WordCounter);
+
+    assert_eq!(registry.get("words").unwrap().count_tokens("a b c"), 3);
+    assert_eq!(registry.get("approx").unwrap().count_tokens("a b c"), 3);
+    assert_ne!(
+        registry.get("words").unwrap().fingerprint(),
+        TokenizerRegistry::new().get("approx").unwrap().fingerprint()
+    );
+}
+
+#[test]
+fn chosen_name_is_recorded_in_metadata() {
+    let dir = tempdir().unwrap();
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy the service")], &dir.path().join("cache"))
+        .unwrap();
+
+    let mut registry = TokenizerRegistry::new();
+    registry.register("words", WordCounter);
+    let selector = ContextSelector::new(TermFrequencyScorer, registry.get("words").unwrap());
+    let result = selector.select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(result.selection.tokenizer.as_deref(), Some("words"));
+    assert_eq!(result.documents[0].tokens, 3);
+
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+    assert_eq!(plain.selection.tokenizer, None);
+    assert!(!serde_json::to_string(&plain).unwrap().contains("tokenizer"));
+}
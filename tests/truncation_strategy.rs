@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache};
+use context_core::compression::{ExtractiveCompressor, TruncationStrategy};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::{BytesPerTokenCounter, ContextSelector, SelectionOptions};
+use context_core::types::{BudgetVerdict, Query};
+use tempfile::{tempdir, TempDir};
+
+const CONTENT: &str = "# Title\nintro text\n## Part\nbody ends here";
+
+fn make_id(s: &str) -> DocumentId {
+    let root = Path::new("/root");
+    let path = root.join(s);
+    DocumentId::from_path(root, &path).unwrap()
+}
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let id = make_id(id_str);
+    Document::ingest(
+        id,
+        id_str.to_string(),
+        content.as_bytes().to_vec(),
+        Metadata::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn strategies_keep_different_parts() {
+    // One token per byte
+    let counter = BytesPerTokenCounter::new(1, 1);
+    let truncate = |strategy: TruncationStrategy, max| strategy.truncate(CONTENT, &counter, max);
+
+    assert_eq!(truncate(TruncationStrategy::Head, 10).as_deref(), Some("# Title\nin"));
+    assert_eq!(truncate(TruncationStrategy::Tail, 10).as_deref(), Some(" ends here"));
+    // 7 bytes of marker, then 5 + 5 characters
+    assert_eq!(truncate(TruncationStrategy::MiddleOut, 17).as_deref(), Some("# Tit\n[...]\n here"));
+    assert_eq!(truncate(TruncationStrategy::HeadingsOnly, 15).as_deref(), Some("# Title\n## Part"));
+    assert_eq!(truncate(TruncationStrategy::HeadingsOnly, 10).as_deref(), Some("# Title"));
+}
+
+#[test]
+fn fitting_content_is_kept_and_nothing_fitting_is_none() {
+    let counter = BytesPerTokenCounter::new(1, 1);
+    assert_eq!(TruncationStrategy::Tail.truncate(CONTENT, &counter, 100).as_deref(), Some(CONTENT));
+    assert_eq!(TruncationStrategy::Head.truncate(CONTENT, &counter, 0), None);
+    // The marker alone does not fit
+    assert_eq!(TruncationStrategy::MiddleOut.truncate(CONTENT, &counter, 7), None);
+    assert_eq!(TruncationStrategy::HeadingsOnly.truncate("no headings", &counter, 5), None);
+}
+
+#[test]
+fn cuts_respect_char_boundaries() {
+    let counter = BytesPerTokenCounter::new(1, 1);
+    let text = "ééééé";
+    assert_eq!(TruncationStrategy::Head.truncate(text, &counter, 5).as_deref(), Some("éé"));
+    assert_eq!(TruncationStrategy::Tail.truncate(text, &counter, 5).as_deref(), Some("éé"));
+}
+
+fn build_cache() -> (TempDir, ContextCache) {
+    let dir = tempdir().unwrap();
+    let long = format!("deploy {}", "x".repeat(60));
+    let docs = vec![
+        make_doc("a.md", "deploy deploy deploy"), // 5 tokens, score 1.0
+        make_doc("b.md", &long),                  // 17 tokens, score 0.5
+    ];
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .build(docs, &dir.path().join("cache"))
+        .unwrap();
+    (dir, cache)
+}
+
+#[test]
+fn budgeting_falls_back_to_truncation() {
+    let (_dir, cache) = build_cache();
+    let options = SelectionOptions {
+        trace: true,
+        ..Default::default()
+    };
+
+    let plain = ContextSelector::default().select(&cache, Query::new("deploy"), 10).unwrap();
+    assert_eq!(plain.documents.len(), 1);
+    assert_eq!(plain.selection.documents_truncated, None);
+
+    let selector = ContextSelector::default().with_truncation(TruncationStrategy::Head);
+    let result = selector.select_with(&cache, Query::new("deploy"), 10, &options).unwrap();
+
+    let b = &result.documents[1];
+    assert_eq!(b.id, "b.md");
+    assert_eq!(b.content, format!("deploy {}", "x".repeat(13)));
+    assert_eq!(b.tokens, 5);
+    assert_eq!(b.original_tokens, Some(17));
+    assert_eq!(b.truncation, Some(TruncationStrategy::Head));
+    assert_eq!(result.documents[0].truncation, None);
+
+    assert_eq!(result.selection.tokens_used, 10);
+    assert_eq!(result.selection.documents_excluded_by_budget, 0);
+    assert_eq!(result.selection.documents_truncated, Some(1));
+    assert_eq!(result.selection.trace.unwrap().budget[1].verdict, BudgetVerdict::Truncated);
+    assert!(serde_json::to_string(b).unwrap().contains(r#""original_tokens":17,"truncation":"head","#));
+}
+
+#[test]
+fn truncation_follows_failed_compression() {
+    let (_dir, cache) = build_cache();
+    let selector = ContextSelector::default()
+        .with_compression(ExtractiveCompressor::new())
+        .with_truncation(TruncationStrategy::MiddleOut);
+    let result = selector.select(&cache, Query::new("deploy"), 10).unwrap();
+
+    // b.md is one sentence, too long to compress
+    assert_eq!(result.selection.documents_compressed, Some(0));
+    assert_eq!(result.selection.documents_truncated, Some(1));
+    assert_eq!(result.documents[1].truncation, Some(TruncationStrategy::MiddleOut));
+    assert!(result.documents[1].content.contains("[...]"));
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "http")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use context_core::document::parser::{
+    ingest_url, FetchMode, UrlError, UrlIngestOptions, ETAG_KEY, FETCHED_AT_KEY, URL_KEY,
+};
+use context_core::document::{DocumentVersion, Source};
+use tempfile::tempdir;
+
+// The resource served at `/guide`: its ETag and content
+type Resource = Arc<Mutex<(String, String)>>;
+
+struct Server {
+    base: String,
+    requests: Arc<AtomicUsize>,
+    resource: Resource,
+}
+
+// Serves `/guide` (with ETag revalidation), `/old` (a redirect to it) and
+// 404 for anything else
+fn serve() -> Server {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let resource: Resource = Arc::new(Mutex::new(("\"v1\"".to_string(), "# Guide\n\nrollback\n".to_string())));
+
+    let (count, served) = (requests.clone(), resource.clone());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            count.fetch_add(1, Ordering::SeqCst);
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut if_none_match = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("if-none-match") {
+                        if_none_match = Some(value.trim().to_string());
+                    }
+                }
+            }
+            let path = request_line.split(' ').nth(1).unwrap_or("/");
+            let (etag, body) = served.lock().unwrap().clone();
+            let response = match path {
+                "/guide" if if_none_match.as_deref() == Some(etag.as_str()) => {
+                    format!("HTTP/1.1 304 Not Modified\r\nETag: {}\r\nConnection: close\r\n\r\n", etag)
+                }
+                "/guide" => format!(
+                    "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    etag,
+                    body.len(),
+                    body
+                ),
+                "/old" => "HTTP/1.1 301 Moved\r\nLocation: /guide\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    Server {
+        base,
+        requests,
+        resource,
+    }
+}
+
+fn pinned(dir: &std::path::Path, mode: FetchMode) -> UrlIngestOptions {
+    UrlIngestOptions {
+        pin_dir: Some(dir.to_path_buf()),
+        mode,
+        ..UrlIngestOptions::default()
+    }
+}
+
+#[test]
+fn fetches_and_records_provenance() {
+    let server = serve();
+    let url = format!("{}/guide", server.base);
+
+    let doc = ingest_url(&url, &UrlIngestOptions::default()).unwrap();
+    assert_eq!(doc.content, "# Guide\n\nrollback\n");
+    assert_eq!(doc.version, DocumentVersion::from_content(b"# Guide\n\nrollback\n"));
+    assert_eq!(doc.id.as_str(), format!("{}/guide", server.base.trim_start_matches("http://")));
+    assert_eq!(doc.metadata.get(URL_KEY).and_then(|v| v.as_str()), Some(url.as_str()));
+    assert_eq!(doc.metadata.get(ETAG_KEY).and_then(|v| v.as_str()), Some("\"v1\""));
+    let Source::Url { url: source_url, fetched_at } = &doc.source else {
+        panic!("expected a URL source, got {:?}", doc.source);
+    };
+    assert_eq!(source_url, &url);
+    assert_eq!(doc.metadata.get(FETCHED_AT_KEY).and_then(|v| v.as_str()), Some(fetched_at.to_rfc3339().as_str()));
+}
+
+#[test]
+fn follows_redirects() {
+    let server = serve();
+    let doc = ingest_url(&format!("{}/old", server.base), &UrlIngestOptions::default()).unwrap();
+    assert_eq!(doc.content, "# Guide\n\nrollback\n");
+    assert_eq!(server.requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn http_errors_are_reported() {
+    let server = serve();
+    let result = ingest_url(&format!("{}/missing", server.base), &UrlIngestOptions::default());
+    assert!(matches!(result, Err(UrlError::Status { status: 404, .. })));
+}
+
+#[test]
+fn offline_refuses_unpinned_urls() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let url = format!("{}/guide", server.base);
+
+    let result = ingest_url(&url, &pinned(dir.path(), FetchMode::Offline));
+    assert!(matches!(result, Err(UrlError::NotCached(_))));
+    let result = ingest_url(&url, &UrlIngestOptions {
+        mode: FetchMode::Offline,
+        ..UrlIngestOptions::default()
+    });
+    assert!(matches!(result, Err(UrlError::NotCached(_))));
+    assert_eq!(server.requests.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn pinned_responses_rebuild_identically_offline() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let url = format!("{}/guide", server.base);
+
+    let fetched = ingest_url(&url, &pinned(dir.path(), FetchMode::CacheFirst)).unwrap();
+    assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+
+    // The server changes, but pinned builds do not see it
+    *server.resource.lock().unwrap() = ("\"v2\"".to_string(), "changed".to_string());
+    let cached = ingest_url(&url, &pinned(dir.path(), FetchMode::CacheFirst)).unwrap();
+    let offline = ingest_url(&url, &pinned(dir.path(), FetchMode::Offline)).unwrap();
+    assert_eq!(cached, fetched);
+    assert_eq!(offline, fetched);
+    assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn revalidation_uses_the_etag() {
+    let server = serve();
+    let dir = tempdir().unwrap();
+    let url = format!("{}/guide", server.base);
+
+    let first = ingest_url(&url, &pinned(dir.path(), FetchMode::Revalidate)).unwrap();
+    // Unchanged: a 304, answered from the pin
+    let second = ingest_url(&url, &pinned(dir.path(), FetchMode::Revalidate)).unwrap();
+    assert_eq!(second.content, first.content);
+    assert_eq!(server.requests.load(Ordering::SeqCst), 2);
+
+    *server.resource.lock().unwrap() = ("\"v2\"".to_string(), "changed".to_string());
+    let third = ingest_url(&url, &pinned(dir.path(), FetchMode::Revalidate)).unwrap();
+    assert_eq!(third.content, "changed");
+    assert_ne!(third.version, first.version);
+    assert_eq!(third.metadata.get(ETAG_KEY).and_then(|v| v.as_str()), Some("\"v2\""));
+
+    // The pin follows the latest response
+    let offline = ingest_url(&url, &pinned(dir.path(), FetchMode::Offline)).unwrap();
+    assert_eq!(offline, third);
+}
+
+#[test]
+fn rejects_non_http_urls() {
+    for url in ["file:///etc/passwd", "ftp://example.com/a", "https://", "example.com/a"] {
+        let result = ingest_url(url, &UrlIngestOptions::default());
+        assert!(matches!(result, Err(UrlError::InvalidUrl(_))), "{}", url);
+    }
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::Path;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, CacheReadError, ContextCache, VerificationLevel};
+use context_core::document::{Document, DocumentId, Metadata};
+use context_core::selection::ContextSelector;
+use context_core::types::Query;
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn build(path: &Path) -> ContextCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .build(vec![make_doc("a.md", "deploy alpha"), make_doc("b.md", "deploy beta")], path)
+        .unwrap()
+}
+
+fn open(path: &Path, level: VerificationLevel) -> ContextCache {
+    ContextCache::open(path).unwrap().with_verification(level)
+}
+
+#[test]
+fn every_level_loads_a_sound_cache_identically() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    build(&path);
+    assert_eq!(ContextCache::open(&path).unwrap().verification(), VerificationLevel::Full);
+
+    let full = open(&path, VerificationLevel::Full);
+    let expected = ContextSelector::default().select(&full, Query::new("deploy"), 100).unwrap();
+    for level in [VerificationLevel::ManifestOnly, VerificationLevel::None] {
+        let cache = open(&path, level);
+        assert_eq!(cache.load_documents().unwrap(), full.load_documents().unwrap());
+        let result = ContextSelector::default().select(&cache, Query::new("deploy"), 100).unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), serde_json::to_string(&expected).unwrap());
+    }
+}
+
+#[test]
+fn lower_levels_trust_more() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let built = build(&path);
+    let a = path.join(&built.manifest.documents[0].file);
+    let b = path.join(&built.manifest.documents[1].file);
+
+    // Edited in place: only re-hashing notices
+    let original = fs::read_to_string(&a).unwrap();
+    fs::write(&a, original.replace("alpha", "omega")).unwrap();
+    assert!(matches!(
+        open(&path, VerificationLevel::Full).load_documents(),
+        Err(CacheReadError::VersionMismatch { .. })
+    ));
+    let docs = open(&path, VerificationLevel::ManifestOnly).load_documents().unwrap();
+    assert_eq!(docs[0].content, "deploy omega");
+
+    // Swapped for another document: the manifest check notices
+    fs::copy(&b, &a).unwrap();
+    assert!(matches!(
+        open(&path, VerificationLevel::ManifestOnly).load_documents(),
+        Err(CacheReadError::IdMismatch { .. })
+    ));
+    let docs = open(&path, VerificationLevel::None).load_documents().unwrap();
+    assert_eq!(docs[0].id, docs[1].id);
+
+    // verify() checks fully whatever the level
+    assert!(open(&path, VerificationLevel::None).verify().is_err());
+
+    // Undecodable files fail at every level
+    fs::write(&a, b"not a document").unwrap();
+    assert!(matches!(
+        open(&path, VerificationLevel::None).load_documents(),
+        Err(CacheReadError::Corrupt { .. })
+    ));
+}
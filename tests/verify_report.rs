@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use context_core::cache::{CacheBuildConfig, CacheBuilder, ContextCache, HashingEmbedder, MemoryStore, VerifyOptions};
+use context_core::document::{Document, DocumentId, Metadata};
+use tempfile::tempdir;
+
+fn make_doc(id_str: &str, content: &str) -> Document {
+    let root = Path::new("/root");
+    let id = DocumentId::from_path(root, &root.join(id_str)).unwrap();
+    Document::ingest(id, id_str.to_string(), content.as_bytes().to_vec(), Metadata::default()).unwrap()
+}
+
+fn docs() -> Vec<Document> {
+    (0..10).map(|i| make_doc(&format!("doc{i}.md"), &format!("content {i}"))).collect()
+}
+
+fn build(path: &Path) -> ContextCache {
+    CacheBuilder::new(CacheBuildConfig::v0())
+        .with_embedder(HashingEmbedder::new(8))
+        .build(docs(), path)
+        .unwrap()
+}
+
+fn threads(threads: usize) -> VerifyOptions<'static> {
+    VerifyOptions {
+        threads,
+        ..VerifyOptions::default()
+    }
+}
+
+#[test]
+fn threaded_reports_match_sequential_ones() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+
+    let report = cache.verify_with(&threads(1)).unwrap();
+    // Ten documents, index.json, vectors.json
+    assert_eq!(report.files.len(), 12);
+    assert_eq!(report.files_hashed, 12);
+    assert_eq!(report.files_unchanged, 0);
+    assert_eq!(report.cache_version, cache.manifest.cache_version);
+    assert!(report.files.values().all(|file| file.stamp.is_some()));
+    for n in [2, 4, 64] {
+        assert_eq!(cache.verify_with(&threads(n)).unwrap(), report);
+    }
+
+    // Failures are the same too
+    let file = dir.path().join("cache").join(&cache.manifest.documents[7].file);
+    let original = fs::read_to_string(&file).unwrap();
+    fs::write(&file, original.replace("content 7", "content X")).unwrap();
+    let expected = cache.verify().unwrap_err().to_string();
+    assert!(expected.contains("tree hash mismatch"));
+    for n in [2, 4] {
+        assert_eq!(cache.verify_with(&threads(n)).unwrap_err().to_string(), expected);
+    }
+}
+
+#[test]
+fn unchanged_files_are_skipped() {
+    let dir = tempdir().unwrap();
+    let cache = build(&dir.path().join("cache"));
+    let first = cache.verify_with(&VerifyOptions::default()).unwrap();
+
+    let options = VerifyOptions {
+        threads: 4,
+        previous: Some(&first),
+    };
+    let second = cache.verify_with(&options).unwrap();
+    assert_eq!(second.files, first.files);
+    assert_eq!(second.files_hashed, 0);
+    assert_eq!(second.files_unchanged, 12);
+
+    // A changed file is re-read, and caught
+    let file = dir.path().join("cache").join(&cache.manifest.documents[3].file);
+    let original = fs::read_to_string(&file).unwrap();
+    fs::write(&file, original.replace("content 3", "content 33")).unwrap();
+    assert!(cache.verify_with(&options).is_err());
+
+    // A report for another cache version is ignored
+    let mut other = first.clone();
+    other.cache_version = "sha256:other".to_string();
+    fs::write(&file, original).unwrap();
+    let report = cache
+        .verify_with(&VerifyOptions {
+            threads: 1,
+            previous: Some(&other),
+        })
+        .unwrap();
+    assert_eq!(report.files_hashed, 12);
+}
+
+#[test]
+fn stores_without_stamps_always_hash() {
+    let store = Arc::new(MemoryStore::new());
+    let cache = CacheBuilder::new(CacheBuildConfig::v0())
+        .with_store(store)
+        .build(docs(), Path::new("/caches/memory"))
+        .unwrap();
+
+    let first = cache.verify_with(&VerifyOptions::default()).unwrap();
+    assert!(first.files.values().all(|file| file.stamp.is_none()));
+    let second = cache
+        .verify_with(&VerifyOptions {
+            threads: 1,
+            previous: Some(&first),
+        })
+        .unwrap();
+    assert_eq!(second.files_hashed, 11);
+    assert!(serde_json::to_string(&second).unwrap().contains(r#""files_hashed":11"#));
+}
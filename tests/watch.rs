@@ -0,0 +1,402 @@
+#![cfg(feature = "watch")]
+
+use std::fs;
+use std::time::Duration;
+
+use context_core::cache::ContextCache;
+use context_core::document::{BinaryPolicy, IngestOptions, Rejection, Source, WalkOptions};
+use context_core::engine::{WatchConfig, WatchError, Watcher};
+use tempfile::tempdir;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn config(root: &std::path::Path, generations: &std::path::Path) -> WatchConfig {
+    WatchConfig {
+        debounce: Duration::from_millis(50),
+        ..WatchConfig::new(root, generations)
+    }
+}
+
+#[test]
+fn initial_generation_contains_source_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join("readme.md"), "hello").unwrap();
+    fs::write(root.join("docs/guide.md"), "guide").unwrap();
+    fs::write(root.join(".git/HEAD"), "ref").unwrap();
+    fs::write(root.join("binary.bin"), [0xff, 0xfe]).unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let ids: Vec<_> = watcher
+        .initial()
+        .manifest
+        .documents
+        .iter()
+        .map(|e| e.id.as_str().to_string())
+        .collect();
+    assert_eq!(ids, vec!["docs/guide.md", "readme.md"]);
+    assert!(watcher.initial().root.ends_with("000001"));
+}
+
+#[test]
+fn change_produces_new_generation() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.md"), "alpha").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let initial_version = watcher.initial().manifest.cache_version.clone();
+
+    fs::write(root.join("a.md"), "alpha changed").unwrap();
+    fs::write(root.join("b.md"), "beta").unwrap();
+
+    // Events may arrive in more than one batch; wait for the final state
+    let mut last = None;
+    while let Ok(event) = watcher.events().recv_timeout(TIMEOUT) {
+        let event = event.unwrap();
+        let cache = ContextCache::open(&event.path).unwrap();
+        let docs = cache.load_documents().unwrap();
+        last = Some(event);
+        if docs.len() == 2 && docs[0].content == "alpha changed" {
+            break;
+        }
+    }
+
+    let event = last.expect("no CacheUpdated event");
+    assert_ne!(event.new_version, initial_version);
+    let cache = ContextCache::open(&event.path).unwrap();
+    assert_eq!(cache.manifest.cache_version, event.new_version);
+    assert_eq!(cache.load_documents().unwrap()[0].content, "alpha changed");
+    // The first generation is left in place
+    ContextCache::open(watcher.initial().root.clone()).unwrap();
+}
+
+#[test]
+fn deletion_removes_documents() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("a.md"), "alpha").unwrap();
+    fs::write(root.join("sub/b.md"), "beta").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    assert_eq!(watcher.initial().manifest.document_count, 2);
+
+    fs::remove_dir_all(root.join("sub")).unwrap();
+
+    let mut count = None;
+    while let Ok(event) = watcher.events().recv_timeout(TIMEOUT) {
+        let n = ContextCache::open(&event.unwrap().path).unwrap().manifest.document_count;
+        count = Some(n);
+        if n == 1 {
+            break;
+        }
+    }
+    assert_eq!(count, Some(1));
+}
+
+#[test]
+fn generations_dir_inside_root_is_ignored() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().to_path_buf();
+    fs::write(root.join("a.md"), "alpha").unwrap();
+
+    let watcher = Watcher::start(config(&root, &root.join("gens"))).unwrap();
+    assert_eq!(watcher.initial().manifest.document_count, 1);
+    // Writing the first generation must not trigger a rebuild
+    assert!(watcher.events().recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn markdown_files_get_extracted_metadata() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("guide.md"), "# Deploy Guide\nRun the script.").unwrap();
+    fs::write(root.join("notes.txt"), "# Not markdown").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    assert_eq!(docs[0].metadata.get("title").and_then(|v| v.as_str()), Some("Deploy Guide"));
+    assert!(docs[1].metadata.get("title").is_none());
+
+    // Field-scoped queries can use the extracted title
+    let query = context_core::types::Query::new("title:deploy");
+    assert!(query.matches_fields(&docs[0]));
+
+    let plain = WatchConfig {
+        walk: WalkOptions {
+            markdown_metadata: false,
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("plain"))
+    };
+    let watcher = Watcher::start(plain).unwrap();
+    assert!(watcher.initial().load_documents().unwrap()[0].metadata.get("title").is_none());
+}
+
+#[test]
+fn data_files_are_flattened() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("app.json"), r#"{"server": {"port": 8080}}"#).unwrap();
+    fs::write(root.join("broken.json"), "{ not json").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    assert_eq!(docs[0].content, "server port = 8080");
+    // Files that fail to parse keep their raw text
+    assert_eq!(docs[1].content, "{ not json");
+}
+
+#[test]
+fn source_files_record_their_symbols() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("lib.rs"), "pub struct Cache;\n\nimpl Cache {\n    fn load() {}\n}\n").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    assert!(context_core::types::Query::new("symbol:load").matches_fields(&docs[0]));
+    assert!(!context_core::types::Query::new("symbol:save").matches_fields(&docs[0]));
+
+    let plain = WatchConfig {
+        walk: WalkOptions {
+            code_symbols: false,
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("plain"))
+    };
+    let watcher = Watcher::start(plain).unwrap();
+    assert!(watcher.initial().load_documents().unwrap()[0].metadata.get("symbols").is_none());
+}
+
+#[test]
+fn ignore_files_exclude_paths() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::write(root.join(".gitignore"), "node_modules/\n*.log\n").unwrap();
+    fs::write(root.join("docs/.contextignore"), "draft.md\n").unwrap();
+    fs::write(root.join("node_modules/pkg/index.md"), "vendored").unwrap();
+    fs::write(root.join("build.log"), "noise").unwrap();
+    fs::write(root.join("docs/guide.md"), "guide").unwrap();
+    fs::write(root.join("docs/draft.md"), "draft").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    let ids: Vec<&str> = docs.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["docs/guide.md"]);
+
+    // Editing an ignore file re-ingests its directory
+    fs::write(root.join("docs/.contextignore"), "").unwrap();
+    let mut count = None;
+    while let Ok(event) = watcher.events().recv_timeout(TIMEOUT) {
+        let n = ContextCache::open(&event.unwrap().path).unwrap().manifest.document_count;
+        count = Some(n);
+        if n == 2 {
+            break;
+        }
+    }
+    assert_eq!(count, Some(2));
+    drop(watcher);
+
+    let all = WatchConfig {
+        walk: WalkOptions {
+            ignore_files: false,
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("all"))
+    };
+    let watcher = Watcher::start(all).unwrap();
+    assert_eq!(watcher.initial().manifest.document_count, 4);
+}
+
+#[test]
+fn binary_files_follow_the_policy() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.md"), "alpha").unwrap();
+    fs::write(root.join("bundle.min.js"), format!("var a={};", "1+".repeat(6000))).unwrap();
+    fs::write(root.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+    // Minified data files that parse are kept
+    fs::write(root.join("data.json"), format!("[{}1]", "1,".repeat(6000))).unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("skip"))).unwrap();
+    let report = watcher.report();
+    assert_eq!(report.documents, 2);
+    let skipped: Vec<&str> = report.skipped.iter().map(|f| f.id.as_str()).collect();
+    assert_eq!(skipped, vec!["bundle.min.js", "logo.png"]);
+    assert!(report.stubbed.is_empty());
+    assert_eq!(watcher.initial().manifest.document_count, 2);
+    drop(watcher);
+
+    let stub = WatchConfig {
+        walk: WalkOptions {
+            binary_policy: BinaryPolicy::Stub,
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("stub"))
+    };
+    let watcher = Watcher::start(stub).unwrap();
+    assert_eq!(watcher.report().documents, 4);
+    assert_eq!(watcher.report().stubbed.len(), 2);
+    let docs = watcher.initial().load_documents().unwrap();
+    assert_eq!(docs[3].id.as_str(), "logo.png");
+    assert_eq!(docs[3].content, "");
+    assert!(docs[3].metadata.get("rejection").is_some());
+    drop(watcher);
+
+    let error = WatchConfig {
+        walk: WalkOptions {
+            binary_policy: BinaryPolicy::Error,
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("error"))
+    };
+    assert!(matches!(Watcher::start(error), Err(WatchError::Rejected { .. })));
+}
+
+#[test]
+fn transcoding_reads_legacy_encodings() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("latin1.md"), b"# Caf\xe9\n").unwrap();
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend("notes".encode_utf16().flat_map(u16::to_le_bytes));
+    fs::write(root.join("utf16.txt"), utf16).unwrap();
+
+    // Strict by default: neither file is UTF-8 text
+    let watcher = Watcher::start(config(&root, &dir.path().join("strict"))).unwrap();
+    assert_eq!(watcher.report().skipped.len(), 2);
+    drop(watcher);
+
+    let transcoding = WatchConfig {
+        walk: WalkOptions {
+            ingest: IngestOptions {
+                transcode: true,
+                ..IngestOptions::default()
+            },
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("transcoding"))
+    };
+    let watcher = Watcher::start(transcoding).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    assert_eq!(docs[0].content, "# Café\n");
+    assert_eq!(docs[0].metadata.get("title").and_then(|v| v.as_str()), Some("Café"));
+    assert_eq!(docs[0].metadata.get("original_encoding").and_then(|v| v.as_str()), Some("windows-1252"));
+    assert_eq!(docs[1].content, "notes");
+}
+
+#[test]
+fn oversize_files_are_rejected_or_split() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("small.md"), "short\n").unwrap();
+    fs::write(root.join("large.md"), "first line\nsecond line\nthird line\n").unwrap();
+
+    let limited = WatchConfig {
+        walk: WalkOptions {
+            ingest: IngestOptions {
+                max_bytes: Some(24),
+                ..IngestOptions::default()
+            },
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("limited"))
+    };
+    let watcher = Watcher::start(limited).unwrap();
+    let ids: Vec<_> = watcher.initial().manifest.documents.iter().map(|e| e.id.as_str().to_string()).collect();
+    assert_eq!(ids, vec!["small.md"]);
+    assert_eq!(watcher.report().skipped[0].id.as_str(), "large.md");
+    assert!(matches!(watcher.report().skipped[0].reason, Rejection::TooLarge { bytes: 34, limit: 24 }));
+    drop(watcher);
+
+    let splitting = WatchConfig {
+        walk: WalkOptions {
+            ingest: IngestOptions {
+                max_bytes: Some(24),
+                split_oversize: true,
+                ..IngestOptions::default()
+            },
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("splitting"))
+    };
+    let watcher = Watcher::start(splitting).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    let ids: Vec<_> = docs.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["large.md/part-0001", "large.md/part-0002", "small.md"]);
+    assert_eq!(docs[0].content, "first line\nsecond line\n");
+    assert_eq!(docs[1].content, "third line\n");
+    assert_eq!(docs[1].metadata.get("part_of").and_then(|v| v.as_str()), Some("large.md"));
+    assert!(watcher.report().skipped.is_empty());
+}
+
+#[test]
+fn documents_record_their_local_path() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::write(root.join("docs/guide.md"), "guide").unwrap();
+
+    let watcher = Watcher::start(config(&root, &dir.path().join("gens"))).unwrap();
+    let docs = watcher.initial().load_documents().unwrap();
+    assert_eq!(docs[0].source, Source::local_path(root.to_string_lossy(), "docs/guide.md"));
+}
+
+#[test]
+fn report_lists_per_file_outcomes() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("src");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("small.md"), "short\n").unwrap();
+    fs::write(root.join("large.md"), "first line\nsecond line\nthird line\n").unwrap();
+    fs::write(root.join("latin1.md"), b"Caf\xe9\n").unwrap();
+    fs::write(root.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+
+    let outcomes = WatchConfig {
+        walk: WalkOptions {
+            ingest: IngestOptions {
+                transcode: true,
+                max_bytes: Some(24),
+                split_oversize: true,
+            },
+            ..WalkOptions::default()
+        },
+        ..config(&root, &dir.path().join("gens"))
+    };
+    let watcher = Watcher::start(outcomes).unwrap();
+    let report = watcher.report();
+    assert_eq!(report.documents, 4);
+    assert_eq!(report.total_bytes, 6 + 34 + 5 + 10);
+
+    let ingested: Vec<(&str, u64, Option<&str>, Option<usize>)> = report
+        .ingested
+        .iter()
+        .map(|f| (f.id.as_str(), f.bytes, f.transcoded_from.as_deref(), f.parts))
+        .collect();
+    assert_eq!(
+        ingested,
+        vec![
+            ("large.md", 34, None, Some(2)),
+            ("latin1.md", 5, Some("windows-1252"), None),
+            ("small.md", 6, None, None),
+        ]
+    );
+    assert_eq!(report.skipped[0].id.as_str(), "logo.png");
+
+    let json = serde_json::to_value(report).unwrap();
+    assert_eq!(json["ingested"][2], serde_json::json!({ "id": "small.md", "bytes": 6 }));
+    assert_eq!(json["skipped"][0]["reason"]["kind"], "nul_bytes");
+}